@@ -0,0 +1,39 @@
+use anyhow::{bail, Context, Result};
+use binary_insight_core::analysis::bazaar::{self, BazaarReport};
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Environment variable holding the abuse.ch Auth-Key.
+const AUTH_KEY_VAR: &str = "ABUSECH_AUTH_KEY";
+
+/// Looks the SHA-256 up on MalwareBazaar. The request goes through the
+/// system `curl`, which brings its own TLS stack.
+pub fn query(sha256: &str) -> Result<Option<BazaarReport>> {
+    let key = env::var(AUTH_KEY_VAR).ok();
+    let mut curl = Command::new("curl");
+    curl.args(["--silent", "--show-error", "--fail", "--max-time", "30"])
+        .args(["--data", "query=get_info"])
+        .args(["--data", &format!("hash={}", sha256)])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // The key goes in on stdin so it does not show up in the process list.
+    if key.is_some() {
+        curl.args(["--header", "@-"]).stdin(Stdio::piped());
+    } else {
+        curl.stdin(Stdio::null());
+    }
+    let mut child = curl
+        .arg(bazaar::API_URL)
+        .spawn()
+        .context("Failed to run curl")?;
+    if let (Some(key), Some(mut stdin)) = (key, child.stdin.take()) {
+        writeln!(stdin, "Auth-Key: {}", key).context("Failed to pass the Auth-Key to curl")?;
+    }
+    let output = child.wait_with_output().context("Failed to run curl")?;
+    if !output.status.success() {
+        // With --fail, HTTP errors such as 401 and 429 end up here.
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    bazaar::parse_response(&String::from_utf8_lossy(&output.stdout))
+}
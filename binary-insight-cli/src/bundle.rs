@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use binary_insight_core::analysis::hashes;
+use binary_insight_core::binary::artifacts::Artifact;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const REPORT_NAME: &str = "report.txt";
+const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    path: &'a str,
+    kind: &'a str,
+    size: usize,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    source: &'a str,
+    source_sha256: &'a str,
+    files: Vec<ManifestEntry<'a>>,
+}
+
+/// Writes a zip holding the text report, every artifact under `artifacts/`
+/// and a manifest with their SHA-256 hashes.
+pub fn write_bundle(
+    path: &Path,
+    source: &str,
+    source_sha256: &str,
+    report: &[u8],
+    artifacts: &[Artifact],
+) -> Result<usize> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let names: Vec<String> = artifacts
+        .iter()
+        .map(|a| format!("artifacts/{}", a.path))
+        .collect();
+    let mut files = vec![ManifestEntry {
+        path: REPORT_NAME,
+        kind: "report",
+        size: report.len(),
+        sha256: hashes::calculate_hashes(report).sha256,
+    }];
+    zip.start_file(REPORT_NAME, options)?;
+    zip.write_all(report)?;
+
+    for (artifact, name) in artifacts.iter().zip(&names) {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&artifact.data)?;
+        files.push(ManifestEntry {
+            path: name,
+            kind: artifact.kind,
+            size: artifact.data.len(),
+            sha256: hashes::calculate_hashes(&artifact.data).sha256,
+        });
+    }
+
+    let manifest = Manifest {
+        source,
+        source_sha256,
+        files,
+    };
+    zip.start_file(MANIFEST_NAME, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    zip.finish()?;
+    Ok(artifacts.len())
+}
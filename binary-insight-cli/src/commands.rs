@@ -0,0 +1,604 @@
+use anyhow::{Context, Result};
+use binary_insight_core::analysis::pattern::{self, Pattern};
+use binary_insight_core::analysis::{callgraph, deps, grep, hashdb, listing};
+use binary_insight_core::binary::{AnalysisOptions, BinaryData, BinaryFile};
+use binary_insight_core::utils;
+use clap::builder::PossibleValuesParser;
+use clap::Subcommand;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Build a call graph from direct calls, rooted at the entry point and exports
+    Callgraph {
+        /// Path to the binary file to analyze
+        file: String,
+
+        /// Write the graph in Graphviz DOT format
+        #[arg(long, value_name = "PATH")]
+        dot: Option<String>,
+
+        /// Write the graph in GraphML format (Gephi, yEd)
+        #[arg(long, value_name = "PATH")]
+        graphml: Option<String>,
+    },
+
+    /// Disassemble a whole code section into an objdump-style listing
+    Listing {
+        /// Path to the binary file to analyze
+        file: String,
+
+        /// Section to disassemble (default: the code section holding the entry point)
+        #[arg(long)]
+        section: Option<String>,
+
+        /// Write the listing here instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<String>,
+    },
+
+    /// Disassemble from a virtual address or file offset
+    #[command(group = clap::ArgGroup::new("start").required(true))]
+    Disasm {
+        /// Path to the binary file to analyze
+        file: String,
+
+        /// Virtual address to start at
+        #[arg(long, group = "start", value_parser = crate::parse_number)]
+        addr: Option<u64>,
+
+        /// File offset to start at
+        #[arg(long, group = "start", value_parser = crate::parse_number)]
+        offset: Option<u64>,
+
+        /// Maximum number of instructions (default 100 unless --len is given)
+        #[arg(long)]
+        count: Option<usize>,
+
+        /// Number of bytes to decode (default: to the end of the section)
+        #[arg(long, value_parser = crate::parse_number)]
+        len: Option<u64>,
+    },
+
+    /// Find a byte pattern such as `E8 ?? ?? ?? ?? 48 8B` (`??` matches any byte)
+    Search {
+        /// Path to the binary file to search
+        file: String,
+
+        /// Hex bytes and `??` wildcards; quote it as one argument
+        #[arg(value_parser = parse_pattern)]
+        pattern: Pattern,
+
+        /// Only search this section
+        #[arg(long)]
+        section: Option<String>,
+    },
+
+    /// Search the extracted strings, or the raw bytes, for a regular expression
+    Grep {
+        /// Path to the binary file to search
+        file: String,
+
+        /// Regular expression; `\xNN` matches a byte
+        regex: String,
+
+        /// Search the raw bytes instead, so matches may span non-printable bytes
+        #[arg(long)]
+        bytes: bool,
+
+        /// Ignore ASCII case
+        #[arg(short, long)]
+        ignore_case: bool,
+
+        /// Bytes of context shown around each match
+        #[arg(short = 'C', long, default_value_t = 16)]
+        context: usize,
+    },
+
+    /// Print bytes of the file as hex and ASCII
+    Hexdump {
+        /// Path to the file to dump
+        file: String,
+
+        /// File offset to start at
+        #[arg(long, default_value = "0", value_parser = crate::parse_number)]
+        offset: u64,
+
+        /// Number of bytes to dump (default: to the end of the file)
+        #[arg(long, value_parser = crate::parse_number)]
+        len: Option<u64>,
+
+        /// Bytes per row
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u16).range(1..=256))]
+        width: u16,
+    },
+
+    /// Write the file bytes of a section, segment or address range to a file
+    #[command(group = clap::ArgGroup::new("what").required(true))]
+    Extract {
+        /// Path to the binary file
+        file: String,
+
+        /// Section name, e.g. `.rodata`
+        #[arg(long, group = "what")]
+        section: Option<String>,
+
+        /// Segment name (Mach-O) or type (ELF, e.g. `LOAD`), or its index
+        #[arg(long, group = "what")]
+        segment: Option<String>,
+
+        /// Virtual address range, e.g. `0x401000-0x402000`
+        #[arg(long, group = "what", value_name = "START-END", value_parser = parse_range)]
+        range: Option<Range<u64>>,
+
+        /// File to write
+        #[arg(short, long, value_name = "PATH")]
+        output: String,
+    },
+
+    /// Print the transitive shared-library dependency tree
+    Deps {
+        /// Path to the binary file to analyze
+        file: String,
+
+        /// Resolve absolute library paths and the ldconfig cache under this root
+        #[arg(long, value_name = "DIR")]
+        sysroot: Option<String>,
+
+        /// Extra directory to search for libraries (repeatable)
+        #[arg(short = 'L', long = "search-path", value_name = "DIR")]
+        search_paths: Vec<String>,
+
+        /// Don't consult the ldconfig cache
+        #[arg(long)]
+        no_ldconfig: bool,
+    },
+
+    /// Manage the local database of analyzed hashes and their labels
+    Hashdb {
+        #[command(subcommand)]
+        action: HashDbAction,
+
+        /// Database file (default: ~/.local/share/binaryinsight/hashes.db)
+        #[arg(long, value_name = "PATH", global = true)]
+        db: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HashDbAction {
+    /// Merge labels from CSV (`sha256,label,note`, or a previous export)
+    Import {
+        /// CSV file to read
+        file: String,
+    },
+
+    /// Write every record as CSV
+    Export {
+        /// Write here instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<String>,
+    },
+
+    /// Label a SHA-256 as clean, suspicious or malicious
+    Label {
+        sha256: String,
+
+        #[arg(value_parser = PossibleValuesParser::new(["clean", "suspicious", "malicious", "none"]))]
+        label: String,
+
+        /// Free-form note kept with the label
+        #[arg(long, default_value = "")]
+        note: String,
+    },
+}
+
+pub fn run(command: &Command) -> Result<()> {
+    match command {
+        Command::Callgraph { file, dot, graphml } => {
+            let mut binary = BinaryFile::load(file)?;
+            // Known function starts bound how far each function is decoded.
+            binary.discover_functions();
+            let graph = callgraph::build_call_graph(&binary);
+            if let Some(path) = dot {
+                fs::write(path, graph.to_dot())?;
+                info!("Wrote DOT call graph to {}", path);
+            }
+            if let Some(path) = graphml {
+                fs::write(path, graph.to_graphml())?;
+                info!("Wrote GraphML call graph to {}", path);
+            }
+            println!(
+                "{} functions, {} call edges",
+                graph.functions.len(),
+                graph.edges.len()
+            );
+            if dot.is_none() && graphml.is_none() {
+                for f in &graph.functions {
+                    let callees = graph.edges.iter().filter(|e| e.from == f.addr).count();
+                    let marker = if f.root { "*" } else { " " };
+                    println!(
+                        "{} 0x{:<12x} {:<40} {} calls",
+                        marker, f.addr, f.name, callees
+                    );
+                }
+            }
+        }
+        Command::Listing {
+            file,
+            section,
+            output,
+        } => {
+            let mut binary = BinaryFile::load(file)?;
+            // Discovered functions label the listing alongside symbols.
+            binary.discover_functions();
+            let info = &binary.info;
+            let section = match section {
+                Some(name) => info
+                    .sections
+                    .iter()
+                    .find(|s| &s.name == name)
+                    .with_context(|| format!("No section named {}", name))?,
+                None => info
+                    .section_for_addr(info.entry_point)
+                    .filter(|s| s.is_code())
+                    .or_else(|| info.sections.iter().find(|s| s.is_code()))
+                    .context("No code section found; pick one with --section")?,
+            };
+            let mut out: Box<dyn Write> = match output {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(BufWriter::new(io::stdout().lock())),
+            };
+            let count = listing::write_listing(&mut out, &binary, section)?;
+            out.flush()?;
+            info!("Disassembled {} instructions from {}", count, section.name);
+        }
+        Command::Disasm {
+            file,
+            addr,
+            offset,
+            count,
+            len,
+        } => {
+            let mut binary = BinaryFile::load_with(file, AnalysisOptions::lazy())?;
+            binary.discover_functions();
+            let info = &binary.info;
+            // Unmapped offsets (headers, overlay) are listed at their offset.
+            let (offset, addr) = match (addr, offset) {
+                (Some(addr), _) => {
+                    let offset = info
+                        .addr_to_offset(*addr)
+                        .with_context(|| format!("0x{:x} is not in any section", addr))?;
+                    (offset, *addr)
+                }
+                (None, Some(offset)) => (*offset, info.offset_to_addr(*offset).unwrap_or(*offset)),
+                (None, None) => unreachable!("clap requires --addr or --offset"),
+            };
+            // Without --len, stop at the end of the section (or file).
+            let end = match len {
+                Some(len) => offset.saturating_add(*len),
+                None => info
+                    .memory_map()
+                    .section_for_offset(offset)
+                    .map_or(u64::MAX, |s| s.offset.saturating_add(s.size)),
+            };
+            let data = &binary.data;
+            let start = (offset as usize).min(data.len());
+            let end = (end.min(data.len() as u64) as usize).max(start);
+            let max_count = count.unwrap_or(if len.is_some() { usize::MAX } else { 100 });
+            let mut out = BufWriter::new(io::stdout().lock());
+            listing::write_instructions(&mut out, &binary, &data[start..end], addr, max_count)?;
+            out.flush()?;
+        }
+        Command::Search {
+            file,
+            pattern,
+            section,
+        } => {
+            let binary = BinaryFile::load_with(file, AnalysisOptions::lazy())?;
+            let info = &binary.info;
+            let (data, base) = match section {
+                Some(name) => {
+                    let section = info
+                        .sections
+                        .iter()
+                        .find(|s| &s.name == name)
+                        .with_context(|| format!("No section named {}", name))?;
+                    (binary.section_data(section), section.offset)
+                }
+                None => (&binary.data[..], 0),
+            };
+            let matches = pattern::find(data, pattern);
+            let mut out = BufWriter::new(io::stdout().lock());
+            for start in &matches {
+                let offset = base + *start as u64;
+                let addr = info
+                    .offset_to_addr(offset)
+                    .map_or("-".to_string(), |addr| format!("0x{:x}", addr));
+                let section = info
+                    .memory_map()
+                    .section_for_offset(offset)
+                    .map_or("-", |s| s.name.as_str());
+                let bytes = &data[*start..*start + pattern.len()];
+                writeln!(
+                    out,
+                    "0x{:08x}  {:<18} {:<10} {}",
+                    offset,
+                    addr,
+                    section,
+                    hex_bytes(bytes)
+                )?;
+            }
+            out.flush()?;
+            info!("{} matches of {}", matches.len(), pattern);
+        }
+        Command::Grep {
+            file,
+            regex,
+            bytes,
+            ignore_case,
+            context,
+        } => {
+            let binary = BinaryFile::load(file)?;
+            let info = &binary.info;
+            let re = grep::compile(regex, *ignore_case)?;
+            let matches = if *bytes {
+                grep::grep_bytes(&binary.data, &re)
+            } else {
+                grep::grep_strings(info, &re)
+            };
+            let highlight = io::stdout().is_terminal();
+            let mut out = BufWriter::new(io::stdout().lock());
+            for m in &matches {
+                // String matches show context from their own string only.
+                let bounds = match m.string.and_then(|i| info.string_index.get(i)) {
+                    Some((offset, len)) => offset as usize..offset as usize + len as usize,
+                    None => 0..binary.data.len(),
+                };
+                let start = m.offset as usize;
+                let end = start + m.len;
+                let before = start.saturating_sub(*context).max(bounds.start);
+                let after = (end + context).min(bounds.end);
+                let addr = info
+                    .offset_to_addr(m.offset)
+                    .map_or("-".to_string(), |addr| format!("0x{:x}", addr));
+                let section = info
+                    .memory_map()
+                    .section_for_offset(m.offset)
+                    .map_or("-", |s| s.name.as_str());
+                let data = &binary.data;
+                let matched = escape_bytes(&data[start..end]);
+                writeln!(
+                    out,
+                    "0x{:08x}  {:<18} {:<10} {}{}{}",
+                    m.offset,
+                    addr,
+                    section,
+                    escape_bytes(&data[before..start]),
+                    if highlight {
+                        format!("\x1b[1;31m{}\x1b[0m", matched)
+                    } else {
+                        matched
+                    },
+                    escape_bytes(&data[end..after])
+                )?;
+            }
+            out.flush()?;
+            info!("{} matches", matches.len());
+        }
+        Command::Hexdump {
+            file,
+            offset,
+            len,
+            width,
+        } => {
+            // Mapped, so a dump of a few lines does not read the whole file.
+            let data =
+                BinaryData::open(file).with_context(|| format!("Failed to open {}", file))?;
+            if *offset > data.len() as u64 {
+                anyhow::bail!(
+                    "Offset 0x{:x} is past the end of the file (0x{:x} bytes)",
+                    offset,
+                    data.len()
+                );
+            }
+            let start = *offset as usize;
+            let end = len.map_or(data.len(), |len| {
+                start
+                    .saturating_add(len.min(usize::MAX as u64) as usize)
+                    .min(data.len())
+            });
+            let mut out = BufWriter::new(io::stdout().lock());
+            utils::write_hexdump(&mut out, &data[start..end], *offset, *width as usize)?;
+            out.flush()?;
+        }
+        Command::Extract {
+            file,
+            section,
+            segment,
+            range,
+            output,
+        } => {
+            let binary = BinaryFile::load_with(file, AnalysisOptions::lazy())?;
+            let info = &binary.info;
+            let (what, offsets) = if let Some(name) = section {
+                let section = info
+                    .sections
+                    .iter()
+                    .find(|s| &s.name == name)
+                    .with_context(|| format!("No section named {}", name))?;
+                if section.zero_fill {
+                    anyhow::bail!("{} is zero-filled and has no bytes in the file", name);
+                }
+                let end = section
+                    .offset
+                    .checked_add(section.size)
+                    .with_context(|| format!("{} has an invalid offset or size", name))?;
+                (section.name.clone(), section.offset..end)
+            } else if let Some(name) = segment {
+                let segments = binary.segments();
+                if segments.is_empty() {
+                    anyhow::bail!("{} files have no segments; use --section", info.format);
+                }
+                // An index picks one of several segments sharing a type.
+                let segment = match name.parse::<usize>() {
+                    Ok(i) => segments.get(i),
+                    Err(_) => segments.iter().find(|s| &s.name == name),
+                }
+                .with_context(|| format!("No segment {}", name))?;
+                let end = segment
+                    .offset
+                    .checked_add(segment.file_size)
+                    .with_context(|| format!("Segment {} has an invalid offset or size", name))?;
+                (format!("segment {}", segment.name), segment.offset..end)
+            } else if let Some(range) = range {
+                let offsets = info.addr_range_to_offsets(range.clone()).with_context(|| {
+                    format!(
+                        "0x{:x}-0x{:x} is not mapped contiguously from the file",
+                        range.start, range.end
+                    )
+                })?;
+                (format!("0x{:x}-0x{:x}", range.start, range.end), offsets)
+            } else {
+                unreachable!("clap requires --section, --segment or --range")
+            };
+            let data = &binary.data;
+            let start = (offsets.start as usize).min(data.len());
+            let end = (offsets.end.min(data.len() as u64) as usize).max(start);
+            fs::write(output, &data[start..end])
+                .with_context(|| format!("Failed to write {}", output))?;
+            info!(
+                "Wrote {} bytes of {} (file offset 0x{:x}) to {}",
+                end - start,
+                what,
+                start,
+                output
+            );
+        }
+        Command::Deps {
+            file,
+            sysroot,
+            search_paths,
+            no_ldconfig,
+        } => {
+            let resolver = deps::Resolver::new(
+                sysroot.as_ref().map(PathBuf::from),
+                search_paths.iter().map(PathBuf::from).collect(),
+                !no_ldconfig,
+            );
+            let tree = resolver.resolve(Path::new(file))?;
+            let mut out = BufWriter::new(io::stdout().lock());
+            writeln!(out, "{}", tree.name)?;
+            write_dep_tree(&mut out, &tree.children, "")?;
+            let unresolved = tree.unresolved();
+            if !unresolved.is_empty() {
+                writeln!(
+                    out,
+                    "\n{} unresolved: {}",
+                    unresolved.len(),
+                    unresolved.join(", ")
+                )?;
+            }
+            out.flush()?;
+        }
+        Command::Hashdb { action, db } => {
+            let path = match db {
+                Some(path) => PathBuf::from(path),
+                None => hashdb::HashDb::default_path().context("No home directory; pass --db")?,
+            };
+            let mut database = hashdb::HashDb::open(&path)?;
+            match action {
+                HashDbAction::Import { file } => {
+                    let text = fs::read_to_string(file)
+                        .with_context(|| format!("Failed to read {}", file))?;
+                    let count = database.import_csv(&text, crate::unix_now())?;
+                    database.save(&path)?;
+                    info!("Imported {} records into {}", count, path.display());
+                }
+                HashDbAction::Export { output } => {
+                    let mut out: Box<dyn Write> = match output {
+                        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                        None => Box::new(BufWriter::new(io::stdout().lock())),
+                    };
+                    database.export_csv(&mut out)?;
+                    out.flush()?;
+                }
+                HashDbAction::Label {
+                    sha256,
+                    label,
+                    note,
+                } => {
+                    if sha256.len() != 64 || hex::decode(sha256).is_err() {
+                        anyhow::bail!("not a SHA-256: {}", sha256);
+                    }
+                    let label = hashdb::Label::from_name(label);
+                    database.set_label(sha256, label, note, crate::unix_now());
+                    database.save(&path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses `start-end`, each decimal or 0x-prefixed hex.
+fn parse_range(s: &str) -> Result<Range<u64>, String> {
+    let (start, end) = s
+        .split_once('-')
+        .ok_or_else(|| format!("expected START-END, got '{}'", s))?;
+    let range = crate::parse_number(start)?..crate::parse_number(end)?;
+    if range.is_empty() {
+        return Err(format!("empty range '{}'", s));
+    }
+    Ok(range)
+}
+
+fn parse_pattern(s: &str) -> Result<Pattern, String> {
+    Pattern::parse(s).map_err(|e| e.to_string())
+}
+
+/// `bytes` as space-separated uppercase hex.
+fn hex_bytes(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    hex.join(" ")
+}
+
+/// `bytes` with printable ASCII as is and everything else as `\xNN`.
+fn escape_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            b'\\' => "\\\\".to_string(),
+            0x20..=0x7e => (b as char).to_string(),
+            _ => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
+
+/// Draws `nodes` as the branches of a tree below a line indented by `prefix`.
+fn write_dep_tree(out: &mut dyn Write, nodes: &[deps::DepNode], prefix: &str) -> io::Result<()> {
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        let status = match &node.status {
+            deps::DepStatus::Resolved(path) => format!(" => {}", path.display()),
+            deps::DepStatus::Repeated(path) => format!(" => {} (see above)", path.display()),
+            deps::DepStatus::ApiSet => " (API set)".to_string(),
+            deps::DepStatus::NotFound => " => NOT FOUND".to_string(),
+        };
+        writeln!(
+            out,
+            "{}{}{}{}",
+            prefix,
+            if last { "└── " } else { "├── " },
+            node.name,
+            status
+        )?;
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        write_dep_tree(out, &node.children, &child_prefix)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+use binary_insight_core::analysis::policy::Policy;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// User configuration loaded from `--config <file.toml>`.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// Computed report fields: name -> expression over the analysis model.
+    #[serde(default)]
+    pub derived: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path.as_ref())
+            .with_context(|| format!("Failed to read config file {}", path.as_ref().display()))?;
+        toml::from_str(&text).context("Invalid config file")
+    }
+}
+
+/// Reads a `--policy` file.
+pub fn load_policy<P: AsRef<Path>>(path: P) -> Result<Policy> {
+    let text = fs::read_to_string(path.as_ref())
+        .with_context(|| format!("Failed to read policy file {}", path.as_ref().display()))?;
+    toml::from_str(&text).context("Invalid policy file")
+}
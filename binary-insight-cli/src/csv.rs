@@ -0,0 +1,181 @@
+use binary_insight_core::analysis::entropy;
+use binary_insight_core::analysis::findings::Finding;
+use binary_insight_core::binary::BinaryFile;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes sections, symbols, imports, strings, findings and derived fields
+/// as one CSV file each in `dir`.
+pub fn export(
+    dir: &Path,
+    binary: &BinaryFile,
+    findings: &[Finding],
+    derived: &BTreeMap<String, String>,
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let info = &binary.info;
+    let hex = |value: u64| format!("0x{:x}", value);
+    let opt_hex = |value: Option<u64>| value.map(hex).unwrap_or_default();
+
+    write_table(
+        &dir.join("sections.csv"),
+        &[
+            "name", "address", "size", "offset", "entropy", "md5", "sha256",
+        ],
+        info.sections.iter().map(|s| {
+            let entropy = entropy::calculate_entropy(binary.section_data(s));
+            vec![
+                s.name.clone(),
+                hex(s.addr),
+                hex(s.size),
+                hex(s.offset),
+                format!("{:.4}", entropy),
+                s.hashes.as_ref().map(|h| h.md5.clone()).unwrap_or_default(),
+                s.hashes
+                    .as_ref()
+                    .map(|h| h.sha256.clone())
+                    .unwrap_or_default(),
+            ]
+        }),
+    )?;
+    write_table(
+        &dir.join("symbols.csv"),
+        &["name", "address"],
+        info.symbols
+            .iter()
+            .map(|s| vec![s.name.clone(), hex(s.addr)]),
+    )?;
+    write_table(
+        &dir.join("imports.csv"),
+        &["name", "library", "slot"],
+        info.imports.iter().map(|i| {
+            vec![
+                i.name.clone(),
+                i.library.clone().unwrap_or_default(),
+                opt_hex(i.slot),
+            ]
+        }),
+    )?;
+    write_table(
+        &dir.join("strings.csv"),
+        &["offset", "address", "string"],
+        info.strings.iter().enumerate().map(|(i, s)| {
+            let offset = info.string_index.get(i).map(|(offset, _)| offset);
+            vec![
+                opt_hex(offset),
+                opt_hex(offset.and_then(|o| info.offset_to_addr(o))),
+                s.clone(),
+            ]
+        }),
+    )?;
+    write_table(
+        &dir.join("findings.csv"),
+        &[
+            "id",
+            "severity",
+            "category",
+            "title",
+            "evidence",
+            "addresses",
+        ],
+        findings.iter().map(|f| {
+            let addresses: Vec<String> = f.addresses.iter().map(|&a| hex(a)).collect();
+            vec![
+                f.id.clone(),
+                f.severity.name().to_string(),
+                f.category.name().to_string(),
+                f.title.clone(),
+                f.evidence.clone(),
+                addresses.join(" "),
+            ]
+        }),
+    )?;
+    write_table(
+        &dir.join("derived.csv"),
+        &["name", "value"],
+        derived
+            .iter()
+            .map(|(name, value)| vec![name.clone(), value.clone()]),
+    )
+}
+
+fn write_table(
+    path: &Path,
+    header: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "{}", header.join(","))?;
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| quote(field)).collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    out.flush()
+}
+
+/// Quotes a field per RFC 4180 when it holds a separator, quote or newline.
+/// Fields a spreadsheet would run as a formula (`=`, `+`, `-`, `@`, tab or
+/// CR first) get a leading `'` so strings from the binary stay text.
+fn quote(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binary_insight_core::analysis::findings::{Category, Severity};
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("plain"), "plain");
+        assert_eq!(quote("a,b"), "\"a,b\"");
+        assert_eq!(quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("=1+1"), "'=1+1");
+        assert_eq!(quote("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(quote("\tcmd"), "'\tcmd");
+        assert_eq!(quote("-2,3"), "\"'-2,3\"");
+    }
+
+    #[test]
+    fn test_export() {
+        let binary =
+            BinaryFile::from_raw("blob".to_string(), b"=HYPERLINK(x)\0".to_vec(), "x86_64", 0);
+        let findings = [Finding {
+            id: "weak_crypto.md5".to_string(),
+            title: "Weak algorithm MD5 in use".to_string(),
+            severity: Severity::Info,
+            category: Category::Crypto,
+            evidence: "+MD5, init constants".to_string(),
+            addresses: vec![0x1000],
+            attack: Vec::new(),
+        }];
+        let derived = BTreeMap::from([("packed".to_string(), "false".to_string())]);
+        let dir = std::env::temp_dir().join(format!("binaryinsight-csv-{}", std::process::id()));
+        export(&dir, &binary, &findings, &derived).unwrap();
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        let (strings, found, fields) = (
+            read("strings.csv"),
+            read("findings.csv"),
+            read("derived.csv"),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(strings, "offset,address,string\n0x0,0x0,'=HYPERLINK(x)\n");
+        assert_eq!(
+            found.lines().nth(1),
+            Some("weak_crypto.md5,info,crypto,Weak algorithm MD5 in use,\"'+MD5, init constants\",0x1000")
+        );
+        assert_eq!(fields, "name,value\npacked,false\n");
+    }
+}
@@ -0,0 +1,403 @@
+use crate::report::{self, ReportInput};
+use binary_insight_core::analysis::{anomalies, entropy, score, yara, Relro, SafeSeh};
+use std::io::{self, Write};
+
+/// Rows shown for the long tables; the text report has the full lists.
+const MAX_STRINGS: usize = 200;
+const MAX_INSTRUCTIONS: usize = 100;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', sans-serif; margin: 2em auto; max-width: 1100px; color: #222; }
+h1 { font-size: 1.5em; } h2 { font-size: 1.15em; border-bottom: 1px solid #ddd; padding-bottom: .2em; margin-top: 1.8em; }
+table { border-collapse: collapse; width: 100%; font-size: .9em; }
+th, td { text-align: left; padding: .25em .6em; border-bottom: 1px solid #eee; vertical-align: top; }
+th { background: #f6f6f6; }
+code, pre, .mono { font-family: 'SFMono-Regular', Consolas, monospace; font-size: .88em; }
+pre { background: #f6f6f6; padding: .8em; overflow-x: auto; }
+.ok { color: #1a7f37; } .bad { color: #cf222e; }
+.sev { font-weight: bold; text-transform: uppercase; font-size: .8em; }
+.high { color: #cf222e; } .medium { color: #bc4c00; } .low { color: #0969da; } .info { color: #666; }
+.bar { background: #eee; width: 160px; height: .8em; display: inline-block; vertical-align: middle; }
+.bar span { display: block; height: 100%; }
+.warn { color: #9a6700; }
+";
+
+/// Writes the analysis as a single HTML page with inline styles.
+pub fn write_html(out: &mut dyn Write, input: &ReportInput) -> io::Result<()> {
+    let ReportInput {
+        binary,
+        args,
+        hashes,
+        entropy,
+        yara_error,
+        yara_skipped,
+        yara_timed_out,
+        yara_scope,
+        violations,
+        findings,
+        derived,
+        ..
+    } = *input;
+    let info = &binary.info;
+
+    writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(
+        out,
+        "<title>{} - BinaryInsight</title>",
+        escape(&binary.name)
+    )?;
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<h1>{}</h1>", escape(&binary.name))?;
+
+    writeln!(out, "<h2>Overview</h2>\n<table>")?;
+    let threat = score::threat_score(info);
+    let mut overview = vec![
+        ("Format", binary.identify().to_string()),
+        ("Architecture", info.arch.clone()),
+        ("Entry point", format!("0x{:x}", info.entry_point)),
+        ("Size", format!("{} bytes", binary.data.len())),
+        ("Entropy", format!("{:.4}", entropy)),
+        ("MD5", hashes.md5.clone()),
+        ("SHA1", hashes.sha1.clone()),
+        ("SHA256", hashes.sha256.clone()),
+        ("Threat score", format!("{}/100", threat.score)),
+    ];
+    if let Some(wrappers) = info.wrapper_names() {
+        overview.insert(1, ("Wrapper", format!("{} (decompressed)", wrappers)));
+    }
+    if let Some(file_type) = &info.file_type {
+        overview.insert(1, ("MIME", file_type.mime.to_string()));
+    }
+    if let Some(language) = &info.language {
+        overview.push(("Language", language.summary()));
+    }
+    if let Some(authentihash) = &info.authentihash {
+        overview.push(("Authentihash", authentihash.clone()));
+    }
+    if let Some(icon) = &info.icon {
+        let hashes = match (&icon.dhash, &icon.phash) {
+            (Some(dhash), Some(phash)) => format!(", dhash {}, phash {}", dhash, phash),
+            _ => String::new(),
+        };
+        overview.push((
+            "Icon",
+            format!("{}x{} {}{}", icon.width, icon.height, icon.format, hashes),
+        ));
+    }
+    if let Some(telfhash) = &info.telfhash {
+        overview.push(("telfhash", telfhash.clone()));
+    }
+    if let Some(algorithm) = info.analysis.as_ref().and_then(|a| a.known_good.as_ref()) {
+        overview.push(("Known good", format!("{} in hash set", algorithm)));
+    }
+    if let Some(report) = &info.bazaar {
+        let family = report.family.as_deref().unwrap_or("unknown family");
+        overview.push((
+            "MalwareBazaar",
+            format!("{} [{}]", family, report.tags.join(", ")),
+        ));
+    }
+    for (name, value) in overview {
+        writeln!(
+            out,
+            "<tr><th>{}</th><td class=\"mono\">{}</td></tr>",
+            name,
+            escape(&value)
+        )?;
+    }
+    writeln!(out, "</table>")?;
+
+    let warnings = anomalies::warnings(info);
+    if !warnings.is_empty() {
+        writeln!(out, "<h2>Warnings</h2>\n<ul>")?;
+        for warning in &warnings {
+            writeln!(out, "<li class=\"warn\">{}</li>", escape(warning))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    if let Some(violations) = violations {
+        writeln!(out, "<h2>Policy</h2>")?;
+        if violations.is_empty() {
+            writeln!(out, "<p class=\"ok\">Passed</p>")?;
+        } else {
+            writeln!(out, "<table>\n<tr><th>Rule</th><th>Violation</th></tr>")?;
+            for violation in violations {
+                writeln!(
+                    out,
+                    "<tr><td class=\"bad mono\">{}</td><td>{}</td></tr>",
+                    escape(&violation.rule),
+                    escape(&violation.message)
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+    }
+
+    writeln!(out, "<h2>Security</h2>\n<table>")?;
+    let security = &info.security;
+    let mut mitigations = vec![
+        ("NX", security.nx, None),
+        ("PIE / ASLR", security.pie, None),
+        ("Stack canary", security.canary, None),
+        ("No RWX mappings", security.rwx.is_empty(), None),
+    ];
+    if info.format == "ELF" {
+        mitigations.push((
+            "RELRO",
+            security.relro != Relro::None,
+            Some(security.relro.name().to_string()),
+        ));
+        mitigations.push((
+            "FORTIFY_SOURCE",
+            !security.fortified.is_empty(),
+            Some(security.fortified.join(", ")),
+        ));
+    }
+    if info.format == "ELF" || security.pe.is_some() {
+        mitigations.push((
+            "CFI",
+            !security.cfi.is_empty(),
+            Some(security.cfi.join(", ")),
+        ));
+    }
+    if let Some(pe) = &security.pe {
+        mitigations.push(("High-entropy VA", pe.high_entropy_va, None));
+        mitigations.push((
+            "SEH",
+            pe.safe_seh != SafeSeh::Missing,
+            Some(pe.safe_seh.name().to_string()),
+        ));
+    }
+    if let Some(macho) = &security.macho {
+        mitigations.push(("Code signature", macho.signed, None));
+        mitigations.push(("Hardened runtime", macho.hardened_runtime, None));
+    }
+    for (name, on, detail) in mitigations {
+        writeln!(
+            out,
+            "<tr><th>{}</th><td class=\"{}\">{}</td><td>{}</td></tr>",
+            name,
+            if on { "ok" } else { "bad" },
+            if on { "yes" } else { "no" },
+            escape(&detail.unwrap_or_default())
+        )?;
+    }
+    writeln!(out, "</table>")?;
+
+    if !findings.is_empty() {
+        writeln!(out, "<h2>Findings ({})</h2>\n<table>", findings.len())?;
+        writeln!(
+            out,
+            "<tr><th>Severity</th><th>Category</th><th>Finding</th><th>Evidence</th></tr>"
+        )?;
+        for finding in findings {
+            writeln!(
+                out,
+                "<tr><td class=\"sev {}\">{}</td><td>{}</td><td>{}</td><td class=\"mono\">{}</td></tr>",
+                finding.severity.name(),
+                finding.severity.name(),
+                finding.category.name(),
+                escape(&finding.title),
+                escape(&finding.evidence)
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if !derived.is_empty() {
+        writeln!(out, "<h2>Derived Fields</h2>\n<table>")?;
+        for (name, value) in derived {
+            writeln!(
+                out,
+                "<tr><th>{}</th><td class=\"mono\">{}</td></tr>",
+                escape(name),
+                escape(value)
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    writeln!(out, "<h2>Sections ({})</h2>\n<table>", info.sections.len())?;
+    writeln!(
+        out,
+        "<tr><th>Name</th><th>Address</th><th>Size</th><th>Entropy</th><th>SHA256</th></tr>"
+    )?;
+    for section in &info.sections {
+        let entropy = entropy::calculate_entropy(binary.section_data(section));
+        // Green through red as the data approaches random.
+        let hue = 120.0 - entropy / 8.0 * 120.0;
+        writeln!(
+            out,
+            "<tr><td class=\"mono\">{}</td><td class=\"mono\">0x{:x}</td><td class=\"mono\">0x{:x}</td>\
+             <td><span class=\"bar\"><span style=\"width: {:.0}%; background: hsl({:.0}, 70%, 45%)\"></span></span> {:.2}</td>\
+             <td class=\"mono\">{}</td></tr>",
+            escape(&section.name),
+            section.addr,
+            section.size,
+            entropy / 8.0 * 100.0,
+            hue,
+            entropy,
+            section.hashes.as_ref().map_or("-", |h| h.sha256.as_str())
+        )?;
+    }
+    writeln!(out, "</table>")?;
+
+    if args.runs_yara() {
+        writeln!(out, "<h2>YARA</h2>")?;
+        if let Some(scope) = yara_scope {
+            writeln!(
+                out,
+                "<p>Scope: {} (file offsets 0x{:x}-0x{:x})</p>",
+                escape(&scope.name),
+                scope.range.start,
+                scope.range.end
+            )?;
+        }
+        for (_, message) in yara_skipped {
+            writeln!(out, "<p class=\"bad\">Skipped {}</p>", escape(message))?;
+        }
+        if yara_timed_out {
+            writeln!(
+                out,
+                "<p class=\"bad\">Timed out after {}s; matches may be incomplete.</p>",
+                args.yara_timeout
+            )?;
+        }
+        if let Some(e) = yara_error {
+            writeln!(out, "<p class=\"bad\">{}</p>", escape(e))?;
+        }
+        if info.yara_matches.is_empty() {
+            if yara_error.is_none() {
+                writeln!(out, "<p>No matches found.</p>")?;
+            }
+        } else {
+            writeln!(
+                out,
+                "<table>\n<tr><th>Rule</th><th>Tags</th><th>Score</th><th>Description</th>\
+                 <th>Author</th><th>Reference</th></tr>"
+            )?;
+            for m in &info.yara_matches {
+                let meta = |key| escape(m.meta(key).unwrap_or("-"));
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&m.rule),
+                    escape(&m.tags.join(", ")),
+                    meta("score"),
+                    meta("description"),
+                    meta("author"),
+                    meta("reference")
+                )?;
+            }
+            writeln!(out, "</table>")?;
+            writeln!(
+                out,
+                "<table>\n<tr><th>Rule</th><th>String</th><th>Offset</th><th>Data</th><th>Address</th></tr>"
+            )?;
+            for hit in yara::locate_hits(&info.yara_matches, info) {
+                let location = match (&hit.addr, &hit.function) {
+                    (Some(addr), Some((name, delta))) => {
+                        format!("0x{:x} ({}+0x{:x})", addr, name, delta)
+                    }
+                    (Some(addr), None) => format!("0x{:x}", addr),
+                    _ => "not mapped".to_string(),
+                };
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td class=\"mono\">{}</td><td class=\"mono\">{}</td>\
+                     <td class=\"mono\">{}</td><td class=\"mono\">{}</td></tr>",
+                    escape(&hit.rule),
+                    escape(&hit.identifier),
+                    escape(&report::scoped_offset(yara_scope, hit.offset)),
+                    escape(&yara::preview(&hit.data)),
+                    escape(&location)
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+    }
+
+    if !info.disassembly.is_empty() {
+        writeln!(out, "<h2>Disassembly</h2>\n<pre>")?;
+        for ins in info.disassembly.iter().take(MAX_INSTRUCTIONS) {
+            writeln!(
+                out,
+                "0x{:x}:  {:<10} {}",
+                ins.address,
+                escape(&ins.mnemonic),
+                escape(&ins.op_str)
+            )?;
+        }
+        if info.disassembly.len() > MAX_INSTRUCTIONS {
+            writeln!(
+                out,
+                "... ({} more instructions)",
+                info.disassembly.len() - MAX_INSTRUCTIONS
+            )?;
+        }
+        writeln!(out, "</pre>")?;
+    }
+
+    writeln!(out, "<h2>Strings ({})</h2>\n<pre>", info.strings.len())?;
+    for s in info.strings.iter().take(MAX_STRINGS) {
+        writeln!(out, "{}", escape(s))?;
+    }
+    if info.strings.len() > MAX_STRINGS {
+        writeln!(out, "... and {} more", info.strings.len() - MAX_STRINGS)?;
+    }
+    writeln!(out, "</pre>\n</body>\n</html>")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binary_insight_core::analysis::findings::{Category, Finding, Severity};
+    use binary_insight_core::binary::BinaryFile;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_write_html() {
+        let binary = BinaryFile::from_raw("<script>".to_string(), vec![0x90; 16], "x86_64", 0);
+        let findings = [Finding {
+            id: "weak_crypto.md5".to_string(),
+            title: "Weak algorithm MD5 in use".to_string(),
+            severity: Severity::Medium,
+            category: Category::Crypto,
+            evidence: "a < b & \"c\"".to_string(),
+            addresses: Vec::new(),
+            attack: Vec::new(),
+        }];
+        let derived = BTreeMap::from([("owner".to_string(), "<b>ops</b>".to_string())]);
+        let html = report::tests::render(&binary, &findings, &derived, |out, input| {
+            Ok(write_html(out, input)?)
+        });
+
+        assert!(html.contains("<h1>&lt;script&gt;</h1>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<h2>Findings (1)</h2>"));
+        assert!(html.contains(
+            "<td class=\"sev medium\">medium</td><td>crypto</td><td>Weak algorithm MD5 in use</td>\
+             <td class=\"mono\">a &lt; b &amp; &quot;c&quot;</td>"
+        ));
+        assert!(html.contains(
+            "<h2>Derived Fields</h2>\n<table>\n<tr><th>owner</th><td class=\"mono\">&lt;b&gt;ops&lt;/b&gt;</td></tr>"
+        ));
+    }
+}
@@ -1,170 +1,818 @@
-use anyhow::Result;
-use binary_insight_core::analysis::{disassembly, entropy, hashes, yara};
-use binary_insight_core::binary::BinaryFile;
+use anyhow::{Context, Result};
+use binary_insight_core::analysis::findings::Severity;
+use binary_insight_core::analysis::template::Template;
+use binary_insight_core::analysis::xrefs::XrefIndex;
+use binary_insight_core::analysis::{
+    capabilities, charset, deobfuscate, disassembly, entropy, evasion, expr, findings, flirt,
+    hashdb, hashes, pyinstaller, sbom, string_xrefs, syscalls, template, vulns, yaml, yara,
+};
+use binary_insight_core::binary::{AnalysisOptions, BinaryData, BinaryFile};
+use clap::builder::PossibleValuesParser;
 use clap::Parser;
 use std::fs;
-use tracing::info;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
 
+mod bazaar;
+mod bundle;
+mod commands;
+pub mod config;
+mod csv;
+mod html;
+mod report;
 pub mod tui;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<commands::Command>,
+
     /// Path to the binary file to analyze
     #[arg(required = true)]
-    file: String,
+    file: Option<String>,
 
     /// Run in CLI mode instead of TUI
     #[arg(short, long)]
     cli: bool,
 
-    /// Path to YARA rules file
+    /// YARA rules file, or a directory searched recursively for .yar/.yara files (repeatable)
+    #[arg(long, value_name = "PATH")]
+    yara: Vec<String>,
+
+    /// Set a YARA external variable, e.g. `owner=alice` or `level=3` (repeatable)
+    #[arg(
+        long,
+        value_name = "NAME=VALUE",
+        requires = "yara",
+        value_parser = parse_define
+    )]
+    yara_define: Vec<(String, yara::ExternalValue)>,
+
+    /// Scan only this section (e.g. `.text`) or virtual address range (`0x1000-0x5000`)
+    #[arg(long, value_name = "SECTION|START-END")]
+    yara_scope: Option<String>,
+
+    /// Don't scan with the bundled starter YARA rules
+    #[arg(long)]
+    no_builtin_rules: bool,
+
+    /// Stop the YARA scan after this many seconds, keeping matches so far (0: no limit)
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    yara_timeout: u64,
+
+    /// Matches kept per YARA string; later ones are not counted by `#a`
+    #[arg(long, value_name = "N", default_value_t = 1_000)]
+    yara_max_matches: u32,
+
+    /// Bytes of matched data kept per YARA match
+    #[arg(long, value_name = "BYTES", default_value_t = 512)]
+    yara_max_match_length: usize,
+
+    /// FLIRT-style `.pat` signature file or directory (repeatable)
+    #[arg(long = "sigs", value_name = "PATH")]
+    sigs: Vec<String>,
+
+    /// Directory of capability rules to use instead of the bundled ones
+    #[arg(long = "capa-rules", value_name = "DIR")]
+    capa_rules: Option<String>,
+
+    /// String deobfuscation presets to run (comma-separated)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(deobfuscate::PRESETS)
+    )]
+    deobfuscate: Vec<String>,
+
+    /// Hex key for the xor/rc4 deobfuscators instead of searching for one
+    #[arg(long, value_name = "HEX", requires = "deobfuscate", value_parser = parse_hex)]
+    deobf_key: Option<Vec<u8>>,
+
+    /// Also extract non-ASCII strings in these charsets (comma-separated)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(charset::CHARSETS)
+    )]
+    charsets: Vec<String>,
+
+    /// Path to a TOML config file (e.g. `[derived]` report fields)
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Show disassembly around each YARA match
+    #[arg(long)]
+    context_disasm: bool,
+
+    /// OSV advisories (a JSON file or a directory of them) to check embedded library versions against
+    #[arg(long, value_name = "PATH")]
+    vuln_db: Option<String>,
+
+    /// Known-good hash set (NSRL `NSRLFile.txt`, md5sum/sha256sum output; repeatable)
+    #[arg(long, value_name = "PATH")]
+    known_good: Vec<String>,
+
+    /// Skip the analysis of files found in a --known-good set
+    #[arg(long, requires = "known_good")]
+    skip_known_good: bool,
+
+    /// Local hash database to consult and record this file in
+    /// (default: ~/.local/share/binaryinsight/hashes.db)
+    #[arg(long, value_name = "PATH")]
+    hashdb: Option<String>,
+
+    /// Neither consult nor update the local hash database
+    #[arg(long, conflicts_with = "hashdb")]
+    no_hashdb: bool,
+
+    /// Look the SHA-256 up on MalwareBazaar (needs curl; set ABUSECH_AUTH_KEY to your abuse.ch key)
     #[arg(long)]
-    yara: Option<String>,
+    bazaar: bool,
+
+    /// Write a report in this format instead of starting the TUI
+    #[arg(
+        long,
+        group = "rendered",
+        value_parser = PossibleValuesParser::new(["text", "html", "json", "yaml", "cyclonedx", "spdx"])
+    )]
+    report: Option<String>,
+
+    /// Render the report through this template instead (`{{ expr }}`, `{% for %}`, `{% if %}`)
+    #[arg(long, value_name = "PATH", group = "rendered")]
+    template: Option<String>,
+
+    /// Write the `--report` or `--template` output here instead of stdout
+    #[arg(short, long, value_name = "PATH", requires = "rendered")]
+    output: Option<String>,
+
+    /// TOML policy to check the binary against; exits with status 1 on violations
+    #[arg(long, value_name = "PATH")]
+    policy: Option<String>,
+
+    /// Exit with status 1 on findings at or above a severity, YARA matches or
+    /// policy violations (comma-separated); analysis errors exit with 2
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(FAIL_ON)
+    )]
+    fail_on: Vec<String>,
+
+    /// Treat the input as a raw blob (e.g. shellcode) instead of parsing headers
+    #[arg(long)]
+    raw: bool,
+
+    /// Keep going on malformed files, analyzing whatever headers parse
+    #[arg(long, conflicts_with = "raw")]
+    permissive: bool,
+
+    /// Force the architecture used for disassembly
+    #[arg(long, value_parser = PossibleValuesParser::new(disassembly::SUPPORTED_ARCHES))]
+    arch: Option<String>,
+
+    /// Load address of a raw blob (decimal or 0x-prefixed hex)
+    #[arg(long, requires = "raw", value_parser = parse_number)]
+    base: Option<u64>,
+
+    /// Write the binary as mapped in memory (sections at their addresses) to this file
+    #[arg(long, value_name = "PATH")]
+    dump_image: Option<String>,
+
+    /// Base address to relocate the dumped image to (decimal or 0x-prefixed hex)
+    #[arg(long, requires = "dump_image", value_parser = parse_number)]
+    dump_base: Option<u64>,
+
+    /// Save the main icon of a PE file as an .ico file
+    #[arg(long, value_name = "PATH")]
+    extract_icon: Option<String>,
+
+    /// Build the control-flow graph of a function (symbol name or address)
+    #[arg(long, value_name = "FUNCTION")]
+    cfg: Option<String>,
+
+    /// Write the `--cfg` graph to this file in Graphviz DOT format
+    #[arg(long, value_name = "PATH", requires = "cfg")]
+    cfg_dot: Option<String>,
+
+    /// List code references to an address or symbol
+    #[arg(long, value_name = "ADDR")]
+    xrefs_to: Option<String>,
+
+    /// Write a zip with the text report, extracted artifacts and a hash manifest
+    #[arg(long, value_name = "PATH")]
+    bundle: Option<String>,
+
+    /// Write sections, symbols, imports, strings and findings as CSV files into this directory
+    #[arg(long, value_name = "DIR")]
+    export_csv: Option<String>,
+
+    /// Extract the compiled scripts of a PyInstaller bundle into this directory
+    #[arg(long, value_name = "DIR")]
+    extract_pyinstaller: Option<String>,
+
+    /// Write every embedded file listed under "Embedded Files" into this directory
+    #[arg(long, value_name = "DIR")]
+    carve: Option<String>,
+
+    /// Analyze embedded PE/ELF executables and archive members as files of their own, this many levels deep
+    #[arg(long, value_name = "DEPTH", default_value_t = 0)]
+    unpack_depth: usize,
+
+    /// Stop unpacking once the unpacked files add up to this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = 256 * 1024 * 1024, value_parser = parse_number)]
+    unpack_budget: u64,
 }
 
-fn main() -> Result<()> {
+impl Args {
+    /// Whether a YARA scan runs, with `--yara` rules or the built-in ones.
+    pub(crate) fn runs_yara(&self) -> bool {
+        !self.yara.is_empty() || (yara::HAS_BUILTIN_RULES && !self.no_builtin_rules)
+    }
+
+    /// Whether only the TUI runs, which analyzes each tab on first view.
+    fn tui_only(&self) -> bool {
+        !self.cli && self.report.is_none() && self.template.is_none() && !self.needs_full_analysis()
+    }
+
+    /// Whether a flag writes files or asks for results the TUI does not
+    /// compute itself.
+    fn needs_full_analysis(&self) -> bool {
+        self.carve.is_some()
+            || self.dump_image.is_some()
+            || self.extract_icon.is_some()
+            || self.extract_pyinstaller.is_some()
+            || self.export_csv.is_some()
+            || self.bundle.is_some()
+            || self.cfg.is_some()
+            || self.unpack_depth > 0
+            || self.vuln_db.is_some()
+            || !self.charsets.is_empty()
+            || !self.deobfuscate.is_empty()
+            || self.bazaar
+    }
+}
+
+/// Conditions `--fail-on` accepts.
+const FAIL_ON: &[&str] = &["info", "low", "medium", "high", "yara", "policy"];
+
+pub(crate) fn parse_number(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|e| format!("invalid number '{}': {}", s, e))
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    hex::decode(s.trim_start_matches("0x")).map_err(|e| format!("invalid hex '{}': {}", s, e))
+}
+
+fn parse_define(s: &str) -> Result<(String, yara::ExternalValue), String> {
+    yara::parse_define(s).map_err(|e| e.to_string())
+}
+
+/// Resolves a symbol name, falling back to a numeric address.
+fn resolve_function(binary: &BinaryFile, name: &str) -> Result<u64> {
+    if let Some(sym) = binary.info.symbols.iter().find(|s| s.name == name) {
+        return Ok(sym.addr);
+    }
+    parse_number(name).map_err(|_| anyhow::anyhow!("Unknown function '{}'", name))
+}
+
+fn main() -> ExitCode {
     tracing_subscriber::fmt::init();
     let args = Args::parse();
+    let result = match &args.command {
+        Some(command) => commands::run(command).map(|()| false),
+        None => analyze(&args),
+    };
+    match result {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Runs the analysis, returning whether a `--fail-on` condition was met.
+fn analyze(args: &Args) -> Result<bool> {
+    let file = args.file.as_deref().expect("clap requires a file");
+
+    info!("Analyzing file: {}", file);
+
+    let config = match &args.config {
+        Some(path) => config::Config::load(path)?,
+        None => config::Config::default(),
+    };
+    let policy = args.policy.as_ref().map(config::load_policy).transpose()?;
+    if args.fail_on.iter().any(|c| c == "policy") && policy.is_none() {
+        anyhow::bail!("--fail-on policy needs a --policy file");
+    }
+
+    let tui_only = args.tui_only();
+    let data = BinaryData::open(file)?;
+    // Hashes and entropy describe the file as given, so they are taken before
+    // loading unwraps any compression. The TUI shows neither and skips them
+    // unless --known-good needs the hashes.
+    let file_stats = (!tui_only || !args.known_good.is_empty()).then(|| {
+        (
+            hashes::calculate_hashes(&data),
+            entropy::calculate_entropy(&data),
+        )
+    });
 
-    info!("Analyzing file: {}", args.file);
+    let mut known_good = None;
+    if let Some((file_hashes, _)) = file_stats.as_ref().filter(|_| !args.known_good.is_empty()) {
+        let mut known = hashes::KnownHashes::default();
+        for path in &args.known_good {
+            known.extend(hashes::KnownHashes::load(path)?);
+        }
+        known_good = known.lookup(file_hashes);
+        if let Some(algorithm) = known_good {
+            info!("{} is known good ({} match)", file, algorithm);
+            if args.skip_known_good {
+                println!("{}: known good ({} match), skipped", file, algorithm);
+                return Ok(false);
+            }
+        }
+    }
 
-    let mut binary = BinaryFile::load(&args.file)?;
+    let name = Path::new(file)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let passes = if tui_only {
+        AnalysisOptions::lazy()
+    } else {
+        AnalysisOptions::default()
+    };
+    let options = AnalysisOptions {
+        permissive: args.permissive,
+        ..passes
+    };
+    let mut binary = if args.raw {
+        BinaryFile::from_raw(
+            name,
+            data,
+            args.arch.as_deref().unwrap_or("Unknown"),
+            args.base.unwrap_or(0),
+        )
+    } else {
+        BinaryFile::from_bytes_with_options(name, data, options)?
+    };
+    for warning in &binary.info.parse_warnings {
+        warn!("Parse warning ({}): {}", warning.stage, warning.message);
+    }
+    if let Some(arch) = &args.arch {
+        binary.info.arch = arch.clone();
+        binary.info.arm_modes.clear();
+    }
     info!("Identified format: {}", binary.identify());
+    if tui_only {
+        println!("Running in TUI mode");
+        tui::run(binary, args, None)?;
+        return Ok(false);
+    }
+    binary.unpack_embedded(args.unpack_depth, args.unpack_budget, options);
 
-    // Calculate advanced analysis data
-    // We need to read the raw file content again or expose it from binary if stored.
-    // BinaryFile stores 'data', but let's read it for now to be safe or assuming BinaryFile holds it.
-    // Checking binary-insight-core code, BinaryFile struct likely has `pub data: Vec<u8>`.
-    // Let's verify via view_file if needed, but assuming standard flow:
-
-    // Actually, let's look at BinaryFile definition first to be sure.
-    // Wait, I can't look inside replace_file_content.
-    // I will assume reading file again for safety in this step or I'll check it in next step if this fails.
-    // Better: Read file content here.
-    let file_data = fs::read(&args.file)?;
-    let hashes = hashes::calculate_hashes(&file_data);
-    let entropy_val = entropy::calculate_entropy(&file_data);
-
-    // Populate analysis data
+    let xrefs = analyze_functions(&mut binary, args)?;
+    if !args.sigs.is_empty() {
+        info!(
+            "Recognized {} library functions",
+            binary.info.signature_matches.len()
+        );
+    }
+    info!("Discovered {} functions", binary.info.functions.len());
+    if let Some(path) = &args.vuln_db {
+        let db = vulns::VulnDatabase::load(path)?;
+        binary.info.vulnerabilities = vulns::find_vulnerabilities(&binary.info, &db);
+        info!(
+            "Matched {} known vulnerabilities against {} advisories",
+            binary.info.vulnerabilities.len(),
+            db.len()
+        );
+    }
+
+    let (hashes, entropy_val) = file_stats.expect("taken unless only the TUI runs");
     binary.info.analysis = Some(binary_insight_core::binary::AnalysisData {
         entropy: entropy_val,
         hashes: Some(hashes.clone()),
+        known_good: known_good.map(str::to_string),
     });
 
-    // Perform Disassembly Analysis
-    let code_section = binary
-        .info
-        .sections
-        .iter()
-        .find(|s| s.name == ".text" || s.name == "__text" || s.name.contains("text"));
+    disassemble_code(&mut binary);
 
-    if let Some(section) = code_section {
-        let start = section.offset as usize;
-        let end = start + section.size as usize;
-        let start = start.min(file_data.len());
-        let end = end.min(file_data.len());
+    if !args.charsets.is_empty() {
+        let charsets: Vec<_> = args
+            .charsets
+            .iter()
+            .filter_map(|name| charset::Charset::from_name(name))
+            .collect();
+        binary.info.charset_strings = charset::extract(&binary.data, &charsets);
+    }
 
-        if start < end {
-            let code = &file_data[start..end];
-            // Disassemble up to 1000 instructions for TUI/CLI
-            if let Ok(instructions) =
-                disassembly::disassemble(&binary.info.arch, code, section.addr, 1000)
-            {
-                binary.info.disassembly = instructions;
+    if !args.deobfuscate.is_empty() {
+        let deobfuscators: Vec<_> = args
+            .deobfuscate
+            .iter()
+            .filter_map(|name| deobfuscate::preset(name, args.deobf_key.as_deref()))
+            .collect();
+        binary.info.decoded_strings = deobfuscate::run(&deobfuscators, &binary.data, &binary.info);
+    }
+
+    let yara_scan = scan_yara(&mut binary, args, Path::new(file))?;
+    if yara_scan.timed_out {
+        warn!("YARA scan timed out after {}s", args.yara_timeout);
+    }
+
+    let hashdb_path = match &args.hashdb {
+        _ if args.no_hashdb => None,
+        Some(path) => Some(PathBuf::from(path)),
+        None => hashdb::HashDb::default_path(),
+    };
+    if let Some(path) = &hashdb_path {
+        match hashdb::HashDb::open(path) {
+            Ok(mut db) => {
+                binary.info.hash_record = db.get(&hashes.sha256).cloned();
+                db.record_seen(&hashes.sha256, &binary.name, unix_now());
+                if let Err(e) = db.save(path) {
+                    warn!("Failed to update hash database {}: {:#}", path.display(), e);
+                }
             }
+            Err(e) => warn!("Hash database unavailable: {:#}", e),
         }
     }
 
-    if args.cli {
-        println!("=== Binary Analysis Report ===");
-        println!("File:         {}", binary.name);
-        println!("Format:       {}", binary.identify());
-        println!("Arch:         {}", binary.info.arch);
-        println!("Entry Point:  0x{:x}", binary.info.entry_point);
-
-        println!("\n[Advanced Analysis]");
-        println!("  Entropy: {:.4} (Scale: 0.0-8.0)", entropy_val);
-        println!("  MD5:     {}", hashes.md5);
-        println!("  SHA1:    {}", hashes.sha1);
-        println!("  SHA256:  {}", hashes.sha256);
-
-        println!("\n[Security Features]");
-        println!("  PIE:    {}", binary.info.security.pie);
-        println!("  NX:     {}", binary.info.security.nx);
-        println!("  RELRO:  {}", binary.info.security.relro);
-        println!("  Canary: {}", binary.info.security.canary);
-
-        if let Some(yara_path) = &args.yara {
-            println!("\n[YARA Scan]");
-            match fs::read_to_string(yara_path) {
-                Ok(rules) => match yara::YaraScanner::scan(&file_data, &rules) {
-                    Ok(matches) => {
-                        if matches.is_empty() {
-                            println!("  No matches found.");
-                        } else {
-                            for m in matches {
-                                println!("  Match: {}", m);
-                            }
-                        }
-                    }
-                    Err(e) => println!("  Scan failed: {}", e),
-                },
-                Err(e) => println!("  Failed to read YARA file: {}", e),
-            }
+    let mut bazaar_error = None;
+    if args.bazaar {
+        match bazaar::query(&hashes.sha256) {
+            Ok(report) => binary.info.bazaar = report,
+            Err(e) => bazaar_error = Some(format!("Lookup failed: {:#}", e)),
         }
+    }
+
+    if let Some(path) = &args.dump_image {
+        let image = binary.memory_image(args.dump_base)?;
+        fs::write(path, &image.data)?;
+        info!(
+            "Wrote 0x{:x}-byte memory image based at 0x{:x} to {} ({} relocations applied)",
+            image.data.len(),
+            image.base,
+            path,
+            image.relocations_applied
+        );
+    }
 
-        println!("\n[Disassembly (Entry Point / .text)]");
-        if !binary.info.disassembly.is_empty() {
-            // Show only first 20 for CLI nicely or all if piped? Let's show first 20-50.
-            for ins in binary.info.disassembly.iter().take(50) {
-                println!(
-                    "  0x{:x}:  {:<10} {}",
-                    ins.address, ins.mnemonic, ins.op_str
-                );
+    if let Some(path) = &args.extract_icon {
+        match &binary.info.icon {
+            Some(icon) => {
+                fs::write(path, &icon.ico)?;
+                info!("Wrote {}x{} icon to {}", icon.width, icon.height, path);
             }
-            if binary.info.disassembly.len() > 50 {
-                println!(
-                    "  ... ({} more instructions)",
-                    binary.info.disassembly.len() - 50
-                );
+            None => anyhow::bail!("{} has no icon resource", binary.name),
+        }
+    }
+
+    let cfg = match &args.cfg {
+        Some(function) => {
+            let addr = resolve_function(&binary, function)?;
+            let graph = binary.function_cfg(addr)?;
+            if let Some(path) = &args.cfg_dot {
+                fs::write(path, graph.to_dot(function))?;
+                info!("Wrote CFG of {} to {}", function, path);
             }
-        } else {
-            println!("  No disassembly available (checked .text section).");
+            Some(graph)
         }
+        None => None,
+    };
 
-        println!("\n[Sections]");
-        println!("{:<20} {:<18} {:<18}", "Name", "Address", "Size");
-        for section in &binary.info.sections {
-            println!(
-                "{:<20} 0x{:<16x} 0x{:<16x}",
-                section.name, section.addr, section.size
-            );
+    let xrefs_to = match &args.xrefs_to {
+        Some(target) => {
+            let addr = resolve_function(&binary, target)?;
+            Some((addr, xrefs.to(addr).to_vec()))
         }
+        None => None,
+    };
 
-        println!("\n[Symbols]");
-        println!("Total symbols: {}", binary.info.symbols.len());
-        // Show first 20 symbols
-        for symbol in binary.info.symbols.iter().take(20) {
-            println!("{:<40} 0x{:<16x}", symbol.name, symbol.addr);
+    if let Some(dir) = &args.extract_pyinstaller {
+        match &binary.info.pyinstaller {
+            Some(archive) => {
+                let written = extract_pyinstaller_scripts(&binary, archive, Path::new(dir))?;
+                info!("Extracted {} PyInstaller scripts to {}", written, dir);
+            }
+            None => anyhow::bail!("{} is not a PyInstaller bundle", binary.name),
         }
-        if binary.info.symbols.len() > 20 {
-            println!("... and {} more", binary.info.symbols.len() - 20);
+    }
+
+    if let Some(dir) = &args.carve {
+        let dir = Path::new(dir);
+        fs::create_dir_all(dir)?;
+        for file in &binary.info.embedded_files {
+            let name = format!("{:08x}.{}", file.offset, file.kind.extension());
+            fs::write(dir.join(name), file.bytes(&binary.data))?;
+        }
+        info!(
+            "Carved {} embedded files to {}",
+            binary.info.embedded_files.len(),
+            dir.display()
+        );
+    }
+
+    // Policy severity overrides apply to every report and to --fail-on.
+    let findings = match &policy {
+        Some(policy) => policy.findings(&binary.info),
+        None => findings::collect(&binary.info),
+    };
+    let derived = expr::derive_fields(&binary, &config.derived);
+
+    if let Some(dir) = &args.export_csv {
+        csv::export(Path::new(dir), &binary, &findings, &derived)?;
+        info!("Wrote CSV tables to {}", dir);
+    }
+
+    let violations = policy.as_ref().map(|p| p.evaluate(&binary, &findings));
+
+    let report = report::ReportInput {
+        binary: &binary,
+        args,
+        hashes: &hashes,
+        entropy: entropy_val,
+        findings: &findings,
+        derived: &derived,
+        yara_error: yara_scan.error.as_deref(),
+        yara_skipped: &yara_scan.skipped,
+        yara_timed_out: yara_scan.timed_out,
+        yara_scope: yara_scan.scope.as_ref(),
+        bazaar_error: bazaar_error.as_deref(),
+        cfg: cfg.as_ref(),
+        xrefs_to: xrefs_to
+            .as_ref()
+            .map(|(addr, refs)| (*addr, refs.as_slice())),
+        violations: violations.as_deref(),
+    };
+
+    if let Some(path) = &args.bundle {
+        let mut text = Vec::new();
+        report::write_text(&mut text, &report)?;
+        let artifacts = binary.artifacts();
+        let written = bundle::write_bundle(
+            Path::new(path),
+            &binary.name,
+            &hashes.sha256,
+            &text,
+            &artifacts,
+        )?;
+        info!("Wrote report bundle with {} artifacts to {}", written, path);
+    }
+
+    let template = match &args.template {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read template {}", path))?;
+            let template =
+                Template::parse(&source).with_context(|| format!("Invalid template {}", path))?;
+            Some(template.render(&template::report_context(
+                &binary,
+                &report.analysis_report(),
+            )?)?)
         }
+        None => None,
+    };
+    let format = match &template {
+        Some(_) => Some("template"),
+        None => args.report.as_deref(),
+    };
 
-        println!("\n[Strings]");
-        println!("Total strings found: {}", binary.info.strings.len());
-        // Show first 20 strings
-        for s in binary.info.strings.iter().take(20) {
-            println!("{}", s);
+    match (format, &args.output) {
+        (Some("template"), Some(path)) => {
+            fs::write(path, template.unwrap_or_default())?;
+            info!("Wrote templated report to {}", path);
         }
-        if binary.info.strings.len() > 20 {
-            println!("... and {} more", binary.info.strings.len() - 20);
+        (Some("template"), None) => print!("{}", template.unwrap_or_default()),
+        (Some(format), Some(path)) => {
+            let mut file = io::BufWriter::new(fs::File::create(path)?);
+            write_report(&mut file, format, &report)?;
+            file.flush()?;
+            info!("Wrote {} report to {}", format, path);
         }
+        (Some(format), None) => write_report(&mut io::stdout().lock(), format, &report)?,
+        (None, _) if args.cli => report::write_text(&mut io::stdout().lock(), &report)?,
+        (None, _) => {
+            println!("Running in TUI mode");
+            tui::run(binary, args, Some(xrefs))?;
+            return Ok(false);
+        }
+    }
+
+    // A policy fails the run unless `--fail-on` says otherwise.
+    let policy_only = [String::from("policy")];
+    let fail_on = if args.fail_on.is_empty() && policy.is_some() {
+        &policy_only[..]
     } else {
-        println!("Running in TUI mode");
-        tui::run(binary)?;
+        &args.fail_on[..]
+    };
+    Ok(fail_on.iter().any(|condition| match condition.as_str() {
+        "yara" => !binary.info.yara_matches.is_empty(),
+        "policy" => violations.as_ref().is_some_and(|v| !v.is_empty()),
+        threshold => {
+            let threshold = Severity::from_name(threshold).unwrap_or(Severity::High);
+            findings.iter().any(|finding| finding.severity >= threshold)
+        }
+    }))
+}
+
+/// Function discovery and the passes built on it: library signatures,
+/// string references, syscalls, anti-analysis checks and capabilities.
+/// Logs nothing, as the TUI may be on screen.
+pub(crate) fn analyze_functions(binary: &mut BinaryFile, args: &Args) -> Result<XrefIndex> {
+    if !args.sigs.is_empty() {
+        let mut signatures = flirt::SignatureSet::default();
+        for path in &args.sigs {
+            signatures.extend(flirt::SignatureSet::load(path)?);
+        }
+        binary.apply_signatures(&signatures);
     }
 
+    binary.discover_functions();
+    let xrefs = binary.xrefs();
+    binary.info.string_xrefs = string_xrefs::find_string_xrefs(binary, &xrefs);
+    binary.info.syscalls = syscalls::find_syscalls(binary);
+    binary.info.anti_debug = evasion::find_anti_debug(binary);
+    binary.info.anti_vm = evasion::find_anti_vm(binary);
+    let rules = match &args.capa_rules {
+        Some(path) => capabilities::RuleSet::load(path)?,
+        None => capabilities::RuleSet::bundled(),
+    };
+    binary.info.capabilities = capabilities::find_capabilities(binary, &rules);
+    Ok(xrefs)
+}
+
+/// Disassembles the start of the code section, or of the section holding
+/// the entry point.
+pub(crate) fn disassemble_code(binary: &mut BinaryFile) {
+    let code_section = binary
+        .info
+        .sections
+        .iter()
+        .find(|s| s.name == ".text" || s.name == "__text" || s.name.contains("text"))
+        .or_else(|| binary.info.section_for_addr(binary.info.entry_point));
+
+    if let Some(section) = code_section {
+        let code = binary.section_data(section);
+        if !code.is_empty() {
+            // Disassemble up to 1000 instructions for TUI/CLI
+            if let Ok(instructions) = disassembly::disassemble_with_data(
+                binary.info.arch_at(section.addr),
+                code,
+                section.addr,
+                1000,
+                true,
+            ) {
+                binary.info.disassembly = instructions;
+            }
+        }
+    }
+}
+
+/// How the YARA scan went; the matches themselves go to `info.yara_matches`.
+#[derive(Default)]
+pub(crate) struct YaraScan {
+    pub error: Option<String>,
+    pub skipped: Vec<(PathBuf, String)>,
+    pub timed_out: bool,
+    pub scope: Option<yara::ScanScope>,
+}
+
+/// Scans with the `--yara` rules and the built-in ones, if any run.
+pub(crate) fn scan_yara(binary: &mut BinaryFile, args: &Args, path: &Path) -> Result<YaraScan> {
+    let mut result = YaraScan::default();
+    if !args.runs_yara() {
+        return Ok(result);
+    }
+    result.scope = args
+        .yara_scope
+        .as_deref()
+        .map(|spec| yara::ScanScope::resolve(&binary.info, spec, binary.data.len() as u64))
+        .transpose()
+        .context("Invalid --yara-scope")?;
+    let limits = yara::ScanLimits {
+        timeout: (args.yara_timeout > 0).then(|| Duration::from_secs(args.yara_timeout)),
+        max_string_matches: args.yara_max_matches,
+        max_match_length: args.yara_max_match_length,
+    };
+    let mut rule_sets = Vec::new();
+    if !args.yara.is_empty() {
+        match yara::YaraRules::load(&args.yara, &args.yara_define) {
+            Ok(rules) => {
+                result.skipped = rules.errors.clone();
+                rule_sets.push(rules);
+            }
+            Err(e) => result.error = Some(format!("{:#}", e)),
+        }
+    }
+    if yara::HAS_BUILTIN_RULES && !args.no_builtin_rules {
+        match yara::YaraRules::builtin() {
+            Ok(rules) => rule_sets.push(rules),
+            Err(e) => result.error = Some(format!("{:#}", e)),
+        }
+    }
+    for rules in rule_sets {
+        let rules = rules.with_limits(limits);
+        let scan = match &result.scope {
+            Some(scope) => rules.scan_scope(&binary.data, path, scope),
+            None => rules.scan_file(&binary.data, path),
+        };
+        binary.info.yara_matches.extend(scan.matches);
+        result.timed_out |= scan.timed_out;
+    }
+    Ok(result)
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn write_report(out: &mut dyn Write, format: &str, report: &report::ReportInput) -> Result<()> {
+    match format {
+        "html" => html::write_html(out, report)?,
+        "json" | "yaml" => {
+            let value = serde_json::to_value(report.analysis_report())?;
+            if format == "json" {
+                serde_json::to_writer_pretty(&mut *out, &value)?;
+                writeln!(out)?;
+            } else {
+                out.write_all(yaml::to_string(&value).as_bytes())?;
+            }
+        }
+        "cyclonedx" | "spdx" => {
+            let document = if format == "spdx" {
+                sbom::spdx(report.binary)
+            } else {
+                sbom::cyclonedx(report.binary)
+            };
+            serde_json::to_writer_pretty(&mut *out, &document)?;
+            writeln!(out)?;
+        }
+        _ => report::write_text(out, report)?,
+    }
     Ok(())
 }
+
+fn extract_pyinstaller_scripts(
+    binary: &BinaryFile,
+    archive: &pyinstaller::PyInstallerArchive,
+    dir: &Path,
+) -> Result<usize> {
+    fs::create_dir_all(dir)?;
+    let header = pyinstaller::pyc_header(&binary.data, archive).unwrap_or_default();
+    let mut written = 0;
+    for entry in archive.entries.iter().filter(|e| e.kind == 's') {
+        let code = pyinstaller::read_entry(&binary.data, entry)?;
+        // Entry names come from the file; keep only the final component.
+        let name = entry.name.rsplit(['/', '\\']).next().unwrap_or("script");
+        fs::write(
+            dir.join(format!("{}.pyc", name)),
+            [header.as_slice(), &code].concat(),
+        )?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_write_report_derived() {
+        let binary = BinaryFile::from_raw("blob".to_string(), vec![0x90; 16], "x86_64", 0);
+        let derived = BTreeMap::from([("owner".to_string(), "ops".to_string())]);
+        let render = |format: &str| {
+            report::tests::render(&binary, &[], &derived, |out, input| {
+                write_report(out, format, input)
+            })
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&render("json")).unwrap();
+        assert_eq!(json["derived"], serde_json::json!({"owner": "ops"}));
+        let yaml = render("yaml");
+        assert!(yaml.contains("derived:\n  owner: ops\n"));
+    }
+}
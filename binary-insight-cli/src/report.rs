@@ -0,0 +1,1378 @@
+use crate::Args;
+use binary_insight_core::analysis::cfg::ControlFlowGraph;
+use binary_insight_core::analysis::findings::{Finding, Severity};
+use binary_insight_core::analysis::hashes::FileHashes;
+use binary_insight_core::analysis::listing::SymbolMap;
+use binary_insight_core::analysis::policy::Violation;
+use binary_insight_core::analysis::syscalls;
+use binary_insight_core::analysis::xrefs::Xref;
+use binary_insight_core::analysis::yara::{self, ScanScope};
+use binary_insight_core::analysis::{attack, pe_headers, score};
+use binary_insight_core::binary::{uefi, BinaryFile, EmbeddedBinary, UnpackedBinary};
+use binary_insight_core::report::AnalysisReport;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Results computed by the CLI on top of the analyzed binary.
+pub struct ReportInput<'a> {
+    pub binary: &'a BinaryFile,
+    pub args: &'a Args,
+    pub hashes: &'a FileHashes,
+    pub entropy: f64,
+    /// Findings with the policy's severity overrides applied, most severe first.
+    pub findings: &'a [Finding],
+    /// Config-defined fields, see [`binary_insight_core::analysis::expr::derive_fields`].
+    pub derived: &'a BTreeMap<String, String>,
+    pub yara_error: Option<&'a str>,
+    /// Rule files that failed to compile and were left out of the scan.
+    pub yara_skipped: &'a [(PathBuf, String)],
+    /// The scan hit `--yara-timeout` and the matches may be incomplete.
+    pub yara_timed_out: bool,
+    /// The part of the file `--yara-scope` limited the scan to.
+    pub yara_scope: Option<&'a ScanScope>,
+    /// Why the `--bazaar` lookup failed, if it did.
+    pub bazaar_error: Option<&'a str>,
+    pub cfg: Option<&'a ControlFlowGraph>,
+    /// Target of `--xrefs-to` and the references to it.
+    pub xrefs_to: Option<(u64, &'a [Xref])>,
+    /// Result of `--policy`, if one was given.
+    pub violations: Option<&'a [Violation]>,
+}
+
+impl ReportInput<'_> {
+    /// The serializable report, carrying the same findings and derived
+    /// fields as the text report.
+    pub fn analysis_report(&self) -> AnalysisReport {
+        let mut report = AnalysisReport::new(self.binary);
+        report.findings = self.findings.to_vec();
+        report.derived = self.derived.clone();
+        report
+    }
+}
+
+/// Severity name of the finding `id`, or of the first one of that kind,
+/// falling back to `default` when no such finding was raised.
+fn severity_of(findings: &[Finding], id: &str, default: Severity) -> &'static str {
+    findings
+        .iter()
+        .find(|f| f.id == id || f.kind() == id)
+        .map_or(default, |f| f.severity)
+        .name()
+}
+
+/// Writes the plain-text analysis report.
+pub fn write_text(out: &mut dyn Write, input: &ReportInput) -> io::Result<()> {
+    let ReportInput {
+        binary,
+        args,
+        hashes,
+        entropy,
+        findings,
+        derived,
+        yara_error,
+        yara_skipped,
+        yara_timed_out,
+        yara_scope,
+        bazaar_error,
+        cfg,
+        xrefs_to,
+        violations,
+    } = *input;
+    // Names call and jump targets in the disassembly sections.
+    let symbols = SymbolMap::new(binary);
+    let target = |ins: &_| {
+        symbols
+            .annotate(ins)
+            .map(|name| format!(" <{}>", name))
+            .unwrap_or_default()
+    };
+
+    writeln!(out, "=== Binary Analysis Report ===")?;
+    writeln!(out, "File:         {}", binary.name)?;
+    writeln!(out, "Format:       {}", binary.identify())?;
+    if let Some(file_type) = &binary.info.file_type {
+        writeln!(out, "MIME:         {}", file_type.mime)?;
+    }
+    if let Some(wrappers) = binary.info.wrapper_names() {
+        writeln!(out, "Wrapper:      {} (decompressed)", wrappers)?;
+    }
+    writeln!(out, "Arch:         {}", binary.info.arch)?;
+    if let Some(language) = &binary.info.language {
+        writeln!(out, "Language:     {}", language.summary())?;
+    }
+    writeln!(out, "Entry Point:  0x{:x}", binary.info.entry_point)?;
+    for anomaly in &binary.info.entry_anomalies {
+        writeln!(out, "Warning:      {}", anomaly.description())?;
+    }
+
+    if !binary.info.anomalies.is_empty() {
+        writeln!(out, "\n[Anomalies]")?;
+        for anomaly in &binary.info.anomalies {
+            writeln!(out, "  {}", anomaly.description())?;
+        }
+    }
+
+    if !binary.info.parse_warnings.is_empty() {
+        writeln!(out, "\n[Parse Warnings]")?;
+        for warning in &binary.info.parse_warnings {
+            writeln!(out, "  {}: {}", warning.stage, warning.message)?;
+        }
+    }
+
+    writeln!(out, "\n[Advanced Analysis]")?;
+    writeln!(out, "  Entropy: {:.4} (Scale: 0.0-8.0)", entropy)?;
+    writeln!(out, "  MD5:     {}", hashes.md5)?;
+    writeln!(out, "  SHA1:    {}", hashes.sha1)?;
+    writeln!(out, "  SHA256:  {}", hashes.sha256)?;
+    if let Some(authentihash) = &binary.info.authentihash {
+        writeln!(out, "  Authentihash: {}", authentihash)?;
+    }
+    if let Some(telfhash) = &binary.info.telfhash {
+        writeln!(out, "  telfhash: {}", telfhash)?;
+    }
+    let known_good = binary
+        .info
+        .analysis
+        .as_ref()
+        .and_then(|a| a.known_good.as_ref());
+    if let Some(algorithm) = known_good {
+        writeln!(out, "  Known:   good ({} in hash set)", algorithm)?;
+    }
+
+    if let Some(icon) = &binary.info.icon {
+        writeln!(out, "\n[Icon]")?;
+        writeln!(
+            out,
+            "  Image:  {}x{}, {}-bit {}",
+            icon.width, icon.height, icon.bit_count, icon.format
+        )?;
+        writeln!(out, "  dhash:  {}", icon.dhash.as_deref().unwrap_or("-"))?;
+        writeln!(out, "  phash:  {}", icon.phash.as_deref().unwrap_or("-"))?;
+    }
+
+    if let Some(record) = &binary.info.hash_record {
+        writeln!(out, "\n[Hash Database]")?;
+        let label = record.label.map_or("unlabeled", |l| l.name());
+        if record.note.is_empty() {
+            writeln!(out, "  Label:    {}", label)?;
+        } else {
+            writeln!(out, "  Label:    {} ({})", label, record.note)?;
+        }
+        if record.times_seen > 0 {
+            let date = |secs: u64| pe_headers::format_timestamp(secs.min(u32::MAX as u64) as u32);
+            writeln!(
+                out,
+                "  Seen:     {} time{} before, first {}, last {} as {}",
+                record.times_seen,
+                if record.times_seen == 1 { "" } else { "s" },
+                date(record.first_seen),
+                date(record.last_seen),
+                record.name
+            )?;
+        }
+    }
+
+    if args.bazaar {
+        writeln!(out, "\n[MalwareBazaar]")?;
+        match (&binary.info.bazaar, bazaar_error) {
+            (_, Some(e)) => writeln!(out, "  {}", e)?,
+            (None, None) => writeln!(out, "  Not found.")?,
+            (Some(report), None) => {
+                let fields = [
+                    ("Family", report.family.clone()),
+                    ("Tags", Some(report.tags.join(", "))),
+                    ("Delivery", report.delivery_method.clone()),
+                    ("First seen", report.first_seen.clone()),
+                    ("File name", report.file_name.clone()),
+                    ("Reporter", report.reporter.clone()),
+                ];
+                for (name, value) in fields {
+                    if let Some(value) = value.filter(|v| !v.is_empty()) {
+                        writeln!(out, "  {:<11} {}", format!("{}:", name), value)?;
+                    }
+                }
+            }
+        }
+    }
+
+    if !derived.is_empty() {
+        writeln!(out, "\n[Derived Fields]")?;
+        for (name, value) in derived.iter() {
+            writeln!(out, "  {:<16} {}", name, value)?;
+        }
+    }
+
+    writeln!(out, "\n[Security Features]")?;
+    let security = &binary.info.security;
+    let yes_no = |on: bool, yes: &'static str, no: &'static str| if on { yes } else { no };
+    writeln!(out, "  RELRO:       {}", security.relro.name())?;
+    writeln!(
+        out,
+        "  Canary:      {}",
+        yes_no(security.canary, "Canary found", "No canary found")
+    )?;
+    writeln!(
+        out,
+        "  NX:          {}",
+        yes_no(security.nx, "NX enabled", "NX disabled")
+    )?;
+    writeln!(
+        out,
+        "  PIE:         {}",
+        yes_no(security.pie, "PIE enabled", "No PIE")
+    )?;
+    if security.rwx.is_empty() {
+        writeln!(out, "  RWX:         None")?;
+    }
+    for region in &security.rwx {
+        writeln!(
+            out,
+            "  RWX:         {} @ 0x{:x} (0x{:x} bytes{}) (severity: {})",
+            region.name,
+            region.addr,
+            region.size,
+            if region.deferred {
+                ", executable via max protection"
+            } else {
+                ""
+            },
+            severity_of(findings, "rwx", Severity::High)
+        )?;
+    }
+    let elf = binary.info.format == "ELF";
+    if elf {
+        match security.fortified.len() {
+            0 => writeln!(out, "  FORTIFY:     No")?,
+            n => writeln!(
+                out,
+                "  FORTIFY:     Yes ({} fortified: {})",
+                n,
+                security.fortified.join(", ")
+            )?,
+        }
+    }
+    if elf || security.pe.is_some() {
+        writeln!(
+            out,
+            "  CFI:         {}",
+            if security.cfi.is_empty() {
+                "None".to_string()
+            } else {
+                security.cfi.join(", ")
+            }
+        )?;
+    }
+    if elf {
+        writeln!(
+            out,
+            "  Stack clash: {}",
+            yes_no(security.stack_clash, "Protected", "No probes found")
+        )?;
+    }
+    if let Some(pe) = &security.pe {
+        writeln!(out, "  SEH:         {}", pe.safe_seh.name())?;
+        writeln!(
+            out,
+            "  High-entropy VA: {}",
+            yes_no(pe.high_entropy_va, "Yes", "No")
+        )?;
+        writeln!(
+            out,
+            "  ForceIntegrity:  {}",
+            yes_no(pe.force_integrity, "Yes", "No")
+        )?;
+        writeln!(
+            out,
+            "  AppContainer:    {}",
+            yes_no(pe.app_container, "Yes", "No")
+        )?;
+    }
+    if let Some(macho) = &security.macho {
+        let signature = match (macho.signed, macho.hardened_runtime) {
+            (false, _) => "Unsigned",
+            (true, false) => "Signed",
+            (true, true) => "Signed, hardened runtime",
+        };
+        writeln!(out, "  Code signature:     {}", signature)?;
+        writeln!(
+            out,
+            "  Library validation: {}",
+            yes_no(macho.library_validation, "Yes", "No")
+        )?;
+        writeln!(
+            out,
+            "  __RESTRICT:         {}",
+            yes_no(macho.restrict, "Yes", "No")
+        )?;
+        writeln!(
+            out,
+            "  Encrypted:          {}",
+            yes_no(macho.encrypted, "Yes", "No")
+        )?;
+        for lib in &macho.rpath_dylibs {
+            writeln!(out, "  @rpath dylib:       {}", lib)?;
+        }
+    }
+
+    if let Some(debug) = &binary.info.debug_info {
+        writeln!(out, "\n[Debug Info]")?;
+        writeln!(out, "  Stripped:  {}", yes_no(debug.stripped, "yes", "no"))?;
+        writeln!(out, "  Symbols:   {}", debug.symbols.name())?;
+        if !debug.formats.is_empty() {
+            writeln!(out, "  Formats:   {}", debug.formats.join(", "))?;
+        }
+        if let Some(pdb) = &debug.pdb {
+            writeln!(
+                out,
+                "  PDB:       {} ({} age {})",
+                pdb.path, pdb.guid, pdb.age
+            )?;
+        }
+        if let Some(build_id) = &debug.build_id {
+            writeln!(out, "  Build ID:  {}", build_id)?;
+        }
+        if let Some(debuglink) = &debug.debuglink {
+            writeln!(out, "  Debuglink: {}", debuglink)?;
+        }
+        if let Some(uuid) = &debug.uuid {
+            writeln!(out, "  UUID:      {}", uuid)?;
+        }
+    }
+
+    if let Some(headers) = &binary.info.pe_headers {
+        writeln!(out, "\n[PE Headers]")?;
+        let status = if headers.stored_checksum == 0 {
+            "not set"
+        } else if headers.checksum_mismatch() {
+            "MISMATCH"
+        } else {
+            "ok"
+        };
+        writeln!(
+            out,
+            "  Checksum: 0x{:08x} (computed 0x{:08x}, {})",
+            headers.stored_checksum, headers.computed_checksum, status
+        )?;
+        let stamps = &headers.timestamps;
+        if stamps.reproducible {
+            writeln!(
+                out,
+                "  Compiled: 0x{:08x} (reproducible build hash)",
+                stamps.coff
+            )?;
+        } else {
+            writeln!(
+                out,
+                "  Compiled: {}",
+                pe_headers::format_timestamp(stamps.coff)
+            )?;
+        }
+        if let Some(export) = stamps.export {
+            writeln!(out, "  Exports:  {}", pe_headers::format_timestamp(export))?;
+        }
+        for &debug in &stamps.debug {
+            writeln!(out, "  Debug:    {}", pe_headers::format_timestamp(debug))?;
+        }
+        for issue in headers.issues.iter().chain(&stamps.issues) {
+            writeln!(out, "  Anomaly:  {}", issue)?;
+        }
+    }
+
+    if let Some(linker) = &binary.info.linker {
+        writeln!(out, "\n[Linker Structures]")?;
+        writeln!(
+            out,
+            "  GOT:    {} bytes ({} entries{})",
+            linker.got_size,
+            linker.got_entries,
+            if linker.got_executable {
+                ", executable"
+            } else {
+                ""
+            }
+        )?;
+        writeln!(
+            out,
+            "  PLT:    {} bytes ({} stubs)",
+            linker.plt_size, linker.plt_stubs
+        )?;
+        if linker.tls_total_size > 0 {
+            writeln!(
+                out,
+                "  TLS:    {} bytes initialized, {} bytes total (align {})",
+                linker.tls_init_size, linker.tls_total_size, linker.tls_align
+            )?;
+        } else {
+            writeln!(out, "  TLS:    none")?;
+        }
+        for warning in &linker.warnings {
+            writeln!(out, "  Warning: {}", warning)?;
+        }
+    }
+
+    if let Some(kernel) = &binary.info.kernel {
+        writeln!(out, "\n[Linux Kernel]")?;
+        writeln!(out, "  Image:    {}", kernel.image.name())?;
+        writeln!(
+            out,
+            "  Version:  {}",
+            kernel.version.as_deref().unwrap_or("unknown")
+        )?;
+        match kernel.kallsyms_count {
+            Some(count) => writeln!(out, "  Kallsyms: {} symbols", count)?,
+            None => writeln!(out, "  Kallsyms: not found")?,
+        }
+        if kernel.config.is_empty() {
+            writeln!(out, "  Config:   not embedded")?;
+        } else {
+            writeln!(
+                out,
+                "  Config:   {} options (IKCONFIG)",
+                kernel.config.len()
+            )?;
+            for line in kernel.config.iter().take(20) {
+                writeln!(out, "    {}", line)?;
+            }
+            if kernel.config.len() > 20 {
+                writeln!(out, "    ... and {} more", kernel.config.len() - 20)?;
+            }
+        }
+    }
+
+    if let Some(module) = &binary.info.kernel_module {
+        writeln!(out, "\n[Kernel Module]")?;
+        writeln!(
+            out,
+            "  Name:     {}",
+            module.name.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            out,
+            "  License:  {}",
+            module.license.as_deref().unwrap_or("none")
+        )?;
+        writeln!(
+            out,
+            "  Vermagic: {}",
+            module.vermagic.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(out, "  Signed:   {}", module.signed)?;
+        writeln!(out, "  In-tree:  {}", module.in_tree)?;
+        if !module.depends.is_empty() {
+            writeln!(out, "  Depends:  {}", module.depends.join(", "))?;
+        }
+        for param in &module.parameters {
+            writeln!(
+                out,
+                "  Param:    {} ({}) {}",
+                param.name,
+                param.kind.as_deref().unwrap_or("?"),
+                param.description.as_deref().unwrap_or("")
+            )?;
+        }
+        writeln!(out, "  Exports:  {}", module.exports.len())?;
+        for export in module.exports.iter().take(20) {
+            writeln!(out, "    {}", export)?;
+        }
+        for warning in &module.warnings {
+            writeln!(out, "  Warning:  {}", warning)?;
+        }
+    }
+
+    if let Some(go) = &binary.info.go {
+        writeln!(out, "\n[Go]")?;
+        writeln!(
+            out,
+            "  Version:  {}",
+            go.version.as_deref().unwrap_or("unknown")
+        )?;
+        if let Some(path) = &go.main_path {
+            writeln!(out, "  Path:     {}", path)?;
+        }
+        if let Some(module) = &go.main_module {
+            writeln!(out, "  Module:   {} {}", module.path, module.version)?;
+        }
+        for (key, value) in &go.settings {
+            writeln!(out, "  Build:    {}={}", key, value)?;
+        }
+        writeln!(out, "  Deps:     {}", go.deps.len())?;
+        for dep in go.deps.iter().take(20) {
+            match &dep.replace {
+                Some(replace) => writeln!(out, "    {} {} => {}", dep.path, dep.version, replace)?,
+                None => writeln!(out, "    {} {}", dep.path, dep.version)?,
+            }
+        }
+        if let Some(pclntab) = &go.pclntab_version {
+            writeln!(
+                out,
+                "  Pclntab:  Go {} format, {} functions recovered",
+                pclntab,
+                go.functions.len()
+            )?;
+        }
+    }
+
+    if !args.sigs.is_empty() {
+        writeln!(out, "\n[Library Signatures]")?;
+        writeln!(out, "  Matches: {}", binary.info.signature_matches.len())?;
+        for m in &binary.info.signature_matches {
+            writeln!(out, "  0x{:<12x} {:<32} ({})", m.addr, m.name, m.library)?;
+        }
+    }
+
+    if !args.charsets.is_empty() {
+        writeln!(out, "\n[Non-Latin Strings]")?;
+        writeln!(out, "  Found: {}", binary.info.charset_strings.len())?;
+        for s in binary.info.charset_strings.iter().take(50) {
+            writeln!(
+                out,
+                "  0x{:<8x} {:<7} {:<10} {}",
+                s.offset,
+                s.charset.name(),
+                s.language,
+                s.value
+            )?;
+        }
+    }
+
+    if !args.deobfuscate.is_empty() {
+        writeln!(out, "\n[Deobfuscated Strings]")?;
+        writeln!(out, "  Recovered: {}", binary.info.decoded_strings.len())?;
+        for s in binary.info.decoded_strings.iter().take(50) {
+            writeln!(out, "  0x{:<8x} {:<16} {}", s.offset, s.method, s.value)?;
+        }
+    }
+
+    if !binary.info.crypto_constants.is_empty() {
+        writeln!(out, "\n[Crypto Constants]")?;
+        for constant in &binary.info.crypto_constants {
+            let offsets: Vec<String> = constant
+                .offsets
+                .iter()
+                .take(4)
+                .map(|o| format!("0x{:x}", o))
+                .collect();
+            writeln!(
+                out,
+                "  {:<17} {:<24} @ {}{}",
+                constant.algorithm,
+                constant.description,
+                offsets.join(", "),
+                if constant.offsets.len() > 4 {
+                    ", ..."
+                } else {
+                    ""
+                }
+            )?;
+        }
+    }
+
+    if !binary.info.weak_crypto.is_empty() {
+        writeln!(
+            out,
+            "\n[Weak Crypto] (severity: {})",
+            severity_of(findings, "weak_crypto", Severity::Info)
+        )?;
+        for usage in &binary.info.weak_crypto {
+            match usage.offset {
+                Some(offset) => writeln!(
+                    out,
+                    "  {:<6} {:<9} {} @ 0x{:x}",
+                    usage.algorithm,
+                    usage.evidence.name(),
+                    usage.detail,
+                    offset
+                )?,
+                None => writeln!(
+                    out,
+                    "  {:<6} {:<9} {}",
+                    usage.algorithm,
+                    usage.evidence.name(),
+                    usage.detail
+                )?,
+            }
+        }
+    }
+
+    if !binary.info.insecure_functions.is_empty() {
+        writeln!(out, "\n[Insecure Functions]")?;
+        for usage in &binary.info.insecure_functions {
+            writeln!(
+                out,
+                "  {:<9} {:<7} {:<7} {}",
+                usage.function,
+                severity_of(
+                    findings,
+                    &format!("insecure_libc.{}", usage.function),
+                    usage.severity
+                ),
+                if usage.imported { "import" } else { "symbol" },
+                usage.reason
+            )?;
+        }
+    }
+
+    let evasion = [
+        ("Anti-debugging", "anti_debug", &binary.info.anti_debug),
+        ("Anti-VM/sandbox", "anti_vm", &binary.info.anti_vm),
+    ];
+    if evasion.iter().any(|(_, _, found)| !found.is_empty()) {
+        writeln!(out, "\n[Evasion]")?;
+        for (label, category, found) in evasion.iter().filter(|(_, _, f)| !f.is_empty()) {
+            writeln!(
+                out,
+                "  {} (severity: {}):",
+                label,
+                severity_of(findings, category, Severity::Medium)
+            )?;
+            for indicator in found.iter() {
+                let location = indicator
+                    .addr
+                    .and_then(|addr| Some((addr, binary.info.section_for_addr(addr)?)))
+                    .and_then(|(addr, section)| symbols.describe(addr, section))
+                    .map(|name| format!(" <{}>", name))
+                    .unwrap_or_default();
+                match indicator.addr {
+                    Some(addr) => writeln!(
+                        out,
+                        "    0x{:<10x} {:<24} {}{}",
+                        addr, indicator.technique, indicator.description, location
+                    )?,
+                    None => writeln!(
+                        out,
+                        "    {:<12} {:<24} {}",
+                        "-", indicator.technique, indicator.description
+                    )?,
+                }
+            }
+        }
+    }
+
+    if !binary.info.capabilities.is_empty() {
+        writeln!(out, "\n[Capabilities]")?;
+        for capability in &binary.info.capabilities {
+            let techniques = if capability.attack.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", capability.attack.join(", "))
+            };
+            writeln!(
+                out,
+                "  {:<40} {}{}",
+                capability.name,
+                capability.namespace.as_deref().unwrap_or("-"),
+                techniques
+            )?;
+            for evidence in &capability.evidence {
+                writeln!(out, "    {}", evidence)?;
+            }
+        }
+    }
+
+    if !findings.is_empty() {
+        writeln!(out, "\n[Findings]")?;
+        for finding in findings {
+            let mut addresses: Vec<String> = finding
+                .addresses
+                .iter()
+                .take(4)
+                .map(|addr| format!("0x{:x}", addr))
+                .collect();
+            if finding.addresses.len() > 4 {
+                addresses.push("...".to_string());
+            }
+            writeln!(
+                out,
+                "  [{:<6}] {:<10} {:<40} {}{}",
+                finding.severity.name(),
+                finding.category.name(),
+                finding.title,
+                finding.evidence,
+                if addresses.is_empty() {
+                    String::new()
+                } else {
+                    format!(" @ {}", addresses.join(", "))
+                }
+            )?;
+        }
+    }
+    let techniques = attack::summary(findings);
+    if !techniques.is_empty() {
+        writeln!(out, "\n[ATT&CK Techniques]")?;
+        for (id, sources) in &techniques {
+            writeln!(
+                out,
+                "  {:<10} {:<36} {}",
+                id,
+                attack::technique_name(id).unwrap_or("-"),
+                sources.join(", ")
+            )?;
+        }
+    }
+
+    if args.vuln_db.is_some() {
+        writeln!(out, "\n[Vulnerabilities]")?;
+        let vulns = &binary.info.vulnerabilities;
+        if vulns.is_empty() {
+            writeln!(
+                out,
+                "  No known vulnerabilities in the detected component versions."
+            )?;
+        }
+        for vuln in vulns {
+            writeln!(
+                out,
+                "  [{:<6}] {:<18} {} {}{}",
+                severity_of(findings, &format!("vuln.{}", vuln.id), vuln.severity),
+                vuln.id,
+                vuln.component,
+                vuln.version,
+                vuln.fixed
+                    .as_ref()
+                    .map(|fixed| format!(" (fixed in {})", fixed))
+                    .unwrap_or_default()
+            )?;
+            if !vuln.summary.is_empty() {
+                writeln!(out, "           {}", vuln.summary)?;
+            }
+        }
+    }
+
+    let threat = score::threat_score(&binary.info);
+    writeln!(out, "\n[Threat Score]")?;
+    writeln!(out, "  Score: {}/100", threat.score)?;
+    for factor in &threat.factors {
+        writeln!(
+            out,
+            "  +{:<3} {:<28} {}",
+            factor.weight, factor.description, factor.evidence
+        )?;
+    }
+
+    if let Some(violations) = violations {
+        writeln!(out, "\n[Policy]")?;
+        if violations.is_empty() {
+            writeln!(out, "  PASS")?;
+        }
+        for violation in violations {
+            writeln!(out, "  FAIL {:<28} {}", violation.rule, violation.message)?;
+        }
+    }
+
+    let calls = &binary.info.syscalls;
+    if !calls.is_empty() {
+        writeln!(out, "\n[Syscalls]")?;
+        writeln!(out, "  Call sites: {}", calls.len())?;
+        for (label, count) in syscalls::summary(calls) {
+            writeln!(out, "  {:<20} x{}", label, count)?;
+        }
+    }
+
+    if let Some(rust) = &binary.info.rust {
+        writeln!(out, "\n[Rust]")?;
+        writeln!(
+            out,
+            "  Rustc:    {}",
+            rust.rustc_version.as_deref().unwrap_or("unknown")
+        )?;
+        if let Some(commit) = &rust.rustc_commit {
+            writeln!(out, "  Commit:   {}", commit)?;
+        }
+        writeln!(
+            out,
+            "  Symbols:  {} legacy, {} v0 mangled",
+            rust.legacy_symbols, rust.v0_symbols
+        )?;
+        writeln!(out, "  Panics:   {} panic strings", rust.panic_strings)?;
+        writeln!(out, "  Crates:   {}", rust.crates.len())?;
+        for krate in rust.crates.iter().take(20) {
+            writeln!(
+                out,
+                "    {} {}",
+                krate.name,
+                krate.version.as_deref().unwrap_or("")
+            )?;
+        }
+    }
+
+    if let Some(dotnet) = &binary.info.dotnet {
+        writeln!(out, "\n[.NET]")?;
+        writeln!(out, "  Runtime:  {}", dotnet.runtime_version)?;
+        if let Some(assembly) = &dotnet.assembly {
+            writeln!(out, "  Assembly: {} {}", assembly.name, assembly.version)?;
+        }
+        writeln!(out, "  References: {}", dotnet.references.len())?;
+        for reference in dotnet.references.iter().take(20) {
+            writeln!(out, "    {} {}", reference.name, reference.version)?;
+        }
+    }
+
+    if let Some(image) = &binary.info.container {
+        writeln!(out, "\n[Container Image]")?;
+        writeln!(out, "  Layout:   {}", image.layout.name())?;
+        if !image.tags.is_empty() {
+            writeln!(out, "  Tags:     {}", image.tags.join(", "))?;
+        }
+        for (i, layer) in image.layers.iter().enumerate() {
+            writeln!(
+                out,
+                "  Layer {}: {} ({} files, {} binaries)",
+                i + 1,
+                layer.digest,
+                layer.files,
+                layer.binaries.len()
+            )?;
+            for bin in &layer.binaries {
+                print_embedded_binary(out, bin)?;
+            }
+            for path in &layer.removed {
+                writeln!(out, "    removed {}", path)?;
+            }
+        }
+    }
+
+    if let Some(archive) = &binary.info.pyinstaller {
+        writeln!(out, "\n[PyInstaller]")?;
+        writeln!(out, "  Offset:   0x{:x}", archive.offset)?;
+        writeln!(
+            out,
+            "  Python:   {} {}",
+            archive.python_version,
+            archive.python_library.as_deref().unwrap_or("")
+        )?;
+        writeln!(out, "  Entries:  {}", archive.entries.len())?;
+        for entry in &archive.entries {
+            writeln!(
+                out,
+                "    {:<40} {:<14} {:>10} bytes",
+                entry.name,
+                entry.kind_name(),
+                entry.size
+            )?;
+        }
+    }
+
+    if let Some(installer) = &binary.info.installer {
+        writeln!(out, "\n[Installer]")?;
+        writeln!(out, "  Framework: {}", installer.kind.name())?;
+        if let Some(version) = &installer.version {
+            writeln!(out, "  Version:   {}", version)?;
+        }
+        if let Some(builder) = &installer.builder {
+            writeln!(out, "  Built with: {}", builder)?;
+        }
+        if let Some(offset) = installer.offset {
+            writeln!(out, "  Data:      0x{:x}", offset)?;
+        }
+        if !installer.files.is_empty() {
+            writeln!(out, "  Files:     {}", installer.files.len())?;
+            for file in &installer.files {
+                writeln!(out, "    {:<50} {:>10} bytes", file.name, file.size)?;
+            }
+        }
+    }
+
+    if let Some(runtime) = &binary.info.app_runtime {
+        writeln!(out, "\n[App Runtime]")?;
+        writeln!(out, "  Runtime:  {}", runtime.kind.name())?;
+        if let Some(version) = &runtime.version {
+            writeln!(out, "  Version:  {}", version)?;
+        }
+        if let Some(node) = &runtime.node_version {
+            writeln!(out, "  Node.js:  {}", node)?;
+        }
+        if let Some(asar) = &runtime.asar {
+            writeln!(out, "  ASAR:     {}", asar)?;
+        }
+        if !runtime.entries.is_empty() {
+            writeln!(out, "  Entries:  {}", runtime.entries.len())?;
+            for entry in &runtime.entries {
+                writeln!(out, "    {:<50} {:>10} bytes", entry.path, entry.size)?;
+            }
+        }
+    }
+
+    if let Some(package) = &binary.info.package {
+        writeln!(out, "\n[Package]")?;
+        writeln!(out, "  Type:       {}", package.kind.name())?;
+        writeln!(
+            out,
+            "  Name:       {}",
+            package.name.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            out,
+            "  Version:    {}",
+            package.version.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            out,
+            "  Arch:       {}",
+            package.architecture.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            out,
+            "  Maintainer: {}",
+            package.maintainer.as_deref().unwrap_or("unknown")
+        )?;
+        if let Some(summary) = &package.summary {
+            writeln!(out, "  Summary:    {}", summary)?;
+        }
+        writeln!(
+            out,
+            "  Members:    {} files, {} binaries",
+            package.members.len(),
+            package.binaries.len()
+        )?;
+        for bin in &package.binaries {
+            print_embedded_binary(out, bin)?;
+        }
+    }
+
+    if let Some(archive) = &binary.info.archive {
+        writeln!(out, "\n[Archive]")?;
+        writeln!(out, "  Type:     {}", archive.kind.name())?;
+        writeln!(
+            out,
+            "  Members:  {} files, {} binaries",
+            archive.members.len(),
+            archive.binaries.len()
+        )?;
+        for bin in &archive.binaries {
+            print_embedded_binary(out, bin)?;
+        }
+    }
+
+    if !binary.info.firmware.is_empty() {
+        writeln!(out, "\n[UEFI Firmware]")?;
+        for volume in &binary.info.firmware {
+            print_firmware_volume(out, volume, 1)?;
+        }
+    }
+
+    if !binary.info.certificates.is_empty() {
+        writeln!(out, "\n[Certificates]")?;
+        for cert in &binary.info.certificates {
+            writeln!(
+                out,
+                "  0x{:08x} ({}) {}",
+                cert.offset,
+                cert.encoding.name(),
+                cert.subject
+            )?;
+            if cert.is_self_signed() {
+                writeln!(out, "    Issuer:  (self-signed)")?;
+            } else {
+                writeln!(out, "    Issuer:  {}", cert.issuer)?;
+            }
+            writeln!(out, "    Valid:   {} - {}", cert.not_before, cert.not_after)?;
+            writeln!(out, "    Serial:  {}", cert.serial)?;
+        }
+    }
+
+    if !binary.info.private_keys.is_empty() {
+        writeln!(out, "\n[Private Keys]")?;
+        for key in &binary.info.private_keys {
+            writeln!(
+                out,
+                "  0x{:08x} ({}) {}",
+                key.offset,
+                key.encoding.name(),
+                key.kind
+            )?;
+        }
+    }
+
+    if !binary.info.embedded_files.is_empty() {
+        writeln!(out, "\n[Embedded Files]")?;
+        for file in &binary.info.embedded_files {
+            writeln!(
+                out,
+                "  0x{:08x} {}{:<10} {}",
+                file.offset,
+                if file.exact { " " } else { "~" },
+                file.size,
+                file.kind.name()
+            )?;
+        }
+    }
+
+    if !binary.info.unpacked.is_empty() {
+        writeln!(out, "\n[Embedded Executables]")?;
+        for unpacked in &binary.info.unpacked {
+            print_unpacked(out, unpacked, 1)?;
+        }
+    }
+
+    if args.runs_yara() {
+        writeln!(out, "\n[YARA Scan]")?;
+        if let Some(scope) = yara_scope {
+            writeln!(
+                out,
+                "  Scope: {} (file offsets 0x{:x}-0x{:x})",
+                scope.name, scope.range.start, scope.range.end
+            )?;
+        }
+        // The compiler's message starts with the file and line.
+        for (_, message) in yara_skipped.iter() {
+            writeln!(out, "  Skipped {}", message)?;
+        }
+        if yara_timed_out {
+            writeln!(
+                out,
+                "  Timed out after {}s; matches may be incomplete.",
+                args.yara_timeout
+            )?;
+        }
+        if let Some(e) = yara_error {
+            writeln!(out, "  {}", e)?;
+        }
+        if binary.info.yara_matches.is_empty() {
+            if yara_error.is_none() {
+                writeln!(out, "  No matches found.")?;
+            }
+        } else {
+            for m in &binary.info.yara_matches {
+                if m.tags.is_empty() {
+                    writeln!(out, "  Match: {}", m.rule)?;
+                } else {
+                    writeln!(out, "  Match: {} [{}]", m.rule, m.tags.join(", "))?;
+                }
+                for key in ["description", "author", "reference", "score"] {
+                    if let Some(value) = m.meta(key) {
+                        writeln!(out, "    {:<12} {}", format!("{}:", key), value)?;
+                    }
+                }
+            }
+            for hit in yara::locate_hits(&binary.info.yara_matches, &binary.info) {
+                let location = match (&hit.addr, &hit.function) {
+                    (Some(addr), Some((name, delta))) => {
+                        format!("0x{:x} in {}+0x{:x}", addr, name, delta)
+                    }
+                    (Some(addr), None) => format!("0x{:x}", addr),
+                    _ => "not mapped".to_string(),
+                };
+                writeln!(
+                    out,
+                    "    {:<24} {:<8} offset {:<10} {:<24} {}",
+                    hit.rule,
+                    hit.identifier,
+                    scoped_offset(yara_scope, hit.offset),
+                    location,
+                    yara::preview(&hit.data)
+                )?;
+                if let (true, Some(addr)) = (args.context_disasm, hit.addr) {
+                    match binary.disassemble_around(addr, 5, 5) {
+                        Ok(instructions) => {
+                            for ins in instructions {
+                                let marker = if ins.address == addr { ">" } else { " " };
+                                writeln!(
+                                    out,
+                                    "      {} 0x{:x}:  {:<10} {}{}",
+                                    marker,
+                                    ins.address,
+                                    ins.mnemonic,
+                                    ins.op_str,
+                                    target(&ins)
+                                )?;
+                            }
+                        }
+                        Err(e) => writeln!(out, "      (no disassembly: {})", e)?,
+                    }
+                }
+            }
+        }
+    }
+
+    writeln!(out, "\n[Disassembly (Entry Point / .text)]")?;
+    if !binary.info.disassembly.is_empty() {
+        // Show only first 20 for CLI nicely or all if piped? Let's show first 20-50.
+        for ins in binary.info.disassembly.iter().take(50) {
+            writeln!(
+                out,
+                "  0x{:x}:  {:<10} {}{}",
+                ins.address,
+                ins.mnemonic,
+                ins.op_str,
+                target(ins)
+            )?;
+        }
+        if binary.info.disassembly.len() > 50 {
+            writeln!(
+                out,
+                "  ... ({} more instructions)",
+                binary.info.disassembly.len() - 50
+            )?;
+        }
+    } else {
+        writeln!(out, "  No disassembly available (checked .text section).")?;
+    }
+
+    let functions = &binary.info.functions;
+    writeln!(out, "\n[Functions]")?;
+    writeln!(out, "  Discovered: {}", functions.len())?;
+    let mut by_source = std::collections::BTreeMap::new();
+    for f in functions {
+        *by_source.entry(f.source).or_insert(0) += 1;
+    }
+    for (source, count) in by_source {
+        writeln!(out, "    {:<10} {}", source.name(), count)?;
+    }
+    for f in functions.iter().take(20) {
+        writeln!(
+            out,
+            "  0x{:<12x} {:>8} bytes  {:<9} {}",
+            f.start,
+            f.size,
+            f.source.name(),
+            f.name
+        )?;
+    }
+    if functions.len() > 20 {
+        writeln!(out, "  ... and {} more", functions.len() - 20)?;
+    }
+
+    if let Some(cfg) = cfg {
+        writeln!(out, "\n[Control Flow Graph]")?;
+        writeln!(out, "  Entry:  0x{:x}", cfg.entry)?;
+        writeln!(
+            out,
+            "  Blocks: {}  Edges: {}",
+            cfg.blocks.len(),
+            cfg.edges.len()
+        )?;
+        for block in &cfg.blocks {
+            let successors: Vec<String> = cfg
+                .successors(block.start)
+                .map(|e| format!("0x{:x}", e.to))
+                .collect();
+            writeln!(
+                out,
+                "  0x{:<12x} {:>3} insns  {:<12} -> {}",
+                block.start,
+                block.instructions.len(),
+                block.kind.name(),
+                if successors.is_empty() {
+                    "-".to_string()
+                } else {
+                    successors.join(", ")
+                }
+            )?;
+        }
+        for target in &cfg.external_targets {
+            writeln!(out, "  external jump target: 0x{:x}", target)?;
+        }
+    }
+
+    if let Some((target, refs)) = xrefs_to {
+        writeln!(out, "\n[Xrefs to 0x{:x}]", target)?;
+        writeln!(out, "  References: {}", refs.len())?;
+        for xref in refs {
+            let function = binary
+                .info
+                .function_at(xref.function)
+                .map(|f| f.name.as_str())
+                .unwrap_or("?");
+            writeln!(
+                out,
+                "  0x{:<12x} {:<7} {}+0x{:x}",
+                xref.from,
+                xref.kind.name(),
+                function,
+                xref.from - xref.function
+            )?;
+        }
+    }
+
+    let string_xrefs = &binary.info.string_xrefs;
+    if !string_xrefs.is_empty() {
+        writeln!(out, "\n[String References]")?;
+        writeln!(out, "  References: {}", string_xrefs.len())?;
+        for xref in string_xrefs.iter().take(30) {
+            let site = match xref.function.and_then(|f| binary.info.function_at(f)) {
+                Some(f) => f.name.as_str(),
+                None => "data",
+            };
+            writeln!(
+                out,
+                "  '{}' referenced from {} (0x{:x}, {})",
+                binary.info.strings[xref.string],
+                site,
+                xref.from,
+                xref.kind.name()
+            )?;
+        }
+        if string_xrefs.len() > 30 {
+            writeln!(out, "  ... and {} more", string_xrefs.len() - 30)?;
+        }
+    }
+
+    writeln!(out, "\n[Sections]")?;
+    writeln!(out, "{:<20} {:<18} {:<18} MD5", "Name", "Address", "Size")?;
+    for section in &binary.info.sections {
+        writeln!(
+            out,
+            "{:<20} 0x{:<16x} 0x{:<16x} {}",
+            section.name,
+            section.addr,
+            section.size,
+            section.hashes.as_ref().map_or("-", |h| h.md5.as_str())
+        )?;
+    }
+
+    writeln!(out, "\n[Symbols]")?;
+    writeln!(out, "Total symbols: {}", binary.info.symbols.len())?;
+    // Show first 20 symbols
+    for symbol in binary.info.symbols.iter().take(20) {
+        writeln!(out, "{:<40} 0x{:<16x}", symbol.name, symbol.addr)?;
+    }
+    if binary.info.symbols.len() > 20 {
+        writeln!(out, "... and {} more", binary.info.symbols.len() - 20)?;
+    }
+
+    writeln!(out, "\n[Strings]")?;
+    writeln!(out, "Total strings found: {}", binary.info.strings.len())?;
+    // Show first 20 strings
+    for s in binary.info.strings.iter().take(20) {
+        writeln!(out, "{}", s)?;
+    }
+    if binary.info.strings.len() > 20 {
+        writeln!(out, "... and {} more", binary.info.strings.len() - 20)?;
+    }
+    Ok(())
+}
+
+fn print_embedded_binary(out: &mut dyn Write, bin: &EmbeddedBinary) -> io::Result<()> {
+    writeln!(
+        out,
+        "    {:<40} {:<6} {:<8} PIE:{} NX:{} RELRO:{} Canary:{}",
+        bin.path,
+        bin.format,
+        bin.arch,
+        bin.security.pie,
+        bin.security.nx,
+        bin.security.relro.name(),
+        bin.security.canary
+    )
+}
+
+/// Summary of an unpacked executable, followed by the ones inside it.
+fn print_unpacked(out: &mut dyn Write, unpacked: &UnpackedBinary, depth: usize) -> io::Result<()> {
+    let indent = "  ".repeat(depth);
+    let report = AnalysisReport::new(&unpacked.file);
+    writeln!(out, "{}0x{:08x} {}", indent, unpacked.offset, report.file)?;
+    let format = match unpacked.file.info.arch.as_str() {
+        "" => report.format.clone(),
+        arch => format!("{} ({})", report.format, arch),
+    };
+    writeln!(
+        out,
+        "{}  Format: {}  Size: {}  Score: {}/100",
+        indent,
+        format,
+        unpacked.file.data.len(),
+        report.threat_score.score
+    )?;
+    writeln!(out, "{}  SHA-256: {}", indent, report.hashes.sha256)?;
+    for finding in &report.findings {
+        writeln!(
+            out,
+            "{}  [{:<6}] {}",
+            indent,
+            finding.severity.name(),
+            finding.title
+        )?;
+    }
+    for child in &unpacked.file.info.unpacked {
+        print_unpacked(out, child, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn print_firmware_volume(
+    out: &mut dyn Write,
+    volume: &uefi::FirmwareVolume,
+    depth: usize,
+) -> io::Result<()> {
+    let indent = "  ".repeat(depth);
+    writeln!(
+        out,
+        "{}Volume @ 0x{:x} ({} bytes, fs {}) - {} files",
+        indent,
+        volume.offset,
+        volume.length,
+        volume.filesystem_guid,
+        volume.files.len()
+    )?;
+    for file in &volume.files {
+        writeln!(
+            out,
+            "{}  {} {:<22} {:<24} 0x{:x}",
+            indent,
+            file.guid,
+            file.type_name(),
+            file.name.as_deref().unwrap_or("-"),
+            file.offset
+        )?;
+        for section in file.sections.iter().filter(|s| s.is_image()) {
+            writeln!(
+                out,
+                "{}    {} image @ 0x{:x} ({} bytes)",
+                indent,
+                section.type_name(),
+                section.offset,
+                section.size
+            )?;
+        }
+        for nested in &file.volumes {
+            print_firmware_volume(out, nested, depth + 2)?;
+        }
+    }
+    Ok(())
+}
+
+/// A match offset, relative to the `--yara-scope` when there is one.
+pub(crate) fn scoped_offset(scope: Option<&ScanScope>, offset: u64) -> String {
+    match scope {
+        Some(scope) => format!("scope+0x{:x}", offset - scope.range.start),
+        None => format!("0x{:x}", offset),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// Renders `binary` with `write`, leaving out YARA, the policy and the
+    /// other optional inputs.
+    pub(crate) fn render(
+        binary: &BinaryFile,
+        findings: &[Finding],
+        derived: &BTreeMap<String, String>,
+        write: impl FnOnce(&mut Vec<u8>, &ReportInput) -> anyhow::Result<()>,
+    ) -> String {
+        let args = Args::parse_from(["binary-insight", &binary.name]);
+        let hashes = binary_insight_core::analysis::hashes::calculate_hashes(&binary.data);
+        let input = ReportInput {
+            binary,
+            args: &args,
+            hashes: &hashes,
+            entropy: 0.0,
+            findings,
+            derived,
+            yara_error: None,
+            yara_skipped: &[],
+            yara_timed_out: false,
+            yara_scope: None,
+            bazaar_error: None,
+            cfg: None,
+            xrefs_to: None,
+            violations: None,
+        };
+        let mut out = Vec::new();
+        write(&mut out, &input).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+}
@@ -1,3 +1,4 @@
+use binary_insight_core::utils::{ascii_column, hex_column};
 use ratatui::{
     layout::Rect,
     style::{Color, Style},
@@ -53,6 +54,11 @@ impl HexViewer {
         }
     }
 
+    /// Scrolls so the row containing `offset` is at the top.
+    pub fn jump_to(&mut self, offset: usize) {
+        self.scroll_offset = offset - offset % self.bytes_per_row;
+    }
+
     pub fn scroll_page_up(&mut self, height: usize) {
         let rows = if height > 2 { height - 2 } else { 1 };
         let jump = rows * self.bytes_per_row;
@@ -63,7 +69,8 @@ impl HexViewer {
         }
     }
 
-    pub fn draw(&self, f: &mut Frame, area: Rect, data: &[u8]) {
+    /// `label` describes what is at the top row, such as the string there.
+    pub fn draw(&self, f: &mut Frame, area: Rect, data: &[u8], label: Option<&str>) {
         if area.height < 3 {
             return;
         }
@@ -87,38 +94,17 @@ impl HexViewer {
             for (i, chunk) in data[start..end].chunks(self.bytes_per_row).enumerate() {
                 let offset = start + i * self.bytes_per_row;
 
-                // Hex part
-                let mut hex_spans = Vec::new();
-                for b in chunk {
-                    hex_spans.push(format!("{:02x} ", b));
-                }
-
-                // Pad if incomplete row
-                let padding_needed = self.bytes_per_row - chunk.len();
-                let hex_string = hex_spans.join("");
-                let padding = "   ".repeat(padding_needed); // 3 chars per byte "XX "
-
-                // Ascii part
-                let ascii_string: String = chunk
-                    .iter()
-                    .map(|&b| {
-                        if (32..=126).contains(&b) {
-                            b as char
-                        } else {
-                            '.'
-                        }
-                    })
-                    .collect();
-
                 let line = Line::from(vec![
                     Span::styled(
                         format!("{:08x}:  ", offset),
                         Style::default().fg(Color::DarkGray),
                     ),
-                    Span::styled(hex_string, Style::default().fg(Color::White)),
-                    Span::raw(padding),
+                    Span::styled(
+                        hex_column(chunk, self.bytes_per_row),
+                        Style::default().fg(Color::White),
+                    ),
                     Span::raw(" |"),
-                    Span::styled(ascii_string, Style::default().fg(Color::Yellow)),
+                    Span::styled(ascii_column(chunk), Style::default().fg(Color::Yellow)),
                     Span::raw("|"),
                 ]);
                 lines.push(line);
@@ -127,7 +113,10 @@ impl HexViewer {
 
         let block = Block::default()
             .borders(Borders::ALL)
-            .title(format!("Hex View (Offset: 0x{:x})", start))
+            .title(match label {
+                Some(label) => format!("Hex View (Offset: 0x{:x}) {}", start, label),
+                None => format!("Hex View (Offset: 0x{:x})", start),
+            })
             .border_style(Style::default().fg(Color::Cyan));
 
         let paragraph = Paragraph::new(lines).block(block);
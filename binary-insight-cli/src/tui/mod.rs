@@ -1,5 +1,12 @@
 use anyhow::Result;
-use binary_insight_core::binary::BinaryFile;
+use binary_insight_core::analysis::anomalies;
+use binary_insight_core::analysis::disassembly::InstructionInfo;
+use binary_insight_core::analysis::findings::{self, Finding, Severity};
+use binary_insight_core::analysis::listing::SymbolMap;
+use binary_insight_core::analysis::string_xrefs;
+use binary_insight_core::analysis::xrefs::{Xref, XrefIndex};
+use binary_insight_core::analysis::yara::{self, YaraHit};
+use binary_insight_core::binary::{AnalysisOptions, BinaryFile};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -14,17 +21,22 @@ use ratatui::{
     Frame, Terminal,
 };
 use std::io;
+use std::path::Path;
+
+use crate::Args;
 
 pub mod hex_view;
 
-pub fn run(binary: BinaryFile) -> Result<()> {
+/// Shows `binary` in the TUI. `xrefs` is given when every pass has already
+/// run; otherwise each tab runs the ones it shows on first view.
+pub(crate) fn run(binary: BinaryFile, args: &Args, xrefs: Option<XrefIndex>) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = run_app(&mut terminal, &binary);
+    let res = run_app(&mut terminal, App::new(binary, args, xrefs));
 
     disable_raw_mode()?;
     execute!(
@@ -42,22 +54,87 @@ pub fn run(binary: BinaryFile) -> Result<()> {
 }
 
 struct App<'a> {
-    binary: &'a BinaryFile,
+    binary: BinaryFile,
+    args: &'a Args,
     tab_index: usize,
-    titles: Vec<&'a str>,
+    titles: Vec<&'static str>,
     hex_viewer: hex_view::HexViewer,
+    disassembled: bool,
     disasm_offset: usize,
+    /// Passes below are `None` until a tab showing them is first opened.
+    yara_hits: Option<Vec<YaraHit>>,
+    yara_selected: usize,
+    string_selected: usize,
+    xrefs: Option<XrefIndex>,
+    /// Index into `info.functions` shown on the Xrefs tab.
+    xref_function: usize,
+    xref_selected: usize,
+    findings: Option<Vec<Finding>>,
 }
 
 impl<'a> App<'a> {
-    fn new(binary: &'a BinaryFile) -> Self {
+    fn new(binary: BinaryFile, args: &'a Args, xrefs: Option<XrefIndex>) -> Self {
+        let analyzed = xrefs.is_some();
+        let yara_hits =
+            analyzed.then(|| yara::locate_hits(&binary.info.yara_matches, &binary.info));
+        let findings = analyzed.then(|| findings::collect(&binary.info));
         Self {
             binary,
+            args,
             tab_index: 0,
-            titles: vec!["Info", "Sections", "Symbols", "Disasm", "Hex"],
+            titles: vec![
+                "Info", "Sections", "Symbols", "Disasm", "Hex", "YARA", "Strings", "Xrefs",
+                "Findings",
+            ],
             hex_viewer: hex_view::HexViewer::new(),
+            disassembled: analyzed,
             disasm_offset: 0,
+            yara_hits,
+            yara_selected: 0,
+            string_selected: 0,
+            xrefs,
+            xref_function: 0,
+            xref_selected: 0,
+            findings,
+        }
+    }
+
+    /// Runs the passes the current tab shows that have not run yet.
+    fn prepare(&mut self) -> Result<()> {
+        let tab = self.titles[self.tab_index];
+        match tab {
+            "Info" | "Sections" => return Ok(()),
+            "Hex" => {
+                // Only the string labels.
+                self.binary.analyze(AnalysisOptions {
+                    scans: false,
+                    ..Default::default()
+                });
+                return Ok(());
+            }
+            _ => self.binary.analyze(AnalysisOptions::default()),
+        }
+        if tab != "Symbols" && self.xrefs.is_none() {
+            self.xrefs = Some(crate::analyze_functions(&mut self.binary, self.args)?);
         }
+        if tab == "Disasm" && !self.disassembled {
+            crate::disassemble_code(&mut self.binary);
+            self.disassembled = true;
+        }
+        if matches!(tab, "YARA" | "Findings") && self.yara_hits.is_none() {
+            let path = self.args.file.as_deref().unwrap_or_default();
+            crate::scan_yara(&mut self.binary, self.args, Path::new(path))?;
+            let info = &self.binary.info;
+            self.yara_hits = Some(yara::locate_hits(&info.yara_matches, info));
+        }
+        if tab == "Findings" && self.findings.is_none() {
+            self.findings = Some(findings::collect(&self.binary.info));
+        }
+        Ok(())
+    }
+
+    fn yara_hits(&self) -> &[YaraHit] {
+        self.yara_hits.as_deref().unwrap_or_default()
     }
 
     fn next_tab(&mut self) {
@@ -71,12 +148,50 @@ impl<'a> App<'a> {
             self.tab_index = self.titles.len() - 1;
         }
     }
-}
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, binary: &BinaryFile) -> Result<()> {
-    let mut app = App::new(binary);
+    fn xrefs_to_selected(&self) -> &[Xref] {
+        match (
+            &self.xrefs,
+            self.binary.info.functions.get(self.xref_function),
+        ) {
+            (Some(xrefs), Some(f)) => xrefs.to(f.start),
+            _ => &[],
+        }
+    }
+
+    /// Selects the function containing the highlighted reference, so callers
+    /// can be followed up the chain.
+    fn follow_xref(&mut self) {
+        let Some(xref) = self.xrefs_to_selected().get(self.xref_selected).copied() else {
+            return;
+        };
+        let functions = &self.binary.info.functions;
+        if let Some(i) = functions.iter().position(|f| f.start == xref.function) {
+            self.xref_function = i;
+            self.xref_selected = 0;
+        }
+    }
+
+    /// Opens the hex view at the selected YARA match.
+    fn jump_to_yara_hit(&mut self) {
+        if let Some(hit) = self.yara_hits().get(self.yara_selected) {
+            self.hex_viewer.jump_to(hit.offset as usize);
+            self.tab_index = self.titles.iter().position(|t| *t == "Hex").unwrap_or(0);
+        }
+    }
+
+    /// Opens the hex view at the selected string.
+    fn jump_to_string(&mut self) {
+        if let Some((offset, _)) = self.binary.info.string_index.get(self.string_selected) {
+            self.hex_viewer.jump_to(offset as usize);
+            self.tab_index = self.titles.iter().position(|t| *t == "Hex").unwrap_or(0);
+        }
+    }
+}
 
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<()> {
     loop {
+        app.prepare()?;
         terminal.draw(|f| ui(f, &app))?;
 
         if let Event::Key(key) = event::read()? {
@@ -91,6 +206,19 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, binary: &BinaryFile) -> Resul
                         && app.disasm_offset < app.binary.info.disassembly.len().saturating_sub(1)
                     {
                         app.disasm_offset += 1;
+                    } else if app.titles[app.tab_index] == "YARA"
+                        && app.yara_selected < app.yara_hits().len().saturating_sub(1)
+                    {
+                        app.yara_selected += 1;
+                    } else if app.titles[app.tab_index] == "Strings"
+                        && app.string_selected < app.binary.info.strings.len().saturating_sub(1)
+                    {
+                        app.string_selected += 1;
+                    } else if app.titles[app.tab_index] == "Xrefs"
+                        && app.xref_function < app.binary.info.functions.len().saturating_sub(1)
+                    {
+                        app.xref_function += 1;
+                        app.xref_selected = 0;
                     }
                 }
                 KeyCode::Up | KeyCode::Char('k') => {
@@ -98,8 +226,26 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, binary: &BinaryFile) -> Resul
                         app.hex_viewer.scroll_up();
                     } else if app.titles[app.tab_index] == "Disasm" && app.disasm_offset > 0 {
                         app.disasm_offset -= 1;
+                    } else if app.titles[app.tab_index] == "YARA" && app.yara_selected > 0 {
+                        app.yara_selected -= 1;
+                    } else if app.titles[app.tab_index] == "Strings" && app.string_selected > 0 {
+                        app.string_selected -= 1;
+                    } else if app.titles[app.tab_index] == "Xrefs" && app.xref_function > 0 {
+                        app.xref_function -= 1;
+                        app.xref_selected = 0;
                     }
                 }
+                KeyCode::Char('n') if app.titles[app.tab_index] == "Xrefs" => {
+                    let count = app.xrefs_to_selected().len();
+                    if count > 0 {
+                        app.xref_selected = (app.xref_selected + 1) % count;
+                    }
+                }
+                KeyCode::Enter if app.titles[app.tab_index] == "Xrefs" => app.follow_xref(),
+                KeyCode::Enter if app.titles[app.tab_index] == "Strings" => {
+                    app.jump_to_string();
+                }
+                KeyCode::Enter if app.titles[app.tab_index] == "YARA" => app.jump_to_yara_hit(),
                 KeyCode::PageDown => {
                     if app.titles[app.tab_index] == "Hex" {
                         let height = terminal.size().map(|r| r.height).unwrap_or(20) as usize;
@@ -153,14 +299,18 @@ fn ui(f: &mut Frame, app: &App) {
         1 => draw_sections_tab(f, app, chunks[1]),
         2 => draw_symbols_tab(f, app, chunks[1]),
         3 => draw_disassembly_tab(f, app, chunks[1]),
-        4 => app.hex_viewer.draw(f, chunks[1], &app.binary.data),
+        4 => draw_hex_tab(f, app, chunks[1]),
+        5 => draw_yara_tab(f, app, chunks[1]),
+        6 => draw_strings_tab(f, app, chunks[1]),
+        7 => draw_xrefs_tab(f, app, chunks[1]),
+        8 => draw_findings_tab(f, app, chunks[1]),
         _ => {}
     }
 }
 
 fn draw_info_tab(f: &mut Frame, app: &App, area: Rect) {
     let info = &app.binary.info;
-    let text = vec![
+    let mut text = vec![
         Line::from(vec![
             Span::raw("File Name: "),
             Span::styled(&app.binary.name, Style::default().fg(Color::Green)),
@@ -169,6 +319,40 @@ fn draw_info_tab(f: &mut Frame, app: &App, area: Rect) {
             Span::raw("Format:    "),
             Span::styled(&info.format, Style::default().fg(Color::Cyan)),
         ]),
+    ];
+    if let Some(wrappers) = info.wrapper_names() {
+        text.push(Line::from(vec![
+            Span::raw("Wrapper:   "),
+            Span::styled(wrappers, Style::default().fg(Color::Cyan)),
+        ]));
+    }
+    if let Some(kernel) = &info.kernel {
+        text.push(Line::from(vec![
+            Span::raw("Kernel:    "),
+            Span::styled(
+                format!(
+                    "{} ({})",
+                    kernel.version.as_deref().unwrap_or("unknown version"),
+                    kernel.image.name()
+                ),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]));
+    }
+    if !info.firmware.is_empty() {
+        text.push(Line::from(vec![
+            Span::raw("Firmware:  "),
+            Span::styled(
+                format!(
+                    "{} volume(s), {} FFS files",
+                    info.firmware.len(),
+                    info.firmware.iter().map(|v| v.file_count()).sum::<usize>()
+                ),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]));
+    }
+    text.extend(vec![
         Line::from(vec![
             Span::raw("Arch:      "),
             Span::styled(&info.arch, Style::default().fg(Color::Cyan)),
@@ -183,8 +367,30 @@ fn draw_info_tab(f: &mut Frame, app: &App, area: Rect) {
         Line::from(""),
         Line::from(format!("Total Sections: {}", info.sections.len())),
         Line::from(format!("Total Symbols:  {}", info.symbols.len())),
-        Line::from(format!("Disassembled:   {} instrs", info.disassembly.len())),
-    ];
+        Line::from(match &info.debug_info {
+            Some(debug) if debug.stripped => {
+                format!("Symbols from:   {} (stripped)", debug.symbols.name())
+            }
+            Some(debug) => format!("Symbols from:   {}", debug.symbols.name()),
+            None => "Symbols from:   n/a".to_string(),
+        }),
+        Line::from(if app.disassembled {
+            format!("Disassembled:   {} instrs", info.disassembly.len())
+        } else {
+            "Disassembled:   on the Disasm tab".to_string()
+        }),
+    ]);
+    let warnings = anomalies::warnings(info);
+    if !warnings.is_empty() {
+        text.push(Line::from(""));
+        text.push(Line::from(format!("Warnings ({}):", warnings.len())));
+        for warning in warnings {
+            text.push(Line::from(Span::styled(
+                format!("  {}", warning),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+    }
     let p =
         Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("General Info"));
     f.render_widget(p, area);
@@ -217,6 +423,46 @@ fn draw_sections_tab(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(table, area);
 }
 
+fn draw_findings_tab(f: &mut Frame, app: &App, area: Rect) {
+    let header_cells = ["Severity", "Category", "Finding", "Evidence"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD)));
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let findings = app.findings.as_deref().unwrap_or_default();
+    let rows = findings.iter().map(|finding| {
+        let color = match finding.severity {
+            Severity::High => Color::Red,
+            Severity::Medium => Color::Yellow,
+            Severity::Low => Color::Cyan,
+            Severity::Info => Color::Gray,
+        };
+        Row::new(vec![
+            Cell::from(finding.severity.name()).style(Style::default().fg(color)),
+            Cell::from(finding.category.name()),
+            Cell::from(finding.title.clone()),
+            Cell::from(finding.evidence.clone()),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Percentage(40),
+            Constraint::Percentage(60),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Findings ({})", findings.len())),
+    );
+    f.render_widget(table, area);
+}
+
 fn draw_symbols_tab(f: &mut Frame, app: &App, area: Rect) {
     let header_cells = ["Name", "Address"]
         .iter()
@@ -244,7 +490,16 @@ fn draw_symbols_tab(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(table, area);
 }
 
+/// Operands followed by the named call/jump target, if any.
+fn with_target(symbols: &SymbolMap, ins: &InstructionInfo) -> String {
+    match symbols.annotate(ins) {
+        Some(name) => format!("{} <{}>", ins.op_str, name),
+        None => ins.op_str.clone(),
+    }
+}
+
 fn draw_disassembly_tab(f: &mut Frame, app: &App, area: Rect) {
+    let symbols = SymbolMap::new(&app.binary);
     let header_cells = ["Address", "Mnemonic", "Operands"]
         .iter()
         .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD)));
@@ -257,10 +512,19 @@ fn draw_disassembly_tab(f: &mut Frame, app: &App, area: Rect) {
         .iter()
         .skip(app.disasm_offset)
         .map(|ins| {
+            let color = if ins.in_group("call") {
+                Color::Cyan
+            } else if ins.in_group("ret") || ins.in_group("int") {
+                Color::Red
+            } else if ins.in_group("jump") {
+                Color::Magenta
+            } else {
+                Color::Yellow
+            };
             Row::new(vec![
                 Cell::from(format!("0x{:x}", ins.address)),
-                Cell::from(ins.mnemonic.clone()).style(Style::default().fg(Color::Yellow)),
-                Cell::from(ins.op_str.clone()),
+                Cell::from(ins.mnemonic.clone()).style(Style::default().fg(color)),
+                Cell::from(with_target(&symbols, ins)),
             ])
         });
 
@@ -280,3 +544,280 @@ fn draw_disassembly_tab(f: &mut Frame, app: &App, area: Rect) {
     );
     f.render_widget(table, area);
 }
+
+fn draw_hex_tab(f: &mut Frame, app: &App, area: Rect) {
+    let info = &app.binary.info;
+    let offset = app.hex_viewer.scroll_offset as u64;
+    // Name the string at the top row, or the first one starting on it.
+    let index = info.string_index.find(offset).or_else(|| {
+        let row = info
+            .string_index
+            .starting_in(offset..offset + app.hex_viewer.bytes_per_row as u64);
+        (!row.is_empty()).then_some(row.start)
+    });
+    let label = index.map(|i| format!("\"{}\"", info.strings[i]));
+    app.hex_viewer
+        .draw(f, area, &app.binary.data, label.as_deref());
+}
+
+fn draw_strings_tab(f: &mut Frame, app: &App, area: Rect) {
+    let header_cells = ["Offset", "Length", "String", "Referenced From"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD)));
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let info = &app.binary.info;
+    // Keep the selection on screen without building rows for every string.
+    let visible = area.height.saturating_sub(4) as usize;
+    let first = app
+        .string_selected
+        .saturating_sub(visible.saturating_sub(1));
+    let rows = info
+        .strings
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(i, s)| {
+            let style = if i == app.string_selected {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            let (offset, len) = info.string_index.get(i).unwrap_or_default();
+            let refs = string_xrefs::refs_to(&info.string_xrefs, i);
+            let sites: Vec<&str> = refs
+                .iter()
+                .filter_map(|r| info.function_at(r.function?))
+                .map(|f| f.name.as_str())
+                .collect();
+            let referenced = match (sites.first(), refs.len()) {
+                (_, 0) => String::new(),
+                (Some(site), 1) => site.to_string(),
+                (Some(site), n) => format!("{} (+{})", site, n - 1),
+                (None, n) => format!("{} data ref(s)", n),
+            };
+            Row::new(vec![
+                Cell::from(format!("0x{:08x}", offset)),
+                Cell::from(len.to_string()),
+                Cell::from(s.clone()),
+                Cell::from(referenced),
+            ])
+            .style(style)
+        });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Min(20),
+            Constraint::Length(32),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(format!(
+        "Strings ({}) - Enter: show in hex",
+        info.strings.len()
+    )));
+    f.render_widget(table, area);
+}
+
+fn draw_xrefs_tab(f: &mut Frame, app: &App, area: Rect) {
+    let functions = &app.binary.info.functions;
+    if functions.is_empty() {
+        let p = Paragraph::new("No functions discovered.")
+            .block(Block::default().borders(Borders::ALL).title("Xrefs"));
+        f.render_widget(p, area);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+
+    let header_cells = ["Address", "Refs", "Function"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD)));
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+    let visible = chunks[0].height.saturating_sub(4) as usize;
+    let first = app.xref_function.saturating_sub(visible.saturating_sub(1));
+    let rows = functions
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(i, func)| {
+            let style = if i == app.xref_function {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(format!("0x{:x}", func.start)),
+                Cell::from(
+                    app.xrefs
+                        .as_ref()
+                        .map_or(0, |x| x.to(func.start).len())
+                        .to_string(),
+                ),
+                Cell::from(func.name.clone()),
+            ])
+            .style(style)
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(18),
+            Constraint::Length(6),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Functions ({})", functions.len())),
+    );
+    f.render_widget(table, chunks[0]);
+
+    let header_cells = ["From", "Kind", "Caller"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD)));
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+    let refs = app.xrefs_to_selected();
+    let rows = refs.iter().enumerate().map(|(i, xref)| {
+        let style = if i == app.xref_selected {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let caller = app
+            .binary
+            .info
+            .function_at(xref.function)
+            .map(|f| f.name.as_str())
+            .unwrap_or("?");
+        Row::new(vec![
+            Cell::from(format!("0x{:x}", xref.from)),
+            Cell::from(xref.kind.name()),
+            Cell::from(format!("{}+0x{:x}", caller, xref.from - xref.function)),
+        ])
+        .style(style)
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(18),
+            Constraint::Length(8),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(format!(
+        "Xrefs to {} ({}) - n: next, Enter: go to caller",
+        functions[app.xref_function].name,
+        refs.len()
+    )));
+    f.render_widget(table, chunks[1]);
+}
+
+fn draw_yara_tab(f: &mut Frame, app: &App, area: Rect) {
+    let hits = app.yara_hits();
+    if hits.is_empty() {
+        let p = Paragraph::new("No YARA matches (pass --yara <rules> to scan).")
+            .block(Block::default().borders(Borders::ALL).title("YARA"));
+        f.render_widget(p, area);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(area);
+
+    let header_cells = ["Rule", "String", "Offset", "Address", "Function"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD)));
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows = hits.iter().enumerate().map(|(i, hit)| {
+        let style = if i == app.yara_selected {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Row::new(vec![
+            Cell::from(hit.rule.clone()),
+            Cell::from(hit.identifier.clone()),
+            Cell::from(format!("0x{:x}", hit.offset)),
+            Cell::from(
+                hit.addr
+                    .map(|a| format!("0x{:x}", a))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            Cell::from(
+                hit.function
+                    .as_ref()
+                    .map(|(name, delta)| format!("{}+0x{:x}", name, delta))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ])
+        .style(style)
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(25),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(18),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("YARA Hits ({}) - Enter: show in hex", hits.len())),
+    );
+    f.render_widget(table, chunks[0]);
+
+    let symbols = SymbolMap::new(&app.binary);
+    let selected = &hits[app.yara_selected];
+    let lines: Vec<Line> = match selected.addr {
+        Some(addr) => match app.binary.disassemble_around(addr, 8, 8) {
+            Ok(instructions) => instructions
+                .iter()
+                .map(|ins| {
+                    let style = if ins.address == addr {
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+                    Line::styled(
+                        format!(
+                            "0x{:x}:  {:<10} {}",
+                            ins.address,
+                            ins.mnemonic,
+                            with_target(&symbols, ins)
+                        ),
+                        style,
+                    )
+                })
+                .collect(),
+            Err(e) => vec![Line::from(format!("No disassembly: {}", e))],
+        },
+        None => vec![Line::from("Match is not inside a mapped section.")],
+    };
+    let p = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Disassembly Around Match"),
+    );
+    f.render_widget(p, chunks[1]);
+}
@@ -0,0 +1,316 @@
+use crate::binary::BinaryInfo;
+use goblin::{elf, mach, pe, Object};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Sections mapping at least this much more than they store are inflated.
+const INFLATION_RATIO: u64 = 10;
+/// Smaller gaps are ordinary alignment padding and `.bss`-style tails.
+const INFLATION_MIN: u64 = 0x10000;
+
+/// A structural oddity in the section table or headers: rarely produced by
+/// linkers, common in packed, patched or hand-crafted files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Anomaly {
+    EmptySection {
+        section: String,
+    },
+    /// Control or non-ASCII bytes in a section name (shown escaped).
+    NonPrintableName {
+        section: String,
+    },
+    /// Much larger in memory than in the file, where unpacked code lands.
+    InflatedSection {
+        section: String,
+        virtual_size: u64,
+        raw_size: u64,
+    },
+    DuplicateSectionName {
+        section: String,
+        count: usize,
+    },
+    /// A header places data past the end of the file.
+    BeyondEof {
+        what: String,
+        end: u64,
+    },
+}
+
+impl Anomaly {
+    pub fn description(&self) -> String {
+        match self {
+            Anomaly::EmptySection { section } => format!("section {} is empty", section),
+            Anomaly::NonPrintableName { section } => {
+                format!("section name {} has non-printable characters", section)
+            }
+            Anomaly::InflatedSection {
+                section,
+                virtual_size,
+                raw_size,
+            } => format!(
+                "section {} maps 0x{:x} bytes but stores 0x{:x}",
+                section, virtual_size, raw_size
+            ),
+            Anomaly::DuplicateSectionName { section, count } => {
+                format!("{} sections are named {}", count, section)
+            }
+            Anomaly::BeyondEof { what, end } => {
+                format!("{} extends to 0x{:x}, past the end of the file", what, end)
+            }
+        }
+    }
+}
+
+/// A section as the checks see it, whatever the format.
+struct Section {
+    name: Vec<u8>,
+    /// Size once mapped.
+    size: u64,
+    /// File offset and size, unless the section has no file contents
+    /// (`SHT_NOBITS`, zerofill).
+    file: Option<(u64, u64)>,
+}
+
+/// Checks the section table and headers of `object` against the file size.
+pub fn find_anomalies(object: &Object, data: &[u8]) -> Vec<Anomaly> {
+    let (sections, regions) = match object {
+        Object::Elf(elf) => elf_layout(elf),
+        Object::PE(pe) => pe_layout(pe),
+        Object::Mach(mach::Mach::Binary(macho)) => mach_layout(macho),
+        _ => return Vec::new(),
+    };
+    check_layout(&sections, &regions, data.len() as u64)
+}
+
+/// Sections, plus other `(what, offset, size)` file regions the headers claim.
+type Layout = (Vec<Section>, Vec<(String, u64, u64)>);
+
+fn elf_layout(elf: &elf::Elf) -> Layout {
+    use elf::section_header::{SHT_GROUP, SHT_NOBITS, SHT_NULL};
+    let sections = elf
+        .section_headers
+        .iter()
+        // Group sections share names by design, and objects keep empty
+        // placeholders (`.note.GNU-stack`, `.bss`) that linkers drop.
+        .filter(|sh| sh.sh_type != SHT_NULL && sh.sh_type != SHT_GROUP)
+        .filter(|sh| sh.sh_size != 0 || elf.header.e_type != elf::header::ET_REL)
+        .map(|sh| Section {
+            name: elf
+                .shdr_strtab
+                .get_at(sh.sh_name)
+                .unwrap_or("<unknown>")
+                .into(),
+            size: sh.sh_size,
+            file: (sh.sh_type != SHT_NOBITS).then_some((sh.sh_offset, sh.sh_size)),
+        })
+        .collect();
+    let segments = elf
+        .program_headers
+        .iter()
+        .enumerate()
+        .map(|(i, ph)| (format!("segment {}", i), ph.p_offset, ph.p_filesz))
+        .collect();
+    (sections, segments)
+}
+
+fn pe_layout(pe: &pe::PE) -> Layout {
+    let sections = pe
+        .sections
+        .iter()
+        .map(|s| {
+            let len = s.name.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+            Section {
+                name: s.name[..len].to_vec(),
+                size: s.virtual_size as u64,
+                file: Some((s.pointer_to_raw_data as u64, s.size_of_raw_data as u64)),
+            }
+        })
+        .collect();
+    let mut regions = Vec::new();
+    if let Some(opt) = &pe.header.optional_header {
+        regions.push((
+            "SizeOfHeaders".to_string(),
+            0,
+            opt.windows_fields.size_of_headers as u64,
+        ));
+        // The certificate directory holds a file offset, not an RVA.
+        if let Some(dir) = opt.data_directories.get_certificate_table() {
+            regions.push((
+                "certificate table".to_string(),
+                dir.virtual_address as u64,
+                dir.size as u64,
+            ));
+        }
+    }
+    (sections, regions)
+}
+
+fn mach_layout(macho: &mach::MachO) -> Layout {
+    let mut sections = Vec::new();
+    let mut segments = Vec::new();
+    for segment in &macho.segments {
+        let segname = segment.name().unwrap_or("<bad>");
+        segments.push((
+            format!("segment {}", segname),
+            segment.fileoff,
+            segment.filesize,
+        ));
+        for (section, _) in segment.sections().unwrap_or_default() {
+            let len = section
+                .sectname
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(section.sectname.len());
+            // Section names repeat across segments (`__DATA,__const`,
+            // `__DATA_CONST,__const`), so qualify them.
+            let mut name = format!("{},", segname).into_bytes();
+            name.extend_from_slice(&section.sectname[..len]);
+            let zerofill = matches!(
+                section.flags & mach::constants::SECTION_TYPE,
+                mach::constants::S_ZEROFILL
+                    | mach::constants::S_GB_ZEROFILL
+                    | mach::constants::S_THREAD_LOCAL_ZEROFILL
+            );
+            sections.push(Section {
+                name,
+                size: section.size,
+                file: (!zerofill).then_some((section.offset as u64, section.size)),
+            });
+        }
+    }
+    (sections, segments)
+}
+
+fn check_layout(
+    sections: &[Section],
+    regions: &[(String, u64, u64)],
+    file_size: u64,
+) -> Vec<Anomaly> {
+    let display = |name: &[u8]| String::from_utf8_lossy(name).escape_debug().to_string();
+    let mut anomalies = Vec::new();
+    let mut names: BTreeMap<&[u8], usize> = BTreeMap::new();
+    for section in sections {
+        let name = display(&section.name);
+        *names.entry(&section.name).or_default() += 1;
+        if section.name.iter().any(|&b| !(0x20..0x7f).contains(&b)) {
+            anomalies.push(Anomaly::NonPrintableName {
+                section: name.clone(),
+            });
+        }
+        let raw_size = section.file.map_or(0, |(_, size)| size);
+        if section.size == 0 && raw_size == 0 {
+            anomalies.push(Anomaly::EmptySection {
+                section: name.clone(),
+            });
+        }
+        if let Some((offset, size)) = section.file {
+            if section.size >= INFLATION_MIN && section.size > size.saturating_mul(INFLATION_RATIO)
+            {
+                anomalies.push(Anomaly::InflatedSection {
+                    section: name.clone(),
+                    virtual_size: section.size,
+                    raw_size: size,
+                });
+            }
+            if size != 0 && offset.saturating_add(size) > file_size {
+                anomalies.push(Anomaly::BeyondEof {
+                    what: format!("section {}", name),
+                    end: offset.saturating_add(size),
+                });
+            }
+        }
+    }
+    for (name, count) in names {
+        if count > 1 {
+            anomalies.push(Anomaly::DuplicateSectionName {
+                section: display(name),
+                count,
+            });
+        }
+    }
+    for (what, offset, size) in regions {
+        let end = offset.saturating_add(*size);
+        if *size != 0 && end > file_size {
+            anomalies.push(Anomaly::BeyondEof {
+                what: what.clone(),
+                end,
+            });
+        }
+    }
+    anomalies
+}
+
+/// Every warning raised about the file's structure: parse failures, entry
+/// point and section anomalies, and PE header and timestamp issues.
+pub fn warnings(info: &BinaryInfo) -> Vec<String> {
+    let mut warnings: Vec<String> = info
+        .parse_warnings
+        .iter()
+        .map(|w| format!("{}: {}", w.stage, w.message))
+        .collect();
+    warnings.extend(info.entry_anomalies.iter().map(|a| a.description()));
+    warnings.extend(info.anomalies.iter().map(|a| a.description()));
+    if let Some(headers) = &info.pe_headers {
+        warnings.extend(headers.issues.iter().cloned());
+        warnings.extend(headers.timestamps.issues.iter().cloned());
+    }
+    if let Some(module) = &info.kernel_module {
+        warnings.extend(module.warnings.iter().cloned());
+    }
+    if let Some(linker) = &info.linker {
+        warnings.extend(linker.warnings.iter().cloned());
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &[u8], size: u64, file: Option<(u64, u64)>) -> Section {
+        Section {
+            name: name.to_vec(),
+            size,
+            file,
+        }
+    }
+
+    #[test]
+    fn test_check_layout() {
+        let sections = [
+            section(b".text", 0x1000, Some((0x400, 0x1000))),
+            section(b".bss", 0x100000, None),
+            section(b"UPX0", 0x100000, Some((0x1400, 0))),
+            section(b".text", 0, Some((0, 0))),
+            section(b"\x01bad", 0x10, Some((0x1400, 0x10000))),
+        ];
+        let regions = [("segment 0".to_string(), 0, 0x2000)];
+        let anomalies = check_layout(&sections, &regions, 0x1800);
+        let expected = vec![
+            Anomaly::InflatedSection {
+                section: "UPX0".to_string(),
+                virtual_size: 0x100000,
+                raw_size: 0,
+            },
+            Anomaly::EmptySection {
+                section: ".text".to_string(),
+            },
+            Anomaly::NonPrintableName {
+                section: "\\u{1}bad".to_string(),
+            },
+            Anomaly::BeyondEof {
+                what: "section \\u{1}bad".to_string(),
+                end: 0x11400,
+            },
+            Anomaly::DuplicateSectionName {
+                section: ".text".to_string(),
+                count: 2,
+            },
+            Anomaly::BeyondEof {
+                what: "segment 0".to_string(),
+                end: 0x2000,
+            },
+        ];
+        assert_eq!(anomalies, expected);
+    }
+}
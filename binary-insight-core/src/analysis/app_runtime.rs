@@ -0,0 +1,190 @@
+use crate::binary::archive::{self, ArchiveKind, ArchiveMember};
+use crate::binary::compression::DEFAULT_DECOMPRESSION_LIMIT;
+use crate::binary::BinaryInfo;
+
+/// Strings only the Electron runtime carries.
+const ELECTRON_MARKERS: &[&[u8]] = &[b"electron.asar", b"ELECTRON_RUN_AS_NODE"];
+const ELECTRON_VERSION: &[u8] = b"Electron/";
+const NWJS_MARKERS: &[&[u8]] = &[b"package.nw", b"nwjs.io"];
+/// Node's single executable application fuse, flipped to `1` once a blob
+/// has been injected.
+const NODE_SEA_FUSE: &[u8] = b"NODE_SEA_FUSE_fce680ab2cc467b6e072b8b5df1996b2:1";
+/// `process.release` URLs carry the bundled Node.js version.
+const NODE_RELEASE_URL: &[u8] = b"nodejs.org/download/release/v";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppRuntimeKind {
+    Electron,
+    NwJs,
+    /// Node.js single executable application.
+    NodeSea,
+}
+
+impl AppRuntimeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppRuntimeKind::Electron => "Electron",
+            AppRuntimeKind::NwJs => "NW.js",
+            AppRuntimeKind::NodeSea => "Node.js single executable",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AppRuntime {
+    pub kind: AppRuntimeKind,
+    pub version: Option<String>,
+    pub node_version: Option<String>,
+    /// Path of the `app.asar` inside an archive; `None` when the input is
+    /// the ASAR itself or no ASAR was found.
+    pub asar: Option<String>,
+    /// Files in the application's ASAR archive.
+    pub entries: Vec<ArchiveMember>,
+}
+
+/// Recognizes apps built on a bundled JavaScript runtime: the runtime
+/// executable itself, an ASAR archive, or an archive of an app directory.
+pub fn detect_runtime(data: &[u8], info: &BinaryInfo) -> Option<AppRuntime> {
+    if let Some(archive) = &info.archive {
+        return detect_in_archive(data, archive);
+    }
+    let executable =
+        info.format == "PE" || info.format == "ELF" || info.format.starts_with("Mach-O");
+    if !executable {
+        return None;
+    }
+    let kind = if contains(data, NODE_SEA_FUSE) {
+        AppRuntimeKind::NodeSea
+    } else if ELECTRON_MARKERS.iter().any(|m| contains(data, m)) {
+        AppRuntimeKind::Electron
+    } else if NWJS_MARKERS.iter().any(|m| contains(data, m)) {
+        AppRuntimeKind::NwJs
+    } else {
+        return None;
+    };
+    Some(AppRuntime {
+        kind,
+        version: (kind == AppRuntimeKind::Electron)
+            .then(|| version_after(data, ELECTRON_VERSION))
+            .flatten(),
+        node_version: version_after(data, NODE_RELEASE_URL),
+        asar: None,
+        entries: Vec::new(),
+    })
+}
+
+fn detect_in_archive(data: &[u8], archive: &archive::ArchiveInfo) -> Option<AppRuntime> {
+    let runtime = |kind, asar, entries| AppRuntime {
+        kind,
+        version: None,
+        node_version: None,
+        asar,
+        entries,
+    };
+    if archive.kind == ArchiveKind::Asar {
+        return Some(runtime(
+            AppRuntimeKind::Electron,
+            None,
+            archive.members.clone(),
+        ));
+    }
+    let named = |name: &str| {
+        archive
+            .members
+            .iter()
+            .find(|m| m.path == name || m.path.ends_with(&format!("/{}", name)))
+    };
+    if let Some(member) = named("app.asar") {
+        let entries = archive::read_member(data, archive.kind, member, DEFAULT_DECOMPRESSION_LIMIT)
+            .ok()
+            .filter(|asar| ArchiveKind::detect(asar) == Some(ArchiveKind::Asar))
+            .and_then(|asar| archive::parse_archive(&asar, ArchiveKind::Asar).ok())
+            .map(|asar| asar.members)
+            .unwrap_or_default();
+        return Some(runtime(
+            AppRuntimeKind::Electron,
+            Some(member.path.clone()),
+            entries,
+        ));
+    }
+    if named("electron.asar").is_some() {
+        return Some(runtime(AppRuntimeKind::Electron, None, Vec::new()));
+    }
+    if named("package.nw").is_some() || named("nw.pak").is_some() {
+        return Some(runtime(AppRuntimeKind::NwJs, None, Vec::new()));
+    }
+    None
+}
+
+/// The dotted version number right after `prefix`, e.g. `28.1.0`.
+fn version_after(data: &[u8], prefix: &[u8]) -> Option<String> {
+    let mut from = 0;
+    while let Some(pos) = find(&data[from..], prefix) {
+        let start = from + pos + prefix.len();
+        let len = data[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit() || **b == b'.')
+            .count();
+        let version = &data[start..start + len];
+        if version.first().is_some_and(u8::is_ascii_digit) && version.contains(&b'.') {
+            return Some(
+                String::from_utf8_lossy(version)
+                    .trim_end_matches('.')
+                    .to_string(),
+            );
+        }
+        from = start;
+    }
+    None
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pe_info() -> BinaryInfo {
+        BinaryInfo {
+            format: "PE".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_detect_electron() {
+        let data = b"..resources\\electron.asar..Chrome/120.0 Electron/28.1.0 \
+            ..https://nodejs.org/download/release/v18.18.2/node-v18.18.2-headers.tar.gz";
+        let runtime = detect_runtime(data, &pe_info()).expect("not detected");
+        assert_eq!(runtime.kind, AppRuntimeKind::Electron);
+        assert_eq!(runtime.version.as_deref(), Some("28.1.0"));
+        assert_eq!(runtime.node_version.as_deref(), Some("18.18.2"));
+
+        // The same strings in a data file are not a runtime.
+        assert!(detect_runtime(data, &BinaryInfo::default()).is_none());
+    }
+
+    #[test]
+    fn test_detect_other_runtimes() {
+        let sea = b"NODE_SEA_FUSE_fce680ab2cc467b6e072b8b5df1996b2:1 electron.asar";
+        let runtime = detect_runtime(sea, &pe_info()).unwrap();
+        assert_eq!(runtime.kind, AppRuntimeKind::NodeSea);
+        assert_eq!(runtime.version, None);
+
+        // Every Node.js binary has the fuse, unflipped.
+        let node = b"NODE_SEA_FUSE_fce680ab2cc467b6e072b8b5df1996b2:0";
+        assert!(detect_runtime(node, &pe_info()).is_none());
+
+        let nw = b"loading package.nw from the executable";
+        assert_eq!(
+            detect_runtime(nw, &pe_info()).unwrap().kind,
+            AppRuntimeKind::NwJs
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use super::findings::Finding;
+use std::collections::BTreeMap;
+
+/// MITRE ATT&CK techniques the bundled passes and rules can report.
+const TECHNIQUES: &[(&str, &str)] = &[
+    ("T1027", "Obfuscated Files or Information"),
+    ("T1055", "Process Injection"),
+    ("T1056", "Input Capture"),
+    ("T1056.001", "Keylogging"),
+    ("T1057", "Process Discovery"),
+    ("T1059", "Command and Scripting Interpreter"),
+    ("T1070", "Indicator Removal"),
+    ("T1070.006", "Timestomp"),
+    ("T1071", "Application Layer Protocol"),
+    ("T1083", "File and Directory Discovery"),
+    ("T1095", "Non-Application Layer Protocol"),
+    ("T1105", "Ingress Tool Transfer"),
+    ("T1106", "Native API"),
+    ("T1113", "Screen Capture"),
+    ("T1115", "Clipboard Data"),
+    ("T1497", "Virtualization/Sandbox Evasion"),
+    ("T1547", "Boot or Logon Autostart Execution"),
+    ("T1547.001", "Registry Run Keys / Startup Folder"),
+    ("T1574", "Hijack Execution Flow"),
+    ("T1574.004", "Dylib Hijacking"),
+    ("T1622", "Debugger Evasion"),
+];
+
+/// Checks for a debugger, reported for anti-debugging findings.
+pub const DEBUGGER_EVASION: &str = "T1622";
+/// Checks for VMs and sandboxes, reported for anti-VM findings.
+pub const SANDBOX_EVASION: &str = "T1497";
+
+/// The name of technique `id`; sub-techniques not in the table fall back to
+/// their parent's name.
+pub fn technique_name(id: &str) -> Option<&'static str> {
+    let lookup = |id: &str| TECHNIQUES.iter().find(|(t, _)| *t == id).map(|(_, n)| *n);
+    lookup(id).or_else(|| lookup(id.split('.').next()?))
+}
+
+/// The technique id in `text`: either a bare id or capa's
+/// `Tactic::Technique [T1055]` form.
+pub fn parse_technique(text: &str) -> Option<String> {
+    let id = match text.rfind('[') {
+        Some(start) => text[start + 1..].strip_suffix(']')?,
+        None => text.trim(),
+    };
+    let digits = id.strip_prefix('T')?;
+    let valid = digits
+        .split('.')
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    valid.then(|| id.to_string())
+}
+
+/// Technique id to the ids of the findings tagged with it.
+pub fn summary(findings: &[Finding]) -> BTreeMap<&str, Vec<&str>> {
+    let mut techniques: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for finding in findings {
+        for technique in &finding.attack {
+            let ids = techniques.entry(technique.as_str()).or_default();
+            if !ids.contains(&finding.id.as_str()) {
+                ids.push(&finding.id);
+            }
+        }
+    }
+    techniques
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::findings::{Category, Severity};
+
+    #[test]
+    fn test_technique_ids() {
+        assert_eq!(parse_technique("T1055"), Some("T1055".to_string()));
+        assert_eq!(
+            parse_technique("Persistence::Boot or Logon Autostart Execution [T1547.001]"),
+            Some("T1547.001".to_string())
+        );
+        assert_eq!(parse_technique("process injection"), None);
+        assert_eq!(parse_technique("T10x"), None);
+        assert_eq!(technique_name("T1055"), Some("Process Injection"));
+        assert_eq!(technique_name("T1055.012"), Some("Process Injection"));
+        assert_eq!(technique_name("T9999"), None);
+    }
+
+    #[test]
+    fn test_summary_groups_findings() {
+        let finding = |id: &str, attack: &[&str]| Finding {
+            id: id.to_string(),
+            title: String::new(),
+            severity: Severity::Medium,
+            category: Category::Evasion,
+            evidence: String::new(),
+            addresses: Vec::new(),
+            attack: attack.iter().map(|t| t.to_string()).collect(),
+        };
+        let findings = [
+            finding("anti_debug.ptrace", &[DEBUGGER_EVASION]),
+            finding("anti_debug.ptrace", &[DEBUGGER_EVASION]),
+            finding("capability.inject", &["T1055", DEBUGGER_EVASION]),
+        ];
+        let summary = summary(&findings);
+        assert_eq!(summary["T1055"], vec!["capability.inject"]);
+        assert_eq!(
+            summary[DEBUGGER_EVASION],
+            vec!["anti_debug.ptrace", "capability.inject"]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! MalwareBazaar (abuse.ch) sample reports, parsed from the `get_info`
+//! API response. The query itself is left to the caller.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// API endpoint taking `query=get_info&hash=<sha256>` as a form POST.
+pub const API_URL: &str = "https://mb-api.abuse.ch/api/v1/";
+
+/// What MalwareBazaar knows about a sample.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BazaarReport {
+    /// Malware family, e.g. `AgentTesla`.
+    #[serde(rename = "signature")]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// How the sample was delivered, e.g. `email_attachment`.
+    pub delivery_method: Option<String>,
+    pub first_seen: Option<String>,
+    pub file_name: Option<String>,
+    pub reporter: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    query_status: String,
+    #[serde(default)]
+    data: Vec<BazaarReport>,
+}
+
+/// `Ok(None)` when the hash is unknown to MalwareBazaar.
+pub fn parse_response(body: &str) -> Result<Option<BazaarReport>> {
+    let response: Response = serde_json::from_str(body)?;
+    match response.query_status.as_str() {
+        "ok" => Ok(response.data.into_iter().next()),
+        "hash_not_found" | "no_results" => Ok(None),
+        status => bail!("MalwareBazaar query failed: {}", status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let body = r#"{
+            "query_status": "ok",
+            "data": [{
+                "sha256_hash": "0000",
+                "file_name": "invoice.exe",
+                "signature": "AgentTesla",
+                "tags": ["exe", "AgentTesla"],
+                "delivery_method": "email_attachment",
+                "first_seen": "2024-01-02 03:04:05",
+                "reporter": "abuse_ch",
+                "intelligence": { "downloads": "12" }
+            }]
+        }"#;
+        let report = parse_response(body).unwrap().unwrap();
+        assert_eq!(report.family.as_deref(), Some("AgentTesla"));
+        assert_eq!(report.tags, ["exe", "AgentTesla"]);
+        assert_eq!(report.delivery_method.as_deref(), Some("email_attachment"));
+
+        assert_eq!(
+            parse_response(r#"{"query_status": "hash_not_found"}"#).unwrap(),
+            None
+        );
+        assert!(parse_response(r#"{"query_status": "unknown_auth_key"}"#).is_err());
+    }
+}
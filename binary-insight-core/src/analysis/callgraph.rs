@@ -0,0 +1,190 @@
+use crate::binary::BinaryFile;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Functions decoded before the walk gives up on very large binaries.
+const MAX_FUNCTIONS: usize = 20_000;
+
+#[derive(Debug, Clone)]
+pub struct FunctionNode {
+    pub addr: u64,
+    /// Symbol name, or `sub_<addr>` when the function is unnamed.
+    pub name: String,
+    /// Entry point or exported function.
+    pub root: bool,
+    pub blocks: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallEdge {
+    pub from: u64,
+    pub to: u64,
+    /// Reached by a jump out of the caller rather than a call.
+    pub tail_call: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    /// Functions in address order.
+    pub functions: Vec<FunctionNode>,
+    pub edges: Vec<CallEdge>,
+}
+
+impl CallGraph {
+    pub fn function(&self, addr: u64) -> Option<&FunctionNode> {
+        self.functions.iter().find(|f| f.addr == addr)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph callgraph {\n  node [shape=box];\n");
+        for f in &self.functions {
+            let style = if f.root { " style=bold" } else { "" };
+            let _ = writeln!(
+                out,
+                "  \"0x{:x}\" [label=\"{}\"{}];",
+                f.addr,
+                escape_dot(&f.name),
+                style
+            );
+        }
+        for e in &self.edges {
+            let style = if e.tail_call { " [style=dashed]" } else { "" };
+            let _ = writeln!(out, "  \"0x{:x}\" -> \"0x{:x}\"{};", e.from, e.to, style);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+            "  <key id=\"root\" for=\"node\" attr.name=\"root\" attr.type=\"boolean\"/>\n",
+            "  <key id=\"blocks\" for=\"node\" attr.name=\"blocks\" attr.type=\"int\"/>\n",
+            "  <key id=\"tail\" for=\"edge\" attr.name=\"tail_call\" attr.type=\"boolean\"/>\n",
+            "  <graph id=\"callgraph\" edgedefault=\"directed\">\n",
+        ));
+        for f in &self.functions {
+            let _ = writeln!(
+                out,
+                "    <node id=\"0x{:x}\"><data key=\"name\">{}</data><data key=\"root\">{}</data><data key=\"blocks\">{}</data></node>",
+                f.addr,
+                escape_xml(&f.name),
+                f.root,
+                f.blocks
+            );
+        }
+        for e in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge source=\"0x{:x}\" target=\"0x{:x}\"><data key=\"tail\">{}</data></edge>",
+                e.from, e.to, e.tail_call
+            );
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Follows direct calls (and tail jumps) from the entry point and exported
+/// functions, building each function's CFG along the way.
+pub fn build_call_graph(file: &BinaryFile) -> CallGraph {
+    let info = &file.info;
+    let names: BTreeMap<u64, &str> = info
+        .symbols
+        .iter()
+        .chain(&info.exports)
+        .filter(|s| !s.name.is_empty())
+        .map(|s| (s.addr, s.name.as_str()))
+        .collect();
+
+    let mut roots: BTreeSet<u64> = info.exports.iter().map(|e| e.addr).collect();
+    if info.section_for_addr(info.entry_point).is_some() {
+        roots.insert(info.entry_point);
+    }
+
+    let mut functions = BTreeMap::new();
+    let mut edges = BTreeSet::new();
+    let mut work: Vec<u64> = roots.iter().copied().collect();
+    while let Some(addr) = work.pop() {
+        if functions.contains_key(&addr) || functions.len() >= MAX_FUNCTIONS {
+            continue;
+        }
+        let Ok(cfg) = file.function_cfg(addr) else {
+            continue;
+        };
+        let calls = cfg
+            .blocks
+            .iter()
+            .flat_map(|b| &b.calls)
+            .map(|&t| (t, false));
+        let tails = cfg.external_targets.iter().map(|&t| (t, true));
+        for (target, tail_call) in calls.chain(tails) {
+            if info.section_for_addr(target).is_none() {
+                continue;
+            }
+            edges.insert((addr, target, tail_call));
+            work.push(target);
+        }
+        functions.insert(
+            addr,
+            FunctionNode {
+                addr,
+                name: names
+                    .get(&addr)
+                    .map(|n| n.to_string())
+                    .unwrap_or_else(|| format!("sub_{:x}", addr)),
+                root: roots.contains(&addr),
+                blocks: cfg.blocks.len(),
+            },
+        );
+    }
+
+    CallGraph {
+        edges: edges
+            .into_iter()
+            .filter(|(_, to, _)| functions.contains_key(to))
+            .map(|(from, to, tail_call)| CallEdge {
+                from,
+                to,
+                tail_call,
+            })
+            .collect(),
+        functions: functions.into_values().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_graph_follows_calls() {
+        // 0x1000: call 0x1006; ret
+        // 0x1006: call 0x100c; ret
+        // 0x100c: ret
+        let code = vec![
+            0xe8, 0x01, 0x00, 0x00, 0x00, 0xc3, 0xe8, 0x01, 0x00, 0x00, 0x00, 0xc3, 0xc3,
+        ];
+        let file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        let graph = build_call_graph(&file);
+        let addrs: Vec<u64> = graph.functions.iter().map(|f| f.addr).collect();
+        assert_eq!(addrs, vec![0x1000, 0x1006, 0x100c]);
+        assert!(graph.functions[0].root);
+        assert_eq!(graph.edges.len(), 2);
+        assert!(graph.to_dot().contains("\"0x1000\" -> \"0x1006\""));
+        assert!(graph.to_graphml().contains("<node id=\"0x100c\">"));
+    }
+}
@@ -0,0 +1,441 @@
+use super::pattern::Pattern;
+use super::yaml::{self, Yaml};
+use super::{attack, disassembly};
+use crate::binary::{BinaryFile, BinaryInfo};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Rules shipped with the tool, used unless a rule directory is given.
+const BUNDLED_RULES: &[(&str, &str)] = &[
+    (
+        "create-remote-thread.yml",
+        include_str!("../../rules/create-remote-thread.yml"),
+    ),
+    (
+        "write-process-memory.yml",
+        include_str!("../../rules/write-process-memory.yml"),
+    ),
+    (
+        "enumerate-processes.yml",
+        include_str!("../../rules/enumerate-processes.yml"),
+    ),
+    (
+        "read-clipboard.yml",
+        include_str!("../../rules/read-clipboard.yml"),
+    ),
+    (
+        "log-keystrokes.yml",
+        include_str!("../../rules/log-keystrokes.yml"),
+    ),
+    (
+        "capture-screenshot.yml",
+        include_str!("../../rules/capture-screenshot.yml"),
+    ),
+    (
+        "download-file.yml",
+        include_str!("../../rules/download-file.yml"),
+    ),
+    (
+        "connect-socket.yml",
+        include_str!("../../rules/connect-socket.yml"),
+    ),
+    (
+        "persist-run-key.yml",
+        include_str!("../../rules/persist-run-key.yml"),
+    ),
+    (
+        "execute-shell-command.yml",
+        include_str!("../../rules/execute-shell-command.yml"),
+    ),
+    (
+        "enumerate-files.yml",
+        include_str!("../../rules/enumerate-files.yml"),
+    ),
+    (
+        "create-mutex.yml",
+        include_str!("../../rules/create-mutex.yml"),
+    ),
+    ("access-peb.yml", include_str!("../../rules/access-peb.yml")),
+    ("aes-ni.yml", include_str!("../../rules/aes-ni.yml")),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Feature {
+    /// Imported or defined function; `A`/`W` variants match too.
+    Api(String),
+    /// An extracted string, exactly.
+    String(String),
+    /// An extracted string containing this, case-insensitively.
+    Substring(String),
+    Mnemonic(String),
+    /// Bytes anywhere in the file.
+    Bytes(Pattern),
+    And(Vec<Feature>),
+    Or(Vec<Feature>),
+    Not(Box<Feature>),
+    AtLeast(usize, Vec<Feature>),
+}
+
+/// A capa-style capability rule.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    /// Grouping such as `host-interaction/process/inject`.
+    pub namespace: Option<String>,
+    /// ATT&CK technique ids from `meta.attack`.
+    pub attack: Vec<String>,
+    features: Feature,
+}
+
+impl Rule {
+    /// Parses one rule:
+    ///
+    /// ```yaml
+    /// rule:
+    ///   meta:
+    ///     name: create remote thread
+    ///     namespace: host-interaction/thread/create
+    ///     attack: [T1055]
+    ///   features:
+    ///     - or:
+    ///       - api: CreateRemoteThread
+    ///       - api: NtCreateThreadEx
+    /// ```
+    pub fn parse(text: &str) -> Result<Self> {
+        let doc = yaml::parse(text)?;
+        let rule = doc.get("rule").context("missing `rule`")?;
+        let meta = rule.get("meta").context("missing `rule.meta`")?;
+        let name = meta
+            .get("name")
+            .and_then(Yaml::as_str)
+            .context("missing `meta.name`")?;
+        let features = match rule.get("features") {
+            Some(Yaml::List(items)) => Feature::And(parse_features(items)?),
+            _ => bail!("`features` must be a list"),
+        };
+        let attack = match meta.get("attack") {
+            None => Vec::new(),
+            Some(Yaml::List(items)) => items.iter().map(technique).collect::<Result<_>>()?,
+            Some(value) => vec![technique(value)?],
+        };
+        Ok(Rule {
+            name: name.to_string(),
+            namespace: meta
+                .get("namespace")
+                .and_then(Yaml::as_str)
+                .map(str::to_string),
+            attack,
+            features,
+        })
+    }
+}
+
+fn technique(value: &Yaml) -> Result<String> {
+    value
+        .as_str()
+        .and_then(attack::parse_technique)
+        .ok_or_else(|| anyhow!("invalid ATT&CK technique {:?}", value))
+}
+
+fn parse_features(items: &[Yaml]) -> Result<Vec<Feature>> {
+    items.iter().map(parse_feature).collect()
+}
+
+fn parse_feature(item: &Yaml) -> Result<Feature> {
+    let Yaml::Map(entries) = item else {
+        bail!("feature must be a `kind: value` mapping");
+    };
+    let [(kind, value)] = entries.as_slice() else {
+        bail!("feature must have exactly one kind");
+    };
+    let children = || match value {
+        Yaml::List(items) => parse_features(items),
+        _ => bail!("`{}` needs a list of features", kind),
+    };
+    let text = || {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("`{}` needs a value", kind))
+    };
+    Ok(match kind.as_str() {
+        "and" => Feature::And(children()?),
+        "or" => Feature::Or(children()?),
+        "not" => match children()?.as_slice() {
+            [inner] => Feature::Not(Box::new(inner.clone())),
+            _ => bail!("`not` takes exactly one feature"),
+        },
+        "api" => Feature::Api(text()?),
+        "string" => Feature::String(text()?),
+        "substring" => Feature::Substring(text()?.to_lowercase()),
+        "mnemonic" => Feature::Mnemonic(text()?.to_lowercase()),
+        "bytes" => Feature::Bytes(Pattern::parse(&text()?)?),
+        _ => match kind.strip_suffix(" or more").map(str::parse) {
+            Some(Ok(count)) => Feature::AtLeast(count, children()?),
+            _ => bail!("unknown feature `{}`", kind),
+        },
+    })
+}
+
+/// A collection of rules, loaded from `.yml`/`.yaml` files or bundled.
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// The starter rules built into the binary.
+    pub fn bundled() -> Self {
+        let rules = BUNDLED_RULES
+            .iter()
+            .map(|(name, text)| {
+                Rule::parse(text).unwrap_or_else(|e| panic!("bundled rule {}: {}", name, e))
+            })
+            .collect();
+        RuleSet { rules }
+    }
+
+    /// Loads a rule file, or every rule file in a directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let files = if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| {
+                    p.extension()
+                        .is_some_and(|ext| ext == "yml" || ext == "yaml")
+                })
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut set = RuleSet::default();
+        for file in files {
+            let text = fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read rule file {}", file.display()))?;
+            let rule = Rule::parse(&text).with_context(|| format!("Invalid {}", file.display()))?;
+            set.rules.push(rule);
+        }
+        Ok(set)
+    }
+}
+
+/// A rule that matched, with the features that satisfied it.
+#[derive(Debug, Clone)]
+pub struct Capability {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub attack: Vec<String>,
+    /// Matched features, e.g. `api: CreateRemoteThread`.
+    pub evidence: Vec<String>,
+}
+
+/// What rules are matched against, gathered once per file.
+struct Features<'a> {
+    /// Lowercased import and symbol names.
+    apis: HashSet<String>,
+    strings: &'a [String],
+    mnemonics: HashSet<String>,
+    data: &'a [u8],
+}
+
+/// Matches `rules` against the whole file; rules are not scoped to
+/// functions.
+pub fn find_capabilities(file: &BinaryFile, rules: &RuleSet) -> Vec<Capability> {
+    let info = &file.info;
+    let apis = api_names(info);
+    let mut mnemonics = HashSet::new();
+    if rules.rules.iter().any(|r| uses_mnemonics(&r.features)) {
+        for section in info.sections.iter().filter(|s| s.is_code()) {
+            let arch = info.arch_at(section.addr);
+            let _ =
+                disassembly::linear_sweep(arch, file.section_data(section), section.addr, |ins| {
+                    mnemonics.insert(ins.mnemonic);
+                    Ok(())
+                });
+        }
+    }
+    let features = Features {
+        apis,
+        strings: &info.strings,
+        mnemonics,
+        data: &file.data,
+    };
+
+    rules
+        .rules
+        .iter()
+        .filter_map(|rule| {
+            Some(Capability {
+                name: rule.name.clone(),
+                namespace: rule.namespace.clone(),
+                attack: rule.attack.clone(),
+                evidence: evaluate(&rule.features, &features)?,
+            })
+        })
+        .collect()
+}
+
+/// Lowercased import and symbol names, without `@` versions or a leading
+/// underscore.
+pub(crate) fn api_names(info: &BinaryInfo) -> HashSet<String> {
+    info.imports
+        .iter()
+        .map(|i| i.name.as_str())
+        .chain(info.symbols.iter().map(|s| s.name.as_str()))
+        .map(|name| {
+            let name = name.split('@').next().unwrap_or(name);
+            name.strip_prefix('_').unwrap_or(name).to_lowercase()
+        })
+        .collect()
+}
+
+/// Whether `api` (optionally `library.api`) or its `A`/`W` variant is in
+/// `apis`, as built by [`api_names`].
+pub(crate) fn has_api(apis: &HashSet<String>, api: &str) -> bool {
+    let name = api.rsplit('.').next().unwrap_or(api).to_lowercase();
+    ["", "a", "w"]
+        .iter()
+        .any(|suffix| apis.contains(&format!("{}{}", name, suffix)))
+}
+
+fn uses_mnemonics(feature: &Feature) -> bool {
+    match feature {
+        Feature::Mnemonic(_) => true,
+        Feature::And(children) | Feature::Or(children) | Feature::AtLeast(_, children) => {
+            children.iter().any(uses_mnemonics)
+        }
+        Feature::Not(inner) => uses_mnemonics(inner),
+        _ => false,
+    }
+}
+
+/// The evidence for `feature`, if it holds.
+fn evaluate(feature: &Feature, features: &Features) -> Option<Vec<String>> {
+    let leaf = |matched: bool, kind: &str, value: &str| {
+        matched.then(|| vec![format!("{}: {}", kind, value)])
+    };
+    match feature {
+        Feature::Api(api) => leaf(has_api(&features.apis, api), "api", api),
+        Feature::String(s) => leaf(features.strings.contains(s), "string", s),
+        Feature::Substring(s) => leaf(
+            features
+                .strings
+                .iter()
+                .any(|x| x.to_lowercase().contains(s)),
+            "substring",
+            s,
+        ),
+        Feature::Mnemonic(m) => leaf(features.mnemonics.contains(m), "mnemonic", m),
+        Feature::Bytes(pattern) => {
+            let matched = features
+                .data
+                .windows(pattern.len())
+                .any(|w| pattern.matches(w));
+            leaf(matched, "bytes", &pattern.to_string())
+        }
+        Feature::And(children) => {
+            let mut evidence = Vec::new();
+            for child in children {
+                evidence.extend(evaluate(child, features)?);
+            }
+            Some(evidence)
+        }
+        Feature::Or(children) => children.iter().find_map(|c| evaluate(c, features)),
+        Feature::Not(inner) => match evaluate(inner, features) {
+            Some(_) => None,
+            None => Some(Vec::new()),
+        },
+        Feature::AtLeast(count, children) => {
+            let matched: Vec<Vec<String>> = children
+                .iter()
+                .filter_map(|c| evaluate(c, features))
+                .collect();
+            (matched.len() >= *count).then(|| matched.concat())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::ImportInfo;
+
+    fn import(name: &str) -> ImportInfo {
+        ImportInfo {
+            name: name.to_string(),
+            library: Some("KERNEL32.dll".to_string()),
+            slot: None,
+        }
+    }
+
+    #[test]
+    fn test_bundled_rules_parse() {
+        let rules = RuleSet::bundled().rules;
+        assert_eq!(rules.len(), BUNDLED_RULES.len());
+        for id in rules.iter().flat_map(|r| &r.attack) {
+            assert!(attack::technique_name(id).is_some(), "unnamed {}", id);
+        }
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let rule = Rule::parse(
+            r#"
+rule:
+  meta:
+    name: inject code
+    namespace: host-interaction/process/inject
+    attack: Defense Evasion::Process Injection [T1055]
+  features:
+    - and:
+      - api: kernel32.WriteProcessMemory
+      - 1 or more:
+        - api: CreateRemoteThread
+        - bytes: 0F ?? C3
+      - not:
+        - substring: benign
+"#,
+        )
+        .unwrap();
+        let set = RuleSet { rules: vec![rule] };
+        let mut file = BinaryFile::from_raw(
+            "t".to_string(),
+            vec![0x90, 0x0f, 0x31, 0xc3, 0x90],
+            "x86_64",
+            0,
+        );
+        file.info.imports = vec![import("WriteProcessMemory"), import("OpenClipboard")];
+        let found = find_capabilities(&file, &set);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].attack, vec!["T1055"]);
+        assert_eq!(
+            found[0].evidence,
+            vec!["api: kernel32.WriteProcessMemory", "bytes: 0F ?? C3"]
+        );
+
+        file.info.strings.push("a Benign tool".to_string());
+        assert!(find_capabilities(&file, &set).is_empty());
+    }
+
+    #[test]
+    fn test_bundled_rules_on_imports() {
+        let mut file = BinaryFile::from_raw("t".to_string(), vec![0x90], "x86", 0);
+        file.info.imports = vec![import("OpenClipboard"), import("GetClipboardData")];
+        let found = find_capabilities(&file, &RuleSet::bundled());
+        let names: Vec<&str> = found.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["read clipboard data"]);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Rule::parse("rule:\n  meta:\n    name: x\n  features:\n    - fly: y\n").is_err());
+        assert!(
+            Rule::parse("rule:\n  meta:\n    name: x\n  features:\n    - bytes: ?? ??\n").is_err()
+        );
+    }
+}
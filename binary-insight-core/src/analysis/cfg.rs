@@ -0,0 +1,547 @@
+use super::disassembly::{self, InstructionInfo};
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// How an instruction affects control flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Normal,
+    Call(Option<u64>),
+    /// Unconditional jump; `None` when the target is computed at runtime.
+    Jump(Option<u64>),
+    /// Conditional branch that may fall through.
+    Branch(Option<u64>),
+    Return,
+    /// Traps and halts that never continue (`ud2`, `hlt`, `brk`).
+    Halt,
+}
+
+/// Classifies an instruction by mnemonic and reads its direct target, if any.
+pub fn classify(arch: &str, ins: &InstructionInfo) -> Flow {
+    let m = ins.mnemonic.as_str();
+    let target = direct_target(&ins.op_str);
+    match arch {
+        "aarch64" => match m {
+            "ret" | "retaa" | "retab" | "eret" => Flow::Return,
+            "b" => Flow::Jump(target),
+            "br" | "braa" | "brab" | "braaz" | "brabz" => Flow::Jump(None),
+            "bl" => Flow::Call(target),
+            "blr" | "blraa" | "blrab" | "blraaz" | "blrabz" => Flow::Call(None),
+            "cbz" | "cbnz" | "tbz" | "tbnz" => Flow::Branch(target),
+            "brk" | "hlt" | "udf" => Flow::Halt,
+            _ if m.starts_with("b.") => Flow::Branch(target),
+            _ => Flow::Normal,
+        },
+        "arm" | "thumb" => classify_arm(ins, target),
+        "riscv32" | "riscv64" => classify_riscv(ins),
+        _ if arch.starts_with("ppc") => match m {
+            "blr" => Flow::Return,
+            "b" | "ba" => Flow::Jump(target),
+            "bl" | "bla" => Flow::Call(target),
+            "bctr" => Flow::Jump(None),
+            "bctrl" | "blrl" => Flow::Call(None),
+            "trap" => Flow::Halt,
+            // Conditional returns and jumps through the count register.
+            _ if m.starts_with('b') && (m.ends_with("lr") || m.ends_with("ctr")) => {
+                Flow::Branch(None)
+            }
+            _ if m.starts_with('b') => Flow::Branch(target),
+            _ => Flow::Normal,
+        },
+        _ if arch.starts_with("sparc") => {
+            // `be,a` annuls the delay slot; `bne,pt` adds a prediction hint.
+            match m.split(',').next().unwrap_or(m) {
+                "ret" | "retl" | "retry" | "done" => Flow::Return,
+                "ba" | "b" => Flow::Jump(target),
+                "bn" => Flow::Normal,
+                "call" => Flow::Call(target),
+                "jmp" | "jmpl" => Flow::Jump(None),
+                "illtrap" | "unimp" => Flow::Halt,
+                m if m.starts_with('b') || m.starts_with("fb") || m.starts_with("cb") => {
+                    Flow::Branch(target)
+                }
+                _ => Flow::Normal,
+            }
+        }
+        _ if arch.starts_with("mips") => match m {
+            "jr" | "jr.hb" if ins.op_str == "$ra" => Flow::Return,
+            "eret" | "deret" => Flow::Return,
+            "jr" | "jr.hb" => Flow::Jump(None),
+            "j" | "b" => Flow::Jump(target),
+            "jal" | "bal" | "jalx" | "bltzal" | "bgezal" => Flow::Call(target),
+            "jalr" | "jalr.hb" => Flow::Call(None),
+            "break" | "sdbbp" => Flow::Halt,
+            _ if m.starts_with('b') => Flow::Branch(target),
+            _ => Flow::Normal,
+        },
+        _ => match m {
+            "ret" | "retf" | "retn" | "iret" | "iretd" | "iretq" => Flow::Return,
+            "jmp" | "ljmp" => Flow::Jump(target),
+            "call" | "lcall" => Flow::Call(target),
+            "loop" | "loope" | "loopne" => Flow::Branch(target),
+            "hlt" | "ud2" | "int3" => Flow::Halt,
+            _ if m.starts_with('j') => Flow::Branch(target),
+            _ => Flow::Normal,
+        },
+    }
+}
+
+/// ARM condition code suffixes (`beq`, `blne`, `bxlt`).
+const ARM_CONDITIONS: &[&str] = &[
+    "eq", "ne", "cs", "hs", "cc", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le",
+];
+
+fn classify_arm(ins: &InstructionInfo, target: Option<u64>) -> Flow {
+    // Thumb-2 width qualifiers (`b.w`, `bne.n`) do not change the meaning.
+    let m = ins.mnemonic.trim_end_matches(".w").trim_end_matches(".n");
+    let ops = ins.op_str.as_str();
+    let writes_pc = ops.starts_with("pc,") || ops.contains("pc}");
+    match m {
+        "bx" if ops == "lr" => Flow::Return,
+        "bx" => Flow::Jump(None),
+        "b" => Flow::Jump(target),
+        "bl" | "blx" => Flow::Call(target),
+        "cbz" | "cbnz" => Flow::Branch(target),
+        "udf" | "bkpt" => Flow::Halt,
+        "mov" if ops == "pc, lr" => Flow::Return,
+        _ if writes_pc && (m.starts_with("pop") || m.starts_with("ldm")) => Flow::Return,
+        _ if writes_pc => Flow::Jump(None),
+        _ if m.len() == 3 && m.starts_with('b') && ARM_CONDITIONS.contains(&&m[1..]) => {
+            Flow::Branch(target)
+        }
+        _ if m.len() == 4 && m.starts_with("bl") && ARM_CONDITIONS.contains(&&m[2..]) => {
+            Flow::Call(target)
+        }
+        _ => Flow::Normal,
+    }
+}
+
+/// Capstone prints RISC-V branch targets as offsets from the instruction.
+fn classify_riscv(ins: &InstructionInfo) -> Flow {
+    let target = ins.op_str.rsplit(", ").next().and_then(|last| {
+        let (negative, abs) = match last.strip_prefix('-') {
+            Some(abs) => (true, abs),
+            None => (false, last),
+        };
+        let offset = match abs.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+            None => abs.parse().ok()?,
+        };
+        Some(match negative {
+            true => ins.address.wrapping_sub(offset),
+            false => ins.address.wrapping_add(offset),
+        })
+    });
+    let ops = ins.op_str.as_str();
+    match ins.mnemonic.trim_start_matches("c.") {
+        "ret" | "mret" | "sret" | "uret" => Flow::Return,
+        "jr" if ops == "ra" => Flow::Return,
+        "jr" => Flow::Jump(None),
+        "j" => Flow::Jump(target),
+        // `jal rd, off` with rd other than ra is a rare out-of-line link.
+        "jal" => Flow::Call(target),
+        "jalr" => Flow::Call(None),
+        "ebreak" | "unimp" => Flow::Halt,
+        m if m.starts_with('b') => Flow::Branch(target),
+        _ => Flow::Normal,
+    }
+}
+
+/// The last operand when it is an immediate address (`0x401000`, `#0x401000`).
+fn direct_target(op_str: &str) -> Option<u64> {
+    let last = op_str.rsplit(',').next()?.trim().trim_start_matches('#');
+    u64::from_str_radix(last.strip_prefix("0x")?, 16).ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    /// Ends because the next instruction starts another block.
+    Fallthrough,
+    Jump,
+    Conditional,
+    /// Ends in a jump through a register or memory operand.
+    Indirect,
+    Return,
+    Halt,
+    /// Decoding stopped (end of function bytes or invalid instruction).
+    Truncated,
+}
+
+impl BlockKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Fallthrough => "fallthrough",
+            BlockKind::Jump => "jump",
+            BlockKind::Conditional => "conditional",
+            BlockKind::Indirect => "indirect",
+            BlockKind::Return => "return",
+            BlockKind::Halt => "halt",
+            BlockKind::Truncated => "truncated",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Jump,
+    /// Taken side of a conditional branch.
+    Taken,
+}
+
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+    pub start: u64,
+    /// Address one past the last instruction.
+    pub end: u64,
+    pub kind: BlockKind,
+    pub instructions: Vec<InstructionInfo>,
+    /// Direct call targets made from this block.
+    pub calls: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: u64,
+    pub to: u64,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+    pub entry: u64,
+    /// Blocks in address order.
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+    /// Jump targets outside the decoded range (tail calls, thunks).
+    pub external_targets: Vec<u64>,
+}
+
+impl ControlFlowGraph {
+    pub fn block_at(&self, addr: u64) -> Option<&BasicBlock> {
+        self.blocks.iter().find(|b| b.start == addr)
+    }
+
+    pub fn successors(&self, addr: u64) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |e| e.from == addr)
+    }
+
+    /// Renders the graph in Graphviz DOT, one record node per block.
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph \"{}\" {{", escape(name));
+        let _ = writeln!(out, "  node [shape=box fontname=\"monospace\"];");
+        for block in &self.blocks {
+            let mut label = format!("0x{:x}:\\l", block.start);
+            for ins in &block.instructions {
+                label.push_str(&escape(&format!("  {} {}", ins.mnemonic, ins.op_str)));
+                label.push_str("\\l");
+            }
+            let _ = writeln!(out, "  \"0x{:x}\" [label=\"{}\"];", block.start, label);
+        }
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Fallthrough => "color=gray",
+                EdgeKind::Jump => "color=blue",
+                EdgeKind::Taken => "color=darkgreen",
+            };
+            let _ = writeln!(
+                out,
+                "  \"0x{:x}\" -> \"0x{:x}\" [{}];",
+                edge.from, edge.to, style
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Builds the CFG of the function starting at `entry`, where `code` holds the
+/// function's bytes (or an upper bound of them) mapped at `entry`. Only blocks
+/// reachable from the entry are included.
+pub fn build_cfg(arch: &str, code: &[u8], entry: u64) -> Result<ControlFlowGraph> {
+    let decoded = disassembly::disassemble(arch, code, entry, usize::MAX)?;
+    if decoded.is_empty() {
+        bail!("No instructions decoded at 0x{:x}", entry);
+    }
+    let end = entry + code.len() as u64;
+    let mut flows: Vec<Flow> = decoded.iter().map(|ins| classify(arch, ins)).collect();
+    // The delay slot runs before the branch lands, so the block ends after it.
+    if disassembly::has_delay_slots(arch) {
+        for i in 0..flows.len().saturating_sub(1) {
+            if !matches!(flows[i], Flow::Normal | Flow::Halt) && flows[i + 1] == Flow::Normal {
+                flows[i + 1] = flows[i];
+                flows[i] = Flow::Normal;
+            }
+        }
+    }
+    let insns: BTreeMap<u64, (InstructionInfo, Flow)> = decoded
+        .into_iter()
+        .zip(flows)
+        .map(|(ins, flow)| (ins.address, (ins, flow)))
+        .collect();
+    let in_range = |addr: u64| addr >= entry && addr < end && insns.contains_key(&addr);
+
+    // Walk reachable instructions, recording block leaders along the way.
+    let mut leaders = BTreeSet::from([entry]);
+    let mut reachable = BTreeSet::new();
+    let mut external = BTreeSet::new();
+    let mut work = vec![entry];
+    while let Some(mut addr) = work.pop() {
+        while let Some((ins, flow)) = insns.get(&addr) {
+            if !reachable.insert(addr) {
+                break;
+            }
+            let next = addr + ins.size as u64;
+            match *flow {
+                Flow::Jump(Some(t)) | Flow::Branch(Some(t)) => {
+                    if in_range(t) {
+                        leaders.insert(t);
+                        work.push(t);
+                    } else {
+                        external.insert(t);
+                    }
+                }
+                _ => {}
+            }
+            match flow {
+                Flow::Branch(_) => {
+                    leaders.insert(next);
+                }
+                Flow::Jump(_) | Flow::Return | Flow::Halt => break,
+                _ => {}
+            }
+            addr = next;
+        }
+    }
+
+    let mut blocks: Vec<BasicBlock> = Vec::new();
+    let mut edges = Vec::new();
+    for &addr in &reachable {
+        let (ins, flow) = &insns[&addr];
+        let continues = blocks
+            .last()
+            .is_some_and(|b| b.end == addr && b.kind == BlockKind::Truncated)
+            && !leaders.contains(&addr);
+        if !continues {
+            blocks.push(BasicBlock {
+                start: addr,
+                end: addr,
+                kind: BlockKind::Truncated,
+                instructions: Vec::new(),
+                calls: Vec::new(),
+            });
+        }
+        let block = blocks.last_mut().expect("block pushed above");
+        block.instructions.push(ins.clone());
+        block.end = addr + ins.size as u64;
+        let next = block.end;
+        let start = block.start;
+
+        match *flow {
+            Flow::Normal => {}
+            Flow::Call(target) => block.calls.extend(target),
+            Flow::Jump(target) => {
+                block.kind = match target {
+                    Some(_) => BlockKind::Jump,
+                    None => BlockKind::Indirect,
+                };
+                if let Some(t) = target.filter(|&t| in_range(t)) {
+                    edges.push(Edge {
+                        from: start,
+                        to: t,
+                        kind: EdgeKind::Jump,
+                    });
+                }
+                continue;
+            }
+            Flow::Branch(target) => {
+                block.kind = BlockKind::Conditional;
+                if let Some(t) = target.filter(|&t| in_range(t)) {
+                    edges.push(Edge {
+                        from: start,
+                        to: t,
+                        kind: EdgeKind::Taken,
+                    });
+                }
+                if reachable.contains(&next) {
+                    edges.push(Edge {
+                        from: start,
+                        to: next,
+                        kind: EdgeKind::Fallthrough,
+                    });
+                }
+                continue;
+            }
+            Flow::Return => {
+                block.kind = BlockKind::Return;
+                continue;
+            }
+            Flow::Halt => {
+                block.kind = BlockKind::Halt;
+                continue;
+            }
+        }
+        if leaders.contains(&next) && reachable.contains(&next) {
+            block.kind = BlockKind::Fallthrough;
+            edges.push(Edge {
+                from: start,
+                to: next,
+                kind: EdgeKind::Fallthrough,
+            });
+        }
+    }
+
+    Ok(ControlFlowGraph {
+        entry,
+        blocks,
+        edges,
+        external_targets: external.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direct_target() {
+        assert_eq!(direct_target("0x401000"), Some(0x401000));
+        assert_eq!(direct_target("w0, #3, #0x1010"), Some(0x1010));
+        assert_eq!(direct_target("rax"), None);
+        assert_eq!(direct_target("qword ptr [rip + 0x10]"), None);
+    }
+
+    #[test]
+    fn test_build_cfg_diamond() {
+        // 0x1000: test edi, edi
+        // 0x1002: je 0x1009
+        // 0x1004: mov eax, 1
+        // 0x1009: ret       (both paths end here)
+        let code = [0x85, 0xff, 0x74, 0x05, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3];
+        let cfg = build_cfg("x86_64", &code, 0x1000).expect("cfg failed");
+        let starts: Vec<u64> = cfg.blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![0x1000, 0x1004, 0x1009]);
+        assert_eq!(cfg.blocks[0].kind, BlockKind::Conditional);
+        assert_eq!(cfg.blocks[1].kind, BlockKind::Fallthrough);
+        assert_eq!(cfg.blocks[2].kind, BlockKind::Return);
+        assert_eq!(cfg.edges.len(), 3);
+        assert!(cfg.edges.contains(&Edge {
+            from: 0x1000,
+            to: 0x1009,
+            kind: EdgeKind::Taken
+        }));
+        assert!(cfg.to_dot("f").contains("\"0x1004\" -> \"0x1009\""));
+    }
+
+    #[test]
+    fn test_unreachable_bytes_skipped() {
+        // jmp 0x1003; int3; ret
+        let code = [0xeb, 0x01, 0xcc, 0xc3];
+        let cfg = build_cfg("x86_64", &code, 0x1000).expect("cfg failed");
+        assert_eq!(cfg.blocks.len(), 2);
+        assert_eq!(cfg.blocks[1].start, 0x1003);
+    }
+
+    #[test]
+    fn test_thumb_cfg() {
+        // 0x1000: cmp r0, #0
+        // 0x1002: beq 0x1008
+        // 0x1004: movs r0, #1
+        // 0x1006: nop
+        // 0x1008: bx lr
+        let code = [0x00, 0x28, 0x01, 0xd0, 0x01, 0x20, 0x00, 0xbf, 0x70, 0x47];
+        let cfg = build_cfg("thumb", &code, 0x1000).expect("cfg failed");
+        let kinds: Vec<(u64, BlockKind)> = cfg.blocks.iter().map(|b| (b.start, b.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0x1000, BlockKind::Conditional),
+                (0x1004, BlockKind::Fallthrough),
+                (0x1008, BlockKind::Return),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mips_delay_slot() {
+        // 0x1000: beqz $a0, 0x1010
+        // 0x1004: nop               (delay slot)
+        // 0x1008: jr $ra
+        // 0x100c: move $v0, $zero   (delay slot)
+        // 0x1010: jr $ra
+        // 0x1014: nop
+        let code = [
+            0x10, 0x80, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x03, 0xe0, 0x00, 0x08, 0x00, 0x00,
+            0x10, 0x25, 0x03, 0xe0, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let cfg = build_cfg("mips", &code, 0x1000).expect("cfg failed");
+        let blocks: Vec<(u64, u64, BlockKind)> = cfg
+            .blocks
+            .iter()
+            .map(|b| (b.start, b.end, b.kind))
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                (0x1000, 0x1008, BlockKind::Conditional),
+                (0x1008, 0x1010, BlockKind::Return),
+                (0x1010, 0x1018, BlockKind::Return),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_riscv_relative_targets() {
+        // 0x1000: beqz a0, 0x100a
+        // 0x1004: jal  0x1008
+        // 0x1008: c.j  0x1000
+        // 0x100a: ret
+        let code = [
+            0x63, 0x05, 0x05, 0x00, 0xef, 0x00, 0x40, 0x00, 0xe5, 0xbf, 0x67, 0x80, 0x00, 0x00,
+        ];
+        let cfg = build_cfg("riscv64", &code, 0x1000).expect("cfg failed");
+        let kinds: Vec<(u64, BlockKind)> = cfg.blocks.iter().map(|b| (b.start, b.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0x1000, BlockKind::Conditional),
+                (0x1004, BlockKind::Jump),
+                (0x100a, BlockKind::Return),
+            ]
+        );
+        assert_eq!(cfg.blocks[1].calls, vec![0x1008]);
+    }
+
+    #[test]
+    fn test_ppc_and_sparc_flow() {
+        let ins = |mnemonic: &str, op_str: &str| InstructionInfo {
+            address: 0x1000,
+            size: 4,
+            mnemonic: mnemonic.to_string(),
+            op_str: op_str.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            classify("ppc", &ins("bl", "0x1008")),
+            Flow::Call(Some(0x1008))
+        );
+        assert_eq!(
+            classify("ppc64", &ins("beq", "cr1, 0x1010")),
+            Flow::Branch(Some(0x1010))
+        );
+        assert_eq!(classify("ppc", &ins("bnelr", "")), Flow::Branch(None));
+        assert_eq!(classify("ppc", &ins("blr", "")), Flow::Return);
+        assert_eq!(
+            classify("sparc", &ins("be,a", "0x102c")),
+            Flow::Branch(Some(0x102c))
+        );
+        assert_eq!(classify("sparc", &ins("call", "%g1")), Flow::Call(None));
+        assert_eq!(classify("sparc64", &ins("retl", "")), Flow::Return);
+    }
+}
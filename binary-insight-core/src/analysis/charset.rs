@@ -0,0 +1,339 @@
+use encoding_rs::{Encoding, GBK, SHIFT_JIS, WINDOWS_1251};
+
+/// Shortest string reported, in characters.
+const MIN_CHARS: usize = 4;
+/// Script letters a legacy code page decode needs before it is trusted.
+const MIN_LEGACY_LETTERS: usize = 4;
+/// CP1251 decodes every byte, so it needs more letters than the others.
+const MIN_CP1251_LETTERS: usize = 6;
+/// ASCII punctuation accepted inside legacy code page strings; the rest
+/// mostly shows up when machine code happens to decode.
+const LEGACY_PUNCTUATION: &str = " .,:;!?()'\"-/%";
+/// Non-ASCII punctuation accepted alongside legacy code page letters.
+const LEGACY_SYMBOLS: &str = "–—‘’“”«»№";
+
+/// Names accepted by [`Charset::from_name`].
+pub const CHARSETS: &[&str] = &["utf8", "sjis", "cp1251", "gbk"];
+
+/// Encodings tried for non-ASCII strings, in the order ambiguous byte runs
+/// are attributed to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Charset {
+    Utf8,
+    ShiftJis,
+    Cp1251,
+    Gbk,
+}
+
+impl Charset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Charset::Utf8 => "utf8",
+            Charset::ShiftJis => "sjis",
+            Charset::Cp1251 => "cp1251",
+            Charset::Gbk => "gbk",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "utf8" => Some(Charset::Utf8),
+            "sjis" => Some(Charset::ShiftJis),
+            "cp1251" => Some(Charset::Cp1251),
+            "gbk" => Some(Charset::Gbk),
+            _ => None,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<String> {
+        let encoding: &'static Encoding = match self {
+            Charset::Utf8 => return std::str::from_utf8(bytes).ok().map(str::to_string),
+            Charset::ShiftJis => SHIFT_JIS,
+            Charset::Cp1251 => WINDOWS_1251,
+            Charset::Gbk => GBK,
+        };
+        encoding
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(|s| s.into_owned())
+    }
+
+    /// Whether `text`, decoded from `bytes`, reads like this charset's
+    /// languages rather than noise that happens to decode.
+    fn plausible(&self, bytes: &[u8], text: &str) -> bool {
+        if text.chars().count() < MIN_CHARS || !text.chars().all(is_text_char) {
+            return false;
+        }
+        let letters = || text.chars().filter(|c| !c.is_ascii() && c.is_alphabetic());
+        // Machine code that happens to decode mixes stray ASCII into the
+        // letters; real text keeps scripts apart.
+        let chars: Vec<char> = text.chars().collect();
+        let foreign = |c: char| script(c).is_some();
+        let mixed = chars.windows(2).any(|w| {
+            (foreign(w[0]) && w[1].is_ascii_alphanumeric())
+                || (w[0].is_ascii_alphanumeric() && foreign(w[1]))
+        });
+        if mixed {
+            return false;
+        }
+        if *self == Charset::Utf8 {
+            return letters().all(|c| foreign(c) || is_latin(c))
+                && letters().filter(|&c| foreign(c)).count() >= 2;
+        }
+
+        let symbol_ok = |c: char| {
+            c.is_alphabetic()
+                || if c.is_ascii() {
+                    c.is_ascii_digit() || LEGACY_PUNCTUATION.contains(c)
+                } else {
+                    matches!(c as u32, 0x3000..=0x303f | 0xff01..=0xff5e)
+                        || LEGACY_SYMBOLS.contains(c)
+                }
+        };
+        if !chars.iter().all(|&c| symbol_ok(c)) {
+            return false;
+        }
+        match self {
+            Charset::Utf8 => unreachable!("handled above"),
+            Charset::ShiftJis => {
+                letters().count() >= MIN_LEGACY_LETTERS
+                    && text.chars().filter(|c| !c.is_ascii()).all(is_japanese)
+                    && text.chars().any(is_kana)
+            }
+            Charset::Cp1251 => {
+                let words: Vec<&str> = text
+                    .split(|c: char| !c.is_alphabetic() || c.is_ascii())
+                    .filter(|w| !w.is_empty())
+                    .collect();
+                // Letters only one of the two alphabets has.
+                let russian = letters().any(|c| "ыэъЫЭЪ".contains(c));
+                let ukrainian = letters().any(|c| "іїєґІЇЄҐ".contains(c));
+                words.iter().all(|w| russian_word(w))
+                    && words.iter().any(|w| w.chars().count() >= MIN_LEGACY_LETTERS)
+                    && letters().count() >= MIN_CP1251_LETTERS
+                    // `0xff` and `0xe9` are common opcode bytes, rare letters.
+                    && letters().filter(|c| "яй".contains(*c)).count() * 4 <= letters().count()
+                    && !(russian && ukrainian)
+            }
+            // Random byte pairs decode as GBK almost always, so only accept
+            // GB2312 punctuation and level-1 (common) hanzi.
+            Charset::Gbk => {
+                let mut pairs = bytes.iter().filter(|&&b| b >= 0x80).copied();
+                while let Some(lead) = pairs.next() {
+                    let common = matches!(lead, 0xa1..=0xa3 | 0xb0..=0xd7);
+                    if !common || !matches!(pairs.next(), Some(0xa1..=0xfe)) {
+                        return false;
+                    }
+                }
+                letters().count() >= MIN_LEGACY_LETTERS
+            }
+        }
+    }
+}
+
+/// A string in a non-Latin script, with the charset it decoded from.
+#[derive(Debug, Clone)]
+pub struct CharsetString {
+    pub value: String,
+    pub offset: u64,
+    pub charset: Charset,
+    /// Best guess from the scripts used, e.g. "Japanese" or "Russian".
+    pub language: &'static str,
+}
+
+/// Finds runs of printable ASCII and high bytes that decode cleanly in one of
+/// `charsets` and contain non-ASCII letters. Pure ASCII runs are left to
+/// [`super::extract_strings`].
+pub fn extract(data: &[u8], charsets: &[Charset]) -> Vec<CharsetString> {
+    let mut charsets = charsets.to_vec();
+    charsets.sort();
+    charsets.dedup();
+
+    let mut found = Vec::new();
+    let mut start = 0;
+    for (i, b) in data.iter().copied().chain([0]).enumerate() {
+        if (0x20..0x7f).contains(&b) || b >= 0x80 {
+            continue;
+        }
+        let run = &data[start..i];
+        if run.iter().any(|&b| b >= 0x80) {
+            let decoded = charsets.iter().find_map(|charset| {
+                let text = charset.decode(run)?;
+                charset.plausible(run, &text).then_some((*charset, text))
+            });
+            if let Some((charset, value)) = decoded {
+                found.push(CharsetString {
+                    language: guess_language(&value),
+                    value,
+                    offset: start as u64,
+                    charset,
+                });
+            }
+        }
+        start = i + 1;
+    }
+    found
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Kana,
+    Han,
+    Hangul,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Thai,
+    Devanagari,
+}
+
+fn script(c: char) -> Option<Script> {
+    Some(match c as u32 {
+        0x3040..=0x30ff | 0xff66..=0xff9f => Script::Kana,
+        0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xf900..=0xfaff => Script::Han,
+        0x1100..=0x11ff | 0x3130..=0x318f | 0xac00..=0xd7af => Script::Hangul,
+        0x0400..=0x04ff => Script::Cyrillic,
+        0x0370..=0x03ff => Script::Greek,
+        0x0600..=0x06ff => Script::Arabic,
+        0x0590..=0x05ff => Script::Hebrew,
+        0x0e00..=0x0e7f => Script::Thai,
+        0x0900..=0x097f => Script::Devanagari,
+        _ => return None,
+    })
+}
+
+/// A Cyrillic word spelled the way Russian (or Ukrainian) text is: optional
+/// leading capital, vowels, no letter tripled. `яя` is rejected outright as
+/// it is how `0xff 0xff` decodes.
+fn russian_word(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    let vowels = "аеёиоуыэюяіїєАЕЁИОУЫЭЮЯІЇЄ";
+    let letter = |c: char| matches!(c, 'А'..='я') || "ёЁіїєґІЇЄҐ".contains(c);
+    chars.iter().all(|&c| letter(c))
+        && !word.contains("яя")
+        && (chars.iter().skip(1).all(|c| c.is_lowercase())
+            || chars.iter().all(|c| c.is_uppercase()) && chars.len() <= 3)
+        && (chars.len() < 3 || chars.iter().any(|&c| vowels.contains(c)))
+        && !chars.windows(3).any(|w| w[0] == w[1] && w[1] == w[2])
+}
+
+fn is_latin(c: char) -> bool {
+    matches!(c as u32, 0x00c0..=0x024f | 0x1e00..=0x1eff)
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30ff)
+}
+
+/// Characters Shift-JIS text is made of; half-width katakana is excluded
+/// since stray single high bytes decode to it.
+fn is_japanese(c: char) -> bool {
+    is_kana(c) || matches!(c as u32, 0x3000..=0x303f | 0x4e00..=0x9fff | 0xff01..=0xff5e)
+}
+
+fn is_text_char(c: char) -> bool {
+    c == ' '
+        || c.is_ascii_graphic()
+        || (!c.is_ascii()
+            && (c.is_alphanumeric()
+                || matches!(c as u32, 0x2010..=0x206f | 0x3000..=0x303f | 0xff01..=0xff5e)
+                || "№«»".contains(c)))
+}
+
+/// Guesses the language from the dominant script. Kana implies Japanese even
+/// when most characters are kanji.
+pub fn guess_language(text: &str) -> &'static str {
+    let scripts: Vec<Script> = text.chars().filter_map(script).collect();
+    if scripts.contains(&Script::Kana) {
+        return "Japanese";
+    }
+    let dominant = [
+        Script::Han,
+        Script::Hangul,
+        Script::Cyrillic,
+        Script::Greek,
+        Script::Arabic,
+        Script::Hebrew,
+        Script::Thai,
+        Script::Devanagari,
+    ]
+    .into_iter()
+    .max_by_key(|s| scripts.iter().filter(|&x| x == s).count())
+    .filter(|s| scripts.contains(s));
+    match dominant {
+        Some(Script::Han) => "Chinese",
+        Some(Script::Hangul) => "Korean",
+        Some(Script::Cyrillic) if text.chars().any(|c| "іїєґІЇЄҐ".contains(c)) => {
+            "Ukrainian"
+        }
+        Some(Script::Cyrillic) => "Russian",
+        Some(Script::Greek) => "Greek",
+        Some(Script::Arabic) => "Arabic",
+        Some(Script::Hebrew) => "Hebrew",
+        Some(Script::Thai) => "Thai",
+        Some(Script::Devanagari) => "Hindi",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: &[Charset] = &[
+        Charset::Utf8,
+        Charset::ShiftJis,
+        Charset::Cp1251,
+        Charset::Gbk,
+    ];
+
+    fn tagged(data: &[u8]) -> Vec<(String, &'static str, &'static str)> {
+        extract(data, ALL)
+            .into_iter()
+            .map(|s| (s.value, s.charset.name(), s.language))
+            .collect()
+    }
+
+    #[test]
+    fn test_utf8_strings() {
+        let data = "\x00Привет мир\x00\x01连接服务器\x00plain ascii\x00".as_bytes();
+        assert_eq!(
+            tagged(data),
+            vec![
+                ("Привет мир".to_string(), "utf8", "Russian"),
+                ("连接服务器".to_string(), "utf8", "Chinese"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_legacy_code_pages() {
+        let mut data = vec![0u8];
+        // "ファイルを開く" in Shift-JIS
+        data.extend([
+            0x83, 0x74, 0x83, 0x40, 0x83, 0x43, 0x83, 0x8b, 0x82, 0xf0, 0x8a, 0x4a, 0x82, 0xad,
+        ]);
+        data.push(0);
+        // "Ошибка файла" in CP1251
+        data.extend([
+            0xce, 0xf8, 0xe8, 0xe1, 0xea, 0xe0, 0x20, 0xf4, 0xe0, 0xe9, 0xeb, 0xe0,
+        ]);
+        data.push(0);
+        // "文件打开失败" in GBK
+        data.extend([
+            0xce, 0xc4, 0xbc, 0xfe, 0xb4, 0xf2, 0xbf, 0xaa, 0xca, 0xa7, 0xb0, 0xdc,
+        ]);
+        data.push(0);
+
+        assert_eq!(
+            tagged(&data),
+            vec![
+                ("ファイルを開く".to_string(), "sjis", "Japanese"),
+                ("Ошибка файла".to_string(), "cp1251", "Russian"),
+                ("文件打开失败".to_string(), "gbk", "Chinese"),
+            ]
+        );
+        // Only the requested charsets are tried.
+        assert!(extract(&data, &[Charset::Utf8]).is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+use crate::binary::BinaryInfo;
+use std::collections::HashMap;
+
+/// API names per deprecated algorithm; `*` matches any run of characters.
+const WEAK_APIS: &[(&str, &[&str])] = &[
+    (
+        "MD5",
+        &[
+            "MD5",
+            "MD5_Init",
+            "MD5_Update",
+            "MD5_Final",
+            "EVP_md5",
+            "CC_MD5*",
+        ],
+    ),
+    (
+        "MD4",
+        &["MD4", "MD4_Init", "MD4_Update", "EVP_md4", "CC_MD4*"],
+    ),
+    (
+        "SHA-1",
+        &["SHA1", "SHA1_Init", "SHA1_Update", "EVP_sha1", "CC_SHA1*"],
+    ),
+    (
+        "RC4",
+        &["RC4", "RC4_set_key", "EVP_rc4*", "SystemFunction032"],
+    ),
+    ("RC2", &["RC2_set_key", "RC2_*crypt", "EVP_rc2_*"]),
+    (
+        "DES",
+        &["DES_set_key*", "DES_*crypt*", "EVP_des_*", "DES_crypt"],
+    ),
+];
+
+enum Pattern {
+    /// 32-bit words that must all occur somewhere, in either byte order.
+    /// Compilers often load these as immediates rather than keep a table.
+    Words(&'static [u32]),
+    /// A contiguous run of bytes.
+    Bytes(&'static [u8]),
+}
+
+/// Constants that give away an implementation of a primitive. Each entry is
+/// chosen to be unique to its algorithm; lone polynomials are weaker evidence
+/// but are how bitwise CRC loops show up.
+const CRYPTO_CONSTANTS: &[(&str, &str, Pattern)] = &[
+    (
+        "MD5",
+        "MD5 T-table",
+        Pattern::Words(&[0xd76aa478, 0xe8c7b756, 0x242070db]),
+    ),
+    (
+        "SHA-1",
+        "SHA-1 round constants",
+        Pattern::Words(&[0x8f1bbcdc, 0xca62c1d6]),
+    ),
+    (
+        "SHA-1",
+        "SHA-1 initial hash",
+        Pattern::Words(&[0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0]),
+    ),
+    (
+        "SHA-256",
+        "SHA-256 initial hash",
+        Pattern::Words(&[0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f]),
+    ),
+    (
+        "SHA-256",
+        "SHA-256 round constants",
+        Pattern::Words(&[0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5]),
+    ),
+    // Low halves of the 64-bit words; the high halves are SHA-256's.
+    (
+        "SHA-512",
+        "SHA-512 initial hash",
+        Pattern::Words(&[0xf3bcc908, 0x84caa73b, 0xfe94f82b, 0x5f1d36f1]),
+    ),
+    (
+        "AES",
+        "AES S-box",
+        Pattern::Bytes(&[
+            0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7,
+            0xab, 0x76,
+        ]),
+    ),
+    (
+        "AES",
+        "AES inverse S-box",
+        Pattern::Bytes(&[
+            0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3,
+            0xd7, 0xfb,
+        ]),
+    ),
+    (
+        "AES",
+        "AES T-table",
+        Pattern::Words(&[0xc66363a5, 0xf87c7c84, 0xee777799, 0xf67b7b8d]),
+    ),
+    (
+        "ChaCha20/Salsa20",
+        "\"expand 32-byte k\"",
+        Pattern::Bytes(b"expand 32-byte k"),
+    ),
+    (
+        "ChaCha20/Salsa20",
+        "\"expand 16-byte k\"",
+        Pattern::Bytes(b"expand 16-byte k"),
+    ),
+    (
+        "Blowfish",
+        "Blowfish P-array",
+        Pattern::Words(&[0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344]),
+    ),
+    (
+        "CRC-32",
+        "CRC-32 table",
+        Pattern::Words(&[0x77073096, 0xee0e612c, 0x990951ba]),
+    ),
+    ("CRC-32", "CRC-32 polynomial", Pattern::Words(&[0xedb88320])),
+    (
+        "CRC-32C",
+        "CRC-32C table",
+        Pattern::Words(&[0xf26b8303, 0xe13b70f7, 0x1350f3f4]),
+    ),
+    (
+        "CRC-32C",
+        "CRC-32C polynomial",
+        Pattern::Words(&[0x82f63b78]),
+    ),
+    (
+        "DES",
+        "DES SPtrans table",
+        Pattern::Bytes(&[
+            0x00, 0x08, 0x08, 0x02, 0x00, 0x00, 0x08, 0x00, 0x02, 0x00, 0x00, 0x02, 0x02, 0x08,
+            0x08, 0x02,
+        ]),
+    ),
+    (
+        "DES",
+        "DES S-box",
+        Pattern::Bytes(&[14, 4, 13, 1, 2, 15, 11, 8, 3, 10, 6, 12, 5, 9, 0, 7]),
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoEvidence {
+    /// Imported from a shared library.
+    Import,
+    /// Statically linked function found in the symbol table.
+    Symbol,
+    /// Algorithm constants embedded in the file.
+    Constant,
+}
+
+impl CryptoEvidence {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CryptoEvidence::Import => "import",
+            CryptoEvidence::Symbol => "symbol",
+            CryptoEvidence::Constant => "constant",
+        }
+    }
+}
+
+/// A primitive whose constants are embedded in the file.
+#[derive(Debug, Clone)]
+pub struct CryptoConstant {
+    pub algorithm: &'static str,
+    pub description: &'static str,
+    /// Every occurrence of a byte table; for word sets, the first occurrence
+    /// of each word. Sorted.
+    pub offsets: Vec<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WeakCryptoUsage {
+    pub algorithm: &'static str,
+    pub evidence: CryptoEvidence,
+    /// API name or constant description.
+    pub detail: String,
+    /// File offset of the first matched constant.
+    pub offset: Option<u64>,
+}
+
+/// Flags use of deprecated hashes and ciphers (MD5, MD4, SHA-1, RC4, RC2, DES)
+/// from imports, symbols and `info.crypto_constants`.
+pub fn find_weak_crypto(info: &BinaryInfo) -> Vec<WeakCryptoUsage> {
+    let mut usages = Vec::new();
+
+    let names = info
+        .imports
+        .iter()
+        .map(|i| (i.name.as_str(), CryptoEvidence::Import))
+        .chain(
+            info.symbols
+                .iter()
+                .map(|s| (s.name.as_str(), CryptoEvidence::Symbol)),
+        );
+    for (name, evidence) in names {
+        if let Some((algorithm, _)) = WEAK_APIS
+            .iter()
+            .find(|(_, apis)| apis.iter().any(|api| api_matches(api, name)))
+        {
+            usages.push(WeakCryptoUsage {
+                algorithm,
+                evidence,
+                detail: name.to_string(),
+                offset: None,
+            });
+        }
+    }
+
+    let weak = |algorithm: &str| WEAK_APIS.iter().any(|(a, _)| *a == algorithm);
+    for constant in info.crypto_constants.iter().filter(|c| weak(c.algorithm)) {
+        usages.push(WeakCryptoUsage {
+            algorithm: constant.algorithm,
+            evidence: CryptoEvidence::Constant,
+            detail: constant.description.to_string(),
+            offset: constant.offsets.first().copied(),
+        });
+    }
+
+    usages
+}
+
+fn api_matches(pattern: &str, name: &str) -> bool {
+    // Versioned ELF names (`MD5_Init@OPENSSL_3.0.0`) and MSVC decorations are ignored.
+    let name = name.split('@').next().unwrap_or(name);
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    if !pattern.contains('*') {
+        return rest.is_empty();
+    }
+    for part in parts.filter(|p| !p.is_empty()) {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Finds primitives whose well-known constants occur in `data` (AES, SHA-2,
+/// MD5, CRC-32, ChaCha20, ...).
+pub fn find_crypto_constants(data: &[u8]) -> Vec<CryptoConstant> {
+    let found = find_words(data);
+    let mut constants = Vec::new();
+    for (algorithm, description, pattern) in CRYPTO_CONSTANTS {
+        let offsets = match pattern {
+            Pattern::Words(words) => words
+                .iter()
+                .map(|w| found.get(w).copied())
+                .collect::<Option<Vec<u64>>>(),
+            Pattern::Bytes(table) => Some(
+                data.windows(table.len())
+                    .enumerate()
+                    .filter(|(_, w)| w == table)
+                    .map(|(pos, _)| pos as u64)
+                    .collect(),
+            ),
+        };
+        if let Some(mut offsets) = offsets.filter(|o| !o.is_empty()) {
+            offsets.sort_unstable();
+            constants.push(CryptoConstant {
+                algorithm,
+                description,
+                offsets,
+            });
+        }
+    }
+    constants
+}
+
+/// Returns the first offset of every tracked word present in `data`, read
+/// in either byte order.
+fn find_words(data: &[u8]) -> HashMap<u32, u64> {
+    let mut tracked: Vec<u32> = CRYPTO_CONSTANTS
+        .iter()
+        .filter_map(|(_, _, pattern)| match pattern {
+            Pattern::Words(words) => Some(*words),
+            Pattern::Bytes(_) => None,
+        })
+        .flatten()
+        .copied()
+        .collect();
+    tracked.sort_unstable();
+    let mut found = HashMap::new();
+    for (offset, window) in data.windows(4).enumerate() {
+        let word = u32::from_le_bytes([window[0], window[1], window[2], window[3]]);
+        for word in [word, word.swap_bytes()] {
+            if tracked.binary_search(&word).is_ok() {
+                found.entry(word).or_insert(offset as u64);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::ImportInfo;
+
+    #[test]
+    fn test_api_matches() {
+        assert!(api_matches("MD5_Init", "MD5_Init@OPENSSL_3.0.0"));
+        assert!(api_matches("EVP_des_*", "EVP_des_ede3_cbc"));
+        assert!(api_matches("DES_*crypt*", "DES_ecb_encrypt"));
+        assert!(!api_matches("MD5", "MD5_Init"));
+        assert!(!api_matches("RC4", "RC4X"));
+    }
+
+    #[test]
+    fn test_find_weak_crypto() {
+        let info = BinaryInfo {
+            imports: vec![
+                ImportInfo {
+                    name: "RC4_set_key".to_string(),
+                    library: None,
+                    slot: None,
+                },
+                ImportInfo {
+                    name: "SHA256_Init".to_string(),
+                    library: None,
+                    slot: None,
+                },
+            ],
+            ..Default::default()
+        };
+        let mut data = vec![0u8; 8];
+        for word in [0xd76aa478u32, 0xe8c7b756, 0x242070db] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        let info = BinaryInfo {
+            crypto_constants: find_crypto_constants(&data),
+            ..info
+        };
+
+        let usages = find_weak_crypto(&info);
+        assert_eq!(usages.len(), 2);
+        assert_eq!(usages[0].algorithm, "RC4");
+        assert_eq!(usages[0].evidence, CryptoEvidence::Import);
+        assert_eq!(usages[1].algorithm, "MD5");
+        assert_eq!(usages[1].offset, Some(8));
+    }
+
+    #[test]
+    fn test_find_crypto_constants() {
+        let mut data = b"..expand 32-byte k..".to_vec();
+        // SHA-256 initial hash, stored big-endian.
+        for word in [
+            0x6a09e667u32,
+            0xbb67ae85,
+            0x3c6ef372,
+            0xa54ff53a,
+            0x510e527f,
+        ] {
+            data.extend_from_slice(&word.to_be_bytes());
+        }
+        let constants = find_crypto_constants(&data);
+        let found: Vec<_> = constants
+            .iter()
+            .map(|c| (c.algorithm, c.offsets[0]))
+            .collect();
+        assert_eq!(found, vec![("SHA-256", 20), ("ChaCha20/Salsa20", 2)]);
+    }
+}
@@ -0,0 +1,305 @@
+use goblin::{elf, mach, pe, Object};
+use serde::Serialize;
+
+/// Where the names in the symbol view come from, from most to least
+/// complete.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum SymbolSource {
+    /// A full static symbol table (`.symtab`, COFF symbols, Mach-O locals).
+    SymbolTable,
+    /// Only the dynamic or exported symbols the loader needs survived.
+    DynamicOnly,
+    /// Recovered from the Go runtime's pclntab.
+    GoPclntab,
+    #[default]
+    None,
+}
+
+impl SymbolSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SymbolSource::SymbolTable => "symbol table",
+            SymbolSource::DynamicOnly => "dynamic/exported symbols only",
+            SymbolSource::GoPclntab => "Go pclntab",
+            SymbolSource::None => "none",
+        }
+    }
+}
+
+/// A CodeView record naming the PDB that holds a PE's debug information.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PdbReference {
+    pub path: String,
+    pub guid: String,
+    pub age: u32,
+}
+
+impl PdbReference {
+    /// The directory key symbol servers store the PDB under.
+    pub fn symbol_server_key(&self) -> String {
+        format!("{}{:X}", self.guid.replace('-', ""), self.age)
+    }
+}
+
+/// How much symbol and debug information the binary carries.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DebugInfo {
+    /// No full symbol table and no debug information in the file itself.
+    pub stripped: bool,
+    pub symbols: SymbolSource,
+    /// Debug formats present in the file ("DWARF", "STABS", ...).
+    pub formats: Vec<&'static str>,
+    pub pdb: Option<PdbReference>,
+    /// GNU build ID, the key for debuginfod and `/usr/lib/debug/.build-id`.
+    pub build_id: Option<String>,
+    /// `.gnu_debuglink` file holding the separated debug information.
+    pub debuglink: Option<String>,
+    /// Mach-O `LC_UUID`, which the matching dSYM bundle shares.
+    pub uuid: Option<String>,
+}
+
+pub fn analyze_debug_info(object: &Object, data: &[u8]) -> Option<DebugInfo> {
+    Some(match object {
+        Object::Elf(elf) => analyze_elf(elf, data),
+        Object::PE(pe) => analyze_pe(pe),
+        Object::Mach(mach::Mach::Binary(macho)) => analyze_mach(macho),
+        _ => return None,
+    })
+}
+
+fn add_format(formats: &mut Vec<&'static str>, format: &'static str) {
+    if !formats.contains(&format) {
+        formats.push(format);
+    }
+}
+
+/// Debug formats recognized by section name, shared by ELF and PE (MinGW
+/// keeps DWARF in `.debug_*` sections).
+fn section_format(name: &str) -> Option<&'static str> {
+    if name.starts_with(".zdebug_") {
+        Some("DWARF (compressed)")
+    } else if name.starts_with(".debug_") {
+        Some("DWARF")
+    } else if name == ".stab" {
+        Some("STABS")
+    } else if name == ".gnu_debugdata" {
+        Some("MiniDebugInfo")
+    } else if name == ".ctf" {
+        Some("CTF")
+    } else if name == ".BTF" {
+        Some("BTF")
+    } else {
+        None
+    }
+}
+
+fn analyze_elf(elf: &elf::Elf, data: &[u8]) -> DebugInfo {
+    let mut info = DebugInfo {
+        symbols: if !elf.syms.is_empty() {
+            SymbolSource::SymbolTable
+        } else if !elf.dynsyms.is_empty() {
+            SymbolSource::DynamicOnly
+        } else {
+            SymbolSource::None
+        },
+        ..Default::default()
+    };
+    for sh in &elf.section_headers {
+        let Some(name) = elf.shdr_strtab.get_at(sh.sh_name) else {
+            continue;
+        };
+        match section_format(name) {
+            Some("DWARF") if sh.sh_flags & elf::section_header::SHF_COMPRESSED as u64 != 0 => {
+                add_format(&mut info.formats, "DWARF (compressed)")
+            }
+            Some(format) => add_format(&mut info.formats, format),
+            None => {}
+        }
+        if name == ".gnu_debuglink" {
+            let start = sh.sh_offset as usize;
+            let contents = data.get(start..start.saturating_add(sh.sh_size as usize));
+            info.debuglink = contents
+                .and_then(|c| c.split(|&b| b == 0).next())
+                .map(|file| String::from_utf8_lossy(file).to_string());
+        }
+    }
+    // goblin repeats a note parse error forever, so stop at the first one.
+    let notes = elf.iter_note_headers(data).into_iter().flatten();
+    for note in notes.map_while(Result::ok) {
+        if note.name == "GNU" && note.n_type == elf::note::NT_GNU_BUILD_ID {
+            info.build_id = Some(hex::encode(note.desc));
+        }
+    }
+    info.stripped = info.symbols != SymbolSource::SymbolTable
+        && !info.formats.iter().any(|f| f.starts_with("DWARF"));
+    info
+}
+
+fn analyze_pe(pe: &pe::PE) -> DebugInfo {
+    let coff = &pe.header.coff_header;
+    let mut info = DebugInfo {
+        symbols: if coff.pointer_to_symbol_table != 0 && coff.number_of_symbol_table != 0 {
+            SymbolSource::SymbolTable
+        } else if !pe.exports.is_empty() {
+            SymbolSource::DynamicOnly
+        } else {
+            SymbolSource::None
+        },
+        ..Default::default()
+    };
+    for section in &pe.sections {
+        // Long MinGW names (`.debug_info`) live in the COFF string table.
+        let name = section
+            .real_name
+            .as_deref()
+            .or_else(|| section.name().ok())
+            .unwrap_or_default();
+        if let Some(format) = section_format(name) {
+            add_format(&mut info.formats, format);
+        }
+    }
+    if let Some(debug) = &pe.debug_data {
+        if let Some(cv) = &debug.codeview_pdb70_debug_info {
+            add_format(&mut info.formats, "CodeView (PDB 7.0)");
+            info.pdb = Some(PdbReference {
+                path: pdb_path(cv.filename),
+                guid: format_guid(&cv.signature),
+                age: cv.age,
+            });
+        } else if let Some(cv) = &debug.codeview_pdb20_debug_info {
+            add_format(&mut info.formats, "CodeView (PDB 2.0)");
+            info.pdb = Some(PdbReference {
+                path: pdb_path(cv.filename),
+                guid: format!("{:08X}", cv.signature),
+                age: cv.age,
+            });
+        }
+    }
+    // Release builds keep only a PDB reference; the debug data is elsewhere.
+    info.stripped = info.symbols != SymbolSource::SymbolTable
+        && !info.formats.iter().any(|f| f.starts_with("DWARF"));
+    info
+}
+
+fn analyze_mach(macho: &mach::MachO) -> DebugInfo {
+    use mach::symbols::{N_EXT, N_STAB};
+    let mut info = DebugInfo::default();
+    let mut locals = false;
+    let mut any = false;
+    for (_, nlist) in macho.symbols().filter_map(|s| s.ok()) {
+        any = true;
+        if nlist.n_type & N_STAB != 0 {
+            // The debug map pointing dsymutil at the object files.
+            add_format(&mut info.formats, "STABS debug map");
+        } else if nlist.n_type & N_EXT == 0 {
+            locals = true;
+        }
+    }
+    info.symbols = match (locals, any) {
+        (true, _) => SymbolSource::SymbolTable,
+        (false, true) => SymbolSource::DynamicOnly,
+        (false, false) => SymbolSource::None,
+    };
+    for segment in &macho.segments {
+        // dSYM companions carry DWARF in a segment of its own.
+        if segment.name().ok() == Some("__DWARF") {
+            add_format(&mut info.formats, "DWARF");
+        }
+    }
+    for command in &macho.load_commands {
+        if let mach::load_command::CommandVariant::Uuid(uuid) = &command.command {
+            info.uuid = Some(format_uuid(&uuid.uuid));
+        }
+    }
+    info.stripped = info.symbols != SymbolSource::SymbolTable && info.formats.is_empty();
+    info
+}
+
+fn pdb_path(filename: &[u8]) -> String {
+    let end = filename
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(filename.len());
+    String::from_utf8_lossy(&filename[..end]).to_string()
+}
+
+/// A GUID as stored in CodeView records: the first three fields are
+/// little-endian.
+fn format_guid(bytes: &[u8; 16]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex::encode_upper(&bytes[8..10]),
+        hex::encode_upper(&bytes[10..])
+    )
+}
+
+/// `LC_UUID` bytes are in display order.
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex::encode_upper(&bytes[..4]),
+        hex::encode_upper(&bytes[4..6]),
+        hex::encode_upper(&bytes[6..8]),
+        hex::encode_upper(&bytes[8..10]),
+        hex::encode_upper(&bytes[10..])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guid_formats() {
+        let bytes = [
+            0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let guid = format_guid(&bytes);
+        assert_eq!(guid, "00112233-4455-6677-8899-AABBCCDDEEFF");
+        assert_eq!(format_uuid(&bytes), "33221100-5544-7766-8899-AABBCCDDEEFF");
+        let pdb = PdbReference {
+            path: "app.pdb".to_string(),
+            guid,
+            age: 0x1a,
+        };
+        assert_eq!(
+            pdb.symbol_server_key(),
+            "00112233445566778899AABBCCDDEEFF1A"
+        );
+    }
+
+    /// A 64-bit ELF whose only program header is a PT_NOTE running past EOF.
+    fn elf_with_corrupt_note() -> Vec<u8> {
+        let mut data = vec![0u8; 0x100];
+        data[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        data[16] = 2; // ET_EXEC
+        data[18] = 62; // EM_X86_64
+        data[20] = 1;
+        data[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data[52] = 64;
+        data[54] = 56;
+        data[56] = 1; // e_phnum
+        data[64..68].copy_from_slice(&elf::program_header::PT_NOTE.to_le_bytes());
+        data[72..80].copy_from_slice(&0xf0u64.to_le_bytes()); // p_offset
+        data[96..104].copy_from_slice(&0x4e20u64.to_le_bytes()); // p_filesz
+        data
+    }
+
+    #[test]
+    fn test_corrupt_note_segment() {
+        let data = elf_with_corrupt_note();
+        let elf = elf::Elf::parse(&data).unwrap();
+        assert_eq!(analyze_elf(&elf, &data).build_id, None);
+    }
+
+    #[test]
+    fn test_section_formats() {
+        assert_eq!(section_format(".debug_info"), Some("DWARF"));
+        assert_eq!(section_format(".zdebug_line"), Some("DWARF (compressed)"));
+        assert_eq!(section_format(".text"), None);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::binary::{BinaryInfo, SectionInfo};
+
+/// Decoded strings shorter than this are discarded as noise.
+const MIN_DECODED_LEN: usize = 5;
+/// Minimum length for strings recovered with a brute-forced key.
+const MIN_BRUTE_LEN: usize = 8;
+/// Upper bound on RC4 key candidates tried per run.
+const MAX_RC4_KEYS: usize = 64;
+/// Ciphertext bytes decrypted after each candidate key.
+const RC4_WINDOW: usize = 256;
+
+/// Names accepted by [`preset`].
+pub const PRESETS: &[&str] = &["stackstrings", "xor", "rc4"];
+
+#[derive(Debug, Clone)]
+pub struct DecodedString {
+    pub value: String,
+    /// File offset of the encoded bytes (or of the first store instruction).
+    pub offset: u64,
+    /// Deobfuscator that produced the string, e.g. `xor(0x5a)`.
+    pub method: String,
+}
+
+/// A string-deobfuscation scheme. Implementations receive the raw file and
+/// the parsed layout and return whatever strings they can recover.
+pub trait Deobfuscator {
+    fn name(&self) -> &'static str;
+    fn decode(&self, data: &[u8], info: &BinaryInfo) -> Vec<DecodedString>;
+}
+
+/// Builds a preset deobfuscator by name. `key` overrides the key search of
+/// the `xor` and `rc4` presets.
+pub fn preset(name: &str, key: Option<&[u8]>) -> Option<Box<dyn Deobfuscator>> {
+    match name {
+        "stackstrings" => Some(Box::new(StackStrings)),
+        "xor" => Some(Box::new(XorStrings {
+            key: key.map(<[u8]>::to_vec),
+        })),
+        "rc4" => Some(Box::new(Rc4Strings {
+            key: key.map(<[u8]>::to_vec),
+        })),
+        _ => None,
+    }
+}
+
+/// Runs each deobfuscator and drops results that already appear as plain strings.
+pub fn run(
+    deobfuscators: &[Box<dyn Deobfuscator>],
+    data: &[u8],
+    info: &BinaryInfo,
+) -> Vec<DecodedString> {
+    let mut decoded: Vec<DecodedString> = Vec::new();
+    for deobfuscator in deobfuscators {
+        for s in deobfuscator.decode(data, info) {
+            if !info.strings.contains(&s.value) && !decoded.iter().any(|d| d.value == s.value) {
+                decoded.push(s);
+            }
+        }
+    }
+    decoded
+}
+
+fn is_code(section: &SectionInfo) -> bool {
+    section.name.contains("text") || section.name == "CODE"
+}
+
+/// Splits decoded bytes into printable runs of at least `MIN_DECODED_LEN`.
+fn printable_runs(bytes: &[u8]) -> Vec<(usize, String)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (i, &b) in bytes.iter().chain(std::iter::once(&0)).enumerate() {
+        if b.is_ascii_graphic() || b == b' ' {
+            continue;
+        }
+        if i - start >= MIN_DECODED_LEN {
+            runs.push((start, String::from_utf8_lossy(&bytes[start..i]).to_string()));
+        }
+        start = i + 1;
+    }
+    runs
+}
+
+fn mostly(value: &[u8], pred: impl Fn(u8) -> bool) -> bool {
+    value.iter().filter(|b| pred(**b) || **b == b' ').count() * 10 >= value.len() * 7
+}
+
+/// Brute-forced keys decode plenty of short junk; demand longer runs that are
+/// mostly letters and contain at least one lowercase word.
+fn looks_like_words(value: &[u8]) -> bool {
+    let has_word = value
+        .split(|b| !b.is_ascii_lowercase())
+        .any(|w| w.len() >= 4);
+    value.len() >= MIN_BRUTE_LEN && mostly(value, |b| b.is_ascii_alphabetic()) && has_word
+}
+
+/// Strings assembled on the stack one immediate store at a time (x86/x86_64).
+pub struct StackStrings;
+
+impl StackStrings {
+    /// Decodes `mov byte/word/dword [rbp|rsp + disp], imm` at the start of
+    /// `code`, returning (instruction length, displacement, stored bytes).
+    fn decode_store(code: &[u8]) -> Option<(usize, i64, Vec<u8>)> {
+        let (prefix, rest) = match code.first()? {
+            0x66 => (2usize, &code[1..]),
+            0x48 => (8, &code[1..]),
+            _ => (0, code),
+        };
+        let (imm_len, stored) = match (rest.first()?, prefix) {
+            (0xc6, 0) => (1, 1),
+            (0xc7, 2) => (2, 2),
+            (0xc7, 0) => (4, 4),
+            (0xc7, 8) => (4, 8),
+            _ => return None,
+        };
+        let modrm = *rest.get(1)?;
+        let (disp_len, sib) = match modrm {
+            0x45 => (1, 0),
+            0x85 => (4, 0),
+            0x44 if rest.get(2) == Some(&0x24) => (1, 1),
+            0x84 if rest.get(2) == Some(&0x24) => (4, 1),
+            _ => return None,
+        };
+        let disp_at = 2 + sib;
+        let disp_bytes = rest.get(disp_at..disp_at + disp_len)?;
+        let disp = match disp_len {
+            1 => disp_bytes[0] as i8 as i64,
+            _ => i32::from_le_bytes(disp_bytes.try_into().ok()?) as i64,
+        };
+        let imm = rest.get(disp_at + disp_len..disp_at + disp_len + imm_len)?;
+        let mut bytes = imm.to_vec();
+        bytes.resize(stored, 0);
+        let len = (code.len() - rest.len()) + disp_at + disp_len + imm_len;
+        Some((len, disp, bytes))
+    }
+
+    fn flush(stores: &mut Vec<(i64, Vec<u8>)>, offset: u64, out: &mut Vec<DecodedString>) {
+        if stores.len() >= 2 {
+            let min = stores.iter().map(|(d, _)| *d).min().unwrap_or(0);
+            let max = stores
+                .iter()
+                .map(|(d, b)| d + b.len() as i64)
+                .max()
+                .unwrap_or(min);
+            let mut buf = vec![0u8; (max - min) as usize];
+            for (disp, bytes) in stores.iter() {
+                let at = (disp - min) as usize;
+                buf[at..at + bytes.len()].copy_from_slice(bytes);
+            }
+            // UTF-16LE stack strings store a zero high byte after every character.
+            let wide = buf.len() >= 4 && buf.iter().skip(1).step_by(2).all(|&b| b == 0);
+            if wide {
+                buf = buf.iter().step_by(2).copied().collect();
+            }
+            for (_, value) in printable_runs(&buf) {
+                out.push(DecodedString {
+                    value,
+                    offset,
+                    method: "stackstrings".to_string(),
+                });
+            }
+        }
+        stores.clear();
+    }
+}
+
+impl Deobfuscator for StackStrings {
+    fn name(&self) -> &'static str {
+        "stackstrings"
+    }
+
+    fn decode(&self, data: &[u8], info: &BinaryInfo) -> Vec<DecodedString> {
+        let mut out = Vec::new();
+        if info.arch != "x86" && info.arch != "x86_64" {
+            return out;
+        }
+        for section in info.sections.iter().filter(|s| is_code(s)) {
+            let code = &data[section.file_range(data.len())];
+            let mut stores = Vec::new();
+            let mut run_start = 0;
+            let mut pos = 0;
+            while pos < code.len() {
+                match Self::decode_store(&code[pos..]) {
+                    Some((len, disp, bytes)) => {
+                        if stores.is_empty() {
+                            run_start = pos;
+                        }
+                        stores.push((disp, bytes));
+                        pos += len;
+                    }
+                    None => {
+                        if !stores.is_empty() {
+                            Self::flush(&mut stores, section.offset + run_start as u64, &mut out);
+                        }
+                        pos += 1;
+                    }
+                }
+            }
+            Self::flush(&mut stores, section.offset + run_start as u64, &mut out);
+        }
+        out
+    }
+}
+
+/// NUL-terminated strings XORed with a repeating key. Without a key, every
+/// single-byte key is tried.
+pub struct XorStrings {
+    pub key: Option<Vec<u8>>,
+}
+
+impl Deobfuscator for XorStrings {
+    fn name(&self) -> &'static str {
+        "xor"
+    }
+
+    fn decode(&self, data: &[u8], info: &BinaryInfo) -> Vec<DecodedString> {
+        let keys: Vec<Vec<u8>> = match &self.key {
+            Some(key) if !key.is_empty() => vec![key.clone()],
+            _ => (1..=255u8).map(|k| vec![k]).collect(),
+        };
+        let mut out = Vec::new();
+        for section in info.sections.iter().filter(|s| s.size > 0 && !is_code(s)) {
+            let bytes = &data[section.file_range(data.len())];
+            for key in &keys {
+                let decoded: Vec<u8> = bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| b ^ key[i % key.len()])
+                    .collect();
+                for (start, value) in printable_runs(&decoded) {
+                    // Both string boundaries must decode to NUL, which rules out
+                    // plain text (its terminator would decode to the key).
+                    let bounded = decoded.get(start + value.len()) == Some(&0)
+                        && (start == 0 || decoded[start - 1] == 0);
+                    // A raw NUL inside the run decodes to the key byte itself, which
+                    // means zero padding or tables rather than encoded text.
+                    let raw = &bytes[start..start + value.len()];
+                    let encoded = !raw.contains(&0);
+                    // Plain text or digits XORed with a small key (e.g. a case
+                    // flip) still decode to text.
+                    let brute_ok = self.key.is_some()
+                        || (looks_like_words(value.as_bytes())
+                            && !mostly(raw, |b| b.is_ascii_alphanumeric()));
+                    if bounded && encoded && brute_ok {
+                        out.push(DecodedString {
+                            value,
+                            offset: section.offset + start as u64,
+                            method: format!("xor(0x{})", hex::encode(key)),
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// RC4-encrypted blobs stored right after their key in a data section. Without
+/// a key, printable strings in data sections are tried as keys.
+pub struct Rc4Strings {
+    pub key: Option<Vec<u8>>,
+}
+
+impl Deobfuscator for Rc4Strings {
+    fn name(&self) -> &'static str {
+        "rc4"
+    }
+
+    fn decode(&self, data: &[u8], info: &BinaryInfo) -> Vec<DecodedString> {
+        let mut out = Vec::new();
+        for section in info.sections.iter().filter(|s| s.size > 0 && !is_code(s)) {
+            let bytes = &data[section.file_range(data.len())];
+            // (key, offset of the ciphertext that follows it)
+            let candidates: Vec<(Vec<u8>, usize)> = match &self.key {
+                Some(key) => (0..bytes.len())
+                    .step_by(4)
+                    .map(|pos| (key.clone(), pos))
+                    .collect(),
+                None => printable_runs(bytes)
+                    .into_iter()
+                    .filter(|(_, k)| k.len() <= 32)
+                    .take(MAX_RC4_KEYS)
+                    .map(|(start, k)| {
+                        let mut after = start + k.len();
+                        while bytes.get(after) == Some(&0) {
+                            after += 1;
+                        }
+                        (k.into_bytes(), after)
+                    })
+                    .collect(),
+            };
+            for (key, pos) in candidates {
+                let end = (pos + RC4_WINDOW).min(bytes.len());
+                if pos >= end {
+                    continue;
+                }
+                let plain = rc4(&key, &bytes[pos..end]);
+                if let Some((0, value)) = printable_runs(&plain).into_iter().next() {
+                    if plain.get(value.len()) == Some(&0) {
+                        out.push(DecodedString {
+                            value,
+                            offset: section.offset + pos as u64,
+                            method: format!("rc4({})", String::from_utf8_lossy(&key)),
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+pub fn rc4(key: &[u8], input: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    input
+        .iter()
+        .map(|b| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(s[i as usize]);
+            s.swap(i as usize, j as usize);
+            b ^ s[s[i as usize].wrapping_add(s[j as usize]) as usize]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_with(name: &str, size: usize, arch: &str) -> BinaryInfo {
+        BinaryInfo {
+            arch: arch.to_string(),
+            sections: vec![SectionInfo {
+                name: name.to_string(),
+                addr: 0x1000,
+                size: size as u64,
+                offset: 0,
+                hashes: None,
+                zero_fill: false,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stackstrings() {
+        // nop; mov byte [rbp-0x10+i], c for each character and the terminator; ret
+        let mut code = vec![0x90];
+        for (i, c) in b"admin".iter().enumerate() {
+            code.extend_from_slice(&[0xc6, 0x45, 0xf0 + i as u8, *c]);
+        }
+        code.extend_from_slice(&[0xc6, 0x45, 0xf5, 0x00, 0xc3]);
+        let info = info_with(".text", code.len(), "x86_64");
+        let found = StackStrings.decode(&code, &info);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, "admin");
+        assert_eq!(found[0].offset, 1);
+    }
+
+    #[test]
+    fn test_xor_single_byte() {
+        let mut data: Vec<u8> = b"http://evil.example\0".iter().map(|b| b ^ 0x5a).collect();
+        data.extend_from_slice(&[0x5a; 4]);
+        let mut info = info_with(".data", data.len(), "x86_64");
+        let found = XorStrings { key: None }.decode(&data, &info);
+        assert!(found
+            .iter()
+            .any(|s| s.value == "http://evil.example" && s.method == "xor(0x5a)"));
+
+        // A bogus section size is clamped to the file.
+        info.sections[0].size = u64::MAX;
+        let clamped = XorStrings { key: None }.decode(&data, &info);
+        assert_eq!(clamped.len(), found.len());
+    }
+
+    #[test]
+    fn test_rc4_key_in_data() {
+        assert_eq!(hex::encode(rc4(b"Key", b"Plaintext")), "bbf316e8d940af0ad3");
+        let mut data = b"secretkey\0\0\0".to_vec();
+        data.extend(rc4(b"secretkey", b"cmd.exe /c whoami\0"));
+        let info = info_with(".data", data.len(), "x86");
+        let found = Rc4Strings { key: None }.decode(&data, &info);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, "cmd.exe /c whoami");
+        assert_eq!(found[0].offset, 12);
+    }
+}
@@ -0,0 +1,382 @@
+//! Transitive shared-library dependencies, resolved the way the loader
+//! would: ELF `DT_NEEDED` through `DT_RPATH`/`DT_RUNPATH`, the ldconfig
+//! cache and the default directories; PE imports next to the executable
+//! and in the search paths; Mach-O dylibs with `@rpath`, `@loader_path`
+//! and `@executable_path` expanded.
+
+use anyhow::{Context, Result};
+use goblin::Object;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LD_CACHE_MAGIC: &[u8] = b"glibc-ld.so.cache1.1";
+
+/// Directories the glibc loader falls back to after the cache.
+const ELF_DEFAULT_DIRS: &[&str] = &["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+
+/// How a needed library was resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepStatus {
+    Resolved(PathBuf),
+    /// Resolved and already expanded earlier in the tree.
+    Repeated(PathBuf),
+    /// Windows API set (`api-ms-win-*`), mapped by the loader at run time.
+    ApiSet,
+    NotFound,
+}
+
+#[derive(Debug, Clone)]
+pub struct DepNode {
+    pub name: String,
+    pub status: DepStatus,
+    pub children: Vec<DepNode>,
+}
+
+impl DepNode {
+    /// Names of the libraries that could not be found, anywhere in the tree.
+    pub fn unresolved(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for child in &self.children {
+            if child.status == DepStatus::NotFound {
+                names.push(child.name.as_str());
+            }
+            names.extend(child.unresolved());
+        }
+        names
+    }
+}
+
+/// Where libraries are looked up.
+#[derive(Debug, Default)]
+pub struct Resolver {
+    /// Root that absolute library paths and the ldconfig cache are read
+    /// under, for cross-compiled or extracted firmware trees.
+    pub sysroot: Option<PathBuf>,
+    /// Extra directories, searched after the binary's own run paths.
+    pub search_paths: Vec<PathBuf>,
+    /// `(soname, path)` entries of the ldconfig cache, in cache order.
+    pub ld_cache: Vec<(String, PathBuf)>,
+}
+
+/// What a binary needs and where it asks for it to be looked up.
+struct Needs {
+    libraries: Vec<String>,
+    /// `DT_RUNPATH`/`DT_RPATH` or `LC_RPATH` entries, unexpanded.
+    rpaths: Vec<String>,
+    /// ELF class and machine, which candidate libraries must share.
+    elf_target: Option<(u8, u16)>,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Elf,
+    Pe,
+    MachO,
+}
+
+impl Resolver {
+    /// A resolver reading the ldconfig cache under `sysroot` (or `/`) when
+    /// `ldconfig` is set.
+    pub fn new(sysroot: Option<PathBuf>, search_paths: Vec<PathBuf>, ldconfig: bool) -> Self {
+        let mut resolver = Resolver {
+            sysroot,
+            search_paths,
+            ld_cache: Vec::new(),
+        };
+        if ldconfig {
+            let cache = resolver.rooted(Path::new("/etc/ld.so.cache"));
+            if let Ok(data) = fs::read(cache) {
+                resolver.ld_cache = parse_ld_cache(&data);
+            }
+        }
+        resolver
+    }
+
+    /// Builds the dependency tree of the binary at `path`.
+    pub fn resolve(&self, path: &Path) -> Result<DepNode> {
+        let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let needs = needs(&data)?;
+        let mut seen = HashSet::from([canonical(path)]);
+        let children = self.children(path, path, &needs, &mut seen);
+        Ok(DepNode {
+            name: path.display().to_string(),
+            status: DepStatus::Resolved(path.to_path_buf()),
+            children,
+        })
+    }
+
+    fn children(
+        &self,
+        path: &Path,
+        executable: &Path,
+        needs: &Needs,
+        seen: &mut HashSet<PathBuf>,
+    ) -> Vec<DepNode> {
+        let mut children = Vec::new();
+        for name in &needs.libraries {
+            let found = if needs.kind == Kind::Pe && is_api_set(name) {
+                Err(DepStatus::ApiSet)
+            } else {
+                self.find(name, path, executable, needs)
+                    .ok_or(DepStatus::NotFound)
+            };
+            let node = match found {
+                // The same library may be reached through differently
+                // spelled paths (`$ORIGIN/..`, symlinks).
+                Ok((lib, _)) if seen.contains(&canonical(&lib)) => DepNode {
+                    name: name.clone(),
+                    status: DepStatus::Repeated(lib),
+                    children: Vec::new(),
+                },
+                Ok((lib, lib_needs)) => {
+                    seen.insert(canonical(&lib));
+                    let grandchildren = self.children(&lib, executable, &lib_needs, seen);
+                    DepNode {
+                        name: name.clone(),
+                        status: DepStatus::Resolved(lib),
+                        children: grandchildren,
+                    }
+                }
+                Err(status) => DepNode {
+                    name: name.clone(),
+                    status,
+                    children: Vec::new(),
+                },
+            };
+            children.push(node);
+        }
+        children
+    }
+
+    /// The first candidate for `name` that parses as a compatible binary.
+    fn find(
+        &self,
+        name: &str,
+        parent: &Path,
+        executable: &Path,
+        needs: &Needs,
+    ) -> Option<(PathBuf, Needs)> {
+        self.candidates(name, parent, executable, needs)
+            .into_iter()
+            .find_map(|candidate| {
+                let data = fs::read(&candidate).ok()?;
+                let lib_needs = self::needs(&data).ok()?;
+                let compatible = lib_needs.kind == needs.kind
+                    && (needs.elf_target.is_none() || lib_needs.elf_target == needs.elf_target);
+                compatible.then_some((candidate, lib_needs))
+            })
+    }
+
+    fn candidates(
+        &self,
+        name: &str,
+        parent: &Path,
+        executable: &Path,
+        needs: &Needs,
+    ) -> Vec<PathBuf> {
+        let parent_dir = parent.parent().unwrap_or(Path::new("."));
+        let mut candidates = Vec::new();
+        match needs.kind {
+            Kind::Elf => {
+                if name.contains('/') {
+                    return vec![self.rooted(Path::new(name))];
+                }
+                for rpath in &needs.rpaths {
+                    let dir = rpath.replace("${ORIGIN}", "$ORIGIN");
+                    let dir = dir.replace("$ORIGIN", &parent_dir.to_string_lossy());
+                    candidates.push(self.rooted(Path::new(&dir)).join(name));
+                }
+                candidates.extend(self.search_paths.iter().map(|dir| dir.join(name)));
+                candidates.extend(
+                    self.ld_cache
+                        .iter()
+                        .filter(|(soname, _)| soname == name)
+                        .map(|(_, path)| self.rooted(path)),
+                );
+                candidates.extend(
+                    ELF_DEFAULT_DIRS
+                        .iter()
+                        .map(|dir| self.rooted(Path::new(dir)).join(name)),
+                );
+            }
+            Kind::Pe => {
+                // Windows file names are case-insensitive.
+                for dir in std::iter::once(parent_dir)
+                    .chain(self.search_paths.iter().map(PathBuf::as_path))
+                {
+                    candidates.extend(find_case_insensitive(dir, name));
+                }
+            }
+            Kind::MachO => {
+                let exe_dir = executable.parent().unwrap_or(Path::new("."));
+                let expand = |path: &str| {
+                    path.replace("@loader_path", &parent_dir.to_string_lossy())
+                        .replace("@executable_path", &exe_dir.to_string_lossy())
+                };
+                if let Some(rest) = name.strip_prefix("@rpath/") {
+                    for rpath in &needs.rpaths {
+                        candidates.push(self.rooted(Path::new(&expand(rpath))).join(rest));
+                    }
+                } else {
+                    candidates.push(self.rooted(Path::new(&expand(name))));
+                }
+                let file_name = Path::new(name).file_name().unwrap_or_default();
+                candidates.extend(self.search_paths.iter().map(|dir| dir.join(file_name)));
+            }
+        }
+        candidates
+    }
+
+    /// `path` under the sysroot, if one is set and `path` is absolute.
+    fn rooted(&self, path: &Path) -> PathBuf {
+        match (&self.sysroot, path.strip_prefix("/")) {
+            (Some(root), Ok(relative)) if !path.starts_with(root) => root.join(relative),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+fn needs(data: &[u8]) -> Result<Needs> {
+    let split = |paths: &[&str]| -> Vec<String> {
+        paths
+            .iter()
+            .flat_map(|p| p.split(':'))
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    Ok(match Object::parse(data)? {
+        Object::Elf(elf) => Needs {
+            libraries: elf.libraries.iter().map(|l| l.to_string()).collect(),
+            // The loader ignores DT_RPATH when DT_RUNPATH is present.
+            rpaths: if elf.runpaths.is_empty() {
+                split(&elf.rpaths)
+            } else {
+                split(&elf.runpaths)
+            },
+            elf_target: Some((data[4], elf.header.e_machine)),
+            kind: Kind::Elf,
+        },
+        Object::PE(pe) => Needs {
+            libraries: pe.libraries.iter().map(|l| l.to_string()).collect(),
+            rpaths: Vec::new(),
+            elf_target: None,
+            kind: Kind::Pe,
+        },
+        Object::Mach(goblin::mach::Mach::Binary(macho)) => Needs {
+            // The first entry is the image's own install name.
+            libraries: macho
+                .libs
+                .iter()
+                .filter(|&&l| l != "self")
+                .map(|l| l.to_string())
+                .collect(),
+            rpaths: split(&macho.rpaths),
+            elf_target: None,
+            kind: Kind::MachO,
+        },
+        _ => anyhow::bail!("not an ELF, PE or thin Mach-O binary"),
+    })
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_api_set(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("api-ms-win-") || name.starts_with("ext-ms-")
+}
+
+fn find_case_insensitive(dir: &Path, name: &str) -> Option<PathBuf> {
+    let exact = dir.join(name);
+    if exact.is_file() {
+        return Some(exact);
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .find(|e| e.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+        .map(|e| e.path())
+}
+
+/// Reads the `(soname, path)` entries of a glibc `ld.so.cache` in the
+/// `glibc-ld.so.cache1.1` format, which may follow an old-format prefix.
+pub fn parse_ld_cache(data: &[u8]) -> Vec<(String, PathBuf)> {
+    let Some(start) = data
+        .windows(LD_CACHE_MAGIC.len())
+        .position(|w| w == LD_CACHE_MAGIC)
+    else {
+        return Vec::new();
+    };
+    let cache = &data[start..];
+    let read_u32 = |at: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(cache.get(at..at + 4)?.try_into().ok()?) as usize)
+    };
+    // String offsets are relative to the new-format header.
+    let string = |at: usize| -> Option<String> {
+        let bytes = cache.get(at..)?;
+        let end = bytes.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+    };
+    let count = read_u32(20).unwrap_or(0);
+    let mut entries = Vec::new();
+    for i in 0..count {
+        let entry = 48 + i * 24;
+        let (Some(key), Some(value)) = (read_u32(entry + 4), read_u32(entry + 8)) else {
+            break;
+        };
+        if let (Some(name), Some(path)) = (string(key), string(value)) {
+            entries.push((name, PathBuf::from(path)));
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ld_cache() {
+        let strings = b"libc.so.6\0/lib/x86_64-linux-gnu/libc.so.6\0";
+        let mut cache = LD_CACHE_MAGIC.to_vec();
+        cache.extend(1u32.to_le_bytes());
+        cache.extend((strings.len() as u32).to_le_bytes());
+        cache.resize(48, 0);
+        let strings_at = 48 + 24;
+        cache.extend(0x0303u32.to_le_bytes());
+        cache.extend((strings_at as u32).to_le_bytes());
+        cache.extend((strings_at as u32 + 10).to_le_bytes());
+        cache.extend([0; 12]);
+        cache.extend(strings);
+
+        let mut data = b"ld.so-1.7.0\0".to_vec();
+        data.extend(cache);
+        assert_eq!(
+            parse_ld_cache(&data),
+            [(
+                "libc.so.6".to_string(),
+                PathBuf::from("/lib/x86_64-linux-gnu/libc.so.6")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_rooted() {
+        let resolver = Resolver {
+            sysroot: Some(PathBuf::from("/srv/rootfs")),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolver.rooted(Path::new("/usr/lib/libz.so.1")),
+            PathBuf::from("/srv/rootfs/usr/lib/libz.so.1")
+        );
+        assert_eq!(
+            resolver.rooted(Path::new("lib/libz.so.1")),
+            PathBuf::from("lib/libz.so.1")
+        );
+    }
+}
@@ -1,37 +1,133 @@
 use anyhow::{anyhow, Result};
+use capstone::arch::ArchOperand;
 use capstone::prelude::*;
+use capstone::Insn;
 
-#[derive(Debug)]
+/// Architecture names accepted by [`disassemble`].
+pub const SUPPORTED_ARCHES: &[&str] = &[
+    "x86",
+    "x86_64",
+    "aarch64",
+    "arm",
+    "thumb",
+    "mips",
+    "mipsel",
+    "mips64",
+    "mips64el",
+    "mipsn32",
+    "mipsn32el",
+    "riscv32",
+    "riscv64",
+    "ppc",
+    "ppcle",
+    "ppc64",
+    "ppc64le",
+    "sparc",
+    "sparc64",
+];
+
+#[derive(Debug, Clone, Default)]
 pub struct InstructionInfo {
     pub address: u64,
+    pub size: usize,
     pub mnemonic: String,
     pub op_str: String,
+    /// Explicit operands, in printed order. This and the fields below are
+    /// only filled by [`disassemble_detailed`].
+    pub operands: Vec<Operand>,
+    /// Registers read, implicit ones included. Explicit register operands
+    /// are only classified where capstone reports their access (x86).
+    pub regs_read: Vec<String>,
+    /// Registers written, with the same caveat as `regs_read`.
+    pub regs_written: Vec<String>,
+    /// Capstone instruction groups (`jump`, `call`, `ret`, `int`, `aes`, ...).
+    pub groups: Vec<String>,
 }
 
-pub fn disassemble(
-    arch: &str,
-    code: &[u8],
-    address: u64,
-    limit: usize,
-) -> Result<Vec<InstructionInfo>> {
+impl InstructionInfo {
+    pub fn in_group(&self, group: &str) -> bool {
+        self.groups.iter().any(|g| g == group)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Reg(String),
+    Imm(i64),
+    /// `[base + index * scale + disp]`
+    Mem {
+        base: Option<String>,
+        index: Option<String>,
+        scale: i32,
+        disp: i64,
+    },
+    /// Floating-point, condition and other arch-specific operands.
+    Other,
+}
+
+/// Capstone for `arch`; `detail` enables operand, register and group data,
+/// which roughly doubles decoding cost. With `skip_data`, undecodable bytes
+/// come back as `.byte` instructions (one instruction width at a time)
+/// instead of ending the decode.
+fn engine(arch: &str, detail: bool, skip_data: bool) -> Result<Capstone> {
     let cs = match arch {
         "x86_64" => Capstone::new()
             .x86()
             .mode(arch::x86::ArchMode::Mode64)
             .syntax(arch::x86::ArchSyntax::Intel)
-            .build()
-            .map_err(|e| anyhow!("Failed to initialize Capstone: {}", e))?,
+            .build(),
         "x86" => Capstone::new()
             .x86()
             .mode(arch::x86::ArchMode::Mode32)
             .syntax(arch::x86::ArchSyntax::Intel)
-            .build()
-            .map_err(|e| anyhow!("Failed to initialize Capstone: {}", e))?,
+            .build(),
         "aarch64" => Capstone::new()
             .arm64()
             .mode(arch::arm64::ArchMode::Arm)
-            .build()
-            .map_err(|e| anyhow!("Failed to initialize Capstone: {}", e))?,
+            .build(),
+        "arm" => Capstone::new().arm().mode(arch::arm::ArchMode::Arm).build(),
+        "thumb" => Capstone::new()
+            .arm()
+            .mode(arch::arm::ArchMode::Thumb)
+            .build(),
+        "mips" | "mipsel" => mips(arch::mips::ArchMode::Mips32, arch == "mipsel"),
+        // n32 code uses the 64-bit instruction set.
+        "mips64" | "mips64el" | "mipsn32" | "mipsn32el" => {
+            mips(arch::mips::ArchMode::Mips64, arch.ends_with("el"))
+        }
+        // Compressed (C extension) parcels are told apart by their low bits,
+        // so decoding them unconditionally is harmless for RV32G/RV64G code.
+        "riscv32" | "riscv64" => Capstone::new()
+            .riscv()
+            .mode(if arch == "riscv64" {
+                arch::riscv::ArchMode::RiscV64
+            } else {
+                arch::riscv::ArchMode::RiscV32
+            })
+            .extra_mode([arch::riscv::ArchExtraMode::RiscVC].iter().copied())
+            .build(),
+        "ppc" | "ppcle" | "ppc64" | "ppc64le" => Capstone::new()
+            .ppc()
+            .mode(if arch.starts_with("ppc64") {
+                arch::ppc::ArchMode::Mode64
+            } else {
+                arch::ppc::ArchMode::Mode32
+            })
+            .endian(if arch.ends_with("le") {
+                capstone::Endian::Little
+            } else {
+                capstone::Endian::Big
+            })
+            .build(),
+        // SPARC is big-endian only.
+        "sparc" => Capstone::new()
+            .sparc()
+            .mode(arch::sparc::ArchMode::Default)
+            .build(),
+        "sparc64" => Capstone::new()
+            .sparc()
+            .mode(arch::sparc::ArchMode::V9)
+            .build(),
         // Add more as needed or if we improve arch detection
         _ => {
             return Err(anyhow!(
@@ -40,19 +136,299 @@ pub fn disassemble(
             ))
         }
     };
+    let mut cs = cs.map_err(|e| anyhow!("Failed to initialize Capstone: {}", e))?;
+    cs.set_detail(detail)
+        .map_err(|e| anyhow!("Failed to enable instruction details: {}", e))?;
+    cs.set_skipdata(skip_data)
+        .map_err(|e| anyhow!("Failed to enable skipdata: {}", e))?;
+    Ok(cs)
+}
+
+fn mips(mode: arch::mips::ArchMode, little_endian: bool) -> CsResult<Capstone> {
+    let endian = if little_endian {
+        capstone::Endian::Little
+    } else {
+        capstone::Endian::Big
+    };
+    Capstone::new().mips().mode(mode).endian(endian).build()
+}
+
+/// Whether branches on `arch` execute the following instruction (the delay
+/// slot) before transferring control.
+pub fn has_delay_slots(arch: &str) -> bool {
+    arch.starts_with("mips") || arch.starts_with("sparc")
+}
 
+fn decode(
+    cs: &Capstone,
+    code: &[u8],
+    address: u64,
+    limit: usize,
+    detail: bool,
+) -> Result<Vec<InstructionInfo>> {
     let instructions = cs
         .disasm_count(code, address, limit)
         .map_err(|e| anyhow!("Disassembly failed: {}", e))?;
 
     let mut results = Vec::new();
     for i in instructions.iter() {
-        results.push(InstructionInfo {
+        let mut info = InstructionInfo {
             address: i.address(),
+            size: i.bytes().len(),
             mnemonic: i.mnemonic().unwrap_or("???").to_string(),
             op_str: i.op_str().unwrap_or("").to_string(),
-        });
+            ..Default::default()
+        };
+        if detail {
+            add_detail(cs, i, &mut info);
+        }
+        results.push(info);
     }
 
     Ok(results)
 }
+
+/// Fills operands, register access and groups from capstone's detail data.
+fn add_detail(cs: &Capstone, insn: &Insn, info: &mut InstructionInfo) {
+    let Ok(detail) = cs.insn_detail(insn) else {
+        return;
+    };
+    let name = |reg: RegId| cs.reg_name(reg).unwrap_or_default();
+    // Register id 0 means "no register" on every architecture.
+    let opt = |reg: RegId| (reg.0 != 0).then(|| name(reg));
+    let mem = |base: RegId, index: Option<RegId>, scale: i32, disp: i64| Operand::Mem {
+        base: opt(base),
+        index: index.and_then(opt),
+        scale,
+        disp,
+    };
+    info.regs_read = detail.regs_read().iter().map(|&r| name(r)).collect();
+    info.regs_written = detail.regs_write().iter().map(|&r| name(r)).collect();
+    info.groups = detail
+        .groups()
+        .iter()
+        .filter_map(|&g| cs.group_name(g))
+        .collect();
+
+    for op in detail.arch_detail().operands() {
+        use capstone::arch::{arm, arm64, mips, ppc, riscv, sparc, x86};
+        let mut access = None;
+        let operand = match op {
+            ArchOperand::X86Operand(op) => {
+                access = op.access;
+                match op.op_type {
+                    x86::X86OperandType::Reg(r) => Operand::Reg(name(r)),
+                    x86::X86OperandType::Imm(i) => Operand::Imm(i),
+                    x86::X86OperandType::Mem(m) => {
+                        mem(m.base(), Some(m.index()), m.scale(), m.disp())
+                    }
+                    x86::X86OperandType::Invalid => Operand::Other,
+                }
+            }
+            ArchOperand::Arm64Operand(op) => match op.op_type {
+                arm64::Arm64OperandType::Reg(r) => Operand::Reg(name(r)),
+                arm64::Arm64OperandType::Imm(i) | arm64::Arm64OperandType::Cimm(i) => {
+                    Operand::Imm(i)
+                }
+                arm64::Arm64OperandType::Mem(m) => {
+                    mem(m.base(), Some(m.index()), 1, m.disp() as i64)
+                }
+                _ => Operand::Other,
+            },
+            ArchOperand::ArmOperand(op) => match op.op_type {
+                arm::ArmOperandType::Reg(r) => Operand::Reg(name(r)),
+                arm::ArmOperandType::Imm(i) | arm::ArmOperandType::Cimm(i) => {
+                    Operand::Imm(i as i64)
+                }
+                arm::ArmOperandType::Mem(m) => {
+                    mem(m.base(), Some(m.index()), m.scale(), m.disp() as i64)
+                }
+                _ => Operand::Other,
+            },
+            ArchOperand::MipsOperand(op) => match op {
+                mips::MipsOperand::Reg(r) => Operand::Reg(name(r)),
+                mips::MipsOperand::Imm(i) => Operand::Imm(i),
+                mips::MipsOperand::Mem(m) => mem(m.base(), None, 1, m.disp()),
+                mips::MipsOperand::Invalid => Operand::Other,
+            },
+            ArchOperand::RiscVOperand(op) => match op {
+                riscv::RiscVOperand::Reg(r) => Operand::Reg(name(r)),
+                riscv::RiscVOperand::Imm(i) => Operand::Imm(i),
+                riscv::RiscVOperand::Mem(m) => mem(m.base(), None, 1, m.disp()),
+                riscv::RiscVOperand::Invalid => Operand::Other,
+            },
+            ArchOperand::PpcOperand(op) => match op {
+                ppc::PpcOperand::Reg(r) => Operand::Reg(name(r)),
+                ppc::PpcOperand::Imm(i) => Operand::Imm(i),
+                ppc::PpcOperand::Mem(m) => mem(m.base(), None, 1, m.disp() as i64),
+                _ => Operand::Other,
+            },
+            ArchOperand::SparcOperand(op) => match op {
+                sparc::SparcOperand::Reg(r) => Operand::Reg(name(r)),
+                sparc::SparcOperand::Imm(i) => Operand::Imm(i),
+                sparc::SparcOperand::Mem(m) => mem(m.base(), Some(m.index()), 1, m.disp() as i64),
+                sparc::SparcOperand::Invalid => Operand::Other,
+            },
+            _ => Operand::Other,
+        };
+        match &operand {
+            Operand::Reg(reg) => {
+                if access.is_some_and(|a| a.is_readable()) {
+                    push_unique(&mut info.regs_read, reg);
+                }
+                if access.is_some_and(|a| a.is_writable()) {
+                    push_unique(&mut info.regs_written, reg);
+                }
+            }
+            // Address registers are read whatever the access.
+            Operand::Mem { base, index, .. } => {
+                for reg in base.iter().chain(index) {
+                    push_unique(&mut info.regs_read, reg);
+                }
+            }
+            _ => {}
+        }
+        info.operands.push(operand);
+    }
+}
+
+fn push_unique(regs: &mut Vec<String>, reg: &str) {
+    if !regs.iter().any(|r| r == reg) {
+        regs.push(reg.to_string());
+    }
+}
+
+/// Decodes up to `limit` instructions, stopping at the first invalid one.
+pub fn disassemble(
+    arch: &str,
+    code: &[u8],
+    address: u64,
+    limit: usize,
+) -> Result<Vec<InstructionInfo>> {
+    decode(&engine(arch, false, false)?, code, address, limit, false)
+}
+
+/// Like [`disassemble`], with `operands`, `regs_read`, `regs_written` and
+/// `groups` filled in.
+pub fn disassemble_detailed(
+    arch: &str,
+    code: &[u8],
+    address: u64,
+    limit: usize,
+) -> Result<Vec<InstructionInfo>> {
+    decode(&engine(arch, true, false)?, code, address, limit, true)
+}
+
+/// Decodes up to `limit` instructions for display, turning embedded data
+/// (jump tables, padding) into `.byte` lines rather than stopping there.
+/// `detail` fills the same fields as [`disassemble_detailed`].
+pub fn disassemble_with_data(
+    arch: &str,
+    code: &[u8],
+    address: u64,
+    limit: usize,
+    detail: bool,
+) -> Result<Vec<InstructionInfo>> {
+    decode(&engine(arch, detail, true)?, code, address, limit, detail)
+}
+
+/// Bytes decoded per capstone call during a linear sweep.
+const SWEEP_WINDOW: usize = 0x4000;
+
+/// Decodes all of `code` front to back, calling `f` for every instruction
+/// and stopping at its first error. Undecodable bytes are reported as
+/// `.byte` so the sweep resynchronizes instead of stopping.
+pub fn linear_sweep(
+    arch: &str,
+    code: &[u8],
+    address: u64,
+    mut f: impl FnMut(InstructionInfo) -> Result<()>,
+) -> Result<()> {
+    let cs = engine(arch, false, true)?;
+    let mut pos = 0;
+    while pos < code.len() {
+        let window = &code[pos..(pos + SWEEP_WINDOW).min(code.len())];
+        let decoded = decode(&cs, window, address + pos as u64, usize::MAX, false)?;
+        // Only a tail shorter than one instruction width is left undecoded.
+        if decoded.is_empty() {
+            f(InstructionInfo {
+                address: address + pos as u64,
+                size: 1,
+                mnemonic: ".byte".to_string(),
+                op_str: format!("0x{:02x}", code[pos]),
+                ..Default::default()
+            })?;
+            pos += 1;
+            continue;
+        }
+        for ins in decoded {
+            pos += ins.size;
+            f(ins)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_sweep_resyncs() {
+        // nop; <0x06, invalid in 64-bit mode>; ret
+        let mut seen = Vec::new();
+        linear_sweep("x86_64", &[0x90, 0x06, 0xc3], 0x1000, |ins| {
+            seen.push((ins.address, ins.mnemonic));
+            Ok(())
+        })
+        .expect("sweep failed");
+        assert_eq!(
+            seen,
+            vec![
+                (0x1000, "nop".to_string()),
+                (0x1001, ".byte".to_string()),
+                (0x1002, "ret".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_data_keeps_going() {
+        // ARM: bx lr; <jump table word>; bx lr
+        let code = [
+            0x1e, 0xff, 0x2f, 0xe1, 0xff, 0xff, 0xff, 0xff, 0x1e, 0xff, 0x2f, 0xe1,
+        ];
+        assert_eq!(disassemble("arm", &code, 0, 10).unwrap().len(), 1);
+        let insns = disassemble_with_data("arm", &code, 0, 10, true).unwrap();
+        let lines: Vec<_> = insns
+            .iter()
+            .map(|i| (i.address, i.mnemonic.as_str(), i.size))
+            .collect();
+        assert_eq!(lines, vec![(0, "bx", 4), (4, ".byte", 4), (8, "bx", 4)]);
+        assert_eq!(insns[1].op_str, "0xff, 0xff, 0xff, 0xff");
+    }
+
+    #[test]
+    fn test_instruction_detail() {
+        // mov rax, qword ptr [rbx + rcx*8 + 0x10]; push rbp; ret
+        let code = [0x48, 0x8b, 0x44, 0xcb, 0x10, 0x55, 0xc3];
+        let insns = disassemble_detailed("x86_64", &code, 0x1000, 3).expect("disassembly failed");
+        assert_eq!(
+            insns[0].operands,
+            vec![
+                Operand::Reg("rax".to_string()),
+                Operand::Mem {
+                    base: Some("rbx".to_string()),
+                    index: Some("rcx".to_string()),
+                    scale: 8,
+                    disp: 0x10,
+                },
+            ]
+        );
+        assert_eq!(insns[0].regs_written, vec!["rax"]);
+        assert!(insns[0].regs_read.contains(&"rcx".to_string()));
+        // push reads and writes rsp implicitly.
+        assert!(insns[1].regs_written.contains(&"rsp".to_string()));
+        assert!(insns[2].in_group("ret"));
+    }
+}
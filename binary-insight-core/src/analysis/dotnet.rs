@@ -0,0 +1,350 @@
+//! .NET assembly identity and references from the CLI metadata tables
+//! (ECMA-335 §II.24).
+
+use super::pe_headers::directory_data;
+use goblin::pe::data_directories::DataDirectory;
+use goblin::pe::PE;
+use serde::Serialize;
+
+const METADATA_SIGNATURE: u32 = 0x424a_5342; // "BSJB"
+const TABLE_ASSEMBLY: usize = 0x20;
+const TABLE_ASSEMBLY_REF: usize = 0x23;
+
+/// Managed code metadata of a .NET PE.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DotNetInfo {
+    /// Runtime the metadata targets, e.g. `v4.0.30319`.
+    pub runtime_version: String,
+    pub assembly: Option<AssemblyName>,
+    /// Referenced assemblies, in table order.
+    pub references: Vec<AssemblyName>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssemblyName {
+    pub name: String,
+    /// `major.minor.build.revision`.
+    pub version: String,
+    pub culture: Option<String>,
+}
+
+pub fn analyze_dotnet(pe: &PE, data: &[u8]) -> Option<DotNetInfo> {
+    let opt = pe.header.optional_header.as_ref()?;
+    let cor20 = directory_data(pe, data, opt.data_directories.get_clr_runtime_header())?;
+    let metadata = DataDirectory {
+        virtual_address: read_u32(cor20, 8)?,
+        size: read_u32(cor20, 12)?,
+    };
+    parse_metadata(directory_data(pe, data, Some(&metadata))?)
+}
+
+/// A column of a metadata table.
+#[derive(Clone, Copy)]
+enum Col {
+    U16,
+    U32,
+    Str,
+    Guid,
+    Blob,
+    /// Index into one table.
+    Table(usize),
+    /// Coded index over several tables (`None` for unused tags).
+    Coded(&'static [Option<usize>]),
+}
+
+use Col::*;
+
+const TYPE_DEF_OR_REF: Col = Coded(&[Some(0x02), Some(0x01), Some(0x1b)]);
+const HAS_CONSTANT: Col = Coded(&[Some(0x04), Some(0x08), Some(0x17)]);
+const HAS_CUSTOM_ATTRIBUTE: Col = Coded(&[
+    Some(0x06),
+    Some(0x04),
+    Some(0x01),
+    Some(0x02),
+    Some(0x08),
+    Some(0x09),
+    Some(0x0a),
+    Some(0x00),
+    Some(0x0e),
+    Some(0x17),
+    Some(0x14),
+    Some(0x11),
+    Some(0x1a),
+    Some(0x1b),
+    Some(0x20),
+    Some(0x23),
+    Some(0x26),
+    Some(0x27),
+    Some(0x28),
+    Some(0x2a),
+    Some(0x2c),
+    Some(0x2b),
+]);
+const HAS_FIELD_MARSHAL: Col = Coded(&[Some(0x04), Some(0x08)]);
+const HAS_DECL_SECURITY: Col = Coded(&[Some(0x02), Some(0x06), Some(0x20)]);
+const MEMBER_REF_PARENT: Col = Coded(&[Some(0x02), Some(0x01), Some(0x1a), Some(0x06), Some(0x1b)]);
+const HAS_SEMANTICS: Col = Coded(&[Some(0x14), Some(0x17)]);
+const METHOD_DEF_OR_REF: Col = Coded(&[Some(0x06), Some(0x0a)]);
+const MEMBER_FORWARDED: Col = Coded(&[Some(0x04), Some(0x06)]);
+const CUSTOM_ATTRIBUTE_TYPE: Col = Coded(&[None, None, Some(0x06), Some(0x0a), None]);
+const RESOLUTION_SCOPE: Col = Coded(&[Some(0x00), Some(0x01), Some(0x1a), Some(0x23)]);
+
+/// Columns of tables 0x00 (Module) through 0x23 (AssemblyRef); the tables
+/// after it are never read.
+const SCHEMA: [&[Col]; 0x24] = [
+    &[U16, Str, Guid, Guid, Guid],                               // Module
+    &[RESOLUTION_SCOPE, Str, Str],                               // TypeRef
+    &[U32, Str, Str, TYPE_DEF_OR_REF, Table(0x04), Table(0x06)], // TypeDef
+    &[Table(0x04)],                                              // FieldPtr
+    &[U16, Str, Blob],                                           // Field
+    &[Table(0x06)],                                              // MethodPtr
+    &[U32, U16, U16, Str, Blob, Table(0x08)],                    // MethodDef
+    &[Table(0x08)],                                              // ParamPtr
+    &[U16, U16, Str],                                            // Param
+    &[Table(0x02), TYPE_DEF_OR_REF],                             // InterfaceImpl
+    &[MEMBER_REF_PARENT, Str, Blob],                             // MemberRef
+    &[U16, HAS_CONSTANT, Blob],                                  // Constant
+    &[HAS_CUSTOM_ATTRIBUTE, CUSTOM_ATTRIBUTE_TYPE, Blob],        // CustomAttribute
+    &[HAS_FIELD_MARSHAL, Blob],                                  // FieldMarshal
+    &[U16, HAS_DECL_SECURITY, Blob],                             // DeclSecurity
+    &[U16, U32, Table(0x02)],                                    // ClassLayout
+    &[U32, Table(0x04)],                                         // FieldLayout
+    &[Blob],                                                     // StandAloneSig
+    &[Table(0x02), Table(0x14)],                                 // EventMap
+    &[Table(0x14)],                                              // EventPtr
+    &[U16, Str, TYPE_DEF_OR_REF],                                // Event
+    &[Table(0x02), Table(0x17)],                                 // PropertyMap
+    &[Table(0x17)],                                              // PropertyPtr
+    &[U16, Str, Blob],                                           // Property
+    &[U16, Table(0x06), HAS_SEMANTICS],                          // MethodSemantics
+    &[Table(0x02), METHOD_DEF_OR_REF, METHOD_DEF_OR_REF],        // MethodImpl
+    &[Str],                                                      // ModuleRef
+    &[Blob],                                                     // TypeSpec
+    &[U16, MEMBER_FORWARDED, Str, Table(0x1a)],                  // ImplMap
+    &[U32, Table(0x04)],                                         // FieldRVA
+    &[U32, U32],                                                 // EncLog
+    &[U32],                                                      // EncMap
+    &[U32, U16, U16, U16, U16, U32, Blob, Str, Str],             // Assembly
+    &[U32],                                                      // AssemblyProcessor
+    &[U32, U32, U32],                                            // AssemblyOS
+    &[U16, U16, U16, U16, U32, Blob, Str, Str, Blob],            // AssemblyRef
+];
+
+/// Parses the metadata root (`BSJB`) and the `#~` table stream.
+fn parse_metadata(metadata: &[u8]) -> Option<DotNetInfo> {
+    if read_u32(metadata, 0)? != METADATA_SIGNATURE {
+        return None;
+    }
+    let version_len = read_u32(metadata, 12)? as usize;
+    let version = metadata.get(16..16 + version_len)?;
+    let mut info = DotNetInfo {
+        runtime_version: c_string(version),
+        ..Default::default()
+    };
+
+    let mut pos = 16 + version_len;
+    let stream_count = read_u16(metadata, pos + 2)?;
+    pos += 4;
+    let (mut tables, mut strings) = (None, None);
+    for _ in 0..stream_count {
+        let offset = read_u32(metadata, pos)? as usize;
+        let size = read_u32(metadata, pos + 4)? as usize;
+        let name = c_string(metadata.get(pos + 8..)?);
+        // Names are NUL-terminated and padded to four bytes.
+        pos += 8 + (name.len() + 4) / 4 * 4;
+        let stream = metadata.get(offset..offset.checked_add(size)?)?;
+        match name.as_str() {
+            "#~" | "#-" => tables = Some(stream),
+            "#Strings" => strings = Some(stream),
+            _ => {}
+        }
+    }
+    let (tables, strings) = (tables?, strings?);
+
+    let heap_sizes = *tables.get(6)?;
+    let valid = read_u64(tables, 8)?;
+    let mut rows = [0u32; 64];
+    let mut pos = 24;
+    for (table, count) in rows.iter_mut().enumerate() {
+        if valid & (1 << table) != 0 {
+            *count = read_u32(tables, pos)?;
+            pos += 4;
+        }
+    }
+    // Uncompressed (`#-`) streams may carry four extra bytes.
+    if heap_sizes & 0x40 != 0 {
+        pos += 4;
+    }
+
+    let wide_heap = |bit: u8| heap_sizes & bit != 0;
+    let width = |col: Col| -> usize {
+        match col {
+            U16 => 2,
+            U32 => 4,
+            Str if wide_heap(0x01) => 4,
+            Guid if wide_heap(0x02) => 4,
+            Blob if wide_heap(0x04) => 4,
+            Str | Guid | Blob => 2,
+            Table(t) if rows[t] >= 1 << 16 => 4,
+            Table(_) => 2,
+            Coded(targets) => {
+                let tag_bits = usize::BITS - (targets.len() - 1).leading_zeros();
+                let max = targets
+                    .iter()
+                    .flatten()
+                    .map(|&t| rows[t])
+                    .max()
+                    .unwrap_or(0);
+                if max >= 1 << (16 - tag_bits) {
+                    4
+                } else {
+                    2
+                }
+            }
+        }
+    };
+
+    let mut table_start = [0usize; 0x24];
+    for (table, columns) in SCHEMA.iter().enumerate() {
+        table_start[table] = pos;
+        let row_size: usize = columns.iter().map(|&c| width(c)).sum();
+        pos = pos.checked_add(row_size.checked_mul(rows[table] as usize)?)?;
+    }
+
+    // Reads row `row` of `table` as one value per column.
+    let read_row = |table: usize, row: usize| -> Option<Vec<u32>> {
+        let columns = SCHEMA[table];
+        let row_size: usize = columns.iter().map(|&c| width(c)).sum();
+        let mut at = table_start[table] + row * row_size;
+        columns
+            .iter()
+            .map(|&col| {
+                let value = match width(col) {
+                    2 => read_u16(tables, at)? as u32,
+                    _ => read_u32(tables, at)?,
+                };
+                at += width(col);
+                Some(value)
+            })
+            .collect()
+    };
+    let string = |index: u32| c_string(strings.get(index as usize..).unwrap_or_default());
+    let name = |fields: &[u32], version: usize, name: usize| {
+        let culture = string(fields[name + 1]);
+        AssemblyName {
+            name: string(fields[name]),
+            version: format!(
+                "{}.{}.{}.{}",
+                fields[version],
+                fields[version + 1],
+                fields[version + 2],
+                fields[version + 3]
+            ),
+            culture: (!culture.is_empty()).then_some(culture),
+        }
+    };
+
+    if rows[TABLE_ASSEMBLY] > 0 {
+        let fields = read_row(TABLE_ASSEMBLY, 0)?;
+        info.assembly = Some(name(&fields, 1, 7));
+    }
+    for row in 0..rows[TABLE_ASSEMBLY_REF] as usize {
+        let Some(fields) = read_row(TABLE_ASSEMBLY_REF, row) else {
+            break;
+        };
+        info.references.push(name(&fields, 0, 6));
+    }
+    Some(info)
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Metadata with a Module row, one Assembly and two AssemblyRefs.
+    fn metadata() -> Vec<u8> {
+        let strings = b"\0App\0System.Runtime\0Newtonsoft.Json\0";
+        let mut tables = vec![0, 0, 0, 0, 2, 0, 0, 1];
+        let valid: u64 = 1 | 1 << TABLE_ASSEMBLY | 1 << TABLE_ASSEMBLY_REF;
+        tables.extend(valid.to_le_bytes());
+        tables.extend(0u64.to_le_bytes());
+        for count in [1u32, 1, 2] {
+            tables.extend(count.to_le_bytes());
+        }
+        // Module: generation, name, three GUIDs.
+        tables.extend([0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        // Assembly: hash alg, 1.2.3.4, flags, public key, name, culture.
+        tables.extend(0x8004u32.to_le_bytes());
+        for part in [1u16, 2, 3, 4] {
+            tables.extend(part.to_le_bytes());
+        }
+        tables.extend([0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+        for (version, name) in [([8u16, 0, 0, 0], 5u16), ([13, 0, 0, 0], 20)] {
+            for part in version {
+                tables.extend(part.to_le_bytes());
+            }
+            tables.extend([0, 0, 0, 0, 0, 0]);
+            tables.extend(name.to_le_bytes());
+            tables.extend([0, 0, 0, 0]);
+        }
+
+        let version = b"v4.0.30319\0\0";
+        let mut data = METADATA_SIGNATURE.to_le_bytes().to_vec();
+        data.extend([1, 0, 1, 0, 0, 0, 0, 0]);
+        data.extend((version.len() as u32).to_le_bytes());
+        data.extend(version);
+        data.extend([0, 0, 2, 0]);
+        let headers_len = 12 + 20;
+        let tables_at = data.len() + headers_len;
+        let strings_at = tables_at + tables.len();
+        data.extend((tables_at as u32).to_le_bytes());
+        data.extend((tables.len() as u32).to_le_bytes());
+        data.extend(b"#~\0\0");
+        data.extend((strings_at as u32).to_le_bytes());
+        data.extend((strings.len() as u32).to_le_bytes());
+        data.extend(b"#Strings\0\0\0\0");
+        data.extend(tables);
+        data.extend(strings);
+        data
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let info = parse_metadata(&metadata()).unwrap();
+        assert_eq!(info.runtime_version, "v4.0.30319");
+        let assembly = info.assembly.unwrap();
+        assert_eq!(
+            (assembly.name.as_str(), assembly.version.as_str()),
+            ("App", "1.2.3.4")
+        );
+        let references: Vec<_> = info
+            .references
+            .iter()
+            .map(|r| (r.name.as_str(), r.version.as_str()))
+            .collect();
+        assert_eq!(
+            references,
+            [
+                ("System.Runtime", "8.0.0.0"),
+                ("Newtonsoft.Json", "13.0.0.0")
+            ]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use super::entropy;
+use crate::binary::BinaryInfo;
+
+/// Section entropy above which the entry point is taken to be in packed or
+/// encrypted data.
+const HIGH_ENTROPY: f64 = 7.2;
+
+/// Something unusual about where the entry point lies; packers and file
+/// infectors produce most of these.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryAnomaly {
+    /// No section contains the entry point.
+    OutsideSections,
+    /// The entry point is in a section that is not mapped executable.
+    NotExecutable {
+        section: String,
+    },
+    /// The entry point is in the last section, where appended code lands.
+    LastSection {
+        section: String,
+    },
+    HighEntropy {
+        section: String,
+        entropy: f64,
+    },
+}
+
+impl EntryAnomaly {
+    pub fn description(&self) -> String {
+        match self {
+            EntryAnomaly::OutsideSections => "entry point is outside every section".to_string(),
+            EntryAnomaly::NotExecutable { section } => {
+                format!("entry point is in non-executable section {}", section)
+            }
+            EntryAnomaly::LastSection { section } => {
+                format!("entry point is in the last section ({})", section)
+            }
+            EntryAnomaly::HighEntropy { section, entropy } => format!(
+                "entry point is in high-entropy section {} ({:.2})",
+                section, entropy
+            ),
+        }
+    }
+}
+
+/// Checks the entry point against the sections in `info`. `executable` holds
+/// the `(addr, size)` ranges the format maps executable.
+pub fn check_entry_point(
+    info: &BinaryInfo,
+    data: &[u8],
+    executable: &[(u64, u64)],
+) -> Vec<EntryAnomaly> {
+    let entry = info.entry_point;
+    // Libraries and objects often have no entry point at all.
+    if entry == 0 || info.sections.is_empty() {
+        return Vec::new();
+    }
+    let Some(section) = info
+        .sections
+        .iter()
+        .find(|s| s.addr != 0 && s.addr <= entry && entry - s.addr < s.size)
+    else {
+        return vec![EntryAnomaly::OutsideSections];
+    };
+
+    let mut anomalies = Vec::new();
+    if !executable
+        .iter()
+        .any(|&(addr, size)| addr <= entry && entry - addr < size)
+    {
+        anomalies.push(EntryAnomaly::NotExecutable {
+            section: section.name.clone(),
+        });
+    }
+    let mapped = info.sections.iter().filter(|s| s.addr != 0);
+    if mapped.clone().count() > 1
+        && mapped.max_by_key(|s| s.addr).map(|s| s.addr) == Some(section.addr)
+    {
+        anomalies.push(EntryAnomaly::LastSection {
+            section: section.name.clone(),
+        });
+    }
+    let entropy = entropy::calculate_entropy(&data[section.file_range(data.len())]);
+    if entropy > HIGH_ENTROPY {
+        anomalies.push(EntryAnomaly::HighEntropy {
+            section: section.name.clone(),
+            entropy,
+        });
+    }
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::SectionInfo;
+
+    fn section(name: &str, addr: u64, offset: u64) -> SectionInfo {
+        SectionInfo {
+            name: name.to_string(),
+            addr,
+            size: 0x100,
+            offset,
+            hashes: None,
+            zero_fill: false,
+        }
+    }
+
+    #[test]
+    fn test_entry_anomalies() {
+        let mut data = vec![0x90; 0x100];
+        // A byte ramp: maximal entropy.
+        data.extend((0..0x100).map(|i| i as u8));
+        let mut info = BinaryInfo {
+            entry_point: 0x1010,
+            sections: vec![section(".text", 0x1000, 0), section("UPX1", 0x2000, 0x100)],
+            ..Default::default()
+        };
+        let executable = [(0x1000, 0x100)];
+        assert!(check_entry_point(&info, &data, &executable).is_empty());
+
+        info.entry_point = 0x2010;
+        let anomalies = check_entry_point(&info, &data, &executable);
+        assert_eq!(anomalies.len(), 3);
+        assert_eq!(
+            anomalies[0],
+            EntryAnomaly::NotExecutable {
+                section: "UPX1".to_string()
+            }
+        );
+        assert!(matches!(anomalies[2], EntryAnomaly::HighEntropy { .. }));
+
+        info.entry_point = 0x5000;
+        assert_eq!(
+            check_entry_point(&info, &data, &executable),
+            vec![EntryAnomaly::OutsideSections]
+        );
+    }
+
+    #[test]
+    fn test_huge_section() {
+        let data = vec![0x90; 0x100];
+        let mut text = section(".text", 0x1000, 0x80);
+        text.size = u64::MAX - 0x800;
+        let info = BinaryInfo {
+            entry_point: 0x1010,
+            sections: vec![text],
+            ..Default::default()
+        };
+        let executable = [(0x1000, u64::MAX - 0x800)];
+        assert!(check_entry_point(&info, &data, &executable).is_empty());
+    }
+}
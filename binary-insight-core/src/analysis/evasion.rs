@@ -0,0 +1,389 @@
+use super::cfg::{self, Flow};
+use super::disassembly::{self, InstructionInfo};
+use super::listing::SymbolMap;
+use super::syscalls::{constant_in, BACKTRACK};
+use crate::binary::BinaryFile;
+use std::collections::HashSet;
+
+/// Windows APIs whose only common use is noticing a debugger.
+const ANTI_DEBUG_APIS: &[&str] = &[
+    "IsDebuggerPresent",
+    "CheckRemoteDebuggerPresent",
+    "NtQueryInformationProcess",
+    "ZwQueryInformationProcess",
+    "NtSetInformationThread",
+    "ZwSetInformationThread",
+];
+
+/// Two `rdtsc` reads at most this many instructions apart time the code
+/// in between, which single-stepping slows down.
+const RDTSC_WINDOW: usize = 64;
+/// Instructions after a PEB load or `cpuid` in which the check on its
+/// result counts.
+const LOOKAHEAD: usize = 8;
+
+/// Guest tools, drivers and sandboxes named in VM-aware code, lowercase.
+const VM_STRINGS: &[(&str, &str)] = &[
+    ("vboxservice", "VirtualBox"),
+    ("vboxtray", "VirtualBox"),
+    ("vboxguest", "VirtualBox"),
+    ("vboxmouse", "VirtualBox"),
+    ("vboxsf", "VirtualBox"),
+    ("virtualbox", "VirtualBox"),
+    ("vmtoolsd", "VMware"),
+    ("vmwaretray", "VMware"),
+    ("vmwareuser", "VMware"),
+    ("vmware tools", "VMware"),
+    ("vmhgfs", "VMware"),
+    ("vmmouse", "VMware"),
+    ("qemu-ga", "QEMU"),
+    ("qemu guest agent", "QEMU"),
+    ("vmsrvc", "Virtual PC"),
+    ("xenservice", "Xen"),
+    ("prl_tools", "Parallels"),
+    ("sbiedll", "Sandboxie"),
+    ("cuckoomon", "Cuckoo"),
+    ("wine_get_unix_file_name", "Wine"),
+];
+
+/// Virtual NIC MAC prefixes (OUIs), lowercase with `:` separators.
+const VM_MAC_PREFIXES: &[(&str, &str)] = &[
+    ("00:05:69", "VMware"),
+    ("00:0c:29", "VMware"),
+    ("00:1c:14", "VMware"),
+    ("00:50:56", "VMware"),
+    ("08:00:27", "VirtualBox"),
+    ("00:16:3e", "Xen"),
+    ("52:54:00", "QEMU/KVM"),
+];
+
+/// `in eax, dx` with these in `eax`/`dx` talks to the VMware backdoor.
+const VMWARE_MAGIC: u64 = 0x564d5868;
+const VMWARE_PORT: u64 = 0x5658;
+
+/// A static sign that the binary checks for analysis tooling.
+#[derive(Debug, Clone)]
+pub struct EvasionIndicator {
+    /// Stable identifier, e.g. `debugger_api` or `rdtsc_timing`.
+    pub technique: &'static str,
+    pub description: String,
+    /// The offending instruction, or the import slot when no call to the
+    /// import was found.
+    pub addr: Option<u64>,
+}
+
+/// Finds anti-debugging checks: debugger-detection APIs, `ptrace` self-attach,
+/// PEB `BeingDebugged`/`NtGlobalFlag` reads and `rdtsc` timing. Uses
+/// `info.syscalls` for direct `ptrace` system calls.
+pub fn find_anti_debug(file: &BinaryFile) -> Vec<EvasionIndicator> {
+    let info = &file.info;
+    let symbols = SymbolMap::new(file);
+    let macho = info.format.starts_with("Mach-O");
+    let ptrace_syscalls: HashSet<u64> = info
+        .syscalls
+        .iter()
+        .filter(|c| c.name == Some("ptrace"))
+        .map(|c| c.addr)
+        .collect();
+
+    let mut found = Vec::new();
+    let mut called = HashSet::new();
+    let mut index = 0;
+    // Register holding the PEB and where it was loaded.
+    let mut peb: Option<(String, usize)> = None;
+    let mut rdtsc: Option<(u64, usize)> = None;
+    sweep_code(file, |arch, ins, history| {
+        index += 1;
+        let callee = match cfg::classify(arch, ins) {
+            Flow::Call(_) | Flow::Jump(_) => symbols.annotate(ins),
+            _ => None,
+        };
+        let callee = callee.as_deref().map(api_name);
+        if let Some(api) = callee.and_then(|c| ANTI_DEBUG_APIS.iter().find(|a| **a == c)) {
+            called.insert(*api);
+            found.push(EvasionIndicator {
+                technique: "debugger_api",
+                description: format!("calls {}", api),
+                addr: Some(ins.address),
+            });
+        }
+        let request = if callee == Some("ptrace") {
+            constant_in(arch, first_arg(arch, false), history)
+        } else if ptrace_syscalls.contains(&ins.address) {
+            constant_in(arch, first_arg(arch, true), history)
+        } else {
+            None
+        };
+        match request {
+            Some(0) if !macho => found.push(EvasionIndicator {
+                technique: "ptrace_traceme",
+                description: "ptrace(PTRACE_TRACEME) fails if a debugger is attached".to_string(),
+                addr: Some(ins.address),
+            }),
+            Some(31) if macho => found.push(EvasionIndicator {
+                technique: "ptrace_deny_attach",
+                description: "ptrace(PT_DENY_ATTACH) keeps debuggers from attaching".to_string(),
+                addr: Some(ins.address),
+            }),
+            _ => {}
+        }
+
+        if let Some((reg, at)) = &peb {
+            let reads = |disp: &str| ins.op_str.contains(&format!("[{} + {}]", reg, disp));
+            let flag = if reads("2") {
+                Some(("peb_being_debugged", "BeingDebugged"))
+            } else if reads("0x68") || reads("0xbc") {
+                Some(("peb_nt_global_flag", "NtGlobalFlag"))
+            } else {
+                None
+            };
+            match flag {
+                Some((technique, flag)) => {
+                    found.push(EvasionIndicator {
+                        technique,
+                        description: format!("reads PEB {}", flag),
+                        addr: Some(ins.address),
+                    });
+                    peb = None;
+                }
+                None if index - at > LOOKAHEAD => peb = None,
+                None => {}
+            }
+        }
+        // mov eax, dword ptr fs:[0x30] / mov rax, qword ptr gs:[0x60]
+        if ins.op_str.contains("fs:[0x30]") || ins.op_str.contains("gs:[0x60]") {
+            if let Some(dest) = ins.op_str.split(',').next() {
+                peb = Some((dest.trim().to_string(), index));
+            }
+        }
+
+        if ins.mnemonic.starts_with("rdtsc") {
+            match rdtsc {
+                Some((first, at)) if index - at <= RDTSC_WINDOW => {
+                    found.push(EvasionIndicator {
+                        technique: "rdtsc_timing",
+                        description: "times code with a pair of rdtsc reads".to_string(),
+                        addr: Some(first),
+                    });
+                    rdtsc = None;
+                }
+                _ => rdtsc = Some((ins.address, index)),
+            }
+        }
+    });
+
+    // Imports resolved at runtime or called in ways we cannot follow.
+    for import in &info.imports {
+        let name = api_name(&import.name);
+        if let Some(api) = ANTI_DEBUG_APIS.iter().find(|a| **a == name) {
+            if !called.contains(api) {
+                found.push(EvasionIndicator {
+                    technique: "debugger_api",
+                    description: format!("imports {}", api),
+                    addr: import.slot,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Finds VM and sandbox checks: `cpuid` hypervisor bit and vendor leaf, the
+/// VMware backdoor port, guest tool and sandbox names, and virtual NIC MAC
+/// prefixes.
+pub fn find_anti_vm(file: &BinaryFile) -> Vec<EvasionIndicator> {
+    let info = &file.info;
+    let mut found = Vec::new();
+    let mut index = 0;
+    // `cpuid` leaf 1, whose ecx bit 31 is set under a hypervisor.
+    let mut features: Option<(u64, usize)> = None;
+    sweep_code(file, |arch, ins, history| {
+        index += 1;
+        let eax = || constant_in(arch, &["eax", "rax", "ax"], history);
+        match ins.mnemonic.as_str() {
+            "cpuid" => match eax() {
+                Some(1) => features = Some((ins.address, index)),
+                Some(0x4000_0000) => found.push(EvasionIndicator {
+                    technique: "cpuid_hypervisor_vendor",
+                    description: "reads the hypervisor vendor with cpuid 0x40000000".to_string(),
+                    addr: Some(ins.address),
+                }),
+                _ => {}
+            },
+            "in" if eax() == Some(VMWARE_MAGIC)
+                || constant_in(arch, &["dx", "edx", "rdx"], history) == Some(VMWARE_PORT) =>
+            {
+                found.push(EvasionIndicator {
+                    technique: "vmware_backdoor",
+                    description: "queries the VMware backdoor I/O port".to_string(),
+                    addr: Some(ins.address),
+                })
+            }
+            _ => {}
+        }
+        if let Some((cpuid, at)) = features {
+            if ins.op_str.contains("ecx, 0x1f") || ins.op_str.contains("ecx, 0x80000000") {
+                found.push(EvasionIndicator {
+                    technique: "cpuid_hypervisor_bit",
+                    description: "tests the cpuid hypervisor-present bit".to_string(),
+                    addr: Some(cpuid),
+                });
+                features = None;
+            } else if index - at > LOOKAHEAD {
+                features = None;
+            }
+        }
+    });
+
+    for (i, string) in info.strings.iter().enumerate() {
+        let lower = string.to_lowercase().replace('-', ":");
+        let vm = VM_STRINGS
+            .iter()
+            .find(|(needle, _)| lower.contains(needle))
+            .map(|(_, vm)| ("vm_string", vm))
+            .or_else(|| {
+                VM_MAC_PREFIXES
+                    .iter()
+                    .find(|(prefix, _)| lower.contains(prefix))
+                    .map(|(_, vm)| ("vm_mac_prefix", vm))
+            });
+        if let Some((technique, vm)) = vm {
+            let offset = info.string_index.get(i).map(|(offset, _)| offset);
+            found.push(EvasionIndicator {
+                technique,
+                description: format!("{} indicator \"{}\"", vm, string),
+                addr: offset.and_then(|o| info.offset_to_addr(o)),
+            });
+        }
+    }
+    found
+}
+
+/// Runs `f` over every instruction of the code sections, with the
+/// straight-line instructions before it (up to [`BACKTRACK`]).
+fn sweep_code(file: &BinaryFile, mut f: impl FnMut(&str, &InstructionInfo, &[InstructionInfo])) {
+    let info = &file.info;
+    for section in info.sections.iter().filter(|s| s.is_code()) {
+        let arch = info.arch_at(section.addr);
+        let mut history: Vec<InstructionInfo> = Vec::new();
+        let _ = disassembly::linear_sweep(arch, file.section_data(section), section.addr, |ins| {
+            f(arch, &ins, &history);
+            // Register values do not survive control flow.
+            if cfg::classify(arch, &ins) == Flow::Normal {
+                if history.len() == BACKTRACK {
+                    history.remove(0);
+                }
+                history.push(ins);
+            } else {
+                history.clear();
+            }
+            Ok(())
+        });
+    }
+}
+
+/// `ptrace@plt`, `_ptrace` (Mach-O) and `ptrace@GLIBC_2.2.5` all name `ptrace`.
+fn api_name(name: &str) -> &str {
+    let name = name.split('@').next().unwrap_or(name);
+    name.strip_prefix('_').unwrap_or(name)
+}
+
+/// Registers carrying the first argument of a call, or of a system call
+/// when `syscall` is set. 32-bit x86 passes call arguments on the stack.
+fn first_arg(arch: &str, syscall: bool) -> &'static [&'static str] {
+    match arch {
+        "x86_64" => &["rdi", "edi", "di", "dil"],
+        "x86" if syscall => &["ebx", "bx", "bl"],
+        "aarch64" => &["x0", "w0"],
+        "arm" | "thumb" => &["r0"],
+        "riscv32" | "riscv64" => &["a0"],
+        _ if arch.starts_with("mips") => &["$a0", "a0"],
+        _ if arch.starts_with("ppc") => &["r3"],
+        _ if arch.starts_with("sparc") => &["%o0"],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::syscalls;
+    use crate::binary::ImportInfo;
+
+    fn techniques(found: &[EvasionIndicator]) -> Vec<(&str, Option<u64>)> {
+        found.iter().map(|i| (i.technique, i.addr)).collect()
+    }
+
+    #[test]
+    fn test_windows_anti_debug() {
+        // 0x1000: call [rip + 0xffa] (IsDebuggerPresent, slot 0x2000)
+        // 0x1006: mov rax, qword ptr gs:[0x60]; movzx eax, byte ptr [rax + 2]
+        // 0x1013: rdtsc; rdtsc; ret
+        let mut code = vec![0xff, 0x15, 0xfa, 0x0f, 0x00, 0x00];
+        code.extend([0x65, 0x48, 0x8b, 0x04, 0x25, 0x60, 0x00, 0x00, 0x00]);
+        code.extend([0x0f, 0xb6, 0x40, 0x02, 0x0f, 0x31, 0x0f, 0x31, 0xc3]);
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        file.info.imports = vec![
+            ImportInfo {
+                name: "IsDebuggerPresent".to_string(),
+                library: Some("KERNEL32.dll".to_string()),
+                slot: Some(0x2000),
+            },
+            ImportInfo {
+                name: "NtQueryInformationProcess".to_string(),
+                library: Some("ntdll.dll".to_string()),
+                slot: Some(0x2008),
+            },
+        ];
+        let found = find_anti_debug(&file);
+        assert_eq!(
+            techniques(&found),
+            vec![
+                ("debugger_api", Some(0x1000)),
+                ("peb_being_debugged", Some(0x100f)),
+                ("rdtsc_timing", Some(0x1013)),
+                ("debugger_api", Some(0x2008)),
+            ]
+        );
+        assert_eq!(found[0].description, "calls IsDebuggerPresent");
+    }
+
+    #[test]
+    fn test_anti_vm() {
+        // mov eax, 1; cpuid; bt ecx, 0x1f
+        let mut code = vec![
+            0xb8, 0x01, 0x00, 0x00, 0x00, 0x0f, 0xa2, 0x0f, 0xba, 0xe1, 0x1f,
+        ];
+        // mov eax, 0x564d5868; mov dx, 0x5658; in eax, dx
+        code.extend([
+            0xb8, 0x68, 0x58, 0x4d, 0x56, 0x66, 0xba, 0x58, 0x56, 0xed, 0xc3,
+        ]);
+        code.extend(b"\0VBoxService.exe\0mac 08-00-27-aa-bb-cc\0");
+        let file = BinaryFile::from_raw("t".to_string(), code, "x86", 0x1000);
+        let found = find_anti_vm(&file);
+        assert_eq!(
+            techniques(&found),
+            vec![
+                ("cpuid_hypervisor_bit", Some(0x1005)),
+                ("vmware_backdoor", Some(0x1014)),
+                ("vm_string", Some(0x1017)),
+                ("vm_mac_prefix", Some(0x1027)),
+            ]
+        );
+        assert_eq!(
+            found[2].description,
+            "VirtualBox indicator \"VBoxService.exe\""
+        );
+    }
+
+    #[test]
+    fn test_ptrace_traceme_syscall() {
+        // xor edi, edi; mov eax, 0x65 (ptrace); syscall
+        let code = vec![0x31, 0xff, 0xb8, 0x65, 0x00, 0x00, 0x00, 0x0f, 0x05];
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0);
+        file.info.format = "ELF".to_string();
+        file.info.syscalls = syscalls::find_syscalls(&file);
+        let found = find_anti_debug(&file);
+        assert_eq!(techniques(&found), vec![("ptrace_traceme", Some(7))]);
+    }
+}
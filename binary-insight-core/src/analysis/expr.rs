@@ -0,0 +1,671 @@
+//! A small expression language for user-defined report fields, e.g.
+//! `entropy > 7.2 && sections[".text"].entropy > 7.0`.
+
+use crate::analysis::{anomalies, entropy, score, syscalls, Relro};
+use crate::binary::BinaryFile;
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Num(n) => *n != 0.0,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(l) => !l.is_empty(),
+            Value::Map(m) => !m.is_empty(),
+        }
+    }
+
+    fn field(&self, name: &str) -> Value {
+        match self {
+            Value::Map(m) => m.get(name).cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        }
+    }
+
+    fn index(&self, key: &Value) -> Value {
+        match (self, key) {
+            (Value::List(items), Value::Num(n)) if *n >= 0.0 => {
+                items.get(*n as usize).cloned().unwrap_or(Value::Null)
+            }
+            // Lists of named records (sections, symbols) can be indexed by name.
+            (Value::List(items), Value::Str(name)) => items
+                .iter()
+                .find(|item| item.field("name") == Value::Str(name.clone()))
+                .cloned()
+                .unwrap_or(Value::Null),
+            (Value::Map(m), Value::Str(name)) => m.get(name).cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl From<&serde_json::Value> for Value {
+    fn from(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => Value::Num(n.as_f64().unwrap_or_default()),
+            serde_json::Value::String(s) => Value::Str(s.clone()),
+            serde_json::Value::Array(items) => Value::List(items.iter().map(Value::from).collect()),
+            serde_json::Value::Object(map) => Value::Map(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Num(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Num(n) => write!(f, "{:.4}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::List(items) => write!(f, "[{} items]", items.len()),
+            Value::Map(m) => write!(f, "{{{} fields}}", m.len()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", "[", "]",
+    ".", ",",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            if c == '0' && matches!(chars.get(i + 1), Some('x') | Some('X')) {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+                let digits: String = chars[start + 2..i].iter().collect();
+                let n = u64::from_str_radix(&digits, 16)
+                    .map_err(|e| anyhow!("invalid hex literal: {}", e))?;
+                tokens.push(Token::Num(n as f64));
+            } else {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Num(
+                    text.parse()
+                        .map_err(|_| anyhow!("invalid number '{}'", text))?,
+                ));
+            }
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&ch| ch == c)
+                .ok_or_else(|| anyhow!("unterminated string literal"))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| anyhow!("unexpected character '{}'", c))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Var(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<()> {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            bail!("expected '{}'", op)
+        }
+    }
+
+    fn binary(&mut self, ops: &[&str], next: fn(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        let mut lhs = next(self)?;
+        while let Some(op) = self.peek_op().filter(|op| ops.contains(op)) {
+            self.pos += 1;
+            let rhs = next(self)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        self.binary(&["||"], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        self.binary(&["&&"], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        self.binary(&["==", "!=", "<", "<=", ">", ">="], Self::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        self.binary(&["+", "-"], Self::multiplicative)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr> {
+        self.binary(&["*", "/", "%"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.peek_op() {
+            Some(op @ ("!" | "-")) => {
+                self.pos += 1;
+                Ok(Expr::Unary(op, Box::new(self.unary()?)))
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            match self.peek_op() {
+                Some(".") => {
+                    self.pos += 1;
+                    match self.tokens.get(self.pos) {
+                        Some(Token::Ident(name)) => {
+                            expr = Expr::Field(Box::new(expr), name.clone());
+                            self.pos += 1;
+                        }
+                        _ => bail!("expected field name after '.'"),
+                    }
+                }
+                Some("[") => {
+                    self.pos += 1;
+                    let key = self.or()?;
+                    self.expect("]")?;
+                    expr = Expr::Index(Box::new(expr), Box::new(key));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end of expression"))?;
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(Expr::Literal(Value::Num(n))),
+            Token::Str(s) => Ok(Expr::Literal(Value::Str(s))),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.peek_op() == Some("(") => {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek_op() != Some(")") {
+                        loop {
+                            args.push(self.or()?);
+                            if self.peek_op() == Some(",") {
+                                self.pos += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                    self.expect(")")?;
+                    Ok(Expr::Call(name, args))
+                }
+                _ => Ok(Expr::Var(name)),
+            },
+            Token::Op("(") => {
+                let expr = self.or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Op(op) => bail!("unexpected '{}'", op),
+        }
+    }
+}
+
+pub fn parse(src: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    if parser.pos != parser.tokens.len() {
+        bail!("unexpected trailing input in expression");
+    }
+    Ok(expr)
+}
+
+pub fn evaluate(expr: &Expr, ctx: &Value) -> Result<Value> {
+    Ok(match expr {
+        Expr::Literal(v) => v.clone(),
+        Expr::Var(name) => ctx.field(name),
+        Expr::Field(base, name) => evaluate(base, ctx)?.field(name),
+        Expr::Index(base, key) => evaluate(base, ctx)?.index(&evaluate(key, ctx)?),
+        Expr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|a| evaluate(a, ctx))
+                .collect::<Result<Vec<_>>>()?;
+            call(name, &args)?
+        }
+        Expr::Unary("!", inner) => Value::Bool(!evaluate(inner, ctx)?.truthy()),
+        Expr::Unary(_, inner) => match evaluate(inner, ctx)? {
+            Value::Num(n) => Value::Num(-n),
+            _ => Value::Null,
+        },
+        Expr::Binary("&&", lhs, rhs) => {
+            Value::Bool(evaluate(lhs, ctx)?.truthy() && evaluate(rhs, ctx)?.truthy())
+        }
+        Expr::Binary("||", lhs, rhs) => {
+            Value::Bool(evaluate(lhs, ctx)?.truthy() || evaluate(rhs, ctx)?.truthy())
+        }
+        Expr::Binary(op, lhs, rhs) => binary_op(op, evaluate(lhs, ctx)?, evaluate(rhs, ctx)?),
+    })
+}
+
+/// Parses and evaluates `src` against `ctx` in one step.
+pub fn eval_str(src: &str, ctx: &Value) -> Result<Value> {
+    evaluate(&parse(src)?, ctx)
+}
+
+fn binary_op(op: &str, lhs: Value, rhs: Value) -> Value {
+    match (op, &lhs, &rhs) {
+        ("==", _, _) => Value::Bool(lhs == rhs),
+        ("!=", _, _) => Value::Bool(lhs != rhs),
+        ("+", Value::Str(a), Value::Str(b)) => Value::Str(format!("{}{}", a, b)),
+        (_, Value::Num(a), Value::Num(b)) => match op {
+            "<" => Value::Bool(a < b),
+            "<=" => Value::Bool(a <= b),
+            ">" => Value::Bool(a > b),
+            ">=" => Value::Bool(a >= b),
+            "+" => Value::Num(a + b),
+            "-" => Value::Num(a - b),
+            "*" => Value::Num(a * b),
+            "/" if *b != 0.0 => Value::Num(a / b),
+            "%" if *b != 0.0 => Value::Num(a % b),
+            _ => Value::Null,
+        },
+        // Comparisons involving missing data are false rather than errors.
+        ("<" | "<=" | ">" | ">=", _, _) => Value::Bool(false),
+        _ => Value::Null,
+    }
+}
+
+fn call(name: &str, args: &[Value]) -> Result<Value> {
+    Ok(match (name, args) {
+        ("len", [Value::List(items)]) => Value::Num(items.len() as f64),
+        ("len", [Value::Str(s)]) => Value::Num(s.len() as f64),
+        ("len", [Value::Map(m)]) => Value::Num(m.len() as f64),
+        ("len", [_]) => Value::Num(0.0),
+        ("contains", [Value::Str(s), Value::Str(needle)]) => {
+            Value::Bool(s.contains(needle.as_str()))
+        }
+        ("contains", [Value::List(items), needle]) => Value::Bool(
+            items
+                .iter()
+                .any(|item| item == needle || item.field("name") == *needle),
+        ),
+        ("contains", [_, _]) => Value::Bool(false),
+        ("hex", [Value::Num(n)]) => Value::Str(format!("0x{:x}", *n as u64)),
+        ("join", [Value::List(items), Value::Str(sep)]) => Value::Str(
+            items
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+                .join(sep),
+        ),
+        ("max", [Value::Num(a), Value::Num(b)]) => Value::Num(a.max(*b)),
+        ("min", [Value::Num(a), Value::Num(b)]) => Value::Num(a.min(*b)),
+        _ => bail!(
+            "unknown function or bad arguments: {}({} args)",
+            name,
+            args.len()
+        ),
+    })
+}
+
+/// Evaluates config-defined derived fields (name -> expression) over
+/// `binary`. A field that fails to evaluate shows the error as its value.
+pub fn derive_fields(
+    binary: &BinaryFile,
+    fields: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    if fields.is_empty() {
+        return BTreeMap::new();
+    }
+    let ctx = binary_context(binary);
+    fields
+        .iter()
+        .map(|(name, source)| {
+            let value = eval_str(source, &ctx)
+                .map(|v| v.to_string())
+                .unwrap_or_else(|e| format!("<error: {}>", e));
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Builds the evaluation context exposing the analysis model of `binary`.
+pub fn binary_context(binary: &BinaryFile) -> Value {
+    let info = &binary.info;
+    let mut root = BTreeMap::new();
+    root.insert("name".to_string(), Value::Str(binary.name.clone()));
+    root.insert("format".to_string(), Value::Str(info.format.clone()));
+    root.insert("arch".to_string(), Value::Str(info.arch.clone()));
+    root.insert("size".to_string(), Value::Num(binary.data.len() as f64));
+    root.insert(
+        "entry_point".to_string(),
+        Value::Num(info.entry_point as f64),
+    );
+    root.insert(
+        "entropy".to_string(),
+        match &info.analysis {
+            Some(analysis) => Value::Num(analysis.entropy),
+            None => Value::Num(entropy::calculate_entropy(&binary.data)),
+        },
+    );
+
+    let security = BTreeMap::from([
+        ("pie".to_string(), Value::Bool(info.security.pie)),
+        ("nx".to_string(), Value::Bool(info.security.nx)),
+        (
+            "relro".to_string(),
+            Value::Bool(info.security.relro != Relro::None),
+        ),
+        (
+            "full_relro".to_string(),
+            Value::Bool(info.security.relro == Relro::Full),
+        ),
+        ("canary".to_string(), Value::Bool(info.security.canary)),
+        (
+            "fortify".to_string(),
+            Value::Bool(!info.security.fortified.is_empty()),
+        ),
+        (
+            "cfi".to_string(),
+            Value::List(
+                info.security
+                    .cfi
+                    .iter()
+                    .map(|c| Value::Str(c.to_string()))
+                    .collect(),
+            ),
+        ),
+        (
+            "stack_clash".to_string(),
+            Value::Bool(info.security.stack_clash),
+        ),
+    ]);
+    for (key, value) in &security {
+        root.insert(key.clone(), value.clone());
+    }
+    root.insert("security".to_string(), Value::Map(security));
+
+    let sections = info
+        .sections
+        .iter()
+        .map(|s| {
+            Value::Map(BTreeMap::from([
+                ("name".to_string(), Value::Str(s.name.clone())),
+                ("addr".to_string(), Value::Num(s.addr as f64)),
+                ("size".to_string(), Value::Num(s.size as f64)),
+                ("offset".to_string(), Value::Num(s.offset as f64)),
+                (
+                    "entropy".to_string(),
+                    Value::Num(entropy::calculate_entropy(binary.section_data(s))),
+                ),
+                (
+                    "md5".to_string(),
+                    s.hashes
+                        .as_ref()
+                        .map_or(Value::Null, |h| Value::Str(h.md5.clone())),
+                ),
+                (
+                    "sha256".to_string(),
+                    s.hashes
+                        .as_ref()
+                        .map_or(Value::Null, |h| Value::Str(h.sha256.clone())),
+                ),
+            ]))
+        })
+        .collect();
+    root.insert("sections".to_string(), Value::List(sections));
+
+    let symbols = info
+        .symbols
+        .iter()
+        .map(|s| {
+            Value::Map(BTreeMap::from([
+                ("name".to_string(), Value::Str(s.name.clone())),
+                ("addr".to_string(), Value::Num(s.addr as f64)),
+            ]))
+        })
+        .collect();
+    root.insert("symbols".to_string(), Value::List(symbols));
+    root.insert(
+        "strings".to_string(),
+        Value::List(info.strings.iter().cloned().map(Value::Str).collect()),
+    );
+    root.insert(
+        "yara".to_string(),
+        Value::List(
+            info.yara_matches
+                .iter()
+                .map(|m| Value::Str(m.rule.clone()))
+                .collect(),
+        ),
+    );
+    root.insert(
+        "imports".to_string(),
+        Value::List(
+            info.imports
+                .iter()
+                .map(|i| Value::Str(i.name.clone()))
+                .collect(),
+        ),
+    );
+    root.insert(
+        "stripped".to_string(),
+        Value::Bool(info.debug_info.as_ref().is_some_and(|d| d.stripped)),
+    );
+    root.insert(
+        "warnings".to_string(),
+        Value::List(
+            anomalies::warnings(info)
+                .into_iter()
+                .map(Value::Str)
+                .collect(),
+        ),
+    );
+    let mut weak_crypto: Vec<&str> = info.weak_crypto.iter().map(|w| w.algorithm).collect();
+    weak_crypto.dedup();
+    root.insert(
+        "weak_crypto".to_string(),
+        Value::List(
+            weak_crypto
+                .into_iter()
+                .map(|a| Value::Str(a.to_string()))
+                .collect(),
+        ),
+    );
+    let mut crypto: Vec<&str> = info.crypto_constants.iter().map(|c| c.algorithm).collect();
+    crypto.dedup();
+    root.insert(
+        "crypto".to_string(),
+        Value::List(
+            crypto
+                .into_iter()
+                .map(|a| Value::Str(a.to_string()))
+                .collect(),
+        ),
+    );
+    let mut anti_debug: Vec<&str> = info.anti_debug.iter().map(|i| i.technique).collect();
+    anti_debug.sort_unstable();
+    anti_debug.dedup();
+    root.insert(
+        "anti_debug".to_string(),
+        Value::List(
+            anti_debug
+                .into_iter()
+                .map(|t| Value::Str(t.to_string()))
+                .collect(),
+        ),
+    );
+    let mut anti_vm: Vec<&str> = info.anti_vm.iter().map(|i| i.technique).collect();
+    anti_vm.sort_unstable();
+    anti_vm.dedup();
+    root.insert(
+        "anti_vm".to_string(),
+        Value::List(
+            anti_vm
+                .into_iter()
+                .map(|t| Value::Str(t.to_string()))
+                .collect(),
+        ),
+    );
+    root.insert(
+        "threat_score".to_string(),
+        Value::Num(score::threat_score(info).score as f64),
+    );
+    root.insert(
+        "capabilities".to_string(),
+        Value::List(
+            info.capabilities
+                .iter()
+                .map(|c| Value::Str(c.name.clone()))
+                .collect(),
+        ),
+    );
+    root.insert(
+        "syscalls".to_string(),
+        Value::List(
+            syscalls::summary(&info.syscalls)
+                .into_keys()
+                .map(Value::Str)
+                .collect(),
+        ),
+    );
+
+    Value::Map(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> Value {
+        let text = Value::Map(BTreeMap::from([
+            ("name".to_string(), Value::Str(".text".to_string())),
+            ("entropy".to_string(), Value::Num(7.5)),
+        ]));
+        Value::Map(BTreeMap::from([
+            ("entropy".to_string(), Value::Num(7.4)),
+            ("format".to_string(), Value::Str("PE".to_string())),
+            ("sections".to_string(), Value::List(vec![text])),
+        ]))
+    }
+
+    #[test]
+    fn test_eval_packed_heuristic() {
+        let v = eval_str(
+            r#"entropy > 7.2 && sections[".text"].entropy > 7.0"#,
+            &ctx(),
+        )
+        .expect("eval failed");
+        assert_eq!(v, Value::Bool(true));
+    }
+
+    #[test]
+    fn test_eval_arithmetic_and_functions() {
+        assert_eq!(
+            eval_str("len(sections) * 2 + 0x10", &ctx()).unwrap(),
+            Value::Num(18.0)
+        );
+        assert_eq!(
+            eval_str(r#"format == "PE" && !contains(format, "ELF")"#, &ctx()).unwrap(),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_eval_missing_field_is_null() {
+        assert_eq!(eval_str("missing.field", &ctx()).unwrap(), Value::Null);
+        assert_eq!(eval_str("missing > 1", &ctx()).unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("entropy >").is_err());
+        assert!(parse("(entropy").is_err());
+        assert!(parse("entropy $ 2").is_err());
+    }
+}
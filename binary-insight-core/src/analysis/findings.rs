@@ -0,0 +1,494 @@
+use super::hashdb::Label;
+use super::yara::YaraMatch;
+use super::{attack, Relro};
+use crate::binary::BinaryInfo;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "info" => Some(Severity::Info),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            _ => None,
+        }
+    }
+}
+
+/// The kind of pass that raised a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// Missing mitigations and unsafe code or mappings.
+    Security,
+    /// Malformed or unusual headers, sections and entry points.
+    Anomaly,
+    Capability,
+    Evasion,
+    Crypto,
+    Yara,
+}
+
+impl Category {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Security => "security",
+            Category::Anomaly => "anomaly",
+            Category::Capability => "capability",
+            Category::Evasion => "evasion",
+            Category::Crypto => "crypto",
+            Category::Yara => "yara",
+        }
+    }
+}
+
+/// Something an analysis pass flagged about the binary.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Stable identifier, e.g. `weak_crypto.md5` or `yara.<rule>`.
+    pub id: String,
+    pub title: String,
+    pub severity: Severity,
+    pub category: Category,
+    /// What was matched, e.g. an import name or constant offset.
+    pub evidence: String,
+    /// Virtual addresses the finding points at, where known.
+    pub addresses: Vec<u64>,
+    /// MITRE ATT&CK technique ids, e.g. `T1055`.
+    pub attack: Vec<String>,
+}
+
+impl Finding {
+    /// The part of `id` before the first `.`, e.g. `weak_crypto` for
+    /// `weak_crypto.md5`; severity overrides are keyed by it.
+    pub fn kind(&self) -> &str {
+        self.id.split('.').next().unwrap_or_default()
+    }
+}
+
+/// Findings from the passes already stored in `info`, most severe first.
+pub fn collect(info: &BinaryInfo) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = info
+        .yara_matches
+        .iter()
+        .map(|m| Finding {
+            id: format!("yara.{}", m.rule),
+            title: match m.meta("description") {
+                Some(description) => format!("YARA rule {} matched: {}", m.rule, description),
+                None => format!("YARA rule {} matched", m.rule),
+            },
+            severity: m.score().map_or(Severity::High, yara_severity),
+            category: Category::Yara,
+            evidence: yara_evidence(m),
+            addresses: m
+                .offsets()
+                .into_iter()
+                .filter_map(|offset| info.offset_to_addr(offset))
+                .collect(),
+            attack: Vec::new(),
+        })
+        .collect();
+    if info.security.loadable {
+        findings.extend(missing_mitigations(info));
+    }
+    findings.extend(info.weak_crypto.iter().map(|usage| {
+        Finding {
+            id: format!("weak_crypto.{}", usage.algorithm.to_lowercase()),
+            title: format!("Weak algorithm {} in use", usage.algorithm),
+            severity: Severity::Info,
+            category: Category::Crypto,
+            evidence: match usage.offset {
+                Some(offset) => format!("{} @ 0x{:x}", usage.detail, offset),
+                None => usage.detail.clone(),
+            },
+            addresses: usage
+                .offset
+                .and_then(|offset| info.offset_to_addr(offset))
+                .into_iter()
+                .collect(),
+            attack: Vec::new(),
+        }
+    }));
+    findings.extend(info.private_keys.iter().map(|key| Finding {
+        id: format!(
+            "private_key.{}",
+            key.kind.split_whitespace().next().unwrap_or_default().to_lowercase()
+        ),
+        title: format!("Embedded {}", key.kind),
+        severity: Severity::High,
+        category: Category::Crypto,
+        evidence: format!("{} @ 0x{:x}", key.encoding.name(), key.offset),
+        addresses: info.offset_to_addr(key.offset).into_iter().collect(),
+        attack: Vec::new(),
+    }));
+    findings.extend(info.insecure_functions.iter().map(|usage| Finding {
+        id: format!("insecure_libc.{}", usage.function),
+        title: format!("Insecure function {} in use", usage.function),
+        severity: usage.severity,
+        category: Category::Security,
+        evidence: usage.reason.to_string(),
+        addresses: usage.addr.into_iter().collect(),
+        attack: Vec::new(),
+    }));
+    findings.extend(info.vulnerabilities.iter().map(|vuln| Finding {
+        id: format!("vuln.{}", vuln.id),
+        title: format!(
+            "{} {} is affected by {}",
+            vuln.component, vuln.version, vuln.id
+        ),
+        severity: vuln.severity,
+        category: Category::Security,
+        evidence: match &vuln.fixed {
+            Some(fixed) => format!("{} (fixed in {})", vuln.summary, fixed),
+            None => vuln.summary.clone(),
+        },
+        addresses: Vec::new(),
+        attack: Vec::new(),
+    }));
+    if let Some(record) = &info.hash_record {
+        let severity = match record.label {
+            Some(Label::Malicious) => Some(Severity::High),
+            Some(Label::Suspicious) => Some(Severity::Medium),
+            _ => None,
+        };
+        if let (Some(severity), Some(label)) = (severity, record.label) {
+            findings.push(Finding {
+                id: format!("hashdb.{}", label.name()),
+                title: format!("Labeled {} in the local hash database", label.name()),
+                severity,
+                category: Category::Security,
+                evidence: if record.note.is_empty() {
+                    record.sha256.clone()
+                } else {
+                    record.note.clone()
+                },
+                addresses: Vec::new(),
+                attack: Vec::new(),
+            });
+        }
+    }
+    findings.extend(info.entry_anomalies.iter().map(|anomaly| Finding {
+        id: "entry_point".to_string(),
+        title: "Entry point anomaly".to_string(),
+        severity: Severity::Medium,
+        category: Category::Anomaly,
+        evidence: anomaly.description(),
+        addresses: vec![info.entry_point],
+        attack: Vec::new(),
+    }));
+    findings.extend(info.anomalies.iter().map(|anomaly| Finding {
+        id: "anomaly".to_string(),
+        title: "Structural anomaly".to_string(),
+        severity: Severity::Low,
+        category: Category::Anomaly,
+        evidence: anomaly.description(),
+        addresses: Vec::new(),
+        attack: Vec::new(),
+    }));
+    if let Some(headers) = &info.pe_headers {
+        if headers.checksum_mismatch() {
+            findings.push(Finding {
+                id: "pe.checksum".to_string(),
+                title: "PE checksum does not match the file".to_string(),
+                severity: Severity::Medium,
+                category: Category::Anomaly,
+                evidence: format!(
+                    "stored 0x{:08x}, computed 0x{:08x}",
+                    headers.stored_checksum, headers.computed_checksum
+                ),
+                addresses: Vec::new(),
+                attack: Vec::new(),
+            });
+        }
+        findings.extend(headers.issues.iter().map(|issue| Finding {
+            id: "pe.header".to_string(),
+            title: "Inconsistent PE header".to_string(),
+            severity: Severity::Low,
+            category: Category::Anomaly,
+            evidence: issue.clone(),
+            addresses: Vec::new(),
+            attack: Vec::new(),
+        }));
+        findings.extend(headers.timestamps.issues.iter().map(|issue| Finding {
+            id: "pe.timestamp".to_string(),
+            title: "Implausible PE timestamp".to_string(),
+            severity: Severity::Low,
+            category: Category::Anomaly,
+            evidence: issue.clone(),
+            addresses: Vec::new(),
+            attack: vec!["T1070.006".to_string()],
+        }));
+    }
+    if let Some(module) = &info.kernel_module {
+        findings.extend(module.warnings.iter().map(|warning| Finding {
+            id: "kernel_module".to_string(),
+            title: "Kernel module taints or bypasses signing".to_string(),
+            severity: Severity::Low,
+            category: Category::Security,
+            evidence: warning.clone(),
+            addresses: Vec::new(),
+            attack: Vec::new(),
+        }));
+    }
+    if let Some(linker) = &info.linker {
+        findings.extend(linker.warnings.iter().map(|warning| Finding {
+            id: "linker".to_string(),
+            title: "Unusual GOT/PLT layout".to_string(),
+            severity: Severity::Low,
+            category: Category::Anomaly,
+            evidence: warning.clone(),
+            addresses: Vec::new(),
+            attack: Vec::new(),
+        }));
+    }
+    findings.extend(info.security.rwx.iter().map(|region| Finding {
+        id: "rwx".to_string(),
+        title: if region.deferred {
+            "Writable mapping that may become executable".to_string()
+        } else {
+            "Writable and executable mapping".to_string()
+        },
+        severity: Severity::High,
+        category: Category::Security,
+        evidence: format!("{} (0x{:x} bytes)", region.name, region.size),
+        addresses: vec![region.addr],
+        attack: Vec::new(),
+    }));
+    if let Some(macho) = &info.security.macho {
+        findings.extend(macho.rpath_dylibs.iter().map(|lib| Finding {
+            id: "macho.rpath_dylib".to_string(),
+            title: "Dylib loaded via @rpath (hijackable search path)".to_string(),
+            severity: Severity::Low,
+            category: Category::Security,
+            evidence: lib.clone(),
+            addresses: Vec::new(),
+            attack: vec!["T1574.004".to_string()],
+        }));
+    }
+    findings.extend(info.anti_debug.iter().map(|indicator| Finding {
+        id: format!("anti_debug.{}", indicator.technique),
+        title: "Anti-debugging check".to_string(),
+        severity: Severity::Medium,
+        category: Category::Evasion,
+        evidence: indicator.description.clone(),
+        addresses: indicator.addr.into_iter().collect(),
+        attack: vec![attack::DEBUGGER_EVASION.to_string()],
+    }));
+    findings.extend(info.anti_vm.iter().map(|indicator| Finding {
+        id: format!("anti_vm.{}", indicator.technique),
+        title: "VM/sandbox evasion check".to_string(),
+        severity: Severity::Medium,
+        category: Category::Evasion,
+        evidence: indicator.description.clone(),
+        addresses: indicator.addr.into_iter().collect(),
+        attack: vec![attack::SANDBOX_EVASION.to_string()],
+    }));
+    findings.extend(info.capabilities.iter().map(|capability| Finding {
+        id: format!("capability.{}", capability.name.replace(' ', "_")),
+        title: format!("Capability: {}", capability.name),
+        severity: Severity::Low,
+        category: Category::Capability,
+        evidence: capability.evidence.join(", "),
+        addresses: Vec::new(),
+        attack: capability.attack.clone(),
+    }));
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+/// Hardening the binary was built without.
+fn missing_mitigations(info: &BinaryInfo) -> Vec<Finding> {
+    let security = &info.security;
+    let missing = |id: &str, title: &str, severity: Severity, evidence: &str| Finding {
+        id: format!("mitigation.{}", id),
+        title: title.to_string(),
+        severity,
+        category: Category::Security,
+        evidence: evidence.to_string(),
+        addresses: Vec::new(),
+        attack: Vec::new(),
+    };
+    let mut findings = Vec::new();
+    if !security.nx {
+        findings.push(missing(
+            "nx",
+            "Executable stack or data (NX disabled)",
+            Severity::Medium,
+            match info.format.as_str() {
+                "PE" => "NX_COMPAT not set",
+                "Mach-O" => "MH_ALLOW_STACK_EXECUTION set",
+                _ => "no non-executable PT_GNU_STACK",
+            },
+        ));
+    }
+    if !security.pie {
+        findings.push(missing(
+            "pie",
+            "Fixed load address (no ASLR)",
+            Severity::Low,
+            match info.format.as_str() {
+                "PE" => "DYNAMIC_BASE not set",
+                "Mach-O" => "MH_PIE not set",
+                _ => "ET_EXEC executable",
+            },
+        ));
+    }
+    if !security.canary {
+        findings.push(missing(
+            "canary",
+            "No stack canary",
+            Severity::Low,
+            "no stack protector found",
+        ));
+    }
+    if info.format == "ELF" && security.relro == Relro::None {
+        findings.push(missing(
+            "relro",
+            "No RELRO",
+            Severity::Low,
+            "GOT stays writable",
+        ));
+    }
+    findings
+}
+
+/// Severity for a rule's `score` meta, on the usual 0-100 scale.
+fn yara_severity(score: i64) -> Severity {
+    match score {
+        75.. => Severity::High,
+        50..=74 => Severity::Medium,
+        25..=49 => Severity::Low,
+        _ => Severity::Info,
+    }
+}
+
+/// Match count followed by the tags, author and reference the rule gives.
+fn yara_evidence(m: &YaraMatch) -> String {
+    let mut evidence = format!("{} string match(es)", m.strings.len());
+    if !m.tags.is_empty() {
+        evidence.push_str(&format!("; tags: {}", m.tags.join(", ")));
+    }
+    for key in ["author", "reference"] {
+        if let Some(value) = m.meta(key) {
+            evidence.push_str(&format!("; {}: {}", key, value));
+        }
+    }
+    evidence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_mitigations() {
+        let mut info = BinaryInfo {
+            format: "ELF".to_string(),
+            ..Default::default()
+        };
+        // Object files are not hardened; nothing to report.
+        assert!(collect(&info).is_empty());
+
+        info.security.loadable = true;
+        let findings = collect(&info);
+        let ids: Vec<&str> = findings.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "mitigation.nx",
+                "mitigation.pie",
+                "mitigation.canary",
+                "mitigation.relro"
+            ]
+        );
+        assert!(findings.iter().all(|f| f.category == Category::Security));
+    }
+
+    #[test]
+    fn test_module_and_linker_warnings() {
+        let info = BinaryInfo {
+            kernel_module: Some(crate::analysis::kernel::KernelModuleInfo {
+                warnings: vec!["Module is not signed".to_string()],
+                ..Default::default()
+            }),
+            linker: Some(crate::analysis::linker::LinkerSummary {
+                warnings: vec!["GOT is mapped executable".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let findings = collect(&info);
+        let found: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|f| (f.id.as_str(), f.evidence.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("kernel_module", "Module is not signed"),
+                ("linker", "GOT is mapped executable")
+            ]
+        );
+        assert_eq!(
+            crate::analysis::anomalies::warnings(&info),
+            ["Module is not signed", "GOT is mapped executable"]
+        );
+    }
+
+    #[test]
+    fn test_yara_meta() {
+        let meta = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        let info = BinaryInfo {
+            yara_matches: vec![
+                YaraMatch {
+                    rule: "Packed".to_string(),
+                    tags: vec!["packer".to_string()],
+                    meta: meta(&[
+                        ("description", "UPX packed"),
+                        ("author", "me"),
+                        ("score", "40"),
+                    ]),
+                    strings: Vec::new(),
+                },
+                YaraMatch {
+                    rule: "Plain".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let findings = collect(&info);
+        assert_eq!(findings[0].title, "YARA rule Plain matched");
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(findings[1].title, "YARA rule Packed matched: UPX packed");
+        assert_eq!(findings[1].severity, Severity::Low);
+        assert_eq!(
+            findings[1].evidence,
+            "0 string match(es); tags: packer; author: me"
+        );
+    }
+}
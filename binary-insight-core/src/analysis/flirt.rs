@@ -0,0 +1,344 @@
+use crate::binary::{BinaryInfo, SectionInfo};
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Bytes covered by the leading pattern of a `.pat` line.
+const PATTERN_LEN: usize = 32;
+/// Signatures whose first bytes are wildcards are only tried at this alignment.
+const FALLBACK_ALIGN: usize = 16;
+const PREFIX_LEN: usize = 4;
+
+/// A function signature in IDA FLAIR `.pat` form: a leading byte pattern,
+/// a CRC16 over the following bytes, the function length and trailing bytes.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// Leading bytes; `None` is a wildcard (relocated or variable byte).
+    pub pattern: Vec<Option<u8>>,
+    pub crc_len: usize,
+    pub crc: u16,
+    pub length: u64,
+    /// Public names and their offsets from the function start.
+    pub names: Vec<(u64, String)>,
+    /// Bytes after the CRC region, as (offset, value).
+    pub tail: Vec<(u64, Option<u8>)>,
+    /// Pack the signature came from.
+    pub library: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SignatureMatch {
+    pub name: String,
+    pub addr: u64,
+    pub library: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SignatureSet {
+    pub signatures: Vec<Signature>,
+}
+
+impl SignatureSet {
+    /// Loads a `.pat` file, or every `.pat` file in a directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut set = SignatureSet::default();
+        if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "pat"))
+                .collect();
+            files.sort();
+            for file in files {
+                set.add_file(&file)?;
+            }
+        } else {
+            set.add_file(path)?;
+        }
+        Ok(set)
+    }
+
+    fn add_file(&mut self, path: &Path) -> Result<()> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read signature file {}", path.display()))?;
+        let library = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let parsed =
+            parse_pat(&text, &library).with_context(|| format!("Invalid {}", path.display()))?;
+        self.signatures.extend(parsed);
+        Ok(())
+    }
+
+    pub fn extend(&mut self, other: SignatureSet) {
+        self.signatures.extend(other.signatures);
+    }
+}
+
+pub fn parse_pat(text: &str, library: &str) -> Result<Vec<Signature>> {
+    let mut signatures = Vec::new();
+    for (lineno, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if line == "---" {
+            break;
+        }
+        let signature =
+            parse_pat_line(line, library).with_context(|| format!("Line {}", lineno + 1))?;
+        signatures.push(signature);
+    }
+    Ok(signatures)
+}
+
+fn parse_pat_line(line: &str, library: &str) -> Result<Signature> {
+    let mut tokens = line.split_whitespace();
+    let mut next = |what: &str| tokens.next().ok_or_else(|| anyhow!("missing {}", what));
+    let pattern = parse_hex_pattern(next("pattern")?)?;
+    let crc_len = usize::from_str_radix(next("CRC length")?, 16)?;
+    let crc = u16::from_str_radix(next("CRC")?, 16)?;
+    let length = u64::from_str_radix(next("function length")?, 16)?;
+
+    let mut names = Vec::new();
+    let mut tail = Vec::new();
+    let rest: Vec<&str> = tokens.collect();
+    let mut i = 0;
+    while i < rest.len() {
+        let token = rest[i];
+        if let Some(offset) = token.strip_prefix(':') {
+            // `:0010@` marks a local name; `:-0010` a negative offset we cannot place.
+            let offset = offset.trim_end_matches('@');
+            let name = rest
+                .get(i + 1)
+                .ok_or_else(|| anyhow!("name missing after {}", token))?;
+            if let Ok(offset) = u64::from_str_radix(offset, 16) {
+                names.push((offset, name.to_string()));
+            }
+            i += 2;
+        } else if token.starts_with('^') {
+            // Referenced names cannot be verified without relocation info.
+            i += 2;
+        } else {
+            let start = (PATTERN_LEN + crc_len) as u64;
+            tail.extend(
+                parse_hex_pattern(token)?
+                    .into_iter()
+                    .enumerate()
+                    .map(|(j, b)| (start + j as u64, b)),
+            );
+            i += 1;
+        }
+    }
+    if names.is_empty() {
+        bail!("signature has no public name");
+    }
+
+    Ok(Signature {
+        pattern,
+        crc_len,
+        crc,
+        length,
+        names,
+        tail,
+        library: library.to_string(),
+    })
+}
+
+fn parse_hex_pattern(s: &str) -> Result<Vec<Option<u8>>> {
+    if !s.len().is_multiple_of(2) {
+        bail!("odd-length byte pattern {}", s);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| match &s[i..i + 2] {
+            ".." => Ok(None),
+            hex => Ok(Some(u8::from_str_radix(hex, 16)?)),
+        })
+        .collect()
+}
+
+/// CRC16 as computed by FLAIR's `sigmake` (CCITT, reflected, byte-swapped).
+pub fn crc16(data: &[u8]) -> u16 {
+    if data.is_empty() {
+        return 0;
+    }
+    let mut crc: u32 = 0xffff;
+    for &byte in data {
+        let mut b = byte as u32;
+        for _ in 0..8 {
+            crc = if (crc ^ b) & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+            b >>= 1;
+        }
+    }
+    let crc = !crc & 0xffff;
+    (((crc << 8) | (crc >> 8)) & 0xffff) as u16
+}
+
+fn matches_at(sig: &Signature, code: &[u8]) -> bool {
+    if (code.len() as u64) < sig.length.max(sig.pattern.len() as u64) {
+        return false;
+    }
+    let pattern_ok = sig
+        .pattern
+        .iter()
+        .zip(code)
+        .all(|(p, b)| p.is_none_or(|p| p == *b));
+    if !pattern_ok {
+        return false;
+    }
+    let crc_start = sig.pattern.len();
+    let Some(crc_bytes) = code.get(crc_start..crc_start + sig.crc_len) else {
+        return false;
+    };
+    if crc16(crc_bytes) != sig.crc {
+        return false;
+    }
+    sig.tail
+        .iter()
+        .all(|&(off, b)| b.is_none_or(|b| code.get(off as usize) == Some(&b)))
+}
+
+/// Scans executable sections for signature matches and returns the named functions.
+pub fn match_signatures(data: &[u8], info: &BinaryInfo, set: &SignatureSet) -> Vec<SignatureMatch> {
+    // Index signatures by their first bytes so most offsets cost one lookup.
+    let mut by_prefix: HashMap<[u8; PREFIX_LEN], Vec<&Signature>> = HashMap::new();
+    let mut fallback = Vec::new();
+    for sig in &set.signatures {
+        let prefix: Option<Vec<u8>> = sig.pattern.iter().take(PREFIX_LEN).copied().collect();
+        match prefix {
+            Some(p) if p.len() == PREFIX_LEN => by_prefix
+                .entry(p.try_into().unwrap())
+                .or_default()
+                .push(sig),
+            _ => fallback.push(sig),
+        }
+    }
+
+    let mut matches = Vec::new();
+    for section in code_sections(info) {
+        let code = &data[section.file_range(data.len())];
+        let mut pos = 0;
+        while pos < code.len() {
+            let window = &code[pos..];
+            let indexed = window
+                .get(..PREFIX_LEN)
+                .and_then(|p| by_prefix.get(p))
+                .into_iter()
+                .flatten();
+            let aligned = (section.addr as usize + pos).is_multiple_of(FALLBACK_ALIGN);
+            let candidates = indexed.chain(fallback.iter().filter(|_| aligned));
+            let mut matched = None;
+            for sig in candidates {
+                if matches_at(sig, window) {
+                    matched = Some(*sig);
+                    break;
+                }
+            }
+            match matched {
+                Some(sig) => {
+                    let addr = section.addr + pos as u64;
+                    for (offset, name) in &sig.names {
+                        matches.push(SignatureMatch {
+                            name: name.clone(),
+                            addr: addr + offset,
+                            library: sig.library.clone(),
+                        });
+                    }
+                    pos += sig.length.max(1) as usize;
+                }
+                None => pos += 1,
+            }
+        }
+    }
+    matches
+}
+
+fn code_sections(info: &BinaryInfo) -> Vec<&SectionInfo> {
+    let code: Vec<_> = info
+        .sections
+        .iter()
+        .filter(|s| s.size > 0 && (s.name.contains("text") || s.name == "CODE"))
+        .collect();
+    if !code.is_empty() {
+        return code;
+    }
+    // Raw and firmware inputs have no named code sections; scan everything.
+    info.sections.iter().filter(|s| s.size > 0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function() -> Vec<u8> {
+        let mut code: Vec<u8> = (0u8..48).collect();
+        code[0] = 0x55;
+        code[1] = 0x48;
+        code[2] = 0x89;
+        code[3] = 0xe5;
+        code
+    }
+
+    fn pat_line(code: &[u8]) -> String {
+        let mut pattern: String = code[..32].iter().map(|b| format!("{:02X}", b)).collect();
+        pattern.replace_range(8..10, "..");
+        format!(
+            "{} 08 {:04X} {:04X} :0000 my_memcpy :0010 inner_label {}",
+            pattern,
+            crc16(&code[32..40]),
+            code.len(),
+            "....2A2B"
+        )
+    }
+
+    #[test]
+    fn test_crc16_known_value() {
+        assert_eq!(crc16(b""), 0);
+        assert_eq!(crc16(b"123456789"), 0x6e90);
+    }
+
+    #[test]
+    fn test_parse_and_match() {
+        let code = function();
+        let text = format!("{}\n---\n", pat_line(&code));
+        let sigs = parse_pat(&text, "libc").expect("parse failed");
+        assert_eq!(sigs.len(), 1);
+        assert_eq!(sigs[0].pattern[4], None);
+        assert_eq!(sigs[0].names.len(), 2);
+        assert_eq!(sigs[0].tail.len(), 4);
+
+        let mut data = vec![0xccu8; 0x10];
+        data.extend_from_slice(&code);
+        let mut info = BinaryInfo {
+            sections: vec![SectionInfo {
+                name: ".text".to_string(),
+                addr: 0x401000,
+                size: data.len() as u64,
+                offset: 0,
+                hashes: None,
+                zero_fill: false,
+            }],
+            ..Default::default()
+        };
+        let set = SignatureSet { signatures: sigs };
+        let matches = match_signatures(&data, &info, &set);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].name, "my_memcpy");
+        assert_eq!(matches[0].addr, 0x401010);
+        assert_eq!(matches[1].addr, 0x401020);
+
+        info.sections[0].size = u64::MAX;
+        assert_eq!(match_signatures(&data, &info, &set).len(), 2);
+
+        data[0x10 + 35] ^= 0xff;
+        assert!(match_signatures(&data, &info, &set).is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+use crate::binary::{BinaryFile, SectionInfo};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Functions decoded before discovery gives up on very large binaries.
+const MAX_FUNCTIONS: usize = 50_000;
+/// Prologue candidates are only considered at this alignment.
+const PROLOGUE_ALIGN: u64 = 16;
+const RUNTIME_FUNCTION_SIZE: usize = 12;
+
+/// Where a function start was learned from, strongest evidence first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FunctionSource {
+    Symbol,
+    Export,
+    Entry,
+    /// x64 `.pdata` RUNTIME_FUNCTION entry.
+    Pdata,
+    /// `.eh_frame` FDE.
+    EhFrame,
+    /// Target of a direct call from another function.
+    Call,
+    /// Matched a common prologue byte pattern.
+    Prologue,
+}
+
+impl FunctionSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FunctionSource::Symbol => "symbol",
+            FunctionSource::Export => "export",
+            FunctionSource::Entry => "entry",
+            FunctionSource::Pdata => "pdata",
+            FunctionSource::EhFrame => "eh_frame",
+            FunctionSource::Call => "call",
+            FunctionSource::Prologue => "prologue",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionInfo {
+    pub start: u64,
+    pub size: u64,
+    /// Symbol name, or `sub_<addr>` when the function is unnamed.
+    pub name: String,
+    pub source: FunctionSource,
+}
+
+/// Finds function starts from symbols, exports, the entry point, unwind
+/// tables and prologues, then follows direct calls recursively. Sizes come
+/// from unwind tables when present, otherwise from the decoded CFG.
+pub fn discover_functions(file: &BinaryFile) -> Vec<FunctionInfo> {
+    let info = &file.info;
+    let in_code = |addr: u64| info.section_for_addr(addr).is_some_and(|s| s.is_code());
+
+    // start -> (source, size known from metadata)
+    let mut seeds: BTreeMap<u64, (FunctionSource, Option<u64>)> = BTreeMap::new();
+    let mut add = |addr: u64, source: FunctionSource, size: Option<u64>| {
+        let entry = seeds.entry(addr).or_insert((source, size));
+        entry.0 = entry.0.min(source);
+        entry.1 = entry.1.or(size);
+    };
+    for sym in info.symbols.iter().filter(|s| !s.name.is_empty()) {
+        add(sym.addr, FunctionSource::Symbol, None);
+    }
+    for export in &info.exports {
+        add(export.addr, FunctionSource::Export, None);
+    }
+    add(info.entry_point, FunctionSource::Entry, None);
+    for (start, size) in pdata_functions(file) {
+        add(start, FunctionSource::Pdata, Some(size));
+    }
+    for (start, size) in eh_frame_functions(file) {
+        add(start, FunctionSource::EhFrame, Some(size));
+    }
+    seeds.retain(|&addr, _| in_code(addr));
+
+    let mut found: BTreeMap<u64, (FunctionSource, u64)> = BTreeMap::new();
+    descend(file, &mut seeds, &mut found, &in_code);
+
+    // Prologues in bytes no discovered function covers are new roots.
+    let covered: Vec<(u64, u64)> = found.iter().map(|(&s, &(_, size))| (s, s + size)).collect();
+    let is_covered = |addr: u64| {
+        let idx = covered.partition_point(|&(s, _)| s <= addr);
+        idx > 0 && addr < covered[idx - 1].1
+    };
+    for addr in prologue_candidates(file) {
+        if !is_covered(addr) {
+            seeds.insert(addr, (FunctionSource::Prologue, None));
+        }
+    }
+    descend(file, &mut seeds, &mut found, &in_code);
+
+    let names: BTreeMap<u64, &str> = info
+        .symbols
+        .iter()
+        .chain(&info.exports)
+        .filter(|s| !s.name.is_empty())
+        .map(|s| (s.addr, s.name.as_str()))
+        .collect();
+    found
+        .into_iter()
+        .map(|(start, (source, size))| FunctionInfo {
+            start,
+            size,
+            name: names
+                .get(&start)
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("sub_{:x}", start)),
+            source,
+        })
+        .collect()
+}
+
+/// Decodes every pending seed, queueing direct call targets as new seeds.
+fn descend(
+    file: &BinaryFile,
+    seeds: &mut BTreeMap<u64, (FunctionSource, Option<u64>)>,
+    found: &mut BTreeMap<u64, (FunctionSource, u64)>,
+    in_code: &dyn Fn(u64) -> bool,
+) {
+    while let Some((addr, (source, size))) = seeds.pop_first() {
+        if found.contains_key(&addr) || found.len() >= MAX_FUNCTIONS {
+            continue;
+        }
+        // Stop decoding at the next known start to avoid sweeping whole sections.
+        let next_start = seeds
+            .range(addr + 1..)
+            .next()
+            .map(|(&a, _)| a)
+            .into_iter()
+            .chain(found.range(addr + 1..).next().map(|(&a, _)| a))
+            .min()
+            .unwrap_or(u64::MAX);
+        let Ok(cfg) = file.function_cfg_until(addr, next_start) else {
+            // Keep metadata-backed starts even when they do not decode.
+            if let Some(size) = size {
+                found.insert(addr, (source, size));
+            }
+            continue;
+        };
+        for &target in cfg.blocks.iter().flat_map(|b| &b.calls) {
+            if in_code(target) && !found.contains_key(&target) {
+                seeds.entry(target).or_insert((FunctionSource::Call, None));
+            }
+        }
+        let extent = cfg.blocks.iter().map(|b| b.end).max().unwrap_or(addr) - addr;
+        found.insert(addr, (source, size.unwrap_or(extent)));
+    }
+}
+
+fn section_named<'a>(file: &'a BinaryFile, name: &str) -> Option<(&'a SectionInfo, &'a [u8])> {
+    let section = file.info.sections.iter().find(|s| s.name == name)?;
+    Some((section, file.section_data(section)))
+}
+
+/// x64 RUNTIME_FUNCTION entries: begin and end RVAs of every non-leaf function.
+fn pdata_functions(file: &BinaryFile) -> Vec<(u64, u64)> {
+    if file.info.format != "PE" || file.info.arch != "x86_64" {
+        return Vec::new();
+    }
+    let Some((_, data)) = section_named(file, ".pdata") else {
+        return Vec::new();
+    };
+    data.chunks_exact(RUNTIME_FUNCTION_SIZE)
+        .map(|entry| {
+            let begin = u32::from_le_bytes(entry[0..4].try_into().unwrap()) as u64;
+            let end = u32::from_le_bytes(entry[4..8].try_into().unwrap()) as u64;
+            (begin, end.saturating_sub(begin))
+        })
+        .filter(|&(begin, size)| begin != 0 && size != 0)
+        .collect()
+}
+
+/// Address ranges of the FDEs in `.eh_frame`.
+fn eh_frame_functions(file: &BinaryFile) -> Vec<(u64, u64)> {
+    let Some((section, data)) = section_named(file, ".eh_frame") else {
+        return Vec::new();
+    };
+    let ptr_size = if file.info.arch == "x86" { 4 } else { 8 };
+    let mut functions = Vec::new();
+    // CIE offset -> FDE pointer encoding
+    let mut encodings: BTreeMap<usize, u8> = BTreeMap::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let mut reader = Reader { data, pos };
+        let Some(length) = reader.u32() else { break };
+        if length == 0 {
+            break;
+        }
+        // 64-bit DWARF lengths are not produced for .eh_frame in practice.
+        if length == u32::MAX {
+            break;
+        }
+        let start = reader.pos;
+        let next = start + length as usize;
+        if next > data.len() {
+            break;
+        }
+        let Some(id) = reader.u32() else { break };
+        if id == 0 {
+            if let Some(encoding) = parse_cie(&mut reader) {
+                encodings.insert(pos, encoding);
+            }
+        } else if let Some(&encoding) = start
+            .checked_sub(id as usize)
+            .and_then(|cie| encodings.get(&cie))
+        {
+            let field_addr = section.addr + reader.pos as u64;
+            if let (Some(begin), Some(range)) = (
+                reader.pointer(encoding, field_addr, ptr_size),
+                reader.pointer(encoding & 0x0f, 0, ptr_size),
+            ) {
+                if begin != 0 && range != 0 {
+                    functions.push((begin, range));
+                }
+            }
+        }
+        pos = next;
+    }
+    functions
+}
+
+/// Returns the FDE pointer encoding declared by a CIE (absolute pointers when
+/// the augmentation has no `R`).
+fn parse_cie(reader: &mut Reader) -> Option<u8> {
+    let version = reader.u8()?;
+    let augmentation = reader.cstr()?;
+    reader.uleb()?; // code alignment
+    reader.sleb()?; // data alignment
+    if version == 1 {
+        reader.u8()?;
+    } else {
+        reader.uleb()?;
+    }
+    let mut encoding = 0;
+    if augmentation.first() == Some(&b'z') {
+        reader.uleb()?;
+        for &c in &augmentation[1..] {
+            match c {
+                b'R' => encoding = reader.u8()?,
+                b'L' => {
+                    reader.u8()?;
+                }
+                b'P' => {
+                    let personality = reader.u8()?;
+                    reader.pointer(personality & 0x0f, 0, 8)?;
+                }
+                _ => {}
+            }
+        }
+    }
+    Some(encoding)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        let bytes = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    fn cstr(&mut self) -> Option<Vec<u8>> {
+        let len = self.data.get(self.pos..)?.iter().position(|&b| b == 0)?;
+        let s = self.take(len)?.to_vec();
+        self.pos += 1;
+        Some(s)
+    }
+
+    fn uleb(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn sleb(&mut self) -> Option<i64> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as i64) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Some(value);
+            }
+            if shift >= 64 {
+                return None;
+            }
+        }
+    }
+
+    /// Reads a `DW_EH_PE_*` encoded pointer; `field_addr` is the address of
+    /// the field for PC-relative encodings.
+    fn pointer(&mut self, encoding: u8, field_addr: u64, ptr_size: usize) -> Option<u64> {
+        let value = match encoding & 0x0f {
+            0x00 if ptr_size == 4 => self.u32()? as u64,
+            0x00 | 0x04 | 0x0c => u64::from_le_bytes(self.take(8)?.try_into().ok()?),
+            0x01 => self.uleb()?,
+            0x02 => u16::from_le_bytes(self.take(2)?.try_into().ok()?) as u64,
+            0x03 => self.u32()? as u64,
+            0x09 => self.sleb()? as u64,
+            0x0a => i16::from_le_bytes(self.take(2)?.try_into().ok()?) as i64 as u64,
+            0x0b => i32::from_le_bytes(self.take(4)?.try_into().ok()?) as i64 as u64,
+            _ => return None,
+        };
+        Some(match encoding & 0x70 {
+            0x10 => field_addr.wrapping_add(value),
+            _ => value,
+        })
+    }
+}
+
+/// Aligned addresses in code sections that start with a typical prologue.
+fn prologue_candidates(file: &BinaryFile) -> Vec<u64> {
+    let patterns: &[&[Option<u8>]] = match file.info.arch.as_str() {
+        // push rbp; mov rbp, rsp / endbr64
+        "x86_64" => &[
+            &[Some(0x55), Some(0x48), Some(0x89), Some(0xe5)],
+            &[Some(0xf3), Some(0x0f), Some(0x1e), Some(0xfa)],
+        ],
+        // push ebp; mov ebp, esp (both encodings) / endbr32
+        "x86" => &[
+            &[Some(0x55), Some(0x89), Some(0xe5)],
+            &[Some(0x55), Some(0x8b), Some(0xec)],
+            &[Some(0xf3), Some(0x0f), Some(0x1e), Some(0xfb)],
+        ],
+        // stp x29, x30, [sp, #-N]! / paciasp
+        "aarch64" => &[
+            &[Some(0xfd), Some(0x7b), None, Some(0xa9)],
+            &[Some(0x3f), Some(0x23), Some(0x03), Some(0xd5)],
+        ],
+        _ => return Vec::new(),
+    };
+    let mut candidates = BTreeSet::new();
+    for section in file.info.sections.iter().filter(|s| s.is_code()) {
+        let data = file.section_data(section);
+        let first = section.addr.next_multiple_of(PROLOGUE_ALIGN) - section.addr;
+        for offset in (first as usize..data.len()).step_by(PROLOGUE_ALIGN as usize) {
+            let window = &data[offset..];
+            let matched = patterns.iter().any(|p| {
+                window.len() >= p.len()
+                    && p.iter().zip(window).all(|(b, w)| b.is_none_or(|b| b == *w))
+            });
+            if matched {
+                candidates.insert(section.addr + offset as u64);
+            }
+        }
+    }
+    candidates.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::SectionInfo;
+
+    #[test]
+    fn test_discovers_call_targets_and_prologues() {
+        let mut code = vec![0xccu8; 0x30];
+        // 0x1000: call 0x1010; ret
+        code[..6].copy_from_slice(&[0xe8, 0x0b, 0x00, 0x00, 0x00, 0xc3]);
+        // 0x1010: ret
+        code[0x10] = 0xc3;
+        // 0x1020: push rbp; mov rbp, rsp; pop rbp; ret (never called)
+        code[0x20..0x26].copy_from_slice(&[0x55, 0x48, 0x89, 0xe5, 0x5d, 0xc3]);
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        file.info.sections[0].name = ".text".to_string();
+
+        let functions = discover_functions(&file);
+        let found: Vec<(u64, u64, FunctionSource)> = functions
+            .iter()
+            .map(|f| (f.start, f.size, f.source))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (0x1000, 6, FunctionSource::Entry),
+                (0x1010, 1, FunctionSource::Call),
+                (0x1020, 6, FunctionSource::Prologue),
+            ]
+        );
+        assert_eq!(functions[1].name, "sub_1010");
+    }
+
+    #[test]
+    fn test_eh_frame_fde() {
+        // CIE: version 1, "zR", code align 1, data align -8, RA 16, aug len 1, pcrel|sdata4
+        let mut cie = vec![0, 0, 0, 0, 1, b'z', b'R', 0, 1, 0x78, 16, 1, 0x1b];
+        cie.resize(16, 0);
+        let mut data = (cie.len() as u32).to_le_bytes().to_vec();
+        data.extend(&cie);
+        // FDE at offset 20: CIE pointer, pc_begin (pcrel), pc_range
+        let fde_start = data.len() + 4;
+        let mut fde = ((fde_start) as u32).to_le_bytes().to_vec();
+        let field_addr = 0x2000 + (fde_start + 4) as u64;
+        fde.extend(((0x1000i64 - field_addr as i64) as i32).to_le_bytes());
+        fde.extend(0x40u32.to_le_bytes());
+        fde.extend([0, 0, 0, 0]);
+        data.extend((fde.len() as u32).to_le_bytes());
+        data.extend(&fde);
+
+        let mut file = BinaryFile::from_raw("t".to_string(), data, "x86_64", 0x2000);
+        file.info.sections = vec![SectionInfo {
+            name: ".eh_frame".to_string(),
+            addr: 0x2000,
+            size: file.data.len() as u64,
+            offset: 0,
+            hashes: None,
+            zero_fill: false,
+        }];
+        assert_eq!(eh_frame_functions(&file), vec![(0x1000, 0x40)]);
+    }
+}
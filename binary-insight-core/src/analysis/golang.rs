@@ -0,0 +1,357 @@
+use crate::binary::{BinaryInfo, SymbolInfo};
+
+const BUILDINFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+/// Flag bit set since Go 1.18 when version/modinfo are stored inline.
+const BUILDINFO_INLINE: u8 = 0x2;
+const BUILDINFO_BIG_ENDIAN: u8 = 0x1;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoModule {
+    pub path: String,
+    pub version: String,
+    pub sum: Option<String>,
+    /// `=>` replacement directive, if any.
+    pub replace: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GoInfo {
+    /// Toolchain version, e.g. `go1.21.5`.
+    pub version: Option<String>,
+    pub main_path: Option<String>,
+    pub main_module: Option<GoModule>,
+    pub deps: Vec<GoModule>,
+    /// `build` settings such as `-ldflags`, `GOOS`, `vcs.revision`.
+    pub settings: Vec<(String, String)>,
+    /// pclntab format, e.g. `1.20+`.
+    pub pclntab_version: Option<String>,
+    /// Functions recovered from the pclntab.
+    pub functions: Vec<SymbolInfo>,
+}
+
+/// Returns Go metadata if the binary carries a Go build info blob or pclntab.
+pub fn analyze_go(data: &[u8], info: &BinaryInfo) -> Option<GoInfo> {
+    let mut go = GoInfo::default();
+    let mut found = false;
+
+    if let Some((version, modinfo)) = find_buildinfo(data, info) {
+        found = true;
+        go.version = version;
+        if let Some(modinfo) = modinfo {
+            parse_modinfo(&modinfo, &mut go);
+        }
+    }
+
+    // Only scan raw bytes for a pclntab header when we already know it's Go.
+    if let Some((format, functions)) = find_pclntab(data, info, found) {
+        found = true;
+        go.pclntab_version = Some(format.to_string());
+        go.functions = functions;
+    }
+
+    found.then_some(go)
+}
+
+fn find_buildinfo(data: &[u8], info: &BinaryInfo) -> Option<(Option<String>, Option<String>)> {
+    let start = match info
+        .sections
+        .iter()
+        .find(|s| s.name == ".go.buildinfo" || s.name == "__go_buildinfo")
+    {
+        Some(section)
+            if data
+                .get(section.offset as usize..)
+                .is_some_and(|d| d.starts_with(BUILDINFO_MAGIC)) =>
+        {
+            section.offset as usize
+        }
+        _ => (0..data.len().saturating_sub(32))
+            .step_by(16)
+            .find(|&off| data[off..].starts_with(BUILDINFO_MAGIC))?,
+    };
+    let header = data.get(start..start + 32)?;
+    let ptr_size = header[14] as usize;
+    let flags = header[15];
+
+    if flags & BUILDINFO_INLINE != 0 {
+        let (version, rest) = read_varint_string(data.get(start + 32..)?)?;
+        let (modinfo, _) = read_varint_string(rest)?;
+        return Some((non_empty(version), strip_modinfo_sentinels(&modinfo)));
+    }
+
+    // Pre-1.18 layout: two pointers to Go string headers.
+    let big_endian = flags & BUILDINFO_BIG_ENDIAN != 0;
+    let read_string = |ptr_offset: usize| -> Option<String> {
+        let header_addr = read_uint(data, start + ptr_offset, ptr_size, big_endian)?;
+        let header = info.addr_to_offset(header_addr)? as usize;
+        let str_addr = read_uint(data, header, ptr_size, big_endian)?;
+        let len = read_uint(data, header + ptr_size, ptr_size, big_endian)? as usize;
+        let off = info.addr_to_offset(str_addr)? as usize;
+        data.get(off..off + len)
+            .map(|b| String::from_utf8_lossy(b).to_string())
+    };
+    let version = read_string(16);
+    let modinfo = read_string(16 + ptr_size);
+    Some((
+        version.and_then(non_empty),
+        modinfo.and_then(|m| strip_modinfo_sentinels(&m)),
+    ))
+}
+
+fn read_varint_string(data: &[u8]) -> Option<(String, &[u8])> {
+    let mut len: usize = 0;
+    let mut shift = 0;
+    let mut used = 0;
+    for &b in data.iter().take(10) {
+        used += 1;
+        len |= ((b & 0x7f) as usize) << shift;
+        if b & 0x80 == 0 {
+            let bytes = data.get(used..used + len)?;
+            return Some((
+                String::from_utf8_lossy(bytes).to_string(),
+                &data[used + len..],
+            ));
+        }
+        shift += 7;
+    }
+    None
+}
+
+/// The linker wraps modinfo in 16-byte sentinels on both ends.
+fn strip_modinfo_sentinels(modinfo: &str) -> Option<String> {
+    let bytes = modinfo.as_bytes();
+    if bytes.len() >= 33 && bytes[bytes.len() - 17] == b'\n' {
+        Some(String::from_utf8_lossy(&bytes[16..bytes.len() - 16]).to_string())
+    } else {
+        non_empty(modinfo.to_string())
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+fn parse_modinfo(modinfo: &str, go: &mut GoInfo) {
+    for line in modinfo.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let module = || GoModule {
+            path: fields.get(1).unwrap_or(&"").to_string(),
+            version: fields.get(2).unwrap_or(&"").to_string(),
+            sum: fields.get(3).map(|s| s.to_string()),
+            replace: None,
+        };
+        match fields[0] {
+            "path" => go.main_path = fields.get(1).map(|s| s.to_string()),
+            "mod" => go.main_module = Some(module()),
+            "dep" => go.deps.push(module()),
+            "=>" => {
+                let replacement = fields[1..].join(" ");
+                if let Some(last) = go.deps.last_mut() {
+                    last.replace = Some(replacement);
+                } else if let Some(main) = go.main_module.as_mut() {
+                    main.replace = Some(replacement);
+                }
+            }
+            "build" => {
+                if let Some((key, value)) = fields.get(1).and_then(|kv| kv.split_once('=')) {
+                    go.settings.push((key.to_string(), value.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn find_pclntab(
+    data: &[u8],
+    info: &BinaryInfo,
+    scan: bool,
+) -> Option<(&'static str, Vec<SymbolInfo>)> {
+    let text_addr = info
+        .sections
+        .iter()
+        .find(|s| s.name == ".text" || s.name == "__text")
+        .map(|s| s.addr)
+        .unwrap_or(0);
+
+    if let Some(section) = info
+        .sections
+        .iter()
+        .find(|s| s.name == ".gopclntab" || s.name == "__gopclntab")
+    {
+        return parse_pclntab(&data[section.file_range(data.len())], text_addr);
+    }
+
+    // PE and section-less binaries: look for a plausible header in the raw bytes.
+    if !scan {
+        return None;
+    }
+    (0..data.len().saturating_sub(64))
+        .step_by(4)
+        .filter(|&off| pclntab_magic(&data[off..]).is_some())
+        .find_map(|off| parse_pclntab(&data[off..], text_addr))
+}
+
+fn pclntab_magic(table: &[u8]) -> Option<(&'static str, bool)> {
+    let header = table.get(..8)?;
+    if header[4] != 0
+        || header[5] != 0
+        || !matches!(header[6], 1 | 2 | 4)
+        || !matches!(header[7], 4 | 8)
+    {
+        return None;
+    }
+    let le = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let be = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let version = |magic: u32| match magic {
+        0xffff_fffb => Some("1.2"),
+        0xffff_fffa => Some("1.16"),
+        0xffff_fff0 => Some("1.18"),
+        0xffff_fff1 => Some("1.20+"),
+        _ => None,
+    };
+    version(le)
+        .map(|v| (v, false))
+        .or_else(|| version(be).map(|v| (v, true)))
+}
+
+fn parse_pclntab(table: &[u8], text_addr: u64) -> Option<(&'static str, Vec<SymbolInfo>)> {
+    let (format, big_endian) = pclntab_magic(table)?;
+    let p = table[7] as usize;
+    let word = |off: usize| read_uint(table, off, p, big_endian);
+    let u32_at = |off: usize| read_uint(table, off, 4, big_endian);
+
+    let nfunc = word(8)? as usize;
+    if nfunc == 0 || nfunc > table.len() / 8 {
+        return None;
+    }
+
+    let mut functions = Vec::with_capacity(nfunc);
+    match format {
+        "1.18" | "1.20+" => {
+            let text_start = match word(8 + 2 * p)? {
+                0 => text_addr,
+                addr => addr,
+            };
+            let funcname = word(8 + 3 * p)? as usize;
+            let functab = word(8 + 7 * p)? as usize;
+            for i in 0..nfunc {
+                let entry_off = u32_at(functab + i * 8)?;
+                let func_off = u32_at(functab + i * 8 + 4)? as usize;
+                let name_off = u32_at(functab + func_off + 4)? as usize;
+                functions.push(SymbolInfo {
+                    name: read_cstr(table, funcname + name_off)?,
+                    addr: text_start + entry_off,
+                });
+            }
+        }
+        "1.16" => {
+            let funcname = word(8 + 2 * p)? as usize;
+            let functab = word(8 + 6 * p)? as usize;
+            for i in 0..nfunc {
+                let entry = word(functab + i * 2 * p)?;
+                let func_off = word(functab + i * 2 * p + p)? as usize;
+                let name_off = u32_at(functab + func_off + p)? as usize;
+                functions.push(SymbolInfo {
+                    name: read_cstr(table, funcname + name_off)?,
+                    addr: entry,
+                });
+            }
+        }
+        _ => {
+            let functab = 8 + p;
+            for i in 0..nfunc {
+                let entry = word(functab + i * 2 * p)?;
+                let func_off = word(functab + i * 2 * p + p)? as usize;
+                let name_off = u32_at(func_off + p)? as usize;
+                functions.push(SymbolInfo {
+                    name: read_cstr(table, name_off)?,
+                    addr: entry,
+                });
+            }
+        }
+    }
+
+    Some((format, functions))
+}
+
+fn read_cstr(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+fn read_uint(data: &[u8], offset: usize, size: usize, big_endian: bool) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(size)?)?;
+    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+    Some(if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_modinfo() {
+        let modinfo = "path\texample.com/tool\nmod\texample.com/tool\t(devel)\t\ndep\tgolang.org/x/sys\tv0.15.0\th1:abc=\n=>\t../sys\t\nbuild\tGOOS=linux\n";
+        let mut go = GoInfo::default();
+        parse_modinfo(modinfo, &mut go);
+        assert_eq!(go.main_path.as_deref(), Some("example.com/tool"));
+        assert_eq!(go.deps.len(), 1);
+        assert_eq!(go.deps[0].version, "v0.15.0");
+        assert!(go.deps[0].replace.is_some());
+        assert_eq!(go.settings, vec![("GOOS".to_string(), "linux".to_string())]);
+    }
+
+    #[test]
+    fn test_inline_buildinfo() {
+        let mut data = vec![0u8; 16];
+        data.extend_from_slice(BUILDINFO_MAGIC);
+        data.push(8);
+        data.push(BUILDINFO_INLINE);
+        data.resize(16 + 32, 0);
+        data.push(8);
+        data.extend_from_slice(b"go1.21.5");
+        data.push(0);
+
+        let (version, modinfo) = find_buildinfo(&data, &BinaryInfo::default()).unwrap();
+        assert_eq!(version.as_deref(), Some("go1.21.5"));
+        assert_eq!(modinfo, None);
+    }
+
+    #[test]
+    fn test_parse_pclntab_120() {
+        // Header: magic, pad, minLC, ptrSize, then 8 words.
+        let mut table = Vec::new();
+        table.extend_from_slice(&0xffff_fff1u32.to_le_bytes());
+        table.extend_from_slice(&[0, 0, 1, 8]);
+        let funcname = 8 + 8 * 8;
+        let functab = funcname + 16;
+        for value in [2u64, 0, 0x401000, funcname as u64, 0, 0, 0, functab as u64] {
+            table.extend_from_slice(&value.to_le_bytes());
+        }
+        table.extend_from_slice(b"main.main\0\0\0\0\0\0\0");
+        // functab: (entryOff, funcOff) x2, funcOff relative to functab.
+        for (entry, func) in [(0u32, 16u32), (0x40, 24)] {
+            table.extend_from_slice(&entry.to_le_bytes());
+            table.extend_from_slice(&func.to_le_bytes());
+        }
+        // _func records: entryOff, nameOff
+        for name_off in [0u32, 5] {
+            table.extend_from_slice(&0u32.to_le_bytes());
+            table.extend_from_slice(&name_off.to_le_bytes());
+        }
+
+        let (format, functions) = parse_pclntab(&table, 0).expect("pclntab parse failed");
+        assert_eq!(format, "1.20+");
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].name, "main.main");
+        assert_eq!(functions[0].addr, 0x401000);
+        assert_eq!(functions[1].name, "main");
+        assert_eq!(functions[1].addr, 0x401040);
+    }
+}
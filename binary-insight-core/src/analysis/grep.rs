@@ -0,0 +1,95 @@
+use crate::binary::BinaryInfo;
+use anyhow::{Context, Result};
+use regex_automata::meta::{Config, Regex};
+use regex_automata::util::syntax;
+
+/// A regex match in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrepMatch {
+    pub offset: u64,
+    pub len: usize,
+    /// Index into `BinaryInfo::strings` of the string holding the match;
+    /// `None` for raw byte matches.
+    pub string: Option<usize>,
+}
+
+/// Compiles `pattern` for byte haystacks: `.` and classes match single
+/// bytes and `\xNN` matches any byte value, UTF-8 or not.
+pub fn compile(pattern: &str, ignore_case: bool) -> Result<Regex> {
+    Regex::builder()
+        .configure(Config::new().utf8_empty(false))
+        .syntax(
+            syntax::Config::new()
+                .unicode(false)
+                .utf8(false)
+                .case_insensitive(ignore_case),
+        )
+        .build(pattern)
+        .with_context(|| format!("Invalid regex '{}'", pattern))
+}
+
+/// Matches inside the extracted strings, in file order. A match never
+/// spans two strings.
+pub fn grep_strings(info: &BinaryInfo, re: &Regex) -> Vec<GrepMatch> {
+    let mut matches = Vec::new();
+    for (i, string) in info.strings.iter().enumerate() {
+        let Some((offset, _)) = info.string_index.get(i) else {
+            break;
+        };
+        matches.extend(
+            re.find_iter(string.as_bytes())
+                .filter(|m| !m.is_empty())
+                .map(|m| GrepMatch {
+                    offset: offset + m.start() as u64,
+                    len: m.len(),
+                    string: Some(i),
+                }),
+        );
+    }
+    matches
+}
+
+/// Matches anywhere in `data`.
+pub fn grep_bytes(data: &[u8], re: &Regex) -> Vec<GrepMatch> {
+    re.find_iter(data)
+        .filter(|m| !m.is_empty())
+        .map(|m| GrepMatch {
+            offset: m.start() as u64,
+            len: m.len(),
+            string: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::extract_strings_indexed;
+
+    #[test]
+    fn test_grep() {
+        let data = b"\x00\x00http://a.example\x00\xffkey=\xfe\x01PASSWORD\x00";
+        let (strings, string_index) = extract_strings_indexed(data);
+        let info = BinaryInfo {
+            strings,
+            string_index,
+            ..Default::default()
+        };
+
+        let re = compile(r"https?://[a-z.]+", false).unwrap();
+        let found = grep_strings(&info, &re);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].offset, found[0].len), (2, 16));
+        assert_eq!(found[0].string, Some(0));
+
+        let re = compile("password", true).unwrap();
+        assert_eq!(grep_strings(&info, &re)[0].offset, 26);
+
+        let re = compile(r"=\xfe.", false).unwrap();
+        assert!(grep_strings(&info, &re).is_empty());
+        let found = grep_bytes(data, &re);
+        assert_eq!((found[0].offset, found[0].len), (23, 3));
+
+        assert!(compile("(", false).is_err());
+    }
+}
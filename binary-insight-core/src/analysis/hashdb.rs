@@ -0,0 +1,323 @@
+//! Local database of analyzed files by SHA-256, with the label (clean,
+//! suspicious, malicious) a user gave each. Stored as a tab-separated text
+//! file, one record per line.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const HEADER: &str =
+    "# binaryinsight hashdb v1: sha256 label first_seen last_seen times_seen name note";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Label {
+    Clean,
+    Suspicious,
+    Malicious,
+}
+
+impl Label {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Label::Clean => "clean",
+            Label::Suspicious => "suspicious",
+            Label::Malicious => "malicious",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "clean" => Some(Label::Clean),
+            "suspicious" => Some(Label::Suspicious),
+            "malicious" => Some(Label::Malicious),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HashRecord {
+    pub sha256: String,
+    pub label: Option<Label>,
+    /// Unix times of the first and latest analysis.
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub times_seen: u32,
+    /// File name it was last analyzed under.
+    pub name: String,
+    pub note: String,
+}
+
+#[derive(Debug, Default)]
+pub struct HashDb {
+    records: BTreeMap<String, HashRecord>,
+}
+
+impl HashDb {
+    /// `$XDG_DATA_HOME/binaryinsight/hashes.db`, falling back to
+    /// `~/.local/share`.
+    pub fn default_path() -> Option<PathBuf> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share"))
+            })?;
+        Some(data_home.join("binaryinsight").join("hashes.db"))
+    }
+
+    /// Opens the database at `path`; a missing file is an empty database.
+    pub fn open(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).with_context(|| format!("Invalid {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let mut db = HashDb::default();
+        for (i, line) in text.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [sha256, label, first_seen, last_seen, times_seen, name, note] = fields[..] else {
+                bail!("line {}: expected 7 tab-separated fields", i + 1);
+            };
+            let number = |field: &str| -> Result<u64> {
+                field
+                    .parse()
+                    .with_context(|| format!("line {}: bad number {:?}", i + 1, field))
+            };
+            db.records.insert(
+                sha256.to_string(),
+                HashRecord {
+                    sha256: sha256.to_string(),
+                    label: Label::from_name(label),
+                    first_seen: number(first_seen)?,
+                    last_seen: number(last_seen)?,
+                    times_seen: number(times_seen)? as u32,
+                    name: name.to_string(),
+                    note: note.to_string(),
+                },
+            );
+        }
+        Ok(db)
+    }
+
+    /// Writes the database to `path`, creating its directory.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Replace the file in one step so an interrupted run can't truncate it.
+        let tmp = path.with_extension("db.tmp");
+        fs::write(&tmp, self.to_text())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        for r in self.records.values() {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                r.sha256,
+                r.label.map_or("-", |l| l.name()),
+                r.first_seen,
+                r.last_seen,
+                r.times_seen,
+                clean_field(&r.name),
+                clean_field(&r.note)
+            ));
+        }
+        text
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn get(&self, sha256: &str) -> Option<&HashRecord> {
+        self.records.get(&sha256.to_ascii_lowercase())
+    }
+
+    /// Notes an analysis of `sha256` at `now`.
+    pub fn record_seen(&mut self, sha256: &str, name: &str, now: u64) {
+        let record = self.entry(sha256, now);
+        record.last_seen = now;
+        record.times_seen += 1;
+        record.name = name.to_string();
+    }
+
+    pub fn set_label(&mut self, sha256: &str, label: Option<Label>, note: &str, now: u64) {
+        let record = self.entry(sha256, now);
+        record.label = label;
+        if !note.is_empty() {
+            record.note = note.to_string();
+        }
+    }
+
+    fn entry(&mut self, sha256: &str, now: u64) -> &mut HashRecord {
+        let sha256 = sha256.to_ascii_lowercase();
+        self.records
+            .entry(sha256.clone())
+            .or_insert_with(|| HashRecord {
+                sha256,
+                label: None,
+                first_seen: now,
+                last_seen: now,
+                times_seen: 0,
+                name: String::new(),
+                note: String::new(),
+            })
+    }
+
+    /// Merges CSV records, returning how many were imported. Columns are
+    /// `sha256,label,note` unless a header line names them, so the output of
+    /// [`HashDb::export_csv`] imports as is.
+    pub fn import_csv(&mut self, text: &str, now: u64) -> Result<usize> {
+        let mut columns = (0, Some(1), Some(2));
+        let mut imported = 0;
+        for (i, line) in text.lines().enumerate() {
+            let fields = split_csv(line);
+            if i == 0
+                && fields
+                    .first()
+                    .is_some_and(|f| f.eq_ignore_ascii_case("sha256"))
+            {
+                let find = |name: &str| fields.iter().position(|f| f.eq_ignore_ascii_case(name));
+                columns = (0, find("label"), find("note"));
+                continue;
+            }
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|c| fields.get(c))
+                    .map_or("", |f| f.as_str())
+            };
+            let sha256 = field(Some(columns.0));
+            if sha256.is_empty() {
+                continue;
+            }
+            if sha256.len() != 64 || hex::decode(sha256).is_err() {
+                bail!("line {}: not a SHA-256: {}", i + 1, sha256);
+            }
+            let label = match field(columns.1) {
+                "" => None,
+                name => Some(
+                    Label::from_name(name)
+                        .with_context(|| format!("line {}: unknown label {}", i + 1, name))?,
+                ),
+            };
+            self.set_label(sha256, label, field(columns.2), now);
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// Writes every record as CSV with a header line.
+    pub fn export_csv(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "sha256,label,first_seen,last_seen,times_seen,name,note"
+        )?;
+        for r in self.records.values() {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                r.sha256,
+                r.label.map_or("", |l| l.name()),
+                r.first_seen,
+                r.last_seen,
+                r.times_seen,
+                csv_field(&r.name),
+                csv_field(&r.note)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits a CSV line, honoring `"` quoting.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("starts with one field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Tabs and newlines would split a record.
+fn clean_field(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    #[test]
+    fn test_round_trip() {
+        let mut db = HashDb::default();
+        db.record_seen(SHA, "hello.bin", 100);
+        db.record_seen(SHA, "hello.bin", 200);
+        db.set_label(SHA, Some(Label::Malicious), "dropper\tstage 1", 300);
+
+        let reopened = HashDb::parse(&db.to_text()).unwrap();
+        let record = reopened.get(SHA).unwrap();
+        assert_eq!(record.label, Some(Label::Malicious));
+        assert_eq!((record.first_seen, record.last_seen), (100, 200));
+        assert_eq!(record.times_seen, 2);
+        assert_eq!(record.note, "dropper stage 1");
+    }
+
+    #[test]
+    fn test_import_export() {
+        let mut db = HashDb::default();
+        let csv = format!(
+            "sha256,label,note\n{},suspicious,\"packed, unsigned\"\n",
+            SHA.to_uppercase()
+        );
+        assert_eq!(db.import_csv(&csv, 5).unwrap(), 1);
+        assert_eq!(db.get(SHA).unwrap().label, Some(Label::Suspicious));
+        assert!(db.import_csv("abc,clean", 5).is_err());
+
+        let mut out = Vec::new();
+        db.export_csv(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with(&format!("{},suspicious,5,5,0,,\"packed, unsigned\"\n", SHA)));
+
+        let mut reimported = HashDb::default();
+        assert_eq!(reimported.import_csv(&text, 9).unwrap(), 1);
+        assert_eq!(reimported.get(SHA).unwrap().note, "packed, unsigned");
+    }
+}
@@ -1,7 +1,28 @@
+use anyhow::{Context, Result};
+use goblin::elf::{sym, Elf};
+use goblin::pe::PE;
 use md5::Md5;
 use serde::Serialize;
 use sha1::Sha1;
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tlsh2::TlshDefaultBuilder;
+
+/// Symbols telfhash leaves out: compiler and libc plumbing that nearly
+/// every binary has.
+const TELFHASH_EXCLUDED: &[&str] = &[
+    "__libc_start_main",
+    "main",
+    "abort",
+    "cachectl",
+    "cacheflush",
+    "puts",
+    "atol",
+    "malloc_trim",
+];
 
 #[derive(Debug, Serialize, Clone)]
 pub struct FileHashes {
@@ -26,6 +47,168 @@ pub fn calculate_hashes(data: &[u8]) -> FileHashes {
     FileHashes { md5, sha1, sha256 }
 }
 
+/// Digests of one section's bytes, comparable across samples to spot shared
+/// code or the one section that changed between builds.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SectionHashes {
+    pub md5: String,
+    pub sha256: String,
+}
+
+/// Hashes `size` bytes at `offset`, clamped to the file; `None` when the
+/// section has no bytes in the file.
+pub fn section_hashes(data: &[u8], offset: u64, size: u64) -> Option<SectionHashes> {
+    let start = usize::try_from(offset).ok().filter(|&o| o < data.len())?;
+    let end = start.saturating_add(size as usize).min(data.len());
+    if end == start {
+        return None;
+    }
+    let bytes = &data[start..end];
+    Some(SectionHashes {
+        md5: hex::encode(Md5::digest(bytes)),
+        sha256: hex::encode(Sha256::digest(bytes)),
+    })
+}
+
+/// Authenticode SHA-256 of a PE file: the hash a signature covers, which
+/// leaves out the checksum, the security directory entry and the
+/// certificate table. Unsigned files have one too.
+pub fn authentihash(pe: &PE, data: &[u8]) -> Option<String> {
+    if !authenticode_in_bounds(pe, data.len()) {
+        return None;
+    }
+    let mut hasher = Sha256::new();
+    for range in pe.authenticode_ranges() {
+        hasher.update(range);
+    }
+    Some(hex::encode(hasher.finalize()))
+}
+
+/// Whether the ranges goblin's Authenticode iterator slices lie inside the
+/// file; it does not check them and panics on truncated files.
+fn authenticode_in_bounds(pe: &PE, len: usize) -> bool {
+    let Some(optional_header) = pe.header.optional_header else {
+        return false;
+    };
+    // Signature, COFF header, then the optional header up to the end of
+    // the security directory entry.
+    let is_64 = optional_header.standard_fields.magic == goblin::pe::optional_header::MAGIC_64;
+    let security_dir_end =
+        pe.header.dos_header.pe_pointer as usize + 4 + 20 + if is_64 { 152 } else { 136 };
+    let headers = optional_header.windows_fields.size_of_headers as usize;
+    if security_dir_end > headers || headers > len {
+        return false;
+    }
+    let mut hashed = headers;
+    for section in pe.sections.iter().filter(|s| s.size_of_raw_data != 0) {
+        let size = section.size_of_raw_data as usize;
+        match (section.pointer_to_raw_data as usize).checked_add(size) {
+            Some(end) if end <= len => hashed = hashed.saturating_add(size),
+            _ => return false,
+        }
+    }
+    // Data past the sections is hashed up to the certificate table.
+    let certificates = optional_header
+        .data_directories
+        .get_certificate_table()
+        .map_or(0, |dir| dir.size as usize);
+    len <= hashed || hashed.saturating_add(certificates) <= len
+}
+
+/// telfhash of an ELF file: the TLSH of its sorted global function
+/// symbols, comparable across builds the way imphash is for PE imports.
+/// `None` when there are too few symbols to hash.
+pub fn telfhash(elf: &Elf) -> Option<String> {
+    let tables = [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)];
+    let names = tables.into_iter().flat_map(|(syms, strtab)| {
+        syms.iter()
+            .filter(|sym| {
+                sym.st_type() == sym::STT_FUNC
+                    && sym.st_bind() == sym::STB_GLOBAL
+                    && sym.st_visibility() == sym::STV_DEFAULT
+            })
+            .filter_map(|sym| strtab.get_at(sym.st_name))
+    });
+    telfhash_symbols(names)
+}
+
+fn telfhash_symbols<'a>(names: impl Iterator<Item = &'a str>) -> Option<String> {
+    let symbols: BTreeSet<String> = names
+        .filter(|name| {
+            !name.is_empty()
+                && !name.starts_with(['_', '.'])
+                && !name.ends_with("64")
+                && !name.starts_with("str")
+                && !name.starts_with("mem")
+                && !TELFHASH_EXCLUDED.contains(name)
+        })
+        .map(|name| name.to_lowercase())
+        .collect();
+    let joined = symbols.into_iter().collect::<Vec<_>>().join(",");
+    let tlsh = TlshDefaultBuilder::build_from(joined.as_bytes())?;
+    Some(String::from_utf8_lossy(&tlsh.hash()).to_lowercase())
+}
+
+/// A set of known-good file hashes, e.g. the NSRL RDS `NSRLFile.txt` or
+/// the output of `md5sum`, `sha256sum` and hashdeep.
+#[derive(Debug, Default)]
+pub struct KnownHashes {
+    /// Raw MD5, SHA-1 and SHA-256 digests; their lengths tell them apart.
+    digests: HashSet<Vec<u8>>,
+}
+
+impl KnownHashes {
+    /// Loads every MD5, SHA-1 and SHA-256 found in the fields of each line.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open hash set {}", path.display()))?;
+        let mut set = KnownHashes::default();
+        // NSRL sets run to gigabytes, so read them a line at a time.
+        for line in BufReader::new(file).split(b'\n') {
+            set.add_line(&String::from_utf8_lossy(&line?));
+        }
+        Ok(set)
+    }
+
+    pub fn extend(&mut self, other: KnownHashes) {
+        self.digests.extend(other.digests);
+    }
+
+    /// Adds the hashes of one line: quoted CSV fields (NSRL), or a digest
+    /// followed by a file name.
+    pub fn add_line(&mut self, line: &str) {
+        let fields = line.split(|c: char| c == ',' || c.is_whitespace());
+        for field in fields.map(|f| f.trim_matches('"')) {
+            if matches!(field.len(), 32 | 40 | 64) {
+                if let Ok(digest) = hex::decode(field) {
+                    self.digests.insert(digest);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+
+    /// The algorithm whose digest of the file is in the set, if any.
+    pub fn lookup(&self, hashes: &FileHashes) -> Option<&'static str> {
+        [
+            ("SHA-256", &hashes.sha256),
+            ("SHA-1", &hashes.sha1),
+            ("MD5", &hashes.md5),
+        ]
+        .into_iter()
+        .find(|(_, digest)| hex::decode(digest).is_ok_and(|d| self.digests.contains(&d)))
+        .map(|(name, _)| name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,4 +225,114 @@ mod tests {
             "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
         );
     }
+
+    #[test]
+    fn test_section_hashes() {
+        let data = b"headerhello world";
+        let hashes = section_hashes(data, 6, 11).unwrap();
+        assert_eq!(hashes.md5, "5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(section_hashes(data, 6, 100), Some(hashes));
+        assert_eq!(section_hashes(data, 6, 0), None);
+        assert_eq!(section_hashes(data, 100, 4), None);
+    }
+
+    /// A PE32+ file with one 0x200-byte section at file offset 0x200.
+    fn pe64() -> Vec<u8> {
+        let mut data = vec![0u8; 0x400];
+        data[..2].copy_from_slice(b"MZ");
+        data[0x3c] = 0x80;
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        data[0x84..0x86].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[0x86] = 1; // NumberOfSections
+        data[0x94] = 0xf0; // SizeOfOptionalHeader
+        data[0x96] = 0x22;
+        let optional = 0x98;
+        data[optional..optional + 2].copy_from_slice(&0x20bu16.to_le_bytes());
+        data[optional + 32..optional + 36].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[optional + 36..optional + 40].copy_from_slice(&0x200u32.to_le_bytes());
+        data[optional + 56..optional + 60].copy_from_slice(&0x2000u32.to_le_bytes());
+        data[optional + 60..optional + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        data[optional + 108] = 16; // NumberOfRvaAndSizes
+        let section = optional + 0xf0;
+        data[section..section + 5].copy_from_slice(b".text");
+        data[section + 8..section + 12].copy_from_slice(&0x200u32.to_le_bytes());
+        data[section + 12..section + 16].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[section + 16..section + 20].copy_from_slice(&0x200u32.to_le_bytes());
+        data[section + 20..section + 24].copy_from_slice(&0x200u32.to_le_bytes());
+        data[0x200..].fill(0xcc);
+        data
+    }
+
+    #[test]
+    fn test_authentihash() {
+        let mut data = pe64();
+        let unsigned = authentihash(&PE::parse(&data).unwrap(), &data).unwrap();
+
+        // The checksum is left out of the digest, section bytes are not.
+        data[0x98 + 64..0x98 + 68].copy_from_slice(&[1, 2, 3, 4]);
+        let digest = authentihash(&PE::parse(&data).unwrap(), &data);
+        assert_eq!(digest.as_deref(), Some(unsigned.as_str()));
+        data[0x300] = 0;
+        let digest = authentihash(&PE::parse(&data).unwrap(), &data);
+        assert_ne!(digest.as_deref(), Some(unsigned.as_str()));
+    }
+
+    #[test]
+    fn test_authentihash_truncated() {
+        // The section's raw data runs past the end of the file.
+        let data = &pe64()[..0x300];
+        assert_eq!(authentihash(&PE::parse(data).unwrap(), data), None);
+    }
+
+    #[test]
+    fn test_telfhash_symbols() {
+        let names = [
+            "connect",
+            "send",
+            "recv",
+            "socket",
+            "fork",
+            "execve",
+            "kill",
+            "getpid",
+            "setsid",
+            "chdir",
+            "Daemonize",
+            "scan_subnet",
+            "attack_udp",
+            "attack_syn",
+        ];
+        let hash = telfhash_symbols(names.into_iter()).unwrap();
+        assert!(hash.starts_with("t1"));
+        assert_eq!(hash.len(), 72);
+
+        // Order, case and excluded symbols don't change the hash.
+        let noisy = names.iter().rev().copied().chain([
+            "main",
+            "_init",
+            "memcpy",
+            "strlen",
+            "lseek64",
+            "daemonize",
+        ]);
+        assert_eq!(telfhash_symbols(noisy), Some(hash));
+        assert_eq!(telfhash_symbols(["main", "puts"].into_iter()), None);
+    }
+
+    #[test]
+    fn test_known_hashes() {
+        let hashes = calculate_hashes(b"hello world");
+        let mut set = KnownHashes::default();
+        set.add_line("\"SHA-1\",\"MD5\",\"CRC32\",\"FileName\",\"FileSize\"");
+        set.add_line(
+            "\"2AAE6C35C94FCFB415DBE95F408B9CE91EE846ED\",\"00000000000000000000000000000000\",\"0D4A1185\",\"hello.txt\",11",
+        );
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.lookup(&hashes), Some("SHA-1"));
+
+        let mut sums = KnownHashes::default();
+        sums.add_line("5eb63bbbe01eeed093cb22bb8f5acdc3  hello.txt");
+        assert_eq!(sums.lookup(&hashes), Some("MD5"));
+        assert_eq!(KnownHashes::default().lookup(&hashes), None);
+    }
 }
@@ -0,0 +1,533 @@
+//! The main icon of a PE file, from its `RT_GROUP_ICON`/`RT_ICON`
+//! resources, with perceptual hashes so lookalike icons (fake installer
+//! branding) cluster across samples.
+
+use super::pe_headers::directory_data;
+use flate2::read::ZlibDecoder;
+use goblin::pe::data_directories::DataDirectory;
+use goblin::pe::PE;
+use serde::Serialize;
+use std::io::Read;
+
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+/// High bit of a resource entry: a name (first field) or a subdirectory
+/// (second field) rather than an id or a data entry.
+const RESOURCE_HIGH_BIT: u32 = 0x8000_0000;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Larger images are not icons; don't decode them.
+const MAX_DIMENSION: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IconInfo {
+    pub width: u32,
+    pub height: u32,
+    pub bit_count: u16,
+    /// Encoding of the image: `png` or `bmp`.
+    pub format: &'static str,
+    /// Difference hash, 16 hex digits; `None` if the image can't be decoded.
+    pub dhash: Option<String>,
+    /// DCT-based perceptual hash, 16 hex digits.
+    pub phash: Option<String>,
+    /// The icon as a standalone `.ico` file.
+    #[serde(skip)]
+    pub ico: Vec<u8>,
+}
+
+/// One image of an icon group (`GRPICONDIRENTRY`).
+#[derive(Debug, Clone, Copy)]
+struct GroupEntry {
+    width: u8,
+    height: u8,
+    colors: u8,
+    planes: u16,
+    bit_count: u16,
+    id: u16,
+}
+
+impl GroupEntry {
+    /// Width and height, with 0 standing for 256 as in the icon format.
+    fn size(&self) -> (u32, u32) {
+        let side = |v: u8| if v == 0 { 256 } else { v as u32 };
+        (side(self.width), side(self.height))
+    }
+}
+
+/// The largest, deepest image of the first icon group, which is what
+/// Explorer shows for the file.
+pub fn extract_icon(pe: &PE, data: &[u8]) -> Option<IconInfo> {
+    let opt = pe.header.optional_header.as_ref()?;
+    let rsrc = directory_data(pe, data, opt.data_directories.get_resource_table())?;
+    let group = find_resource(pe, data, rsrc, RT_GROUP_ICON, None)?;
+    let entry = group_entries(group)
+        .into_iter()
+        .max_by_key(|e| (e.size().0 * e.size().1, e.bit_count))?;
+    let image = find_resource(pe, data, rsrc, RT_ICON, Some(entry.id as u32))?;
+
+    let is_png = image.starts_with(PNG_SIGNATURE);
+    let decoded = if is_png {
+        decode_png(image)
+    } else {
+        decode_dib(image)
+    };
+    let (width, height) = match &decoded {
+        Some(gray) => (gray.width as u32, gray.height as u32),
+        None => entry.size(),
+    };
+    Some(IconInfo {
+        width,
+        height,
+        bit_count: entry.bit_count,
+        format: if is_png { "png" } else { "bmp" },
+        dhash: decoded.as_ref().map(dhash),
+        phash: decoded.as_ref().map(phash),
+        ico: to_ico(&entry, image),
+    })
+}
+
+/// `(id, offset)` of each entry in the resource directory at `dir`; `id`
+/// is `None` for named entries.
+fn directory_entries(rsrc: &[u8], dir: usize) -> Vec<(Option<u32>, u32)> {
+    let count = match (read_u16(rsrc, dir + 12), read_u16(rsrc, dir + 14)) {
+        (Some(named), Some(ids)) => named as usize + ids as usize,
+        _ => return Vec::new(),
+    };
+    (0..count)
+        .map_while(|i| {
+            let at = dir + 16 + i * 8;
+            let name = read_u32(rsrc, at)?;
+            let offset = read_u32(rsrc, at + 4)?;
+            Some(((name & RESOURCE_HIGH_BIT == 0).then_some(name), offset))
+        })
+        .collect()
+}
+
+/// Data of the first resource of type `kind` (with the given id, if any),
+/// in any language.
+fn find_resource<'a>(
+    pe: &PE,
+    data: &'a [u8],
+    rsrc: &[u8],
+    kind: u32,
+    id: Option<u32>,
+) -> Option<&'a [u8]> {
+    let subdirectory = |offset: u32| {
+        (offset & RESOURCE_HIGH_BIT != 0).then_some((offset & !RESOURCE_HIGH_BIT) as usize)
+    };
+    let (_, names) = directory_entries(rsrc, 0)
+        .into_iter()
+        .find(|&(entry_id, _)| entry_id == Some(kind))?;
+    let (_, languages) = directory_entries(rsrc, subdirectory(names)?)
+        .into_iter()
+        .find(|&(entry_id, _)| id.is_none() || entry_id == id)?;
+    let (_, leaf) = directory_entries(rsrc, subdirectory(languages)?)
+        .into_iter()
+        .next()?;
+    if leaf & RESOURCE_HIGH_BIT != 0 {
+        return None;
+    }
+    let leaf = leaf as usize;
+    let entry = DataDirectory {
+        virtual_address: read_u32(rsrc, leaf)?,
+        size: read_u32(rsrc, leaf + 4)?,
+    };
+    directory_data(pe, data, Some(&entry))
+}
+
+fn group_entries(group: &[u8]) -> Vec<GroupEntry> {
+    let count = read_u16(group, 4).unwrap_or(0) as usize;
+    (0..count)
+        .map_while(|i| {
+            let at = 6 + i * 14;
+            let entry = group.get(at..at + 14)?;
+            Some(GroupEntry {
+                width: entry[0],
+                height: entry[1],
+                colors: entry[2],
+                planes: read_u16(entry, 4)?,
+                bit_count: read_u16(entry, 6)?,
+                id: read_u16(entry, 12)?,
+            })
+        })
+        .collect()
+}
+
+/// A one-image `.ico` file holding `image`.
+fn to_ico(entry: &GroupEntry, image: &[u8]) -> Vec<u8> {
+    let mut ico = Vec::with_capacity(22 + image.len());
+    ico.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    ico.extend_from_slice(&[entry.width, entry.height, entry.colors, 0]);
+    ico.extend_from_slice(&entry.planes.to_le_bytes());
+    ico.extend_from_slice(&entry.bit_count.to_le_bytes());
+    ico.extend_from_slice(&(image.len() as u32).to_le_bytes());
+    ico.extend_from_slice(&22u32.to_le_bytes());
+    ico.extend_from_slice(image);
+    ico
+}
+
+/// Luminance image, with transparent pixels composited onto white.
+struct Gray {
+    width: usize,
+    height: usize,
+    pixels: Vec<f32>,
+}
+
+impl Gray {
+    fn from_rgba(width: usize, height: usize, rgba: impl Iterator<Item = [u8; 4]>) -> Self {
+        let pixels = rgba
+            .map(|[r, g, b, a]| {
+                let luma = r as f32 * 0.299 + g as f32 * 0.587 + b as f32 * 0.114;
+                let alpha = a as f32 / 255.0;
+                luma * alpha + 255.0 * (1.0 - alpha)
+            })
+            .collect();
+        Gray {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Area-averaged resample to `width` x `height`: each output pixel is
+    /// the mean of the source area it covers, so an icon and its 2x
+    /// upscale give the same thumbnail.
+    fn resize(&self, width: usize, height: usize) -> Vec<f32> {
+        let columns = coverage(self.width, width);
+        let rows = coverage(self.height, height);
+        let mut out = Vec::with_capacity(width * height);
+        for row in &rows {
+            for column in &columns {
+                let mut sum = 0.0;
+                for &(y, wy) in row {
+                    for &(x, wx) in column {
+                        sum += self.pixels[y * self.width + x] * wy * wx;
+                    }
+                }
+                out.push(sum);
+            }
+        }
+        out
+    }
+}
+
+/// For each of `dst` output pixels, the source pixels it overlaps and the
+/// share of the output each one contributes.
+fn coverage(src: usize, dst: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = src as f64 / dst as f64;
+    (0..dst)
+        .map(|i| {
+            let (start, end) = (i as f64 * scale, (i + 1) as f64 * scale);
+            (start.floor() as usize..(end.ceil() as usize).min(src))
+                .map(|j| {
+                    let overlap = end.min(j as f64 + 1.0) - start.max(j as f64);
+                    (j, (overlap / scale) as f32)
+                })
+                .filter(|&(_, weight)| weight > 0.0)
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether each pixel of a 9x8 thumbnail is brighter than its left
+/// neighbor.
+fn dhash(image: &Gray) -> String {
+    let pixels = image.resize(9, 8);
+    let bits = (0..8).flat_map(|y| (0..8).map(move |x| (y, x)));
+    let hash = bits.fold(0u64, |hash, (y, x)| {
+        hash << 1 | (pixels[y * 9 + x + 1] > pixels[y * 9 + x]) as u64
+    });
+    format!("{:016x}", hash)
+}
+
+/// Whether each of the 8x8 lowest frequencies of the DCT of a 32x32
+/// thumbnail is above their median.
+fn phash(image: &Gray) -> String {
+    const SIZE: usize = 32;
+    const LOW: usize = 8;
+    let pixels = image.resize(SIZE, SIZE);
+    let dct = |values: &[f32], k: usize| -> f32 {
+        values
+            .iter()
+            .enumerate()
+            .map(|(n, v)| {
+                v * (std::f32::consts::PI / SIZE as f32 * (n as f32 + 0.5) * k as f32).cos()
+            })
+            .sum()
+    };
+    // Rows first, keeping only the low frequencies, then columns.
+    let rows: Vec<[f32; LOW]> = pixels
+        .chunks(SIZE)
+        .map(|row| std::array::from_fn(|k| dct(row, k)))
+        .collect();
+    let mut low = Vec::with_capacity(LOW * LOW);
+    for v in 0..LOW {
+        for u in 0..LOW {
+            let column: Vec<f32> = rows.iter().map(|row| row[u]).collect();
+            low.push(dct(&column, v));
+        }
+    }
+    let mut sorted = low.clone();
+    sorted.sort_by(f32::total_cmp);
+    let median = (sorted[LOW * LOW / 2 - 1] + sorted[LOW * LOW / 2]) / 2.0;
+    let hash = low
+        .iter()
+        .fold(0u64, |hash, &c| hash << 1 | (c > median) as u64);
+    format!("{:016x}", hash)
+}
+
+/// 8-bit, non-interlaced PNG, which covers the PNG images Windows icons use.
+fn decode_png(image: &[u8]) -> Option<Gray> {
+    let mut at = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut compressed = Vec::new();
+    while let Some(length) = image.get(at..at + 4) {
+        let length = u32::from_be_bytes(length.try_into().ok()?) as usize;
+        let kind = image.get(at + 4..at + 8)?;
+        let body = image.get(at + 8..at + 8 + length)?;
+        match kind {
+            b"IHDR" => header = Some(body),
+            b"PLTE" => palette = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        at += 12 + length;
+    }
+    let header = header?;
+    let width = u32::from_be_bytes(header.get(0..4)?.try_into().ok()?) as usize;
+    let height = u32::from_be_bytes(header.get(4..8)?.try_into().ok()?) as usize;
+    let (depth, color, interlace) = (*header.get(8)?, *header.get(9)?, *header.get(12)?);
+    let channels = match color {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        6 => 4,
+        _ => return None,
+    };
+    if depth != 8 || interlace != 0 || width == 0 || height == 0 {
+        return None;
+    }
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return None;
+    }
+
+    let stride = width * channels;
+    let mut raw = Vec::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .take(((stride + 1) * height) as u64)
+        .read_to_end(&mut raw)
+        .ok()?;
+    let pixels = unfilter(&raw, stride, height, channels)?;
+    let rgba = pixels.chunks(channels).map(|p| match color {
+        0 => [p[0], p[0], p[0], 255],
+        4 => [p[0], p[0], p[0], p[1]],
+        2 => [p[0], p[1], p[2], 255],
+        6 => [p[0], p[1], p[2], p[3]],
+        _ => match palette.get(p[0] as usize * 3..p[0] as usize * 3 + 3) {
+            Some(rgb) => [rgb[0], rgb[1], rgb[2], 255],
+            None => [0, 0, 0, 255],
+        },
+    });
+    Some(Gray::from_rgba(width, height, rgba))
+}
+
+/// Reverses the per-row PNG filters.
+fn unfilter(raw: &[u8], stride: usize, height: usize, bpp: usize) -> Option<Vec<u8>> {
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let row = raw.get(y * (stride + 1)..(y + 1) * (stride + 1))?;
+        let (filter, row) = (row[0], &row[1..]);
+        for x in 0..stride {
+            let left = if x >= bpp {
+                out[y * stride + x - bpp]
+            } else {
+                0
+            };
+            let up = if y > 0 { out[(y - 1) * stride + x] } else { 0 };
+            let up_left = if y > 0 && x >= bpp {
+                out[(y - 1) * stride + x - bpp]
+            } else {
+                0
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return None,
+            };
+            out[y * stride + x] = row[x].wrapping_add(predicted);
+        }
+    }
+    Some(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// A `BITMAPINFOHEADER` image as stored in `RT_ICON`: rows bottom-up, twice
+/// the icon's height to make room for the 1-bit transparency mask.
+fn decode_dib(image: &[u8]) -> Option<Gray> {
+    let header_size = read_u32(image, 0)? as usize;
+    let width = read_u32(image, 4)? as i32;
+    let height = read_u32(image, 8)? as i32 / 2;
+    let bit_count = read_u16(image, 14)? as usize;
+    let compression = read_u32(image, 16)?;
+    let colors_used = read_u32(image, 32)? as usize;
+    if width <= 0 || height <= 0 || compression != 0 && compression != 3 {
+        return None;
+    }
+    let (width, height) = (width as usize, height as usize);
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return None;
+    }
+
+    let palette_len = match bit_count {
+        1 | 4 | 8 if colors_used != 0 => colors_used,
+        1 | 4 | 8 => 1 << bit_count,
+        24 | 32 => 0,
+        _ => return None,
+    };
+    // BI_BITFIELDS puts three masks after a 40-byte header.
+    let masks = if compression == 3 && header_size == 40 {
+        12
+    } else {
+        0
+    };
+    let palette = image.get(header_size + masks..header_size + masks + palette_len * 4)?;
+    let pixels_start = header_size + masks + palette_len * 4;
+    let stride = (width * bit_count).div_ceil(32) * 4;
+    let mask_start = pixels_start + stride * height;
+    let mask_stride = width.div_ceil(32) * 4;
+
+    let mut rgba = Vec::with_capacity(width * height);
+    for y in 0..height {
+        // Bottom-up rows.
+        let row = image.get(pixels_start + (height - 1 - y) * stride..)?;
+        let mask_row = image.get(mask_start + (height - 1 - y) * mask_stride..);
+        for x in 0..width {
+            let [b, g, r, a] = match bit_count {
+                32 => row.get(x * 4..x * 4 + 4)?.try_into().ok()?,
+                24 => {
+                    let p = row.get(x * 3..x * 3 + 3)?;
+                    [p[0], p[1], p[2], 255]
+                }
+                _ => {
+                    let bit = x * bit_count;
+                    let byte = *row.get(bit / 8)?;
+                    let index = (byte >> (8 - bit_count - bit % 8)) & ((1 << bit_count) - 1) as u8;
+                    let p = palette.get(index as usize * 4..index as usize * 4 + 4)?;
+                    [p[0], p[1], p[2], 255]
+                }
+            };
+            let masked = mask_row
+                .and_then(|m| m.get(x / 8))
+                .is_some_and(|m| m & (0x80 >> (x % 8)) != 0);
+            rgba.push([r, g, b, a, masked as u8]);
+        }
+    }
+    // 32-bit images without any alpha rely on the mask like the others.
+    let has_alpha = bit_count == 32 && rgba.iter().any(|p| p[3] != 0);
+    let rgba = rgba.into_iter().map(|[r, g, b, a, masked]| {
+        if has_alpha {
+            [r, g, b, a]
+        } else if masked == 1 {
+            [r, g, b, 0]
+        } else {
+            [r, g, b, 255]
+        }
+    });
+    Some(Gray::from_rgba(width, height, rgba))
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    /// A `width` x `height` 32-bit DIB with opaque BGR `pixel(x, y)` colors.
+    fn dib(width: usize, height: usize, pixel: impl Fn(usize, usize) -> [u8; 3]) -> Vec<u8> {
+        let mut dib = Vec::new();
+        dib.extend_from_slice(&40u32.to_le_bytes());
+        dib.extend_from_slice(&(width as u32).to_le_bytes());
+        dib.extend_from_slice(&(height as u32 * 2).to_le_bytes());
+        dib.extend_from_slice(&1u16.to_le_bytes());
+        dib.extend_from_slice(&32u16.to_le_bytes());
+        dib.resize(40, 0);
+        for y in (0..height).rev() {
+            for x in 0..width {
+                dib.extend_from_slice(&pixel(x, y));
+                dib.push(255);
+            }
+        }
+        dib.resize(dib.len() + width.div_ceil(32) * 4 * height, 0);
+        dib
+    }
+
+    #[test]
+    fn test_hashes() {
+        let gradient = decode_dib(&dib(32, 32, |x, _| [x as u8 * 8, 0, 0])).unwrap();
+        assert_eq!((gradient.width, gradient.height), (32, 32));
+        assert_eq!(dhash(&gradient), "ffffffffffffffff");
+
+        // Scaling the icon keeps its hashes.
+        let pattern = |x: usize, y: usize| [(x * 7 + y * 13) as u8, (x * x + y * 3) as u8, 90];
+        let icon = decode_dib(&dib(32, 32, pattern)).unwrap();
+        let large = decode_dib(&dib(64, 64, |x, y| pattern(x / 2, y / 2))).unwrap();
+        assert_eq!(dhash(&large), dhash(&icon));
+        assert_eq!(phash(&large), phash(&icon));
+        assert_ne!(phash(&icon), phash(&gradient));
+    }
+
+    #[test]
+    fn test_decode_png() {
+        // 2x2 RGBA: white, black / transparent, black; the first row is
+        // Sub-filtered.
+        let raw = [
+            1, 255, 255, 255, 255, 1, 1, 1, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 255,
+        ];
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&raw).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        let mut chunk = |kind: &[u8], body: &[u8]| {
+            png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            png.extend_from_slice(kind);
+            png.extend_from_slice(body);
+            png.extend_from_slice(&[0; 4]);
+        };
+        chunk(b"IHDR", &[0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+        chunk(b"IDAT", &compressed);
+        chunk(b"IEND", &[]);
+
+        let gray = decode_png(&png).unwrap();
+        let pixels: Vec<u8> = gray.pixels.iter().map(|p| p.round() as u8).collect();
+        assert_eq!(pixels, [255, 0, 255, 0]);
+    }
+}
@@ -0,0 +1,108 @@
+use super::findings::Severity;
+use crate::binary::BinaryInfo;
+
+/// C library functions that are unsafe by design, with why.
+const INSECURE_FUNCTIONS: &[(&str, Severity, &str)] = &[
+    ("gets", Severity::High, "reads a line with no bounds check"),
+    ("strcpy", Severity::Medium, "unbounded string copy"),
+    ("strcat", Severity::Medium, "unbounded string append"),
+    ("sprintf", Severity::Medium, "unbounded formatted write"),
+    ("vsprintf", Severity::Medium, "unbounded formatted write"),
+    (
+        "system",
+        Severity::Medium,
+        "runs a command through the shell",
+    ),
+    ("tmpnam", Severity::Low, "predictable temporary file name"),
+    ("tempnam", Severity::Low, "predictable temporary file name"),
+    ("mktemp", Severity::Low, "predictable temporary file name"),
+    ("getwd", Severity::Low, "unbounded path buffer"),
+];
+
+/// A call to one of the functions in [`find_insecure_functions`].
+#[derive(Debug, Clone)]
+pub struct InsecureFunction {
+    pub function: &'static str,
+    pub severity: Severity,
+    pub reason: &'static str,
+    /// Imported rather than found in the symbol table.
+    pub imported: bool,
+    /// The import slot or the symbol's address, where known.
+    pub addr: Option<u64>,
+}
+
+/// Flags `gets`, `strcpy`, `sprintf`, `system`, `tmpnam` and similar in the
+/// imports or symbols, once per function.
+pub fn find_insecure_functions(info: &BinaryInfo) -> Vec<InsecureFunction> {
+    let names = info
+        .imports
+        .iter()
+        .map(|i| (i.name.as_str(), true, i.slot))
+        .chain(
+            info.symbols
+                .iter()
+                .map(|s| (s.name.as_str(), false, Some(s.addr).filter(|&a| a != 0))),
+        );
+    let mut found: Vec<InsecureFunction> = Vec::new();
+    for (name, imported, addr) in names {
+        // Versioned ELF names (`gets@GLIBC_2.2.5`) and Mach-O underscores.
+        let name = name.split('@').next().unwrap_or(name);
+        let name = name.strip_prefix('_').unwrap_or(name);
+        let Some(&(function, severity, reason)) =
+            INSECURE_FUNCTIONS.iter().find(|(f, _, _)| *f == name)
+        else {
+            continue;
+        };
+        if found.iter().any(|f| f.function == function) {
+            continue;
+        }
+        found.push(InsecureFunction {
+            function,
+            severity,
+            reason,
+            imported,
+            addr,
+        });
+    }
+    found.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::{ImportInfo, SymbolInfo};
+
+    #[test]
+    fn test_find_insecure_functions() {
+        let import = |name: &str| ImportInfo {
+            name: name.to_string(),
+            library: None,
+            slot: None,
+        };
+        let info = BinaryInfo {
+            imports: vec![
+                import("strcpy@GLIBC_2.2.5"),
+                import("strncpy"),
+                import("gets"),
+            ],
+            symbols: vec![
+                SymbolInfo {
+                    name: "_strcpy".to_string(),
+                    addr: 0x1000,
+                },
+                SymbolInfo {
+                    name: "_tmpnam".to_string(),
+                    addr: 0x2000,
+                },
+            ],
+            ..Default::default()
+        };
+        let found = find_insecure_functions(&info);
+        let names: Vec<_> = found.iter().map(|f| (f.function, f.imported)).collect();
+        assert_eq!(
+            names,
+            vec![("gets", true), ("strcpy", true), ("tmpnam", false)]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use crate::binary::artifacts::overlay_offset;
+use crate::binary::BinaryInfo;
+
+/// NSIS first header: flags, then this signature.
+const NSIS_SIGNATURE: &[u8] = b"\xef\xbe\xad\xdeNullsoftInst";
+const NSIS_VERSION: &[u8] = b"Nullsoft Install System v";
+const INNO_SETUP_DATA: &[u8] = b"Inno Setup Setup Data (";
+const WIX_TOOLSETS: &[&[u8]] = &[b"Windows Installer XML Toolset (", b"WiX Toolset ("];
+const WIX_BURN_SECTION: &str = ".wixburn";
+
+const CFB_MAGIC: &[u8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+const CFB_HEADER_SIZE: usize = 512;
+/// Sector ids at or above this mark free or end-of-chain sectors.
+const CFB_MAX_SECTOR: u32 = 0xfffffffa;
+const CFB_DIR_ENTRY_SIZE: usize = 128;
+const CFB_STREAM: u8 = 2;
+/// MSI table streams carry this character in place of a leading `!`.
+const MSI_TABLE_PREFIX: u16 = 0x4840;
+/// Every MSI database has a string pool table.
+const MSI_STRING_POOL: &str = "!_StringPool";
+
+const CAB_MAGIC: &[u8] = b"MSCF";
+/// Fixed part of a `CFFILE` entry before the name.
+const CAB_FILE_HEADER: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallerKind {
+    Nsis,
+    InnoSetup,
+    Msi,
+    WixBurn,
+}
+
+impl InstallerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InstallerKind::Nsis => "NSIS",
+            InstallerKind::InnoSetup => "Inno Setup",
+            InstallerKind::Msi => "Windows Installer (MSI)",
+            InstallerKind::WixBurn => "WiX Burn bundle",
+        }
+    }
+}
+
+/// A file packed into the installer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallerFile {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallerInfo {
+    pub kind: InstallerKind,
+    pub version: Option<String>,
+    /// Tool that authored the installer, e.g. `WiX Toolset 3.11.2.4516`.
+    pub builder: Option<String>,
+    /// Offset of the installer data (NSIS/Inno header, Burn container).
+    pub offset: Option<u64>,
+    /// Payload files, when the format lists them without decompression:
+    /// MSI streams and cabinet members. Empty for NSIS and Inno Setup.
+    pub files: Vec<InstallerFile>,
+}
+
+/// Fingerprints the installer framework of a setup executable or MSI.
+pub fn detect_installer(data: &[u8], info: &BinaryInfo) -> Option<InstallerInfo> {
+    if data.starts_with(CFB_MAGIC) {
+        return parse_msi(data);
+    }
+    if info.format != "PE" {
+        return None;
+    }
+    let overlay = overlay_offset(data).map(|o| o as usize);
+
+    if info.sections.iter().any(|s| s.name == WIX_BURN_SECTION) {
+        return Some(InstallerInfo {
+            kind: InstallerKind::WixBurn,
+            version: None,
+            builder: find_wix_toolset(data),
+            offset: overlay.map(|o| o as u64),
+            files: overlay
+                .and_then(|o| list_cab(&data[o..]))
+                .unwrap_or_default(),
+        });
+    }
+    if let Some(offset) = find(&data[overlay.unwrap_or(0)..], NSIS_SIGNATURE) {
+        return Some(InstallerInfo {
+            kind: InstallerKind::Nsis,
+            version: version_after(data, NSIS_VERSION, b" <\"\0"),
+            builder: None,
+            // The flags word precedes the signature.
+            offset: (overlay.unwrap_or(0) + offset)
+                .checked_sub(4)
+                .map(|o| o as u64),
+            files: Vec::new(),
+        });
+    }
+    if let Some(offset) = find(data, INNO_SETUP_DATA) {
+        return Some(InstallerInfo {
+            kind: InstallerKind::InnoSetup,
+            version: version_after(data, INNO_SETUP_DATA, b")"),
+            builder: None,
+            offset: Some(offset as u64),
+            files: Vec::new(),
+        });
+    }
+    None
+}
+
+/// Text following `prefix` up to one of `terminators`, if it looks like a version.
+fn version_after(data: &[u8], prefix: &[u8], terminators: &[u8]) -> Option<String> {
+    let start = find(data, prefix)? + prefix.len();
+    let rest = data.get(start..start + 32.min(data.len() - start))?;
+    let end = rest
+        .iter()
+        .position(|b| terminators.contains(b) || !b.is_ascii_graphic())
+        .unwrap_or(rest.len());
+    let version = std::str::from_utf8(&rest[..end]).ok()?;
+    version
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then(|| version.to_string())
+}
+
+fn find_wix_toolset(data: &[u8]) -> Option<String> {
+    WIX_TOOLSETS.iter().find_map(|prefix| {
+        let version = version_after(data, prefix, b")")?;
+        Some(format!("WiX Toolset {}", version))
+    })
+}
+
+/// Lists the streams of an MSI database and the members of its cabinets.
+fn parse_msi(data: &[u8]) -> Option<InstallerInfo> {
+    let cfb = Cfb::parse(data)?;
+    let streams = cfb.streams();
+    if !streams.iter().any(|s| s.name == MSI_STRING_POOL) {
+        return None;
+    }
+    let mut files = Vec::new();
+    for stream in &streams {
+        // Tables and property sets are database internals.
+        if stream.name.starts_with(['!', '\u{5}']) {
+            continue;
+        }
+        files.push(InstallerFile {
+            name: stream.name.clone(),
+            size: stream.size,
+        });
+        if let Some(members) = cfb.read_stream(stream).and_then(|s| list_cab(&s)) {
+            files.extend(members.into_iter().map(|member| InstallerFile {
+                name: format!("{}/{}", stream.name, member.name),
+                size: member.size,
+            }));
+        }
+    }
+    Some(InstallerInfo {
+        kind: InstallerKind::Msi,
+        version: None,
+        builder: find_wix_toolset(data),
+        offset: None,
+        files,
+    })
+}
+
+/// Member names and sizes from a cabinet's `CFFILE` table.
+fn list_cab(data: &[u8]) -> Option<Vec<InstallerFile>> {
+    if !data.starts_with(CAB_MAGIC) {
+        return None;
+    }
+    let files_offset = u32::from_le_bytes(data.get(16..20)?.try_into().ok()?) as usize;
+    let count = u16::from_le_bytes(data.get(28..30)?.try_into().ok()?);
+    let mut pos = files_offset;
+    let mut files = Vec::new();
+    for _ in 0..count {
+        let size = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+        let name = data.get(pos + CAB_FILE_HEADER..)?;
+        let len = name.iter().position(|&b| b == 0)?;
+        files.push(InstallerFile {
+            name: String::from_utf8_lossy(&name[..len]).replace('\\', "/"),
+            size: size as u64,
+        });
+        pos += CAB_FILE_HEADER + len + 1;
+    }
+    Some(files)
+}
+
+struct CfbStream {
+    name: String,
+    start: u32,
+    size: u64,
+}
+
+/// Just enough of an OLE compound file to list and read its streams.
+struct Cfb<'a> {
+    data: &'a [u8],
+    sector_size: usize,
+    mini_cutoff: u64,
+    fat: Vec<u32>,
+    directory: Vec<u8>,
+}
+
+impl<'a> Cfb<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let header = data.get(..CFB_HEADER_SIZE)?;
+        let u32_at = |off: usize| u32::from_le_bytes(header[off..off + 4].try_into().unwrap());
+        let shift = u16::from_le_bytes([header[0x1e], header[0x1f]]);
+        if !(9..=12).contains(&shift) {
+            return None;
+        }
+        let sector_size = 1usize << shift;
+
+        // The header lists the first 109 FAT sectors, DIFAT sectors the rest.
+        let mut fat_sectors: Vec<u32> = (0..109).map(|i| u32_at(0x4c + i * 4)).collect();
+        let mut difat = u32_at(0x44);
+        for _ in 0..(u32_at(0x48) as usize).min(data.len() / sector_size) {
+            let sector = sector_slice(data, sector_size, difat)?;
+            let ids: Vec<u32> = sector
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+                .collect();
+            let (next, ids) = ids.split_last()?;
+            fat_sectors.extend(ids);
+            difat = *next;
+        }
+        // Repeated FAT sectors would multiply the table past the file size.
+        let fat_count = (u32_at(0x2c) as usize).min(data.len() / sector_size);
+        let mut fat = Vec::new();
+        for &sector in fat_sectors.iter().take(fat_count) {
+            let sector = sector_slice(data, sector_size, sector)?;
+            fat.extend(
+                sector
+                    .chunks_exact(4)
+                    .map(|c| u32::from_le_bytes(c.try_into().unwrap())),
+            );
+        }
+
+        let mut cfb = Cfb {
+            data,
+            sector_size,
+            mini_cutoff: u32_at(0x38) as u64,
+            fat,
+            directory: Vec::new(),
+        };
+        cfb.directory = cfb.read_chain(u32_at(0x30), None)?;
+        Some(cfb)
+    }
+
+    /// Concatenated sectors of a FAT chain, up to `len` bytes.
+    fn read_chain(&self, mut sector: u32, len: Option<u64>) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        // Each sector is read at most once, so a looping chain stops and the
+        // output never exceeds the file.
+        let mut visited = vec![false; self.data.len() / self.sector_size];
+        loop {
+            if sector >= CFB_MAX_SECTOR || len.is_some_and(|len| out.len() as u64 >= len) {
+                break;
+            }
+            let seen = visited.get_mut(sector as usize)?;
+            if std::mem::replace(seen, true) {
+                break;
+            }
+            out.extend_from_slice(sector_slice(self.data, self.sector_size, sector)?);
+            sector = *self.fat.get(sector as usize)?;
+        }
+        if let Some(len) = len {
+            out.truncate(len as usize);
+        }
+        Some(out)
+    }
+
+    fn streams(&self) -> Vec<CfbStream> {
+        self.directory
+            .chunks_exact(CFB_DIR_ENTRY_SIZE)
+            .filter(|entry| entry[0x42] == CFB_STREAM)
+            .map(|entry| {
+                let name_len = (u16::from_le_bytes([entry[0x40], entry[0x41]]) as usize).min(64);
+                let units: Vec<u16> = entry[..name_len]
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .take_while(|&u| u != 0)
+                    .collect();
+                CfbStream {
+                    name: decode_msi_name(&units),
+                    start: u32::from_le_bytes(entry[0x74..0x78].try_into().unwrap()),
+                    // Version 3 files only define the low half.
+                    size: u32::from_le_bytes(entry[0x78..0x7c].try_into().unwrap()) as u64,
+                }
+            })
+            .collect()
+    }
+
+    /// Contents of a stream stored in regular sectors; small streams in the
+    /// mini stream are not read.
+    fn read_stream(&self, stream: &CfbStream) -> Option<Vec<u8>> {
+        if stream.size < self.mini_cutoff {
+            return None;
+        }
+        self.read_chain(stream.start, Some(stream.size))
+    }
+}
+
+fn sector_slice(data: &[u8], sector_size: usize, sector: u32) -> Option<&[u8]> {
+    let start = (sector as usize + 1).checked_mul(sector_size)?;
+    data.get(start..start + sector_size)
+}
+
+/// MSI packs two characters of `[0-9A-Za-z._]` into one UTF-16 unit of
+/// the stream name.
+fn decode_msi_name(units: &[u16]) -> String {
+    fn mime(value: u16) -> char {
+        match value {
+            0..=9 => (b'0' + value as u8) as char,
+            10..=35 => (b'A' + (value - 10) as u8) as char,
+            36..=61 => (b'a' + (value - 36) as u8) as char,
+            62 => '.',
+            _ => '_',
+        }
+    }
+    let mut name = String::new();
+    for &unit in units {
+        match unit {
+            MSI_TABLE_PREFIX => name.push('!'),
+            0x4800..0x4840 => name.push(mime(unit - 0x4800)),
+            0x3800..0x4800 => {
+                let value = unit - 0x3800;
+                name.push(mime(value & 0x3f));
+                name.push(mime((value >> 6) & 0x3f));
+            }
+            _ => name.extend(char::decode_utf16([unit]).map(|c| c.unwrap_or('?'))),
+        }
+    }
+    name
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cab(files: &[(&str, u32)]) -> Vec<u8> {
+        let mut cab = CAB_MAGIC.to_vec();
+        cab.resize(36, 0);
+        cab[16..20].copy_from_slice(&36u32.to_le_bytes());
+        cab[28..30].copy_from_slice(&(files.len() as u16).to_le_bytes());
+        for (name, size) in files {
+            cab.extend(size.to_le_bytes());
+            cab.extend([0; CAB_FILE_HEADER - 4]);
+            cab.extend(name.as_bytes());
+            cab.push(0);
+        }
+        cab
+    }
+
+    #[test]
+    fn test_list_cab() {
+        let files = list_cab(&cab(&[("app\\main.exe", 4096), ("readme.txt", 12)])).unwrap();
+        assert_eq!(
+            files,
+            [
+                InstallerFile {
+                    name: "app/main.exe".to_string(),
+                    size: 4096
+                },
+                InstallerFile {
+                    name: "readme.txt".to_string(),
+                    size: 12
+                },
+            ]
+        );
+        assert!(list_cab(b"MZ").is_none());
+    }
+
+    #[test]
+    fn test_cfb_looping_chain() {
+        let mut data = vec![0u8; 8192];
+        data[..8].copy_from_slice(CFB_MAGIC);
+        data[0x1e] = 9; // 512-byte sectors
+        data[0x2c..0x30].copy_from_slice(&1000u32.to_le_bytes()); // FAT sectors
+        data[0x30..0x34].copy_from_slice(&1u32.to_le_bytes()); // directory start
+        data[0x44..0x48].copy_from_slice(&0xfffffffeu32.to_le_bytes());
+        // Every header FAT entry names sector 0, whose second entry points
+        // the directory sector back at itself.
+        data[0x200..0x204].copy_from_slice(&0xfffffffdu32.to_le_bytes());
+        data[0x204..0x208].copy_from_slice(&1u32.to_le_bytes());
+        let cfb = Cfb::parse(&data).unwrap();
+        assert!(cfb.fat.len() * 4 <= data.len());
+        assert_eq!(cfb.directory.len(), 512);
+    }
+
+    #[test]
+    fn test_decode_msi_name() {
+        // "!_StringPool" as MSI stores it.
+        let units = [
+            MSI_TABLE_PREFIX,
+            0x3800 + (63 | 28 << 6),
+            0x3800 + (55 | 53 << 6),
+            0x3800 + (44 | 49 << 6),
+            0x3800 + (42 | 25 << 6),
+            0x3800 + (50 | 50 << 6),
+            0x4800 + 47,
+        ];
+        assert_eq!(decode_msi_name(&units), MSI_STRING_POOL);
+        assert_eq!(decode_msi_name(&[0x5, b'S' as u16]), "\u{5}S");
+    }
+
+    #[test]
+    fn test_detect_setup_executables() {
+        let info = BinaryInfo {
+            format: "PE".to_string(),
+            ..Default::default()
+        };
+        let mut data = b"MZ <description>Nullsoft Install System v3.08</description>".to_vec();
+        data.extend(b"\x00\x00\x00\x00");
+        data.extend(NSIS_SIGNATURE);
+        let nsis = detect_installer(&data, &info).unwrap();
+        assert_eq!(nsis.kind, InstallerKind::Nsis);
+        assert_eq!(nsis.version.as_deref(), Some("3.08"));
+        assert_eq!(nsis.offset, Some(59));
+
+        let inno = detect_installer(b"MZ....Inno Setup Setup Data (6.2.0) (u)", &info).unwrap();
+        assert_eq!(inno.kind, InstallerKind::InnoSetup);
+        assert_eq!(inno.version.as_deref(), Some("6.2.0"));
+        assert_eq!(inno.offset, Some(6));
+
+        assert!(detect_installer(b"MZ plain program", &info).is_none());
+        assert!(detect_installer(&data, &BinaryInfo::default()).is_none());
+    }
+}
@@ -0,0 +1,368 @@
+use crate::binary::compression::{self, Compression};
+use crate::binary::BinaryInfo;
+use anyhow::{anyhow, bail, Result};
+
+const BZIMAGE_MAGIC_OFFSET: usize = 0x202;
+const ARM64_IMAGE_MAGIC_OFFSET: usize = 0x38;
+const IKCONFIG_START: &[u8] = b"IKCFG_ST";
+const IKCONFIG_END: &[u8] = b"IKCFG_ED";
+const VERSION_PREFIX: &[u8] = b"Linux version ";
+const MODULE_SIG_MAGIC: &[u8] = b"~Module signature appended~\n";
+const GPL_COMPATIBLE: &[&str] = &[
+    "GPL",
+    "GPL v2",
+    "GPL and additional rights",
+    "Dual BSD/GPL",
+    "Dual MIT/GPL",
+    "Dual MPL/GPL",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelImageKind {
+    /// x86 boot image whose compressed vmlinux payload was unpacked.
+    BzImage,
+    /// Plain kernel ELF.
+    Vmlinux,
+    /// Raw arm64 `Image` as produced by the kernel build.
+    Arm64Image,
+}
+
+impl KernelImageKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KernelImageKind::BzImage => "bzImage",
+            KernelImageKind::Vmlinux => "vmlinux",
+            KernelImageKind::Arm64Image => "arm64 Image",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KernelInfo {
+    pub image: KernelImageKind,
+    pub version: Option<String>,
+    /// Non-comment lines of the embedded `.config` (CONFIG_IKCONFIG).
+    pub config: Vec<String>,
+    /// Number of kallsyms entries, if the table could be located.
+    pub kallsyms_count: Option<u64>,
+}
+
+pub fn is_bzimage(data: &[u8]) -> bool {
+    data.get(BZIMAGE_MAGIC_OFFSET..BZIMAGE_MAGIC_OFFSET + 4) == Some(b"HdrS")
+}
+
+pub fn is_arm64_image(data: &[u8]) -> bool {
+    data.get(ARM64_IMAGE_MAGIC_OFFSET..ARM64_IMAGE_MAGIC_OFFSET + 4) == Some(b"ARM\x64")
+}
+
+/// Locates the compressed payload of a bzImage via the x86 boot protocol
+/// setup header and decompresses it, yielding the embedded vmlinux.
+pub fn extract_bzimage_payload(data: &[u8]) -> Result<Vec<u8>> {
+    if !is_bzimage(data) {
+        bail!("Not a bzImage (missing HdrS signature)");
+    }
+
+    let setup_sects = match data.get(0x1f1) {
+        Some(0) | None => 4,
+        Some(&n) => n as usize,
+    };
+    let pm_start = (setup_sects + 1) * 512;
+    let protocol = read_u16(data, 0x206).unwrap_or(0);
+
+    let payload = if protocol >= 0x208 {
+        let offset = read_u32(data, 0x248).ok_or_else(|| anyhow!("Truncated setup header"))?;
+        let length = read_u32(data, 0x24c).ok_or_else(|| anyhow!("Truncated setup header"))?;
+        let start = pm_start + offset as usize;
+        let end = start + length as usize;
+        data.get(start..end)
+            .ok_or_else(|| anyhow!("Kernel payload lies beyond end of file"))?
+    } else {
+        // Old boot protocols do not describe the payload; fall back to the first gzip stream.
+        let rest = data
+            .get(pm_start..)
+            .ok_or_else(|| anyhow!("Truncated bzImage"))?;
+        let start = find(rest, &[0x1f, 0x8b, 0x08])
+            .ok_or_else(|| anyhow!("No compressed kernel payload found"))?;
+        &rest[start..]
+    };
+
+    let kind = Compression::detect(payload)
+        .ok_or_else(|| anyhow!("Unsupported kernel payload compression"))?;
+    // The build appends the decompressed size as a trailing le32.
+    let payload = if protocol >= 0x208 && payload.len() > 4 {
+        &payload[..payload.len() - 4]
+    } else {
+        payload
+    };
+    compression::decompress(payload, kind, compression::DEFAULT_DECOMPRESSION_LIMIT)
+}
+
+/// Kernel images recognizable from their headers alone: a bzImage whose
+/// payload was unpacked, or an arm64 `Image`. A vmlinux is only known by
+/// its version string, see [`analyze_kernel`].
+pub fn detect_image(data: &[u8], unpacked_bzimage: bool) -> Option<KernelImageKind> {
+    if unpacked_bzimage {
+        Some(KernelImageKind::BzImage)
+    } else if is_arm64_image(data) {
+        Some(KernelImageKind::Arm64Image)
+    } else {
+        None
+    }
+}
+
+/// Returns kernel metadata if the (already unwrapped) image looks like a
+/// Linux kernel. Scans the whole file for the version string and config.
+pub fn analyze_kernel(data: &[u8], info: &BinaryInfo) -> Option<KernelInfo> {
+    let version = find_version(data);
+    let image = match info.kernel.as_ref().map(|k| k.image) {
+        Some(image) => image,
+        None if info.format == "ELF"
+            && version.is_some()
+            && info
+                .sections
+                .iter()
+                .any(|s| s.name == "__ksymtab" || s.name == ".init.text") =>
+        {
+            KernelImageKind::Vmlinux
+        }
+        None => return None,
+    };
+
+    Some(KernelInfo {
+        image,
+        version,
+        config: extract_config(data),
+        kallsyms_count: kallsyms_count(data, info),
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModuleParam {
+    pub name: String,
+    pub kind: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KernelModuleInfo {
+    pub name: Option<String>,
+    pub license: Option<String>,
+    pub vermagic: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub parameters: Vec<ModuleParam>,
+    pub depends: Vec<String>,
+    /// Symbols exported to the rest of the kernel via `EXPORT_SYMBOL*`.
+    pub exports: Vec<String>,
+    pub signed: bool,
+    pub in_tree: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Returns module metadata if the binary is a loadable kernel module (`.ko`).
+pub fn analyze_module(data: &[u8], info: &BinaryInfo) -> Option<KernelModuleInfo> {
+    if info.format != "ELF"
+        || !info
+            .sections
+            .iter()
+            .any(|s| s.name == ".gnu.linkonce.this_module")
+    {
+        return None;
+    }
+    let modinfo = info.sections.iter().find(|s| s.name == ".modinfo")?;
+    let start = modinfo.offset as usize;
+    let bytes = data.get(start..start + modinfo.size as usize)?;
+
+    let mut module = KernelModuleInfo::default();
+    for entry in bytes.split(|&b| b == 0).filter(|e| !e.is_empty()) {
+        let entry = String::from_utf8_lossy(entry);
+        let Some((key, value)) = entry.split_once('=') else {
+            continue;
+        };
+        match key {
+            "name" => module.name = Some(value.to_string()),
+            "license" => module.license = Some(value.to_string()),
+            "vermagic" => module.vermagic = Some(value.to_string()),
+            "version" => module.version = Some(value.to_string()),
+            "description" => module.description = Some(value.to_string()),
+            "author" => module.author = Some(value.to_string()),
+            "intree" => module.in_tree = value == "Y",
+            "depends" => {
+                module.depends = value
+                    .split(',')
+                    .filter(|d| !d.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            "parm" | "parmtype" => {
+                let (name, rest) = value.split_once(':').unwrap_or((value, ""));
+                let index = match module.parameters.iter().position(|p| p.name == name) {
+                    Some(index) => index,
+                    None => {
+                        module.parameters.push(ModuleParam {
+                            name: name.to_string(),
+                            ..Default::default()
+                        });
+                        module.parameters.len() - 1
+                    }
+                };
+                let param = &mut module.parameters[index];
+                if key == "parm" {
+                    param.description = Some(rest.to_string());
+                } else {
+                    param.kind = Some(rest.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    module.exports = info
+        .symbols
+        .iter()
+        .filter_map(|s| s.name.strip_prefix("__ksymtab_"))
+        .filter(|name| *name != "strings")
+        .map(str::to_string)
+        .collect();
+    module.signed = data.ends_with(MODULE_SIG_MAGIC);
+
+    if !module.signed {
+        module
+            .warnings
+            .push("Module is not signed (no appended module signature)".to_string());
+    }
+    if !module.in_tree {
+        module
+            .warnings
+            .push("Module is out-of-tree (no intree=Y in .modinfo)".to_string());
+    }
+    match &module.license {
+        Some(license) if GPL_COMPATIBLE.contains(&license.as_str()) => {}
+        Some(license) => module.warnings.push(format!(
+            "Non-GPL-compatible license \"{}\" taints the kernel",
+            license
+        )),
+        None => module
+            .warnings
+            .push("Module declares no license".to_string()),
+    }
+
+    Some(module)
+}
+
+fn find_version(data: &[u8]) -> Option<String> {
+    let start = find(data, VERSION_PREFIX)?;
+    let rest = &data[start..];
+    let end = rest
+        .iter()
+        .position(|&b| b == 0 || b == b'\n')
+        .unwrap_or(rest.len().min(256));
+    Some(String::from_utf8_lossy(&rest[..end]).trim().to_string())
+}
+
+fn extract_config(data: &[u8]) -> Vec<String> {
+    let Some(start) = find(data, IKCONFIG_START) else {
+        return Vec::new();
+    };
+    let body = &data[start + IKCONFIG_START.len()..];
+    let Some(end) = find(body, IKCONFIG_END) else {
+        return Vec::new();
+    };
+
+    match compression::decompress(
+        &body[..end],
+        Compression::Gzip,
+        compression::DEFAULT_DECOMPRESSION_LIMIT,
+    ) {
+        Ok(text) => String::from_utf8_lossy(&text)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_string)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn kallsyms_count(data: &[u8], info: &BinaryInfo) -> Option<u64> {
+    let sym = info
+        .symbols
+        .iter()
+        .find(|s| s.name == "kallsyms_num_syms")?;
+    let offset = info.addr_to_offset(sym.addr)? as usize;
+    read_u32(data, offset).map(u64::from)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_version() {
+        let data = b"\x00\x00Linux version 6.1.0-13-amd64 (gcc 12.2)\n\x00";
+        assert_eq!(
+            find_version(data).as_deref(),
+            Some("Linux version 6.1.0-13-amd64 (gcc 12.2)")
+        );
+    }
+
+    #[test]
+    fn test_analyze_module_modinfo() {
+        use crate::binary::SectionInfo;
+
+        let modinfo = b"license=Proprietary\0depends=usbcore,hid\0parm=debug:Enable debug\0parmtype=debug:int\0";
+        let info = BinaryInfo {
+            format: "ELF".to_string(),
+            sections: vec![
+                SectionInfo {
+                    name: ".modinfo".to_string(),
+                    addr: 0,
+                    size: modinfo.len() as u64,
+                    offset: 0,
+                    hashes: None,
+                    zero_fill: false,
+                },
+                SectionInfo {
+                    name: ".gnu.linkonce.this_module".to_string(),
+                    addr: 0,
+                    size: 0,
+                    offset: 0,
+                    hashes: None,
+                    zero_fill: false,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let module = analyze_module(modinfo, &info).expect("not detected as module");
+        assert_eq!(module.depends, vec!["usbcore", "hid"]);
+        assert_eq!(module.parameters.len(), 1);
+        assert_eq!(module.parameters[0].kind.as_deref(), Some("int"));
+        assert!(!module.signed);
+        assert!(!module.in_tree);
+        assert_eq!(module.warnings.len(), 3);
+    }
+
+    #[test]
+    fn test_is_bzimage() {
+        let mut data = vec![0u8; 0x300];
+        assert!(!is_bzimage(&data));
+        data[0x202..0x206].copy_from_slice(b"HdrS");
+        assert!(is_bzimage(&data));
+    }
+}
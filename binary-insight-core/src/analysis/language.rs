@@ -0,0 +1,300 @@
+use crate::binary::BinaryInfo;
+use serde::Serialize;
+
+/// Script blob headers that Aut2Exe appends as the `SCRIPT` resource.
+const AUTOIT_SCRIPT_MAGIC: &[&[u8]] = &[b"AU3!EA06", b"AU3!EA05"];
+const AUTOIT_STUB: &str = "This is a third-party compiled AutoIt script.";
+const DELPHI_STRINGS: &[&str] = &["SOFTWARE\\Borland\\Delphi\\RTL", "Embarcadero Delphi"];
+/// `DVCLAL` license resource, as the UTF-16 name stored in `.rsrc`.
+const DELPHI_DVCLAL: &[u8] = b"D\0V\0C\0L\0A\0L\0";
+/// Section names of the Borland linker.
+const BORLAND_SECTIONS: &[&str] = &["CODE", "DATA", "BSS"];
+const NIM_SYMBOLS: &[&str] = &["NimMain", "NimMainModule", "nimGC_setStackBottom"];
+const NIM_STRINGS: &[&str] = &[
+    "fatal.nim",
+    "SIGSEGV: Illegal storage access. (Attempt to read from nil?)",
+];
+const GCC_COMMENT: &str = "GCC: (";
+const CLANG_COMMENT: &str = "clang version ";
+/// MSVC's linker leaves this marker at the end of the Rich header.
+const RICH_MAGIC: &[u8] = b"Rich";
+const DOS_HEADER_SIZE: usize = 0x40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Language {
+    Go,
+    Rust,
+    #[serde(rename = ".NET")]
+    DotNet,
+    Delphi,
+    Nim,
+    AutoIt,
+    Python,
+    JavaScript,
+    #[serde(rename = "C/C++")]
+    C,
+}
+
+impl Language {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Go => "Go",
+            Language::Rust => "Rust",
+            Language::DotNet => ".NET",
+            Language::Delphi => "Delphi",
+            Language::Nim => "Nim",
+            Language::AutoIt => "AutoIt",
+            Language::Python => "Python",
+            Language::JavaScript => "JavaScript",
+            Language::C => "C/C++",
+        }
+    }
+}
+
+/// What a program was written in and what it was packaged with.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageInfo {
+    pub language: Option<Language>,
+    /// Compiler, runtime or interpreter version, e.g. `go1.21.5`.
+    pub version: Option<String>,
+    /// Bundler or installer wrapped around the program, e.g. `PyInstaller`.
+    pub packaged_with: Option<String>,
+    /// The fingerprints the classification rests on.
+    pub evidence: Vec<String>,
+}
+
+impl LanguageInfo {
+    /// One line, e.g. `Python 3.11, packaged with PyInstaller`.
+    pub fn summary(&self) -> String {
+        let mut summary = match (&self.language, &self.version) {
+            (Some(language), Some(version)) => format!("{} {}", language.name(), version),
+            (Some(language), None) => language.name().to_string(),
+            (None, _) => "unknown".to_string(),
+        };
+        if let Some(packager) = &self.packaged_with {
+            summary.push_str(&format!(", packaged with {}", packager));
+        }
+        summary
+    }
+}
+
+/// Combines the toolchain passes with import, string and structural
+/// fingerprints. Script packagers win over the language of their stub, so a
+/// PyInstaller executable is Python rather than C.
+pub fn classify(data: &[u8], info: &BinaryInfo) -> Option<LanguageInfo> {
+    let mut result = LanguageInfo {
+        language: None,
+        version: None,
+        packaged_with: None,
+        evidence: Vec::new(),
+    };
+    let mut set = |language, version: Option<String>, packager: Option<String>, evidence: &str| {
+        result.language = Some(language);
+        result.version = version;
+        result.packaged_with = packager;
+        result.evidence.push(evidence.to_string());
+    };
+
+    if let Some(archive) = &info.pyinstaller {
+        set(
+            Language::Python,
+            Some(archive.python_version.clone()),
+            Some("PyInstaller".to_string()),
+            "PyInstaller CArchive",
+        );
+    } else if let Some(runtime) = &info.app_runtime {
+        let packager = match &runtime.version {
+            Some(version) => format!("{} {}", runtime.kind.name(), version),
+            None => runtime.kind.name().to_string(),
+        };
+        set(
+            Language::JavaScript,
+            runtime
+                .node_version
+                .as_ref()
+                .map(|v| format!("Node.js {}", v)),
+            Some(packager),
+            "bundled JavaScript runtime",
+        );
+    } else if AUTOIT_SCRIPT_MAGIC.iter().any(|m| contains(data, m)) {
+        set(
+            Language::AutoIt,
+            None,
+            Some("Aut2Exe".to_string()),
+            "compiled AutoIt script",
+        );
+    } else if has_string(info, &[AUTOIT_STUB]) {
+        set(Language::AutoIt, None, None, "AutoIt interpreter stub");
+    } else if let Some(dotnet) = &info.dotnet {
+        set(
+            Language::DotNet,
+            Some(dotnet.runtime_version.clone()),
+            None,
+            "CLR metadata",
+        );
+    } else if let Some(go) = &info.go {
+        set(Language::Go, go.version.clone(), None, "Go build info");
+    } else if let Some(rust) = &info.rust {
+        set(
+            Language::Rust,
+            rust.rustc_version.clone(),
+            None,
+            "rustc symbols and panic paths",
+        );
+    } else if info
+        .symbols
+        .iter()
+        .chain(&info.exports)
+        .any(|s| NIM_SYMBOLS.contains(&s.name.as_str()))
+    {
+        set(Language::Nim, None, None, "Nim runtime symbols");
+    } else if has_string(info, NIM_STRINGS) {
+        set(Language::Nim, None, None, "Nim runtime strings");
+    } else if let Some(evidence) = delphi_evidence(data, info) {
+        set(Language::Delphi, None, None, evidence);
+    } else if let Some((version, evidence)) = native_compiler(data, info) {
+        set(Language::C, version, None, evidence);
+    }
+
+    if let Some(installer) = &info.installer {
+        let installer_name = match &installer.version {
+            Some(version) => format!("{} {}", installer.kind.name(), version),
+            None => installer.kind.name().to_string(),
+        };
+        result.packaged_with = Some(match result.packaged_with.take() {
+            Some(inner) => format!("{} in {}", inner, installer_name),
+            None => installer_name,
+        });
+        result.evidence.push("installer data".to_string());
+    }
+    (result.language.is_some() || result.packaged_with.is_some()).then_some(result)
+}
+
+fn delphi_evidence(data: &[u8], info: &BinaryInfo) -> Option<&'static str> {
+    if info.format != "PE" {
+        return None;
+    }
+    if has_string(info, DELPHI_STRINGS) {
+        Some("Delphi RTL strings")
+    } else if contains(data, DELPHI_DVCLAL) {
+        Some("DVCLAL resource")
+    } else if info.libraries.iter().any(|lib| {
+        let lib = lib.to_ascii_lowercase();
+        (lib.starts_with("rtl") || lib.starts_with("vcl")) && lib.ends_with(".bpl")
+    }) {
+        Some("Delphi runtime packages")
+    } else if BORLAND_SECTIONS
+        .iter()
+        .all(|name| info.sections.iter().any(|s| s.name == *name))
+    {
+        Some("Borland linker sections")
+    } else {
+        None
+    }
+}
+
+/// GCC or clang from the `.comment` strings, or MSVC from a Rich header.
+fn native_compiler(data: &[u8], info: &BinaryInfo) -> Option<(Option<String>, &'static str)> {
+    for (marker, evidence) in [
+        (GCC_COMMENT, "GCC version string"),
+        (CLANG_COMMENT, "clang version string"),
+    ] {
+        if let Some(comment) = info.strings.iter().find(|s| s.starts_with(marker)) {
+            return Some((Some(comment.replacen(": (", " (", 1)), evidence));
+        }
+    }
+    if info.format == "PE" && has_rich_header(data) {
+        return Some((Some("MSVC".to_string()), "Rich header"));
+    }
+    None
+}
+
+/// The Rich header sits between the DOS header and the PE header.
+fn has_rich_header(data: &[u8]) -> bool {
+    let Some(lfanew) = data.get(0x3c..0x40) else {
+        return false;
+    };
+    let lfanew = u32::from_le_bytes([lfanew[0], lfanew[1], lfanew[2], lfanew[3]]) as usize;
+    data.get(DOS_HEADER_SIZE..lfanew)
+        .is_some_and(|stub| contains(stub, RICH_MAGIC))
+}
+
+fn has_string(info: &BinaryInfo, markers: &[&str]) -> bool {
+    info.strings
+        .iter()
+        .any(|s| markers.iter().any(|m| s.contains(m)))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::golang::GoInfo;
+    use crate::binary::SectionInfo;
+
+    fn pe_info() -> BinaryInfo {
+        BinaryInfo {
+            format: "PE".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_classify_toolchains() {
+        let go = BinaryInfo {
+            go: Some(GoInfo {
+                version: Some("go1.21.5".to_string()),
+                ..Default::default()
+            }),
+            strings: vec!["GCC: (GNU) 13.2.0".to_string()],
+            ..Default::default()
+        };
+        let result = classify(b"", &go).unwrap();
+        assert_eq!(result.language, Some(Language::Go));
+        assert_eq!(result.summary(), "Go go1.21.5");
+
+        let gcc = BinaryInfo {
+            strings: vec!["GCC: (GNU) 13.2.0".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            classify(b"", &gcc).unwrap().summary(),
+            "C/C++ GCC (GNU) 13.2.0"
+        );
+        assert!(classify(b"", &BinaryInfo::default()).is_none());
+    }
+
+    #[test]
+    fn test_classify_fingerprints() {
+        let mut delphi = pe_info();
+        delphi.sections = ["CODE", "DATA", "BSS", ".idata"]
+            .iter()
+            .map(|name| SectionInfo {
+                name: name.to_string(),
+                addr: 0,
+                size: 0,
+                offset: 0,
+                hashes: None,
+                zero_fill: false,
+            })
+            .collect();
+        let result = classify(b"", &delphi).unwrap();
+        assert_eq!(result.language, Some(Language::Delphi));
+        assert_eq!(result.evidence, ["Borland linker sections"]);
+
+        let autoit = classify(b"MZ....\xa3\x48\x4b\xbeAU3!EA06", &pe_info()).unwrap();
+        assert_eq!(autoit.summary(), "AutoIt, packaged with Aut2Exe");
+
+        let mut nim = pe_info();
+        nim.strings = vec!["@fatal.nim".to_string()];
+        assert_eq!(classify(b"", &nim).unwrap().language, Some(Language::Nim));
+
+        let mut msvc = vec![0u8; 0x100];
+        msvc[0x3c] = 0xc0;
+        msvc[0x90..0x94].copy_from_slice(RICH_MAGIC);
+        assert_eq!(classify(&msvc, &pe_info()).unwrap().summary(), "C/C++ MSVC");
+    }
+}
@@ -0,0 +1,120 @@
+use goblin::elf::program_header::PT_TLS;
+use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR};
+use goblin::elf::Elf;
+
+/// GOT larger than this share of the mapped image is considered unusual.
+const HUGE_GOT_RATIO: f64 = 0.25;
+const HUGE_GOT_MIN_SIZE: u64 = 4096;
+
+#[derive(Debug, Default, Clone)]
+pub struct LinkerSummary {
+    pub pointer_size: u64,
+    /// Combined size of `.got` and `.got.plt`.
+    pub got_size: u64,
+    pub got_entries: u64,
+    pub got_executable: bool,
+    pub plt_size: u64,
+    /// One stub per `JUMP_SLOT` relocation.
+    pub plt_stubs: u64,
+    /// Initialized TLS template (`.tdata`) size from `PT_TLS`.
+    pub tls_init_size: u64,
+    /// Full per-thread TLS block size including `.tbss`.
+    pub tls_total_size: u64,
+    pub tls_align: u64,
+    /// Total size of all allocated sections, used as a reference for ratios.
+    pub image_size: u64,
+    pub warnings: Vec<String>,
+}
+
+pub fn analyze_linker_elf(elf: &Elf) -> LinkerSummary {
+    let mut summary = LinkerSummary {
+        pointer_size: if elf.is_64 { 8 } else { 4 },
+        ..Default::default()
+    };
+
+    for sh in &elf.section_headers {
+        let name = elf.shdr_strtab.get_at(sh.sh_name).unwrap_or("");
+        let flags = sh.sh_flags as u32;
+        if flags & SHF_ALLOC != 0 {
+            summary.image_size = summary.image_size.saturating_add(sh.sh_size);
+        }
+        match name {
+            ".got" | ".got.plt" => {
+                summary.got_size = summary.got_size.saturating_add(sh.sh_size);
+                if flags & SHF_EXECINSTR != 0 {
+                    summary.got_executable = true;
+                }
+            }
+            ".plt" | ".plt.sec" | ".plt.got" => {
+                summary.plt_size = summary.plt_size.saturating_add(sh.sh_size)
+            }
+            _ => {}
+        }
+    }
+    summary.got_entries = summary.got_size / summary.pointer_size;
+    summary.plt_stubs = elf.pltrelocs.len() as u64;
+
+    if let Some(tls) = elf.program_headers.iter().find(|ph| ph.p_type == PT_TLS) {
+        summary.tls_init_size = tls.p_filesz;
+        summary.tls_total_size = tls.p_memsz;
+        summary.tls_align = tls.p_align;
+    }
+
+    summary.warnings = linker_warnings(&summary);
+    summary
+}
+
+fn linker_warnings(summary: &LinkerSummary) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if summary.got_executable {
+        warnings.push("GOT is mapped executable".to_string());
+    }
+    if summary.got_size >= HUGE_GOT_MIN_SIZE
+        && summary.image_size > 0
+        && summary.got_size as f64 / summary.image_size as f64 > HUGE_GOT_RATIO
+    {
+        warnings.push(format!(
+            "GOT occupies {:.0}% of the mapped image ({} entries)",
+            summary.got_size as f64 * 100.0 / summary.image_size as f64,
+            summary.got_entries
+        ));
+    }
+    if summary.plt_stubs > summary.got_entries && summary.got_entries > 0 {
+        warnings.push(format!(
+            "{} PLT relocations but only {} GOT slots",
+            summary.plt_stubs, summary.got_entries
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linker_warnings_clean() {
+        let summary = LinkerSummary {
+            pointer_size: 8,
+            got_size: 0x200,
+            got_entries: 0x40,
+            plt_stubs: 20,
+            image_size: 0x10000,
+            ..Default::default()
+        };
+        assert!(linker_warnings(&summary).is_empty());
+    }
+
+    #[test]
+    fn test_linker_warnings_huge_executable_got() {
+        let summary = LinkerSummary {
+            pointer_size: 8,
+            got_size: 0x8000,
+            got_entries: 0x1000,
+            got_executable: true,
+            image_size: 0x10000,
+            ..Default::default()
+        };
+        assert_eq!(linker_warnings(&summary).len(), 2);
+    }
+}
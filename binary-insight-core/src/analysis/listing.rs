@@ -0,0 +1,319 @@
+use super::cfg::{self, Flow};
+use super::disassembly::{self, InstructionInfo};
+use super::xrefs;
+use crate::binary::{BinaryFile, SectionInfo};
+use anyhow::Result;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// Opcode bytes shown per line; longer instructions are elided with `..`.
+const BYTES_COLUMN: usize = 10;
+/// No supported architecture has instructions longer than this.
+const MAX_INSTRUCTION_LEN: usize = 16;
+
+/// Sections holding one jump stub per imported function.
+const STUB_SECTIONS: &[&str] = &[".plt", ".plt.sec", ".plt.got", "__stubs", "__auth_stubs"];
+
+/// Names for addresses, from symbols, exports, discovered functions, PLT
+/// stubs and import thunks.
+pub struct SymbolMap<'a> {
+    file: &'a BinaryFile,
+    names: BTreeMap<u64, Cow<'a, str>>,
+    /// Import names by GOT/IAT slot address.
+    slots: HashMap<u64, &'a str>,
+}
+
+impl<'a> SymbolMap<'a> {
+    pub fn new(file: &'a BinaryFile) -> Self {
+        let info = &file.info;
+        let slots = info
+            .imports
+            .iter()
+            .filter_map(|i| Some((i.slot?, i.name.as_str())))
+            .collect();
+        let mut map = Self {
+            file,
+            names: BTreeMap::new(),
+            slots,
+        };
+        for f in &info.functions {
+            // `jmp [slot]` thunks (PE, static stubs) take the import's name.
+            let name = match map.thunk_import(f.start) {
+                Some(import) => Cow::Borrowed(import),
+                None => Cow::Borrowed(f.name.as_str()),
+            };
+            map.names.insert(f.start, name);
+        }
+        map.name_stubs();
+        // Real symbol names win over generated `sub_` names.
+        for s in info.symbols.iter().chain(&info.exports) {
+            if !s.name.is_empty() {
+                map.names.insert(s.addr, Cow::Borrowed(s.name.as_str()));
+            }
+        }
+        map
+    }
+
+    /// Names each PLT/stub entry after the import slot it jumps through.
+    fn name_stubs(&mut self) {
+        let info = &self.file.info;
+        for section in info
+            .sections
+            .iter()
+            .filter(|s| STUB_SECTIONS.contains(&s.name.as_str()))
+        {
+            let suffix = if section.name.starts_with(".plt") {
+                "@plt"
+            } else {
+                ""
+            };
+            let arch = info.arch_at(section.addr);
+            let code = self.file.section_data(section);
+            let mut entry = section.addr;
+            let mut pages = HashMap::new();
+            let mut stubs = Vec::new();
+            // Entries end at their jump; padding nops belong to the next one.
+            let _ = disassembly::linear_sweep(arch, code, section.addr, |ins| {
+                let next = ins.address + ins.size as u64;
+                if ins.address == entry && ins.mnemonic.starts_with("nop") {
+                    entry = next;
+                    return Ok(());
+                }
+                if let Some(import) =
+                    xrefs::data_target(arch, &ins, &mut pages).and_then(|slot| self.import_at(slot))
+                {
+                    stubs.push((entry, import));
+                }
+                if matches!(cfg::classify(arch, &ins), Flow::Jump(_)) {
+                    entry = next;
+                    pages.clear();
+                }
+                Ok(())
+            });
+            // Stubs are call targets, so they may already have a `sub_` name.
+            for (addr, import) in stubs {
+                self.names
+                    .insert(addr, Cow::Owned(format!("{}{}", import, suffix)));
+            }
+        }
+    }
+
+    /// The import whose slot is at `slot` (an address, or a VA in PE code).
+    fn import_at(&self, slot: u64) -> Option<&'a str> {
+        let image_base = self.file.info.image_base;
+        self.slots
+            .get(&slot)
+            .or_else(|| self.slots.get(&slot.wrapping_sub(image_base)))
+            .copied()
+    }
+
+    /// The import reached by a jump through its slot at `addr`, if the code
+    /// there is such a thunk.
+    fn thunk_import(&self, addr: u64) -> Option<&'a str> {
+        let info = &self.file.info;
+        // ELF and Mach-O keep their stubs in dedicated sections.
+        if info.format != "PE" || self.slots.is_empty() {
+            return None;
+        }
+        let offset = info.addr_to_offset(addr)? as usize;
+        let code = self.file.data.get(offset..)?;
+        let code = &code[..code.len().min(MAX_INSTRUCTION_LEN)];
+        let arch = info.arch_at(addr);
+        let ins = disassembly::disassemble(arch, code, addr, 1).ok()?.pop()?;
+        if cfg::classify(arch, &ins) != Flow::Jump(None) {
+            return None;
+        }
+        self.import_at(xrefs::data_target(arch, &ins, &mut HashMap::new())?)
+    }
+
+    /// Name defined exactly at `addr`.
+    pub fn label(&self, addr: u64) -> Option<&str> {
+        self.names.get(&addr).map(|name| name.as_ref())
+    }
+
+    /// `name` or `name+0xoff` for the closest name at or below `addr`,
+    /// within `section`.
+    pub fn describe(&self, addr: u64, section: &SectionInfo) -> Option<String> {
+        let (&base, name) = self.names.range(section.addr..=addr).next_back()?;
+        Some(match addr - base {
+            0 => name.to_string(),
+            delta => format!("{}+0x{:x}", name, delta),
+        })
+    }
+
+    /// What `ins` calls or jumps to, by name: its direct target, or the
+    /// import whose slot it goes through (`call [rip + ..]`).
+    pub fn annotate(&self, ins: &InstructionInfo) -> Option<String> {
+        let info = &self.file.info;
+        let arch = info.arch_at(ins.address);
+        match cfg::classify(arch, ins) {
+            Flow::Call(Some(t)) | Flow::Jump(Some(t)) | Flow::Branch(Some(t)) => {
+                self.describe(t, info.section_for_addr(t)?)
+            }
+            Flow::Call(None) | Flow::Jump(None) => {
+                let slot = xrefs::data_target(arch, ins, &mut HashMap::new())?;
+                self.import_at(slot).map(str::to_string)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Writes an objdump-style listing of `section`: labels at known names, then
+/// address, opcode bytes, mnemonic and operands, with direct branch targets
+/// annotated by name. Returns the number of instructions written.
+pub fn write_listing(
+    out: &mut dyn Write,
+    file: &BinaryFile,
+    section: &SectionInfo,
+) -> Result<usize> {
+    let code = file.section_data(section);
+    writeln!(
+        out,
+        "Disassembly of section {} (0x{:x}-0x{:x}):",
+        section.name,
+        section.addr,
+        section.addr + code.len() as u64
+    )?;
+    write_instructions(out, file, code, section.addr, usize::MAX)
+}
+
+/// Lists `code`, mapped at `addr`, in the [`write_listing`] format, stopping
+/// after `max_count` instructions. Returns the number written.
+pub fn write_instructions(
+    out: &mut dyn Write,
+    file: &BinaryFile,
+    code: &[u8],
+    addr: u64,
+    max_count: usize,
+) -> Result<usize> {
+    let info = &file.info;
+    let symbols = SymbolMap::new(file);
+    let code = &code[..code
+        .len()
+        .min(max_count.saturating_mul(MAX_INSTRUCTION_LEN))];
+
+    // ARM binaries switch between ARM and Thumb; sweep each region in its mode.
+    let end = addr + code.len() as u64;
+    let mut regions: Vec<u64> = vec![addr];
+    regions.extend(
+        info.arm_modes
+            .range(addr..end)
+            .map(|(&a, _)| a)
+            .filter(|&a| a > addr),
+    );
+    regions.push(end);
+
+    let mut count = 0;
+    for region in regions.windows(2) {
+        let region_code = &code[(region[0] - addr) as usize..(region[1] - addr) as usize];
+        let arch = info.arch_at(region[0]);
+        disassembly::linear_sweep(arch, region_code, region[0], |ins| {
+            if count == max_count {
+                return Ok(());
+            }
+            let start = (ins.address - addr) as usize;
+            let bytes = &code[start..start + ins.size];
+            let mut hex: Vec<String> = bytes
+                .iter()
+                .take(BYTES_COLUMN)
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            if bytes.len() > BYTES_COLUMN {
+                hex.push("..".to_string());
+            }
+            let annotation = symbols
+                .annotate(&ins)
+                .map(|name| format!(" <{}>", name))
+                .unwrap_or_default();
+            if let Some(label) = symbols.label(ins.address) {
+                writeln!(out, "\n{:016x} <{}>:", ins.address, label)?;
+            }
+            writeln!(
+                out,
+                "  {:>10x}:  {:<32} {:<8} {}{}",
+                ins.address,
+                hex.join(" "),
+                ins.mnemonic,
+                ins.op_str,
+                annotation
+            )?;
+            count += 1;
+            Ok(())
+        })?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing_labels_and_targets() {
+        // 0x1000: call 0x1006; ret; 0x1006: ret
+        let code = vec![0xe8, 0x01, 0x00, 0x00, 0x00, 0xc3, 0xc3];
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        file.discover_functions();
+        let section = &file.info.sections[0];
+        let mut out = Vec::new();
+        let count = write_listing(&mut out, &file, section).expect("listing failed");
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(count, 3);
+        assert!(text.contains("0000000000001006 <sub_1006>:"));
+        assert!(text.contains("e8 01 00 00 00"));
+        assert!(text.contains("call     0x1006 <sub_1006>"));
+
+        let mut out = Vec::new();
+        let count = write_instructions(&mut out, &file, &file.data[5..], 0x1005, 1).unwrap();
+        assert_eq!(count, 1);
+        assert!(String::from_utf8(out).unwrap().contains("1005:  c3"));
+    }
+
+    #[test]
+    fn test_targets_resolve_to_imports() {
+        // 0x1000: call 0x1010; call [rip + 0xff5]; ret; 4 nops
+        // 0x1010 (.plt): jmp [rip + 0xfea]; both slots are 0x2000
+        let mut code = vec![0xe8, 0x0b, 0x00, 0x00, 0x00];
+        code.extend([
+            0xff, 0x15, 0xf5, 0x0f, 0x00, 0x00, 0xc3, 0x90, 0x90, 0x90, 0x90,
+        ]);
+        code.extend([0xff, 0x25, 0xea, 0x0f, 0x00, 0x00]);
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        file.info.sections.push(SectionInfo {
+            name: ".plt".to_string(),
+            addr: 0x1010,
+            size: 6,
+            offset: 0x10,
+            hashes: None,
+            zero_fill: false,
+        });
+        file.info.imports.push(crate::binary::ImportInfo {
+            name: "puts".to_string(),
+            library: None,
+            slot: Some(0x2000),
+        });
+        file.discover_functions();
+        let mut out = Vec::new();
+        write_listing(&mut out, &file, &file.info.sections[0]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("call     0x1010 <puts@plt>"));
+        assert!(text.contains("call     qword ptr [rip + 0xff5] <puts>"));
+        assert!(text.contains("0000000000001010 <puts@plt>:"));
+    }
+
+    #[test]
+    fn test_listing_switches_to_thumb() {
+        // 0x1000: bx lr (ARM); 0x1004: bx lr (Thumb)
+        let code = vec![0x1e, 0xff, 0x2f, 0xe1, 0x70, 0x47];
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "arm", 0x1000);
+        file.info.arm_modes.insert(0x1004, true);
+        let mut out = Vec::new();
+        let count = write_listing(&mut out, &file, &file.info.sections[0]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(count, 2);
+        assert!(text.contains("1000:  1e ff 2f e1"));
+        assert!(text.contains("1004:  70 47"));
+    }
+}
@@ -1,149 +1,84 @@
-use goblin::elf::Elf;
-use goblin::mach::Mach;
-use goblin::pe::PE;
-
+pub mod anomalies;
+pub mod app_runtime;
+pub mod attack;
+pub mod bazaar;
+pub mod callgraph;
+pub mod capabilities;
+pub mod cfg;
+pub mod charset;
+pub mod crypto;
+pub mod debuginfo;
+pub mod deobfuscate;
+pub mod deps;
 pub mod disassembly;
+pub mod dotnet;
 pub mod entropy;
+pub mod entry;
+pub mod evasion;
+pub mod expr;
+pub mod findings;
+pub mod flirt;
+pub mod functions;
+pub mod golang;
+pub mod grep;
+pub mod hashdb;
 pub mod hashes;
+pub mod icon;
+pub mod insecure;
+pub mod installer;
+pub mod kernel;
+pub mod language;
+pub mod linker;
+pub mod listing;
+pub mod pattern;
+pub mod pe_headers;
+pub mod policy;
+pub mod pyinstaller;
+pub mod rust;
+pub mod sbom;
+pub mod score;
+pub mod security;
+pub mod string_index;
+pub mod string_xrefs;
+pub mod syscalls;
+pub mod template;
+pub mod vulns;
+pub mod x509;
+pub mod xrefs;
+pub mod yaml;
 pub mod yara;
 
-#[derive(Debug, Default, Clone)]
-pub struct SecurityFeatures {
-    pub pie: bool,
-    pub nx: bool,
-    pub relro: bool, // Simplified: Full/Partial/None can be enum, sticking to bool = "has relro" for now
-    pub canary: bool,
-}
-
-pub fn analyze_security_elf(elf: &Elf) -> SecurityFeatures {
-    let mut features = SecurityFeatures::default();
-
-    // PIE: ET_DYN (3) usually implies PIE for executables (though shared libs are also ET_DYN)
-    // Actually, distinct PIE vs Shared Lib is harder without context, but strictly, ET_DYN means it supports ASLR.
-    if elf.header.e_type == goblin::elf::header::ET_DYN {
-        features.pie = true;
-    }
-
-    // NX: PT_GNU_STACK header with !PF_X
-    if let Some(phdr) = elf
-        .program_headers
-        .iter()
-        .find(|ph| ph.p_type == goblin::elf::program_header::PT_GNU_STACK)
-    {
-        if phdr.p_flags & goblin::elf::program_header::PF_X == 0 {
-            features.nx = true;
-        }
-    } else {
-        // Default stack executable? Typically yes if missing on older linux, but modern often default NX.
-        // Let's assume false (executable stack) if not explicitly disabled, to be safe/conservative in reporting "NX".
-    }
-
-    // RELRO: PT_GNU_RELRO
-    if elf
-        .program_headers
-        .iter()
-        .any(|ph| ph.p_type == goblin::elf::program_header::PT_GNU_RELRO)
-    {
-        features.relro = true;
-    }
-
-    // Canary: Check for symbol like __stack_chk_fail
-    // This is a naive check.
-    if elf.syms.iter().any(|sym| {
-        if let Some(name) = elf.strtab.get_at(sym.st_name) {
-            name.contains("__stack_chk_fail")
-        } else {
-            false
-        }
-    }) || elf.dynsyms.iter().any(|sym| {
-        if let Some(name) = elf.dynstrtab.get_at(sym.st_name) {
-            name.contains("__stack_chk_fail")
-        } else {
-            false
-        }
-    }) {
-        features.canary = true;
-    }
-
-    features
-}
-
-pub fn analyze_security_pe(pe: &PE) -> SecurityFeatures {
-    let mut features = SecurityFeatures::default();
+pub use security::{
+    analyze_security_elf, analyze_security_mach, analyze_security_pe, MachOSecurity, PeMitigations,
+    Relro, RwxRegion, SafeSeh, SecurityFeatures,
+};
+pub use string_index::StringIndex;
 
-    // DLL Characteristics
-    if let Some(opt_header) = &pe.header.optional_header {
-        let dll_char = opt_header.windows_fields.dll_characteristics;
-
-        // ASLR / PIE
-        if dll_char & 0x0040 != 0 {
-            // IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE
-            features.pie = true;
-        }
-
-        // NX / DEP
-        if dll_char & 0x0100 != 0 {
-            // IMAGE_DLLCHARACTERISTICS_NX_COMPAT
-            features.nx = true;
-        }
-    }
-
-    // RELRO / Canary concepts don't map 1:1 same way.
-    // PE has /GS for stack cookies. We'd check for imports like __security_check_cookie.
-    // For now leaving false.
-
-    // Check imports for stack cookie check
-    for import in &pe.imports {
-        if import.name.eq_ignore_ascii_case("VCRUNTIME140.dll")
-            || import.name.eq_ignore_ascii_case("KERNEL32.dll")
-        {
-            // Simplified heuristic
-            /* Real check would iterate import.imports expecting __security_check_cookie or similar */
-        }
-    }
-
-    features
-}
-
-pub fn analyze_security_mach(mach: &Mach) -> SecurityFeatures {
-    match mach {
-        Mach::Binary(macho) => {
-            let flags = macho.header.flags;
-            SecurityFeatures {
-                pie: (flags & 0x200000) != 0, // MH_PIE
-                nx: (flags & 0x20000) == 0, // MH_ALLOW_STACK_EXECUTION (0x20000). If NOT set, stack is non-exec (NX is true).
-                ..Default::default()
-            }
-        }
-        _ => SecurityFeatures::default(),
-    }
+pub fn extract_strings(data: &[u8]) -> Vec<String> {
+    extract_strings_indexed(data).0
 }
 
-pub fn extract_strings(data: &[u8]) -> Vec<String> {
+/// Extracts strings along with the file offset of each one.
+pub fn extract_strings_indexed(data: &[u8]) -> (Vec<String>, StringIndex) {
     let min_len = 4;
     let mut strings = Vec::new();
-    let mut current_string = Vec::new();
+    let mut index = StringIndex::default();
+    let mut start = 0;
 
-    for &b in data {
+    // A trailing sentinel flushes the last run.
+    for (i, b) in data.iter().copied().chain([0]).enumerate() {
         if b.is_ascii_graphic() || b == b' ' {
-            current_string.push(b);
-        } else {
-            if current_string.len() >= min_len {
-                if let Ok(s) = String::from_utf8(current_string.clone()) {
-                    strings.push(s);
-                }
-            }
-            current_string.clear();
+            continue;
         }
-    }
-    // catch last one
-    if current_string.len() >= min_len {
-        if let Ok(s) = String::from_utf8(current_string) {
-            strings.push(s);
+        if i - start >= min_len {
+            // Printable ASCII is always valid UTF-8.
+            strings.push(String::from_utf8_lossy(&data[start..i]).into_owned());
+            index.push(start as u64, (i - start) as u32);
         }
+        start = i + 1;
     }
 
-    strings
+    (strings, index)
 }
 
 #[cfg(test)]
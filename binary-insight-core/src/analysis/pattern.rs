@@ -0,0 +1,119 @@
+use anyhow::{anyhow, bail, Result};
+use std::fmt;
+
+/// An IDA-style byte pattern such as `E8 ?? ?? ?? ?? 48 8B`; `None` is a
+/// wildcard that matches any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(pub Vec<Option<u8>>);
+
+impl Pattern {
+    /// Parses whitespace-separated hex bytes and `??` (or `?`) wildcards.
+    /// A token may hold several bytes, as in `4883EC ?? E8`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut bytes = Vec::new();
+        for token in text.split_whitespace() {
+            if token == "?" || token == "??" {
+                bytes.push(None);
+                continue;
+            }
+            if token.len() % 2 != 0 {
+                bail!("invalid byte `{}`", token);
+            }
+            for i in (0..token.len()).step_by(2) {
+                let byte = token
+                    .get(i..i + 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| anyhow!("invalid byte `{}`", token))?;
+                bytes.push(Some(byte));
+            }
+        }
+        if bytes.iter().all(Option::is_none) {
+            bail!("byte pattern needs at least one fixed byte");
+        }
+        Ok(Pattern(bytes))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether `data` starts with the pattern.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.len()
+            && self
+                .0
+                .iter()
+                .zip(data)
+                .all(|(p, b)| p.is_none_or(|p| p == *b))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match byte {
+                Some(b) => write!(f, "{:02X}", b)?,
+                None => write!(f, "??")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Offsets of every match of `pattern` in `data`, overlapping ones included.
+pub fn find(data: &[u8], pattern: &Pattern) -> Vec<usize> {
+    if pattern.is_empty() || data.len() < pattern.len() {
+        return Vec::new();
+    }
+    // Only try windows whose first fixed byte lines up.
+    let (anchor, first) = pattern
+        .0
+        .iter()
+        .enumerate()
+        .find_map(|(i, b)| b.map(|b| (i, b)))
+        .unwrap_or((0, 0));
+    let last_start = data.len() - pattern.len();
+    data[anchor..=last_start + anchor]
+        .iter()
+        .enumerate()
+        .filter(|&(start, &b)| {
+            (pattern.0[anchor].is_none() || b == first) && pattern.matches(&data[start..])
+        })
+        .map(|(start, _)| start)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pattern = Pattern::parse("E8 ? ?? 488B").unwrap();
+        assert_eq!(
+            pattern.0,
+            vec![Some(0xe8), None, None, Some(0x48), Some(0x8b)]
+        );
+        assert_eq!(pattern.to_string(), "E8 ?? ?? 48 8B");
+        assert!(Pattern::parse("?? ??").is_err());
+        assert!(Pattern::parse("E8 4").is_err());
+        assert!(Pattern::parse("ZZ").is_err());
+    }
+
+    #[test]
+    fn test_find() {
+        let data = [0x90, 0xe8, 0x01, 0x02, 0x48, 0xe8, 0xe8, 0x00, 0x48];
+        let pattern = Pattern::parse("E8 ?? ?? 48").unwrap();
+        assert_eq!(find(&data, &pattern), vec![1, 5]);
+        let leading = Pattern::parse("?? 48").unwrap();
+        assert_eq!(find(&data, &leading), vec![3, 7]);
+        assert!(find(&data[..3], &pattern).is_empty());
+    }
+}
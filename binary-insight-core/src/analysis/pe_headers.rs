@@ -0,0 +1,310 @@
+use goblin::pe::data_directories::DataDirectory;
+use goblin::pe::options::ParseOptions;
+use goblin::pe::utils::find_offset;
+use goblin::pe::PE;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Offset of `CheckSum` from the start of the optional header, the same for
+/// PE32 and PE32+.
+const CHECKSUM_OFFSET: usize = 64;
+/// Size of an `IMAGE_DEBUG_DIRECTORY` entry.
+pub(crate) const DEBUG_ENTRY_SIZE: usize = 28;
+const IMAGE_DEBUG_TYPE_REPRO: u32 = 16;
+/// 1993-01-01, shortly before the first PE files (Windows NT 3.1).
+const FIRST_PE_TIMESTAMP: u32 = 725_846_400;
+/// Linkers stamp the header and debug entries together; allow a day of skew.
+const TIMESTAMP_SKEW: u32 = 86_400;
+
+/// PE header fields that loaders mostly ignore but tools and tampering
+/// leave inconsistent.
+#[derive(Debug, Clone, Default)]
+pub struct PeHeaderCheck {
+    /// `CheckSum` from the optional header; 0 means not set.
+    pub stored_checksum: u32,
+    pub computed_checksum: u32,
+    /// Inconsistencies between the headers and the section table.
+    pub issues: Vec<String>,
+    pub timestamps: PeTimestamps,
+}
+
+/// Build timestamps recorded in the headers, as seconds since 1970.
+#[derive(Debug, Clone, Default)]
+pub struct PeTimestamps {
+    /// `TimeDateStamp` in the COFF file header.
+    pub coff: u32,
+    /// From the export directory, when set.
+    pub export: Option<u32>,
+    /// Non-zero stamps from the debug directory entries.
+    pub debug: Vec<u32>,
+    /// Linked with `/Brepro`: the stamps are content hashes, not dates.
+    pub reproducible: bool,
+    /// Zeroed, future-dated or mutually inconsistent stamps.
+    pub issues: Vec<String>,
+}
+
+impl PeHeaderCheck {
+    /// Set and wrong. Only drivers and some system DLLs are required to have
+    /// a valid checksum, so an unset one is not a mismatch.
+    pub fn checksum_mismatch(&self) -> bool {
+        self.stored_checksum != 0 && self.stored_checksum != self.computed_checksum
+    }
+}
+
+pub fn check_pe_headers(pe: &PE, data: &[u8]) -> Option<PeHeaderCheck> {
+    let opt = pe.header.optional_header.as_ref()?;
+    let fields = &opt.windows_fields;
+    let checksum_at = pe.header.dos_header.pe_pointer as usize + 4 + 20 + CHECKSUM_OFFSET;
+    let mut check = PeHeaderCheck {
+        stored_checksum: fields.check_sum,
+        computed_checksum: pe_checksum(data, checksum_at),
+        issues: Vec::new(),
+        timestamps: read_timestamps(pe, data),
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    check_timestamps(&mut check.timestamps, now);
+    let issues = &mut check.issues;
+
+    let (section_align, file_align) = (fields.section_alignment, fields.file_alignment);
+    if !file_align.is_power_of_two() || !section_align.is_power_of_two() {
+        issues.push(format!(
+            "alignments are not powers of two (section 0x{:x}, file 0x{:x})",
+            section_align, file_align
+        ));
+        return Some(check);
+    }
+    if section_align < file_align {
+        issues.push(format!(
+            "SectionAlignment 0x{:x} is below FileAlignment 0x{:x}",
+            section_align, file_align
+        ));
+    }
+    if fields.size_of_headers % file_align != 0 {
+        issues.push(format!(
+            "SizeOfHeaders 0x{:x} is not a multiple of FileAlignment",
+            fields.size_of_headers
+        ));
+    }
+
+    let mut sections: Vec<_> = pe.sections.iter().collect();
+    sections.sort_by_key(|s| s.virtual_address);
+    for section in &sections {
+        let name = section.name().unwrap_or("<bad>");
+        if section.virtual_address % section_align != 0 {
+            issues.push(format!(
+                "section {} address 0x{:x} is not section-aligned",
+                name, section.virtual_address
+            ));
+        }
+        if section.size_of_raw_data != 0 && section.pointer_to_raw_data % file_align != 0 {
+            issues.push(format!(
+                "section {} raw data offset 0x{:x} is not file-aligned",
+                name, section.pointer_to_raw_data
+            ));
+        }
+    }
+    for pair in sections.windows(2) {
+        let end = pair[0].virtual_address as u64 + virtual_size(pair[0]);
+        if end > pair[1].virtual_address as u64 {
+            issues.push(format!(
+                "sections {} and {} overlap",
+                pair[0].name().unwrap_or("<bad>"),
+                pair[1].name().unwrap_or("<bad>")
+            ));
+        }
+    }
+    if let Some(last) = sections.last() {
+        let end = last.virtual_address as u64 + virtual_size(last);
+        let expected = end.div_ceil(section_align as u64) * section_align as u64;
+        if fields.size_of_image as u64 != expected {
+            issues.push(format!(
+                "SizeOfImage is 0x{:x}, sections end at 0x{:x}",
+                fields.size_of_image, expected
+            ));
+        }
+    }
+    Some(check)
+}
+
+/// The bytes of a data directory, if it lies within the file.
+pub(crate) fn directory_data<'a>(
+    pe: &PE,
+    data: &'a [u8],
+    dir: Option<&DataDirectory>,
+) -> Option<&'a [u8]> {
+    let dir = dir?;
+    let opt = pe.header.optional_header.as_ref()?;
+    let offset = find_offset(
+        dir.virtual_address as usize,
+        &pe.sections,
+        opt.windows_fields.file_alignment,
+        &ParseOptions::default(),
+    )?;
+    data.get(offset..)?.get(..dir.size as usize)
+}
+
+fn read_timestamps(pe: &PE, data: &[u8]) -> PeTimestamps {
+    let mut timestamps = PeTimestamps {
+        coff: pe.header.coff_header.time_date_stamp,
+        export: pe
+            .export_data
+            .as_ref()
+            .map(|e| e.export_directory_table.time_date_stamp)
+            .filter(|&t| t != 0 && t != u32::MAX),
+        ..Default::default()
+    };
+    let debug = pe
+        .header
+        .optional_header
+        .as_ref()
+        .and_then(|opt| directory_data(pe, data, opt.data_directories.get_debug_table()));
+    for entry in debug.unwrap_or_default().chunks_exact(DEBUG_ENTRY_SIZE) {
+        let field = |at: usize| u32::from_le_bytes(entry[at..at + 4].try_into().unwrap());
+        if field(12) == IMAGE_DEBUG_TYPE_REPRO {
+            timestamps.reproducible = true;
+        }
+        if field(4) != 0 && !timestamps.debug.contains(&field(4)) {
+            timestamps.debug.push(field(4));
+        }
+    }
+    timestamps
+}
+
+fn check_timestamps(timestamps: &mut PeTimestamps, now: u64) {
+    let coff = timestamps.coff;
+    let mut issues = Vec::new();
+    if coff == 0 {
+        issues.push("COFF timestamp is zeroed".to_string());
+    } else if !timestamps.reproducible {
+        if coff as u64 > now {
+            issues.push(format!(
+                "COFF timestamp {} is in the future",
+                format_timestamp(coff)
+            ));
+        } else if coff < FIRST_PE_TIMESTAMP {
+            issues.push(format!(
+                "COFF timestamp {} predates the PE format",
+                format_timestamp(coff)
+            ));
+        }
+        let others = timestamps
+            .export
+            .iter()
+            .map(|&t| ("export", t))
+            .chain(timestamps.debug.iter().map(|&t| ("debug", t)));
+        for (source, stamp) in others {
+            if stamp.abs_diff(coff) > TIMESTAMP_SKEW {
+                issues.push(format!(
+                    "{} timestamp {} disagrees with COFF timestamp {}",
+                    source,
+                    format_timestamp(stamp),
+                    format_timestamp(coff)
+                ));
+            }
+        }
+    }
+    timestamps.issues = issues;
+}
+
+/// `2021-03-04 05:06:07 UTC`.
+pub fn format_timestamp(timestamp: u32) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+    // Howard Hinnant's days-to-civil conversion.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Loaders map `SizeOfRawData` when `VirtualSize` is zero.
+fn virtual_size(section: &goblin::pe::section_table::SectionTable) -> u64 {
+    match section.virtual_size {
+        0 => section.size_of_raw_data as u64,
+        size => size as u64,
+    }
+}
+
+/// The `imagehlp` checksum: a folded 16-bit sum of the file, skipping the
+/// checksum field itself, plus the file length.
+fn pe_checksum(data: &[u8], checksum_at: usize) -> u32 {
+    let mut sum: u64 = 0;
+    for (i, chunk) in data.chunks(2).enumerate() {
+        if (checksum_at..checksum_at + 4).contains(&(i * 2)) {
+            continue;
+        }
+        let word = match chunk {
+            [lo, hi] => u16::from_le_bytes([*lo, *hi]),
+            [lo] => *lo as u16,
+            _ => 0,
+        };
+        sum += word as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum = (sum & 0xffff) + (sum >> 16);
+    (sum as u32).wrapping_add(data.len() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pe_checksum() {
+        // Sum of words 0x0201 + 0x0003 (the 0xff bytes are the skipped
+        // field), plus the length.
+        let data = [0x01, 0x02, 0xff, 0xff, 0xff, 0xff, 0x03];
+        assert_eq!(pe_checksum(&data, 2), 0x0204 + 7);
+        // Carries fold back into the low 16 bits.
+        let data = [0xff, 0xff, 0x02, 0x00];
+        assert_eq!(pe_checksum(&data, 8), 0x0002 + 4);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_614_834_367), "2021-03-04 05:06:07 UTC");
+    }
+
+    #[test]
+    fn test_timestamp_checks() {
+        let now = 1_700_000_000;
+        let mut stamps = PeTimestamps {
+            coff: 1_600_000_000,
+            debug: vec![1_600_000_000],
+            ..Default::default()
+        };
+        check_timestamps(&mut stamps, now);
+        assert!(stamps.issues.is_empty());
+
+        stamps.export = Some(1_500_000_000);
+        stamps.coff = 1_800_000_000;
+        check_timestamps(&mut stamps, now);
+        assert_eq!(stamps.issues.len(), 3);
+        assert!(stamps.issues[0].contains("future"));
+
+        // Reproducible builds store hashes; only a zeroed stamp stands out.
+        stamps.reproducible = true;
+        check_timestamps(&mut stamps, now);
+        assert!(stamps.issues.is_empty());
+        stamps.coff = 0;
+        check_timestamps(&mut stamps, now);
+        assert_eq!(stamps.issues, vec!["COFF timestamp is zeroed"]);
+    }
+}
@@ -0,0 +1,289 @@
+use super::entropy::calculate_entropy;
+use super::findings::{self, Finding, Severity};
+use super::Relro;
+use crate::binary::{BinaryFile, BinaryInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Release rules a binary is checked against, read from a TOML policy file:
+///
+/// ```toml
+/// [require]
+/// nx = true
+/// pie = true
+/// relro = "full"
+///
+/// [deny]
+/// rwx_sections = true
+/// findings = ["yara.*", "anti_debug.*"]
+///
+/// [limits]
+/// max_entropy_section = 7.5
+///
+/// [severity]
+/// weak_crypto = "medium"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    pub require: Requirements,
+    pub deny: Denials,
+    pub limits: Limits,
+    /// Severity overrides by [`Finding::kind`], e.g. `weak_crypto` or `rwx`.
+    pub severity: BTreeMap<String, Severity>,
+}
+
+/// Mitigations the binary must be built with. Only checked for linked
+/// executables and libraries.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Requirements {
+    pub nx: bool,
+    pub pie: bool,
+    pub canary: bool,
+    /// Minimum RELRO level for ELF files, `"partial"` or `"full"`.
+    pub relro: Option<RelroLevel>,
+    /// At least one FORTIFY_SOURCE `__*_chk` call.
+    pub fortify: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelroLevel {
+    Partial,
+    Full,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Denials {
+    /// Writable and executable sections or segments.
+    pub rwx_sections: bool,
+    /// Finding ids, with `*` matching any run of characters.
+    pub findings: Vec<String>,
+    /// Any finding at or above this severity.
+    pub severity: Option<Severity>,
+    /// Imported or linked function names.
+    pub imports: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Highest Shannon entropy (bits per byte) any section may have.
+    pub max_entropy_section: Option<f64>,
+}
+
+/// A policy rule the binary breaks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// The rule as written in the policy, e.g. `require.nx`.
+    pub rule: String,
+    pub message: String,
+}
+
+impl Policy {
+    /// [`findings::collect`] with the `[severity]` overrides applied, most
+    /// severe first.
+    pub fn findings(&self, info: &BinaryInfo) -> Vec<Finding> {
+        let mut findings = findings::collect(info);
+        for finding in &mut findings {
+            if let Some(&severity) = self.severity.get(finding.kind()) {
+                finding.severity = severity;
+            }
+        }
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    }
+
+    /// Checks `binary` against the policy; `findings` are the ones to judge
+    /// `deny.findings` and `deny.severity` by, see [`Policy::findings`].
+    pub fn evaluate(&self, binary: &BinaryFile, findings: &[Finding]) -> Vec<Violation> {
+        let info = &binary.info;
+        let mut violations = Vec::new();
+        let mut violate = |rule: &str, message: String| {
+            violations.push(Violation {
+                rule: rule.to_string(),
+                message,
+            })
+        };
+
+        let require = &self.require;
+        let security = &info.security;
+        if security.loadable {
+            if require.nx && !security.nx {
+                violate("require.nx", "NX is not enabled".to_string());
+            }
+            if require.pie && !security.pie {
+                violate("require.pie", "not position-independent".to_string());
+            }
+            if require.canary && !security.canary {
+                violate("require.canary", "no stack canary".to_string());
+            }
+            if require.fortify && security.fortified.is_empty() {
+                violate("require.fortify", "no fortified calls".to_string());
+            }
+            let relro_ok = match require.relro {
+                Some(RelroLevel::Full) => security.relro == Relro::Full,
+                Some(RelroLevel::Partial) => security.relro != Relro::None,
+                None => true,
+            };
+            if info.format == "ELF" && !relro_ok {
+                violate("require.relro", security.relro.name().to_string());
+            }
+        }
+
+        let deny = &self.deny;
+        if deny.rwx_sections {
+            for region in &security.rwx {
+                violate(
+                    "deny.rwx_sections",
+                    format!(
+                        "{} @ 0x{:x} is writable and executable",
+                        region.name, region.addr
+                    ),
+                );
+            }
+        }
+        for name in &deny.imports {
+            let imported = info.imports.iter().any(|i| &i.name == name)
+                || info.symbols.iter().any(|s| &s.name == name);
+            if imported {
+                violate("deny.imports", format!("uses {}", name));
+            }
+        }
+        for finding in findings {
+            if let Some(pattern) = deny.findings.iter().find(|p| glob_match(p, &finding.id)) {
+                violate(
+                    "deny.findings",
+                    format!("{} ({}): {}", finding.id, pattern, finding.evidence),
+                );
+            } else if deny.severity.is_some_and(|s| finding.severity >= s) {
+                violate(
+                    "deny.severity",
+                    format!("{} is {}", finding.id, finding.severity.name()),
+                );
+            }
+        }
+
+        if let Some(max) = self.limits.max_entropy_section {
+            for section in &info.sections {
+                let entropy = calculate_entropy(binary.section_data(section));
+                if entropy > max {
+                    violate(
+                        "limits.max_entropy_section",
+                        format!("section {} has entropy {:.2}", section.name, entropy),
+                    );
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` stands for any run of
+/// characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::BinaryInfo;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("yara.*", "yara.upx"));
+        assert!(glob_match("*.md5", "weak_crypto.md5"));
+        assert!(glob_match("anti_*.ptrace", "anti_debug.ptrace"));
+        assert!(glob_match("mitigation.nx", "mitigation.nx"));
+        assert!(!glob_match("mitigation.nx", "mitigation.nx2"));
+        assert!(!glob_match("yara.*", "anomaly"));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut binary = BinaryFile {
+            name: "test".to_string(),
+            data: Vec::new().into(),
+            info: BinaryInfo {
+                format: "ELF".to_string(),
+                ..Default::default()
+            },
+            passes: Default::default(),
+        };
+        binary.info.security.loadable = true;
+        binary.info.security.nx = true;
+        binary.info.security.relro = Relro::Partial;
+        let policy = Policy {
+            require: Requirements {
+                nx: true,
+                pie: true,
+                relro: Some(RelroLevel::Full),
+                ..Default::default()
+            },
+            deny: Denials {
+                findings: vec!["mitigation.canary".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let rules: Vec<String> = policy
+            .evaluate(&binary, &policy.findings(&binary.info))
+            .into_iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(rules, ["require.pie", "require.relro", "deny.findings"]);
+    }
+
+    #[test]
+    fn test_severity_overrides() {
+        let mut binary = BinaryFile {
+            name: "test".to_string(),
+            data: Vec::new().into(),
+            info: BinaryInfo {
+                format: "ELF".to_string(),
+                ..Default::default()
+            },
+            passes: Default::default(),
+        };
+        let security = &mut binary.info.security;
+        security.loadable = true;
+        security.nx = true;
+        security.pie = true;
+        security.relro = Relro::Full;
+        let policy = Policy {
+            severity: BTreeMap::from([("mitigation".to_string(), Severity::High)]),
+            deny: Denials {
+                severity: Some(Severity::High),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let findings = policy.findings(&binary.info);
+        assert_eq!(findings[0].id, "mitigation.canary");
+        assert_eq!(findings[0].severity, Severity::High);
+        assert!(policy
+            .evaluate(&binary, &findings::collect(&binary.info))
+            .is_empty());
+        let violations = policy.evaluate(&binary, &findings);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "deny.severity");
+    }
+}
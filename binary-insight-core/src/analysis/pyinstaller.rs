@@ -0,0 +1,271 @@
+use crate::binary::compression::DEFAULT_DECOMPRESSION_LIMIT;
+use anyhow::{bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+/// CArchive cookie magic: `MEI\014\013\012\013\016`.
+const COOKIE_MAGIC: &[u8] = b"MEI\x0c\x0b\x0a\x0b\x0e";
+/// PyInstaller >= 2.1 cookie: magic, len, toc offset, toc len, pyvers, pylibname[64].
+const COOKIE_SIZE: usize = 88;
+/// PyInstaller 2.0 cookie without the python library name.
+const COOKIE_SIZE_V20: usize = 24;
+/// Fixed part of a TOC entry before the name.
+const TOC_ENTRY_HEADER: usize = 18;
+
+#[derive(Debug, Clone)]
+pub struct PyInstallerEntry {
+    pub name: String,
+    /// Type code: `s` script, `m`/`M` module/package, `z` PYZ, `b` binary, `x` data, ...
+    pub kind: char,
+    /// Offset of the entry data from the start of the file.
+    pub offset: u64,
+    pub compressed_size: u64,
+    pub size: u64,
+    pub compressed: bool,
+}
+
+impl PyInstallerEntry {
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            's' => "script",
+            'm' => "module",
+            'M' => "package",
+            'z' | 'Z' => "PYZ archive",
+            'b' => "binary",
+            'x' => "data",
+            'o' => "runtime option",
+            'd' => "dependency",
+            'n' => "symlink",
+            'l' => "zipfile",
+            _ => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PyInstallerArchive {
+    /// Offset of the CArchive within the file (the overlay start).
+    pub offset: u64,
+    /// Python version, e.g. `3.11`.
+    pub python_version: String,
+    pub python_library: Option<String>,
+    pub entries: Vec<PyInstallerEntry>,
+}
+
+/// Locates and parses a PyInstaller CArchive appended to an executable.
+pub fn find_archive(data: &[u8]) -> Option<PyInstallerArchive> {
+    // The cookie sits at the very end of the archive, which may be followed by a signature.
+    let cookie = data
+        .windows(COOKIE_MAGIC.len())
+        .rposition(|w| w == COOKIE_MAGIC)?;
+    parse_cookie(data, cookie, COOKIE_SIZE).or_else(|| parse_cookie(data, cookie, COOKIE_SIZE_V20))
+}
+
+fn parse_cookie(data: &[u8], cookie: usize, cookie_size: usize) -> Option<PyInstallerArchive> {
+    let header = data.get(cookie..cookie + cookie_size)?;
+    let be = |off: usize| u32::from_be_bytes(header[off..off + 4].try_into().unwrap()) as usize;
+    let length = be(8);
+    let toc_offset = be(12);
+    let toc_length = be(16);
+    let pyvers = be(20);
+
+    let start = (cookie + cookie_size).checked_sub(length)?;
+    let toc = data.get(start + toc_offset..start + toc_offset + toc_length)?;
+    let entries = parse_toc(toc, start)?;
+
+    let python_library = (cookie_size == COOKIE_SIZE)
+        .then(|| {
+            let raw = &header[24..88];
+            let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+            String::from_utf8_lossy(&raw[..end]).to_string()
+        })
+        .filter(|s| !s.is_empty());
+
+    // pyvers is major * 100 + minor for 3.10+, major * 10 + minor before.
+    let python_version = if pyvers >= 100 {
+        format!("{}.{}", pyvers / 100, pyvers % 100)
+    } else {
+        format!("{}.{}", pyvers / 10, pyvers % 10)
+    };
+
+    Some(PyInstallerArchive {
+        offset: start as u64,
+        python_version,
+        python_library,
+        entries,
+    })
+}
+
+fn parse_toc(toc: &[u8], base: usize) -> Option<Vec<PyInstallerEntry>> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while pos + TOC_ENTRY_HEADER <= toc.len() {
+        let be = |off: usize| u32::from_be_bytes(toc[pos + off..pos + off + 4].try_into().unwrap());
+        let entry_size = be(0) as usize;
+        if entry_size < TOC_ENTRY_HEADER || pos + entry_size > toc.len() {
+            return None;
+        }
+        let raw_name = &toc[pos + TOC_ENTRY_HEADER..pos + entry_size];
+        let end = raw_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(raw_name.len());
+        entries.push(PyInstallerEntry {
+            name: String::from_utf8_lossy(&raw_name[..end]).to_string(),
+            kind: toc[pos + 17] as char,
+            offset: (base + be(4) as usize) as u64,
+            compressed_size: be(8) as u64,
+            size: be(12) as u64,
+            compressed: toc[pos + 16] != 0,
+        });
+        pos += entry_size;
+    }
+    Some(entries)
+}
+
+/// Returns the (decompressed) contents of an archive entry.
+pub fn read_entry(data: &[u8], entry: &PyInstallerEntry) -> Result<Vec<u8>> {
+    let raw = raw_entry(data, entry)?;
+    if !entry.compressed {
+        return Ok(raw.to_vec());
+    }
+    if entry.size > DEFAULT_DECOMPRESSION_LIMIT {
+        bail!(
+            "Entry {} is {} bytes, exceeding the limit of {} bytes",
+            entry.name,
+            entry.size,
+            DEFAULT_DECOMPRESSION_LIMIT
+        );
+    }
+    // One byte past the recorded size is enough to tell it is wrong.
+    let mut out = Vec::new();
+    ZlibDecoder::new(raw)
+        .take(entry.size + 1)
+        .read_to_end(&mut out)
+        .with_context(|| format!("Failed to inflate entry {}", entry.name))?;
+    if out.len() as u64 != entry.size {
+        bail!(
+            "Entry {} inflates to {} bytes instead of {}",
+            entry.name,
+            out.len(),
+            entry.size
+        );
+    }
+    Ok(out)
+}
+
+fn raw_entry<'a>(data: &'a [u8], entry: &PyInstallerEntry) -> Result<&'a [u8]> {
+    let start = entry.offset as usize;
+    let end = start.checked_add(entry.compressed_size as usize);
+    match end.and_then(|end| data.get(start..end)) {
+        Some(raw) => Ok(raw),
+        None => bail!("Entry {} extends past the end of the file", entry.name),
+    }
+}
+
+/// The first `len` bytes of an entry, inflating no more than that.
+fn read_prefix(data: &[u8], entry: &PyInstallerEntry, len: usize) -> Option<Vec<u8>> {
+    let raw = raw_entry(data, entry).ok()?;
+    if !entry.compressed {
+        return raw.get(..len).map(<[u8]>::to_vec);
+    }
+    let mut out = Vec::new();
+    ZlibDecoder::new(raw)
+        .take(len as u64)
+        .read_to_end(&mut out)
+        .ok()?;
+    (out.len() == len).then_some(out)
+}
+
+/// Returns the 16-byte `.pyc` header taken from a bundled module, used to turn
+/// the headerless code objects stored for scripts back into loadable `.pyc` files.
+pub fn pyc_header(data: &[u8], archive: &PyInstallerArchive) -> Option<Vec<u8>> {
+    archive
+        .entries
+        .iter()
+        .filter(|e| e.kind == 'm' || e.kind == 'M')
+        .filter_map(|e| read_prefix(data, e, 16))
+        .find(|header| &header[2..4] == b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toc_entry(name: &str, offset: u32, len: u32, kind: u8) -> Vec<u8> {
+        let size = (TOC_ENTRY_HEADER + name.len() + 1) as u32;
+        let mut entry = Vec::new();
+        for v in [size, offset, len, len] {
+            entry.extend_from_slice(&v.to_be_bytes());
+        }
+        entry.push(0);
+        entry.push(kind);
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry
+    }
+
+    #[test]
+    fn test_parse_carchive() {
+        let mut data = b"MZ fake executable stub".to_vec();
+        let archive_start = data.len();
+        data.extend_from_slice(b"print('hi')");
+        let toc_offset = data.len() - archive_start;
+        let toc = toc_entry("hello", 0, 11, b's');
+        data.extend_from_slice(&toc);
+
+        let length = (data.len() - archive_start + COOKIE_SIZE) as u32;
+        data.extend_from_slice(COOKIE_MAGIC);
+        for v in [length, toc_offset as u32, toc.len() as u32, 311] {
+            data.extend_from_slice(&v.to_be_bytes());
+        }
+        let mut lib = b"libpython3.11.so.1.0".to_vec();
+        lib.resize(64, 0);
+        data.extend_from_slice(&lib);
+
+        let archive = find_archive(&data).expect("archive not found");
+        assert_eq!(archive.offset, archive_start as u64);
+        assert_eq!(archive.python_version, "3.11");
+        assert_eq!(
+            archive.python_library.as_deref(),
+            Some("libpython3.11.so.1.0")
+        );
+        assert_eq!(archive.entries.len(), 1);
+        assert_eq!(archive.entries[0].kind_name(), "script");
+        assert_eq!(
+            read_entry(&data, &archive.entries[0]).unwrap(),
+            b"print('hi')"
+        );
+    }
+
+    #[test]
+    fn test_read_entry_size_mismatch() {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        std::io::Write::write_all(&mut encoder, &[0u8; 4096]).unwrap();
+        let data = encoder.finish().unwrap();
+        let mut entry = PyInstallerEntry {
+            name: "bomb".to_string(),
+            kind: 'm',
+            offset: 0,
+            compressed_size: data.len() as u64,
+            size: 4096,
+            compressed: true,
+        };
+        assert_eq!(read_entry(&data, &entry).unwrap().len(), 4096);
+        assert_eq!(read_prefix(&data, &entry, 16), Some(vec![0; 16]));
+
+        entry.size = 16;
+        assert!(read_entry(&data, &entry).is_err());
+        entry.size = 8192;
+        assert!(read_entry(&data, &entry).is_err());
+        entry.size = u64::MAX;
+        assert!(read_entry(&data, &entry).is_err());
+        entry.compressed_size = u64::MAX;
+        assert!(read_entry(&data, &entry).is_err());
+    }
+
+    #[test]
+    fn test_no_archive() {
+        assert!(find_archive(b"\x7fELF plain binary").is_none());
+    }
+}
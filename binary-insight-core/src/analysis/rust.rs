@@ -0,0 +1,199 @@
+use crate::binary::{BinaryInfo, SymbolInfo};
+
+/// Strings the Rust standard library embeds for common panics.
+const PANIC_MARKERS: &[&str] = &[
+    "called `Option::unwrap()` on a `None` value",
+    "called `Result::unwrap()` on an `Err` value",
+    "RUST_BACKTRACE",
+    "/rustc/",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RustCrate {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RustInfo {
+    /// Version from the `.comment` section, e.g. `1.75.0`.
+    pub rustc_version: Option<String>,
+    /// Commit hash from `/rustc/<hash>/library/...` panic paths.
+    pub rustc_commit: Option<String>,
+    /// Dependencies named in cargo registry panic paths.
+    pub crates: Vec<RustCrate>,
+    pub legacy_symbols: usize,
+    pub v0_symbols: usize,
+    pub panic_strings: usize,
+}
+
+/// Returns Rust toolchain metadata if the binary looks Rust-compiled.
+pub fn analyze_rust(data: &[u8], info: &BinaryInfo) -> Option<RustInfo> {
+    let mut rust = RustInfo::default();
+
+    for symbol in &info.symbols {
+        match mangling(&symbol.name) {
+            Some(Mangling::Legacy) => rust.legacy_symbols += 1,
+            Some(Mangling::V0) => rust.v0_symbols += 1,
+            None => {}
+        }
+    }
+
+    rust.rustc_version = info
+        .sections
+        .iter()
+        .find(|s| s.name == ".comment")
+        .map(|s| &data[s.file_range(data.len())])
+        .and_then(comment_rustc_version);
+
+    for s in &info.strings {
+        if PANIC_MARKERS.iter().any(|m| s.contains(m)) {
+            rust.panic_strings += 1;
+        }
+        if rust.rustc_commit.is_none() {
+            rust.rustc_commit = rustc_commit(s);
+        }
+        for krate in registry_crates(s) {
+            if !rust.crates.contains(&krate) {
+                rust.crates.push(krate);
+            }
+        }
+    }
+    rust.crates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let detected = rust.rustc_version.is_some()
+        || rust.rustc_commit.is_some()
+        || rust.legacy_symbols + rust.v0_symbols > 0
+        || rust.panic_strings >= 2;
+    detected.then_some(rust)
+}
+
+enum Mangling {
+    Legacy,
+    V0,
+}
+
+fn mangling(name: &str) -> Option<Mangling> {
+    // Mach-O prefixes every symbol with an extra underscore.
+    let name = if name.starts_with("__") {
+        &name[1..]
+    } else {
+        name
+    };
+    if name.starts_with("_R") && name.len() > 2 && rustc_demangle::try_demangle(name).is_ok() {
+        return Some(Mangling::V0);
+    }
+    // Legacy Rust symbols are Itanium-style paths ending in a `17h<hash>E` segment.
+    let body = name.strip_prefix("_ZN")?.strip_suffix('E')?;
+    let hash = body.get(body.len().checked_sub(19)?..)?;
+    (hash.starts_with("17h") && hash[3..].bytes().all(|b| b.is_ascii_hexdigit()))
+        .then_some(Mangling::Legacy)
+}
+
+/// Replaces Rust-mangled symbol names with their demangled form, dropping hashes.
+pub fn demangle_symbols(symbols: &mut [SymbolInfo]) {
+    for symbol in symbols {
+        if mangling(&symbol.name).is_some() {
+            if let Ok(demangled) = rustc_demangle::try_demangle(&symbol.name) {
+                symbol.name = format!("{:#}", demangled);
+            }
+        }
+    }
+}
+
+fn comment_rustc_version(comment: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(comment);
+    let rest = &text[text.find("rustc version ")? + "rustc version ".len()..];
+    let version: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-')
+        .collect();
+    (!version.is_empty()).then_some(version)
+}
+
+fn rustc_commit(s: &str) -> Option<String> {
+    let rest = &s[s.find("/rustc/")? + "/rustc/".len()..];
+    let hash = rest.get(..40)?;
+    (hash.bytes().all(|b| b.is_ascii_hexdigit()) && rest[40..].starts_with('/'))
+        .then(|| hash.to_string())
+}
+
+/// Parses `.cargo/registry/src/<index>/<name>-<version>/` components.
+fn registry_crates(s: &str) -> Vec<RustCrate> {
+    let mut crates = Vec::new();
+    for (idx, _) in s.match_indices("registry/src/") {
+        let mut parts = s[idx + "registry/src/".len()..].split(['/', '\\']);
+        let (Some(_index), Some(dir)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let split = dir
+            .match_indices('-')
+            .map(|(i, _)| i)
+            .find(|&i| dir[i + 1..].starts_with(|c: char| c.is_ascii_digit()));
+        crates.push(match split {
+            Some(i) => RustCrate {
+                name: dir[..i].to_string(),
+                version: Some(dir[i + 1..].to_string()),
+            },
+            None => RustCrate {
+                name: dir.to_string(),
+                version: None,
+            },
+        });
+    }
+    crates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_crates() {
+        let s = "/home/u/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-json-1.0.108/src/de.rs\
+                 /home/u/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.35.0/src/lib.rs";
+        let crates = registry_crates(s);
+        assert_eq!(crates.len(), 2);
+        assert_eq!(crates[0].name, "serde-json");
+        assert_eq!(crates[0].version.as_deref(), Some("1.0.108"));
+        assert_eq!(crates[1].name, "tokio");
+    }
+
+    #[test]
+    fn test_rustc_metadata() {
+        assert_eq!(
+            comment_rustc_version(
+                b"GCC: (GNU) 13.2\0rustc version 1.75.0 (82e1608df 2023-12-21)\0"
+            )
+            .as_deref(),
+            Some("1.75.0")
+        );
+        let path = "/rustc/82e1608dfa6e0b5569232559e3d385fea5a93112/library/core/src/option.rs";
+        assert_eq!(
+            rustc_commit(path).as_deref(),
+            Some("82e1608dfa6e0b5569232559e3d385fea5a93112")
+        );
+    }
+
+    #[test]
+    fn test_demangle_symbols() {
+        let mut symbols = vec![
+            SymbolInfo {
+                name: "_ZN4core9panicking5panic17h0123456789abcdefE".to_string(),
+                addr: 0x1000,
+            },
+            SymbolInfo {
+                name: "_RNvCs1234_7mycrate4main".to_string(),
+                addr: 0x2000,
+            },
+            SymbolInfo {
+                name: "main".to_string(),
+                addr: 0x3000,
+            },
+        ];
+        demangle_symbols(&mut symbols);
+        assert_eq!(symbols[0].name, "core::panicking::panic");
+        assert_eq!(symbols[1].name, "mycrate::main");
+        assert_eq!(symbols[2].name, "main");
+    }
+}
@@ -0,0 +1,424 @@
+//! Software bill of materials: the components a binary is built from, as
+//! far as its metadata and strings reveal them.
+
+use super::hashes::{self, FileHashes};
+use super::pe_headers::format_timestamp;
+use crate::binary::{BinaryFile, BinaryInfo};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where a component was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// Go buildinfo module list.
+    GoModule,
+    /// Crate paths in Rust panic messages and symbols.
+    Cargo,
+    /// .NET AssemblyRef table.
+    DotnetReference,
+    /// Shared library needed at load time.
+    DynamicLibrary,
+    /// Library functions matched by a signature pack (statically linked).
+    Signature,
+    /// Version banner of a statically linked library.
+    VersionString,
+}
+
+impl Source {
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::GoModule => "go-module",
+            Source::Cargo => "cargo",
+            Source::DotnetReference => "dotnet-reference",
+            Source::DynamicLibrary => "dynamic-library",
+            Source::Signature => "signature",
+            Source::VersionString => "version-string",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Component {
+    pub name: String,
+    pub version: Option<String>,
+    /// Package URL, when the ecosystem is known.
+    pub purl: Option<String>,
+    pub source: Source,
+}
+
+/// Version banners of commonly vendored C libraries: the text before the
+/// version and the component name.
+const BANNERS: &[(&str, &str)] = &[
+    ("OpenSSL ", "openssl"),
+    ("LibreSSL ", "libressl"),
+    ("deflate ", "zlib"),
+    ("inflate ", "zlib"),
+    ("libpng version ", "libpng"),
+    ("libcurl/", "curl"),
+    ("mbed TLS ", "mbedtls"),
+    ("Lua ", "lua"),
+    ("expat_", "expat"),
+    ("SQLite version ", "sqlite"),
+];
+
+/// Collects the components of `info`, dropping repeats of the same name
+/// and version.
+pub fn components(info: &BinaryInfo) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut add = |name: &str, version: Option<&str>, purl_type: Option<&str>, source| {
+        let purl = purl_type.map(|kind| match version {
+            Some(version) => format!("pkg:{}/{}@{}", kind, name, version.replace('+', "%2B")),
+            None => format!("pkg:{}/{}", kind, name),
+        });
+        components.push(Component {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            purl,
+            source,
+        });
+    };
+
+    if let Some(go) = &info.go {
+        for dep in &go.deps {
+            // A `path version` replacement is what was actually built.
+            let replaced = dep.replace.as_deref().and_then(|r| r.split_once(' '));
+            let (path, version) = replaced.unwrap_or((&dep.path, &dep.version));
+            add(path, Some(version), Some("golang"), Source::GoModule);
+        }
+    }
+    if let Some(rust) = &info.rust {
+        for krate in &rust.crates {
+            add(
+                &krate.name,
+                krate.version.as_deref(),
+                Some("cargo"),
+                Source::Cargo,
+            );
+        }
+    }
+    if let Some(dotnet) = &info.dotnet {
+        for reference in &dotnet.references {
+            add(
+                &reference.name,
+                Some(&reference.version),
+                None,
+                Source::DotnetReference,
+            );
+        }
+    }
+    for library in &info.libraries {
+        add(library, None, None, Source::DynamicLibrary);
+    }
+    for m in &info.signature_matches {
+        add(&m.library, None, None, Source::Signature);
+    }
+    for s in &info.strings {
+        for &(marker, name) in BANNERS {
+            if let Some(version) = banner_version(s, marker) {
+                add(name, Some(version), Some("generic"), Source::VersionString);
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    components.retain(|c| seen.insert((c.name.clone(), c.version.clone())));
+    components
+}
+
+/// The version following `marker` in `s`, e.g. `1.1.1k` in
+/// `OpenSSL 1.1.1k  25 Mar 2021`.
+fn banner_version<'a>(s: &'a str, marker: &str) -> Option<&'a str> {
+    let at = s.find(marker)? + marker.len();
+    let rest = &s[at..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+        .unwrap_or(rest.len());
+    let version = rest[..end].trim_end_matches(['.', '-']);
+    let starts_with_digit = version.starts_with(|c: char| c.is_ascii_digit());
+    (starts_with_digit && version.contains('.')).then_some(version)
+}
+
+/// A CycloneDX 1.5 JSON document describing `binary` and its components.
+pub fn cyclonedx(binary: &BinaryFile) -> Value {
+    let hashes = file_hashes(binary);
+    let components = components(&binary.info);
+    let refs: Vec<String> = (1..=components.len())
+        .map(|i| format!("component-{}", i))
+        .collect();
+    let entries: Vec<Value> = components
+        .iter()
+        .zip(&refs)
+        .map(|(c, bom_ref)| {
+            let mut entry = json!({
+                "type": "library",
+                "bom-ref": bom_ref,
+                "name": c.name,
+            });
+            if let Some(version) = &c.version {
+                entry["version"] = json!(version);
+            }
+            if let Some(purl) = &c.purl {
+                entry["purl"] = json!(purl);
+            }
+            entry["properties"] =
+                json!([{ "name": "binaryinsight:source", "value": c.source.name() }]);
+            entry
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", uuid_from_hash(&hashes.sha256)),
+        "version": 1,
+        "metadata": {
+            "timestamp": now_iso8601(),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "BinaryInsight",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": {
+                "type": "application",
+                "bom-ref": "root",
+                "name": binary.name,
+                "hashes": [
+                    { "alg": "SHA-256", "content": hashes.sha256 },
+                    { "alg": "SHA-1", "content": hashes.sha1 },
+                    { "alg": "MD5", "content": hashes.md5 },
+                ],
+            },
+        },
+        "components": entries,
+        "dependencies": [{ "ref": "root", "dependsOn": refs }],
+    })
+}
+
+/// An SPDX 2.3 JSON document with `binary` as the described package and
+/// one package per component, statically or dynamically linked to it.
+pub fn spdx(binary: &BinaryFile) -> Value {
+    let hashes = file_hashes(binary);
+    let root = "SPDXRef-Package-root";
+    let mut packages = vec![json!({
+        "name": binary.name,
+        "SPDXID": root,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "primaryPackagePurpose": "APPLICATION",
+        "checksums": [
+            { "algorithm": "SHA256", "checksumValue": hashes.sha256 },
+            { "algorithm": "SHA1", "checksumValue": hashes.sha1 },
+            { "algorithm": "MD5", "checksumValue": hashes.md5 },
+        ],
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "copyrightText": "NOASSERTION",
+    })];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": root,
+    })];
+    for (i, c) in components(&binary.info).iter().enumerate() {
+        let id = format!("SPDXRef-Package-{}", i + 1);
+        let mut package = json!({
+            "name": c.name,
+            "SPDXID": id,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "primaryPackagePurpose": "LIBRARY",
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": "NOASSERTION",
+            "copyrightText": "NOASSERTION",
+            "comment": format!("source: {}", c.source.name()),
+        });
+        if let Some(version) = &c.version {
+            package["versionInfo"] = json!(version);
+        }
+        if let Some(purl) = &c.purl {
+            package["externalRefs"] = json!([{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": purl,
+            }]);
+        }
+        packages.push(package);
+        let link = match c.source {
+            Source::DynamicLibrary | Source::DotnetReference => "DYNAMIC_LINK",
+            _ => "STATIC_LINK",
+        };
+        relationships.push(json!({
+            "spdxElementId": root,
+            "relationshipType": link,
+            "relatedSpdxElement": id,
+        }));
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": binary.name,
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/binaryinsight/{}",
+            uuid_from_hash(&hashes.sha256)
+        ),
+        "creationInfo": {
+            "created": now_iso8601(),
+            "creators": [format!("Tool: BinaryInsight-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// The hashes stored by the analysis pass, or computed now.
+fn file_hashes(binary: &BinaryFile) -> FileHashes {
+    binary
+        .info
+        .analysis
+        .as_ref()
+        .and_then(|a| a.hashes.clone())
+        .unwrap_or_else(|| hashes::calculate_hashes(&binary.data))
+}
+
+/// A stable, name-based (version 5 layout) UUID from a hex digest.
+fn uuid_from_hash(hex_digest: &str) -> String {
+    let mut digits: Vec<char> = hex_digest
+        .chars()
+        .chain("0".repeat(32).chars())
+        .take(32)
+        .collect();
+    digits[12] = '5';
+    let variant = digits[16].to_digit(16).unwrap_or(0) & 0x3 | 0x8;
+    digits[16] = char::from_digit(variant, 16).unwrap_or('8');
+    let s: String = digits.into_iter().collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &s[..8],
+        &s[8..12],
+        &s[12..16],
+        &s[16..20],
+        &s[20..]
+    )
+}
+
+/// The current time as `YYYY-MM-DDTHH:MM:SSZ`.
+fn now_iso8601() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as u32);
+    let stamp = format_timestamp(secs);
+    format!("{}Z", stamp.trim_end_matches(" UTC").replace(' ', "T"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::golang::{GoInfo, GoModule};
+
+    fn info() -> BinaryInfo {
+        let module = |path: &str, version: &str| GoModule {
+            path: path.to_string(),
+            version: version.to_string(),
+            sum: None,
+            replace: None,
+        };
+        BinaryInfo {
+            libraries: vec!["libc.so.6".to_string()],
+            strings: vec![
+                "OpenSSL 1.1.1k  25 Mar 2021".to_string(),
+                " deflate 1.2.11 Copyright 1995-2017 Jean-loup Gailly ".to_string(),
+                " inflate 1.2.11 Copyright 1995-2017 Mark Adler ".to_string(),
+                "Lua is great".to_string(),
+            ],
+            go: Some(GoInfo {
+                deps: vec![module("golang.org/x/sys", "v0.15.0")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_components() {
+        let found: Vec<_> = components(&info())
+            .into_iter()
+            .map(|c| (c.name, c.version, c.purl))
+            .collect();
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            found,
+            [
+                (
+                    "golang.org/x/sys".to_string(),
+                    some("v0.15.0"),
+                    some("pkg:golang/golang.org/x/sys@v0.15.0")
+                ),
+                ("libc.so.6".to_string(), None, None),
+                (
+                    "openssl".to_string(),
+                    some("1.1.1k"),
+                    some("pkg:generic/openssl@1.1.1k")
+                ),
+                (
+                    "zlib".to_string(),
+                    some("1.2.11"),
+                    some("pkg:generic/zlib@1.2.11")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cyclonedx() {
+        let binary = BinaryFile {
+            name: "app".to_string(),
+            data: Vec::new().into(),
+            info: info(),
+            passes: Default::default(),
+        };
+        let bom = cyclonedx(&binary);
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["components"].as_array().unwrap().len(), 4);
+        assert_eq!(bom["dependencies"][0]["dependsOn"][3], "component-4");
+        let serial = bom["serialNumber"].as_str().unwrap();
+        assert_eq!(serial.len(), "urn:uuid:".len() + 36);
+        assert_eq!(&serial[9 + 14..9 + 15], "5");
+    }
+
+    #[test]
+    fn test_spdx() {
+        let binary = BinaryFile {
+            name: "app".to_string(),
+            data: Vec::new().into(),
+            info: info(),
+            passes: Default::default(),
+        };
+        let doc = spdx(&binary);
+        assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+        assert_eq!(doc["packages"].as_array().unwrap().len(), 5);
+        assert_eq!(doc["packages"][3]["versionInfo"], "1.1.1k");
+        let links: Vec<&str> = doc["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["relationshipType"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            links,
+            [
+                "DESCRIBES",
+                "STATIC_LINK",
+                "DYNAMIC_LINK",
+                "STATIC_LINK",
+                "STATIC_LINK"
+            ]
+        );
+    }
+}
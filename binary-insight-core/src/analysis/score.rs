@@ -0,0 +1,202 @@
+use super::capabilities::{api_names, has_api};
+use crate::binary::BinaryInfo;
+use serde::Serialize;
+
+/// What an indicator looks for.
+enum Condition {
+    /// All of these APIs are imported or defined.
+    Apis(&'static [&'static str]),
+    /// Some extracted string contains this, case-insensitively.
+    String(&'static str),
+}
+
+struct Indicator {
+    description: &'static str,
+    weight: u32,
+    condition: Condition,
+}
+
+const INDICATORS: &[Indicator] = &[
+    Indicator {
+        description: "Remote thread injection",
+        weight: 40,
+        condition: Condition::Apis(&["VirtualAllocEx", "WriteProcessMemory", "CreateRemoteThread"]),
+    },
+    Indicator {
+        description: "Process hollowing",
+        weight: 35,
+        condition: Condition::Apis(&["NtUnmapViewOfSection", "SetThreadContext", "ResumeThread"]),
+    },
+    Indicator {
+        description: "APC injection",
+        weight: 30,
+        condition: Condition::Apis(&["WriteProcessMemory", "QueueUserAPC"]),
+    },
+    Indicator {
+        description: "Downloads files",
+        weight: 25,
+        condition: Condition::Apis(&["URLDownloadToFile"]),
+    },
+    Indicator {
+        description: "Keyboard hook",
+        weight: 20,
+        condition: Condition::Apis(&["SetWindowsHookEx", "GetAsyncKeyState"]),
+    },
+    Indicator {
+        description: "Encrypts data (CryptoAPI)",
+        weight: 10,
+        condition: Condition::Apis(&["CryptEncrypt"]),
+    },
+    Indicator {
+        description: "Encrypts data (CNG)",
+        weight: 10,
+        condition: Condition::Apis(&["BCryptEncrypt"]),
+    },
+    Indicator {
+        description: "Adjusts token privileges",
+        weight: 10,
+        condition: Condition::Apis(&["AdjustTokenPrivileges"]),
+    },
+    Indicator {
+        description: "Installs a service",
+        weight: 10,
+        condition: Condition::Apis(&["CreateService"]),
+    },
+    Indicator {
+        description: "Checks for a debugger",
+        weight: 10,
+        condition: Condition::Apis(&["IsDebuggerPresent"]),
+    },
+    Indicator {
+        description: "Checks for a remote debugger",
+        weight: 10,
+        condition: Condition::Apis(&["CheckRemoteDebuggerPresent"]),
+    },
+    Indicator {
+        description: "Resolves APIs at run time",
+        weight: 5,
+        condition: Condition::Apis(&["LoadLibrary", "GetProcAddress"]),
+    },
+    Indicator {
+        description: "Changes memory protection",
+        weight: 5,
+        condition: Condition::Apis(&["VirtualProtect"]),
+    },
+    Indicator {
+        description: "Deletes shadow copies",
+        weight: 30,
+        condition: Condition::String("vssadmin delete shadows"),
+    },
+    Indicator {
+        description: "Crypto-mining pool URL",
+        weight: 25,
+        condition: Condition::String("stratum+tcp://"),
+    },
+    Indicator {
+        description: "Run key persistence",
+        weight: 15,
+        condition: Condition::String("\\currentversion\\run"),
+    },
+    Indicator {
+        description: "Tor hidden service",
+        weight: 15,
+        condition: Condition::String(".onion"),
+    },
+    Indicator {
+        description: "Runs shell commands",
+        weight: 10,
+        condition: Condition::String("cmd.exe /c"),
+    },
+    Indicator {
+        description: "Runs PowerShell",
+        weight: 10,
+        condition: Condition::String("powershell"),
+    },
+];
+
+/// One indicator that contributed to a [`ThreatScore`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Factor {
+    pub description: String,
+    pub weight: u32,
+    /// The matched APIs or string.
+    pub evidence: String,
+}
+
+/// A 0-100 suspicion score for ordering files in triage; not a verdict.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreatScore {
+    pub score: u32,
+    /// Heaviest first.
+    pub factors: Vec<Factor>,
+}
+
+/// Sums the weights of the indicators found in the imports and strings,
+/// capped at 100.
+pub fn threat_score(info: &BinaryInfo) -> ThreatScore {
+    let apis = api_names(info);
+    let strings: Vec<String> = info.strings.iter().map(|s| s.to_lowercase()).collect();
+    let mut factors: Vec<Factor> = INDICATORS
+        .iter()
+        .filter_map(|indicator| {
+            let evidence = match indicator.condition {
+                Condition::Apis(names) => names
+                    .iter()
+                    .all(|name| has_api(&apis, name))
+                    .then(|| names.join(" + "))?,
+                Condition::String(needle) => {
+                    let index = strings.iter().position(|s| s.contains(needle))?;
+                    info.strings[index].clone()
+                }
+            };
+            Some(Factor {
+                description: indicator.description.to_string(),
+                weight: indicator.weight,
+                evidence,
+            })
+        })
+        .collect();
+    factors.sort_by_key(|f| std::cmp::Reverse(f.weight));
+    ThreatScore {
+        score: factors.iter().map(|f| f.weight).sum::<u32>().min(100),
+        factors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::{BinaryFile, ImportInfo};
+
+    #[test]
+    fn test_threat_score() {
+        let mut binary = BinaryFile::from_raw("t".to_string(), vec![0; 4], "x86", 0);
+        assert_eq!(threat_score(&binary.info).score, 0);
+
+        let info = &mut binary.info;
+        for name in [
+            "VirtualAllocEx",
+            "WriteProcessMemory",
+            "CreateRemoteThreadEx",
+        ] {
+            info.imports.push(ImportInfo {
+                name: name.to_string(),
+                library: None,
+                slot: None,
+            });
+        }
+        info.strings.push("cmd.exe /C whoami".to_string());
+        let score = threat_score(info);
+        // CreateRemoteThreadEx is not CreateRemoteThread.
+        assert_eq!(score.score, 10);
+
+        info.imports[2].name = "CreateRemoteThread".to_string();
+        info.strings
+            .push("vssadmin delete shadows /all".to_string());
+        info.strings.push("stratum+tcp://pool".to_string());
+        let score = threat_score(info);
+        assert_eq!(score.score, 100);
+        assert_eq!(score.factors[0].description, "Remote thread injection");
+        assert_eq!(score.factors[3].evidence, "cmd.exe /C whoami");
+    }
+}
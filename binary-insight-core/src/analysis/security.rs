@@ -0,0 +1,648 @@
+use super::pe_headers;
+use goblin::elf::{dynamic, program_header, section_header, Elf};
+use goblin::mach::load_command::CommandVariant;
+use goblin::mach::Mach;
+use goblin::pe::PE;
+
+/// How much of the GOT and other relocated data is read-only after start-up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Relro {
+    #[default]
+    None,
+    /// `PT_GNU_RELRO` without immediate binding; the GOT stays writable.
+    Partial,
+    /// `PT_GNU_RELRO` with `BIND_NOW`.
+    Full,
+}
+
+impl Relro {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Relro::None => "No RELRO",
+            Relro::Partial => "Partial RELRO",
+            Relro::Full => "Full RELRO",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SecurityFeatures {
+    /// A linked executable or library rather than an object file; the
+    /// mitigations below only mean something for these.
+    pub loadable: bool,
+    pub pie: bool,
+    pub nx: bool,
+    pub relro: Relro,
+    pub canary: bool,
+    /// Functions called through their `__*_chk` FORTIFY_SOURCE variants,
+    /// e.g. `memcpy` for `__memcpy_chk`.
+    pub fortified: Vec<String>,
+    /// Control-flow protections the binary is marked for: `IBT` and `SHSTK`
+    /// (x86 CET) or `BTI` and `PAC` (AArch64) for ELF, `CFG` and `CET` for PE.
+    pub cfi: Vec<&'static str>,
+    /// Stack probes from `-fstack-clash-protection` were seen in the code.
+    /// Heuristic, x86 only.
+    pub stack_clash: bool,
+    /// Mappings that are writable and executable.
+    pub rwx: Vec<RwxRegion>,
+    pub pe: Option<PeMitigations>,
+    pub macho: Option<MachOSecurity>,
+}
+
+/// A section or segment mapped both writable and executable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RwxRegion {
+    /// Section name, or `LOAD[i]` for the i-th ELF program header.
+    pub name: String,
+    pub addr: u64,
+    pub size: u64,
+    /// Mapped writable, with only the maximum protection allowing execution
+    /// later (Mach-O).
+    pub deferred: bool,
+}
+
+/// PE mitigations with no ELF counterpart.
+#[derive(Debug, Default, Clone)]
+pub struct PeMitigations {
+    /// 64-bit ASLR (`HIGH_ENTROPY_VA`).
+    pub high_entropy_va: bool,
+    /// The loader requires a valid signature (`FORCE_INTEGRITY`).
+    pub force_integrity: bool,
+    pub app_container: bool,
+    pub safe_seh: SafeSeh,
+}
+
+/// Mach-O code signing and loader restrictions.
+#[derive(Debug, Default, Clone)]
+pub struct MachOSecurity {
+    /// `LC_ENCRYPTION_INFO` with a non-zero `cryptid`: App Store encrypted.
+    pub encrypted: bool,
+    /// Has an embedded code signature.
+    pub signed: bool,
+    pub hardened_runtime: bool,
+    /// Only Apple- or same-team-signed libraries may be loaded.
+    pub library_validation: bool,
+    /// A `__RESTRICT` segment, which makes dyld ignore `DYLD_*` variables.
+    pub restrict: bool,
+    /// Libraries loaded via `@rpath`, which an attacker-controlled earlier
+    /// `LC_RPATH` directory could satisfy.
+    pub rpath_dylibs: Vec<String>,
+}
+
+/// Structured exception handler protection, which only 32-bit images need.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SafeSeh {
+    /// 64-bit images use table-based unwinding.
+    #[default]
+    NotApplicable,
+    /// The image declares it has no exception handlers (`NO_SEH`).
+    NoSeh,
+    /// Handlers are registered in the load config's SafeSEH table.
+    Table,
+    /// Handlers may be installed without any table to check them against.
+    Missing,
+}
+
+impl SafeSeh {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SafeSeh::NotApplicable => "n/a (64-bit)",
+            SafeSeh::NoSeh => "No SEH",
+            SafeSeh::Table => "SafeSEH",
+            SafeSeh::Missing => "No SafeSEH",
+        }
+    }
+}
+
+const IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA: u16 = 0x0020;
+const IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE: u16 = 0x0040;
+const IMAGE_DLLCHARACTERISTICS_FORCE_INTEGRITY: u16 = 0x0080;
+const IMAGE_DLLCHARACTERISTICS_NX_COMPAT: u16 = 0x0100;
+const IMAGE_DLLCHARACTERISTICS_NO_SEH: u16 = 0x0400;
+const IMAGE_DLLCHARACTERISTICS_APPCONTAINER: u16 = 0x1000;
+const IMAGE_DLLCHARACTERISTICS_GUARD_CF: u16 = 0x4000;
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+const IMAGE_GUARD_CF_INSTRUMENTED: u32 = 0x100;
+const IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS: u32 = 20;
+const IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT: u32 = 0x01;
+
+const VM_PROT_WRITE: u32 = 0x2;
+const VM_PROT_EXECUTE: u32 = 0x4;
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade_0cc0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade_0c02;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade_7171;
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CSSLOT_ENTITLEMENTS: u32 = 5;
+const CS_REQUIRE_LV: u32 = 0x2000;
+const CS_RUNTIME: u32 = 0x1_0000;
+const DISABLE_LIBRARY_VALIDATION: &[u8] = b"com.apple.security.cs.disable-library-validation";
+
+const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc000_0000;
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+
+/// `or qword [rsp], 0` and `or dword [esp], 0`, the probes GCC and Clang
+/// emit after each page of a large stack allocation.
+const STACK_PROBES: &[&[u8]] = &[&[0x48, 0x83, 0x0c, 0x24, 0x00], &[0x83, 0x0c, 0x24, 0x00]];
+
+pub fn analyze_security_elf(elf: &Elf, data: &[u8]) -> SecurityFeatures {
+    let mut features = SecurityFeatures {
+        loadable: matches!(
+            elf.header.e_type,
+            goblin::elf::header::ET_EXEC | goblin::elf::header::ET_DYN
+        ),
+        ..Default::default()
+    };
+
+    // PIE: ET_DYN (3) usually implies PIE for executables (though shared libs are also ET_DYN)
+    // Actually, distinct PIE vs Shared Lib is harder without context, but strictly, ET_DYN means it supports ASLR.
+    if elf.header.e_type == goblin::elf::header::ET_DYN {
+        features.pie = true;
+    }
+
+    // NX: PT_GNU_STACK header with !PF_X
+    if let Some(phdr) = elf
+        .program_headers
+        .iter()
+        .find(|ph| ph.p_type == program_header::PT_GNU_STACK)
+    {
+        if phdr.p_flags & program_header::PF_X == 0 {
+            features.nx = true;
+        }
+    } else {
+        // Default stack executable? Typically yes if missing on older linux, but modern often default NX.
+        // Let's assume false (executable stack) if not explicitly disabled, to be safe/conservative in reporting "NX".
+    }
+
+    // RELRO: PT_GNU_RELRO, made full by resolving every symbol at load time.
+    if elf
+        .program_headers
+        .iter()
+        .any(|ph| ph.p_type == program_header::PT_GNU_RELRO)
+    {
+        let bind_now = elf.dynamic.as_ref().is_some_and(|d| {
+            d.dyns.iter().any(|dyn_| match dyn_.d_tag {
+                dynamic::DT_BIND_NOW => true,
+                dynamic::DT_FLAGS => dyn_.d_val & dynamic::DF_BIND_NOW != 0,
+                dynamic::DT_FLAGS_1 => dyn_.d_val & dynamic::DF_1_NOW != 0,
+                _ => false,
+            })
+        });
+        features.relro = if bind_now {
+            Relro::Full
+        } else {
+            Relro::Partial
+        };
+    }
+
+    let names = elf
+        .syms
+        .iter()
+        .filter_map(|sym| elf.strtab.get_at(sym.st_name))
+        .chain(
+            elf.dynsyms
+                .iter()
+                .filter_map(|sym| elf.dynstrtab.get_at(sym.st_name)),
+        );
+    for name in names {
+        let name = name.split('@').next().unwrap_or(name);
+        // Canary: Check for symbol like __stack_chk_fail
+        if name.contains("__stack_chk_fail") {
+            features.canary = true;
+        } else if let Some(function) = fortified_function(name) {
+            if !features.fortified.iter().any(|f| f == function) {
+                features.fortified.push(function.to_string());
+            }
+        }
+    }
+    features.fortified.sort();
+
+    if let Some(notes) = elf.iter_note_headers(data) {
+        // goblin repeats a note parse error forever, so stop at the first one.
+        for note in notes.map_while(Result::ok) {
+            if note.name == "GNU" && note.n_type == NT_GNU_PROPERTY_TYPE_0 {
+                let align = if elf.is_64 { 8 } else { 4 };
+                features.cfi = cfi_properties(note.desc, elf.little_endian, align);
+            }
+        }
+    }
+
+    let rwx = program_header::PF_W | program_header::PF_X;
+    for (i, ph) in elf.program_headers.iter().enumerate() {
+        if ph.p_type == program_header::PT_LOAD && ph.p_flags & rwx == rwx {
+            features.rwx.push(RwxRegion {
+                name: format!("LOAD[{}]", i),
+                addr: ph.p_vaddr,
+                size: ph.p_memsz,
+                deferred: false,
+            });
+        }
+    }
+    let rwx = (section_header::SHF_WRITE | section_header::SHF_EXECINSTR) as u64;
+    for sh in &elf.section_headers {
+        if sh.sh_flags & rwx == rwx {
+            features.rwx.push(RwxRegion {
+                name: elf
+                    .shdr_strtab
+                    .get_at(sh.sh_name)
+                    .unwrap_or("?")
+                    .to_string(),
+                addr: sh.sh_addr,
+                size: sh.sh_size,
+                deferred: false,
+            });
+        }
+    }
+
+    features.stack_clash = matches!(
+        elf.header.e_machine,
+        goblin::elf::header::EM_X86_64 | goblin::elf::header::EM_386
+    ) && elf
+        .program_headers
+        .iter()
+        .filter(|ph| ph.p_type == program_header::PT_LOAD && ph.p_flags & program_header::PF_X != 0)
+        .filter_map(|ph| {
+            data.get(ph.p_offset as usize..)?
+                .get(..ph.p_filesz as usize)
+        })
+        .any(|code| {
+            STACK_PROBES
+                .iter()
+                .any(|probe| code.windows(probe.len()).any(|w| w == *probe))
+        });
+
+    features
+}
+
+/// `memcpy` for `__memcpy_chk`; the stack protector's own symbols are not
+/// FORTIFY functions.
+fn fortified_function(name: &str) -> Option<&str> {
+    let function = name.strip_prefix("__")?.strip_suffix("_chk")?;
+    (!function.is_empty() && !function.starts_with("stack_chk")).then_some(function)
+}
+
+/// The CET or BTI/PAC feature bits in an `NT_GNU_PROPERTY_TYPE_0` note.
+fn cfi_properties(mut desc: &[u8], little_endian: bool, align: usize) -> Vec<&'static str> {
+    let read = |bytes: &[u8]| {
+        let bytes: [u8; 4] = bytes.try_into().ok()?;
+        Some(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    };
+    let mut cfi = Vec::new();
+    while desc.len() >= 8 {
+        let (Some(kind), Some(size)) = (read(&desc[..4]), read(&desc[4..8])) else {
+            break;
+        };
+        let size = size as usize;
+        let Some(value) = desc.get(8..8 + size) else {
+            break;
+        };
+        let bits = if size == 4 { read(value) } else { None }.unwrap_or(0);
+        let names: &[(u32, &str)] = match kind {
+            GNU_PROPERTY_X86_FEATURE_1_AND => &[(1, "IBT"), (2, "SHSTK")],
+            GNU_PROPERTY_AARCH64_FEATURE_1_AND => &[(1, "BTI"), (2, "PAC")],
+            _ => &[],
+        };
+        cfi.extend(
+            names
+                .iter()
+                .filter(|(bit, _)| bits & bit != 0)
+                .map(|(_, n)| *n),
+        );
+        let next = (8 + size).div_ceil(align) * align;
+        desc = desc.get(next..).unwrap_or_default();
+    }
+    cfi
+}
+
+pub fn analyze_security_pe(pe: &PE, data: &[u8]) -> SecurityFeatures {
+    let mut features = SecurityFeatures::default();
+    let Some(opt_header) = &pe.header.optional_header else {
+        return features;
+    };
+    let dll_char = opt_header.windows_fields.dll_characteristics;
+    let rwx = IMAGE_SCN_MEM_WRITE | IMAGE_SCN_MEM_EXECUTE;
+    features.rwx = pe
+        .sections
+        .iter()
+        .filter(|s| s.characteristics & rwx == rwx)
+        .map(|s| RwxRegion {
+            name: s.name().unwrap_or("<bad>").to_string(),
+            addr: s.virtual_address as u64,
+            size: s.virtual_size as u64,
+            deferred: false,
+        })
+        .collect();
+    features.loadable = true;
+    features.pie = dll_char & IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE != 0;
+    features.nx = dll_char & IMAGE_DLLCHARACTERISTICS_NX_COMPAT != 0;
+
+    let directory = |dir| pe_headers::directory_data(pe, data, dir);
+    let load_config = directory(opt_header.data_directories.get_load_config_table())
+        .map(|bytes| LoadConfig::parse(bytes, pe.is_64))
+        .unwrap_or_default();
+
+    // /GS stack cookies are checked against the load config's cookie.
+    features.canary = load_config.security_cookie;
+    if dll_char & IMAGE_DLLCHARACTERISTICS_GUARD_CF != 0
+        && load_config.guard_flags & IMAGE_GUARD_CF_INSTRUMENTED != 0
+    {
+        features.cfi.push("CFG");
+    }
+    if directory(opt_header.data_directories.get_debug_table())
+        .is_some_and(|entries| cet_compatible(entries, data))
+    {
+        features.cfi.push("CET");
+    }
+
+    features.pe = Some(PeMitigations {
+        high_entropy_va: dll_char & IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA != 0,
+        force_integrity: dll_char & IMAGE_DLLCHARACTERISTICS_FORCE_INTEGRITY != 0,
+        app_container: dll_char & IMAGE_DLLCHARACTERISTICS_APPCONTAINER != 0,
+        safe_seh: if pe.is_64 {
+            SafeSeh::NotApplicable
+        } else if dll_char & IMAGE_DLLCHARACTERISTICS_NO_SEH != 0 {
+            SafeSeh::NoSeh
+        } else if load_config.se_handlers {
+            SafeSeh::Table
+        } else {
+            SafeSeh::Missing
+        },
+    });
+    features
+}
+
+/// The load config fields that record compiler mitigations.
+#[derive(Debug, Default, PartialEq)]
+struct LoadConfig {
+    security_cookie: bool,
+    se_handlers: bool,
+    guard_flags: u32,
+}
+
+impl LoadConfig {
+    /// Fields past the structure's own `Size` were added by later linkers and
+    /// read as absent.
+    fn parse(bytes: &[u8], is_64: bool) -> Self {
+        let size = read_u32(bytes, 0).unwrap_or(0) as usize;
+        let bytes = &bytes[..size.min(bytes.len())];
+        let pointer = |offset: usize| {
+            if is_64 {
+                read_u64(bytes, offset)
+            } else {
+                read_u32(bytes, offset).map(u64::from)
+            }
+        };
+        let (cookie, handlers, count, guard_flags) = if is_64 {
+            (0x58, 0x60, 0x68, 0x90)
+        } else {
+            (0x3c, 0x40, 0x44, 0x58)
+        };
+        LoadConfig {
+            security_cookie: pointer(cookie).is_some_and(|va| va != 0),
+            se_handlers: pointer(handlers).is_some_and(|va| va != 0)
+                && pointer(count).is_some_and(|n| n != 0),
+            guard_flags: read_u32(bytes, guard_flags).unwrap_or(0),
+        }
+    }
+}
+
+/// Whether a debug directory entry marks the image shadow-stack compatible.
+fn cet_compatible(entries: &[u8], data: &[u8]) -> bool {
+    entries
+        .chunks_exact(pe_headers::DEBUG_ENTRY_SIZE)
+        .any(|entry| {
+            read_u32(entry, 12) == Some(IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS)
+                && read_u32(entry, 24)
+                    .and_then(|offset| read_u32(data, offset as usize))
+                    .is_some_and(|flags| flags & IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT != 0)
+        })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+}
+
+pub fn analyze_security_mach(mach: &Mach, data: &[u8]) -> SecurityFeatures {
+    match mach {
+        Mach::Binary(macho) => {
+            let flags = macho.header.flags;
+            let mut macho_security = MachOSecurity {
+                restrict: macho
+                    .segments
+                    .iter()
+                    .any(|seg| seg.name().is_ok_and(|name| name == "__RESTRICT")),
+                rpath_dylibs: macho
+                    .libs
+                    .iter()
+                    .filter(|lib| lib.starts_with("@rpath/"))
+                    .map(|lib| lib.to_string())
+                    .collect(),
+                ..Default::default()
+            };
+            for command in &macho.load_commands {
+                match &command.command {
+                    CommandVariant::EncryptionInfo32(info) => {
+                        macho_security.encrypted |= info.cryptid != 0
+                    }
+                    CommandVariant::EncryptionInfo64(info) => {
+                        macho_security.encrypted |= info.cryptid != 0
+                    }
+                    CommandVariant::CodeSignature(sig) => {
+                        let blob = data
+                            .get(sig.dataoff as usize..)
+                            .and_then(|d| d.get(..sig.datasize as usize));
+                        if let Some(blob) = blob {
+                            macho_security.apply_signature(blob);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let rwx = macho
+                .segments
+                .iter()
+                .filter(|seg| {
+                    seg.initprot & VM_PROT_WRITE != 0
+                        && (seg.initprot | seg.maxprot) & VM_PROT_EXECUTE != 0
+                })
+                .map(|seg| RwxRegion {
+                    name: seg.name().unwrap_or("?").to_string(),
+                    addr: seg.vmaddr,
+                    size: seg.vmsize,
+                    deferred: seg.initprot & VM_PROT_EXECUTE == 0,
+                })
+                .collect();
+            SecurityFeatures {
+                // MH_PIE and the stack flag are only set on executables.
+                loadable: macho.header.filetype == goblin::mach::header::MH_EXECUTE,
+                pie: (flags & 0x200000) != 0, // MH_PIE
+                nx: (flags & 0x20000) == 0, // MH_ALLOW_STACK_EXECUTION (0x20000). If NOT set, stack is non-exec (NX is true).
+                rwx,
+                macho: Some(macho_security),
+                ..Default::default()
+            }
+        }
+        _ => SecurityFeatures::default(),
+    }
+}
+
+impl MachOSecurity {
+    /// Reads the code directory flags and entitlements from an embedded
+    /// signature superblob.
+    fn apply_signature(&mut self, blob: &[u8]) {
+        if read_be_u32(blob, 0) != Some(CSMAGIC_EMBEDDED_SIGNATURE) {
+            return;
+        }
+        self.signed = true;
+        let count = read_be_u32(blob, 8).unwrap_or(0) as usize;
+        let mut code_directory_flags = None;
+        let mut disables_validation = false;
+        for i in 0..count.min(blob.len() / 8) {
+            let (Some(slot), Some(offset)) =
+                (read_be_u32(blob, 12 + i * 8), read_be_u32(blob, 16 + i * 8))
+            else {
+                break;
+            };
+            let Some(inner) = blob.get(offset as usize..) else {
+                continue;
+            };
+            match (slot, read_be_u32(inner, 0)) {
+                (CSSLOT_CODEDIRECTORY, Some(CSMAGIC_CODEDIRECTORY)) => {
+                    code_directory_flags = read_be_u32(inner, 12)
+                }
+                (CSSLOT_ENTITLEMENTS, Some(CSMAGIC_EMBEDDED_ENTITLEMENTS)) => {
+                    let length = read_be_u32(inner, 4).unwrap_or(0) as usize;
+                    let plist = inner.get(8..length.min(inner.len())).unwrap_or_default();
+                    disables_validation = plist
+                        .windows(DISABLE_LIBRARY_VALIDATION.len())
+                        .any(|w| w == DISABLE_LIBRARY_VALIDATION);
+                }
+                _ => {}
+            }
+        }
+        let flags = code_directory_flags.unwrap_or(0);
+        self.hardened_runtime = flags & CS_RUNTIME != 0;
+        // The hardened runtime turns library validation on unless entitled off.
+        self.library_validation =
+            flags & CS_REQUIRE_LV != 0 || (self.hardened_runtime && !disables_validation);
+    }
+}
+
+fn read_be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fortified_function() {
+        assert_eq!(fortified_function("__memcpy_chk"), Some("memcpy"));
+        assert_eq!(fortified_function("__stack_chk_fail"), None);
+        assert_eq!(fortified_function("__chk"), None);
+        assert_eq!(fortified_function("memcpy"), None);
+    }
+
+    #[test]
+    fn test_corrupt_note_segment() {
+        // A PT_NOTE segment whose p_filesz runs far past the end of the file.
+        let mut data = vec![0u8; 0x100];
+        data[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        data[16] = 2; // ET_EXEC
+        data[18] = 62; // EM_X86_64
+        data[20] = 1;
+        data[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data[52] = 64;
+        data[54] = 56;
+        data[56] = 1; // e_phnum
+        data[64..68].copy_from_slice(&program_header::PT_NOTE.to_le_bytes());
+        data[72..80].copy_from_slice(&0xf0u64.to_le_bytes()); // p_offset
+        data[96..104].copy_from_slice(&0x4e20u64.to_le_bytes()); // p_filesz
+        let elf = Elf::parse(&data).unwrap();
+        assert!(analyze_security_elf(&elf, &data).cfi.is_empty());
+    }
+
+    #[test]
+    fn test_load_config() {
+        let mut bytes = vec![0u8; 0x5c];
+        bytes[..4].copy_from_slice(&0x5cu32.to_le_bytes());
+        bytes[0x3c..0x40].copy_from_slice(&0x40_3000u32.to_le_bytes());
+        bytes[0x40..0x44].copy_from_slice(&0x40_2000u32.to_le_bytes());
+        bytes[0x44..0x48].copy_from_slice(&3u32.to_le_bytes());
+        bytes[0x58..0x5c].copy_from_slice(&IMAGE_GUARD_CF_INSTRUMENTED.to_le_bytes());
+        let expected = LoadConfig {
+            security_cookie: true,
+            se_handlers: true,
+            guard_flags: IMAGE_GUARD_CF_INSTRUMENTED,
+        };
+        assert_eq!(LoadConfig::parse(&bytes, false), expected);
+
+        // An older, shorter structure ends before the guard flags.
+        bytes[..4].copy_from_slice(&0x48u32.to_le_bytes());
+        let config = LoadConfig::parse(&bytes, false);
+        assert!(config.security_cookie && config.se_handlers);
+        assert_eq!(config.guard_flags, 0);
+    }
+
+    #[test]
+    fn test_code_signature_flags() {
+        let entitlements = b"<key>com.apple.security.cs.disable-library-validation</key><true/>";
+        let mut blob = Vec::new();
+        blob.extend(CSMAGIC_EMBEDDED_SIGNATURE.to_be_bytes());
+        blob.extend(0u32.to_be_bytes());
+        blob.extend(2u32.to_be_bytes());
+        for (slot, offset) in [(CSSLOT_CODEDIRECTORY, 28u32), (CSSLOT_ENTITLEMENTS, 44)] {
+            blob.extend(slot.to_be_bytes());
+            blob.extend(offset.to_be_bytes());
+        }
+        // Code directory: magic, length, version, flags.
+        for word in [CSMAGIC_CODEDIRECTORY, 16, 0x20400, CS_RUNTIME] {
+            blob.extend(word.to_be_bytes());
+        }
+        blob.extend(CSMAGIC_EMBEDDED_ENTITLEMENTS.to_be_bytes());
+        blob.extend((8 + entitlements.len() as u32).to_be_bytes());
+        blob.extend(entitlements);
+
+        let mut security = MachOSecurity::default();
+        security.apply_signature(&blob);
+        assert!(security.signed && security.hardened_runtime);
+        assert!(!security.library_validation);
+
+        blob.truncate(44);
+        let mut security = MachOSecurity::default();
+        security.apply_signature(&blob);
+        assert!(security.hardened_runtime && security.library_validation);
+    }
+
+    #[test]
+    fn test_cfi_properties() {
+        let mut desc = Vec::new();
+        // An unrelated property (ISA needed), then x86 features IBT | SHSTK.
+        for (kind, value) in [(0xc000_8002u32, 1u32), (GNU_PROPERTY_X86_FEATURE_1_AND, 3)] {
+            desc.extend(kind.to_le_bytes());
+            desc.extend(4u32.to_le_bytes());
+            desc.extend(value.to_le_bytes());
+            desc.extend([0; 4]);
+        }
+        assert_eq!(cfi_properties(&desc, true, 8), vec!["IBT", "SHSTK"]);
+
+        let mut desc = Vec::new();
+        desc.extend(GNU_PROPERTY_AARCH64_FEATURE_1_AND.to_be_bytes());
+        desc.extend(4u32.to_be_bytes());
+        desc.extend(1u32.to_be_bytes());
+        assert_eq!(cfi_properties(&desc, false, 4), vec!["BTI"]);
+    }
+}
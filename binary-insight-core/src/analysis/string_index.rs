@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+/// Size of one serialized entry: little-endian `u64` offset and `u32` length.
+pub const RECORD_SIZE: usize = 12;
+
+/// File offsets of extracted strings, parallel to `BinaryInfo::strings` and
+/// sorted by offset, so lookups by position are binary searches.
+#[derive(Debug, Clone, Default)]
+pub struct StringIndex {
+    entries: Vec<(u64, u32)>,
+}
+
+impl StringIndex {
+    /// Entries must be pushed in increasing offset order.
+    pub fn push(&mut self, offset: u64, len: u32) {
+        debug_assert!(self.entries.last().is_none_or(|&(last, _)| last < offset));
+        self.entries.push((offset, len));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// File offset and length of the `i`-th string.
+    pub fn get(&self, i: usize) -> Option<(u64, u32)> {
+        self.entries.get(i).copied()
+    }
+
+    /// Index of the string whose bytes cover `offset`.
+    pub fn find(&self, offset: u64) -> Option<usize> {
+        let i = self.entries.partition_point(|&(start, _)| start <= offset);
+        let (start, len) = self.entries.get(i.checked_sub(1)?)?;
+        (offset < start + *len as u64).then_some(i - 1)
+    }
+
+    /// Indices of the strings starting within `range`.
+    pub fn starting_in(&self, range: Range<u64>) -> Range<usize> {
+        let lo = self
+            .entries
+            .partition_point(|&(start, _)| start < range.start);
+        let hi = self
+            .entries
+            .partition_point(|&(start, _)| start < range.end);
+        lo..hi.max(lo)
+    }
+
+    /// Serializes to fixed-size records that [`find_in_records`] can search
+    /// in place, e.g. from a memory-mapped file.
+    pub fn to_records(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.entries.len() * RECORD_SIZE);
+        for &(offset, len) in &self.entries {
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&len.to_le_bytes());
+        }
+        out
+    }
+}
+
+fn record(records: &[u8], i: usize) -> (u64, u32) {
+    let r = &records[i * RECORD_SIZE..(i + 1) * RECORD_SIZE];
+    (
+        u64::from_le_bytes(r[..8].try_into().unwrap()),
+        u32::from_le_bytes(r[8..].try_into().unwrap()),
+    )
+}
+
+/// [`StringIndex::find`] over serialized records without deserializing them.
+pub fn find_in_records(records: &[u8], offset: u64) -> Option<usize> {
+    let count = records.len() / RECORD_SIZE;
+    let (mut lo, mut hi) = (0, count);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if record(records, mid).0 <= offset {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    let (start, len) = record(records, lo.checked_sub(1)?);
+    (offset < start + len as u64).then_some(lo - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::extract_strings_indexed;
+
+    #[test]
+    fn test_lookup_by_offset() {
+        let data = b"\x00\x00Hello World\x00\x01TestString\x00abc\x00";
+        let (strings, index) = extract_strings_indexed(data);
+        assert_eq!(strings, vec!["Hello World", "TestString"]);
+        assert_eq!(index.get(0), Some((2, 11)));
+        assert_eq!(index.find(2), Some(0));
+        assert_eq!(index.find(12), Some(0));
+        assert_eq!(index.find(13), None);
+        assert_eq!(index.find(16), Some(1));
+        assert_eq!(index.find(0), None);
+        assert_eq!(index.starting_in(0..15), 0..1);
+        assert_eq!(index.starting_in(20..40), 2..2);
+
+        let records = index.to_records();
+        assert_eq!(records.len(), 2 * RECORD_SIZE);
+        for offset in 0..data.len() as u64 {
+            assert_eq!(find_in_records(&records, offset), index.find(offset));
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use super::xrefs::{XrefIndex, XrefKind};
+use crate::binary::BinaryFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringRefKind {
+    /// An instruction computes or loads the string's address.
+    Code,
+    /// A pointer-sized slot in a data section holds the string's address.
+    Pointer,
+}
+
+impl StringRefKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StringRefKind::Code => "code",
+            StringRefKind::Pointer => "pointer",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StringXref {
+    /// Index into `BinaryInfo::strings`.
+    pub string: usize,
+    /// Referencing instruction, or the pointer slot.
+    pub from: u64,
+    pub kind: StringRefKind,
+    /// Function the reference was made from; for pointer slots, the function
+    /// that references the slot, if any.
+    pub function: Option<u64>,
+}
+
+/// References to extracted strings, sorted by string index then source.
+pub fn find_string_xrefs(file: &BinaryFile, xrefs: &XrefIndex) -> Vec<StringXref> {
+    let info = &file.info;
+    let map = info.memory_map();
+    let index = &info.string_index;
+    // Printable runs inside code are instruction bytes, not strings.
+    let string_at = |addr: u64| {
+        let section = map.section_for_addr(addr)?;
+        if section.is_code() && section.name != "raw" {
+            return None;
+        }
+        index.find(map.addr_to_offset(addr)?)
+    };
+
+    let mut refs: Vec<StringXref> = xrefs
+        .iter()
+        .filter(|x| x.kind == XrefKind::Data)
+        .filter_map(|x| {
+            Some(StringXref {
+                string: string_at(x.to)?,
+                from: x.from,
+                kind: StringRefKind::Code,
+                function: Some(x.function),
+            })
+        })
+        .collect();
+
+    // Pointer tables (argv-style arrays, message tables) only count when the
+    // slot points at the very start of a string. Only data sections are
+    // scanned (`.data`, `.rdata`, `.data.rel.ro`, `__const`); raw blobs mix
+    // code and data, so their single section is scanned too.
+    let arch = info.architecture();
+    if let Some(bits) = arch.bits() {
+        let width = bits as usize / 8;
+        let is_data = |name: &str| name.contains("data") || name.contains("const") || name == "raw";
+        for section in map.sections().filter(|s| is_data(&s.name)) {
+            let data = file.section_data(section);
+            for (i, slot) in data.chunks_exact(width).enumerate() {
+                let value = match (width, arch.big_endian()) {
+                    (4, false) => u32::from_le_bytes(slot.try_into().unwrap()) as u64,
+                    (4, true) => u32::from_be_bytes(slot.try_into().unwrap()) as u64,
+                    (_, false) => u64::from_le_bytes(slot.try_into().unwrap()),
+                    (_, true) => u64::from_be_bytes(slot.try_into().unwrap()),
+                };
+                let addr = match value.checked_sub(info.image_base) {
+                    Some(rva) if info.image_base != 0 => rva,
+                    _ => value,
+                };
+                let Some(string) = string_at(addr) else {
+                    continue;
+                };
+                if index.get(string).map(|(start, _)| start) != map.addr_to_offset(addr) {
+                    continue;
+                }
+                let from = section.addr + (i * width) as u64;
+                refs.push(StringXref {
+                    string,
+                    from,
+                    kind: StringRefKind::Pointer,
+                    function: xrefs.to(from).first().map(|x| x.function),
+                });
+            }
+        }
+    }
+
+    refs.sort_by_key(|r| (r.string, r.from));
+    refs.dedup_by_key(|r| (r.string, r.from));
+    refs
+}
+
+/// The entries of `refs` (as returned by [`find_string_xrefs`]) for `string`.
+pub fn refs_to(refs: &[StringXref], string: usize) -> &[StringXref] {
+    let lo = refs.partition_point(|r| r.string < string);
+    let hi = refs.partition_point(|r| r.string <= string);
+    &refs[lo..hi]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lea_and_pointer_to_string() {
+        // 0x1000: lea rax, [rip + 0x9]   -> 0x1010
+        // 0x1007: ret
+        // 0x1008: dq 0x1010
+        // 0x1010: "cmd.exe /c\0"
+        let mut code = vec![0x48, 0x8d, 0x05, 0x09, 0x00, 0x00, 0x00, 0xc3];
+        code.extend(0x1010u64.to_le_bytes());
+        code.extend(b"cmd.exe /c\x00");
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        file.discover_functions();
+        let xrefs = file.xrefs();
+        let refs = find_string_xrefs(&file, &xrefs);
+
+        assert_eq!(file.info.strings[0], "cmd.exe /c");
+        let found: Vec<(u64, StringRefKind)> =
+            refs_to(&refs, 0).iter().map(|r| (r.from, r.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (0x1000, StringRefKind::Code),
+                (0x1008, StringRefKind::Pointer)
+            ]
+        );
+        assert_eq!(refs[0].function, Some(0x1000));
+    }
+}
@@ -0,0 +1,782 @@
+use super::cfg::{self, Flow};
+use super::disassembly::{self, InstructionInfo};
+use super::xrefs::parse_imm;
+use crate::binary::BinaryFile;
+use std::collections::BTreeMap;
+
+/// A system call instruction (`syscall`, `int 0x80`, `svc`, `ecall`, ...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Syscall {
+    pub addr: u64,
+    /// Call number, when a constant is loaded into the number register
+    /// earlier in the same straight-line run of code.
+    pub number: Option<u64>,
+    /// Linux or macOS name of `number`, where the table for the ABI is known.
+    pub name: Option<&'static str>,
+}
+
+impl Syscall {
+    /// The name, else `#<number>`, else `unknown`.
+    pub fn label(&self) -> String {
+        match (self.name, self.number) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(number)) => format!("#{}", number),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+}
+
+/// How one kind of system call instruction passes the call number.
+struct Convention {
+    /// Names of the number register, all widths.
+    registers: &'static [&'static str],
+    /// Number to name, sorted by number; `None` where we carry no table.
+    table: Option<&'static [(u64, &'static str)]>,
+    /// macOS x86_64 tags BSD calls with class 2 in the top byte.
+    macos_class: bool,
+}
+
+impl Convention {
+    const fn new(
+        registers: &'static [&'static str],
+        table: &'static [(u64, &'static str)],
+    ) -> Self {
+        Self {
+            registers,
+            table: Some(table),
+            macos_class: false,
+        }
+    }
+
+    const fn numbers_only(registers: &'static [&'static str]) -> Self {
+        Self {
+            registers,
+            table: None,
+            macos_class: false,
+        }
+    }
+
+    fn name(&self, number: u64) -> Option<&'static str> {
+        let number = match self.macos_class {
+            true if number >> 24 == 2 => number & 0xff_ffff,
+            true => return None,
+            false => number,
+        };
+        let table = self.table?;
+        let i = table.binary_search_by_key(&number, |&(n, _)| n).ok()?;
+        Some(table[i].1)
+    }
+}
+
+/// Instructions looked back through for the number register's value.
+pub(crate) const BACKTRACK: usize = 16;
+
+const X86_64_REGS: &[&str] = &["rax", "eax", "ax", "al"];
+
+/// The convention of `ins` if it is a system call. Mach-O code follows the
+/// macOS ABI; everything else (ELF, raw shellcode) is taken to be Linux.
+/// Windows system call numbers change between builds, so PE is skipped.
+fn convention(format: &str, arch: &str, ins: &InstructionInfo) -> Option<Convention> {
+    let m = ins.mnemonic.as_str();
+    let int80 = m == "int" && ins.op_str == "0x80";
+    if format.starts_with("Mach-O") {
+        return match (arch, m) {
+            ("x86_64", "syscall") => Some(Convention {
+                macos_class: true,
+                ..Convention::new(X86_64_REGS, MACOS_BSD)
+            }),
+            ("aarch64", "svc") => Some(Convention::new(&["x16", "w16"], MACOS_BSD)),
+            _ => None,
+        };
+    }
+    if format == "PE" || format.starts_with("TE") {
+        return None;
+    }
+    match arch {
+        "x86_64" if m == "syscall" => Some(Convention::new(X86_64_REGS, LINUX_X86_64)),
+        // The 32-bit entry point stays reachable from 64-bit code.
+        "x86_64" if int80 => Some(Convention::new(X86_64_REGS, LINUX_I386)),
+        "x86" if int80 || m == "sysenter" => {
+            Some(Convention::new(&["eax", "ax", "al"], LINUX_I386))
+        }
+        "aarch64" if m == "svc" => Some(Convention::new(&["x8", "w8"], LINUX_GENERIC)),
+        "riscv32" | "riscv64" if m == "ecall" => Some(Convention::new(&["a7"], LINUX_GENERIC)),
+        "arm" | "thumb" if m == "svc" || m == "swi" => Some(Convention::numbers_only(&["r7"])),
+        _ if arch.starts_with("mips") && m == "syscall" => {
+            Some(Convention::numbers_only(&["$v0", "v0"]))
+        }
+        _ if arch.starts_with("ppc") && m == "sc" => Some(Convention::numbers_only(&["r0"])),
+        _ if arch.starts_with("sparc") && m == "ta" => Some(Convention::numbers_only(&["%g1"])),
+        _ => None,
+    }
+}
+
+/// Registers that always read as zero.
+const ZERO_REGS: &[&str] = &["wzr", "xzr", "zero", "$zero", "%g0"];
+
+/// The constant `registers` hold after `history`, if the last write to them
+/// loads one.
+pub(crate) fn constant_in(
+    arch: &str,
+    registers: &[&str],
+    history: &[InstructionInfo],
+) -> Option<u64> {
+    for ins in history.iter().rev() {
+        let ops: Vec<&str> = ins.op_str.split(',').map(str::trim).collect();
+        // SPARC writes its last operand; everything else its first.
+        let (dest, sources) = if arch.starts_with("sparc") {
+            let (dest, sources) = ops.split_last()?;
+            (*dest, sources)
+        } else {
+            let (dest, sources) = ops.split_first()?;
+            (*dest, sources)
+        };
+        if !registers.contains(&dest) {
+            continue;
+        }
+        let m = ins.mnemonic.as_str();
+        return match sources {
+            // xor eax, eax
+            [src] if m == "xor" && *src == dest => Some(0),
+            [src] if ZERO_REGS.contains(src) && m.starts_with("mov") => Some(0),
+            [src] if m.starts_with("mov") || m == "li" => parse_imm(src),
+            // addiu $v0, $zero, 0xfa1 / or %g0, 1, %g1
+            [zero, imm] if ZERO_REGS.contains(zero) => parse_imm(imm),
+            [imm, zero] if *zero == "%g0" => parse_imm(imm),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// Finds system calls in code sections with a linear sweep, recovering
+/// their numbers where they are set just before the call.
+pub fn find_syscalls(file: &BinaryFile) -> Vec<Syscall> {
+    let info = &file.info;
+    let mut calls = Vec::new();
+    for section in info.sections.iter().filter(|s| s.is_code()) {
+        let arch = info.arch_at(section.addr);
+        let mut history: Vec<InstructionInfo> = Vec::new();
+        let _ = disassembly::linear_sweep(arch, file.section_data(section), section.addr, |ins| {
+            if let Some(convention) = convention(&info.format, arch, &ins) {
+                let number = constant_in(arch, convention.registers, &history);
+                calls.push(Syscall {
+                    addr: ins.address,
+                    number,
+                    name: number.and_then(|n| convention.name(n)),
+                });
+            }
+            // Register values do not survive control flow.
+            if cfg::classify(arch, &ins) == Flow::Normal {
+                if history.len() == BACKTRACK {
+                    history.remove(0);
+                }
+                history.push(ins);
+            } else {
+                history.clear();
+            }
+            Ok(())
+        });
+    }
+    calls
+}
+
+/// Call sites per label, for a "syscalls used" summary.
+pub fn summary(calls: &[Syscall]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for call in calls {
+        *counts.entry(call.label()).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Tables cover the calls worth flagging during triage, not every number.
+// Linux numbers come from the kernel's unistd headers.
+
+const LINUX_X86_64: &[(u64, &str)] = &[
+    (0, "read"),
+    (1, "write"),
+    (2, "open"),
+    (3, "close"),
+    (4, "stat"),
+    (5, "fstat"),
+    (6, "lstat"),
+    (7, "poll"),
+    (8, "lseek"),
+    (9, "mmap"),
+    (10, "mprotect"),
+    (11, "munmap"),
+    (12, "brk"),
+    (13, "rt_sigaction"),
+    (14, "rt_sigprocmask"),
+    (15, "rt_sigreturn"),
+    (16, "ioctl"),
+    (17, "pread64"),
+    (18, "pwrite64"),
+    (19, "readv"),
+    (20, "writev"),
+    (21, "access"),
+    (22, "pipe"),
+    (23, "select"),
+    (24, "sched_yield"),
+    (25, "mremap"),
+    (28, "madvise"),
+    (29, "shmget"),
+    (30, "shmat"),
+    (32, "dup"),
+    (33, "dup2"),
+    (35, "nanosleep"),
+    (39, "getpid"),
+    (40, "sendfile"),
+    (41, "socket"),
+    (42, "connect"),
+    (43, "accept"),
+    (44, "sendto"),
+    (45, "recvfrom"),
+    (46, "sendmsg"),
+    (47, "recvmsg"),
+    (48, "shutdown"),
+    (49, "bind"),
+    (50, "listen"),
+    (51, "getsockname"),
+    (52, "getpeername"),
+    (53, "socketpair"),
+    (54, "setsockopt"),
+    (55, "getsockopt"),
+    (56, "clone"),
+    (57, "fork"),
+    (58, "vfork"),
+    (59, "execve"),
+    (60, "exit"),
+    (61, "wait4"),
+    (62, "kill"),
+    (63, "uname"),
+    (72, "fcntl"),
+    (73, "flock"),
+    (74, "fsync"),
+    (76, "truncate"),
+    (77, "ftruncate"),
+    (78, "getdents"),
+    (79, "getcwd"),
+    (80, "chdir"),
+    (81, "fchdir"),
+    (82, "rename"),
+    (83, "mkdir"),
+    (84, "rmdir"),
+    (85, "creat"),
+    (86, "link"),
+    (87, "unlink"),
+    (88, "symlink"),
+    (89, "readlink"),
+    (90, "chmod"),
+    (91, "fchmod"),
+    (92, "chown"),
+    (93, "fchown"),
+    (95, "umask"),
+    (96, "gettimeofday"),
+    (101, "ptrace"),
+    (102, "getuid"),
+    (103, "syslog"),
+    (104, "getgid"),
+    (105, "setuid"),
+    (106, "setgid"),
+    (107, "geteuid"),
+    (108, "getegid"),
+    (110, "getppid"),
+    (113, "setreuid"),
+    (114, "setregid"),
+    (117, "setresuid"),
+    (119, "setresgid"),
+    (125, "capget"),
+    (126, "capset"),
+    (131, "sigaltstack"),
+    (135, "personality"),
+    (155, "pivot_root"),
+    (157, "prctl"),
+    (158, "arch_prctl"),
+    (161, "chroot"),
+    (164, "settimeofday"),
+    (165, "mount"),
+    (166, "umount2"),
+    (169, "reboot"),
+    (170, "sethostname"),
+    (175, "init_module"),
+    (176, "delete_module"),
+    (186, "gettid"),
+    (200, "tkill"),
+    (202, "futex"),
+    (217, "getdents64"),
+    (218, "set_tid_address"),
+    (228, "clock_gettime"),
+    (230, "clock_nanosleep"),
+    (231, "exit_group"),
+    (234, "tgkill"),
+    (246, "kexec_load"),
+    (247, "waitid"),
+    (248, "add_key"),
+    (250, "keyctl"),
+    (254, "inotify_add_watch"),
+    (257, "openat"),
+    (258, "mkdirat"),
+    (260, "fchownat"),
+    (262, "newfstatat"),
+    (263, "unlinkat"),
+    (264, "renameat"),
+    (265, "linkat"),
+    (266, "symlinkat"),
+    (267, "readlinkat"),
+    (268, "fchmodat"),
+    (269, "faccessat"),
+    (270, "pselect6"),
+    (271, "ppoll"),
+    (272, "unshare"),
+    (273, "set_robust_list"),
+    (288, "accept4"),
+    (292, "dup3"),
+    (293, "pipe2"),
+    (294, "inotify_init1"),
+    (298, "perf_event_open"),
+    (302, "prlimit64"),
+    (308, "setns"),
+    (310, "process_vm_readv"),
+    (311, "process_vm_writev"),
+    (313, "finit_module"),
+    (316, "renameat2"),
+    (317, "seccomp"),
+    (318, "getrandom"),
+    (319, "memfd_create"),
+    (320, "kexec_file_load"),
+    (321, "bpf"),
+    (322, "execveat"),
+    (323, "userfaultfd"),
+    (332, "statx"),
+    (425, "io_uring_setup"),
+    (426, "io_uring_enter"),
+    (435, "clone3"),
+    (437, "openat2"),
+];
+
+const LINUX_I386: &[(u64, &str)] = &[
+    (1, "exit"),
+    (2, "fork"),
+    (3, "read"),
+    (4, "write"),
+    (5, "open"),
+    (6, "close"),
+    (8, "creat"),
+    (9, "link"),
+    (10, "unlink"),
+    (11, "execve"),
+    (12, "chdir"),
+    (15, "chmod"),
+    (19, "lseek"),
+    (20, "getpid"),
+    (21, "mount"),
+    (23, "setuid"),
+    (24, "getuid"),
+    (26, "ptrace"),
+    (33, "access"),
+    (37, "kill"),
+    (38, "rename"),
+    (39, "mkdir"),
+    (40, "rmdir"),
+    (41, "dup"),
+    (42, "pipe"),
+    (45, "brk"),
+    (46, "setgid"),
+    (47, "getgid"),
+    (49, "geteuid"),
+    (50, "getegid"),
+    (52, "umount2"),
+    (54, "ioctl"),
+    (55, "fcntl"),
+    (60, "umask"),
+    (61, "chroot"),
+    (63, "dup2"),
+    (64, "getppid"),
+    (70, "setreuid"),
+    (71, "setregid"),
+    (74, "sethostname"),
+    (78, "gettimeofday"),
+    (79, "settimeofday"),
+    (82, "select"),
+    (83, "symlink"),
+    (85, "readlink"),
+    (88, "reboot"),
+    (90, "mmap"),
+    (91, "munmap"),
+    (92, "truncate"),
+    (93, "ftruncate"),
+    (94, "fchmod"),
+    (95, "fchown"),
+    (102, "socketcall"),
+    (103, "syslog"),
+    (106, "stat"),
+    (107, "lstat"),
+    (108, "fstat"),
+    (114, "wait4"),
+    (118, "fsync"),
+    (120, "clone"),
+    (122, "uname"),
+    (125, "mprotect"),
+    (128, "init_module"),
+    (129, "delete_module"),
+    (133, "fchdir"),
+    (136, "personality"),
+    (140, "_llseek"),
+    (141, "getdents"),
+    (143, "flock"),
+    (145, "readv"),
+    (146, "writev"),
+    (158, "sched_yield"),
+    (162, "nanosleep"),
+    (163, "mremap"),
+    (164, "setresuid"),
+    (168, "poll"),
+    (170, "setresgid"),
+    (172, "prctl"),
+    (173, "rt_sigreturn"),
+    (174, "rt_sigaction"),
+    (175, "rt_sigprocmask"),
+    (180, "pread64"),
+    (181, "pwrite64"),
+    (182, "chown"),
+    (183, "getcwd"),
+    (184, "capget"),
+    (185, "capset"),
+    (186, "sigaltstack"),
+    (187, "sendfile"),
+    (190, "vfork"),
+    (192, "mmap2"),
+    (195, "stat64"),
+    (197, "fstat64"),
+    (217, "pivot_root"),
+    (219, "madvise"),
+    (220, "getdents64"),
+    (224, "gettid"),
+    (238, "tkill"),
+    (240, "futex"),
+    (252, "exit_group"),
+    (258, "set_tid_address"),
+    (265, "clock_gettime"),
+    (267, "clock_nanosleep"),
+    (270, "tgkill"),
+    (283, "kexec_load"),
+    (284, "waitid"),
+    (286, "add_key"),
+    (288, "keyctl"),
+    (292, "inotify_add_watch"),
+    (295, "openat"),
+    (296, "mkdirat"),
+    (298, "fchownat"),
+    (300, "fstatat64"),
+    (301, "unlinkat"),
+    (302, "renameat"),
+    (303, "linkat"),
+    (304, "symlinkat"),
+    (305, "readlinkat"),
+    (306, "fchmodat"),
+    (307, "faccessat"),
+    (308, "pselect6"),
+    (309, "ppoll"),
+    (310, "unshare"),
+    (311, "set_robust_list"),
+    (330, "dup3"),
+    (331, "pipe2"),
+    (332, "inotify_init1"),
+    (336, "perf_event_open"),
+    (340, "prlimit64"),
+    (346, "setns"),
+    (347, "process_vm_readv"),
+    (348, "process_vm_writev"),
+    (350, "finit_module"),
+    (353, "renameat2"),
+    (354, "seccomp"),
+    (355, "getrandom"),
+    (356, "memfd_create"),
+    (357, "bpf"),
+    (358, "execveat"),
+    (359, "socket"),
+    (360, "socketpair"),
+    (361, "bind"),
+    (362, "connect"),
+    (363, "listen"),
+    (364, "accept4"),
+    (365, "getsockopt"),
+    (366, "setsockopt"),
+    (367, "getsockname"),
+    (368, "getpeername"),
+    (369, "sendto"),
+    (370, "sendmsg"),
+    (371, "recvfrom"),
+    (372, "recvmsg"),
+    (373, "shutdown"),
+    (374, "userfaultfd"),
+    (383, "statx"),
+    (384, "arch_prctl"),
+    (395, "shmget"),
+    (397, "shmat"),
+    (425, "io_uring_setup"),
+    (426, "io_uring_enter"),
+    (435, "clone3"),
+    (437, "openat2"),
+];
+
+const LINUX_GENERIC: &[(u64, &str)] = &[
+    (17, "getcwd"),
+    (23, "dup"),
+    (24, "dup3"),
+    (25, "fcntl"),
+    (26, "inotify_init1"),
+    (27, "inotify_add_watch"),
+    (29, "ioctl"),
+    (32, "flock"),
+    (34, "mkdirat"),
+    (35, "unlinkat"),
+    (36, "symlinkat"),
+    (37, "linkat"),
+    (38, "renameat"),
+    (39, "umount2"),
+    (40, "mount"),
+    (41, "pivot_root"),
+    (45, "truncate"),
+    (46, "ftruncate"),
+    (48, "faccessat"),
+    (49, "chdir"),
+    (50, "fchdir"),
+    (51, "chroot"),
+    (52, "fchmod"),
+    (53, "fchmodat"),
+    (54, "fchownat"),
+    (55, "fchown"),
+    (56, "openat"),
+    (57, "close"),
+    (59, "pipe2"),
+    (61, "getdents64"),
+    (62, "lseek"),
+    (63, "read"),
+    (64, "write"),
+    (65, "readv"),
+    (66, "writev"),
+    (67, "pread64"),
+    (68, "pwrite64"),
+    (71, "sendfile"),
+    (72, "pselect6"),
+    (73, "ppoll"),
+    (78, "readlinkat"),
+    (79, "newfstatat"),
+    (80, "fstat"),
+    (82, "fsync"),
+    (90, "capget"),
+    (91, "capset"),
+    (92, "personality"),
+    (93, "exit"),
+    (94, "exit_group"),
+    (95, "waitid"),
+    (96, "set_tid_address"),
+    (97, "unshare"),
+    (98, "futex"),
+    (99, "set_robust_list"),
+    (101, "nanosleep"),
+    (104, "kexec_load"),
+    (105, "init_module"),
+    (106, "delete_module"),
+    (113, "clock_gettime"),
+    (115, "clock_nanosleep"),
+    (116, "syslog"),
+    (117, "ptrace"),
+    (124, "sched_yield"),
+    (129, "kill"),
+    (130, "tkill"),
+    (131, "tgkill"),
+    (132, "sigaltstack"),
+    (134, "rt_sigaction"),
+    (135, "rt_sigprocmask"),
+    (139, "rt_sigreturn"),
+    (142, "reboot"),
+    (143, "setregid"),
+    (144, "setgid"),
+    (145, "setreuid"),
+    (146, "setuid"),
+    (147, "setresuid"),
+    (149, "setresgid"),
+    (160, "uname"),
+    (161, "sethostname"),
+    (166, "umask"),
+    (167, "prctl"),
+    (169, "gettimeofday"),
+    (170, "settimeofday"),
+    (172, "getpid"),
+    (173, "getppid"),
+    (174, "getuid"),
+    (175, "geteuid"),
+    (176, "getgid"),
+    (177, "getegid"),
+    (178, "gettid"),
+    (194, "shmget"),
+    (196, "shmat"),
+    (198, "socket"),
+    (199, "socketpair"),
+    (200, "bind"),
+    (201, "listen"),
+    (202, "accept"),
+    (203, "connect"),
+    (204, "getsockname"),
+    (205, "getpeername"),
+    (206, "sendto"),
+    (207, "recvfrom"),
+    (208, "setsockopt"),
+    (209, "getsockopt"),
+    (210, "shutdown"),
+    (211, "sendmsg"),
+    (212, "recvmsg"),
+    (214, "brk"),
+    (215, "munmap"),
+    (216, "mremap"),
+    (217, "add_key"),
+    (219, "keyctl"),
+    (220, "clone"),
+    (221, "execve"),
+    (222, "mmap"),
+    (226, "mprotect"),
+    (233, "madvise"),
+    (241, "perf_event_open"),
+    (242, "accept4"),
+    (260, "wait4"),
+    (261, "prlimit64"),
+    (268, "setns"),
+    (270, "process_vm_readv"),
+    (271, "process_vm_writev"),
+    (273, "finit_module"),
+    (276, "renameat2"),
+    (277, "seccomp"),
+    (278, "getrandom"),
+    (279, "memfd_create"),
+    (280, "bpf"),
+    (281, "execveat"),
+    (282, "userfaultfd"),
+    (291, "statx"),
+    (294, "kexec_file_load"),
+    (425, "io_uring_setup"),
+    (426, "io_uring_enter"),
+    (435, "clone3"),
+    (437, "openat2"),
+];
+
+const MACOS_BSD: &[(u64, &str)] = &[
+    (1, "exit"),
+    (2, "fork"),
+    (3, "read"),
+    (4, "write"),
+    (5, "open"),
+    (6, "close"),
+    (7, "wait4"),
+    (9, "link"),
+    (10, "unlink"),
+    (12, "chdir"),
+    (13, "fchdir"),
+    (15, "chmod"),
+    (16, "chown"),
+    (20, "getpid"),
+    (23, "setuid"),
+    (24, "getuid"),
+    (25, "geteuid"),
+    (26, "ptrace"),
+    (27, "recvmsg"),
+    (28, "sendmsg"),
+    (29, "recvfrom"),
+    (30, "accept"),
+    (31, "getpeername"),
+    (32, "getsockname"),
+    (33, "access"),
+    (37, "kill"),
+    (39, "getppid"),
+    (41, "dup"),
+    (42, "pipe"),
+    (43, "getegid"),
+    (46, "sigaction"),
+    (47, "getgid"),
+    (48, "sigprocmask"),
+    (54, "ioctl"),
+    (57, "symlink"),
+    (58, "readlink"),
+    (59, "execve"),
+    (60, "umask"),
+    (61, "chroot"),
+    (66, "vfork"),
+    (73, "munmap"),
+    (74, "mprotect"),
+    (75, "madvise"),
+    (90, "dup2"),
+    (92, "fcntl"),
+    (93, "select"),
+    (95, "fsync"),
+    (97, "socket"),
+    (98, "connect"),
+    (104, "bind"),
+    (105, "setsockopt"),
+    (106, "listen"),
+    (116, "gettimeofday"),
+    (118, "getsockopt"),
+    (120, "readv"),
+    (121, "writev"),
+    (128, "rename"),
+    (131, "flock"),
+    (133, "sendto"),
+    (134, "shutdown"),
+    (135, "socketpair"),
+    (136, "mkdir"),
+    (137, "rmdir"),
+    (181, "setgid"),
+    (197, "mmap"),
+    (199, "lseek"),
+    (200, "truncate"),
+    (201, "ftruncate"),
+    (202, "sysctl"),
+    (244, "posix_spawn"),
+    (338, "stat64"),
+    (339, "fstat64"),
+    (340, "lstat64"),
+    (344, "getdirentries64"),
+    (463, "openat"),
+    (500, "getentropy"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linux_x86_64_syscalls() {
+        // mov eax, 0x3b; syscall; xor eax, eax; syscall; call rbx; syscall
+        let mut code = vec![0xb8, 0x3b, 0x00, 0x00, 0x00, 0x0f, 0x05];
+        code.extend([0x31, 0xc0, 0x0f, 0x05, 0xff, 0xd3, 0x0f, 0x05]);
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        file.info.format = "ELF".to_string();
+        let calls = find_syscalls(&file);
+        let found: Vec<_> = calls.iter().map(|c| (c.addr, c.label())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (0x1005, "execve".to_string()),
+                (0x1009, "read".to_string()),
+                (0x100d, "unknown".to_string()),
+            ]
+        );
+        assert_eq!(summary(&calls).get("execve"), Some(&1));
+    }
+
+    #[test]
+    fn test_aarch64_and_macos_syscalls() {
+        // mov x8, #0x5d; svc #0
+        let code = vec![0xa8, 0x0b, 0x80, 0xd2, 0x01, 0x00, 0x00, 0xd4];
+        let file = BinaryFile::from_raw("t".to_string(), code, "aarch64", 0);
+        assert_eq!(find_syscalls(&file)[0].name, Some("exit"));
+
+        // mov eax, 0x2000004; syscall
+        let code = vec![0xb8, 0x04, 0x00, 0x00, 0x02, 0x0f, 0x05];
+        let mut file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0);
+        file.info.format = "Mach-O".to_string();
+        assert_eq!(find_syscalls(&file)[0].name, Some("write"));
+    }
+}
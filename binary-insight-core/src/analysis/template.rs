@@ -0,0 +1,269 @@
+//! Report templates in a small Tera/Jinja-like syntax: `{{ expr }}` prints
+//! an [`expr`] expression, `{% if expr %}`, `{% else %}`, `{% endif %}`,
+//! `{% for x in expr %}` and `{% endfor %}` control the output, and
+//! `{# ... #}` is a comment. A tag alone on its line leaves no blank line.
+
+use super::expr::{self, Expr, Value};
+use crate::binary::BinaryFile;
+use crate::report::AnalysisReport;
+use anyhow::{anyhow, bail, Context, Result};
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Print(Expr),
+    If(Expr, Vec<Node>, Vec<Node>),
+    For(String, Expr, Vec<Node>),
+}
+
+/// A template parsed once and rendered against any number of contexts.
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// A `{% ... %}` or `{{ ... }}` tag, with the line it starts on.
+enum Piece<'a> {
+    Text(&'a str),
+    Print(&'a str, usize),
+    Tag(&'a str, usize),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self> {
+        let pieces = split(source)?;
+        let mut pos = 0;
+        let (nodes, end) = parse_nodes(&pieces, &mut pos)?;
+        if let Some((tag, line)) = end {
+            bail!("line {}: unexpected {{% {} %}}", line, tag);
+        }
+        Ok(Self { nodes })
+    }
+
+    pub fn render(&self, ctx: &Value) -> Result<String> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut ctx.clone(), &mut out)?;
+        Ok(out)
+    }
+}
+
+/// The serialized `report` of `binary`, plus the sections, symbols,
+/// imports, strings and security flags available to `[derived]` expressions.
+pub fn report_context(binary: &BinaryFile, report: &AnalysisReport) -> Result<Value> {
+    let report = serde_json::to_value(report)?;
+    let Value::Map(mut root) = Value::from(&report) else {
+        bail!("report did not serialize to a map");
+    };
+    if let Value::Map(extra) = expr::binary_context(binary) {
+        for (key, value) in extra {
+            root.entry(key).or_insert(value);
+        }
+    }
+    Ok(Value::Map(root))
+}
+
+fn split(source: &str) -> Result<Vec<Piece<'_>>> {
+    let mut pieces = Vec::new();
+    let mut rest = source;
+    let mut at_line_start = true;
+    let line_of = |rest: &str| source[..source.len() - rest.len()].matches('\n').count() + 1;
+    while let Some(start) = rest.find('{') {
+        let close = match rest[start..].get(..2) {
+            Some("{{") => "}}",
+            Some("{%") => "%}",
+            Some("{#") => "#}",
+            _ => {
+                pieces.push(Piece::Text(&rest[..start + 1]));
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        let line = line_of(&rest[start..]);
+        let end = rest[start + 2..]
+            .find(close)
+            .map(|i| start + 2 + i)
+            .ok_or_else(|| anyhow!("line {}: unclosed tag", line))?;
+        let inner = rest[start + 2..end].trim();
+        let mut text = &rest[..start];
+        let mut after = &rest[end + 2..];
+        // Drop the line of a block tag or comment that stands alone on it.
+        let line_start = text.rfind('\n').map(|i| i + 1);
+        let line_end = after.find('\n');
+        let alone = (line_start.is_some() || at_line_start)
+            && text[line_start.unwrap_or(0)..].trim().is_empty()
+            && after[..line_end.unwrap_or(after.len())].trim().is_empty();
+        at_line_start = close != "}}" && alone;
+        if at_line_start {
+            text = &text[..line_start.unwrap_or(0)];
+            after = line_end.map_or("", |i| &after[i + 1..]);
+        }
+        pieces.push(Piece::Text(text));
+        match close {
+            "}}" => pieces.push(Piece::Print(inner, line)),
+            "%}" => pieces.push(Piece::Tag(inner, line)),
+            _ => {}
+        }
+        rest = after;
+    }
+    pieces.push(Piece::Text(rest));
+    Ok(pieces)
+}
+
+/// A closing tag (`else`, `endif`, `endfor`) and its line.
+type EndTag<'a> = Option<(&'a str, usize)>;
+
+/// Parses nodes up to a closing tag, which is returned for the caller to
+/// check.
+fn parse_nodes<'a>(pieces: &[Piece<'a>], pos: &mut usize) -> Result<(Vec<Node>, EndTag<'a>)> {
+    let mut nodes = Vec::new();
+    while let Some(piece) = pieces.get(*pos) {
+        *pos += 1;
+        match *piece {
+            Piece::Text("") => {}
+            Piece::Text(text) => nodes.push(Node::Text(text.to_string())),
+            Piece::Print(source, line) => nodes.push(Node::Print(
+                expr::parse(source).with_context(|| format!("line {}", line))?,
+            )),
+            Piece::Tag(tag, line) => {
+                let (keyword, args) = tag.split_once(' ').unwrap_or((tag, ""));
+                match keyword {
+                    "if" => {
+                        let cond = expr::parse(args).with_context(|| format!("line {}", line))?;
+                        let (then, end) = parse_nodes(pieces, pos)?;
+                        let otherwise = match end {
+                            Some(("else", _)) => {
+                                let (otherwise, end) = parse_nodes(pieces, pos)?;
+                                expect_end(end, "endif", line)?;
+                                otherwise
+                            }
+                            end => {
+                                expect_end(end, "endif", line)?;
+                                Vec::new()
+                            }
+                        };
+                        nodes.push(Node::If(cond, then, otherwise));
+                    }
+                    "for" => {
+                        let (var, list) = args
+                            .split_once(" in ")
+                            .ok_or_else(|| anyhow!("line {}: expected `for x in list`", line))?;
+                        let list = expr::parse(list).with_context(|| format!("line {}", line))?;
+                        let (body, end) = parse_nodes(pieces, pos)?;
+                        expect_end(end, "endfor", line)?;
+                        nodes.push(Node::For(var.trim().to_string(), list, body));
+                    }
+                    "else" | "endif" | "endfor" => return Ok((nodes, Some((keyword, line)))),
+                    _ => bail!("line {}: unknown tag {{% {} %}}", line, keyword),
+                }
+            }
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn expect_end(end: EndTag, expected: &str, line: usize) -> Result<()> {
+    match end {
+        Some((tag, _)) if tag == expected => Ok(()),
+        Some((tag, at)) => bail!(
+            "line {}: expected {{% {} %}}, found {{% {} %}}",
+            at,
+            expected,
+            tag
+        ),
+        None => bail!("line {}: missing {{% {} %}}", line, expected),
+    }
+}
+
+fn render_nodes(nodes: &[Node], ctx: &mut Value, out: &mut String) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Print(expr) => match expr::evaluate(expr, ctx)? {
+                Value::Null => {}
+                value => out.push_str(&value.to_string()),
+            },
+            Node::If(cond, then, otherwise) => {
+                let branch = if expr::evaluate(cond, ctx)?.truthy() {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, ctx, out)?;
+            }
+            Node::For(var, list, body) => {
+                let Value::List(items) = expr::evaluate(list, ctx)? else {
+                    continue;
+                };
+                // Bind the loop variable in place, restoring any shadowed value.
+                let mut shadowed = None;
+                for item in items {
+                    let Value::Map(scope) = &mut *ctx else {
+                        bail!("template context is not a map");
+                    };
+                    let previous = scope.insert(var.clone(), item);
+                    shadowed = shadowed.or(Some(previous));
+                    render_nodes(body, ctx, out)?;
+                }
+                if let (Some(previous), Value::Map(scope)) = (shadowed, &mut *ctx) {
+                    match previous {
+                        Some(value) => scope.insert(var.clone(), value),
+                        None => scope.remove(var),
+                    };
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn ctx() -> Value {
+        let finding = |id: &str, severity: &str| {
+            Value::Map(BTreeMap::from([
+                ("id".to_string(), Value::Str(id.to_string())),
+                ("severity".to_string(), Value::Str(severity.to_string())),
+            ]))
+        };
+        Value::Map(BTreeMap::from([
+            ("file".to_string(), Value::Str("app".to_string())),
+            ("entry_point".to_string(), Value::Num(4096.0)),
+            (
+                "findings".to_string(),
+                Value::List(vec![finding("yara.upx", "high"), finding("anomaly", "low")]),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "\
+# {{ file }} @ {{ hex(entry_point) }}
+{# one line per finding #}
+{% for f in findings %}
+- {{ f.id }}{% if f.severity == \"high\" %} (!){% endif %}
+{% endfor %}
+{% if len(findings) == 0 %}
+clean
+{% else %}
+{{ len(findings) }} findings
+{% endif %}
+";
+        let text = Template::parse(source).unwrap().render(&ctx()).unwrap();
+        assert_eq!(
+            text,
+            "# app @ 0x1000\n- yara.upx (!)\n- anomaly\n2 findings\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Template::parse("{% if x %}open").is_err());
+        assert!(Template::parse("{{ unclosed").is_err());
+        let err = Template::parse("a\n{% endfor %}").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+}
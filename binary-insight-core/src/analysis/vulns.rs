@@ -0,0 +1,360 @@
+//! Known vulnerabilities of the components found by [`sbom::components`],
+//! looked up in a local dataset of OSV advisories (one JSON record per
+//! file, or a JSON array of them).
+
+use super::findings::Severity;
+use super::sbom::{self, Component};
+use crate::binary::BinaryInfo;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+/// The subset of the OSV schema needed to match versions.
+#[derive(Debug, Deserialize)]
+struct Advisory {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    affected: Vec<Affected>,
+    #[serde(default)]
+    database_specific: Option<DatabaseSpecific>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Affected {
+    package: Package,
+    #[serde(default)]
+    ranges: Vec<Range>,
+    /// Explicitly listed affected versions.
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Range {
+    #[serde(rename = "type")]
+    kind: String,
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    introduced: Option<String>,
+    fixed: Option<String>,
+    last_affected: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatabaseSpecific {
+    severity: Option<String>,
+}
+
+/// A vulnerability affecting a component at the version found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Vulnerability {
+    /// Advisory id, e.g. `CVE-2016-2107` or `GHSA-...`.
+    pub id: String,
+    pub aliases: Vec<String>,
+    pub summary: String,
+    pub component: String,
+    pub version: String,
+    pub severity: Severity,
+    /// First version with the fix, if the advisory names one.
+    pub fixed: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct VulnDatabase {
+    advisories: Vec<Advisory>,
+}
+
+impl VulnDatabase {
+    /// Loads a JSON file, or every `.json` file of a directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let files = if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut db = VulnDatabase::default();
+        for file in files {
+            let text = fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read advisory {}", file.display()))?;
+            db.extend_json(&text)
+                .with_context(|| format!("Invalid advisory {}", file.display()))?;
+        }
+        Ok(db)
+    }
+
+    /// Adds one OSV record or an array of them.
+    pub fn extend_json(&mut self, text: &str) -> Result<()> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        match value {
+            serde_json::Value::Array(records) => {
+                for record in records {
+                    self.advisories.push(serde_json::from_value(record)?);
+                }
+            }
+            record => self.advisories.push(serde_json::from_value(record)?),
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.advisories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.advisories.is_empty()
+    }
+
+    /// Advisories affecting the versioned `components`; package names are
+    /// compared case-insensitively.
+    pub fn lookup(&self, components: &[Component]) -> Vec<Vulnerability> {
+        let mut found = Vec::new();
+        for component in components {
+            let Some(version) = &component.version else {
+                continue;
+            };
+            for advisory in &self.advisories {
+                let hit = advisory.affected.iter().find_map(|affected| {
+                    if !affected.package.name.eq_ignore_ascii_case(&component.name) {
+                        return None;
+                    }
+                    affects(affected, version)
+                });
+                let Some(fixed) = hit else {
+                    continue;
+                };
+                let severity = advisory
+                    .database_specific
+                    .as_ref()
+                    .and_then(|d| d.severity.as_deref())
+                    .map_or(Severity::Medium, severity_from_name);
+                found.push(Vulnerability {
+                    id: advisory.id.clone(),
+                    aliases: advisory.aliases.clone(),
+                    summary: advisory.summary.clone(),
+                    component: component.name.clone(),
+                    version: version.clone(),
+                    severity,
+                    fixed,
+                });
+            }
+        }
+        found
+    }
+}
+
+/// Looks up the components of `info` in `db`.
+pub fn find_vulnerabilities(info: &BinaryInfo, db: &VulnDatabase) -> Vec<Vulnerability> {
+    db.lookup(&sbom::components(info))
+}
+
+/// `Some(fixed version)` when `version` is affected.
+fn affects(affected: &Affected, version: &str) -> Option<Option<String>> {
+    if affected
+        .versions
+        .iter()
+        .any(|v| compare_versions(v, version).is_eq())
+    {
+        return Some(None);
+    }
+    // Git ranges are in commit hashes, which a version string can't be
+    // placed among.
+    for range in affected.ranges.iter().filter(|r| r.kind != "GIT") {
+        let mut hit = false;
+        let mut fixed = None;
+        for event in &range.events {
+            if let Some(introduced) = &event.introduced {
+                if introduced == "0" || compare_versions(version, introduced).is_ge() {
+                    hit = true;
+                }
+            }
+            if let Some(fix) = &event.fixed {
+                if compare_versions(version, fix).is_ge() {
+                    hit = false;
+                } else if hit && fixed.is_none() {
+                    fixed = Some(fix.clone());
+                }
+            }
+            if let Some(last) = &event.last_affected {
+                if compare_versions(version, last).is_gt() {
+                    hit = false;
+                }
+            }
+        }
+        if hit {
+            return Some(fixed);
+        }
+    }
+    None
+}
+
+fn severity_from_name(name: &str) -> Severity {
+    match name.to_ascii_uppercase().as_str() {
+        "CRITICAL" | "HIGH" => Severity::High,
+        "LOW" => Severity::Low,
+        _ => Severity::Medium,
+    }
+}
+
+/// Tags that mark a pre-release when they follow the release number
+/// without a `-`, as in `2.0beta` or `1.0.0rc1`.
+const PRERELEASE_TAGS: &[&str] = &["alpha", "beta", "pre", "preview", "rc", "dev", "snapshot"];
+
+/// Compares versions by their runs of digits (numerically) and letters, so
+/// that `1.0.2k` < `1.0.2l` < `1.0.10` and `v1.2` equals `1.2`. A
+/// pre-release (`1.0.0-rc1`, `2.0beta`) comes before its release, while a
+/// letter patch (`1.0.2a`) comes after it.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (tokens(a), tokens(b));
+    for ((_, x), (_, y)) in a.iter().zip(&b) {
+        let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Greater,
+            (Err(_), Ok(_)) => Ordering::Less,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+    // One is a prefix of the other; look at what the longer one adds.
+    let common = a.len().min(b.len());
+    let prerelease = |tokens: &[(Option<u8>, &str)]| {
+        tokens.get(common).is_some_and(|&(separator, token)| {
+            !token.as_bytes()[0].is_ascii_digit()
+                && (matches!(separator, Some(b'-' | b'~'))
+                    || PRERELEASE_TAGS.contains(&token.to_ascii_lowercase().as_str()))
+        })
+    };
+    if prerelease(&a) {
+        Ordering::Less
+    } else if prerelease(&b) {
+        Ordering::Greater
+    } else {
+        a.len().cmp(&b.len())
+    }
+}
+
+/// Runs of digits or letters, each with the separator before it, if any.
+fn tokens(version: &str) -> Vec<(Option<u8>, &str)> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+    let bytes = version.as_bytes();
+    for i in 0..=bytes.len() {
+        let same = match (start, bytes.get(i)) {
+            (Some(s), Some(b)) => {
+                let first: u8 = bytes[s];
+                b.is_ascii_alphanumeric() && first.is_ascii_digit() == b.is_ascii_digit()
+            }
+            _ => false,
+        };
+        if same {
+            continue;
+        }
+        if let Some(s) = start.take() {
+            let separator = s
+                .checked_sub(1)
+                .map(|p| bytes[p])
+                .filter(|b| !b.is_ascii_alphanumeric());
+            tokens.push((separator, &version[s..i]));
+        }
+        if bytes.get(i).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            start = Some(i);
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::sbom::Source;
+
+    const ADVISORIES: &str = r#"[
+        {
+            "id": "CVE-2016-2107",
+            "summary": "Padding oracle in AES-NI CBC MAC check",
+            "affected": [{
+                "package": { "name": "OpenSSL" },
+                "ranges": [{ "type": "ECOSYSTEM", "events": [
+                    { "introduced": "1.0.1" }, { "fixed": "1.0.1t" },
+                    { "introduced": "1.0.2" }, { "fixed": "1.0.2h" }
+                ]}]
+            }],
+            "database_specific": { "severity": "HIGH" }
+        },
+        {
+            "id": "CVE-2018-25032",
+            "affected": [{
+                "package": { "name": "zlib" },
+                "versions": ["1.2.11"]
+            }]
+        }
+    ]"#;
+
+    fn component(name: &str, version: &str) -> Component {
+        Component {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            purl: None,
+            source: Source::VersionString,
+        }
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert!(compare_versions("1.0.2k", "1.0.2l").is_lt());
+        assert!(compare_versions("1.0.2", "1.0.2a").is_lt());
+        assert!(compare_versions("1.0.10", "1.0.9").is_gt());
+        assert!(compare_versions("v0.15.0", "0.15.0").is_eq());
+        assert!(compare_versions("1.0.0-rc1", "1.0.0").is_lt());
+        assert!(compare_versions("1.0.0", "1.0.0-alpha").is_gt());
+        assert!(compare_versions("2.0beta2", "2.0").is_lt());
+        assert!(compare_versions("1.0.0-rc1", "1.0.0-rc2").is_lt());
+        assert!(compare_versions("1.0.0-rc2", "1.0.1").is_lt());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut db = VulnDatabase::default();
+        db.extend_json(ADVISORIES).unwrap();
+        let found = db.lookup(&[
+            component("openssl", "1.0.2g"),
+            component("openssl", "1.0.2h"),
+            component("zlib", "1.2.11"),
+        ]);
+        let ids: Vec<_> = found
+            .iter()
+            .map(|v| (v.id.as_str(), v.version.as_str(), v.fixed.as_deref()))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("CVE-2016-2107", "1.0.2g", Some("1.0.2h")),
+                ("CVE-2018-25032", "1.2.11", None),
+            ]
+        );
+        assert_eq!(found[0].severity, Severity::High);
+        assert_eq!(found[1].severity, Severity::Medium);
+    }
+}
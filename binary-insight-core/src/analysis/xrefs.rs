@@ -0,0 +1,335 @@
+use super::cfg::{self, Flow};
+use super::disassembly::InstructionInfo;
+use crate::binary::BinaryFile;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XrefKind {
+    Call,
+    Jump,
+    /// Conditional branch.
+    Branch,
+    /// Address computed or loaded from (`lea rax, [rip + ..]`, `adrp`/`add`).
+    Data,
+}
+
+impl XrefKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            XrefKind::Call => "call",
+            XrefKind::Jump => "jump",
+            XrefKind::Branch => "branch",
+            XrefKind::Data => "data",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Xref {
+    pub to: u64,
+    pub from: u64,
+    pub kind: XrefKind,
+    /// Start of the function the referencing instruction was decoded in.
+    pub function: u64,
+}
+
+/// Code references sorted by target, so "who references X" is a binary search.
+#[derive(Debug, Clone, Default)]
+pub struct XrefIndex {
+    refs: Vec<Xref>,
+}
+
+impl XrefIndex {
+    pub fn len(&self) -> usize {
+        self.refs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.refs.is_empty()
+    }
+
+    /// References to exactly `addr`, ordered by source address.
+    pub fn to(&self, addr: u64) -> &[Xref] {
+        let lo = self.refs.partition_point(|x| x.to < addr);
+        let hi = self.refs.partition_point(|x| x.to <= addr);
+        &self.refs[lo..hi]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Xref> {
+        self.refs.iter()
+    }
+
+    /// References made from inside the function starting at `function`.
+    pub fn from_function(&self, function: u64) -> impl Iterator<Item = &Xref> {
+        self.refs.iter().filter(move |x| x.function == function)
+    }
+}
+
+/// Indexes calls, jumps and data references made by every discovered function
+/// (or just the entry point when [`BinaryFile::discover_functions`] has not
+/// run). Only targets inside a section are kept.
+pub fn build_xrefs(file: &BinaryFile) -> XrefIndex {
+    let info = &file.info;
+    let mut starts: Vec<u64> = info.functions.iter().map(|f| f.start).collect();
+    if starts.is_empty() {
+        starts.push(info.entry_point);
+    }
+
+    let mut refs = BTreeSet::new();
+    for function in starts {
+        let Ok(graph) = file.function_cfg(function) else {
+            continue;
+        };
+        for block in &graph.blocks {
+            // `adrp` pages by register, valid until the end of the block.
+            let mut pages = HashMap::new();
+            for ins in &block.instructions {
+                let target = match cfg::classify(&info.arch, ins) {
+                    Flow::Call(Some(t)) => Some((t, XrefKind::Call)),
+                    Flow::Jump(Some(t)) => Some((t, XrefKind::Jump)),
+                    Flow::Branch(Some(t)) => Some((t, XrefKind::Branch)),
+                    Flow::Normal => data_target(info.arch_at(ins.address), ins, &mut pages)
+                        .map(|t| (t, XrefKind::Data)),
+                    _ => None,
+                };
+                // Absolute addresses in PE code are VAs, not RVAs.
+                let target = target.map(|(to, kind)| match kind {
+                    XrefKind::Data if info.section_for_addr(to).is_none() => {
+                        (to.wrapping_sub(info.image_base), kind)
+                    }
+                    _ => (to, kind),
+                });
+                if let Some((to, kind)) = target {
+                    if info.section_for_addr(to).is_some() {
+                        refs.insert(Xref {
+                            to,
+                            from: ins.address,
+                            kind,
+                            function,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    XrefIndex {
+        refs: refs.into_iter().collect(),
+    }
+}
+
+/// Address a non-branch instruction refers to, if it can be computed
+/// statically.
+pub(crate) fn data_target(
+    arch: &str,
+    ins: &InstructionInfo,
+    pages: &mut HashMap<String, u64>,
+) -> Option<u64> {
+    let ops: Vec<&str> = ins.op_str.split(", ").collect();
+    match arch {
+        "aarch64" => match ins.mnemonic.as_str() {
+            "adrp" => {
+                let page = parse_imm(ops.get(1)?)?;
+                pages.insert(ops[0].to_string(), page);
+                None
+            }
+            "adr" => parse_imm(ops.get(1)?),
+            // add xD, xN, #imm after adrp xN
+            "add" => {
+                let page = *pages.get(*ops.get(1)?)?;
+                Some(page + parse_imm(ops.get(2)?)?)
+            }
+            m if m.starts_with("ldr") || m.starts_with("str") => {
+                // ldr xD, [xN, #imm] after adrp xN, or a literal ldr xD, #addr
+                let mem = ins.op_str.split_once('[');
+                match mem {
+                    Some((_, rest)) => {
+                        let mut parts = rest.trim_end_matches(']').split(", ");
+                        let page = *pages.get(parts.next()?)?;
+                        Some(page + parts.next().map_or(Some(0), parse_imm)?)
+                    }
+                    None => parse_imm(ops.last()?),
+                }
+            }
+            _ => None,
+        },
+        "x86_64" => rip_relative(ins),
+        "arm" | "thumb" => pc_relative(arch, ins),
+        // auipc/lui rD, %hi(addr) then addi or a load/store with %lo(addr)
+        "riscv32" | "riscv64" => match ins.mnemonic.as_str() {
+            "auipc" => {
+                let hi = ins.address.wrapping_add(parse_imm(ops.get(1)?)? << 12);
+                pages.insert(ops[0].to_string(), hi);
+                None
+            }
+            "lui" | "c.lui" => {
+                pages.insert(ops[0].to_string(), parse_imm(ops.get(1)?)? << 12);
+                None
+            }
+            "addi" | "addiw" => {
+                let hi = *pages.get(*ops.get(1)?)?;
+                Some(hi.wrapping_add_signed(parse_signed(ops.get(2)?)?))
+            }
+            m if m.starts_with('l') || m.starts_with('s') || m.starts_with("fl") => {
+                let (disp, base) = ops.last()?.split_once('(')?;
+                let hi = *pages.get(base.trim_end_matches(')'))?;
+                Some(hi.wrapping_add_signed(parse_signed(disp)?))
+            }
+            _ => None,
+        },
+        // lui rD, %hi(addr) then addiu/ori or a load/store with %lo(addr)
+        _ if arch.starts_with("mips") => match ins.mnemonic.as_str() {
+            "lui" => {
+                let hi = parse_imm(ops.get(1)?)?;
+                pages.insert(ops[0].to_string(), hi << 16);
+                None
+            }
+            "addiu" | "daddiu" | "ori" => {
+                let hi = *pages.get(*ops.get(1)?)?;
+                Some(hi.wrapping_add_signed(parse_signed(ops.get(2)?)?))
+            }
+            m if m.starts_with('l') || m.starts_with('s') => {
+                let (disp, base) = ops.last()?.split_once('(')?;
+                let hi = *pages.get(base.trim_end_matches(')'))?;
+                Some(hi.wrapping_add_signed(parse_signed(disp)?))
+            }
+            _ => None,
+        },
+        // 32-bit code addresses globals with absolute displacements and
+        // immediates (`push offset str`); the largest number is the address.
+        "x86" => ins
+            .op_str
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter_map(parse_imm)
+            .max(),
+        _ => None,
+    }
+}
+
+/// Resolves `[rip + 0x..]` / `[rip - 0x..]` against the next instruction.
+fn rip_relative(ins: &InstructionInfo) -> Option<u64> {
+    let (_, rest) = ins.op_str.split_once("[rip ")?;
+    let (sign, rest) = rest.split_at(1);
+    let disp = parse_imm(rest.trim_start().split(']').next()?)?;
+    let next = ins.address + ins.size as u64;
+    match sign {
+        "+" => Some(next.wrapping_add(disp)),
+        "-" => Some(next.wrapping_sub(disp)),
+        _ => None,
+    }
+}
+
+/// Resolves literal-pool loads (`ldr r0, [pc, #0x10]`) and `adr`. The PC
+/// reads 8 bytes ahead in ARM state, and 4 ahead, word-aligned, in Thumb.
+fn pc_relative(arch: &str, ins: &InstructionInfo) -> Option<u64> {
+    let pc = match arch {
+        "thumb" => (ins.address + 4) & !3,
+        _ => ins.address + 8,
+    };
+    let disp = match ins.mnemonic.trim_end_matches(".w") {
+        "adr" => ins.op_str.split(", ").nth(1)?,
+        m if m.starts_with("ldr") => {
+            let (_, mem) = ins.op_str.split_once("[pc")?;
+            match mem.trim_start_matches(", ").trim_end_matches(']') {
+                "" => "0",
+                disp => disp,
+            }
+        }
+        _ => return None,
+    };
+    match disp.trim_start_matches('#').strip_prefix('-') {
+        Some(neg) => Some(pc.wrapping_sub(parse_imm(neg)?)),
+        None => Some(pc.wrapping_add(parse_imm(disp)?)),
+    }
+}
+
+/// Parses a capstone immediate that may be negative (`-0x7ff0`).
+fn parse_signed(s: &str) -> Option<i64> {
+    let s = s.trim().trim_start_matches('#');
+    match s.strip_prefix('-') {
+        Some(abs) => Some(-(parse_imm(abs)? as i64)),
+        None => Some(parse_imm(s)? as i64),
+    }
+}
+
+/// Parses a capstone immediate, which is printed in decimal below 10.
+pub(crate) fn parse_imm(s: &str) -> Option<u64> {
+    let s = s.trim().trim_start_matches('#');
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ins(address: u64, size: usize, mnemonic: &str, op_str: &str) -> InstructionInfo {
+        InstructionInfo {
+            address,
+            size,
+            mnemonic: mnemonic.to_string(),
+            op_str: op_str.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_data_targets() {
+        let mut pages = HashMap::new();
+        let lea = ins(0x1000, 7, "lea", "rax, [rip + 0x2ff9]");
+        assert_eq!(data_target("x86_64", &lea, &mut pages), Some(0x4000));
+        let mov = ins(0x1000, 7, "mov", "rax, qword ptr [rip - 0x7]");
+        assert_eq!(data_target("x86_64", &mov, &mut pages), Some(0x1000));
+        let push = ins(0x1000, 5, "push", "0x403000");
+        assert_eq!(data_target("x86", &push, &mut pages), Some(0x403000));
+        let store = ins(0x1000, 10, "mov", "dword ptr [0x404000], 5");
+        assert_eq!(data_target("x86", &store, &mut pages), Some(0x404000));
+
+        let adrp = ins(0x1000, 4, "adrp", "x0, 0x41000");
+        assert_eq!(data_target("aarch64", &adrp, &mut pages), None);
+        let add = ins(0x1004, 4, "add", "x0, x0, #0x123");
+        assert_eq!(data_target("aarch64", &add, &mut pages), Some(0x41123));
+        let ldr = ins(0x1008, 4, "ldr", "x1, [x0, #0x10]");
+        assert_eq!(data_target("aarch64", &ldr, &mut pages), Some(0x41010));
+
+        let lui = ins(0x1000, 4, "lui", "$a0, 0x41");
+        assert_eq!(data_target("mips", &lui, &mut pages), None);
+        let addiu = ins(0x1004, 4, "addiu", "$a1, $a0, -0x10");
+        assert_eq!(data_target("mips", &addiu, &mut pages), Some(0x40fff0));
+        let lw = ins(0x1008, 4, "lw", "$v0, 0x20($a0)");
+        assert_eq!(data_target("mips", &lw, &mut pages), Some(0x410020));
+
+        let auipc = ins(0x1000, 4, "auipc", "a0, 2");
+        assert_eq!(data_target("riscv64", &auipc, &mut pages), None);
+        let addi = ins(0x1004, 4, "addi", "a0, a0, 0x123");
+        assert_eq!(data_target("riscv64", &addi, &mut pages), Some(0x3123));
+
+        let literal = ins(0x1002, 2, "ldr", "r0, [pc, #0x10]");
+        assert_eq!(data_target("thumb", &literal, &mut pages), Some(0x1014));
+        let literal = ins(0x1000, 4, "ldr", "r0, [pc, #-0x8]");
+        assert_eq!(data_target("arm", &literal, &mut pages), Some(0x1000));
+    }
+
+    #[test]
+    fn test_xrefs_to_callee() {
+        // 0x1000: call 0x100d
+        // 0x1005: lea rax, [rip + 0x1]
+        // 0x100c: ret
+        // 0x100d: jmp 0x100d
+        let code = vec![
+            0xe8, 0x08, 0x00, 0x00, 0x00, 0x48, 0x8d, 0x05, 0x01, 0x00, 0x00, 0x00, 0xc3, 0xeb,
+            0xfe,
+        ];
+        let file = BinaryFile::from_raw("t".to_string(), code, "x86_64", 0x1000);
+        let index = build_xrefs(&file);
+        let refs: Vec<(u64, XrefKind)> =
+            index.to(0x100d).iter().map(|x| (x.from, x.kind)).collect();
+        assert_eq!(
+            refs,
+            vec![(0x1000, XrefKind::Call), (0x1005, XrefKind::Data)]
+        );
+        assert!(index.to(0x100c).is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+//! Reader for the YAML subset used by capability rules: block mappings and
+//! sequences, plain and quoted scalars, `[a, b]` flow lists and `#`
+//! comments. Anchors, multi-line scalars and multiple documents are not
+//! supported. [`to_string`] writes serialized reports in the same style.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Yaml {
+    Scalar(String),
+    List(Vec<Yaml>),
+    /// Entries in file order.
+    Map(Vec<(String, Yaml)>),
+}
+
+impl Yaml {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Yaml::Scalar(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The value of `key`, if this is a mapping that has it.
+    pub fn get(&self, key: &str) -> Option<&Yaml> {
+        match self {
+            Yaml::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+pub fn parse(text: &str) -> Result<Yaml> {
+    let mut lines = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let content = strip_comment(raw).trim_end();
+        if content.trim().is_empty() || content == "---" {
+            continue;
+        }
+        if content.starts_with('\t') {
+            bail!("line {}: tabs are not allowed for indentation", i + 1);
+        }
+        let text = content.trim_start();
+        lines.push(Line {
+            number: i + 1,
+            indent: content.len() - text.len(),
+            text: text.to_string(),
+        });
+    }
+    if lines.is_empty() {
+        return Ok(Yaml::Map(Vec::new()));
+    }
+    let indent = lines[0].indent;
+    let mut pos = 0;
+    let value = parse_block(&mut lines, &mut pos, indent)?;
+    if let Some(line) = lines.get(pos) {
+        bail!("line {}: unexpected indentation", line.number);
+    }
+    Ok(value)
+}
+
+/// Parses the mapping or sequence whose entries start at column `indent`.
+fn parse_block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Yaml> {
+    if is_item(&lines[*pos].text) {
+        let mut items = Vec::new();
+        while *pos < lines.len() && lines[*pos].indent == indent && is_item(&lines[*pos].text) {
+            let rest = lines[*pos].text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                *pos += 1;
+                items.push(parse_child(lines, pos, indent)?);
+            } else {
+                // `- key: value` opens a mapping at the column of `key`.
+                let line = &mut lines[*pos];
+                line.indent += line.text.len() - rest.len();
+                line.text = rest;
+                let inner = line.indent;
+                if is_item(&line.text) || split_key(&line.text).is_some() {
+                    items.push(parse_block(lines, pos, inner)?);
+                } else {
+                    items.push(scalar(&line.text, line.number)?);
+                    *pos += 1;
+                }
+            }
+        }
+        return Ok(Yaml::List(items));
+    }
+
+    let mut entries = Vec::new();
+    while *pos < lines.len() && lines[*pos].indent == indent {
+        let line = &lines[*pos];
+        if is_item(&line.text) {
+            bail!("line {}: list item inside a mapping", line.number);
+        }
+        let (key, value) = split_key(&line.text)
+            .ok_or_else(|| anyhow!("line {}: expected `key: value`", line.number))?;
+        let (key, value, number) = (key.to_string(), value.to_string(), line.number);
+        *pos += 1;
+        let value = if value.is_empty() {
+            // A sequence may sit at the same column as its key.
+            match lines.get(*pos) {
+                Some(next) if next.indent == indent && is_item(&next.text) => {
+                    parse_block(lines, pos, indent)?
+                }
+                _ => parse_child(lines, pos, indent)?,
+            }
+        } else {
+            scalar(&value, number)?
+        };
+        entries.push((unquote(&key, number)?, value));
+    }
+    Ok(Yaml::Map(entries))
+}
+
+/// The block nested deeper than `indent`, or an empty scalar if none.
+fn parse_child(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Yaml> {
+    match lines.get(*pos) {
+        Some(next) if next.indent > indent => {
+            let inner = next.indent;
+            parse_block(lines, pos, inner)
+        }
+        _ => Ok(Yaml::Scalar(String::new())),
+    }
+}
+
+fn is_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Splits `key: value` (or `key:`) at the first colon outside quotes.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, ':') => {
+                let rest = &text[i + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((text[..i].trim(), rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') if prev == ' ' => return &line[..i],
+            _ => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+fn scalar(text: &str, number: usize) -> Result<Yaml> {
+    match text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        Some(inner) => inner
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| Ok(Yaml::Scalar(unquote(item, number)?)))
+            .collect::<Result<_>>()
+            .map(Yaml::List),
+        None => Ok(Yaml::Scalar(unquote(text, number)?)),
+    }
+}
+
+fn unquote(text: &str, number: usize) -> Result<String> {
+    let unterminated = || anyhow!("line {}: unterminated string", number);
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner.strip_suffix('\'').ok_or_else(unterminated)?;
+        return Ok(inner.replace("''", "'"));
+    }
+    let Some(inner) = text.strip_prefix('"') else {
+        return Ok(text.to_string());
+    };
+    let inner = inner.strip_suffix('"').ok_or_else(unterminated)?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('0') => out.push('\0'),
+            Some(c @ ('"' | '\\')) => out.push(c),
+            other => bail!(
+                "line {}: unsupported escape \\{}",
+                number,
+                other.unwrap_or(' ')
+            ),
+        }
+    }
+    Ok(out)
+}
+
+/// Renders a serialized value as a block-style YAML document.
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    if is_block(value) {
+        write_block(&mut out, value, 0, false);
+    } else {
+        out.push_str(&scalar_text(value));
+        out.push('\n');
+    }
+    out
+}
+
+/// Non-empty mappings and sequences; everything else fits on one line.
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Writes a block at column `indent`. With `inline`, the first line
+/// continues the current one (after `- `).
+fn write_block(out: &mut String, value: &Value, indent: usize, mut inline: bool) {
+    let mut pad = |out: &mut String| {
+        if !inline {
+            out.push_str(&" ".repeat(indent));
+        }
+        inline = false;
+    };
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                pad(out);
+                out.push_str(&quote(key));
+                out.push(':');
+                match value {
+                    Value::Object(_) if is_block(value) => {
+                        out.push('\n');
+                        write_block(out, value, indent + 2, false);
+                    }
+                    // Sequences sit at the column of their key.
+                    Value::Array(_) if is_block(value) => {
+                        out.push('\n');
+                        write_block(out, value, indent, false);
+                    }
+                    _ => {
+                        out.push(' ');
+                        out.push_str(&scalar_text(value));
+                        out.push('\n');
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                pad(out);
+                out.push_str("- ");
+                if is_block(item) {
+                    write_block(out, item, indent + 2, true);
+                } else {
+                    out.push_str(&scalar_text(item));
+                    out.push('\n');
+                }
+            }
+        }
+        _ => {
+            pad(out);
+            out.push_str(&scalar_text(value));
+            out.push('\n');
+        }
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => quote(s),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+/// Double-quotes strings a YAML reader would otherwise misread: empty,
+/// padded, typed-looking (`true`, `0x10`, `~`), or holding indicators,
+/// `: `, ` #` or control characters.
+fn quote(text: &str) -> String {
+    let typed = matches!(
+        text.to_ascii_lowercase().as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+    ) || text.parse::<f64>().is_ok()
+        || text.starts_with("0x");
+    let plain = !text.is_empty()
+        && !typed
+        && text.trim() == text
+        && !text.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        && !text.ends_with(':')
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.chars().any(char::is_control);
+    if plain {
+        return text.to_string();
+    }
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(v: &str) -> Yaml {
+        Yaml::Scalar(v.to_string())
+    }
+
+    #[test]
+    fn test_nested_rule_shape() {
+        let text = r#"
+rule:
+  meta:
+    name: create thread  # trailing comment
+    attack: [T1055, "T1106"]
+  features:
+    - and:
+      - api: CreateRemoteThread
+      - "2 or more":
+        - string: 'it''s: here'
+        - mnemonic: rdtsc
+"#;
+        let doc = parse(text).unwrap();
+        let rule = doc.get("rule").unwrap();
+        let meta = rule.get("meta").unwrap();
+        assert_eq!(meta.get("name"), Some(&s("create thread")));
+        assert_eq!(
+            meta.get("attack"),
+            Some(&Yaml::List(vec![s("T1055"), s("T1106")]))
+        );
+        let features = rule.get("features").unwrap();
+        let expected = Yaml::List(vec![Yaml::Map(vec![(
+            "and".to_string(),
+            Yaml::List(vec![
+                Yaml::Map(vec![("api".to_string(), s("CreateRemoteThread"))]),
+                Yaml::Map(vec![(
+                    "2 or more".to_string(),
+                    Yaml::List(vec![
+                        Yaml::Map(vec![("string".to_string(), s("it's: here"))]),
+                        Yaml::Map(vec![("mnemonic".to_string(), s("rdtsc"))]),
+                    ]),
+                )]),
+            ]),
+        )])]);
+        assert_eq!(features, &expected);
+    }
+
+    #[test]
+    fn test_to_string_round_trips() {
+        let value = serde_json::json!({
+            "file": "a: b",
+            "entry_point": 4096,
+            "stripped": true,
+            "empty": [],
+            "findings": [
+                {"id": "yara.upx", "evidence": "0x10", "addresses": [1, 2]},
+                {"id": "", "evidence": "line\nbreak", "addresses": []}
+            ],
+            "nested": {"list": [["x", "- y"]]}
+        });
+        let text = to_string(&value);
+        assert!(text.contains("findings:\n- id: yara.upx\n  evidence: \"0x10\"\n"));
+        let doc = parse(&text).unwrap();
+        assert_eq!(doc.get("file"), Some(&s("a: b")));
+        let findings = doc.get("findings").unwrap();
+        let Yaml::List(findings) = findings else {
+            panic!("findings is not a list");
+        };
+        assert_eq!(findings[1].get("evidence"), Some(&s("line\nbreak")));
+        assert_eq!(
+            findings[0].get("addresses"),
+            Some(&Yaml::List(vec![s("1"), s("2")]))
+        );
+        assert_eq!(
+            doc.get("nested").and_then(|n| n.get("list")),
+            Some(&Yaml::List(vec![Yaml::List(vec![s("x"), s("- y")])]))
+        );
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        let err = parse("a: 1\n  b: 2\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        assert!(parse("a: \"open\n").is_err());
+    }
+}
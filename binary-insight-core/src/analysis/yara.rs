@@ -1,33 +1,494 @@
-use anyhow::{anyhow, Result};
-use boreal::Compiler;
+use crate::analysis::entropy::calculate_entropy;
+use crate::binary::BinaryInfo;
+use anyhow::{anyhow, bail, Context, Result};
+use boreal::module::Console;
+use boreal::scanner::{ScanError, ScanParams};
+use boreal::MetadataValue;
+use boreal::{Compiler, Scanner};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-pub struct YaraScanner;
+pub use boreal::compiler::ExternalValue;
 
-impl YaraScanner {
-    pub fn scan(data: &[u8], rules_str: &str) -> Result<Vec<String>> {
-        let mut compiler = Compiler::new();
-        if let Err(err) = compiler.add_rules_str(rules_str) {
+/// Starter packer, crypter and capability rules shipped with the tool.
+#[cfg(feature = "builtin-yara")]
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    ("packers.yar", include_str!("../../rules/yara/packers.yar")),
+    (
+        "crypters.yar",
+        include_str!("../../rules/yara/crypters.yar"),
+    ),
+    (
+        "capabilities.yar",
+        include_str!("../../rules/yara/capabilities.yar"),
+    ),
+];
+#[cfg(not(feature = "builtin-yara"))]
+const BUILTIN_RULES: &[(&str, &str)] = &[];
+
+/// Whether this build bundles the starter rules.
+pub const HAS_BUILTIN_RULES: bool = !BUILTIN_RULES.is_empty();
+
+/// External variables every rule set declares, set for each scanned file.
+/// `filesize` needs no external: it is a YARA keyword.
+pub const EXTERNALS: &[&str] = &["filename", "filepath", "extension", "entropy"];
+
+#[derive(Debug, Clone, Default)]
+pub struct YaraMatch {
+    pub rule: String,
+    pub tags: Vec<String>,
+    /// The rule's `meta` entries in source order, values as text.
+    pub meta: Vec<(String, String)>,
+    /// Every string match that contributed to the rule, by offset.
+    pub strings: Vec<YaraStringMatch>,
+}
+
+impl YaraMatch {
+    /// The first `meta` entry called `name`.
+    pub fn meta(&self, name: &str) -> Option<&str> {
+        self.meta
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The rule's `score` meta, conventionally 0 to 100.
+    pub fn score(&self) -> Option<i64> {
+        self.meta("score")?.parse().ok()
+    }
+
+    /// Distinct file offsets of the string matches.
+    pub fn offsets(&self) -> Vec<u64> {
+        let mut offsets: Vec<u64> = self.strings.iter().map(|s| s.offset).collect();
+        offsets.dedup();
+        offsets
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct YaraStringMatch {
+    /// String identifier from the rule, e.g. `$a`.
+    pub identifier: String,
+    pub offset: u64,
+    /// Matched bytes; the scanner caps long matches.
+    pub data: Vec<u8>,
+}
+
+/// Bounds on a scan, so a pathological rule or a huge input can't run
+/// away with time or memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanLimits {
+    /// Stop scanning after this long and keep the matches found so far.
+    pub timeout: Option<Duration>,
+    /// Matches kept per string; later ones are dropped and `#a` stops there.
+    pub max_string_matches: u32,
+    /// Bytes of matched data kept per match.
+    pub max_match_length: usize,
+}
+
+impl Default for ScanLimits {
+    /// boreal's defaults: no timeout, 1000 matches per string, 512 bytes per match.
+    fn default() -> Self {
+        ScanLimits {
+            timeout: None,
+            max_string_matches: 1_000,
+            max_match_length: 512,
+        }
+    }
+}
+
+/// Matches from one scan.
+#[derive(Debug, Clone, Default)]
+pub struct YaraScan {
+    pub matches: Vec<YaraMatch>,
+    /// The scan hit [`ScanLimits::timeout`], so `matches` may be missing some.
+    pub timed_out: bool,
+}
+
+/// The part of a file a scan is limited to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanScope {
+    /// The section name or address range asked for.
+    pub name: String,
+    /// File offsets covered.
+    pub range: Range<u64>,
+}
+
+impl ScanScope {
+    /// Resolves a section name such as `.text`, or a `start-end` range of
+    /// virtual addresses, to the file bytes behind it.
+    pub fn resolve(info: &BinaryInfo, spec: &str, file_len: u64) -> Result<Self> {
+        let range = match spec.split_once('-') {
+            Some((start, end)) if !info.sections.iter().any(|s| s.name == spec) => {
+                let (start, end) = (parse_address(start)?, parse_address(end)?);
+                if start >= end {
+                    bail!("Empty address range {}", spec);
+                }
+                info.addr_range_to_offsets(start..end).ok_or_else(|| {
+                    anyhow!(
+                        "Address range {} is not mapped contiguously from the file",
+                        spec
+                    )
+                })?
+            }
+            _ => {
+                let section = info
+                    .sections
+                    .iter()
+                    .find(|s| s.name == spec)
+                    .ok_or_else(|| anyhow!("No section named {}", spec))?;
+                section.offset..section.offset.saturating_add(section.size)
+            }
+        };
+        let range = range.start.min(file_len)..range.end.min(file_len);
+        if range.is_empty() {
+            bail!("{} has no bytes in the file", spec);
+        }
+        Ok(ScanScope {
+            name: spec.to_string(),
+            range,
+        })
+    }
+}
+
+fn parse_address(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.with_context(|| format!("Invalid address '{}'", s))
+}
+
+/// A single match location resolved against the binary layout.
+#[derive(Debug, Clone)]
+pub struct YaraHit {
+    pub rule: String,
+    pub identifier: String,
+    pub offset: u64,
+    pub data: Vec<u8>,
+    pub addr: Option<u64>,
+    /// Containing function and the distance from its start.
+    pub function: Option<(String, u64)>,
+}
+
+/// Rules compiled from one or more files, ready to scan any number of
+/// inputs.
+#[derive(Clone)]
+pub struct YaraRules {
+    scanner: Scanner,
+    /// Rule files left out because they failed to compile, with the
+    /// compiler's `file:line:column: message`.
+    pub errors: Vec<(PathBuf, String)>,
+    /// Names of the externals given a fixed value by `--yara-define`.
+    defined: Vec<String>,
+}
+
+impl YaraRules {
+    pub fn compile(rules: &str) -> Result<Self> {
+        let mut compiler = new_compiler(&[]);
+        if let Err(err) = compiler.add_rules_str(rules) {
             return Err(anyhow!("Failed to compile YARA rules: {:?}", err));
         }
+        Ok(Self::from_compiler(compiler, Vec::new(), &[]))
+    }
+
+    /// The bundled starter rules, each file in a `builtin/` namespace.
+    /// Empty when built without the `builtin-yara` feature.
+    pub fn builtin() -> Result<Self> {
+        let mut compiler = new_compiler(&[]);
+        for (name, source) in BUILTIN_RULES {
+            if let Err(err) =
+                compiler.add_rules_str_in_namespace(source, format!("builtin/{}", name))
+            {
+                bail!(
+                    "Failed to compile built-in YARA rules: {}",
+                    err.to_short_description(name, source).trim()
+                );
+            }
+        }
+        Ok(Self::from_compiler(compiler, Vec::new(), &[]))
+    }
 
-        let scanner = compiler.into_scanner();
-        let scan_result = scanner.scan_mem(data); // Returns Result or ScanResult depending on version, check error msg
+    /// Compiles rule files, searching directories recursively for `.yar`
+    /// and `.yara` files. Each file gets its own namespace, so rule names
+    /// may repeat across files. A file that fails to compile is recorded in
+    /// `errors` and skipped, unless none compile. `defines` declares extra
+    /// externals, or fixes the value of one of [`EXTERNALS`].
+    pub fn load<P: AsRef<Path>>(paths: &[P], defines: &[(String, ExternalValue)]) -> Result<Self> {
+        Self::compile_files(&rule_files(paths)?, defines)
+    }
 
-        // Error message: no field `rules` on type `Result<ScanResult<'_>, ...>`
-        // So it returns a Result in this version.
+    fn compile_files(files: &[PathBuf], defines: &[(String, ExternalValue)]) -> Result<Self> {
+        let mut compiler = new_compiler(defines);
+        let mut errors = Vec::new();
+        for file in files {
+            let namespace = file.to_string_lossy();
+            if let Err(err) = compiler.add_rules_file_in_namespace(file, &namespace) {
+                let source = fs::read_to_string(file).unwrap_or_default();
+                let message = err.to_short_description(&namespace, &source);
+                errors.push((file.clone(), message.trim().to_string()));
+            }
+        }
+        if errors.len() == files.len() {
+            let (file, message) = &errors[0];
+            bail!(
+                "Failed to compile YARA rules in {}: {}",
+                file.display(),
+                message
+            );
+        }
+        Ok(Self::from_compiler(compiler, errors, defines))
+    }
+
+    fn from_compiler(
+        compiler: Compiler,
+        errors: Vec<(PathBuf, String)>,
+        defines: &[(String, ExternalValue)],
+    ) -> Self {
+        let defined = defines.iter().map(|(name, _)| name.clone()).collect();
+        YaraRules {
+            scanner: compiler.into_scanner(),
+            errors,
+            defined,
+        }
+        .with_limits(ScanLimits::default())
+    }
+
+    /// These rules with different scan limits; the compiled rules are shared.
+    pub fn with_limits(mut self, limits: ScanLimits) -> Self {
+        let params = ScanParams::default()
+            // Without full matches boreal may stop at the first string that satisfies the condition.
+            .compute_full_matches(true)
+            .timeout_duration(limits.timeout)
+            .string_max_nb_matches(limits.max_string_matches)
+            .match_max_length(limits.max_match_length);
+        self.scanner.set_scan_params(params);
+        self
+    }
+
+    pub fn scan(&self, data: &[u8]) -> Vec<YaraMatch> {
+        self.scan_with(data, None, 0).matches
+    }
 
-        let scan_results = match scan_result {
-            Ok(res) => res,
-            Err((_err, res)) => res, // Partial results on timeout/error
+    /// Scans the contents of the file at `path`, which sets the `filename`,
+    /// `filepath` and `extension` externals.
+    pub fn scan_file(&self, data: &[u8], path: &Path) -> YaraScan {
+        self.scan_with(data, Some(path), 0)
+    }
+
+    /// [`YaraRules::scan_file`] over just `scope`. Rules see only those
+    /// bytes, so `filesize`, `entropy` and offsets in conditions are
+    /// relative to the scope; match offsets are still file offsets.
+    pub fn scan_scope(&self, data: &[u8], path: &Path, scope: &ScanScope) -> YaraScan {
+        let start = (scope.range.start as usize).min(data.len());
+        let end = (scope.range.end as usize).clamp(start, data.len());
+        self.scan_with(&data[start..end], Some(path), start as u64)
+    }
+
+    /// Scans `data`, which starts at file offset `base`.
+    fn scan_with(&self, data: &[u8], path: Option<&Path>, base: u64) -> YaraScan {
+        let name = |p: Option<&std::ffi::OsStr>| {
+            ExternalValue::from(
+                p.map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            )
+        };
+        let values = [
+            ("filename", name(path.and_then(Path::file_name))),
+            ("filepath", name(path.map(Path::as_os_str))),
+            ("extension", name(path.and_then(Path::extension))),
+            ("entropy", ExternalValue::Float(calculate_entropy(data))),
+        ];
+        let mut scanner = self.scanner.clone();
+        for (symbol, value) in values {
+            if !self.defined.iter().any(|d| d == symbol) {
+                // Every rule set declares these, with matching types.
+                let _ = scanner.define_symbol(symbol, value);
+            }
+        }
+        let (scan_results, timed_out) = match scanner.scan_mem(data) {
+            Ok(res) => (res, false),
+            // Partial results on timeout/error
+            Err((err, res)) => (res, matches!(err, ScanError::Timeout)),
         };
 
         let mut matches = Vec::new();
         for rule in scan_results.matched_rules {
-            matches.push(rule.name.to_string());
+            let mut strings: Vec<YaraStringMatch> = rule
+                .matches
+                .iter()
+                .flat_map(|s| {
+                    s.matches.iter().map(|m| YaraStringMatch {
+                        identifier: format!("${}", s.name),
+                        offset: base + (m.base + m.offset) as u64,
+                        data: m.data.clone(),
+                    })
+                })
+                .collect();
+            strings.sort_by_key(|s| s.offset);
+            matches.push(YaraMatch {
+                rule: rule.name.to_string(),
+                tags: rule.tags.to_vec(),
+                meta: rule
+                    .metadatas
+                    .iter()
+                    .map(|m| {
+                        let value = match &m.value {
+                            MetadataValue::Bytes(b) => String::from_utf8_lossy(b).into_owned(),
+                            MetadataValue::Integer(n) => n.to_string(),
+                            MetadataValue::Boolean(b) => b.to_string(),
+                        };
+                        (m.name.clone(), value)
+                    })
+                    .collect(),
+                strings,
+            });
+        }
+        YaraScan { matches, timed_out }
+    }
+}
+
+/// A compiler with `defines` and then the standard externals declared.
+/// Besides boreal's pe, elf, macho, hash, math, time and string modules,
+/// rules can `import "console"`; its output goes to the log.
+fn new_compiler(defines: &[(String, ExternalValue)]) -> Compiler {
+    let mut compiler = Compiler::new();
+    compiler.add_module(Console::with_callback(Box::new(|message| {
+        tracing::info!("YARA console: {}", message)
+    })));
+    for (name, value) in defines {
+        compiler.define_symbol(name, value.clone());
+    }
+    for name in EXTERNALS {
+        let value = match *name {
+            "entropy" => ExternalValue::Float(0.0),
+            _ => ExternalValue::Bytes(Vec::new()),
+        };
+        // A define of the same name keeps its value and type.
+        compiler.define_symbol(name, value);
+    }
+    compiler
+}
+
+/// Parses a `--yara-define` `name=value`. The value is an integer, float or
+/// boolean when it reads as one and a string otherwise; quotes force a
+/// string.
+pub fn parse_define(spec: &str) -> Result<(String, ExternalValue)> {
+    let (name, value) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected name=value, got '{}'", spec))?;
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("invalid external variable name '{}'", name);
+    }
+    let value = if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        ExternalValue::from(quoted)
+    } else if let Ok(n) = value.parse::<i64>() {
+        ExternalValue::Integer(n)
+    } else if let Ok(f) = value.parse::<f64>() {
+        ExternalValue::Float(f)
+    } else if let Ok(b) = value.parse::<bool>() {
+        ExternalValue::Boolean(b)
+    } else {
+        ExternalValue::from(value)
+    };
+    Ok((name.to_string(), value))
+}
+
+/// The files `paths` name, with directories expanded to the rule files
+/// below them.
+fn rule_files<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if path.is_dir() {
+            let before = files.len();
+            collect_rule_files(path, &mut files)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            if files.len() == before {
+                bail!("No .yar or .yara files in {}", path.display());
+            }
+        } else {
+            files.push(path.to_path_buf());
         }
+    }
+    Ok(files)
+}
 
-        Ok(matches)
+/// `.yar` and `.yara` files under `dir`, sorted within each directory.
+fn collect_rule_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_rule_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "yar" || ext == "yara")
+        {
+            files.push(path);
+        }
     }
+    Ok(())
+}
+
+pub struct YaraScanner;
+
+impl YaraScanner {
+    /// Compiles `rules_str` and scans `data` once.
+    pub fn scan(data: &[u8], rules_str: &str) -> Result<Vec<YaraMatch>> {
+        Ok(YaraRules::compile(rules_str)?.scan(data))
+    }
+
+    /// [`YaraScanner::scan`] within `limits`.
+    pub fn scan_with_limits(data: &[u8], rules_str: &str, limits: ScanLimits) -> Result<YaraScan> {
+        let rules = YaraRules::compile(rules_str)?.with_limits(limits);
+        Ok(rules.scan_with(data, None, 0))
+    }
+}
+
+/// The first bytes of a match, as a quoted string when printable and hex
+/// otherwise.
+pub fn preview(data: &[u8]) -> String {
+    const MAX_PREVIEW: usize = 32;
+    let shown = &data[..data.len().min(MAX_PREVIEW)];
+    let more = if data.len() > MAX_PREVIEW { "..." } else { "" };
+    if !shown.is_empty() && shown.iter().all(|b| (0x20..0x7f).contains(b)) {
+        format!("{:?}{}", String::from_utf8_lossy(shown), more)
+    } else {
+        format!("{}{}", hex::encode(shown), more)
+    }
+}
+
+/// Flattens matches into individual hits and attributes each to a virtual
+/// address and containing function where the layout allows.
+pub fn locate_hits(matches: &[YaraMatch], info: &BinaryInfo) -> Vec<YaraHit> {
+    let mut hits = Vec::new();
+    for m in matches {
+        for string in &m.strings {
+            let offset = string.offset;
+            let addr = info.offset_to_addr(offset);
+            let function = addr.and_then(|addr| {
+                info.containing_function(addr)
+                    .map(|f| (f.name.clone(), addr - f.addr))
+            });
+            hits.push(YaraHit {
+                rule: m.rule.clone(),
+                identifier: string.identifier.clone(),
+                offset,
+                data: string.data.clone(),
+                addr,
+                function,
+            });
+        }
+    }
+    hits
 }
 
 #[cfg(test)]
@@ -47,7 +508,7 @@ mod tests {
         let data = b"Hello World";
         let matches = YaraScanner::scan(data, rules).expect("Scan failed");
         assert_eq!(matches.len(), 1);
-        assert_eq!(matches[0], "TestRule");
+        assert_eq!(matches[0].rule, "TestRule");
     }
 
     #[test]
@@ -64,4 +525,182 @@ mod tests {
         let matches = YaraScanner::scan(data, rules).expect("Scan failed");
         assert!(matches.is_empty());
     }
+
+    #[test]
+    fn test_yara_scan_match_offsets() {
+        let rules = r#"
+            rule TestRule : greeting test {
+                meta:
+                    description = "Greets"
+                    score = 60
+                strings:
+                    $a = "lo"
+                    $b = { 57 6f }
+                condition:
+                    all of them
+            }
+        "#;
+        let data = b"Hello World, hello";
+        let matches = YaraScanner::scan(data, rules).expect("Scan failed");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].tags, ["greeting", "test"]);
+        assert_eq!(matches[0].meta("description"), Some("Greets"));
+        assert_eq!(matches[0].score(), Some(60));
+        assert_eq!(matches[0].offsets(), vec![3, 6, 16]);
+        assert_eq!(
+            matches[0].strings[1],
+            YaraStringMatch {
+                identifier: "$b".to_string(),
+                offset: 6,
+                data: b"Wo".to_vec(),
+            }
+        );
+        assert_eq!(preview(&matches[0].strings[1].data), "\"Wo\"");
+        assert_eq!(preview(b"\x00\xff"), "00ff");
+    }
+
+    #[test]
+    fn test_externals() {
+        let rules = r#"
+            rule Named {
+                condition:
+                    filename == "sample.exe" and extension == "exe" and entropy < 2.0
+            }
+            rule Defined {
+                condition:
+                    level > 2 and owner == "42"
+            }
+        "#;
+        let defines = [
+            parse_define("level=3").unwrap(),
+            parse_define("owner=\"42\"").unwrap(),
+        ];
+        let mut compiler = new_compiler(&defines);
+        compiler.add_rules_str(rules).expect("Compile failed");
+        let compiled = YaraRules::from_compiler(compiler, Vec::new(), &defines);
+
+        let names = |m: Vec<YaraMatch>| m.into_iter().map(|m| m.rule).collect::<Vec<_>>();
+        let path = Path::new("/tmp/sample.exe");
+        assert_eq!(
+            names(compiled.scan_file(b"aaaa", path).matches),
+            ["Named", "Defined"]
+        );
+        assert_eq!(names(compiled.scan(b"aaaa")), ["Defined"]);
+        assert!(matches!(
+            parse_define("f=1.5"),
+            Ok((_, ExternalValue::Float(_)))
+        ));
+        assert!(parse_define("1x=1").is_err());
+        assert!(parse_define("novalue").is_err());
+    }
+
+    #[test]
+    fn test_modules() {
+        let rules = r#"
+            import "pe"
+            import "elf"
+            import "hash"
+            import "math"
+            import "console"
+
+            rule Hashed {
+                condition:
+                    hash.md5(0, filesize) == "5d41402abc4b2a76b9719d911017c592"
+                    and math.entropy(0, filesize) > 1.0
+                    and console.log("hashed")
+            }
+            rule NotExecutable {
+                condition:
+                    pe.is_pe == 0 and not defined elf.number_of_sections
+            }
+        "#;
+        let matches = YaraScanner::scan(b"hello", rules).expect("Scan failed");
+        let names: Vec<_> = matches.iter().map(|m| m.rule.as_str()).collect();
+        assert_eq!(names, ["Hashed", "NotExecutable"]);
+    }
+
+    #[test]
+    fn test_scan_limits() {
+        let rules = r#"
+            rule Many {
+                strings:
+                    $a = "ab"
+                condition:
+                    #a > 2
+            }
+            rule Long {
+                strings:
+                    $b = /c+/
+                condition:
+                    $b
+            }
+        "#;
+        let data = b"ababab cccccccc";
+        let limits = ScanLimits {
+            max_string_matches: 2,
+            max_match_length: 3,
+            ..ScanLimits::default()
+        };
+        let scan = YaraScanner::scan_with_limits(data, rules, limits).expect("Scan failed");
+        assert!(!scan.timed_out);
+        assert_eq!(scan.matches.len(), 1);
+        assert_eq!(scan.matches[0].rule, "Long");
+        assert_eq!(scan.matches[0].strings[0].data, b"ccc");
+        assert_eq!(
+            YaraScanner::scan(data, rules).expect("Scan failed").len(),
+            2
+        );
+    }
+
+    #[cfg(feature = "builtin-yara")]
+    #[test]
+    fn test_builtin_rules() {
+        let rules = YaraRules::builtin().expect("Built-in rules failed to compile");
+        let data = b"@echo off\r\nvssadmin.exe Delete Shadows /All /Quiet\r\n";
+        let matches = rules.scan(data);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rule, "Shadow_Copy_Deletion");
+        assert_eq!(matches[0].score(), Some(80));
+        assert!(rules.scan(b"nothing to see here").is_empty());
+    }
+
+    #[test]
+    fn test_scan_scope() {
+        use crate::binary::SectionInfo;
+
+        let section = |name: &str, addr, offset| SectionInfo {
+            name: name.to_string(),
+            addr,
+            size: 0x10,
+            offset,
+            hashes: None,
+            zero_fill: false,
+        };
+        let info = BinaryInfo {
+            sections: vec![
+                section(".text", 0x1000, 0x10),
+                section(".data", 0x2000, 0x20),
+            ],
+            ..Default::default()
+        };
+        let text = ScanScope::resolve(&info, ".text", 0x30).unwrap();
+        assert_eq!(text.range, 0x10..0x20);
+        let range = ScanScope::resolve(&info, "0x2004-0x2008", 0x30).unwrap();
+        assert_eq!(range.range, 0x24..0x28);
+        assert!(ScanScope::resolve(&info, ".rsrc", 0x30).is_err());
+        assert!(ScanScope::resolve(&info, "0x1008-0x2004", 0x30).is_err());
+        assert!(ScanScope::resolve(&info, "0x3000-0x3004", 0x30).is_err());
+
+        let rules = YaraRules::compile(
+            "rule Hit { strings: $a = \"hit\" condition: $a at 1 and filesize == 16 }",
+        )
+        .unwrap();
+        let mut data = vec![0u8; 0x30];
+        data[0x11..0x14].copy_from_slice(b"hit");
+        data[0x21..0x24].copy_from_slice(b"hit");
+        let scan = rules.scan_scope(&data, Path::new("sample"), &text);
+        assert_eq!(scan.matches.len(), 1);
+        assert_eq!(scan.matches[0].offsets(), [0x11]);
+        assert!(rules.scan(&data).is_empty());
+    }
 }
@@ -0,0 +1,109 @@
+use serde::Serialize;
+
+/// Instruction set of the analyzed code. `BinaryInfo::arch` keeps the raw
+/// name so formats we cannot disassemble still report what they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Arch {
+    X86,
+    X86_64,
+    Aarch64,
+    /// 32-bit ARM; code may switch to Thumb per region.
+    Arm,
+    Thumb,
+    /// 32-bit MIPS, big and little endian. n32 binaries map here too, as
+    /// their pointers are 32 bits wide.
+    Mips,
+    Mipsel,
+    Mips64,
+    Mips64el,
+    Riscv32,
+    Riscv64,
+    Ppc,
+    Ppcle,
+    Ppc64,
+    Ppc64le,
+    Sparc,
+    Sparc64,
+    Unknown,
+}
+
+impl Arch {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "x86" => Arch::X86,
+            "x86_64" => Arch::X86_64,
+            "aarch64" => Arch::Aarch64,
+            "arm" => Arch::Arm,
+            "thumb" => Arch::Thumb,
+            "mips" | "mipsn32" => Arch::Mips,
+            "mipsel" | "mipsn32el" => Arch::Mipsel,
+            "mips64" => Arch::Mips64,
+            "mips64el" => Arch::Mips64el,
+            "riscv32" => Arch::Riscv32,
+            "riscv64" => Arch::Riscv64,
+            "ppc" => Arch::Ppc,
+            "ppcle" => Arch::Ppcle,
+            "ppc64" => Arch::Ppc64,
+            "ppc64le" => Arch::Ppc64le,
+            "sparc" => Arch::Sparc,
+            "sparc64" => Arch::Sparc64,
+            _ => Arch::Unknown,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Arm => "arm",
+            Arch::Thumb => "thumb",
+            Arch::Mips => "mips",
+            Arch::Mipsel => "mipsel",
+            Arch::Mips64 => "mips64",
+            Arch::Mips64el => "mips64el",
+            Arch::Riscv32 => "riscv32",
+            Arch::Riscv64 => "riscv64",
+            Arch::Ppc => "ppc",
+            Arch::Ppcle => "ppcle",
+            Arch::Ppc64 => "ppc64",
+            Arch::Ppc64le => "ppc64le",
+            Arch::Sparc => "sparc",
+            Arch::Sparc64 => "sparc64",
+            Arch::Unknown => "unknown",
+        }
+    }
+
+    /// Pointer width in bits.
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            Arch::X86
+            | Arch::Arm
+            | Arch::Thumb
+            | Arch::Mips
+            | Arch::Mipsel
+            | Arch::Riscv32
+            | Arch::Ppc
+            | Arch::Ppcle
+            | Arch::Sparc => Some(32),
+            Arch::X86_64
+            | Arch::Aarch64
+            | Arch::Mips64
+            | Arch::Mips64el
+            | Arch::Riscv64
+            | Arch::Ppc64
+            | Arch::Ppc64le
+            | Arch::Sparc64 => Some(64),
+            Arch::Unknown => None,
+        }
+    }
+
+    /// Byte order of code and data.
+    pub fn big_endian(&self) -> bool {
+        matches!(
+            self,
+            Arch::Mips | Arch::Mips64 | Arch::Ppc | Arch::Ppc64 | Arch::Sparc | Arch::Sparc64
+        )
+    }
+}
@@ -0,0 +1,354 @@
+use super::container::index_tar;
+use super::sevenzip;
+use super::{compression, BinaryFile, EmbeddedBinary};
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::io::{Cursor, Read};
+use tracing::warn;
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// End-of-central-directory record that an empty ZIP consists of.
+const ZIP_EMPTY_MAGIC: &[u8] = b"PK\x05\x06";
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xbc\xaf\x27\x1c";
+/// Offset of the `ustar` magic in a tar header.
+const TAR_MAGIC_OFFSET: usize = 257;
+/// ASAR starts with a Chromium pickle holding the header size; the JSON
+/// header follows as a second pickle.
+const ASAR_PICKLE_SIZE: &[u8] = b"\x04\x00\x00\x00";
+const ASAR_HEADER_JSON: usize = 16;
+const ASAR_JSON_MAGIC: &[u8] = b"{\"files\":";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    /// Electron application archive (`app.asar`).
+    Asar,
+    SevenZip,
+}
+
+impl ArchiveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArchiveKind::Zip => "ZIP Archive",
+            ArchiveKind::Tar => "Tar Archive",
+            ArchiveKind::Asar => "ASAR Archive",
+            ArchiveKind::SevenZip => "7z Archive",
+        }
+    }
+
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(ZIP_MAGIC) || data.starts_with(ZIP_EMPTY_MAGIC) {
+            Some(ArchiveKind::Zip)
+        } else if data.starts_with(SEVEN_ZIP_MAGIC) {
+            Some(ArchiveKind::SevenZip)
+        } else if data.starts_with(ASAR_PICKLE_SIZE)
+            && data
+                .get(ASAR_HEADER_JSON..)
+                .is_some_and(|json| json.starts_with(ASAR_JSON_MAGIC))
+        {
+            Some(ArchiveKind::Asar)
+        } else if data
+            .get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5)
+            .is_some_and(|magic| magic == b"ustar")
+        {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    pub path: String,
+    /// Uncompressed size.
+    pub size: u64,
+    /// Offset of the member's stored (possibly compressed) data in the
+    /// archive; for 7z, of the block it is unpacked from.
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveInfo {
+    pub kind: ArchiveKind,
+    /// Every regular file, in path order.
+    pub members: Vec<ArchiveMember>,
+    /// Executable members, summarized.
+    pub binaries: Vec<EmbeddedBinary>,
+}
+
+pub fn parse_archive(data: &[u8], kind: ArchiveKind) -> Result<ArchiveInfo> {
+    let mut info = ArchiveInfo {
+        kind,
+        members: Vec::new(),
+        binaries: Vec::new(),
+    };
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(Cursor::new(data)).context("Invalid ZIP archive")?;
+            for i in 0..zip.len() {
+                let entry = zip.by_index_raw(i)?;
+                if entry.is_file() {
+                    info.members.push(ArchiveMember {
+                        path: entry.name().to_string(),
+                        size: entry.size(),
+                        offset: entry.data_start(),
+                    });
+                }
+            }
+        }
+        ArchiveKind::Tar => {
+            for (path, (offset, size)) in index_tar(data)? {
+                info.members.push(ArchiveMember {
+                    path,
+                    size: size as u64,
+                    offset: offset as u64,
+                });
+            }
+        }
+        ArchiveKind::Asar => info.members = parse_asar(data)?,
+        // An encrypted header hides the member list, but not the archive.
+        ArchiveKind::SevenZip => match sevenzip::Archive::parse(data) {
+            Ok(archive) => {
+                info.members = archive
+                    .entries
+                    .iter()
+                    .map(|entry| ArchiveMember {
+                        path: entry.path.clone(),
+                        size: entry.size,
+                        offset: archive.offset(entry),
+                    })
+                    .collect()
+            }
+            Err(e) => warn!("Could not list 7z members: {:#}", e),
+        },
+    }
+    info.members.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut reader = MemberReader::new(data, kind);
+    for member in &info.members {
+        // Oversized or corrupt members are still listed, just not summarized.
+        let Ok(contents) = reader.read(member, compression::DEFAULT_DECOMPRESSION_LIMIT) else {
+            continue;
+        };
+        if let Some(binary) = BinaryFile::summarize_embedded(member.path.clone(), &contents) {
+            info.binaries.push(binary);
+        }
+    }
+    Ok(info)
+}
+
+/// Contents of one member, refusing more than `limit` bytes.
+pub fn read_member(
+    data: &[u8],
+    kind: ArchiveKind,
+    member: &ArchiveMember,
+    limit: u64,
+) -> Result<Vec<u8>> {
+    MemberReader::new(data, kind).read(member, limit)
+}
+
+/// Reads the members of one archive in turn. The 7z index is parsed once
+/// and the last unpacked block kept, as every member of a solid block
+/// needs the whole block unpacked.
+pub struct MemberReader<'a> {
+    data: &'a [u8],
+    kind: ArchiveKind,
+    seven_zip: Option<sevenzip::Archive>,
+}
+
+impl<'a> MemberReader<'a> {
+    pub fn new(data: &'a [u8], kind: ArchiveKind) -> Self {
+        MemberReader {
+            data,
+            kind,
+            seven_zip: None,
+        }
+    }
+
+    /// Contents of `member`, refusing more than `limit` bytes.
+    pub fn read(&mut self, member: &ArchiveMember, limit: u64) -> Result<Vec<u8>> {
+        if member.size > limit {
+            bail!("{} is larger than {} bytes", member.path, limit);
+        }
+        match self.kind {
+            ArchiveKind::Zip => {
+                let mut zip = zip::ZipArchive::new(Cursor::new(self.data))?;
+                let entry = zip
+                    .by_name(&member.path)
+                    .with_context(|| format!("Failed to open {}", member.path))?;
+                let mut contents = Vec::new();
+                // The declared size may lie; cap what is actually inflated.
+                entry
+                    .take(limit.saturating_add(1))
+                    .read_to_end(&mut contents)
+                    .with_context(|| format!("Failed to inflate {}", member.path))?;
+                if contents.len() as u64 > limit {
+                    bail!("{} is larger than {} bytes", member.path, limit);
+                }
+                Ok(contents)
+            }
+            ArchiveKind::Tar | ArchiveKind::Asar => {
+                let start = member.offset as usize;
+                Ok(self.data[start..start + member.size as usize].to_vec())
+            }
+            ArchiveKind::SevenZip => {
+                let archive = match &mut self.seven_zip {
+                    Some(archive) => archive,
+                    None => self.seven_zip.insert(sevenzip::Archive::parse(self.data)?),
+                };
+                archive.read(self.data, &member.path, limit)
+            }
+        }
+    }
+}
+
+/// Files stored in an ASAR archive. Files marked `unpacked` live next to
+/// the archive in `app.asar.unpacked` and are left out.
+fn parse_asar(data: &[u8]) -> Result<Vec<ArchiveMember>> {
+    let field = |at: usize| -> Result<usize> {
+        let bytes = data.get(at..at + 4).context("Truncated ASAR header")?;
+        Ok(u32::from_le_bytes(bytes.try_into()?) as usize)
+    };
+    let header_size = field(4)?;
+    let json_len = field(12)?;
+    let json = data
+        .get(ASAR_HEADER_JSON..ASAR_HEADER_JSON + json_len)
+        .context("Truncated ASAR header")?;
+    let header: Value = serde_json::from_slice(json).context("Invalid ASAR header")?;
+    let base = 8 + header_size as u64;
+
+    let mut members = Vec::new();
+    let mut dirs = vec![(String::new(), &header)];
+    while let Some((prefix, dir)) = dirs.pop() {
+        let Some(files) = dir.get("files").and_then(Value::as_object) else {
+            continue;
+        };
+        for (name, entry) in files {
+            let path = format!("{}{}", prefix, name);
+            if entry.get("files").is_some() {
+                dirs.push((format!("{}/", path), entry));
+                continue;
+            }
+            if entry.get("unpacked").and_then(Value::as_bool) == Some(true) {
+                continue;
+            }
+            // Offsets are strings since they may exceed JavaScript's integers.
+            let offset = entry.get("offset").and_then(Value::as_str);
+            let size = entry.get("size").and_then(Value::as_u64);
+            let (Some(offset), Some(size)) = (offset.and_then(|o| o.parse::<u64>().ok()), size)
+            else {
+                continue;
+            };
+            let offset = base + offset;
+            if offset.saturating_add(size) > data.len() as u64 {
+                bail!("ASAR member {} is out of bounds", path);
+            }
+            members.push(ArchiveMember { path, size, offset });
+        }
+    }
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_parse_zip() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, contents) in [
+            ("docs/README", b"hello".as_slice()),
+            ("bin/tool", b"\x7fELF"),
+        ] {
+            writer
+                .start_file(path, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        let data = writer.finish().unwrap().into_inner();
+
+        assert_eq!(ArchiveKind::detect(&data), Some(ArchiveKind::Zip));
+        let info = parse_archive(&data, ArchiveKind::Zip).expect("parse failed");
+        let paths: Vec<_> = info.members.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["bin/tool", "docs/README"]);
+        let contents = read_member(&data, ArchiveKind::Zip, &info.members[1], 1024).unwrap();
+        assert_eq!(contents, b"hello");
+        assert!(read_member(&data, ArchiveKind::Zip, &info.members[1], 4).is_err());
+    }
+
+    /// An ASAR archive holding `files` at the top level and one file
+    /// left unpacked.
+    fn asar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut entries = serde_json::Map::new();
+        let mut payload: Vec<u8> = Vec::new();
+        for (name, contents) in files {
+            entries.insert(
+                name.to_string(),
+                serde_json::json!({"size": contents.len(), "offset": payload.len().to_string()}),
+            );
+            payload.extend(*contents);
+        }
+        entries.insert(
+            "native.node".to_string(),
+            serde_json::json!({"size": 4, "unpacked": true}),
+        );
+        let json = serde_json::json!({ "files": entries }).to_string();
+        let json_len = json.len() as u32;
+        let padded = (json_len + 3) & !3;
+        let mut data = Vec::new();
+        data.extend(4u32.to_le_bytes());
+        data.extend((padded + 8).to_le_bytes());
+        data.extend((padded + 4).to_le_bytes());
+        data.extend(json_len.to_le_bytes());
+        data.extend(json.as_bytes());
+        data.resize(16 + padded as usize, 0);
+        data.extend(payload);
+        data
+    }
+
+    #[test]
+    fn test_parse_asar() {
+        let data = asar(&[("package.json", b"{}"), ("main.js", b"require('x')")]);
+        assert_eq!(ArchiveKind::detect(&data), Some(ArchiveKind::Asar));
+        let info = parse_archive(&data, ArchiveKind::Asar).unwrap();
+        let paths: Vec<_> = info.members.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(paths, ["main.js", "package.json"]);
+        assert_eq!(
+            read_member(&data, ArchiveKind::Asar, &info.members[0], 1024).unwrap(),
+            b"require('x')"
+        );
+    }
+
+    #[test]
+    fn test_detect_archives() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_ustar();
+        header.set_size(3);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "a.txt", &b"abc"[..])
+            .unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        assert_eq!(ArchiveKind::detect(&tar), Some(ArchiveKind::Tar));
+        let info = parse_archive(&tar, ArchiveKind::Tar).unwrap();
+        assert_eq!(info.members.len(), 1);
+        assert_eq!(
+            read_member(&tar, ArchiveKind::Tar, &info.members[0], 3).unwrap(),
+            b"abc"
+        );
+
+        let seven = b"7z\xbc\xaf\x27\x1c\x00\x04";
+        assert_eq!(ArchiveKind::detect(seven), Some(ArchiveKind::SevenZip));
+        // Truncated: still an archive, just without members.
+        assert!(parse_archive(seven, ArchiveKind::SevenZip)
+            .unwrap()
+            .members
+            .is_empty());
+        assert_eq!(ArchiveKind::detect(b"\x7fELF"), None);
+    }
+}
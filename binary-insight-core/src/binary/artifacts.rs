@@ -0,0 +1,226 @@
+use super::BinaryFile;
+use crate::analysis::pyinstaller;
+use goblin::{elf, pe, Object};
+
+/// `WIN_CERTIFICATE` header preceding each certificate blob.
+const WIN_CERTIFICATE_HEADER: usize = 8;
+
+/// A file extracted from the input, named by its path inside a report bundle.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub path: String,
+    pub kind: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Gathers everything worth shipping alongside a report: the decompressed
+/// payload, overlay, certificates, icon, firmware images, carved files and
+/// bundled scripts.
+pub fn collect_artifacts(file: &BinaryFile) -> Vec<Artifact> {
+    let data = &file.data;
+    let mut artifacts = Vec::new();
+
+    if let Some(wrapper) = file.info.compression.first() {
+        artifacts.push(Artifact {
+            path: format!("payload/{}.unpacked", basename(&file.name)),
+            kind: wrapper.name(),
+            data: data.to_vec(),
+        });
+    }
+
+    if let Some(offset) = overlay_offset(data) {
+        artifacts.push(Artifact {
+            path: format!("overlay/overlay_{:x}.bin", offset),
+            kind: "overlay",
+            data: data[offset as usize..].to_vec(),
+        });
+    }
+
+    for (i, cert) in pe_certificates(data).into_iter().enumerate() {
+        artifacts.push(Artifact {
+            path: format!("certificates/certificate_{}.p7b", i),
+            kind: "certificate",
+            data: cert,
+        });
+    }
+
+    if let Some(icon) = &file.info.icon {
+        artifacts.push(Artifact {
+            path: "icon/icon.ico".to_string(),
+            kind: "icon",
+            data: icon.ico.clone(),
+        });
+    }
+
+    let mut stack: Vec<_> = file.info.firmware.iter().collect();
+    while let Some(volume) = stack.pop() {
+        for ffs in &volume.files {
+            for section in ffs.sections.iter().filter(|s| s.is_image()) {
+                let start = section.offset as usize;
+                let end = start.checked_add(section.size as usize);
+                if let Some(image) = end.and_then(|end| data.get(start..end)) {
+                    artifacts.push(Artifact {
+                        path: format!(
+                            "firmware/{}_{:x}.{}",
+                            ffs.name.as_deref().unwrap_or(&ffs.guid),
+                            section.offset,
+                            section.type_name().to_lowercase()
+                        ),
+                        kind: "firmware image",
+                        data: image.to_vec(),
+                    });
+                }
+            }
+            stack.extend(&ffs.volumes);
+        }
+    }
+
+    for carved in &file.info.embedded_files {
+        artifacts.push(Artifact {
+            path: format!("carved/{:08x}.{}", carved.offset, carved.kind.extension()),
+            kind: carved.kind.name(),
+            data: carved.bytes(data).to_vec(),
+        });
+    }
+
+    if let Some(archive) = &file.info.pyinstaller {
+        let header = pyinstaller::pyc_header(data, archive).unwrap_or_default();
+        for entry in archive.entries.iter().filter(|e| e.kind == 's') {
+            if let Ok(code) = pyinstaller::read_entry(data, entry) {
+                artifacts.push(Artifact {
+                    path: format!("pyinstaller/{}.pyc", basename(&entry.name)),
+                    kind: "python script",
+                    data: [header.as_slice(), &code].concat(),
+                });
+            }
+        }
+    }
+
+    artifacts
+}
+
+/// Names taken from the input keep only their final path component.
+fn basename(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
+}
+
+/// Offset of data appended after the last byte the loader or linker accounts for.
+pub fn overlay_offset(data: &[u8]) -> Option<u64> {
+    let end = match Object::parse(data).ok()? {
+        Object::PE(pe) => pe_end(&pe),
+        Object::Elf(elf) => elf_end(&elf),
+        _ => return None,
+    };
+    (end < data.len() as u64).then_some(end)
+}
+
+pub(super) fn pe_end(pe: &pe::PE) -> u64 {
+    let headers = pe
+        .header
+        .optional_header
+        .map(|o| o.windows_fields.size_of_headers as u64)
+        .unwrap_or(0);
+    pe.sections
+        .iter()
+        .map(|s| s.pointer_to_raw_data as u64 + s.size_of_raw_data as u64)
+        .fold(headers, u64::max)
+}
+
+pub(super) fn elf_end(elf: &elf::Elf) -> u64 {
+    let header = &elf.header;
+    let segments = elf
+        .program_headers
+        .iter()
+        .filter_map(|ph| ph.p_offset.checked_add(ph.p_filesz));
+    let sections = elf
+        .section_headers
+        .iter()
+        .filter(|sh| sh.sh_type != elf::section_header::SHT_NOBITS)
+        .filter_map(|sh| sh.sh_offset.checked_add(sh.sh_size));
+    let tables = [
+        Some(header.e_ehsize as u64),
+        header
+            .e_phoff
+            .checked_add(header.e_phnum as u64 * header.e_phentsize as u64),
+        header
+            .e_shoff
+            .checked_add(header.e_shnum as u64 * header.e_shentsize as u64),
+    ];
+    segments
+        .chain(sections)
+        .chain(tables.into_iter().flatten())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Raw PKCS#7 blobs from the PE certificate table, without `WIN_CERTIFICATE` headers.
+pub fn pe_certificates(data: &[u8]) -> Vec<Vec<u8>> {
+    let Ok(Object::PE(pe)) = Object::parse(data) else {
+        return Vec::new();
+    };
+    let Some(dir) = pe
+        .header
+        .optional_header
+        .and_then(|o| o.data_directories.get_certificate_table().copied())
+    else {
+        return Vec::new();
+    };
+    // The certificate directory holds a file offset, not an RVA.
+    let start = dir.virtual_address as usize;
+    let Some(table) = start
+        .checked_add(dir.size as usize)
+        .and_then(|end| data.get(start..end))
+    else {
+        return Vec::new();
+    };
+
+    let mut certs = Vec::new();
+    let mut pos = 0;
+    while pos + WIN_CERTIFICATE_HEADER <= table.len() {
+        let length = u32::from_le_bytes(table[pos..pos + 4].try_into().unwrap()) as usize;
+        if length < WIN_CERTIFICATE_HEADER || pos + length > table.len() {
+            break;
+        }
+        certs.push(table[pos + WIN_CERTIFICATE_HEADER..pos + length].to_vec());
+        pos = (pos + length).next_multiple_of(8);
+    }
+    certs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basename() {
+        assert_eq!(basename("../../etc/passwd"), "passwd");
+        assert_eq!(basename("C:\\dir\\run.py"), "run.py");
+        assert_eq!(basename("plain"), "plain");
+    }
+
+    #[test]
+    fn test_elf_overlay_skips_huge_section() {
+        // ELF64 header, then a null section and one claiming almost 2^64 bytes.
+        let mut elf = vec![0u8; 0xc0];
+        elf[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        elf[0x10] = 2; // ET_EXEC
+        elf[0x12] = 0x3e; // x86-64
+        elf[0x14] = 1;
+        elf[0x28] = 0x40; // e_shoff
+        elf[0x34] = 0x40; // e_ehsize
+        elf[0x3a] = 0x40; // e_shentsize
+        elf[0x3c] = 2; // e_shnum
+        let section = 0x80;
+        elf[section + 4] = 1; // SHT_PROGBITS
+        elf[section + 0x18] = 0x40; // sh_offset
+        elf[section + 0x20..section + 0x28].copy_from_slice(&(u64::MAX - 0x10).to_le_bytes());
+        elf.extend_from_slice(b"appended");
+        assert_eq!(overlay_offset(&elf), Some(0xc0));
+    }
+
+    #[test]
+    fn test_non_executable_has_no_overlay() {
+        assert_eq!(overlay_offset(b"just some text"), None);
+        assert!(pe_certificates(b"MZ").is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+use super::artifacts::{elf_end, pe_end};
+use super::compression::DEFAULT_DECOMPRESSION_LIMIT;
+use flate2::{Decompress, FlushDecompress, Status};
+use goblin::Object;
+use serde::Serialize;
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+const PEM_BEGIN: &[u8] = b"-----BEGIN CERTIFICATE-----";
+const PEM_END: &[u8] = b"-----END CERTIFICATE-----";
+/// Size of a ZIP end-of-central-directory record without its comment.
+const ZIP_EOCD_LEN: usize = 22;
+
+/// File types recognized by [`carve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CarveKind {
+    Zip,
+    Gzip,
+    Png,
+    Pe,
+    Elf,
+    Sqlite,
+    /// DER-encoded X.509 certificate.
+    Certificate,
+    /// PEM-armored X.509 certificate.
+    PemCertificate,
+}
+
+impl CarveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CarveKind::Zip => "ZIP archive",
+            CarveKind::Gzip => "gzip stream",
+            CarveKind::Png => "PNG image",
+            CarveKind::Pe => "PE executable",
+            CarveKind::Elf => "ELF executable",
+            CarveKind::Sqlite => "SQLite database",
+            CarveKind::Certificate => "X.509 certificate (DER)",
+            CarveKind::PemCertificate => "X.509 certificate (PEM)",
+        }
+    }
+
+    /// File extension for carved copies.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CarveKind::Zip => "zip",
+            CarveKind::Gzip => "gz",
+            CarveKind::Png => "png",
+            CarveKind::Pe => "exe",
+            CarveKind::Elf => "elf",
+            CarveKind::Sqlite => "sqlite",
+            CarveKind::Certificate => "der",
+            CarveKind::PemCertificate => "pem",
+        }
+    }
+
+    pub fn is_executable(&self) -> bool {
+        matches!(self, CarveKind::Pe | CarveKind::Elf)
+    }
+}
+
+/// A file embedded in the input.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CarvedFile {
+    pub offset: u64,
+    pub size: u64,
+    pub kind: CarveKind,
+    /// Whether `size` comes from the file's own structure; otherwise it runs
+    /// to the next carved file or the end of the input.
+    pub exact: bool,
+}
+
+impl CarvedFile {
+    pub fn bytes<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let start = (self.offset as usize).min(data.len());
+        let end = (self.offset + self.size).min(data.len() as u64) as usize;
+        &data[start..end]
+    }
+}
+
+/// Scans `data` for embedded files by their magic bytes, binwalk-style.
+/// The input's own format at offset 0 is not listed, and matches inside an
+/// already carved file are skipped.
+pub fn carve(data: &[u8]) -> Vec<CarvedFile> {
+    let mut found: Vec<CarvedFile> = Vec::new();
+    let mut covered = 0;
+    let mut offset = 0;
+    while offset < data.len() {
+        let Some((kind, size)) = identify(&data[offset..]) else {
+            offset += 1;
+            continue;
+        };
+        if let Some(last) = found.last_mut().filter(|f| !f.exact) {
+            last.size = offset as u64 - last.offset;
+        }
+        if let Some(size) = size {
+            covered = offset + size;
+        }
+        if offset > 0 {
+            found.push(CarvedFile {
+                offset: offset as u64,
+                size: size.unwrap_or(data.len() - offset) as u64,
+                kind,
+                exact: size.is_some(),
+            });
+        }
+        offset = covered.max(offset + 1);
+    }
+    found
+}
+
+/// The file type starting at `data[0]`, with its size when its structure
+/// tells it.
+fn identify(data: &[u8]) -> Option<(CarveKind, Option<usize>)> {
+    let kind = match data.first()? {
+        b'P' if data.starts_with(b"PK\x03\x04") => CarveKind::Zip,
+        0x1f if data.starts_with(&[0x1f, 0x8b, 0x08]) => CarveKind::Gzip,
+        0x89 if data.starts_with(PNG_MAGIC) => CarveKind::Png,
+        b'M' if data.starts_with(b"MZ") => CarveKind::Pe,
+        0x7f if data.starts_with(b"\x7fELF") => CarveKind::Elf,
+        b'S' if data.starts_with(SQLITE_MAGIC) => CarveKind::Sqlite,
+        0x30 if data.starts_with(&[0x30, 0x82]) => CarveKind::Certificate,
+        b'-' if data.starts_with(PEM_BEGIN) => CarveKind::PemCertificate,
+        _ => return None,
+    };
+    let size = match kind {
+        CarveKind::Zip => zip_size(data),
+        CarveKind::Gzip => gzip_size(data)?,
+        CarveKind::Png => Some(png_size(data)?),
+        CarveKind::Pe => Some(pe_size(data)?),
+        CarveKind::Elf => Some(elf_size(data)?),
+        CarveKind::Sqlite => sqlite_size(data),
+        CarveKind::Certificate => Some(der_certificate_size(data)?),
+        CarveKind::PemCertificate => Some(pem_size(data)?),
+    };
+    Some((kind, size.map(|size| size.min(data.len()))))
+}
+
+/// Up to the end of the first end-of-central-directory record.
+fn zip_size(data: &[u8]) -> Option<usize> {
+    let eocd = find(data, b"PK\x05\x06")?;
+    let comment = u16::from_le_bytes(data.get(eocd + 20..eocd + 22)?.try_into().ok()?);
+    Some(eocd + ZIP_EOCD_LEN + comment as usize)
+}
+
+/// Inflates the stream to find where it ends; `None` when it is not valid
+/// deflate data, `Some(None)` when it is cut off.
+fn gzip_size(data: &[u8]) -> Option<Option<usize>> {
+    let flags = *data.get(3)?;
+    if flags & 0xe0 != 0 {
+        return None;
+    }
+    let mut pos = 10;
+    if flags & 0x04 != 0 {
+        let extra = u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?);
+        pos += 2 + extra as usize;
+    }
+    for flag in [0x08, 0x10] {
+        if flags & flag != 0 {
+            pos += data.get(pos..)?.iter().position(|&b| b == 0)? + 1;
+        }
+    }
+    if flags & 0x02 != 0 {
+        pos += 2;
+    }
+
+    let deflate = data.get(pos..)?;
+    let mut inflater = Decompress::new(false);
+    let mut scratch = vec![0u8; 64 * 1024];
+    loop {
+        let input = &deflate[inflater.total_in() as usize..];
+        let status = inflater
+            .decompress(input, &mut scratch, FlushDecompress::None)
+            .ok()?;
+        match status {
+            // CRC-32 and size trailer.
+            Status::StreamEnd => return Some(Some(pos + inflater.total_in() as usize + 8)),
+            _ if inflater.total_out() > DEFAULT_DECOMPRESSION_LIMIT => return Some(None),
+            _ if inflater.total_in() as usize == deflate.len() => {
+                return (inflater.total_out() > 0).then_some(None)
+            }
+            Status::BufError => return None,
+            Status::Ok => {}
+        }
+    }
+}
+
+/// Walks the chunks up to `IEND`.
+fn png_size(data: &[u8]) -> Option<usize> {
+    let mut pos = PNG_MAGIC.len();
+    if data.get(pos + 4..pos + 8)? != b"IHDR" {
+        return None;
+    }
+    loop {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let kind = data.get(pos + 4..pos + 8)?;
+        pos = pos.checked_add(12 + len)?;
+        if kind == b"IEND" {
+            return Some(pos);
+        }
+    }
+}
+
+fn pe_size(data: &[u8]) -> Option<usize> {
+    let lfanew = u32::from_le_bytes(data.get(0x3c..0x40)?.try_into().ok()?) as usize;
+    if data.get(lfanew..lfanew.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+    match Object::parse(data).ok()? {
+        Object::PE(pe) => Some(pe_end(&pe) as usize),
+        _ => None,
+    }
+}
+
+fn elf_size(data: &[u8]) -> Option<usize> {
+    if !matches!(data.get(4..6)?, [1 | 2, 1 | 2]) {
+        return None;
+    }
+    match Object::parse(data).ok()? {
+        Object::Elf(elf) => Some(elf_end(&elf) as usize),
+        _ => None,
+    }
+}
+
+/// Page size times page count from the database header; older writers
+/// leave the count at zero.
+fn sqlite_size(data: &[u8]) -> Option<usize> {
+    let page_size = match u16::from_be_bytes(data.get(16..18)?.try_into().ok()?) {
+        1 => 65536,
+        size => size as usize,
+    };
+    let pages = u32::from_be_bytes(data.get(28..32)?.try_into().ok()?) as usize;
+    (page_size >= 512 && pages > 0).then(|| page_size * pages)
+}
+
+/// A `SEQUENCE` holding a `SEQUENCE` that starts with the explicit v3
+/// version tag, as every X.509 v3 certificate does.
+fn der_certificate_size(data: &[u8]) -> Option<usize> {
+    let len = u16::from_be_bytes(data.get(2..4)?.try_into().ok()?) as usize;
+    let tbs = u16::from_be_bytes(data.get(6..8)?.try_into().ok()?) as usize;
+    let header = data.get(4..6)? == [0x30, 0x82] && data.get(8..12)? == [0xa0, 0x03, 0x02, 0x01];
+    (header && tbs + 4 <= len && len + 4 <= data.len()).then_some(len + 4)
+}
+
+fn pem_size(data: &[u8]) -> Option<usize> {
+    Some(find(data, PEM_END)? + PEM_END.len())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    /// A minimal PNG with `payload` as the data of a `tEXt` chunk.
+    fn png(payload: &[u8]) -> Vec<u8> {
+        let mut png = PNG_MAGIC.to_vec();
+        for (kind, data) in [
+            (b"IHDR", &[0u8; 13][..]),
+            (b"tEXt", payload),
+            (b"IEND", &[]),
+        ] {
+            png.extend((data.len() as u32).to_be_bytes());
+            png.extend(kind);
+            png.extend(data);
+            png.extend([0; 4]);
+        }
+        png
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_carve() {
+        let png = png(b"");
+        let gz = gzip(b"embedded payload");
+        let junk = b"\x1f\x8b\x08\x00 junk that does not inflate";
+        let pem = b"-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----";
+        let zip = b"PK\x03\x04 truncated zip";
+        let parts: [&[u8]; 7] = [b"header", &png, b"gap MZ not a PE ", &gz, junk, pem, zip];
+        let at = |i: usize| parts[..i].iter().map(|p| p.len()).sum::<usize>();
+        let data = parts.concat();
+
+        let found = carve(&data);
+        let summary: Vec<_> = found
+            .iter()
+            .map(|f| (f.offset as usize, f.size as usize, f.kind, f.exact))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (at(1), png.len(), CarveKind::Png, true),
+                (at(3), gz.len(), CarveKind::Gzip, true),
+                (at(5), pem.len(), CarveKind::PemCertificate, true),
+                (at(6), zip.len(), CarveKind::Zip, false),
+            ]
+        );
+        assert_eq!(found[0].bytes(&data), png.as_slice());
+    }
+
+    #[test]
+    fn test_carve_skips_own_format_and_nested() {
+        let outer = png(&png(b"inner"));
+        assert!(carve(&outer).is_empty());
+        let mut data = b"prefix".to_vec();
+        data.extend(&outer);
+        assert_eq!(carve(&data).len(), 1);
+        assert!(carve(b"").is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+use super::BinaryData;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Refuse to inflate payloads beyond this size unless the caller asks for more.
+pub const DEFAULT_DECOMPRESSION_LIMIT: u64 = 512 * 1024 * 1024;
+/// Payloads larger than this are inflated into a temporary file and mapped.
+pub const SPILL_THRESHOLD: usize = 64 * 1024 * 1024;
+/// Wrappers nested deeper than this are left in place.
+pub const MAX_WRAPPERS: usize = 4;
+
+/// Single-file compression wrappers that are unwrapped before parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if data.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+pub fn decompress(data: &[u8], compression: Compression, limit: u64) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    decompress_into(data, compression, limit, &mut out)?;
+    Ok(out)
+}
+
+/// Like [`decompress`], but a payload past [`SPILL_THRESHOLD`] goes to an
+/// unlinked temporary file that is mapped instead of held on the heap.
+pub fn decompress_to_data(data: &[u8], compression: Compression, limit: u64) -> Result<BinaryData> {
+    decompress_spooled(data, compression, limit, SPILL_THRESHOLD)
+}
+
+fn decompress_spooled(
+    data: &[u8],
+    compression: Compression,
+    limit: u64,
+    spill_at: usize,
+) -> Result<BinaryData> {
+    let mut spool = Spool {
+        memory: Vec::new(),
+        file: None,
+        spill_at,
+    };
+    decompress_into(data, compression, limit, &mut spool)?;
+    match spool.file {
+        Some(file) => BinaryData::map(&file),
+        None => Ok(spool.memory.into()),
+    }
+}
+
+fn decompress_into<W: Write>(
+    data: &[u8],
+    compression: Compression,
+    limit: u64,
+    out: &mut W,
+) -> Result<()> {
+    match compression {
+        Compression::Gzip => {
+            let decoder = flate2::read::MultiGzDecoder::new(data);
+            read_limited(decoder, out, limit)?;
+        }
+        Compression::Xz => {
+            // lzma-rs only offers a push-style API, so enforce the limit on the writer side.
+            let mut input = data;
+            let mut writer = LimitedWriter {
+                inner: out,
+                remaining: limit,
+            };
+            lzma_rs::xz_decompress(&mut input, &mut writer)
+                .map_err(|e| anyhow!("xz decompression failed: {:?}", e))?;
+        }
+        Compression::Zstd => {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(data)
+                .map_err(|e| anyhow!("zstd decompression failed: {}", e))?;
+            read_limited(decoder, out, limit)?;
+        }
+    }
+    out.flush().context("Failed to write decompressed data")
+}
+
+fn read_limited<R: Read, W: Write>(reader: R, out: &mut W, limit: u64) -> Result<()> {
+    let read =
+        io::copy(&mut reader.take(limit.saturating_add(1)), out).context("Decompression failed")?;
+    if read > limit {
+        bail!("Decompressed size exceeds limit of {} bytes", limit);
+    }
+    Ok(())
+}
+
+/// Fails writes that would take the total past `remaining` bytes.
+pub(super) struct LimitedWriter<'a, W> {
+    pub(super) inner: &'a mut W,
+    pub(super) remaining: u64,
+}
+
+impl<W: Write> Write for LimitedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() as u64 > self.remaining {
+            return Err(io::Error::other("decompressed size exceeds limit"));
+        }
+        self.remaining -= buf.len() as u64;
+        self.inner.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Collects output in memory until it passes `spill_at` bytes, then moves
+/// it to a temporary file.
+struct Spool {
+    memory: Vec<u8>,
+    file: Option<File>,
+    spill_at: usize,
+}
+
+impl Write for Spool {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(file) = &mut self.file {
+            return file.write(buf);
+        }
+        self.memory.extend_from_slice(buf);
+        if self.memory.len() > self.spill_at {
+            let mut file = temp_file()?;
+            file.write_all(&self.memory)?;
+            self.memory = Vec::new();
+            self.file = Some(file);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A read-write file in the temp directory that is removed once closed.
+fn temp_file() -> io::Result<File> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "binaryinsight-{}-{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_FLAG_DELETE_ON_CLOSE: u32 = 0x0400_0000;
+        options.custom_flags(FILE_FLAG_DELETE_ON_CLOSE);
+    }
+    let file = options.open(&path)?;
+    // Unix keeps the open file (and its mapping) alive after the unlink.
+    #[cfg(not(windows))]
+    std::fs::remove_file(&path)?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(Compression::detect(&gzip(b"abc")), Some(Compression::Gzip));
+        assert_eq!(
+            Compression::detect(b"\xfd7zXZ\x00rest"),
+            Some(Compression::Xz)
+        );
+        assert_eq!(
+            Compression::detect(b"\x28\xb5\x2f\xfdrest"),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect(b"\x7fELF"), None);
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        let packed = gzip(b"hello world");
+        let out = decompress(&packed, Compression::Gzip, 1024).expect("decompress failed");
+        assert_eq!(out, b"hello world");
+    }
+
+    #[test]
+    fn test_decompress_limit() {
+        let packed = gzip(&[0u8; 4096]);
+        assert!(decompress(&packed, Compression::Gzip, 100).is_err());
+        assert!(decompress_spooled(&packed, Compression::Gzip, 100, 16).is_err());
+    }
+
+    #[test]
+    fn test_decompress_spilled() {
+        let payload: Vec<u8> = (0..4096u32).map(|i| i as u8).collect();
+        let packed = gzip(&payload);
+        let small = decompress_spooled(&packed, Compression::Gzip, 8192, 8192).unwrap();
+        assert!(!small.is_mapped());
+        let spilled = decompress_spooled(&packed, Compression::Gzip, 8192, 16).unwrap();
+        assert!(spilled.is_mapped());
+        assert_eq!(&*spilled, &payload[..]);
+    }
+}
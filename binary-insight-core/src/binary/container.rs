@@ -0,0 +1,250 @@
+use super::{compression, BinaryFile, EmbeddedBinary};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// Prefix marking a file deleted by an upper layer (overlayfs whiteout).
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageLayout {
+    /// `docker save` output with a top-level `manifest.json`.
+    Docker,
+    /// OCI image layout with `index.json` and content-addressed blobs.
+    Oci,
+}
+
+impl ImageLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageLayout::Docker => "Docker",
+            ImageLayout::Oci => "OCI",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImageLayer {
+    /// Layer digest (OCI) or tar path (Docker).
+    pub digest: String,
+    pub size: u64,
+    pub files: u64,
+    pub binaries: Vec<EmbeddedBinary>,
+    /// Paths deleted by whiteout entries in this layer.
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainerImage {
+    pub layout: ImageLayout,
+    pub tags: Vec<String>,
+    /// Layers in application order, base layer first.
+    pub layers: Vec<ImageLayer>,
+}
+
+#[derive(Deserialize)]
+struct DockerManifest {
+    #[serde(rename = "RepoTags", default)]
+    repo_tags: Option<Vec<String>>,
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct OciDescriptor {
+    digest: String,
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct OciIndex {
+    #[serde(default)]
+    manifests: Vec<OciDescriptor>,
+    #[serde(default)]
+    layers: Vec<OciDescriptor>,
+}
+
+/// Returns true for a tarball with a Docker or OCI image manifest at its root.
+pub fn is_image_tarball(data: &[u8]) -> bool {
+    if data.get(257..262) != Some(b"ustar") {
+        return false;
+    }
+    index_tar(data).is_ok_and(|entries| {
+        entries.contains_key("manifest.json") || entries.contains_key("index.json")
+    })
+}
+
+pub fn parse_image(data: &[u8]) -> Result<ContainerImage> {
+    let entries = index_tar(data)?;
+    let file = |path: &str| -> Result<&[u8]> {
+        let &(offset, size) = entries
+            .get(path)
+            .ok_or_else(|| anyhow!("Image is missing {}", path))?;
+        Ok(&data[offset..offset + size])
+    };
+
+    let (layout, tags, layer_paths) = if entries.contains_key("manifest.json") {
+        let manifests: Vec<DockerManifest> =
+            serde_json::from_slice(file("manifest.json")?).context("Invalid manifest.json")?;
+        let manifest = manifests
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("manifest.json lists no images"))?;
+        (
+            ImageLayout::Docker,
+            manifest.repo_tags.unwrap_or_default(),
+            manifest.layers,
+        )
+    } else {
+        let index: OciIndex =
+            serde_json::from_slice(file("index.json")?).context("Invalid index.json")?;
+        let tags = index
+            .manifests
+            .iter()
+            .filter_map(|m| {
+                m.annotations
+                    .get("org.opencontainers.image.ref.name")
+                    .cloned()
+            })
+            .collect();
+        // Follow nested indexes (multi-platform images) down to the first manifest.
+        let mut manifest = index;
+        for _ in 0..4 {
+            if !manifest.layers.is_empty() {
+                break;
+            }
+            let Some(first) = manifest.manifests.first() else {
+                break;
+            };
+            manifest = serde_json::from_slice(file(&blob_path(&first.digest))?)
+                .with_context(|| format!("Invalid manifest {}", first.digest))?;
+        }
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|l| blob_path(&l.digest))
+            .collect();
+        (ImageLayout::Oci, tags, layers)
+    };
+
+    let layers = layer_paths
+        .iter()
+        .map(|path| {
+            let blob = file(path)?;
+            let mut layer = analyze_layer(blob).with_context(|| format!("Layer {}", path))?;
+            layer.digest = path
+                .strip_prefix("blobs/")
+                .map(|d| d.replacen('/', ":", 1))
+                .unwrap_or_else(|| path.clone());
+            Ok(layer)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ContainerImage {
+        layout,
+        tags,
+        layers,
+    })
+}
+
+fn blob_path(digest: &str) -> String {
+    format!("blobs/{}", digest.replacen(':', "/", 1))
+}
+
+/// Maps each regular file in a tar archive to its (offset, size) in `data`.
+pub(crate) fn index_tar(data: &[u8]) -> Result<HashMap<String, (usize, usize)>> {
+    let mut archive = tar::Archive::new(Cursor::new(data));
+    let mut entries = HashMap::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()?
+            .to_string_lossy()
+            .trim_start_matches("./")
+            .to_string();
+        let offset = entry.raw_file_position() as usize;
+        let size = entry.size() as usize;
+        if offset + size > data.len() {
+            bail!("Tar entry {} is truncated", path);
+        }
+        entries.insert(path, (offset, size));
+    }
+    Ok(entries)
+}
+
+fn analyze_layer(blob: &[u8]) -> Result<ImageLayer> {
+    let data = match compression::Compression::detect(blob) {
+        Some(kind) => {
+            compression::decompress(blob, kind, compression::DEFAULT_DECOMPRESSION_LIMIT)?
+        }
+        None => blob.to_vec(),
+    };
+    let mut layer = ImageLayer {
+        size: data.len() as u64,
+        ..Default::default()
+    };
+
+    for (path, (offset, size)) in index_tar(&data)? {
+        let file_name = path.rsplit('/').next().unwrap_or(&path);
+        if let Some(removed) = file_name.strip_prefix(WHITEOUT_PREFIX) {
+            let dir = &path[..path.len() - file_name.len()];
+            layer.removed.push(format!("/{}{}", dir, removed));
+            continue;
+        }
+        layer.files += 1;
+
+        let contents = &data[offset..offset + size];
+        if let Some(binary) = BinaryFile::summarize_embedded(format!("/{}", path), contents) {
+            layer.binaries.push(binary);
+        }
+    }
+
+    layer.binaries.sort_by(|a, b| a.path.cmp(&b.path));
+    layer.removed.sort();
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_parse_docker_image() {
+        let base = tar_of(&[("etc/os-release", b"ID=test\n"), ("bin/.wh.sh", b"")]);
+        let manifest =
+            br#"[{"Config":"config.json","RepoTags":["demo:latest"],"Layers":["abc/layer.tar"]}]"#;
+        let image = tar_of(&[("manifest.json", manifest), ("abc/layer.tar", &base)]);
+
+        assert!(is_image_tarball(&image));
+        let parsed = parse_image(&image).expect("parse failed");
+        assert_eq!(parsed.layout, ImageLayout::Docker);
+        assert_eq!(parsed.tags, vec!["demo:latest".to_string()]);
+        assert_eq!(parsed.layers.len(), 1);
+        assert_eq!(parsed.layers[0].files, 1);
+        assert_eq!(parsed.layers[0].removed, vec!["/bin/sh".to_string()]);
+        assert!(parsed.layers[0].binaries.is_empty());
+    }
+
+    #[test]
+    fn test_plain_tar_is_not_image() {
+        let plain = tar_of(&[("README", b"hello")]);
+        assert!(!is_image_tarball(&plain));
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::fs::File;
+use std::ops::Deref;
+use std::path::Path;
+
+/// Contents of a loaded file: mapped from disk, or owned when they were
+/// produced in memory (decompressed payloads, memory images, test data).
+#[derive(Debug)]
+pub enum BinaryData {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl BinaryData {
+    /// Maps `path` read-only. Files that cannot be mapped, such as pipes
+    /// and empty files, are read into memory instead.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).context("Failed to read file")?;
+        let mapped = match file.metadata() {
+            Ok(meta) if meta.is_file() && meta.len() > 0 => {
+                // SAFETY: the mapping is read-only; like every mmap-based
+                // reader we assume the file is not truncated underneath us.
+                unsafe { Mmap::map(&file) }.ok()
+            }
+            _ => None,
+        };
+        match mapped {
+            Some(map) => Ok(BinaryData::Mapped(map)),
+            None => Ok(BinaryData::Owned(
+                std::fs::read(path).context("Failed to read file")?,
+            )),
+        }
+    }
+
+    /// Maps an open file, such as a temporary file of decompressed data.
+    pub(crate) fn map(file: &File) -> Result<Self> {
+        // SAFETY: as in `open`; temporary files are unlinked and private.
+        let map = unsafe { Mmap::map(file) }.context("Failed to map file")?;
+        Ok(BinaryData::Mapped(map))
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, BinaryData::Mapped(_))
+    }
+}
+
+impl Deref for BinaryData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            BinaryData::Owned(data) => data,
+            BinaryData::Mapped(map) => map,
+        }
+    }
+}
+
+impl AsRef<[u8]> for BinaryData {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Vec<u8>> for BinaryData {
+    fn from(data: Vec<u8>) -> Self {
+        BinaryData::Owned(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owned_data() {
+        let data = BinaryData::from(b"\x7fELF".to_vec());
+        assert!(!data.is_mapped());
+        assert_eq!(&data[1..], b"ELF");
+        assert_eq!(data.len(), 4);
+    }
+}
@@ -0,0 +1,271 @@
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Sparse images spanning more than this are rejected rather than zero-filled.
+const MAX_IMAGE_SPAN: u64 = 256 * 1024 * 1024;
+/// Unprogrammed flash reads back as 0xFF, so gaps are filled with it.
+const FILL_BYTE: u8 = 0xff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexFormat {
+    IntelHex,
+    SRecord,
+}
+
+impl HexFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HexFormat::IntelHex => "Intel HEX",
+            HexFormat::SRecord => "Motorola S-record",
+        }
+    }
+
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(data.get(..data.len().min(512))?).ok()?;
+        let first = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+        let is_hex = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit());
+        if let Some(rest) = first.strip_prefix(':') {
+            if rest.len() >= 10 && is_hex(rest) {
+                return Some(HexFormat::IntelHex);
+            }
+        }
+        if let Some(rest) = first.strip_prefix('S') {
+            if rest.len() >= 7 && is_hex(rest) && rest.as_bytes()[0].is_ascii_digit() {
+                return Some(HexFormat::SRecord);
+            }
+        }
+        None
+    }
+}
+
+/// A contiguous run of bytes at a load address.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub addr: u64,
+    pub data: Vec<u8>,
+}
+
+/// Flat memory image reconstructed from a hex file.
+#[derive(Debug, Clone)]
+pub struct LoadImage {
+    pub format: HexFormat,
+    /// Lowest load address; `data[0]` lives here.
+    pub base: u64,
+    pub data: Vec<u8>,
+    /// Contiguous regions as (address, length), in address order.
+    pub segments: Vec<(u64, u64)>,
+    pub entry: Option<u64>,
+}
+
+pub fn parse(data: &[u8], format: HexFormat) -> Result<LoadImage> {
+    let text = std::str::from_utf8(data).context("Hex file is not valid ASCII")?;
+    let (segments, entry) = match format {
+        HexFormat::IntelHex => parse_intel_hex(text)?,
+        HexFormat::SRecord => parse_srecord(text)?,
+    };
+    flatten(format, segments, entry)
+}
+
+fn parse_intel_hex(text: &str) -> Result<(Vec<Segment>, Option<u64>)> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut upper: u64 = 0;
+    let mut entry = None;
+
+    for (lineno, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = line
+            .strip_prefix(':')
+            .ok_or_else(|| anyhow!("Line {}: missing ':'", lineno + 1))?;
+        let bytes = decode_record(record).with_context(|| format!("Line {}", lineno + 1))?;
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            bail!("Line {}: record length mismatch", lineno + 1);
+        }
+        if bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)) != 0 {
+            bail!("Line {}: checksum mismatch", lineno + 1);
+        }
+
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as u64;
+        let payload = &bytes[4..bytes.len() - 1];
+        match bytes[3] {
+            0x00 => push_data(&mut segments, upper + offset, payload),
+            0x01 => break,
+            0x02 => upper = (be_value(payload) as u64) << 4,
+            0x03 => {
+                let v = be_value(payload) as u64;
+                entry = Some(((v >> 16) << 4) + (v & 0xffff));
+            }
+            0x04 => upper = (be_value(payload) as u64) << 16,
+            0x05 => entry = Some(be_value(payload) as u64),
+            other => bail!("Line {}: unknown record type {:02x}", lineno + 1, other),
+        }
+    }
+
+    Ok((segments, entry))
+}
+
+fn parse_srecord(text: &str) -> Result<(Vec<Segment>, Option<u64>)> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut entry = None;
+
+    for (lineno, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = line
+            .strip_prefix('S')
+            .ok_or_else(|| anyhow!("Line {}: missing 'S'", lineno + 1))?;
+        let kind = record.as_bytes().first().copied().unwrap_or(b'?');
+        let digits = record
+            .get(1..)
+            .ok_or_else(|| anyhow!("Line {}: missing record type", lineno + 1))?;
+        let bytes = decode_record(digits).with_context(|| format!("Line {}", lineno + 1))?;
+        if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+            bail!("Line {}: record length mismatch", lineno + 1);
+        }
+        let sum = bytes[..bytes.len() - 1]
+            .iter()
+            .fold(0u8, |acc, &b| acc.wrapping_add(b));
+        if !sum != bytes[bytes.len() - 1] {
+            bail!("Line {}: checksum mismatch", lineno + 1);
+        }
+
+        let body = &bytes[1..bytes.len() - 1];
+        let addr_len = match kind {
+            b'0' | b'1' | b'5' | b'9' => 2,
+            b'2' | b'6' | b'8' => 3,
+            b'3' | b'7' => 4,
+            _ => bail!("Line {}: unknown record type S{}", lineno + 1, kind as char),
+        };
+        if body.len() < addr_len {
+            bail!("Line {}: truncated address", lineno + 1);
+        }
+        let addr = be_value(&body[..addr_len]) as u64;
+        match kind {
+            b'1' | b'2' | b'3' => push_data(&mut segments, addr, &body[addr_len..]),
+            b'7' | b'8' | b'9' => entry = Some(addr),
+            _ => {}
+        }
+    }
+
+    Ok((segments, entry))
+}
+
+fn decode_record(record: &str) -> Result<Vec<u8>> {
+    hex::decode(record).map_err(|e| anyhow!("invalid hex digits: {}", e))
+}
+
+fn be_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn push_data(segments: &mut Vec<Segment>, addr: u64, payload: &[u8]) {
+    if let Some(last) = segments.last_mut() {
+        if last.addr + last.data.len() as u64 == addr {
+            last.data.extend_from_slice(payload);
+            return;
+        }
+    }
+    segments.push(Segment {
+        addr,
+        data: payload.to_vec(),
+    });
+}
+
+fn flatten(format: HexFormat, mut segments: Vec<Segment>, entry: Option<u64>) -> Result<LoadImage> {
+    segments.retain(|s| !s.data.is_empty());
+    segments.sort_by_key(|s| s.addr);
+    let Some(base) = segments.first().map(|s| s.addr) else {
+        bail!("{} file contains no data records", format.name());
+    };
+    let end = segments
+        .iter()
+        .map(|s| s.addr + s.data.len() as u64)
+        .max()
+        .unwrap_or(base);
+    if end - base > MAX_IMAGE_SPAN {
+        bail!(
+            "{} image spans 0x{:x} bytes, exceeding the 0x{:x} limit",
+            format.name(),
+            end - base,
+            MAX_IMAGE_SPAN
+        );
+    }
+
+    let mut data = vec![FILL_BYTE; (end - base) as usize];
+    let mut regions: Vec<(u64, u64)> = Vec::new();
+    for segment in &segments {
+        let start = (segment.addr - base) as usize;
+        data[start..start + segment.data.len()].copy_from_slice(&segment.data);
+        let len = segment.data.len() as u64;
+        match regions.last_mut() {
+            Some((addr, size)) if *addr + *size >= segment.addr => {
+                *size = (*size).max(segment.addr + len - *addr);
+            }
+            _ => regions.push((segment.addr, len)),
+        }
+    }
+
+    Ok(LoadImage {
+        format,
+        base,
+        data,
+        segments: regions,
+        entry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_hex_formats() {
+        assert_eq!(
+            HexFormat::detect(b":020000040800F2\n"),
+            Some(HexFormat::IntelHex)
+        );
+        assert_eq!(
+            HexFormat::detect(b"S00600004844521B\n"),
+            Some(HexFormat::SRecord)
+        );
+        assert_eq!(HexFormat::detect(b"\x7fELF"), None);
+    }
+
+    #[test]
+    fn test_parse_intel_hex() {
+        let text = b":020000040800F2\n:0400000001020304F2\n:0400100005060708D2\n:0400000508000101ED\n:00000001FF\n";
+        let image = parse(text, HexFormat::IntelHex).expect("parse failed");
+        assert_eq!(image.base, 0x0800_0000);
+        assert_eq!(image.data.len(), 0x14);
+        assert_eq!(&image.data[..4], &[1, 2, 3, 4]);
+        assert_eq!(image.data[4], FILL_BYTE);
+        assert_eq!(image.segments, vec![(0x0800_0000, 4), (0x0800_0010, 4)]);
+        assert_eq!(image.entry, Some(0x0800_0101));
+    }
+
+    #[test]
+    fn test_parse_srecord() {
+        let text = b"S00600004844521B\nS1070100AABBCCDDE9\nS9030100FB\n";
+        let image = parse(text, HexFormat::SRecord).expect("parse failed");
+        assert_eq!(image.base, 0x100);
+        assert_eq!(image.data, vec![0xaa, 0xbb, 0xcc, 0xdd]);
+        assert_eq!(image.entry, Some(0x100));
+    }
+
+    #[test]
+    fn test_parse_intel_hex_bad_checksum() {
+        let text = b":0400000001020304F3\n";
+        assert!(parse(text, HexFormat::IntelHex).is_err());
+    }
+
+    #[test]
+    fn test_parse_srecord_missing_type() {
+        for text in ["S1070100AABBCCDDE9\nS\n", "S\u{e9}0701\n"] {
+            let err = parse(text.as_bytes(), HexFormat::SRecord).unwrap_err();
+            assert!(err.to_string().starts_with("Line "), "{}", err);
+        }
+    }
+}
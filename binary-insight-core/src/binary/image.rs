@@ -0,0 +1,333 @@
+use super::BinaryInfo;
+use anyhow::{bail, Context, Result};
+use goblin::{elf, mach, pe, Object};
+
+/// Mapped images larger than this are rejected rather than allocated.
+const MAX_IMAGE_SIZE: u64 = 512 * 1024 * 1024;
+
+const IMAGE_REL_BASED_ABSOLUTE: u16 = 0;
+const IMAGE_REL_BASED_HIGHLOW: u16 = 3;
+const IMAGE_REL_BASED_DIR64: u16 = 10;
+
+/// The binary laid out as the loader would map it.
+#[derive(Debug)]
+pub struct MemoryImage {
+    /// Address of `data[0]` after rebasing.
+    pub base: u64,
+    /// Base address the file was linked for.
+    pub preferred_base: u64,
+    pub data: Vec<u8>,
+    pub relocations_applied: usize,
+}
+
+/// Places headers and sections at their virtual addresses, zero-filling gaps,
+/// and applies base relocations for `base` (default: the preferred base).
+pub fn build_memory_image(
+    data: &[u8],
+    info: &BinaryInfo,
+    base: Option<u64>,
+) -> Result<MemoryImage> {
+    match Object::parse(data) {
+        Ok(Object::Elf(elf)) => elf_image(data, &elf, base),
+        Ok(Object::PE(pe)) => pe_image(data, &pe, base),
+        Ok(Object::Mach(mach::Mach::Binary(macho))) => macho_image(&macho, base),
+        _ => section_image(data, info, base),
+    }
+}
+
+fn allocate(size: u64) -> Result<Vec<u8>> {
+    if size > MAX_IMAGE_SIZE {
+        bail!(
+            "Mapped image is 0x{:x} bytes, exceeding the 0x{:x} limit",
+            size,
+            MAX_IMAGE_SIZE
+        );
+    }
+    Ok(vec![0u8; size as usize])
+}
+
+/// Copies `len` file bytes from `file_offset` to `image_offset`, clamped to both buffers.
+fn place(image: &mut [u8], image_offset: u64, data: &[u8], file_offset: u64, len: u64) {
+    let src_start = (file_offset as usize).min(data.len());
+    let src_end = (file_offset.saturating_add(len) as usize).min(data.len());
+    let dst_start = (image_offset as usize).min(image.len());
+    let n = (src_end - src_start).min(image.len() - dst_start);
+    image[dst_start..dst_start + n].copy_from_slice(&data[src_start..src_start + n]);
+}
+
+fn read_word(image: &[u8], offset: usize, size: usize, little_endian: bool) -> Option<u64> {
+    let bytes = image.get(offset..offset + size)?;
+    Some(match (size, little_endian) {
+        (8, true) => u64::from_le_bytes(bytes.try_into().ok()?),
+        (8, false) => u64::from_be_bytes(bytes.try_into().ok()?),
+        (_, true) => u32::from_le_bytes(bytes.try_into().ok()?) as u64,
+        (_, false) => u32::from_be_bytes(bytes.try_into().ok()?) as u64,
+    })
+}
+
+fn write_word(
+    image: &mut [u8],
+    offset: usize,
+    size: usize,
+    little_endian: bool,
+    value: u64,
+) -> bool {
+    let Some(slot) = image.get_mut(offset..offset + size) else {
+        return false;
+    };
+    match (size, little_endian) {
+        (8, true) => slot.copy_from_slice(&value.to_le_bytes()),
+        (8, false) => slot.copy_from_slice(&value.to_be_bytes()),
+        (_, true) => slot.copy_from_slice(&(value as u32).to_le_bytes()),
+        (_, false) => slot.copy_from_slice(&(value as u32).to_be_bytes()),
+    }
+    true
+}
+
+fn elf_image(data: &[u8], elf: &elf::Elf, base: Option<u64>) -> Result<MemoryImage> {
+    let loads: Vec<_> = elf
+        .program_headers
+        .iter()
+        .filter(|ph| ph.p_type == elf::program_header::PT_LOAD && ph.p_memsz > 0)
+        .collect();
+    let Some(start) = loads.iter().map(|ph| ph.p_vaddr).min() else {
+        bail!("ELF has no loadable segments");
+    };
+    let preferred_base = start & !0xfff;
+    let end = loads
+        .iter()
+        .map(|ph| ph.p_vaddr + ph.p_memsz)
+        .max()
+        .unwrap_or(start);
+    let mut image = allocate(end - preferred_base)?;
+    for ph in &loads {
+        place(
+            &mut image,
+            ph.p_vaddr - preferred_base,
+            data,
+            ph.p_offset,
+            ph.p_filesz,
+        );
+    }
+
+    let base = base.unwrap_or(preferred_base);
+    let bias = base.wrapping_sub(preferred_base);
+    let word = if elf.is_64 { 8 } else { 4 };
+    let relative = match elf.header.e_machine {
+        elf::header::EM_X86_64 => elf::reloc::R_X86_64_RELATIVE,
+        elf::header::EM_386 => elf::reloc::R_386_RELATIVE,
+        elf::header::EM_AARCH64 => elf::reloc::R_AARCH64_RELATIVE,
+        elf::header::EM_ARM => elf::reloc::R_ARM_RELATIVE,
+        _ => u32::MAX,
+    };
+
+    let mut applied = 0;
+    for reloc in elf.dynrelas.iter().chain(elf.dynrels.iter()) {
+        if reloc.r_type != relative {
+            continue;
+        }
+        let Some(offset) = reloc.r_offset.checked_sub(preferred_base) else {
+            continue;
+        };
+        let offset = offset as usize;
+        // RELA carries the addend; REL keeps it in the relocated word.
+        let addend = match reloc.r_addend {
+            Some(addend) => addend as u64,
+            None => match read_word(&image, offset, word, elf.little_endian) {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        if write_word(
+            &mut image,
+            offset,
+            word,
+            elf.little_endian,
+            addend.wrapping_add(bias),
+        ) {
+            applied += 1;
+        }
+    }
+
+    Ok(MemoryImage {
+        base,
+        preferred_base,
+        data: image,
+        relocations_applied: applied,
+    })
+}
+
+fn pe_image(data: &[u8], pe: &pe::PE, base: Option<u64>) -> Result<MemoryImage> {
+    let optional = pe
+        .header
+        .optional_header
+        .context("PE has no optional header")?;
+    let windows = &optional.windows_fields;
+    let mut image = allocate(windows.size_of_image as u64)?;
+    place(&mut image, 0, data, 0, windows.size_of_headers as u64);
+    for section in &pe.sections {
+        let len = match section.virtual_size {
+            0 => section.size_of_raw_data,
+            size => size.min(section.size_of_raw_data),
+        };
+        place(
+            &mut image,
+            section.virtual_address as u64,
+            data,
+            section.pointer_to_raw_data as u64,
+            len as u64,
+        );
+    }
+
+    let preferred_base = windows.image_base;
+    let base = base.unwrap_or(preferred_base);
+    let delta = base.wrapping_sub(preferred_base);
+    let mut applied = 0;
+    if let Some(dir) = optional.data_directories.get_base_relocation_table() {
+        let mut pos = dir.virtual_address as usize;
+        let end = pos + dir.size as usize;
+        while pos + 8 <= end {
+            let (Some(page), Some(block_size)) = (
+                read_word(&image, pos, 4, true),
+                read_word(&image, pos + 4, 4, true),
+            ) else {
+                break;
+            };
+            if block_size < 8 {
+                break;
+            }
+            for entry_pos in (pos + 8..pos + block_size as usize).step_by(2) {
+                let Some(entry) = image
+                    .get(entry_pos..entry_pos + 2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                else {
+                    break;
+                };
+                let target = page as usize + (entry & 0xfff) as usize;
+                let size = match entry >> 12 {
+                    IMAGE_REL_BASED_ABSOLUTE => continue,
+                    IMAGE_REL_BASED_HIGHLOW => 4,
+                    IMAGE_REL_BASED_DIR64 => 8,
+                    _ => continue,
+                };
+                if let Some(value) = read_word(&image, target, size, true) {
+                    if write_word(&mut image, target, size, true, value.wrapping_add(delta)) {
+                        applied += 1;
+                    }
+                }
+            }
+            pos += block_size as usize;
+        }
+    }
+
+    Ok(MemoryImage {
+        base,
+        preferred_base,
+        data: image,
+        relocations_applied: applied,
+    })
+}
+
+/// Mach-O segments are placed verbatim; chained fixups are not applied.
+fn macho_image(macho: &mach::MachO, base: Option<u64>) -> Result<MemoryImage> {
+    let segments: Vec<_> = macho
+        .segments
+        .iter()
+        .filter(|s| s.vmsize > 0 && s.name().map(|n| n != "__PAGEZERO").unwrap_or(true))
+        .collect();
+    let Some(preferred_base) = segments.iter().map(|s| s.vmaddr).min() else {
+        bail!("Mach-O has no mapped segments");
+    };
+    let end = segments
+        .iter()
+        .map(|s| s.vmaddr + s.vmsize)
+        .max()
+        .unwrap_or(preferred_base);
+    let mut image = allocate(end - preferred_base)?;
+    for segment in &segments {
+        let start = (segment.vmaddr - preferred_base) as usize;
+        let n = segment.data.len().min(image.len() - start);
+        image[start..start + n].copy_from_slice(&segment.data[..n]);
+    }
+
+    Ok(MemoryImage {
+        base: base.unwrap_or(preferred_base),
+        preferred_base,
+        data: image,
+        relocations_applied: 0,
+    })
+}
+
+/// Generic layout from the section table for formats without a program header.
+fn section_image(data: &[u8], info: &BinaryInfo, base: Option<u64>) -> Result<MemoryImage> {
+    let sections: Vec<_> = info.sections.iter().filter(|s| s.size > 0).collect();
+    let Some(preferred_base) = sections.iter().map(|s| s.addr).min() else {
+        bail!("{} input has no sections to map", info.format);
+    };
+    let end = sections
+        .iter()
+        .map(|s| s.addr + s.size)
+        .max()
+        .unwrap_or(preferred_base);
+    let mut image = allocate(end - preferred_base)?;
+    for section in &sections {
+        place(
+            &mut image,
+            section.addr - preferred_base,
+            data,
+            section.offset,
+            section.size,
+        );
+    }
+
+    Ok(MemoryImage {
+        base: base.unwrap_or(preferred_base),
+        preferred_base,
+        data: image,
+        relocations_applied: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::SectionInfo;
+
+    #[test]
+    fn test_section_image_fills_gaps() {
+        let data = b"AAAABBBB".to_vec();
+        let info = BinaryInfo {
+            format: "Raw".to_string(),
+            sections: vec![
+                SectionInfo {
+                    name: "a".to_string(),
+                    addr: 0x1000,
+                    size: 4,
+                    offset: 0,
+                    hashes: None,
+                    zero_fill: false,
+                },
+                SectionInfo {
+                    name: "b".to_string(),
+                    addr: 0x1008,
+                    size: 4,
+                    offset: 4,
+                    hashes: None,
+                    zero_fill: false,
+                },
+            ],
+            ..Default::default()
+        };
+        let image = build_memory_image(&data, &info, None).expect("layout failed");
+        assert_eq!(image.base, 0x1000);
+        assert_eq!(image.data, b"AAAA\0\0\0\0BBBB");
+    }
+
+    #[test]
+    fn test_word_roundtrip() {
+        let mut image = vec![0u8; 8];
+        assert!(write_word(&mut image, 0, 8, true, 0x1122334455667788));
+        assert_eq!(read_word(&image, 0, 8, true), Some(0x1122334455667788));
+        assert!(!write_word(&mut image, 6, 4, true, 1));
+    }
+}
@@ -0,0 +1,359 @@
+use serde::Serialize;
+use FileCategory::*;
+
+/// Bytes inspected when deciding whether the input is text.
+const TEXT_SAMPLE: usize = 4096;
+const CFB_MAGIC: &[u8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+/// Offset of the first ISO 9660 volume descriptor's identifier.
+const ISO9660_MAGIC_OFFSET: usize = 0x8001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileCategory {
+    Image,
+    Document,
+    Archive,
+    Audio,
+    Video,
+    Script,
+    Text,
+    Data,
+}
+
+/// Type of a non-executable input, told from its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FileType {
+    pub name: &'static str,
+    pub mime: &'static str,
+    pub category: FileCategory,
+}
+
+const fn file_type(name: &'static str, mime: &'static str, category: FileCategory) -> FileType {
+    FileType {
+        name,
+        mime,
+        category,
+    }
+}
+
+/// Magic bytes at a fixed offset.
+const SIGNATURES: &[(usize, &[u8], FileType)] = &[
+    (
+        0,
+        b"\x89PNG\r\n\x1a\n",
+        file_type("PNG image", "image/png", Image),
+    ),
+    (
+        0,
+        b"\xff\xd8\xff",
+        file_type("JPEG image", "image/jpeg", Image),
+    ),
+    (0, b"GIF87a", file_type("GIF image", "image/gif", Image)),
+    (0, b"GIF89a", file_type("GIF image", "image/gif", Image)),
+    (0, b"II*\0", file_type("TIFF image", "image/tiff", Image)),
+    (0, b"MM\0*", file_type("TIFF image", "image/tiff", Image)),
+    (
+        0,
+        b"8BPS",
+        file_type("Photoshop image", "image/vnd.adobe.photoshop", Image),
+    ),
+    (8, b"WEBP", file_type("WebP image", "image/webp", Image)),
+    (
+        0,
+        b"%PDF-",
+        file_type("PDF document", "application/pdf", Document),
+    ),
+    (
+        0,
+        b"{\\rtf",
+        file_type("RTF document", "application/rtf", Document),
+    ),
+    (
+        0,
+        b"%!PS",
+        file_type("PostScript document", "application/postscript", Document),
+    ),
+    (
+        0,
+        b"Rar!\x1a\x07",
+        file_type("RAR archive", "application/vnd.rar", Archive),
+    ),
+    (
+        0,
+        b"BZh",
+        file_type("bzip2 data", "application/x-bzip2", Archive),
+    ),
+    (
+        0,
+        b"\x04\x22\x4d\x18",
+        file_type("LZ4 data", "application/x-lz4", Archive),
+    ),
+    (
+        0,
+        b"MSCF",
+        file_type(
+            "Cabinet archive",
+            "application/vnd.ms-cab-compressed",
+            Archive,
+        ),
+    ),
+    (
+        0,
+        b"!<arch>\n",
+        file_type("ar archive", "application/x-archive", Archive),
+    ),
+    (
+        ISO9660_MAGIC_OFFSET,
+        b"CD001",
+        file_type("ISO 9660 image", "application/x-iso9660-image", Archive),
+    ),
+    (0, b"ID3", file_type("MP3 audio", "audio/mpeg", Audio)),
+    (0, b"fLaC", file_type("FLAC audio", "audio/flac", Audio)),
+    (0, b"OggS", file_type("Ogg media", "audio/ogg", Audio)),
+    (8, b"WAVE", file_type("WAV audio", "audio/wav", Audio)),
+    (8, b"AVI ", file_type("AVI video", "video/x-msvideo", Video)),
+    (4, b"ftyp", file_type("MP4 video", "video/mp4", Video)),
+    (
+        0,
+        b"\x1a\x45\xdf\xa3",
+        file_type("Matroska video", "video/x-matroska", Video),
+    ),
+    (
+        0,
+        b"SQLite format 3\0",
+        file_type("SQLite database", "application/vnd.sqlite3", Data),
+    ),
+    (
+        0,
+        b"\0asm",
+        file_type("WebAssembly module", "application/wasm", Data),
+    ),
+    (
+        0,
+        b"dex\n",
+        file_type("Dalvik executable", "application/vnd.android.dex", Data),
+    ),
+    (
+        0,
+        b"L\0\0\0\x01\x14\x02\0",
+        file_type("Windows shortcut", "application/x-ms-shortcut", Data),
+    ),
+    (
+        0,
+        b"regf",
+        file_type("Windows registry hive", "application/octet-stream", Data),
+    ),
+    (
+        0,
+        b"ElfFile\0",
+        file_type("Windows event log", "application/octet-stream", Data),
+    ),
+    (
+        0,
+        b"\xd4\xc3\xb2\xa1",
+        file_type("pcap capture", "application/vnd.tcpdump.pcap", Data),
+    ),
+    (
+        0,
+        b"\xa1\xb2\xc3\xd4",
+        file_type("pcap capture", "application/vnd.tcpdump.pcap", Data),
+    ),
+    (
+        0,
+        b"\x0a\x0d\x0d\x0a",
+        file_type("pcapng capture", "application/x-pcapng", Data),
+    ),
+];
+
+/// OLE2 documents by the UTF-16 name of their main stream.
+const OLE_DOCUMENTS: &[(&[u8], FileType)] = &[
+    (
+        b"W\0o\0r\0d\0D\0o\0c\0u\0m\0e\0n\0t\0",
+        file_type("Word 97-2003 document", "application/msword", Document),
+    ),
+    (
+        b"W\0o\0r\0k\0b\0o\0o\0k\0",
+        file_type(
+            "Excel 97-2003 workbook",
+            "application/vnd.ms-excel",
+            Document,
+        ),
+    ),
+    (
+        b"P\0o\0w\0e\0r\0P\0o\0i\0n\0t\0 \0D\0o\0c\0u\0m\0e\0n\0t\0",
+        file_type(
+            "PowerPoint 97-2003 presentation",
+            "application/vnd.ms-powerpoint",
+            Document,
+        ),
+    ),
+];
+const OLE_FILE: FileType = file_type("OLE2 compound file", "application/x-ole-storage", Document);
+
+/// Scripts by the interpreter named in their `#!` line.
+const INTERPRETERS: &[(&str, FileType)] = &[
+    (
+        "python",
+        file_type("Python script", "text/x-python", Script),
+    ),
+    ("node", file_type("JavaScript", "text/javascript", Script)),
+    ("perl", file_type("Perl script", "text/x-perl", Script)),
+    ("ruby", file_type("Ruby script", "text/x-ruby", Script)),
+    ("php", file_type("PHP script", "application/x-php", Script)),
+    (
+        "pwsh",
+        file_type("PowerShell script", "text/x-powershell", Script),
+    ),
+    (
+        "sh",
+        file_type("Shell script", "text/x-shellscript", Script),
+    ),
+];
+const SCRIPT: FileType = file_type("Script", "text/plain", Script);
+
+/// Text formats by how they start, lowercased.
+const TEXT_PREFIXES: &[(&str, FileType)] = &[
+    (
+        "<!doctype html",
+        file_type("HTML document", "text/html", Document),
+    ),
+    ("<html", file_type("HTML document", "text/html", Document)),
+    ("<svg", file_type("SVG image", "image/svg+xml", Image)),
+    ("<?xml", file_type("XML document", "text/xml", Document)),
+    (
+        "-----begin ",
+        file_type("PEM data", "application/x-pem-file", Text),
+    ),
+    (
+        "@echo off",
+        file_type("Windows batch file", "text/x-msdos-batch", Script),
+    ),
+    (
+        "<?php",
+        file_type("PHP script", "application/x-php", Script),
+    ),
+];
+const JSON: FileType = file_type("JSON data", "application/json", Text);
+const TEXT: FileType = file_type("ASCII text", "text/plain", Text);
+const UTF8_TEXT: FileType = file_type("UTF-8 text", "text/plain", Text);
+
+/// Identifies images, documents, archives, media and scripts by content,
+/// for inputs no parser took. `None` for binary data of unknown type.
+pub fn detect_file_type(data: &[u8]) -> Option<FileType> {
+    if data.starts_with(CFB_MAGIC) {
+        let ole = OLE_DOCUMENTS
+            .iter()
+            .find(|(stream, _)| data.windows(stream.len()).any(|w| w == *stream));
+        return Some(ole.map_or(OLE_FILE, |(_, kind)| *kind));
+    }
+    // RIFF containers carry their form type at offset 8.
+    if let Some((_, _, kind)) = SIGNATURES.iter().find(|(offset, magic, _)| {
+        data.get(*offset..offset + magic.len()) == Some(*magic)
+            && (*offset != 8 || data.starts_with(b"RIFF"))
+    }) {
+        return Some(*kind);
+    }
+    detect_text(data)
+}
+
+fn detect_text(data: &[u8]) -> Option<FileType> {
+    let sample = &data[..data.len().min(TEXT_SAMPLE)];
+    let sample = sample.strip_prefix(b"\xef\xbb\xbf").unwrap_or(sample);
+    if sample.is_empty() || !is_text(sample) {
+        return None;
+    }
+    if let Some(line) = sample.strip_prefix(b"#!") {
+        let line = String::from_utf8_lossy(line.split(|&b| b == b'\n').next().unwrap_or(&[]));
+        // `#!/usr/bin/env python3` names the interpreter after `env`.
+        let interpreter = line
+            .split_whitespace()
+            .map(|word| word.rsplit('/').next().unwrap_or(word))
+            .find(|word| *word != "env" && !word.starts_with('-'))
+            .unwrap_or("");
+        let kind = INTERPRETERS
+            .iter()
+            .find(|(name, _)| interpreter.starts_with(name) || interpreter.ends_with(name))
+            .map_or(SCRIPT, |(_, kind)| *kind);
+        return Some(kind);
+    }
+    let head = String::from_utf8_lossy(sample)
+        .trim_start()
+        .to_ascii_lowercase();
+    if let Some((_, kind)) = TEXT_PREFIXES
+        .iter()
+        .find(|(prefix, _)| head.starts_with(prefix))
+    {
+        return Some(*kind);
+    }
+    if (head.starts_with('{') || head.starts_with('['))
+        && serde_json::from_slice::<serde::de::IgnoredAny>(data).is_ok()
+    {
+        return Some(JSON);
+    }
+    Some(if sample.is_ascii() { TEXT } else { UTF8_TEXT })
+}
+
+/// Printable UTF-8 without NULs; a character cut off at the end of the
+/// sample still counts.
+fn is_text(sample: &[u8]) -> bool {
+    let valid = match std::str::from_utf8(sample) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&sample[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    valid
+        .chars()
+        .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t' | '\x0c'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(data: &[u8]) -> Option<&'static str> {
+        detect_file_type(data).map(|kind| kind.name)
+    }
+
+    #[test]
+    fn test_detect_binary_types() {
+        assert_eq!(name(b"%PDF-1.7\n%\xe2\xe3"), Some("PDF document"));
+        assert_eq!(
+            detect_file_type(b"\xff\xd8\xff\xe0\0\x10JFIF")
+                .unwrap()
+                .mime,
+            "image/jpeg"
+        );
+        assert_eq!(name(b"RIFF\x24\0\0\0WAVEfmt "), Some("WAV audio"));
+        assert_eq!(name(b"JUNK\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(name(b"\0\0\0\x18ftypmp42"), Some("MP4 video"));
+
+        let mut doc = CFB_MAGIC.to_vec();
+        doc.resize(512, 0);
+        assert_eq!(name(&doc), Some("OLE2 compound file"));
+        doc.extend(b"W\0o\0r\0d\0D\0o\0c\0u\0m\0e\0n\0t\0");
+        assert_eq!(name(&doc), Some("Word 97-2003 document"));
+
+        let mut iso = vec![0u8; ISO9660_MAGIC_OFFSET];
+        iso.extend(b"CD001\x01");
+        assert_eq!(name(&iso), Some("ISO 9660 image"));
+        assert_eq!(name(b"\x00\x01\x02\x03 opaque"), None);
+    }
+
+    #[test]
+    fn test_detect_text_types() {
+        assert_eq!(
+            name(b"#!/usr/bin/env python3\nprint(1)\n"),
+            Some("Python script")
+        );
+        assert_eq!(name(b"#!/bin/bash\necho hi\n"), Some("Shell script"));
+        assert_eq!(name(b"#!/opt/weird\n"), Some("Script"));
+        assert_eq!(name(b"  <!DOCTYPE html>\n<html>"), Some("HTML document"));
+        assert_eq!(name(b"{\"a\": [1, 2]}\n"), Some("JSON data"));
+        assert_eq!(name(b"{ not json"), Some("ASCII text"));
+        assert_eq!(name("caf\u{e9} au lait\n".as_bytes()), Some("UTF-8 text"));
+        assert_eq!(name(b"text with a \0 NUL"), None);
+        assert_eq!(name(b""), None);
+    }
+}
@@ -0,0 +1,108 @@
+use super::SectionInfo;
+
+/// Address/offset translation over the loaded sections of a binary.
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryMap<'a> {
+    sections: &'a [SectionInfo],
+    /// ELF sections at address zero are not loaded (debug info, symbol
+    /// tables), whereas raw blobs and hex images may be mapped at zero.
+    zero_is_unmapped: bool,
+}
+
+impl<'a> MemoryMap<'a> {
+    pub fn new(sections: &'a [SectionInfo], zero_is_unmapped: bool) -> Self {
+        Self {
+            sections,
+            zero_is_unmapped,
+        }
+    }
+
+    /// Sections that occupy memory.
+    pub fn sections(&self) -> impl Iterator<Item = &'a SectionInfo> + '_ {
+        self.sections
+            .iter()
+            .filter(|s| s.size > 0 && !(s.addr == 0 && self.zero_is_unmapped))
+    }
+
+    /// Returns the loaded section that contains `addr`.
+    pub fn section_for_addr(&self, addr: u64) -> Option<&'a SectionInfo> {
+        self.sections()
+            .find(|s| addr >= s.addr && addr - s.addr < s.size)
+    }
+
+    /// Returns the loaded section whose file bytes contain `offset`.
+    pub fn section_for_offset(&self, offset: u64) -> Option<&'a SectionInfo> {
+        self.sections()
+            .find(|s| !s.zero_fill && offset >= s.offset && offset - s.offset < s.size)
+    }
+
+    /// Maps a virtual address to a file offset.
+    pub fn addr_to_offset(&self, addr: u64) -> Option<u64> {
+        self.section_for_addr(addr)
+            .and_then(|s| s.offset.checked_add(addr - s.addr))
+    }
+
+    /// Maps a file offset back to a virtual address.
+    pub fn offset_to_addr(&self, offset: u64) -> Option<u64> {
+        self.section_for_offset(offset)
+            .and_then(|s| s.addr.checked_add(offset - s.offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, addr: u64, size: u64, offset: u64) -> SectionInfo {
+        SectionInfo {
+            name: name.to_string(),
+            addr,
+            size,
+            offset,
+            hashes: None,
+            zero_fill: false,
+        }
+    }
+
+    #[test]
+    fn test_translation() {
+        let sections = [
+            section(".text", 0x1000, 0x100, 0x400),
+            section(".comment", 0, 0x20, 0x600),
+        ];
+        let map = MemoryMap::new(&sections, true);
+        assert_eq!(map.addr_to_offset(0x1010), Some(0x410));
+        assert_eq!(map.offset_to_addr(0x410), Some(0x1010));
+        assert_eq!(map.addr_to_offset(0x10), None);
+        assert_eq!(map.offset_to_addr(0x600), None);
+        assert_eq!(map.sections().count(), 1);
+
+        let raw = [section("raw", 0, 0x20, 0)];
+        assert_eq!(MemoryMap::new(&raw, false).addr_to_offset(0x10), Some(0x10));
+    }
+
+    #[test]
+    fn test_huge_section() {
+        let huge = section(".text", 0x1000, u64::MAX - 0x100, 0x400);
+        assert_eq!(huge.file_range(0x800), 0x400..0x800);
+        assert_eq!(huge.file_range(0x100), 0x100..0x100);
+        let sections = [huge];
+        let map = MemoryMap::new(&sections, true);
+        assert_eq!(map.addr_to_offset(0x1010), Some(0x410));
+        assert_eq!(map.offset_to_addr(u64::MAX - 1), None);
+    }
+
+    #[test]
+    fn test_zero_fill_section() {
+        let mut bss = section(".bss", 0x2000, 0x100, 0x400);
+        bss.zero_fill = true;
+        assert!(bss.file_range(0x800).is_empty());
+        let sections = [bss, section(".comment", 0, 0x20, 0x400)];
+        let map = MemoryMap::new(&sections, true);
+        assert_eq!(
+            map.section_for_addr(0x2010).map(|s| s.name.as_str()),
+            Some(".bss")
+        );
+        assert_eq!(map.offset_to_addr(0x410), None);
+    }
+}
@@ -1,20 +1,240 @@
 use crate::analysis;
 use anyhow::{Context, Result};
 use goblin::{elf, mach, pe, Object};
-use std::fs;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 use std::path::Path;
+use tracing::warn;
+
+mod arch;
+pub mod archive;
+pub mod artifacts;
+pub mod carve;
+pub mod compression;
+#[doc(hidden)]
+pub mod container;
+mod data;
+#[doc(hidden)]
+pub mod hexfile;
+pub mod image;
+pub mod magic;
+mod memory_map;
+mod options;
+pub mod package;
+mod recovery;
+pub mod segments;
+mod sevenzip;
+pub mod uefi;
+
+pub use arch::Arch;
+pub use data::BinaryData;
+pub use memory_map::MemoryMap;
+pub use options::AnalysisOptions;
+
+/// Format of inputs that no parser recognizes.
+const UNKNOWN_FORMAT: &str = "Unknown/Archive";
+const GOBLIN_PEEK_SIZE: usize = 16;
+/// ELF header flag marking the MIPS n32 ABI.
+const EF_MIPS_ABI2: u32 = 0x20;
+/// Decoding from a function start is skipped when the target lies further in than this.
+const MAX_CONTEXT_DECODE: u64 = 0x10000;
+/// Upper bound on the bytes decoded for a single function's CFG.
+const MAX_FUNCTION_DECODE: u64 = 0x10000;
 
 #[derive(Debug, Default)]
 pub struct BinaryInfo {
     pub format: String,
     pub arch: String,
+    /// ARM/Thumb state switches by address (`true` = Thumb), from ELF mapping
+    /// and function symbols. Only consulted when `arch` is "arm".
+    pub arm_modes: BTreeMap<u64, bool>,
     pub entry_point: u64,
+    /// Preferred load address that absolute pointers are relative to (PE only;
+    /// section addresses are RVAs).
+    pub image_base: u64,
     pub sections: Vec<SectionInfo>,
     pub symbols: Vec<SymbolInfo>,
+    pub imports: Vec<ImportInfo>,
+    /// Shared libraries the binary needs at load time (ELF `DT_NEEDED`, PE
+    /// import DLLs, Mach-O dylibs).
+    pub libraries: Vec<String>,
+    /// Functions exported for other modules to call.
+    pub exports: Vec<SymbolInfo>,
+    /// Function boundaries found by [`BinaryFile::discover_functions`], by start.
+    pub functions: Vec<analysis::functions::FunctionInfo>,
     pub security: analysis::SecurityFeatures,
     pub strings: Vec<String>,
+    /// File offsets of `strings`, for lookups by position.
+    pub string_index: analysis::StringIndex,
     pub disassembly: Vec<analysis::disassembly::InstructionInfo>,
     pub analysis: Option<AnalysisData>,
+    /// Compression wrappers stripped before parsing, outermost first.
+    pub compression: Vec<compression::Compression>,
+    pub kernel: Option<analysis::kernel::KernelInfo>,
+    pub kernel_module: Option<analysis::kernel::KernelModuleInfo>,
+    pub linker: Option<analysis::linker::LinkerSummary>,
+    pub go: Option<analysis::golang::GoInfo>,
+    pub rust: Option<analysis::rust::RustInfo>,
+    /// Symbol-based fuzzy hash of an ELF file, see [`analysis::hashes::telfhash`].
+    pub telfhash: Option<String>,
+    /// Authenticode hash of a PE file, see [`analysis::hashes::authentihash`].
+    pub authentihash: Option<String>,
+    /// Main icon of a PE file.
+    pub icon: Option<analysis::icon::IconInfo>,
+    /// CLI metadata of a .NET assembly.
+    pub dotnet: Option<analysis::dotnet::DotNetInfo>,
+    /// PyInstaller CArchive appended to the executable, if any.
+    pub pyinstaller: Option<analysis::pyinstaller::PyInstallerArchive>,
+    /// Installer framework (NSIS, Inno Setup, MSI, WiX) of a setup program.
+    pub installer: Option<analysis::installer::InstallerInfo>,
+    /// Source language and packager, see [`analysis::language::classify`].
+    pub language: Option<analysis::language::LanguageInfo>,
+    /// Content-based type of an input no parser recognized.
+    pub file_type: Option<magic::FileType>,
+    /// Electron, NW.js or Node.js runtime an application is built on.
+    pub app_runtime: Option<analysis::app_runtime::AppRuntime>,
+    /// Layers and per-layer binaries of a container image tarball.
+    pub container: Option<container::ContainerImage>,
+    /// Metadata and payload of a .deb/.rpm package.
+    pub package: Option<package::PackageInfo>,
+    /// Members of a ZIP/tar/7z archive.
+    pub archive: Option<archive::ArchiveInfo>,
+    /// UEFI firmware volumes found in the input.
+    pub firmware: Vec<uefi::FirmwareVolume>,
+    /// Files embedded in the input, found by their magic bytes.
+    pub embedded_files: Vec<carve::CarvedFile>,
+    /// Embedded executables and archive members analyzed as files of their
+    /// own, filled by [`BinaryFile::unpack_embedded`].
+    pub unpacked: Vec<UnpackedBinary>,
+    pub yara_matches: Vec<analysis::yara::YaraMatch>,
+    /// Label and earlier sightings of the file in the local hash database.
+    pub hash_record: Option<analysis::hashdb::HashRecord>,
+    /// MalwareBazaar report for the file's SHA-256, when one was looked up
+    /// and found.
+    pub bazaar: Option<analysis::bazaar::BazaarReport>,
+    /// Library functions recognized by signature packs.
+    pub signature_matches: Vec<analysis::flirt::SignatureMatch>,
+    /// Strings recovered by the deobfuscators selected for this run.
+    pub decoded_strings: Vec<analysis::deobfuscate::DecodedString>,
+    /// Non-Latin strings from the charsets selected for this run.
+    pub charset_strings: Vec<analysis::charset::CharsetString>,
+    /// Code and pointer references to entries of `strings`.
+    pub string_xrefs: Vec<analysis::string_xrefs::StringXref>,
+    /// Crypto primitives recognized by their constants (AES, SHA-256, ...).
+    pub crypto_constants: Vec<analysis::crypto::CryptoConstant>,
+    /// X.509 certificates found anywhere in the file.
+    pub certificates: Vec<analysis::x509::Certificate>,
+    /// Private key blobs found anywhere in the file.
+    pub private_keys: Vec<analysis::x509::PrivateKey>,
+    /// Deprecated hash/cipher usage (MD5, RC4, DES, ...).
+    pub weak_crypto: Vec<analysis::crypto::WeakCryptoUsage>,
+    /// Known vulnerabilities of embedded components, filled by
+    /// [`analysis::vulns::find_vulnerabilities`].
+    pub vulnerabilities: Vec<analysis::vulns::Vulnerability>,
+    /// Unsafe C library functions (`gets`, `strcpy`, ...) in use.
+    pub insecure_functions: Vec<analysis::insecure::InsecureFunction>,
+    /// System call sites, filled by [`analysis::syscalls::find_syscalls`].
+    pub syscalls: Vec<analysis::syscalls::Syscall>,
+    /// Anti-debugging checks, filled by [`analysis::evasion::find_anti_debug`].
+    pub anti_debug: Vec<analysis::evasion::EvasionIndicator>,
+    /// VM and sandbox checks, filled by [`analysis::evasion::find_anti_vm`].
+    pub anti_vm: Vec<analysis::evasion::EvasionIndicator>,
+    /// Matched capability rules, filled by
+    /// [`analysis::capabilities::find_capabilities`].
+    pub capabilities: Vec<analysis::capabilities::Capability>,
+    /// Where the entry point lies, if unusual (packed or infected files).
+    pub entry_anomalies: Vec<analysis::entry::EntryAnomaly>,
+    /// Oddities in the section table and headers.
+    pub anomalies: Vec<analysis::anomalies::Anomaly>,
+    /// PE checksum and header consistency.
+    pub pe_headers: Option<analysis::pe_headers::PeHeaderCheck>,
+    /// Symbol table and debug information present in the file.
+    pub debug_info: Option<analysis::debuginfo::DebugInfo>,
+    /// Parts of a malformed file that [`BinaryFile::load_permissive`] skipped.
+    pub parse_warnings: Vec<ParseWarning>,
+}
+
+/// A table or header that failed to parse; analysis went on without it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseWarning {
+    /// What was being parsed ("ELF section headers", "PE header", ...).
+    pub stage: &'static str,
+    pub message: String,
+}
+
+impl BinaryInfo {
+    /// The stripped compression wrappers, as in `gzip > xz`.
+    pub fn wrapper_names(&self) -> Option<String> {
+        (!self.compression.is_empty()).then(|| {
+            let names: Vec<_> = self.compression.iter().map(|c| c.name()).collect();
+            names.join(" > ")
+        })
+    }
+
+    /// Address/offset translation over the loaded sections.
+    pub fn memory_map(&self) -> MemoryMap<'_> {
+        MemoryMap::new(&self.sections, self.format == "ELF")
+    }
+
+    /// The instruction set, parsed from `arch`.
+    pub fn architecture(&self) -> Arch {
+        Arch::from_name(&self.arch)
+    }
+
+    /// The disassembler mode for code at `addr`: "thumb" inside Thumb regions
+    /// of an ARM binary, otherwise `arch`.
+    pub fn arch_at(&self, addr: u64) -> &str {
+        match self.arm_modes.range(..=addr).next_back() {
+            Some((_, true)) if self.arch == "arm" => "thumb",
+            _ => &self.arch,
+        }
+    }
+
+    /// Returns the loaded section that contains `addr`.
+    pub fn section_for_addr(&self, addr: u64) -> Option<&SectionInfo> {
+        self.memory_map().section_for_addr(addr)
+    }
+
+    /// Maps a virtual address to a file offset using the section table.
+    pub fn addr_to_offset(&self, addr: u64) -> Option<u64> {
+        self.memory_map().addr_to_offset(addr)
+    }
+
+    /// File offsets of the virtual addresses in `range`, if all of them are
+    /// mapped from one contiguous stretch of the file.
+    pub fn addr_range_to_offsets(&self, range: Range<u64>) -> Option<Range<u64>> {
+        if range.is_empty() {
+            return None;
+        }
+        let start = self.addr_to_offset(range.start)?;
+        let last = range.end - 1 - range.start;
+        (self.addr_to_offset(range.end - 1)? == start + last).then_some(start..start + last + 1)
+    }
+
+    /// Returns the symbol that most plausibly contains `addr`: the closest
+    /// symbol at or below it within the same section.
+    pub fn containing_function(&self, addr: u64) -> Option<&SymbolInfo> {
+        let section = self.section_for_addr(addr)?;
+        self.symbols
+            .iter()
+            .filter(|sym| !sym.name.is_empty() && sym.addr >= section.addr && sym.addr <= addr)
+            .max_by_key(|sym| sym.addr)
+    }
+
+    /// The discovered function starting exactly at `start`.
+    pub fn function_at(&self, start: u64) -> Option<&analysis::functions::FunctionInfo> {
+        let i = self
+            .functions
+            .binary_search_by_key(&start, |f| f.start)
+            .ok()?;
+        self.functions.get(i)
+    }
+
+    /// Maps a file offset back to a virtual address using the section table.
+    pub fn offset_to_addr(&self, offset: u64) -> Option<u64> {
+        self.memory_map().offset_to_addr(offset)
+    }
 }
 
 #[derive(Debug)]
@@ -23,50 +243,578 @@ pub struct SectionInfo {
     pub addr: u64,
     pub size: u64,
     pub offset: u64,
+    /// MD5 and SHA-256 of the section's bytes in the file; `None` for
+    /// sections that occupy none (`.bss`, zero-fill).
+    pub hashes: Option<analysis::hashes::SectionHashes>,
+    /// Takes no bytes in the file and is zero-filled when loaded
+    /// (`SHT_NOBITS`, Mach-O zerofill, PE sections without raw data).
+    pub zero_fill: bool,
 }
 
-#[derive(Debug)]
+impl SectionInfo {
+    /// Code sections by name (`.text`, `__text`, `CODE`, ...); raw blobs count as code.
+    pub fn is_code(&self) -> bool {
+        self.name.contains("text") || self.name == "CODE" || self.name == "raw"
+    }
+
+    /// File range of the section in a file of `len` bytes, clamped so a
+    /// bogus offset or size never reaches past the end; empty for
+    /// zero-fill sections.
+    pub fn file_range(&self, len: usize) -> Range<usize> {
+        let start = usize::try_from(self.offset).unwrap_or(usize::MAX).min(len);
+        if self.zero_fill {
+            return start..start;
+        }
+        let size = usize::try_from(self.size).unwrap_or(usize::MAX);
+        start..start.saturating_add(size).min(len)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SymbolInfo {
     pub name: String,
     pub addr: u64,
 }
 
+#[derive(Debug, Clone)]
+pub struct ImportInfo {
+    pub name: String,
+    /// Providing library, when the format records it (PE, Mach-O).
+    pub library: Option<String>,
+    /// Address of the GOT/IAT pointer the loader fills in, where known.
+    pub slot: Option<u64>,
+}
+
+/// The kind letter of an ELF mapping symbol (`$a`, `$t`, `$d`, `$x`, with an
+/// optional `.suffix`).
+fn mapping_symbol(name: &str) -> Option<char> {
+    let rest = name.strip_prefix('$')?;
+    let kind = rest.chars().next()?;
+    let suffix = &rest[kind.len_utf8()..];
+    (matches!(kind, 'a' | 't' | 'd' | 'x') && (suffix.is_empty() || suffix.starts_with('.')))
+        .then_some(kind)
+}
+
+/// `st_value` without the Thumb bit, for ARM function symbols.
+fn code_addr(sym: elf::Sym, machine: u16) -> u64 {
+    if machine == elf::header::EM_ARM && sym.st_type() == elf::sym::STT_FUNC {
+        sym.st_value & !1
+    } else {
+        sym.st_value
+    }
+}
+
+/// An executable or shared object found inside a container format
+/// (image layer, package payload).
+#[derive(Debug, Clone)]
+pub struct EmbeddedBinary {
+    pub path: String,
+    pub size: u64,
+    pub format: String,
+    pub arch: String,
+    pub security: analysis::SecurityFeatures,
+}
+
+/// Mach-O section types that take no space in the file.
+fn is_zerofill(flags: u32) -> bool {
+    use goblin::mach::constants::{
+        SECTION_TYPE, S_GB_ZEROFILL, S_THREAD_LOCAL_ZEROFILL, S_ZEROFILL,
+    };
+    matches!(
+        flags & SECTION_TYPE,
+        S_ZEROFILL | S_GB_ZEROFILL | S_THREAD_LOCAL_ZEROFILL
+    )
+}
+
+fn is_executable_format(data: &[u8]) -> bool {
+    match data.get(..4) {
+        Some(b"\x7fELF") => true,
+        Some([b'M', b'Z', _, _]) => true,
+        Some(magic) => matches!(
+            u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]),
+            0xfeedface | 0xfeedfacf | 0xcefaedfe | 0xcffaedfe | 0xbebafeca
+        ),
+        None => false,
+    }
+}
+
+/// An executable or archive member taken out of its parent and loaded on
+/// its own.
+#[derive(Debug)]
+pub struct UnpackedBinary {
+    /// Offset in the parent's data; for archive members, of the member's
+    /// stored data.
+    pub offset: u64,
+    pub file: BinaryFile,
+}
+
+#[derive(Debug)]
 pub struct BinaryFile {
     pub name: String,
-    pub data: Vec<u8>,
+    pub data: BinaryData,
     pub info: BinaryInfo,
+    pub(crate) passes: options::Passes,
 }
 
 impl BinaryFile {
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load_with(path, AnalysisOptions::default())
+    }
+
+    /// Loads `path` running only the passes `options` asks for.
+    pub fn load_with<P: AsRef<Path>>(path: P, options: AnalysisOptions) -> Result<Self> {
         let name = path
             .as_ref()
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
-        let data = fs::read(path).context("Failed to read file")?;
+        let data = BinaryData::open(path)?;
 
-        let info = Self::parse(&data)?;
+        Self::from_bytes_with_options(name, data, options)
+    }
 
-        Ok(Self { name, data, info })
+    /// Like [`Self::load`], but a malformed file still loads with whatever
+    /// headers parse; the rest is listed in `info.parse_warnings`.
+    pub fn load_permissive<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let options = AnalysisOptions {
+            permissive: true,
+            ..Default::default()
+        };
+        Self::load_with(path, options)
     }
 
-    fn parse(data: &[u8]) -> Result<BinaryInfo> {
-        let mut info = match Object::parse(data)? {
-            Object::Elf(elf) => Self::parse_elf(&elf)?,
-            Object::PE(pe) => Self::parse_pe(&pe)?,
-            Object::Mach(mach) => Self::parse_mach(&mach)?,
-            _ => BinaryInfo {
-                format: "Unknown/Archive".to_string(),
-                ..Default::default()
+    /// Loads an arbitrary blob (shellcode, memory dump) without format
+    /// detection, treating it as code for `arch` mapped at `base`.
+    pub fn load_raw<P: AsRef<Path>>(path: P, arch: &str, base: u64) -> Result<Self> {
+        let name = path
+            .as_ref()
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let data = BinaryData::open(path)?;
+
+        Ok(Self::from_raw(name, data, arch, base))
+    }
+
+    pub fn from_raw(name: String, data: impl Into<BinaryData>, arch: &str, base: u64) -> Self {
+        let data = data.into();
+        let (strings, string_index) = analysis::extract_strings_indexed(&data);
+        let info = BinaryInfo {
+            format: "Raw".to_string(),
+            arch: arch.to_string(),
+            entry_point: base,
+            sections: vec![SectionInfo {
+                name: "raw".to_string(),
+                addr: base,
+                size: data.len() as u64,
+                offset: 0,
+                hashes: None,
+                zero_fill: false,
+            }],
+            strings,
+            string_index,
+            ..Default::default()
+        };
+
+        // Blobs have no toolchain or embedded files to scan for.
+        let passes = options::Passes {
+            strings: true,
+            scans: true,
+        };
+        Self {
+            name,
+            data,
+            info,
+            passes,
+        }
+    }
+
+    pub fn from_bytes(name: String, data: impl Into<BinaryData>) -> Result<Self> {
+        Self::from_bytes_with_options(name, data, AnalysisOptions::default())
+    }
+
+    /// [`Self::from_bytes`] in permissive mode; see [`Self::load_permissive`].
+    pub fn from_bytes_permissive(name: String, data: impl Into<BinaryData>) -> Self {
+        let options = AnalysisOptions {
+            permissive: true,
+            ..Default::default()
+        };
+        Self::from_bytes_with_options(name, data, options)
+            .expect("permissive parsing does not fail")
+    }
+
+    pub fn from_bytes_with_options(
+        name: String,
+        data: impl Into<BinaryData>,
+        options: AnalysisOptions,
+    ) -> Result<Self> {
+        let mut warnings = Vec::new();
+        let mut binary = Self::from_bytes_with(
+            name,
+            data.into(),
+            options.permissive.then_some(&mut warnings),
+        )?;
+        binary.info.parse_warnings = warnings;
+        binary.analyze(options);
+        Ok(binary)
+    }
+
+    /// Runs the passes of `options` that have not run on this file yet.
+    pub fn analyze(&mut self, options: AnalysisOptions) {
+        if (options.strings || options.scans) && !self.passes.strings {
+            (self.info.strings, self.info.string_index) =
+                analysis::extract_strings_indexed(&self.data);
+            self.passes.strings = true;
+        }
+        if options.scans && !self.passes.scans {
+            self.scan();
+            self.passes.scans = true;
+        }
+    }
+
+    /// Toolchain fingerprints and whole-file scans; see [`AnalysisOptions::scans`].
+    fn scan(&mut self) {
+        let data = &self.data;
+        let info = &mut self.info;
+        info.go = analysis::golang::analyze_go(data, info);
+        if let Some(go) = &info.go {
+            // Stripped Go binaries still carry every function name in the pclntab.
+            if info.symbols.is_empty() {
+                info.symbols = go.functions.clone();
+                if let Some(debug) = &mut info.debug_info {
+                    debug.symbols = analysis::debuginfo::SymbolSource::GoPclntab;
+                }
+            }
+        }
+        info.kernel = analysis::kernel::analyze_kernel(data, info);
+        info.pyinstaller = analysis::pyinstaller::find_archive(data);
+        info.app_runtime = analysis::app_runtime::detect_runtime(data, info);
+        info.crypto_constants = analysis::crypto::find_crypto_constants(data);
+        info.certificates = analysis::x509::find_certificates(data);
+        info.private_keys = analysis::x509::find_private_keys(data);
+        // Archive members are listed and unpacked as members instead.
+        if info.archive.is_none() {
+            info.embedded_files = carve::carve(data);
+        }
+        info.weak_crypto = analysis::crypto::find_weak_crypto(info);
+        info.insecure_functions = analysis::insecure::find_insecure_functions(info);
+        info.rust = analysis::rust::analyze_rust(data, info);
+        if info.rust.is_some() {
+            analysis::rust::demangle_symbols(&mut info.symbols);
+        }
+        info.language = analysis::language::classify(data, info);
+    }
+
+    /// Parses the headers. Errors fail the load unless `warnings` is given,
+    /// in which case they are recorded there and parsing falls back to what
+    /// is left.
+    fn from_bytes_with(
+        name: String,
+        data: BinaryData,
+        mut warnings: Option<&mut Vec<ParseWarning>>,
+    ) -> Result<Self> {
+        let mut data = data;
+        let mut wrappers = Vec::new();
+        while let Some(kind) = compression::Compression::detect(&data) {
+            if wrappers.len() == compression::MAX_WRAPPERS {
+                break;
+            }
+            match compression::decompress_to_data(
+                &data,
+                kind,
+                compression::DEFAULT_DECOMPRESSION_LIMIT,
+            )
+            .with_context(|| format!("Failed to unwrap {} input", kind.name()))
+            {
+                Ok(inner) => {
+                    data = inner;
+                    wrappers.push(kind);
+                }
+                Err(e) => match warnings.as_deref_mut() {
+                    Some(warnings) => {
+                        warnings.push(ParseWarning {
+                            stage: kind.name(),
+                            message: format!("{:#}", e),
+                        });
+                        break;
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
+
+        let (data, unpacked_bzimage) = if analysis::kernel::is_bzimage(&data) {
+            match analysis::kernel::extract_bzimage_payload(&data) {
+                Ok(vmlinux) => (vmlinux.into(), true),
+                Err(e) => {
+                    warn!("Could not unpack bzImage payload: {}", e);
+                    (data, false)
+                }
+            }
+        } else {
+            (data, false)
+        };
+
+        let image = match hexfile::HexFormat::detect(&data) {
+            Some(format) => match (hexfile::parse(&data, format), warnings.as_deref_mut()) {
+                (Ok(image), _) => Some(image),
+                (Err(e), Some(warnings)) => {
+                    warnings.push(ParseWarning {
+                        stage: format.name(),
+                        message: format!("{:#}", e),
+                    });
+                    None
+                }
+                (Err(e), None) => return Err(e),
             },
+            None => None,
         };
-        info.strings = analysis::extract_strings(data);
+        let (data, mut info) = match image {
+            Some(image) => {
+                let info = Self::parse_load_image(&image);
+                (image.data.into(), info)
+            }
+            None => {
+                let info = match (Self::parse(&data), warnings) {
+                    (Ok(info), _) => info,
+                    (Err(e), Some(warnings)) => {
+                        warnings.push(ParseWarning {
+                            stage: "headers",
+                            message: e.to_string(),
+                        });
+                        recovery::recover(&data, warnings)
+                    }
+                    (Err(e), None) => return Err(e),
+                };
+                (data, info)
+            }
+        };
+        info.compression = wrappers;
+        // Version, config and kallsyms are filled in by the scans.
+        info.kernel = analysis::kernel::detect_image(&data, unpacked_bzimage).map(|image| {
+            analysis::kernel::KernelInfo {
+                image,
+                version: None,
+                config: Vec::new(),
+                kallsyms_count: None,
+            }
+        });
+        info.kernel_module = analysis::kernel::analyze_module(&data, &info);
+        if let Some(kernel) = &info.kernel {
+            if kernel.image == analysis::kernel::KernelImageKind::Arm64Image {
+                info.format = "Linux Kernel (arm64 Image)".to_string();
+                info.arch = "aarch64".to_string();
+            }
+        }
+        info.installer = analysis::installer::detect_installer(&data, &info);
+        if info.format == UNKNOWN_FORMAT {
+            if let Some(installer) = &info.installer {
+                info.format = installer.kind.name().to_string();
+            }
+        }
+        if info.format == UNKNOWN_FORMAT {
+            info.file_type = magic::detect_file_type(&data);
+            if let Some(file_type) = &info.file_type {
+                info.format = file_type.name.to_string();
+            } else if let Some(wrapper) = info.compression.last() {
+                info.format = format!("{}-compressed data", wrapper.name());
+            }
+        }
+
+        Ok(Self {
+            name,
+            data,
+            info,
+            passes: options::Passes::default(),
+        })
+    }
+
+    /// Parses headers of an embedded file and summarizes it, if it is an executable.
+    fn summarize_embedded(path: String, contents: &[u8]) -> Option<EmbeddedBinary> {
+        if !is_executable_format(contents) {
+            return None;
+        }
+        let info = Self::parse(contents).ok()?;
+        Some(EmbeddedBinary {
+            path,
+            size: contents.len() as u64,
+            format: info.format,
+            arch: info.arch,
+            security: info.security,
+        })
+    }
+
+    fn parse(data: &[u8]) -> Result<BinaryInfo> {
+        // goblin needs this many bytes to tell formats apart; anything
+        // shorter is no executable.
+        if data.len() < GOBLIN_PEEK_SIZE {
+            return Self::parse_other(data);
+        }
+        Self::parse_object(&Object::parse(data)?, data)
+    }
+
+    fn parse_object(object: &Object, data: &[u8]) -> Result<BinaryInfo> {
+        let mut info = match object {
+            Object::Elf(elf) => Self::parse_elf(elf, data)?,
+            Object::PE(pe) => Self::parse_pe(pe, data)?,
+            Object::Mach(mach) => Self::parse_mach(mach, data)?,
+            _ => return Self::parse_other(data),
+        };
+        if let Some(executable) = Self::executable_ranges(object) {
+            info.entry_anomalies = analysis::entry::check_entry_point(&info, data, &executable);
+        }
+        info.anomalies = analysis::anomalies::find_anomalies(object, data);
+        info.debug_info = analysis::debuginfo::analyze_debug_info(object, data);
+        info.libraries = match object {
+            Object::Elf(elf) => elf.libraries.iter().map(|l| l.to_string()).collect(),
+            Object::PE(pe) => pe.libraries.iter().map(|l| l.to_string()).collect(),
+            // The first entry is the image's own install name.
+            Object::Mach(mach::Mach::Binary(macho)) => macho
+                .libs
+                .iter()
+                .filter(|&&l| l != "self")
+                .map(|l| l.to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let Object::Elf(elf) = object {
+            info.telfhash = analysis::hashes::telfhash(elf);
+        }
+        if let Object::PE(pe) = object {
+            info.authentihash = analysis::hashes::authentihash(pe, data);
+            info.icon = analysis::icon::extract_icon(pe, data);
+            info.dotnet = analysis::dotnet::analyze_dotnet(pe, data);
+        }
         Ok(info)
     }
 
-    fn parse_elf(elf: &elf::Elf) -> Result<BinaryInfo> {
+    /// `(addr, size)` of the regions mapped executable, in the same address
+    /// space as the section table.
+    fn executable_ranges(object: &Object) -> Option<Vec<(u64, u64)>> {
+        Some(match object {
+            Object::Elf(elf) => elf
+                .program_headers
+                .iter()
+                .filter(|ph| ph.p_type == elf::program_header::PT_LOAD && ph.is_executable())
+                .map(|ph| (ph.p_vaddr, ph.p_memsz))
+                .collect(),
+            Object::PE(pe) => pe
+                .sections
+                .iter()
+                .filter(|s| {
+                    s.characteristics
+                        & (pe::section_table::IMAGE_SCN_MEM_EXECUTE
+                            | pe::section_table::IMAGE_SCN_CNT_CODE)
+                        != 0
+                })
+                .map(|s| (s.virtual_address as u64, s.virtual_size as u64))
+                .collect(),
+            Object::Mach(mach::Mach::Binary(macho)) => macho
+                .segments
+                .iter()
+                // VM_PROT_EXECUTE
+                .filter(|seg| seg.initprot & 0x4 != 0)
+                .map(|seg| (seg.vmaddr, seg.vmsize))
+                .collect(),
+            _ => return None,
+        })
+    }
+
+    fn parse_load_image(image: &hexfile::LoadImage) -> BinaryInfo {
+        let sections = image
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, &(addr, size))| SectionInfo {
+                name: format!("seg{}", i),
+                addr,
+                size,
+                offset: addr - image.base,
+                hashes: None,
+                zero_fill: false,
+            })
+            .collect();
+
+        BinaryInfo {
+            format: image.format.name().to_string(),
+            arch: "Unknown".to_string(),
+            entry_point: image.entry.unwrap_or(image.base),
+            sections,
+            ..Default::default()
+        }
+    }
+
+    fn parse_other(data: &[u8]) -> Result<BinaryInfo> {
+        if let Some(kind) = package::PackageKind::detect(data) {
+            return Ok(BinaryInfo {
+                format: kind.name().to_string(),
+                package: Some(package::parse_package(data, kind)?),
+                ..Default::default()
+            });
+        }
+
+        if container::is_image_tarball(data) {
+            let image = container::parse_image(data)?;
+            return Ok(BinaryInfo {
+                format: format!("Container Image ({})", image.layout.name()),
+                container: Some(image),
+                ..Default::default()
+            });
+        }
+
+        if let Some(kind) = archive::ArchiveKind::detect(data) {
+            return Ok(BinaryInfo {
+                format: kind.name().to_string(),
+                archive: Some(archive::parse_archive(data, kind)?),
+                ..Default::default()
+            });
+        }
+
+        if uefi::is_te(data) {
+            return Self::parse_te(&uefi::parse_te(data)?, data);
+        }
+
+        let firmware = uefi::find_firmware_volumes(data);
+        if !firmware.is_empty() {
+            return Ok(BinaryInfo {
+                format: "UEFI Firmware Volume".to_string(),
+                firmware,
+                ..Default::default()
+            });
+        }
+
+        Ok(BinaryInfo {
+            format: UNKNOWN_FORMAT.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn parse_te(te: &uefi::TeImage, data: &[u8]) -> Result<BinaryInfo> {
+        let sections = te
+            .sections
+            .iter()
+            .map(|s| SectionInfo {
+                name: s.name.clone(),
+                addr: s.virtual_address as u64,
+                size: s.virtual_size as u64,
+                offset: s.offset,
+                hashes: analysis::hashes::section_hashes(data, s.offset, s.raw_size as u64),
+                zero_fill: s.raw_size == 0,
+            })
+            .collect();
+
+        Ok(BinaryInfo {
+            format: "TE (UEFI)".to_string(),
+            arch: te.arch(),
+            entry_point: te.entry_point as u64,
+            sections,
+            ..Default::default()
+        })
+    }
+
+    fn parse_elf(elf: &elf::Elf, data: &[u8]) -> Result<BinaryInfo> {
         let sections = elf
             .section_headers
             .iter()
@@ -81,6 +829,12 @@ impl BinaryFile {
                     addr: sh.sh_addr,
                     size: sh.sh_size,
                     offset: sh.sh_offset,
+                    hashes: if sh.sh_type == elf::section_header::SHT_NOBITS {
+                        None
+                    } else {
+                        analysis::hashes::section_hashes(data, sh.sh_offset, sh.sh_size)
+                    },
+                    zero_fill: sh.sh_type == elf::section_header::SHT_NOBITS,
                 }
             })
             .collect();
@@ -88,35 +842,156 @@ impl BinaryFile {
         let symbols = elf
             .syms
             .iter()
-            .map(|sym| {
-                let name = elf
-                    .strtab
-                    .get_at(sym.st_name)
-                    .unwrap_or("<unknown>")
-                    .to_string();
-                SymbolInfo {
-                    name,
-                    addr: sym.st_value,
+            .filter_map(|sym| {
+                let name = elf.strtab.get_at(sym.st_name).unwrap_or("<unknown>");
+                // `$a`/`$t`/`$d` only mark code and data regions.
+                if mapping_symbol(name).is_some() {
+                    return None;
                 }
+                Some(SymbolInfo {
+                    name: name.to_string(),
+                    addr: code_addr(sym, elf.header.e_machine),
+                })
             })
             .collect();
 
-        let security = analysis::analyze_security_elf(elf);
+        // PLT relocations come first so functions get their JUMP_SLOT.
+        let mut slots = HashMap::new();
+        for rel in elf
+            .pltrelocs
+            .iter()
+            .chain(elf.dynrelas.iter())
+            .chain(elf.dynrels.iter())
+        {
+            slots.entry(rel.r_sym).or_insert(rel.r_offset);
+        }
+        let imports = elf
+            .dynsyms
+            .iter()
+            .enumerate()
+            .filter(|(_, sym)| sym.is_import())
+            .filter_map(|(i, sym)| Some((i, elf.dynstrtab.get_at(sym.st_name)?)))
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(i, name)| ImportInfo {
+                name: name.to_string(),
+                library: None,
+                slot: slots.get(&i).copied(),
+            })
+            .collect();
+
+        let exports = elf
+            .dynsyms
+            .iter()
+            .filter(|sym| {
+                !sym.is_import()
+                    && sym.st_type() == elf::sym::STT_FUNC
+                    && sym.st_bind() != elf::sym::STB_LOCAL
+                    && sym.st_value != 0
+            })
+            .filter_map(|sym| {
+                let name = elf.dynstrtab.get_at(sym.st_name)?;
+                Some(SymbolInfo {
+                    name: name.to_string(),
+                    addr: code_addr(sym, elf.header.e_machine),
+                })
+            })
+            .collect();
+
+        let mut arch = Self::elf_arch(elf);
+        let mut arm_modes = BTreeMap::new();
+        if elf.header.e_machine == elf::header::EM_ARM {
+            arm_modes = Self::elf_arm_modes(elf);
+            // Pure Thumb binaries (most Cortex-M firmware) need no regions.
+            if !arm_modes.is_empty() && arm_modes.values().all(|&thumb| thumb) {
+                arch = "thumb".to_string();
+                arm_modes.clear();
+            }
+        }
+
+        let security = analysis::analyze_security_elf(elf, data);
+        let linker = analysis::linker::analyze_linker_elf(elf);
 
         Ok(BinaryInfo {
             format: "ELF".to_string(),
-            arch: elf::header::machine_to_str(elf.header.e_machine).to_string(),
-            entry_point: elf.entry,
+            arch,
+            arm_modes,
+            // The low bit of ARM code addresses selects Thumb state.
+            entry_point: if elf.header.e_machine == elf::header::EM_ARM {
+                elf.entry & !1
+            } else {
+                elf.entry
+            },
             sections,
             symbols,
+            imports,
+            exports,
             security,
-            strings: Vec::new(),
-            disassembly: Vec::new(),
-            analysis: None,
+            linker: Some(linker),
+            ..Default::default()
         })
     }
 
-    fn parse_pe(pe: &pe::PE) -> Result<BinaryInfo> {
+    /// Maps ELF machine types onto the architecture names used across the crate,
+    /// falling back to goblin's name for machines we cannot disassemble.
+    fn elf_arch(elf: &elf::Elf) -> String {
+        match elf.header.e_machine {
+            elf::header::EM_X86_64 => "x86_64".to_string(),
+            elf::header::EM_386 => "x86".to_string(),
+            elf::header::EM_AARCH64 => "aarch64".to_string(),
+            elf::header::EM_ARM => "arm".to_string(),
+            elf::header::EM_MIPS | elf::header::EM_MIPS_RS3_LE => {
+                // n32 is a 64-bit ISA with 32-bit pointers in an ELFCLASS32 file.
+                let abi = if elf.is_64 {
+                    "mips64"
+                } else if elf.header.e_flags & EF_MIPS_ABI2 != 0 {
+                    "mipsn32"
+                } else {
+                    "mips"
+                };
+                let endian = if elf.little_endian { "el" } else { "" };
+                format!("{}{}", abi, endian)
+            }
+            elf::header::EM_RISCV if elf.is_64 => "riscv64".to_string(),
+            elf::header::EM_RISCV => "riscv32".to_string(),
+            elf::header::EM_PPC | elf::header::EM_PPC64 => {
+                let bits = if elf.is_64 { "ppc64" } else { "ppc" };
+                let endian = if elf.little_endian { "le" } else { "" };
+                format!("{}{}", bits, endian)
+            }
+            // SPARC32PLUS is V8+: 32-bit ELF with V9 instructions.
+            elf::header::EM_SPARC => "sparc".to_string(),
+            elf::header::EM_SPARC32PLUS | elf::header::EM_SPARCV9 => "sparc64".to_string(),
+            other => elf::header::machine_to_str(other).to_string(),
+        }
+    }
+
+    /// ARM/Thumb regions from `$a`/`$t` mapping symbols, then from the low
+    /// bit of function symbols and the entry point where no mapping symbol
+    /// exists.
+    fn elf_arm_modes(elf: &elf::Elf) -> BTreeMap<u64, bool> {
+        let mut modes = BTreeMap::new();
+        for sym in elf.syms.iter() {
+            match elf.strtab.get_at(sym.st_name).and_then(mapping_symbol) {
+                Some('a') => modes.insert(sym.st_value, false),
+                Some('t') => modes.insert(sym.st_value, true),
+                _ => None,
+            };
+        }
+        let functions = elf
+            .syms
+            .iter()
+            .chain(elf.dynsyms.iter())
+            .filter(|sym| sym.st_type() == elf::sym::STT_FUNC)
+            .map(|sym| sym.st_value)
+            .chain([elf.entry])
+            .filter(|&addr| addr != 0);
+        for addr in functions {
+            modes.entry(addr & !1).or_insert(addr & 1 == 1);
+        }
+        modes
+    }
+
+    fn parse_pe(pe: &pe::PE, data: &[u8]) -> Result<BinaryInfo> {
         let sections = pe
             .sections
             .iter()
@@ -125,6 +1000,12 @@ impl BinaryFile {
                 addr: s.virtual_address as u64,
                 size: s.virtual_size as u64,
                 offset: s.pointer_to_raw_data as u64,
+                hashes: analysis::hashes::section_hashes(
+                    data,
+                    s.pointer_to_raw_data as u64,
+                    s.size_of_raw_data as u64,
+                ),
+                zero_fill: s.size_of_raw_data == 0,
             })
             .collect();
 
@@ -137,27 +1018,54 @@ impl BinaryFile {
                 addr: export.rva as u64,
             });
         }
+        let exports = symbols.clone();
+
+        let imports = pe
+            .imports
+            .iter()
+            .map(|import| ImportInfo {
+                name: import.name.to_string(),
+                library: Some(import.dll.to_string()),
+                slot: Some(import.offset as u64),
+            })
+            .collect();
 
-        let security = analysis::analyze_security_pe(pe);
+        let security = analysis::analyze_security_pe(pe, data);
+        let pe_headers = analysis::pe_headers::check_pe_headers(pe, data);
 
         Ok(BinaryInfo {
             format: "PE".to_string(),
-            arch: if pe.is_64 {
-                "x86_64".to_string()
-            } else {
-                "x86".to_string()
+            arch: Self::pe_arch(pe.header.coff_header.machine, pe.is_64),
+            // Thumb entry points carry the interworking bit.
+            entry_point: match pe.header.coff_header.machine {
+                pe::header::COFF_MACHINE_THUMB | pe::header::COFF_MACHINE_ARMNT => {
+                    pe.entry as u64 & !1
+                }
+                _ => pe.entry as u64,
             },
-            entry_point: pe.entry as u64,
+            image_base: pe.image_base as u64,
             sections,
             symbols,
+            imports,
+            exports,
             security,
-            strings: Vec::new(),
-            disassembly: Vec::new(),
-            analysis: None,
+            pe_headers,
+            ..Default::default()
         })
     }
 
-    fn parse_mach(mach: &mach::Mach) -> Result<BinaryInfo> {
+    fn pe_arch(machine: u16, is_64: bool) -> String {
+        match machine {
+            pe::header::COFF_MACHINE_ARM64 => "aarch64".to_string(),
+            pe::header::COFF_MACHINE_ARM => "arm".to_string(),
+            // Windows on ARM (ARMNT) is Thumb-2 only.
+            pe::header::COFF_MACHINE_THUMB | pe::header::COFF_MACHINE_ARMNT => "thumb".to_string(),
+            _ if is_64 => "x86_64".to_string(),
+            _ => "x86".to_string(),
+        }
+    }
+
+    fn parse_mach(mach: &mach::Mach, data: &[u8]) -> Result<BinaryInfo> {
         match mach {
             mach::Mach::Binary(macho) => {
                 let mut sections = Vec::new();
@@ -169,6 +1077,16 @@ impl BinaryFile {
                                 addr: section.addr,
                                 size: section.size,
                                 offset: section.offset as u64,
+                                hashes: if is_zerofill(section.flags) {
+                                    None
+                                } else {
+                                    analysis::hashes::section_hashes(
+                                        data,
+                                        section.offset as u64,
+                                        section.size,
+                                    )
+                                },
+                                zero_fill: is_zerofill(section.flags),
                             });
                         }
                     }
@@ -183,23 +1101,59 @@ impl BinaryFile {
                     })
                     .collect();
 
-                let security = analysis::analyze_security_mach(mach);
+                let imports = macho
+                    .imports()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|import| ImportInfo {
+                        // Strip the C symbol prefix so names match other formats.
+                        name: import
+                            .name
+                            .strip_prefix('_')
+                            .unwrap_or(import.name)
+                            .to_string(),
+                        library: Some(import.dylib.to_string()),
+                        slot: Some(import.address),
+                    })
+                    .collect();
+
+                // Export trie offsets are relative to the start of __TEXT.
+                let text_base = macho
+                    .segments
+                    .iter()
+                    .find(|s| s.name().ok() == Some("__TEXT"))
+                    .map(|s| s.vmaddr)
+                    .unwrap_or(0);
+                let exports = macho
+                    .exports()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|export| SymbolInfo {
+                        name: export
+                            .name
+                            .strip_prefix('_')
+                            .unwrap_or(&export.name)
+                            .to_string(),
+                        addr: text_base + export.offset,
+                    })
+                    .collect();
+
+                let security = analysis::analyze_security_mach(mach, data);
 
                 Ok(BinaryInfo {
                     format: "Mach-O".to_string(),
-                    arch: match macho.header.cputype {
-                        goblin::mach::cputype::CPU_TYPE_X86_64 => "x86_64".to_string(),
-                        goblin::mach::cputype::CPU_TYPE_X86 => "x86".to_string(),
-                        goblin::mach::cputype::CPU_TYPE_ARM64 => "aarch64".to_string(),
-                        _ => format!("Unknown ({})", macho.header.cputype),
+                    arch: Self::mach_arch(macho.header.cputype, macho.header.cpusubtype),
+                    entry_point: if macho.header.cputype == goblin::mach::cputype::CPU_TYPE_ARM {
+                        macho.entry & !1
+                    } else {
+                        macho.entry
                     },
-                    entry_point: macho.entry,
                     sections,
                     symbols,
+                    imports,
+                    exports,
                     security,
-                    strings: Vec::new(),
-                    disassembly: Vec::new(),
-                    analysis: None,
+                    ..Default::default()
                 })
             }
             mach::Mach::Fat(_) => Ok(BinaryInfo {
@@ -209,6 +1163,221 @@ impl BinaryFile {
         }
     }
 
+    fn mach_arch(cputype: u32, cpusubtype: u32) -> String {
+        match cputype {
+            goblin::mach::cputype::CPU_TYPE_X86_64 => "x86_64".to_string(),
+            goblin::mach::cputype::CPU_TYPE_X86 => "x86".to_string(),
+            goblin::mach::cputype::CPU_TYPE_ARM64 => "aarch64".to_string(),
+            goblin::mach::cputype::CPU_TYPE_POWERPC => "ppc".to_string(),
+            goblin::mach::cputype::CPU_TYPE_POWERPC64 => "ppc64".to_string(),
+            goblin::mach::cputype::CPU_TYPE_ARM => Self::mach_arm_arch(cpusubtype).to_string(),
+            _ => format!("Unknown ({})", cputype),
+        }
+    }
+
+    /// iOS and watchOS compile ARMv7 code as Thumb-2; older cores default to ARM.
+    fn mach_arm_arch(subtype: u32) -> &'static str {
+        use goblin::mach::cputype::*;
+        match subtype & !CPU_SUBTYPE_MASK {
+            CPU_SUBTYPE_ARM_V7 | CPU_SUBTYPE_ARM_V7F | CPU_SUBTYPE_ARM_V7S
+            | CPU_SUBTYPE_ARM_V7K | CPU_SUBTYPE_ARM_V6M | CPU_SUBTYPE_ARM_V7M
+            | CPU_SUBTYPE_ARM_V7EM => "thumb",
+            _ => "arm",
+        }
+    }
+
+    /// Identifies statically linked library functions and names them in the
+    /// symbol table where no symbol exists yet.
+    pub fn apply_signatures(&mut self, set: &analysis::flirt::SignatureSet) {
+        let matches = analysis::flirt::match_signatures(&self.data, &self.info, set);
+        for m in &matches {
+            if !self.info.symbols.iter().any(|s| s.addr == m.addr) {
+                self.info.symbols.push(SymbolInfo {
+                    name: m.name.clone(),
+                    addr: m.addr,
+                });
+            }
+        }
+        self.info.signature_matches = matches;
+    }
+
+    /// Populates `info.functions` with every function start and size found.
+    pub fn discover_functions(&mut self) {
+        self.info.functions = analysis::functions::discover_functions(self);
+    }
+
+    /// Indexes code references made by the discovered functions.
+    pub fn xrefs(&self) -> analysis::xrefs::XrefIndex {
+        analysis::xrefs::build_xrefs(self)
+    }
+
+    /// File contents of `section`, clamped to the file.
+    pub fn section_data(&self, section: &SectionInfo) -> &[u8] {
+        &self.data[section.file_range(self.data.len())]
+    }
+
+    /// Files worth extracting alongside a report (overlay, certificates, ...).
+    pub fn artifacts(&self) -> Vec<artifacts::Artifact> {
+        artifacts::collect_artifacts(self)
+    }
+
+    /// Loads each embedded PE/ELF executable, and each executable or
+    /// archive member of an archive, as a file of its own, and the ones
+    /// inside those in turn, down to `depth` levels. Files are skipped once
+    /// their sizes add up to more than `budget` bytes. Each file is loaded
+    /// with `options`, e.g. permissively like its parent.
+    pub fn unpack_embedded(&mut self, depth: usize, budget: u64, options: AnalysisOptions) {
+        let mut remaining = budget;
+        self.unpack_within(depth, &mut remaining, options);
+    }
+
+    fn unpack_within(&mut self, depth: usize, remaining: &mut u64, options: AnalysisOptions) {
+        if depth == 0 {
+            return;
+        }
+        let mut found = Vec::new();
+        for carved in self
+            .info
+            .embedded_files
+            .iter()
+            .filter(|f| f.kind.is_executable())
+        {
+            if carved.size > *remaining {
+                warn!(
+                    "Skipping embedded executable at 0x{:x}: unpack budget exhausted",
+                    carved.offset
+                );
+                continue;
+            }
+            *remaining -= carved.size;
+            let name = format!("{}@0x{:x}", self.name, carved.offset);
+            found.push((carved.offset, name, carved.bytes(&self.data).to_vec()));
+        }
+        if let Some(archive) = &self.info.archive {
+            let mut reader = archive::MemberReader::new(&self.data, archive.kind);
+            for member in &archive.members {
+                if member.size > *remaining {
+                    warn!("Skipping {}: unpack budget exhausted", member.path);
+                    continue;
+                }
+                let contents = match reader.read(member, compression::DEFAULT_DECOMPRESSION_LIMIT) {
+                    Ok(contents) => contents,
+                    Err(e) => {
+                        warn!("Could not read archive member {}: {:#}", member.path, e);
+                        continue;
+                    }
+                };
+                if is_executable_format(&contents)
+                    || archive::ArchiveKind::detect(&contents).is_some()
+                    || compression::Compression::detect(&contents).is_some()
+                {
+                    *remaining -= member.size;
+                    let name = format!("{}!{}", self.name, member.path);
+                    found.push((member.offset, name, contents));
+                }
+            }
+        }
+
+        for (offset, name, contents) in found {
+            match Self::from_bytes_with_options(name.clone(), contents, options) {
+                Ok(mut file) => {
+                    file.unpack_within(depth - 1, remaining, options);
+                    self.info.unpacked.push(UnpackedBinary { offset, file });
+                }
+                Err(e) => warn!("Could not load {}: {:#}", name, e),
+            }
+        }
+    }
+
+    /// ELF program headers or Mach-O segments, parsed on demand.
+    pub fn segments(&self) -> Vec<segments::Segment> {
+        segments::segments(&self.data)
+    }
+
+    /// Lays the binary out as it would be mapped in memory, rebased to `base`.
+    pub fn memory_image(&self, base: Option<u64>) -> Result<image::MemoryImage> {
+        image::build_memory_image(&self.data, &self.info, base)
+    }
+
+    /// Builds the control-flow graph of the function at `addr`. Decoding stops
+    /// at the next symbol or the end of the section.
+    pub fn function_cfg(&self, addr: u64) -> Result<analysis::cfg::ControlFlowGraph> {
+        self.function_cfg_until(addr, u64::MAX)
+    }
+
+    /// Like [`Self::function_cfg`], but never decodes at or beyond `end`.
+    pub fn function_cfg_until(
+        &self,
+        addr: u64,
+        end: u64,
+    ) -> Result<analysis::cfg::ControlFlowGraph> {
+        let section = self
+            .info
+            .section_for_addr(addr)
+            .context("Address is not inside any section")?;
+        let limit = self
+            .info
+            .symbols
+            .iter()
+            .map(|s| s.addr)
+            .chain(self.info.functions.iter().map(|f| f.start))
+            .filter(|&a| a > addr)
+            .min()
+            .unwrap_or(u64::MAX)
+            .min(end)
+            .min(section.addr.saturating_add(section.size))
+            .min(addr.saturating_add(MAX_FUNCTION_DECODE));
+        let code = self
+            .section_data(section)
+            .get((addr - section.addr) as usize..)
+            .context("Section data out of range")?;
+        let code = &code[..code.len().min((limit - addr) as usize)];
+        analysis::cfg::build_cfg(self.info.arch_at(addr), code, addr)
+    }
+
+    /// Disassembles a window of instructions around `addr`, decoding from the
+    /// start of the containing function so the listing stays in sync.
+    pub fn disassemble_around(
+        &self,
+        addr: u64,
+        before: usize,
+        after: usize,
+    ) -> Result<Vec<analysis::disassembly::InstructionInfo>> {
+        let section = self
+            .info
+            .section_for_addr(addr)
+            .context("Address is not inside any section")?;
+        let start = self
+            .info
+            .containing_function(addr)
+            .map(|f| f.addr)
+            .filter(|&f| addr - f <= MAX_CONTEXT_DECODE)
+            .unwrap_or(addr);
+        let code = start
+            .checked_sub(section.addr)
+            .and_then(|skip| self.section_data(section).get(skip as usize..))
+            .context("Section data out of range")?;
+        let code = &code[..code.len().min((addr - start) as usize + after * 16 + 16)];
+
+        let instructions = analysis::disassembly::disassemble_with_data(
+            self.info.arch_at(start),
+            code,
+            start,
+            usize::MAX,
+            false,
+        )?;
+        let pos = instructions
+            .iter()
+            .position(|ins| ins.address >= addr)
+            .unwrap_or(instructions.len());
+        let first = pos.saturating_sub(before);
+        Ok(instructions
+            .into_iter()
+            .skip(first)
+            .take(pos - first + after)
+            .collect())
+    }
+
     pub fn identify(&self) -> &str {
         &self.info.format
     }
@@ -218,4 +1387,6 @@ impl BinaryFile {
 pub struct AnalysisData {
     pub entropy: f64,
     pub hashes: Option<analysis::hashes::FileHashes>,
+    /// Algorithm (`SHA-1`, ...) by which the file matched a known-good set.
+    pub known_good: Option<String>,
 }
@@ -0,0 +1,341 @@
+use super::container::index_tar;
+use super::{compression, BinaryFile, EmbeddedBinary};
+use anyhow::{anyhow, bail, Context, Result};
+use goblin::archive::Archive;
+
+const DEB_MAGIC: &[u8] = b"!<arch>\ndebian-binary";
+const RPM_LEAD_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+const RPM_HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8, 0x01];
+const RPM_LEAD_SIZE: usize = 96;
+
+const RPMTAG_NAME: u32 = 1000;
+const RPMTAG_VERSION: u32 = 1001;
+const RPMTAG_RELEASE: u32 = 1002;
+const RPMTAG_SUMMARY: u32 = 1004;
+const RPMTAG_VENDOR: u32 = 1011;
+const RPMTAG_PACKAGER: u32 = 1015;
+const RPMTAG_ARCH: u32 = 1022;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageKind {
+    Deb,
+    Rpm,
+}
+
+impl PackageKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageKind::Deb => "Debian Package",
+            PackageKind::Rpm => "RPM Package",
+        }
+    }
+
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(DEB_MAGIC) {
+            Some(PackageKind::Deb)
+        } else if data.starts_with(RPM_LEAD_MAGIC) {
+            Some(PackageKind::Rpm)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageMember {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageInfo {
+    pub kind: PackageKind,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub architecture: Option<String>,
+    /// Debian `Maintainer`, or RPM packager/vendor.
+    pub maintainer: Option<String>,
+    pub summary: Option<String>,
+    /// Every regular file in the payload, in path order.
+    pub members: Vec<PackageMember>,
+    pub binaries: Vec<EmbeddedBinary>,
+}
+
+pub fn parse_package(data: &[u8], kind: PackageKind) -> Result<PackageInfo> {
+    match kind {
+        PackageKind::Deb => parse_deb(data),
+        PackageKind::Rpm => parse_rpm(data),
+    }
+}
+
+fn unwrap_payload(data: &[u8]) -> Result<Vec<u8>> {
+    match compression::Compression::detect(data) {
+        Some(kind) => compression::decompress(data, kind, compression::DEFAULT_DECOMPRESSION_LIMIT),
+        None => Ok(data.to_vec()),
+    }
+}
+
+fn parse_deb(data: &[u8]) -> Result<PackageInfo> {
+    let archive = Archive::parse(data).context("Invalid deb ar archive")?;
+    let member = |prefix: &str| -> Result<&[u8]> {
+        let name = archive
+            .members()
+            .into_iter()
+            .find(|m| m.trim_end_matches('/').starts_with(prefix))
+            .ok_or_else(|| anyhow!("Package has no {} member", prefix))?;
+        Ok(archive.extract(name, data)?)
+    };
+
+    let mut info = PackageInfo {
+        kind: PackageKind::Deb,
+        name: None,
+        version: None,
+        architecture: None,
+        maintainer: None,
+        summary: None,
+        members: Vec::new(),
+        binaries: Vec::new(),
+    };
+
+    let control_tar = unwrap_payload(member("control.tar")?)?;
+    let control = index_tar(&control_tar)?
+        .get("control")
+        .map(|&(offset, size)| {
+            String::from_utf8_lossy(&control_tar[offset..offset + size]).to_string()
+        })
+        .unwrap_or_default();
+    for line in control.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match key {
+            "Package" => info.name = value,
+            "Version" => info.version = value,
+            "Architecture" => info.architecture = value,
+            "Maintainer" => info.maintainer = value,
+            "Description" => info.summary = value,
+            _ => {}
+        }
+    }
+
+    let payload = unwrap_payload(member("data.tar")?)?;
+    let mut files: Vec<_> = index_tar(&payload)?.into_iter().collect();
+    files.sort();
+    for (path, (offset, size)) in files {
+        add_member(
+            &mut info,
+            format!("/{}", path),
+            &payload[offset..offset + size],
+        );
+    }
+    Ok(info)
+}
+
+fn add_member(info: &mut PackageInfo, path: String, contents: &[u8]) {
+    if let Some(binary) = BinaryFile::summarize_embedded(path.clone(), contents) {
+        info.binaries.push(binary);
+    }
+    info.members.push(PackageMember {
+        path,
+        size: contents.len() as u64,
+    });
+}
+
+/// Parses an RPM header structure, returning its string tags and total length.
+fn parse_rpm_header(data: &[u8]) -> Result<(Vec<(u32, String)>, usize)> {
+    if !data.starts_with(RPM_HEADER_MAGIC) || data.len() < 16 {
+        bail!("Missing RPM header magic");
+    }
+    let be = |off: usize| -> Result<u32> {
+        data.get(off..off + 4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| anyhow!("Truncated RPM header"))
+    };
+    let count = be(8)? as usize;
+    let store_size = be(12)? as usize;
+    let store_start = 16 + count * 16;
+    let store = data
+        .get(store_start..store_start + store_size)
+        .ok_or_else(|| anyhow!("Truncated RPM header store"))?;
+
+    let mut tags = Vec::new();
+    for i in 0..count {
+        let entry = 16 + i * 16;
+        let (tag, kind, offset) = (be(entry)?, be(entry + 4)?, be(entry + 8)? as usize);
+        // STRING (6) and I18NSTRING (9) hold NUL-terminated text.
+        if kind != 6 && kind != 9 {
+            continue;
+        }
+        if let Some(bytes) = store.get(offset..) {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            tags.push((tag, String::from_utf8_lossy(&bytes[..end]).to_string()));
+        }
+    }
+    Ok((tags, store_start + store_size))
+}
+
+fn parse_rpm(data: &[u8]) -> Result<PackageInfo> {
+    let signature = data
+        .get(RPM_LEAD_SIZE..)
+        .ok_or_else(|| anyhow!("Truncated RPM lead"))?;
+    let (_, sig_len) = parse_rpm_header(signature).context("Invalid RPM signature header")?;
+    // The signature header is padded to an 8-byte boundary.
+    let header_start = RPM_LEAD_SIZE + sig_len.div_ceil(8) * 8;
+    let (tags, header_len) =
+        parse_rpm_header(&data[header_start.min(data.len())..]).context("Invalid RPM header")?;
+    let tag = |id: u32| tags.iter().find(|(t, _)| *t == id).map(|(_, v)| v.clone());
+
+    let mut info = PackageInfo {
+        kind: PackageKind::Rpm,
+        name: tag(RPMTAG_NAME),
+        version: tag(RPMTAG_VERSION).map(|v| match tag(RPMTAG_RELEASE) {
+            Some(release) => format!("{}-{}", v, release),
+            None => v,
+        }),
+        architecture: tag(RPMTAG_ARCH),
+        maintainer: tag(RPMTAG_PACKAGER).or_else(|| tag(RPMTAG_VENDOR)),
+        summary: tag(RPMTAG_SUMMARY),
+        members: Vec::new(),
+        binaries: Vec::new(),
+    };
+
+    let payload = data
+        .get(header_start + header_len..)
+        .ok_or_else(|| anyhow!("RPM has no payload"))?;
+    let payload = unwrap_payload(payload)?;
+    for (path, contents) in parse_cpio(&payload)? {
+        add_member(&mut info, path, contents);
+    }
+    info.members.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(info)
+}
+
+/// Walks a `newc` cpio archive, returning regular files.
+fn parse_cpio(data: &[u8]) -> Result<Vec<(String, &[u8])>> {
+    const HEADER: usize = 110;
+    let mut files = Vec::new();
+    let mut pos = 0;
+    while pos + HEADER <= data.len() {
+        let header = &data[pos..pos + HEADER];
+        if !header.starts_with(b"07070") {
+            bail!("Bad cpio header at 0x{:x}", pos);
+        }
+        let field = |i: usize| -> Result<usize> {
+            let text = std::str::from_utf8(&header[6 + i * 8..14 + i * 8])?;
+            Ok(usize::from_str_radix(text, 16)?)
+        };
+        let (mode, file_size, name_size) = (field(1)?, field(6)?, field(11)?);
+        let name_start = pos + HEADER;
+        let name = data
+            .get(name_start..name_start + name_size.saturating_sub(1))
+            .ok_or_else(|| anyhow!("Truncated cpio entry name"))?;
+        let name = String::from_utf8_lossy(name).to_string();
+        if name == "TRAILER!!!" {
+            break;
+        }
+        let data_start = (name_start + name_size).next_multiple_of(4);
+        let contents = data
+            .get(data_start..data_start + file_size)
+            .ok_or_else(|| anyhow!("Truncated cpio entry {}", name))?;
+        if mode & 0o170000 == 0o100000 {
+            files.push((
+                format!("/{}", name.trim_start_matches("./").trim_start_matches('/')),
+                contents,
+            ));
+        }
+        pos = (data_start + file_size).next_multiple_of(4);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn ar_member(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut member = format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            name,
+            0,
+            0,
+            0,
+            100644,
+            contents.len()
+        )
+        .into_bytes();
+        member.extend_from_slice(contents);
+        if contents.len() % 2 == 1 {
+            member.push(b'\n');
+        }
+        member
+    }
+
+    #[test]
+    fn test_parse_deb() {
+        let control = b"Package: demo\nVersion: 1.2-3\nArchitecture: amd64\nMaintainer: Dev <dev@example.com>\nDescription: Demo tool\n";
+        let control_tar = tar_of(&[("./control", control)]);
+        let data_tar = tar_of(&[("./usr/share/doc/demo/README", b"hello")]);
+        let mut deb = b"!<arch>\n".to_vec();
+        deb.extend(ar_member("debian-binary", b"2.0\n"));
+        deb.extend(ar_member("control.tar", &control_tar));
+        deb.extend(ar_member("data.tar", &data_tar));
+
+        assert_eq!(PackageKind::detect(&deb), Some(PackageKind::Deb));
+        let info = parse_package(&deb, PackageKind::Deb).expect("deb parse failed");
+        assert_eq!(info.name.as_deref(), Some("demo"));
+        assert_eq!(info.version.as_deref(), Some("1.2-3"));
+        assert_eq!(info.maintainer.as_deref(), Some("Dev <dev@example.com>"));
+        assert_eq!(info.members.len(), 1);
+        assert_eq!(info.members[0].path, "/usr/share/doc/demo/README");
+        assert!(info.binaries.is_empty());
+    }
+
+    #[test]
+    fn test_parse_cpio() {
+        let mut cpio = Vec::new();
+        for (name, mode, contents) in [
+            ("./bin/tool", 0o100755, &b"abc"[..]),
+            ("TRAILER!!!", 0, &b""[..]),
+        ] {
+            cpio.extend(
+                format!(
+                    "070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+                    0,
+                    mode,
+                    0,
+                    0,
+                    1,
+                    0,
+                    contents.len(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    name.len() + 1,
+                    0
+                )
+                .into_bytes(),
+            );
+            cpio.extend_from_slice(name.as_bytes());
+            cpio.push(0);
+            cpio.resize(cpio.len().next_multiple_of(4), 0);
+            cpio.extend_from_slice(contents);
+            cpio.resize(cpio.len().next_multiple_of(4), 0);
+        }
+        let files = parse_cpio(&cpio).expect("cpio parse failed");
+        assert_eq!(files, vec![("/bin/tool".to_string(), &b"abc"[..])]);
+    }
+}
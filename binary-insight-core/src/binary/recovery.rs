@@ -0,0 +1,211 @@
+//! Best-effort parsing for files goblin rejects as a whole: each table is
+//! parsed on its own and whatever fails is recorded as a [`ParseWarning`].
+
+use super::{BinaryFile, BinaryInfo, ParseWarning, SectionInfo};
+use crate::analysis;
+use goblin::container::Ctx;
+use goblin::elf::program_header::ProgramHeader;
+use goblin::elf::section_header::SectionHeader;
+use goblin::strtab::Strtab;
+use goblin::{elf, mach, pe, Object};
+
+/// Parses as much of `data` as possible after a strict parse failed.
+pub(super) fn recover(data: &[u8], warnings: &mut Vec<ParseWarning>) -> BinaryInfo {
+    let info = match data.get(..4) {
+        Some(b"\x7fELF") => recover_elf(data, warnings),
+        Some([b'M', b'Z', _, _]) => recover_pe(data, warnings),
+        Some(&[a, b, c, d]) if is_macho_magic(u32::from_be_bytes([a, b, c, d])) => {
+            recover_mach(data, warnings)
+        }
+        _ => None,
+    };
+    info.unwrap_or_else(|| BinaryInfo {
+        format: "Unknown".to_string(),
+        arch: "Unknown".to_string(),
+        ..Default::default()
+    })
+}
+
+fn is_macho_magic(magic: u32) -> bool {
+    use goblin::mach::header::*;
+    matches!(magic, MH_MAGIC | MH_CIGAM | MH_MAGIC_64 | MH_CIGAM_64)
+}
+
+fn warn(warnings: &mut Vec<ParseWarning>, stage: &'static str, error: impl ToString) {
+    warnings.push(ParseWarning {
+        stage,
+        message: error.to_string(),
+    });
+}
+
+/// The ELF header, then program headers, section headers and section names
+/// independently; symbol and dynamic tables are left out.
+fn recover_elf(data: &[u8], warnings: &mut Vec<ParseWarning>) -> Option<BinaryInfo> {
+    let header = elf::Elf::parse_header(data)
+        .map_err(|e| warn(warnings, "ELF header", e))
+        .ok()?;
+    let mut elf = elf::Elf::lazy_parse(header)
+        .map_err(|e| warn(warnings, "ELF header", e))
+        .ok()?;
+    // `lazy_parse` has already checked the class and data encoding.
+    let ctx = Ctx::new(header.container().ok()?, header.endianness().ok()?);
+    match ProgramHeader::parse(data, header.e_phoff as usize, header.e_phnum as usize, ctx) {
+        Ok(headers) => elf.program_headers = headers,
+        Err(e) => warn(warnings, "ELF program headers", e),
+    }
+    match SectionHeader::parse(data, header.e_shoff as usize, header.e_shnum as usize, ctx) {
+        Ok(headers) => elf.section_headers = headers,
+        Err(e) => warn(warnings, "ELF section headers", e),
+    }
+    if let Some(strtab) = elf.section_headers.get(header.e_shstrndx as usize) {
+        match Strtab::parse(data, strtab.sh_offset as usize, strtab.sh_size as usize, 0) {
+            Ok(names) => elf.shdr_strtab = names,
+            Err(e) => warn(warnings, "ELF section names", e),
+        }
+    }
+    warnings.push(ParseWarning {
+        stage: "ELF",
+        message: "symbols, imports and dynamic section skipped".to_string(),
+    });
+    BinaryFile::parse_object(&Object::Elf(elf), data)
+        .map_err(|e| warn(warnings, "ELF", e))
+        .ok()
+}
+
+/// Retries without the certificate table, the usual casualty of truncated
+/// or appended data, then falls back to the headers and section table.
+fn recover_pe(data: &[u8], warnings: &mut Vec<ParseWarning>) -> Option<BinaryInfo> {
+    let options = pe::options::ParseOptions {
+        parse_attribute_certificates: false,
+        ..pe::options::ParseOptions::default()
+    };
+    if let Ok(parsed) = pe::PE::parse_with_opts(data, &options) {
+        warn(warnings, "PE certificates", "certificate table skipped");
+        return BinaryFile::parse_object(&Object::PE(parsed), data)
+            .map_err(|e| warn(warnings, "PE", e))
+            .ok();
+    }
+
+    let header = pe::header::Header::parse(data)
+        .map_err(|e| warn(warnings, "PE header", e))
+        .ok()?;
+    let coff = &header.coff_header;
+    let mut offset = header.dos_header.pe_pointer as usize
+        + pe::header::SIZEOF_PE_MAGIC
+        + pe::header::SIZEOF_COFF_HEADER
+        + coff.size_of_optional_header as usize;
+    let sections = match coff.sections(data, &mut offset) {
+        Ok(sections) => sections
+            .iter()
+            .map(|s| SectionInfo {
+                name: s.name().unwrap_or("<bad>").to_string(),
+                addr: s.virtual_address as u64,
+                size: s.virtual_size as u64,
+                offset: s.pointer_to_raw_data as u64,
+                hashes: analysis::hashes::section_hashes(
+                    data,
+                    s.pointer_to_raw_data as u64,
+                    s.size_of_raw_data as u64,
+                ),
+                zero_fill: s.size_of_raw_data == 0,
+            })
+            .collect(),
+        Err(e) => {
+            warn(warnings, "PE section table", e);
+            Vec::new()
+        }
+    };
+    warnings.push(ParseWarning {
+        stage: "PE",
+        message: "imports, exports and data directories skipped".to_string(),
+    });
+    let opt = header.optional_header.as_ref();
+    let is_64 = opt.is_some_and(|o| o.standard_fields.magic == pe::optional_header::MAGIC_64);
+    let arch = BinaryFile::pe_arch(coff.machine, is_64);
+    let entry = opt.map_or(0, |o| o.standard_fields.address_of_entry_point);
+    Some(BinaryInfo {
+        entry_point: if arch == "thumb" { entry & !1 } else { entry },
+        image_base: opt.map_or(0, |o| o.windows_fields.image_base),
+        format: "PE".to_string(),
+        arch,
+        sections,
+        ..Default::default()
+    })
+}
+
+/// goblin's lossy Mach-O mode skips unreadable load commands; failing that,
+/// only the CPU type is read.
+fn recover_mach(data: &[u8], warnings: &mut Vec<ParseWarning>) -> Option<BinaryInfo> {
+    match mach::MachO::parse_lossy(data, 0) {
+        Ok(macho) => {
+            warn(warnings, "Mach-O", "parsed in lossy mode");
+            BinaryFile::parse_object(&Object::Mach(mach::Mach::Binary(macho)), data)
+                .map_err(|e| warn(warnings, "Mach-O", e))
+                .ok()
+        }
+        Err(e) => {
+            warn(warnings, "Mach-O load commands", e);
+            let field = |at: usize| {
+                let bytes: [u8; 4] = data.get(at..at + 4)?.try_into().ok()?;
+                // The magic reads byte-swapped in little-endian files.
+                Some(match data[0] {
+                    0xfe => u32::from_be_bytes(bytes),
+                    _ => u32::from_le_bytes(bytes),
+                })
+            };
+            Some(BinaryInfo {
+                format: "Mach-O".to_string(),
+                arch: BinaryFile::mach_arch(field(4)?, field(8)?),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 64-bit x86-64 ELF header whose section headers lie past the end
+    /// of the file.
+    fn truncated_elf() -> Vec<u8> {
+        let mut data = vec![0u8; 0x200];
+        data[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        data[16] = 2; // ET_EXEC
+        data[18] = 62; // EM_X86_64
+        data[20] = 1;
+        data[24..32].copy_from_slice(&0x401000u64.to_le_bytes());
+        data[40..48].copy_from_slice(&0x10000u64.to_le_bytes());
+        data[52] = 64;
+        data[54] = 56;
+        data[58] = 64;
+        data[60] = 8;
+        data[0x100..0x10c].copy_from_slice(b"hello world\0");
+        data
+    }
+
+    #[test]
+    fn test_recover_truncated_elf() {
+        let data = truncated_elf();
+        assert!(BinaryFile::from_bytes("elf".to_string(), data.clone()).is_err());
+
+        let binary = BinaryFile::from_bytes_permissive("elf".to_string(), data);
+        let info = &binary.info;
+        assert_eq!(info.format, "ELF");
+        assert_eq!(info.arch, "x86_64");
+        assert_eq!(info.entry_point, 0x401000);
+        assert!(info
+            .parse_warnings
+            .iter()
+            .any(|w| w.stage == "ELF section headers"));
+        assert!(info.strings.iter().any(|s| s == "hello world"));
+    }
+
+    #[test]
+    fn test_recover_garbage() {
+        let mut warnings = Vec::new();
+        let info = recover(b"\x7fELF\x02", &mut warnings);
+        assert_eq!(info.format, "Unknown");
+        assert_eq!(warnings[0].stage, "ELF header");
+    }
+}
@@ -0,0 +1,98 @@
+use goblin::{elf, mach, Object};
+
+/// A segment as the file's program headers or load commands describe it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Mach-O segment name, or the ELF program header type (`LOAD`,
+    /// `DYNAMIC`, ...).
+    pub name: String,
+    pub addr: u64,
+    pub mem_size: u64,
+    pub offset: u64,
+    pub file_size: u64,
+}
+
+/// ELF program headers or Mach-O segments, in file order. Other formats
+/// have none.
+pub fn segments(data: &[u8]) -> Vec<Segment> {
+    match Object::parse(data) {
+        Ok(Object::Elf(elf)) => elf
+            .program_headers
+            .iter()
+            .map(|ph| {
+                let name = elf::program_header::pt_to_str(ph.p_type);
+                Segment {
+                    name: name.strip_prefix("PT_").unwrap_or(name).to_string(),
+                    addr: ph.p_vaddr,
+                    mem_size: ph.p_memsz,
+                    offset: ph.p_offset,
+                    file_size: ph.p_filesz,
+                }
+            })
+            .collect(),
+        Ok(Object::Mach(mach::Mach::Binary(macho))) => macho
+            .segments
+            .iter()
+            .map(|s| Segment {
+                name: s.name().unwrap_or_default().to_string(),
+                addr: s.vmaddr,
+                mem_size: s.vmsize,
+                offset: s.fileoff,
+                file_size: s.filesize,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 64-bit ELF header followed by a PT_LOAD and a PT_NOTE program header.
+    fn elf_with_segments() -> Vec<u8> {
+        let mut data = vec![0u8; 0x100];
+        data[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        data[16] = 2; // ET_EXEC
+        data[18] = 62; // EM_X86_64
+        data[20] = 1;
+        data[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data[52] = 64;
+        data[54] = 56;
+        data[56] = 2; // e_phnum
+        data[58] = 64;
+        for (i, (p_type, offset, addr, size)) in [
+            (1u32, 0u64, 0x400000u64, 0x100u64),
+            (4, 0xf0, 0x4000f0, 0x10),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let ph = &mut data[64 + i * 56..64 + (i + 1) * 56];
+            ph[..4].copy_from_slice(&p_type.to_le_bytes());
+            ph[8..16].copy_from_slice(&offset.to_le_bytes());
+            ph[16..24].copy_from_slice(&addr.to_le_bytes());
+            ph[32..40].copy_from_slice(&size.to_le_bytes());
+            ph[40..48].copy_from_slice(&size.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_elf_segments() {
+        let found = segments(&elf_with_segments());
+        let names: Vec<&str> = found.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["LOAD", "NOTE"]);
+        assert_eq!(
+            found[1],
+            Segment {
+                name: "NOTE".to_string(),
+                addr: 0x4000f0,
+                mem_size: 0x10,
+                offset: 0xf0,
+                file_size: 0x10,
+            }
+        );
+        assert!(segments(b"not a binary").is_empty());
+    }
+}
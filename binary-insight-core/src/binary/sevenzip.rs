@@ -0,0 +1,928 @@
+//! Reader for 7z archives: the header, including a compressed one, and
+//! blocks packed with Copy, LZMA, LZMA2 or Deflate, optionally behind the
+//! x86 BCJ filter. Encrypted blocks and other methods are listed but not
+//! unpacked.
+
+use super::compression::LimitedWriter;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Magic, version, start header CRC, then the next header's offset, size and CRC.
+const SIGNATURE_HEADER_SIZE: usize = 32;
+/// Largest compressed header that is unpacked.
+const HEADER_LIMIT: u64 = 64 * 1024 * 1024;
+
+// Property ids.
+const END: u8 = 0x00;
+const HEADER: u8 = 0x01;
+const ARCHIVE_PROPERTIES: u8 = 0x02;
+const ADDITIONAL_STREAMS_INFO: u8 = 0x03;
+const MAIN_STREAMS_INFO: u8 = 0x04;
+const FILES_INFO: u8 = 0x05;
+const PACK_INFO: u8 = 0x06;
+const UNPACK_INFO: u8 = 0x07;
+const SUBSTREAMS_INFO: u8 = 0x08;
+const SIZE: u8 = 0x09;
+const CRC: u8 = 0x0a;
+const FOLDER: u8 = 0x0b;
+const CODERS_UNPACK_SIZE: u8 = 0x0c;
+const NUM_UNPACK_STREAM: u8 = 0x0d;
+const EMPTY_STREAM: u64 = 0x0e;
+const EMPTY_FILE: u64 = 0x0f;
+const NAMES: u64 = 0x11;
+const ENCODED_HEADER: u8 = 0x17;
+
+// Coder method ids.
+const COPY: &[u8] = &[0x00];
+const LZMA: &[u8] = &[0x03, 0x01, 0x01];
+const LZMA2: &[u8] = &[0x21];
+const DEFLATE: &[u8] = &[0x04, 0x01, 0x08];
+const BCJ_X86: &[u8] = &[0x03, 0x03, 0x01, 0x03];
+
+fn method_name(method: &[u8]) -> String {
+    match method {
+        COPY => "Copy".to_string(),
+        LZMA => "LZMA".to_string(),
+        LZMA2 => "LZMA2".to_string(),
+        DEFLATE => "Deflate".to_string(),
+        BCJ_X86 => "BCJ".to_string(),
+        [0x03, 0x03, 0x01, 0x1b] => "BCJ2".to_string(),
+        [0x04, 0x02, 0x02] => "BZip2".to_string(),
+        [0x03, 0x04, 0x01] => "PPMd".to_string(),
+        [0x06, 0xf1, 0x07, 0x01] => "AES-256".to_string(),
+        _ => hex::encode(method),
+    }
+}
+
+struct Coder {
+    method: Vec<u8>,
+    props: Vec<u8>,
+    num_in: usize,
+    num_out: usize,
+}
+
+/// A block of coders unpacked as one stream; a solid archive keeps many
+/// files in one folder.
+#[derive(Default)]
+struct Folder {
+    coders: Vec<Coder>,
+    /// (in stream, out stream) pairs chaining the coders.
+    bind_pairs: Vec<(usize, usize)>,
+    /// In streams fed from the packed streams, in order.
+    packed: Vec<usize>,
+    /// Size of every out stream.
+    unpack_sizes: Vec<u64>,
+    /// Offset of the folder's first packed stream in the archive.
+    offset: u64,
+    /// Sizes of its packed streams.
+    pack_sizes: Vec<u64>,
+}
+
+impl Folder {
+    /// The out stream no other coder consumes.
+    fn main_out(&self) -> Option<usize> {
+        (0..self.unpack_sizes.len()).find(|out| !self.bind_pairs.iter().any(|(_, o)| o == out))
+    }
+
+    fn unpack_size(&self) -> u64 {
+        self.main_out()
+            .map_or(0, |out| self.unpack_sizes.get(out).copied().unwrap_or(0))
+    }
+}
+
+#[derive(Default)]
+struct StreamsInfo {
+    folders: Vec<Folder>,
+    /// (size, CRC) of each file stream, per folder.
+    substreams: Vec<Vec<(u64, Option<u32>)>>,
+}
+
+pub struct Entry {
+    pub path: String,
+    pub size: u64,
+    crc: Option<u32>,
+    /// Folder and offset within its unpacked stream; `None` for empty files.
+    location: Option<(usize, u64)>,
+}
+
+pub struct Archive {
+    folders: Vec<Folder>,
+    pub entries: Vec<Entry>,
+    by_path: HashMap<String, usize>,
+    /// The folder unpacked last, since a solid folder holds many members.
+    unpacked: Option<(usize, Vec<u8>)>,
+}
+
+impl Archive {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let signature = data
+            .get(..SIGNATURE_HEADER_SIZE)
+            .context("Truncated 7z signature header")?;
+        let field = |at: usize| u64::from_le_bytes(signature[at..at + 8].try_into().unwrap());
+        let start = field(12)
+            .checked_add(SIGNATURE_HEADER_SIZE as u64)
+            .context("Invalid 7z header offset")?;
+        let end = start
+            .checked_add(field(20))
+            .context("Invalid 7z header size")?;
+        let header = usize::try_from(start)
+            .ok()
+            .zip(usize::try_from(end).ok())
+            .and_then(|(start, end)| data.get(start..end))
+            .context("7z header is out of bounds")?;
+
+        let decoded;
+        let mut reader = Reader::new(header);
+        if header.is_empty() {
+            // An empty archive has no header at all.
+            return Ok(Self::new(StreamsInfo::default(), Vec::new()));
+        }
+        if header[0] == ENCODED_HEADER {
+            reader.byte()?;
+            let streams = streams_info(&mut reader, data)?;
+            let folder = streams.folders.first().context("Empty 7z encoded header")?;
+            decoded = unpack_folder(data, folder, HEADER_LIMIT)?;
+            reader = Reader::new(&decoded);
+        }
+        if reader.byte()? != HEADER {
+            bail!("Invalid 7z header");
+        }
+
+        let mut id = reader.byte()?;
+        if id == ARCHIVE_PROPERTIES {
+            while reader.number()? != 0 {
+                let size = reader.count()?;
+                reader.bytes(size)?;
+            }
+            id = reader.byte()?;
+        }
+        if id == ADDITIONAL_STREAMS_INFO {
+            streams_info(&mut reader, data)?;
+            id = reader.byte()?;
+        }
+        let mut streams = StreamsInfo::default();
+        if id == MAIN_STREAMS_INFO {
+            streams = streams_info(&mut reader, data)?;
+            id = reader.byte()?;
+        }
+        let mut files = Vec::new();
+        if id == FILES_INFO {
+            files = files_info(&mut reader)?;
+            id = reader.byte()?;
+        }
+        if id != END {
+            bail!("Invalid 7z header");
+        }
+        Ok(Self::new(streams, files))
+    }
+
+    /// Pairs the files that have data with the folders' streams, in order.
+    fn new(streams: StreamsInfo, files: Vec<FileInfo>) -> Self {
+        let mut substreams = streams
+            .substreams
+            .iter()
+            .enumerate()
+            .flat_map(|(folder, sizes)| {
+                sizes.iter().scan(0u64, move |offset, &(size, crc)| {
+                    let at = *offset;
+                    *offset = offset.saturating_add(size);
+                    Some((folder, at, size, crc))
+                })
+            });
+        let mut entries = Vec::new();
+        for file in files {
+            let entry = if file.has_stream {
+                let Some((folder, offset, size, crc)) = substreams.next() else {
+                    break;
+                };
+                Entry {
+                    path: file.name,
+                    size,
+                    crc,
+                    location: Some((folder, offset)),
+                }
+            } else if file.is_empty_file {
+                Entry {
+                    path: file.name,
+                    size: 0,
+                    crc: None,
+                    location: None,
+                }
+            } else {
+                // A directory.
+                continue;
+            };
+            entries.push(entry);
+        }
+        let mut by_path = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            by_path.entry(entry.path.clone()).or_insert(i);
+        }
+        Archive {
+            folders: streams.folders,
+            entries,
+            by_path,
+            unpacked: None,
+        }
+    }
+
+    /// Offset of the packed data `entry` is unpacked from.
+    pub fn offset(&self, entry: &Entry) -> u64 {
+        entry
+            .location
+            .map_or(0, |(folder, _)| self.folders[folder].offset)
+    }
+
+    /// Contents of the file at `path`. Its whole folder is unpacked, so
+    /// this fails when the folder is larger than `limit` bytes.
+    pub fn read(&mut self, data: &[u8], path: &str, limit: u64) -> Result<Vec<u8>> {
+        let entry = self
+            .by_path
+            .get(path)
+            .map(|&i| &self.entries[i])
+            .with_context(|| format!("{} is not in the archive", path))?;
+        let Some((folder, offset)) = entry.location else {
+            return Ok(Vec::new());
+        };
+        if self.unpacked.as_ref().is_none_or(|(i, _)| *i != folder) {
+            let size = self.folders[folder].unpack_size();
+            if size > limit {
+                bail!(
+                    "{} is in a 7z block of {} bytes, larger than {} bytes",
+                    path,
+                    size,
+                    limit
+                );
+            }
+            let contents = unpack_folder(data, &self.folders[folder], limit)
+                .with_context(|| format!("Failed to unpack {}", path))?;
+            self.unpacked = Some((folder, contents));
+        }
+        let (_, unpacked) = self.unpacked.as_ref().unwrap();
+        let contents = usize::try_from(offset)
+            .ok()
+            .zip(usize::try_from(offset.saturating_add(entry.size)).ok())
+            .and_then(|(start, end)| unpacked.get(start..end))
+            .with_context(|| format!("{} is out of bounds", path))?;
+        if let Some(crc) = entry.crc {
+            let mut actual = flate2::Crc::new();
+            actual.update(contents);
+            if actual.sum() != crc {
+                bail!("CRC mismatch in {}", path);
+            }
+        }
+        Ok(contents.to_vec())
+    }
+}
+
+struct FileInfo {
+    name: String,
+    has_stream: bool,
+    is_empty_file: bool,
+}
+
+fn streams_info(reader: &mut Reader, data: &[u8]) -> Result<StreamsInfo> {
+    let mut streams = StreamsInfo::default();
+    let mut pack_pos = 0;
+    let mut pack_sizes = Vec::new();
+    let mut id = reader.byte()?;
+    if id == PACK_INFO {
+        pack_pos = reader.number()?;
+        let count = reader.count()?;
+        loop {
+            match reader.byte()? {
+                END => break,
+                SIZE => pack_sizes = (0..count).map(|_| reader.number()).collect::<Result<_>>()?,
+                CRC => {
+                    reader.digests(count)?;
+                }
+                other => bail!("Unexpected 7z pack info property 0x{:x}", other),
+            }
+        }
+        id = reader.byte()?;
+    }
+    if id == UNPACK_INFO {
+        if reader.byte()? != FOLDER {
+            bail!("Invalid 7z unpack info");
+        }
+        let count = reader.count()?;
+        if reader.byte()? != 0 {
+            bail!("External 7z folders are not supported");
+        }
+        streams.folders = (0..count).map(|_| folder(reader)).collect::<Result<_>>()?;
+        if reader.byte()? != CODERS_UNPACK_SIZE {
+            bail!("Invalid 7z unpack info");
+        }
+        for folder in &mut streams.folders {
+            let outs = folder.coders.iter().map(|c| c.num_out).sum::<usize>();
+            folder.unpack_sizes = (0..outs).map(|_| reader.number()).collect::<Result<_>>()?;
+        }
+        let mut crcs = vec![None; count];
+        loop {
+            match reader.byte()? {
+                END => break,
+                CRC => crcs = reader.digests(count)?,
+                other => bail!("Unexpected 7z unpack info property 0x{:x}", other),
+            }
+        }
+        streams.substreams = streams
+            .folders
+            .iter()
+            .zip(crcs)
+            .map(|(folder, crc)| vec![(folder.unpack_size(), crc)])
+            .collect();
+        id = reader.byte()?;
+    }
+    if id == SUBSTREAMS_INFO {
+        substreams_info(reader, &mut streams)?;
+        id = reader.byte()?;
+    }
+    if id != END {
+        bail!("Unexpected 7z streams info property 0x{:x}", id);
+    }
+
+    // Folders take the packed streams in order.
+    let mut offset = (SIGNATURE_HEADER_SIZE as u64).saturating_add(pack_pos);
+    let mut sizes = pack_sizes.into_iter();
+    for folder in &mut streams.folders {
+        folder.offset = offset;
+        folder.pack_sizes = sizes.by_ref().take(folder.packed.len()).collect();
+        if folder.pack_sizes.len() != folder.packed.len() {
+            bail!("7z folder is missing packed streams");
+        }
+        offset = folder
+            .pack_sizes
+            .iter()
+            .try_fold(offset, |offset, &size| offset.checked_add(size))
+            .filter(|&end| end <= data.len() as u64)
+            .context("7z packed stream is out of bounds")?;
+    }
+    Ok(streams)
+}
+
+fn folder(reader: &mut Reader) -> Result<Folder> {
+    let mut folder = Folder::default();
+    for _ in 0..reader.count()? {
+        let flags = reader.byte()?;
+        if flags & 0x80 != 0 {
+            bail!("Alternative 7z coder methods are not supported");
+        }
+        let method = reader.bytes((flags & 0x0f) as usize)?.to_vec();
+        let (num_in, num_out) = if flags & 0x10 != 0 {
+            (reader.count()?, reader.count()?)
+        } else {
+            (1, 1)
+        };
+        let props = if flags & 0x20 != 0 {
+            let size = reader.count()?;
+            reader.bytes(size)?.to_vec()
+        } else {
+            Vec::new()
+        };
+        folder.coders.push(Coder {
+            method,
+            props,
+            num_in,
+            num_out,
+        });
+    }
+    let ins = folder.coders.iter().map(|c| c.num_in).sum::<usize>();
+    let outs = folder.coders.iter().map(|c| c.num_out).sum::<usize>();
+    let pairs = outs.checked_sub(1).context("7z folder has no coders")?;
+    for _ in 0..pairs {
+        folder.bind_pairs.push((reader.count()?, reader.count()?));
+    }
+    let packed = ins
+        .checked_sub(pairs)
+        .filter(|&packed| packed > 0)
+        .context("Invalid 7z folder")?;
+    folder.packed = if packed == 1 {
+        let input = (0..ins)
+            .find(|i| !folder.bind_pairs.iter().any(|(bound, _)| bound == i))
+            .context("Invalid 7z folder")?;
+        vec![input]
+    } else {
+        (0..packed).map(|_| reader.count()).collect::<Result<_>>()?
+    };
+    Ok(folder)
+}
+
+fn substreams_info(reader: &mut Reader, streams: &mut StreamsInfo) -> Result<()> {
+    let mut counts = vec![1; streams.folders.len()];
+    let mut id = reader.byte()?;
+    if id == NUM_UNPACK_STREAM {
+        for count in &mut counts {
+            *count = reader.count()?;
+        }
+        id = reader.byte()?;
+    }
+
+    let mut substreams = Vec::new();
+    for (folder, &count) in streams.folders.iter().zip(&counts) {
+        let mut sizes = Vec::new();
+        if count > 1 {
+            if id != SIZE {
+                bail!("7z substream sizes are missing");
+            }
+            for _ in 1..count {
+                sizes.push(reader.number()?);
+            }
+        }
+        if count > 0 {
+            let total = sizes
+                .iter()
+                .try_fold(0u64, |sum, &size| sum.checked_add(size));
+            let last = total
+                .and_then(|total| folder.unpack_size().checked_sub(total))
+                .context("7z substreams exceed their folder")?;
+            sizes.push(last);
+        }
+        substreams.push(sizes);
+    }
+    if id == SIZE {
+        id = reader.byte()?;
+    }
+
+    // Folders holding a single stream already carry its CRC.
+    let known: Vec<Option<u32>> = streams
+        .substreams
+        .iter()
+        .map(|s| s.first().and_then(|&(_, crc)| crc))
+        .collect();
+    let unknown = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            if count == 1 && known.get(i).copied().flatten().is_some() {
+                0
+            } else {
+                count
+            }
+        })
+        .sum::<usize>();
+    let mut digests = Vec::new();
+    while id != END {
+        match id {
+            CRC => digests = reader.digests(unknown)?,
+            other => bail!("Unexpected 7z substreams property 0x{:x}", other),
+        }
+        id = reader.byte()?;
+    }
+
+    let mut digests = digests.into_iter();
+    streams.substreams = substreams
+        .into_iter()
+        .enumerate()
+        .map(|(i, sizes)| {
+            let folder_crc = known.get(i).copied().flatten();
+            if sizes.len() == 1 && folder_crc.is_some() {
+                vec![(sizes[0], folder_crc)]
+            } else {
+                sizes
+                    .into_iter()
+                    .map(|size| (size, digests.next().flatten()))
+                    .collect()
+            }
+        })
+        .collect();
+    Ok(())
+}
+
+fn files_info(reader: &mut Reader) -> Result<Vec<FileInfo>> {
+    let count = reader.count()?;
+    let mut empty_stream = vec![false; count];
+    let mut empty_file = Vec::new();
+    let mut names = Vec::new();
+    loop {
+        let kind = reader.number()?;
+        if kind == 0 {
+            break;
+        }
+        let size = reader.count()?;
+        let mut property = Reader::new(reader.bytes(size)?);
+        match kind {
+            EMPTY_STREAM => empty_stream = property.bits(count)?,
+            EMPTY_FILE => {
+                let empty = empty_stream.iter().filter(|&&e| e).count();
+                empty_file = property.bits(empty)?;
+            }
+            NAMES => {
+                if property.byte()? != 0 {
+                    bail!("External 7z file names are not supported");
+                }
+                let units: Vec<u16> = property
+                    .rest()
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                names = units
+                    .split(|&unit| unit == 0)
+                    .take(count)
+                    .map(String::from_utf16_lossy)
+                    .collect();
+            }
+            // Times, attributes and padding.
+            _ => {}
+        }
+    }
+    if names.len() < count {
+        bail!("7z archive names {} of {} files", names.len(), count);
+    }
+
+    let mut empty_file = empty_file.into_iter();
+    Ok(names
+        .into_iter()
+        .zip(empty_stream)
+        .map(|(name, empty)| FileInfo {
+            name,
+            has_stream: !empty,
+            is_empty_file: empty && empty_file.next().unwrap_or(false),
+        })
+        .collect())
+}
+
+/// Unpacks the main stream of `folder`, refusing more than `limit` bytes.
+fn unpack_folder(data: &[u8], folder: &Folder, limit: u64) -> Result<Vec<u8>> {
+    if let Some(coder) = folder
+        .coders
+        .iter()
+        .find(|c| c.num_in != 1 || c.num_out != 1)
+    {
+        bail!("Unsupported 7z method {}", method_name(&coder.method));
+    }
+    let main = folder.main_out().context("Invalid 7z folder")?;
+    unpack_stream(data, folder, main, limit, folder.coders.len())
+}
+
+/// Output of coder `index`; with single-stream coders, stream and coder
+/// indices coincide. `depth` stops bind pairs that form a cycle.
+fn unpack_stream(
+    data: &[u8],
+    folder: &Folder,
+    index: usize,
+    limit: u64,
+    depth: usize,
+) -> Result<Vec<u8>> {
+    let coder = folder.coders.get(index).context("Invalid 7z folder")?;
+    let size = folder.unpack_sizes[index];
+    if size > limit {
+        bail!("7z stream is larger than {} bytes", limit);
+    }
+    let packed;
+    let input = match folder.bind_pairs.iter().find(|(input, _)| *input == index) {
+        Some(&(_, out)) => {
+            let depth = depth.checked_sub(1).context("Cyclic 7z folder")?;
+            packed = unpack_stream(data, folder, out, limit, depth)?;
+            &packed[..]
+        }
+        None => {
+            let stream = folder
+                .packed
+                .iter()
+                .position(|&input| input == index)
+                .context("Invalid 7z folder")?;
+            let start = folder.offset + folder.pack_sizes[..stream].iter().sum::<u64>();
+            &data[start as usize..(start + folder.pack_sizes[stream]) as usize]
+        }
+    };
+
+    let mut out = Vec::new();
+    match coder.method.as_slice() {
+        COPY => out.extend_from_slice(input),
+        LZMA => {
+            // lzma-rs reads the properties and dictionary size as a header.
+            let props = coder.props.get(..5).context("Invalid 7z LZMA properties")?;
+            let options = lzma_rs::decompress::Options {
+                unpacked_size: lzma_rs::decompress::UnpackedSize::UseProvided(Some(size)),
+                ..Default::default()
+            };
+            let mut writer = LimitedWriter {
+                inner: &mut out,
+                remaining: size,
+            };
+            lzma_rs::lzma_decompress_with_options(&mut props.chain(input), &mut writer, &options)
+                .map_err(|e| anyhow!("LZMA decompression failed: {:?}", e))?;
+        }
+        LZMA2 => {
+            let mut writer = LimitedWriter {
+                inner: &mut out,
+                remaining: size,
+            };
+            lzma_rs::lzma2_decompress(&mut &input[..], &mut writer)
+                .map_err(|e| anyhow!("LZMA2 decompression failed: {:?}", e))?;
+        }
+        DEFLATE => {
+            flate2::read::DeflateDecoder::new(input)
+                .take(size)
+                .read_to_end(&mut out)
+                .context("Deflate decompression failed")?;
+        }
+        BCJ_X86 => {
+            out = input.to_vec();
+            bcj_x86_decode(&mut out);
+        }
+        method => bail!("Unsupported 7z method {}", method_name(method)),
+    }
+    if out.len() as u64 != size {
+        bail!(
+            "7z stream unpacked to {} bytes, expected {}",
+            out.len(),
+            size
+        );
+    }
+    Ok(out)
+}
+
+/// Undoes the x86 branch converter, which turns the relative targets of
+/// `call` and `jmp` into absolute ones so they compress better.
+fn bcj_x86_decode(buf: &mut [u8]) {
+    const ALLOWED: [bool; 8] = [true, true, true, false, true, false, false, false];
+    const BIT: [u32; 8] = [0, 1, 2, 2, 3, 3, 3, 3];
+    let is_ms_byte = |b: u8| b == 0 || b == 0xff;
+
+    let mut prev_mask = 0u32;
+    let mut prev_pos = 0u32.wrapping_sub(5);
+    let mut pos = 0;
+    while pos + 5 <= buf.len() {
+        if buf[pos] != 0xe8 && buf[pos] != 0xe9 {
+            pos += 1;
+            continue;
+        }
+        let offset = (pos as u32).wrapping_sub(prev_pos);
+        prev_pos = pos as u32;
+        if offset > 5 {
+            prev_mask = 0;
+        } else {
+            for _ in 0..offset {
+                prev_mask &= 0x77;
+                prev_mask <<= 1;
+            }
+        }
+        let b = buf[pos + 4];
+        if is_ms_byte(b) && ALLOWED[((prev_mask >> 1) & 7) as usize] && (prev_mask >> 1) < 0x10 {
+            let mut src = u32::from_le_bytes(buf[pos + 1..pos + 5].try_into().unwrap());
+            let mut dest;
+            loop {
+                dest = src.wrapping_sub(pos as u32 + 5);
+                if prev_mask == 0 {
+                    break;
+                }
+                let i = BIT[(prev_mask >> 1) as usize];
+                if !is_ms_byte((dest >> (24 - i * 8)) as u8) {
+                    break;
+                }
+                src = dest ^ ((1u32 << (32 - i * 8)) - 1);
+            }
+            let dest = (dest & 0x00ff_ffff)
+                | if dest & 0x0100_0000 != 0 {
+                    0xff00_0000
+                } else {
+                    0
+                };
+            buf[pos + 1..pos + 5].copy_from_slice(&dest.to_le_bytes());
+            pos += 5;
+            prev_mask = 0;
+        } else {
+            pos += 1;
+            prev_mask |= 1;
+            if is_ms_byte(b) {
+                prev_mask |= 0x10;
+            }
+        }
+    }
+}
+
+/// Cursor over header bytes.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .context("Truncated 7z header")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.pos..];
+        self.pos = self.data.len();
+        rest
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// A variable-length number: the leading one bits of the first byte
+    /// count the little-endian bytes that follow.
+    fn number(&mut self) -> Result<u64> {
+        let first = self.byte()?;
+        let mut value = 0u64;
+        for i in 0..8 {
+            let mask = 0x80u8 >> i;
+            if first & mask == 0 {
+                let high = u64::from(first & mask.wrapping_sub(1));
+                return Ok(value | high << (8 * i));
+            }
+            value |= u64::from(self.byte()?) << (8 * i);
+        }
+        Ok(value)
+    }
+
+    /// A number of items, which cannot exceed the bytes describing them.
+    fn count(&mut self) -> Result<usize> {
+        usize::try_from(self.number()?)
+            .ok()
+            .filter(|&count| count <= self.data.len())
+            .context("Implausible count in 7z header")
+    }
+
+    /// `count` flags, most significant bit first.
+    fn bits(&mut self, count: usize) -> Result<Vec<bool>> {
+        let bytes = self.bytes(count.div_ceil(8))?;
+        Ok((0..count)
+            .map(|i| bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+            .collect())
+    }
+
+    /// `count` optional CRCs, preceded by the flags saying which are set.
+    fn digests(&mut self, count: usize) -> Result<Vec<Option<u32>>> {
+        let defined = if self.byte()? != 0 {
+            vec![true; count]
+        } else {
+            self.bits(count)?
+        };
+        defined
+            .into_iter()
+            .map(|defined| {
+                defined
+                    .then(|| Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?)))
+                    .transpose()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: u64) -> Vec<u8> {
+        if value < 0x80 {
+            vec![value as u8]
+        } else if value < 0x4000 {
+            vec![0x80 | (value >> 8) as u8, value as u8]
+        } else {
+            let mut bytes = vec![0xff];
+            bytes.extend(value.to_le_bytes());
+            bytes
+        }
+    }
+
+    /// A solid archive holding `files` in one folder packed by `method`,
+    /// followed by an empty file and a directory.
+    fn seven_zip(files: &[(&str, &[u8])], method: &[u8], props: &[u8], packed: &[u8]) -> Vec<u8> {
+        let total: usize = files.iter().map(|(_, c)| c.len()).sum();
+        let mut header = vec![HEADER, MAIN_STREAMS_INFO, PACK_INFO, 0, 1, SIZE];
+        header.extend(number(packed.len() as u64));
+        header.extend([END, UNPACK_INFO, FOLDER, 1, 0, 1]);
+        header.push(method.len() as u8 | if props.is_empty() { 0 } else { 0x20 });
+        header.extend(method);
+        if !props.is_empty() {
+            header.push(props.len() as u8);
+            header.extend(props);
+        }
+        header.push(CODERS_UNPACK_SIZE);
+        header.extend(number(total as u64));
+        header.extend([
+            END,
+            SUBSTREAMS_INFO,
+            NUM_UNPACK_STREAM,
+            files.len() as u8,
+            SIZE,
+        ]);
+        for (_, contents) in &files[..files.len() - 1] {
+            header.extend(number(contents.len() as u64));
+        }
+        header.extend([CRC, 1]);
+        for (_, contents) in files {
+            let mut crc = flate2::Crc::new();
+            crc.update(contents);
+            header.extend(crc.sum().to_le_bytes());
+        }
+        header.extend([END, END, FILES_INFO, files.len() as u8 + 2]);
+        // The empty file and the directory have no stream.
+        let mut bits = vec![0u8; (files.len() + 2).div_ceil(8)];
+        for i in files.len()..files.len() + 2 {
+            bits[i / 8] |= 0x80 >> (i % 8);
+        }
+        header.extend([EMPTY_STREAM as u8, bits.len() as u8]);
+        header.extend(bits);
+        header.extend([EMPTY_FILE as u8, 1, 0x80]);
+        let mut names = vec![0];
+        for name in files.iter().map(|(n, _)| *n).chain(["empty", "dir"]) {
+            names.extend(name.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        }
+        header.push(NAMES as u8);
+        header.extend(number(names.len() as u64));
+        header.extend(names);
+        header.extend([END, END]);
+
+        let mut data = b"7z\xbc\xaf\x27\x1c\x00\x04".to_vec();
+        data.extend([0; 4]);
+        data.extend((packed.len() as u64).to_le_bytes());
+        data.extend((header.len() as u64).to_le_bytes());
+        data.extend([0; 4]);
+        data.extend(packed);
+        data.extend(header);
+        data
+    }
+
+    const FILES: [(&str, &[u8]); 2] = [("bin/tool", b"\x7fELF\x02\x01"), ("README", b"hello")];
+
+    fn check(data: &[u8]) {
+        let mut archive = Archive::parse(data).expect("parse failed");
+        let listed: Vec<_> = archive
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.size))
+            .collect();
+        assert_eq!(listed, [("bin/tool", 6), ("README", 5), ("empty", 0)]);
+        assert_eq!(archive.read(data, "README", 1024).unwrap(), b"hello");
+        assert_eq!(archive.read(data, "bin/tool", 1024).unwrap(), FILES[0].1);
+        assert!(archive.read(data, "empty", 1024).unwrap().is_empty());
+        assert!(archive.read(data, "dir", 1024).is_err());
+    }
+
+    #[test]
+    fn test_copy() {
+        let packed = [FILES[0].1, FILES[1].1].concat();
+        let data = seven_zip(&FILES, COPY, &[], &packed);
+        check(&data);
+
+        let mut archive = Archive::parse(&data).unwrap();
+        assert!(archive.read(&data, "README", 4).is_err());
+        let mut corrupt = data.clone();
+        corrupt[SIGNATURE_HEADER_SIZE + 7] ^= 1;
+        let err = Archive::parse(&corrupt)
+            .unwrap()
+            .read(&corrupt, "README", 1024)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "CRC mismatch in README");
+    }
+
+    #[test]
+    fn test_lzma() {
+        let contents = [FILES[0].1, FILES[1].1].concat();
+        // An .lzma stream: properties, dictionary size, unpacked size, data.
+        let mut stream = Vec::new();
+        lzma_rs::lzma_compress(&mut &contents[..], &mut stream).unwrap();
+        check(&seven_zip(&FILES, LZMA, &stream[..5], &stream[13..]));
+
+        let mut stream = Vec::new();
+        lzma_rs::lzma2_compress(&mut &contents[..], &mut stream).unwrap();
+        check(&seven_zip(&FILES, LZMA2, &[0x18], &stream));
+    }
+
+    #[test]
+    fn test_unsupported_method() {
+        let packed = [FILES[0].1, FILES[1].1].concat();
+        let data = seven_zip(&FILES, &[0x06, 0xf1, 0x07, 0x01], &[], &packed);
+        let mut archive = Archive::parse(&data).unwrap();
+        assert_eq!(archive.entries.len(), 3);
+        let err = archive.read(&data, "README", 1024).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Failed to unpack README: Unsupported 7z method AES-256"
+        );
+    }
+
+    #[test]
+    fn test_bcj_x86_decode() {
+        let mut buf = hex::decode("55e81600000090e9fcffffffe811010000c3e801020304e80000").unwrap();
+        bcj_x86_decode(&mut buf);
+        assert_eq!(
+            hex::encode(buf),
+            "55e81000000090e9f0ffffffe800010000c3e801020304e80000"
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        for value in [0, 0x7f, 0x80, 0x3fff, 0x4000, u64::MAX] {
+            assert_eq!(Reader::new(&number(value)).number().unwrap(), value);
+        }
+        assert_eq!(Reader::new(&[0xc1, 0x02, 0x03]).number().unwrap(), 0x010302);
+    }
+}
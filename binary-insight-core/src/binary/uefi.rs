@@ -0,0 +1,403 @@
+use anyhow::{bail, Result};
+
+const TE_SIGNATURE: &[u8] = b"VZ";
+const TE_HEADER_SIZE: usize = 40;
+const FV_SIGNATURE: &[u8] = b"_FVH";
+const FV_SIGNATURE_OFFSET: usize = 40;
+const FFS_ATTRIB_LARGE_FILE: u8 = 0x01;
+const FFS_TYPE_RAW: u8 = 0x01;
+const FFS_TYPE_PAD: u8 = 0xf0;
+const SECTION_PE32: u8 = 0x10;
+const SECTION_TE: u8 = 0x12;
+const SECTION_USER_INTERFACE: u8 = 0x15;
+const SECTION_FV_IMAGE: u8 = 0x17;
+/// Nested firmware volumes are followed at most this deep.
+const MAX_FV_DEPTH: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct TeSection {
+    pub name: String,
+    pub virtual_address: u32,
+    pub virtual_size: u32,
+    /// Offset of the raw data within the TE file (already adjusted for the stripped header).
+    pub offset: u64,
+    pub raw_size: u32,
+}
+
+/// Terse Executable header, the stripped-down PE variant used by PEI modules.
+#[derive(Debug, Clone)]
+pub struct TeImage {
+    pub machine: u16,
+    pub subsystem: u8,
+    pub stripped_size: u16,
+    pub entry_point: u32,
+    pub base_of_code: u32,
+    pub image_base: u64,
+    pub sections: Vec<TeSection>,
+}
+
+impl TeImage {
+    pub fn arch(&self) -> String {
+        match self.machine {
+            0x014c => "x86".to_string(),
+            0x8664 => "x86_64".to_string(),
+            0xaa64 => "aarch64".to_string(),
+            0x01c0 | 0x01c2 | 0x01c4 => "arm".to_string(),
+            0x5032 => "riscv32".to_string(),
+            0x5064 => "riscv64".to_string(),
+            other => format!("Unknown (0x{:x})", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FfsSection {
+    pub section_type: u8,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl FfsSection {
+    pub fn type_name(&self) -> &'static str {
+        match self.section_type {
+            0x01 => "COMPRESSION",
+            0x02 => "GUID_DEFINED",
+            0x03 => "DISPOSABLE",
+            SECTION_PE32 => "PE32",
+            0x11 => "PIC",
+            SECTION_TE => "TE",
+            0x13 => "DXE_DEPEX",
+            0x14 => "VERSION",
+            SECTION_USER_INTERFACE => "USER_INTERFACE",
+            0x16 => "COMPATIBILITY16",
+            SECTION_FV_IMAGE => "FIRMWARE_VOLUME_IMAGE",
+            0x18 => "FREEFORM_SUBTYPE_GUID",
+            0x19 => "RAW",
+            0x1b => "PEI_DEPEX",
+            0x1c => "MM_DEPEX",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// True for sections that carry a loadable PE32 or TE image.
+    pub fn is_image(&self) -> bool {
+        self.section_type == SECTION_PE32 || self.section_type == SECTION_TE
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FfsFile {
+    pub guid: String,
+    pub file_type: u8,
+    pub offset: u64,
+    pub size: u64,
+    /// Name from the USER_INTERFACE section, if present.
+    pub name: Option<String>,
+    pub sections: Vec<FfsSection>,
+    pub volumes: Vec<FirmwareVolume>,
+}
+
+impl FfsFile {
+    pub fn type_name(&self) -> &'static str {
+        match self.file_type {
+            FFS_TYPE_RAW => "RAW",
+            0x02 => "FREEFORM",
+            0x03 => "SECURITY_CORE",
+            0x04 => "PEI_CORE",
+            0x05 => "DXE_CORE",
+            0x06 => "PEIM",
+            0x07 => "DRIVER",
+            0x08 => "COMBINED_PEIM_DRIVER",
+            0x09 => "APPLICATION",
+            0x0a => "MM",
+            0x0b => "FIRMWARE_VOLUME_IMAGE",
+            0x0c => "COMBINED_MM_DXE",
+            0x0d => "MM_CORE",
+            FFS_TYPE_PAD => "PAD",
+            _ => "UNKNOWN",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FirmwareVolume {
+    pub offset: u64,
+    pub length: u64,
+    pub filesystem_guid: String,
+    pub files: Vec<FfsFile>,
+}
+
+impl FirmwareVolume {
+    /// Counts files in this volume and all nested volumes.
+    pub fn file_count(&self) -> usize {
+        self.files
+            .iter()
+            .map(|f| 1 + f.volumes.iter().map(|v| v.file_count()).sum::<usize>())
+            .sum()
+    }
+}
+
+pub fn is_te(data: &[u8]) -> bool {
+    data.starts_with(TE_SIGNATURE) && data.len() >= TE_HEADER_SIZE
+}
+
+pub fn parse_te(data: &[u8]) -> Result<TeImage> {
+    if !is_te(data) {
+        bail!("Not a TE image");
+    }
+    let stripped_size = read_u16(data, 6).unwrap_or(0);
+    let section_count = data[4] as usize;
+
+    let mut sections = Vec::with_capacity(section_count);
+    for i in 0..section_count {
+        let base = TE_HEADER_SIZE + i * 40;
+        let Some(header) = data.get(base..base + 40) else {
+            bail!("TE section table truncated");
+        };
+        let name_len = header[..8].iter().position(|&b| b == 0).unwrap_or(8);
+        let pointer_to_raw = read_u32(header, 20).unwrap_or(0) as u64;
+        sections.push(TeSection {
+            name: String::from_utf8_lossy(&header[..name_len]).to_string(),
+            virtual_size: read_u32(header, 8).unwrap_or(0),
+            virtual_address: read_u32(header, 12).unwrap_or(0),
+            raw_size: read_u32(header, 16).unwrap_or(0),
+            offset: (pointer_to_raw + TE_HEADER_SIZE as u64).saturating_sub(stripped_size as u64),
+        });
+    }
+
+    Ok(TeImage {
+        machine: read_u16(data, 2).unwrap_or(0),
+        subsystem: data[5],
+        stripped_size,
+        entry_point: read_u32(data, 8).unwrap_or(0),
+        base_of_code: read_u32(data, 12).unwrap_or(0),
+        image_base: read_u64(data, 16).unwrap_or(0),
+        sections,
+    })
+}
+
+/// Scans the input for firmware volume headers (e.g. in a full SPI flash dump)
+/// and parses each volume's FFS file list.
+pub fn find_firmware_volumes(data: &[u8]) -> Vec<FirmwareVolume> {
+    let mut volumes = Vec::new();
+    let mut offset = 0;
+    while offset + FV_SIGNATURE_OFFSET + 4 <= data.len() {
+        if &data[offset + FV_SIGNATURE_OFFSET..offset + FV_SIGNATURE_OFFSET + 4] == FV_SIGNATURE {
+            if let Some(volume) = parse_volume(data, offset, 0) {
+                let length = volume.length.max(16) as usize;
+                volumes.push(volume);
+                offset += length.next_multiple_of(16);
+                continue;
+            }
+        }
+        offset += 16;
+    }
+    volumes
+}
+
+fn parse_volume(data: &[u8], offset: usize, depth: usize) -> Option<FirmwareVolume> {
+    let header = data.get(offset..)?;
+    if header.get(FV_SIGNATURE_OFFSET..FV_SIGNATURE_OFFSET + 4)? != FV_SIGNATURE {
+        return None;
+    }
+    let length = read_u64(header, 32)?;
+    let header_length = read_u16(header, 48)? as usize;
+    let ext_header_offset = read_u16(header, 52)? as usize;
+    let volume = header.get(..(length as usize).min(header.len()))?;
+
+    let files_start = if ext_header_offset != 0 {
+        let ext_size = read_u32(volume, ext_header_offset + 16)? as usize;
+        (ext_header_offset + ext_size).next_multiple_of(8)
+    } else {
+        header_length
+    };
+
+    let mut files = Vec::new();
+    let mut pos = files_start;
+    while pos + 24 <= volume.len() {
+        let file_header = &volume[pos..pos + 24];
+        if file_header.iter().all(|&b| b == 0xff) {
+            break;
+        }
+        let attributes = file_header[19];
+        let (size, header_size) = if attributes & FFS_ATTRIB_LARGE_FILE != 0 {
+            (read_u64(volume, pos + 24)?, 32)
+        } else {
+            (read_u24(file_header, 20)? as u64, 24)
+        };
+        if size < header_size as u64 || pos + size as usize > volume.len() {
+            break;
+        }
+
+        let file_type = file_header[18];
+        let mut file = FfsFile {
+            guid: format_guid(&file_header[..16]),
+            file_type,
+            offset: (offset + pos) as u64,
+            size,
+            name: None,
+            sections: Vec::new(),
+            volumes: Vec::new(),
+        };
+        if file_type != FFS_TYPE_RAW && file_type != FFS_TYPE_PAD {
+            let body_start = pos + header_size;
+            let body = &volume[body_start..pos + size as usize];
+            parse_sections(data, offset + body_start, body, depth, &mut file);
+        }
+        files.push(file);
+        pos = (pos + size as usize).next_multiple_of(8);
+    }
+
+    Some(FirmwareVolume {
+        offset: offset as u64,
+        length,
+        filesystem_guid: format_guid(&header[16..32]),
+        files,
+    })
+}
+
+fn parse_sections(data: &[u8], base: usize, body: &[u8], depth: usize, file: &mut FfsFile) {
+    let mut pos = 0;
+    while pos + 4 <= body.len() {
+        let Some(size) = read_u24(body, pos) else {
+            break;
+        };
+        let section_type = body[pos + 3];
+        let (size, header_size) = if size == 0x00ff_ffff {
+            match read_u32(body, pos + 4) {
+                Some(extended) => (extended as usize, 8),
+                None => break,
+            }
+        } else {
+            (size as usize, 4)
+        };
+        if size < header_size || pos + size > body.len() {
+            break;
+        }
+
+        let content = &body[pos + header_size..pos + size];
+        match section_type {
+            SECTION_USER_INTERFACE => file.name = Some(decode_ucs2(content)),
+            SECTION_FV_IMAGE if depth < MAX_FV_DEPTH => {
+                if let Some(volume) = parse_volume(data, base + pos + header_size, depth + 1) {
+                    file.volumes.push(volume);
+                }
+            }
+            _ => {}
+        }
+        file.sections.push(FfsSection {
+            section_type,
+            offset: (base + pos + header_size) as u64,
+            size: (size - header_size) as u64,
+        });
+        pos = (pos + size).next_multiple_of(4);
+    }
+}
+
+/// Formats an EFI GUID (mixed-endian) in registry notation.
+pub fn format_guid(bytes: &[u8]) -> String {
+    if bytes.len() < 16 {
+        return String::new();
+    }
+    format!(
+        "{:08X}-{:04X}-{:04X}-{}-{}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex::encode_upper(&bytes[8..10]),
+        hex::encode_upper(&bytes[10..16])
+    )
+}
+
+fn decode_ucs2(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u24(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 3)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_guid() {
+        // gEfiFirmwareFileSystem2Guid
+        let bytes = [
+            0x78, 0xe5, 0x8c, 0x8c, 0x3d, 0x8a, 0x1c, 0x4f, 0x99, 0x35, 0x89, 0x61, 0x85, 0xc3,
+            0x2d, 0xd3,
+        ];
+        assert_eq!(format_guid(&bytes), "8C8CE578-8A3D-4F1C-9935-896185C32DD3");
+    }
+
+    #[test]
+    fn test_parse_te_header() {
+        let mut data = vec![0u8; 0x100];
+        data[..2].copy_from_slice(b"VZ");
+        data[2..4].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[4] = 1;
+        data[6..8].copy_from_slice(&0x1e8u16.to_le_bytes());
+        data[8..12].copy_from_slice(&0x240u32.to_le_bytes());
+        data[40..45].copy_from_slice(b".text");
+        data[60..64].copy_from_slice(&0x240u32.to_le_bytes());
+
+        let te = parse_te(&data).expect("TE parse failed");
+        assert_eq!(te.arch(), "x86_64");
+        assert_eq!(te.entry_point, 0x240);
+        assert_eq!(te.sections[0].name, ".text");
+        // 0x240 - 0x1e8 + 40
+        assert_eq!(te.sections[0].offset, 0x80);
+    }
+
+    #[test]
+    fn test_find_firmware_volume_with_driver() {
+        let mut file = vec![0u8; 24];
+        file[..16].copy_from_slice(&[0x11; 16]);
+        file[18] = 0x07; // DRIVER
+        let name: Vec<u8> = "Dxe\0"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        let ui_size = 4 + name.len();
+        file.extend_from_slice(&(ui_size as u32).to_le_bytes()[..3]);
+        file.push(SECTION_USER_INTERFACE);
+        file.extend_from_slice(&name);
+        let file_size = file.len() as u32;
+        file[20..23].copy_from_slice(&file_size.to_le_bytes()[..3]);
+
+        let mut data = vec![0u8; 0x48];
+        data[32..40].copy_from_slice(&0x100u64.to_le_bytes());
+        data[40..44].copy_from_slice(FV_SIGNATURE);
+        data[48..50].copy_from_slice(&0x48u16.to_le_bytes());
+        data.extend_from_slice(&file);
+        data.resize(0x100, 0xff);
+
+        let volumes = find_firmware_volumes(&data);
+        assert_eq!(volumes.len(), 1);
+        assert_eq!(volumes[0].files.len(), 1);
+        assert_eq!(volumes[0].files[0].type_name(), "DRIVER");
+        assert_eq!(volumes[0].files[0].name.as_deref(), Some("Dxe"));
+    }
+}
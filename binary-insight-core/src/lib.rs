@@ -1,3 +1,14 @@
-pub mod binary;
+//! Binary parsing and analysis behind the `binary-insight` CLI.
+//!
+//! [`binary::BinaryFile`] owns the input bytes and a [`binary::BinaryInfo`]
+//! describing its layout (sections, symbols, imports, strings, ...). Analysis
+//! passes in [`analysis`] read that model and store their results back on it;
+//! [`report::AnalysisReport`] summarizes a file for output. Library users
+//! should start from [`prelude`].
+
 pub mod analysis;
+pub mod binary;
+pub mod prelude;
+pub mod report;
+#[doc(hidden)]
 pub mod utils;
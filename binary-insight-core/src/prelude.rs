@@ -0,0 +1,17 @@
+//! The supported library surface. Types re-exported here keep their shape
+//! across minor releases; everything else may change as analyses evolve.
+//!
+//! ```no_run
+//! use binary_insight_core::prelude::*;
+//!
+//! let binary = BinaryFile::load("/bin/ls")?;
+//! let report = AnalysisReport::new(&binary);
+//! println!("{} {}", report.format, report.arch.name());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub use crate::analysis::findings::{Category, Finding, Severity};
+pub use crate::binary::{
+    AnalysisOptions, Arch, BinaryData, BinaryFile, BinaryInfo, MemoryMap, SectionInfo, SymbolInfo,
+};
+pub use crate::report::AnalysisReport;
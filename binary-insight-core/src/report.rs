@@ -0,0 +1,275 @@
+use crate::analysis::findings::{self, Finding};
+use crate::analysis::score::{self, ThreatScore};
+use crate::analysis::{
+    anomalies, bazaar, debuginfo, entropy, hashdb, hashes, icon, language, x509,
+};
+use crate::binary::carve::CarvedFile;
+use crate::binary::compression::Compression;
+use crate::binary::magic::FileType;
+use crate::binary::{Arch, BinaryFile, ParseWarning};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Summary of one analyzed file, independent of how it is rendered.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisReport {
+    pub file: String,
+    pub format: String,
+    /// Content-based type and MIME of an input no parser recognized.
+    pub file_type: Option<FileType>,
+    /// Compression wrappers stripped before analysis, outermost first.
+    pub compression: Vec<Compression>,
+    pub arch: Arch,
+    /// What the program was written in and packaged with.
+    pub language: Option<language::LanguageInfo>,
+    pub entry_point: u64,
+    pub hashes: hashes::FileHashes,
+    /// ELF only, see [`hashes::telfhash`].
+    pub telfhash: Option<String>,
+    /// PE only, see [`hashes::authentihash`].
+    pub authentihash: Option<String>,
+    pub entropy: f64,
+    /// Main icon of a PE file, with its perceptual hashes.
+    pub icon: Option<icon::IconInfo>,
+    /// Set when the file is in a known-good hash set, see [`hashes::KnownHashes`].
+    pub known_good: Option<String>,
+    pub findings: Vec<Finding>,
+    pub malwarebazaar: Option<bazaar::BazaarReport>,
+    /// The local hash database's record from before this run.
+    pub hashdb: Option<hashdb::HashRecord>,
+    pub threat_score: ThreatScore,
+    pub debug_info: Option<debuginfo::DebugInfo>,
+    /// Headers and tables skipped by a permissive load.
+    pub parse_warnings: Vec<ParseWarning>,
+    /// Structural warnings, see [`anomalies::warnings`].
+    pub warnings: Vec<String>,
+    /// Files found inside the input, see [`crate::binary::carve::carve`].
+    pub embedded_files: Vec<CarvedFile>,
+    /// X.509 certificates found in the file.
+    pub certificates: Vec<x509::Certificate>,
+    /// Reports of the embedded executables that were unpacked, see
+    /// [`BinaryFile::unpack_embedded`].
+    pub embedded: Vec<EmbeddedReport>,
+    /// Config-defined fields, see [`crate::analysis::expr::derive_fields`].
+    /// Left empty by [`AnalysisReport::new`] for the caller to fill in.
+    pub derived: BTreeMap<String, String>,
+}
+
+/// Report of an executable found inside the analyzed file.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddedReport {
+    /// Offset in the parent file.
+    pub offset: u64,
+    pub report: AnalysisReport,
+}
+
+impl AnalysisReport {
+    /// Uses the hashes and entropy stored in `info.analysis` when present,
+    /// computing them over the (decompressed) data otherwise.
+    pub fn new(binary: &BinaryFile) -> Self {
+        let info = &binary.info;
+        let stored = info.analysis.as_ref();
+        Self {
+            file: binary.name.clone(),
+            format: info.format.clone(),
+            file_type: info.file_type,
+            compression: info.compression.clone(),
+            arch: info.architecture(),
+            language: info.language.clone(),
+            entry_point: info.entry_point,
+            hashes: stored
+                .and_then(|a| a.hashes.clone())
+                .unwrap_or_else(|| hashes::calculate_hashes(&binary.data)),
+            telfhash: info.telfhash.clone(),
+            authentihash: info.authentihash.clone(),
+            entropy: stored
+                .map(|a| a.entropy)
+                .unwrap_or_else(|| entropy::calculate_entropy(&binary.data)),
+            icon: info.icon.clone(),
+            known_good: stored.and_then(|a| a.known_good.clone()),
+            findings: findings::collect(info),
+            malwarebazaar: info.bazaar.clone(),
+            hashdb: info.hash_record.clone(),
+            threat_score: score::threat_score(info),
+            debug_info: info.debug_info.clone(),
+            parse_warnings: info.parse_warnings.clone(),
+            warnings: anomalies::warnings(info),
+            embedded_files: info.embedded_files.clone(),
+            certificates: info.certificates.clone(),
+            embedded: info
+                .unpacked
+                .iter()
+                .map(|unpacked| EmbeddedReport {
+                    offset: unpacked.offset,
+                    report: AnalysisReport::new(&unpacked.file),
+                })
+                .collect(),
+            derived: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary::AnalysisOptions;
+
+    #[test]
+    fn test_report_for_raw_blob() {
+        let binary = BinaryFile::from_raw("blob".to_string(), vec![0x90; 16], "x86_64", 0);
+        let report = AnalysisReport::new(&binary);
+        assert_eq!(report.arch, Arch::X86_64);
+        assert_eq!(report.entropy, 0.0);
+        assert!(report.findings.is_empty());
+        assert_eq!(report.threat_score.score, 0);
+        assert_eq!(report.hashes.md5.len(), 32);
+    }
+
+    #[test]
+    fn test_derived_fields_serialized() {
+        let binary = BinaryFile::from_raw("blob".to_string(), vec![0x90; 16], "x86_64", 0);
+        let fields = BTreeMap::from([
+            ("flat".to_string(), "entropy < 1".to_string()),
+            ("broken".to_string(), "entropy <".to_string()),
+        ]);
+        let mut report = AnalysisReport::new(&binary);
+        report.derived = crate::analysis::expr::derive_fields(&binary, &fields);
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["derived"]["flat"], "true");
+        assert!(value["derived"]["broken"]
+            .as_str()
+            .unwrap()
+            .starts_with("<error:"));
+    }
+
+    #[test]
+    fn test_embedded_reports() {
+        // A bare 64-bit ELF header after some padding.
+        let mut elf = vec![0u8; 64];
+        elf[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        elf[16] = 2;
+        elf[18] = 62;
+        elf[20] = 1;
+        elf[52] = 64;
+        let data = [vec![0xcc; 32], elf].concat();
+
+        let mut binary = BinaryFile::from_bytes("dropper".to_string(), data.clone()).unwrap();
+        binary.unpack_embedded(0, u64::MAX, AnalysisOptions::default());
+        assert!(AnalysisReport::new(&binary).embedded.is_empty());
+
+        let mut binary = BinaryFile::from_bytes("dropper".to_string(), data.clone()).unwrap();
+        binary.unpack_embedded(2, u64::MAX, AnalysisOptions::default());
+        let report = AnalysisReport::new(&binary);
+        assert_eq!(report.embedded_files.len(), 1);
+        assert_eq!(report.embedded.len(), 1);
+        assert_eq!(report.embedded[0].offset, 32);
+        assert_eq!(report.embedded[0].report.file, "dropper@0x20");
+        assert_eq!(report.embedded[0].report.format, "ELF");
+
+        let mut binary = BinaryFile::from_bytes("dropper".to_string(), data.clone()).unwrap();
+        binary.unpack_embedded(2, 16, AnalysisOptions::default());
+        assert!(binary.info.unpacked.is_empty());
+
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (path, contents) in [("bin/tool", &data[32..]), ("data.bin", &data[..])] {
+            writer.start_file(path, options).unwrap();
+            std::io::Write::write_all(&mut writer, contents).unwrap();
+        }
+        let zip = writer.finish().unwrap().into_inner();
+        let mut binary = BinaryFile::from_bytes("release.zip".to_string(), zip).unwrap();
+        assert_eq!(binary.info.format, "ZIP Archive");
+        binary.unpack_embedded(1, u64::MAX, AnalysisOptions::default());
+        let report = AnalysisReport::new(&binary);
+        assert_eq!(report.embedded.len(), 1);
+        assert_eq!(report.embedded[0].report.file, "release.zip!bin/tool");
+    }
+
+    #[test]
+    fn test_unpack_malformed_members() {
+        let mut elf = vec![0u8; 0x100];
+        elf[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        elf[16] = 2;
+        elf[18] = 62;
+        elf[20] = 1;
+        elf[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        elf[52] = 64;
+        elf[54] = 56;
+        elf[56] = 1;
+        // A PT_NOTE segment running far past the end of the file.
+        let mut looping_note = elf.clone();
+        looping_note[64] = 4;
+        looping_note[72..80].copy_from_slice(&0xf0u64.to_le_bytes());
+        looping_note[96..104].copy_from_slice(&0x4e20u64.to_le_bytes());
+        // Program headers past the end of the file.
+        let mut truncated = elf[..64].to_vec();
+        truncated[56] = 8;
+
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in [("bin/ls", &looping_note), ("bin/cut", &truncated)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_slice())
+                .unwrap();
+        }
+        let inner = builder.into_inner().unwrap();
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(inner.len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "bins.tar", inner.as_slice())
+            .unwrap();
+        let outer = builder.into_inner().unwrap();
+
+        let unpacked = |options| {
+            let mut binary =
+                BinaryFile::from_bytes("rootfs.tar".to_string(), outer.clone()).unwrap();
+            binary.unpack_embedded(2, u64::MAX, options);
+            let report = AnalysisReport::new(&binary);
+            assert_eq!(report.embedded.len(), 1);
+            let nested = &report.embedded[0].report.embedded;
+            let names: Vec<String> = nested.iter().map(|e| e.report.file.clone()).collect();
+            names
+        };
+        assert_eq!(
+            unpacked(AnalysisOptions::default()),
+            ["rootfs.tar!bins.tar!bin/ls"]
+        );
+        let permissive = AnalysisOptions {
+            permissive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            unpacked(permissive),
+            ["rootfs.tar!bins.tar!bin/cut", "rootfs.tar!bins.tar!bin/ls"]
+        );
+    }
+
+    #[test]
+    fn test_nested_compression() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        let gzip = |data: &[u8]| {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let data = gzip(&gzip(b"\x00\x01opaque payload"));
+        let binary = BinaryFile::from_bytes("notes.gz.gz".to_string(), data).unwrap();
+        assert_eq!(&*binary.data, b"\x00\x01opaque payload");
+        assert_eq!(binary.info.wrapper_names().as_deref(), Some("gzip > gzip"));
+        let report = AnalysisReport::new(&binary);
+        assert_eq!(report.format, "gzip-compressed data");
+        assert_eq!(report.compression, [Compression::Gzip, Compression::Gzip]);
+
+        let data = gzip(b"#!/bin/sh\necho hi\n");
+        let report =
+            AnalysisReport::new(&BinaryFile::from_bytes("run.gz".to_string(), data).unwrap());
+        assert_eq!(report.format, "Shell script");
+        assert_eq!(report.file_type.unwrap().mime, "text/x-shellscript");
+    }
+}
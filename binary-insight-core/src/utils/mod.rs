@@ -1,3 +1,57 @@
+use std::io::{self, Write};
+
 pub fn format_hex(data: &[u8]) -> String {
     hex::encode(data)
 }
+
+/// The hex column of a hexdump row: `xx ` per byte, padded to `width`
+/// bytes when `chunk` is the short last row.
+pub fn hex_column(chunk: &[u8], width: usize) -> String {
+    let mut column: String = chunk.iter().map(|b| format!("{:02x} ", b)).collect();
+    column.push_str(&"   ".repeat(width.saturating_sub(chunk.len())));
+    column
+}
+
+/// The ASCII column of a hexdump row, with non-printable bytes as `.`.
+pub fn ascii_column(chunk: &[u8]) -> String {
+    chunk
+        .iter()
+        .map(|&b| {
+            if (32..=126).contains(&b) {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+/// Writes `data` as `offset:  hex |ascii|` rows of `width` bytes, numbering
+/// offsets from `base`.
+pub fn write_hexdump(out: &mut dyn Write, data: &[u8], base: u64, width: usize) -> io::Result<()> {
+    for (i, chunk) in data.chunks(width.max(1)).enumerate() {
+        writeln!(
+            out,
+            "{:08x}:  {} |{}|",
+            base + (i * width) as u64,
+            hex_column(chunk, width),
+            ascii_column(chunk)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_hexdump() {
+        let mut out = Vec::new();
+        write_hexdump(&mut out, b"ABC\x00\x01", 0x400, 4).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000400:  41 42 43 00  |ABC.|\n00000404:  01           |.|\n"
+        );
+    }
+}